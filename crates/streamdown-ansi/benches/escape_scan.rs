@@ -0,0 +1,55 @@
+//! Escape scanning benchmarks.
+//!
+//! Compares `visible`/`visible_length` against the plain regex pass they
+//! used to run unconditionally, on escape-free text (the common case) and
+//! on styled text.
+//!
+//! ```sh
+//! cargo bench -p streamdown-ansi --bench escape_scan
+//! ```
+
+use std::hint::black_box;
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use regex::Regex;
+use streamdown_ansi::utils::{ANSIESCAPE, visible, visible_length};
+use unicode_width::UnicodeWidthStr;
+
+fn inputs() -> Vec<(&'static str, String)> {
+    let plain = "The quick brown fox jumps over the lazy dog. ".repeat(20);
+    let styled = "The \x1b[1mquick\x1b[0m brown \x1b[38;2;255;0;0mfox\x1b[0m jumps. ".repeat(20);
+    vec![("plain", plain), ("styled", styled)]
+}
+
+fn bench_visible(c: &mut Criterion) {
+    let regex = Regex::new(ANSIESCAPE).unwrap();
+    let mut group = c.benchmark_group("visible");
+
+    for (name, text) in inputs() {
+        group.bench_with_input(BenchmarkId::new("regex", name), &text, |b, text| {
+            b.iter(|| regex.replace_all(black_box(text), "").to_string())
+        });
+        group.bench_with_input(BenchmarkId::new("memchr", name), &text, |b, text| {
+            b.iter(|| visible(black_box(text)))
+        });
+    }
+    group.finish();
+}
+
+fn bench_visible_length(c: &mut Criterion) {
+    let regex = Regex::new(ANSIESCAPE).unwrap();
+    let mut group = c.benchmark_group("visible_length");
+
+    for (name, text) in inputs() {
+        group.bench_with_input(BenchmarkId::new("regex", name), &text, |b, text| {
+            b.iter(|| regex.replace_all(black_box(text), "").width())
+        });
+        group.bench_with_input(BenchmarkId::new("memchr", name), &text, |b, text| {
+            b.iter(|| visible_length(black_box(text)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_visible, bench_visible_length);
+criterion_main!(benches);
@@ -3,6 +3,9 @@
 //! This module provides all the raw ANSI escape sequences used
 //! for terminal formatting and colors.
 
+use alloc::format;
+use alloc::string::{String, ToString};
+
 /// Escape sequence prefix for 24-bit foreground color.
 /// Usage: `format!("{}r;g;bm", FG)` where r, g, b are 0-255.
 pub const FG: &str = "\x1b[38;2;";
@@ -3,6 +3,11 @@
 //! This module provides functions for color space conversions
 //! and style multiplier application.
 
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
 use std::collections::HashMap;
 
 /// Parse an ANSI color code and convert to hex string.
@@ -158,13 +163,21 @@ pub fn hsv_to_rgb(h: f64, s: f64, v: f64) -> (u8, u8, u8) {
         (c, 0.0, x)
     };
 
-    let r = ((r1 + m) * 255.0).round() as u8;
-    let g = ((g1 + m) * 255.0).round() as u8;
-    let b = ((b1 + m) * 255.0).round() as u8;
+    let r = to_channel(r1 + m);
+    let g = to_channel(g1 + m);
+    let b = to_channel(b1 + m);
 
     (r, g, b)
 }
 
+/// Scale a 0.0..1.0 component to 0-255, rounding half away from zero.
+///
+/// `f64::round` needs `std`; the scaled value is never negative in range,
+/// so adding a half and truncating rounds the same way.
+fn to_channel(component: f64) -> u8 {
+    (component * 255.0 + 0.5) as u8
+}
+
 /// Style multipliers for HSV adjustment.
 #[derive(Debug, Clone, Default)]
 pub struct HsvMultiplier {
@@ -221,6 +234,7 @@ impl HsvMultiplier {
 /// let result = apply_multipliers(&styles, "highlight", 0.0, 1.0, 1.0);
 /// assert!(result.ends_with('m'));
 /// ```
+#[cfg(feature = "std")]
 pub fn apply_multipliers(
     style: &HashMap<String, HsvMultiplier>,
     name: &str,
@@ -241,6 +255,7 @@ pub fn apply_multipliers(
 }
 
 /// Create an ANSI foreground color from HSV with multipliers.
+#[cfg(feature = "std")]
 pub fn fg_from_hsv(
     style: &HashMap<String, HsvMultiplier>,
     name: &str,
@@ -252,6 +267,7 @@ pub fn fg_from_hsv(
 }
 
 /// Create an ANSI background color from HSV with multipliers.
+#[cfg(feature = "std")]
 pub fn bg_from_hsv(
     style: &HashMap<String, HsvMultiplier>,
     name: &str,
@@ -262,10 +278,270 @@ pub fn bg_from_hsv(
     format!("\x1b[48;2;{}", apply_multipliers(style, name, h, s, v))
 }
 
+/// Number of colors a terminal can display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum ColorDepth {
+    /// No colors or text attributes: SGR sequences are dropped
+    Monochrome,
+    /// The 16 basic colors (`30`-`37`, `90`-`97`)
+    Ansi16,
+    /// The xterm 256-color palette (`38;5;n`)
+    Ansi256,
+    /// 24-bit color (`38;2;r;g;b`)
+    #[default]
+    TrueColor,
+}
+
+/// The 16 basic colors as xterm draws them.
+const ANSI16_PALETTE: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// Levels of each channel in the 6x6x6 color cube of the 256-color palette.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Squared distance between two colors.
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| (i32::from(x) - i32::from(y)).unsigned_abs();
+    d(a.0, b.0).pow(2) + d(a.1, b.1).pow(2) + d(a.2, b.2).pow(2)
+}
+
+/// Nearest color of the xterm 256-color palette (cube or grey ramp).
+///
+/// # Example
+///
+/// ```
+/// use streamdown_ansi::color::rgb_to_ansi256;
+/// assert_eq!(rgb_to_ansi256(255, 0, 0), 196);
+/// assert_eq!(rgb_to_ansi256(128, 128, 128), 244);
+/// ```
+pub fn rgb_to_ansi256(r: u8, g: u8, b: u8) -> u8 {
+    let level = |c: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|&i| (i32::from(CUBE_LEVELS[i]) - i32::from(c)).unsigned_abs())
+            .unwrap_or(0)
+    };
+    let (ri, gi, bi) = (level(r), level(g), level(b));
+    let cube = (CUBE_LEVELS[ri], CUBE_LEVELS[gi], CUBE_LEVELS[bi]);
+    let cube_index = 16 + 36 * ri + 6 * gi + bi;
+
+    // Grey ramp: 232..=255 are 8, 18, ..., 238
+    let average = (u32::from(r) + u32::from(g) + u32::from(b)) / 3;
+    let step = (average.saturating_sub(3) / 10).min(23);
+    let grey_level = (8 + 10 * step) as u8;
+    let grey = (grey_level, grey_level, grey_level);
+
+    if distance((r, g, b), grey) < distance((r, g, b), cube) {
+        232 + step as u8
+    } else {
+        cube_index as u8
+    }
+}
+
+/// RGB value of an xterm 256-color palette index.
+///
+/// # Example
+///
+/// ```
+/// use streamdown_ansi::color::ansi256_to_rgb;
+/// assert_eq!(ansi256_to_rgb(196), (255, 0, 0));
+/// assert_eq!(ansi256_to_rgb(244), (128, 128, 128));
+/// ```
+pub fn ansi256_to_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => ANSI16_PALETTE[usize::from(index)],
+        16..=231 => {
+            let i = usize::from(index - 16);
+            (
+                CUBE_LEVELS[i / 36],
+                CUBE_LEVELS[i / 6 % 6],
+                CUBE_LEVELS[i % 6],
+            )
+        }
+        _ => {
+            let level = 8 + 10 * (index - 232);
+            (level, level, level)
+        }
+    }
+}
+
+/// Nearest of the 16 basic colors (0-7 normal, 8-15 bright).
+///
+/// # Example
+///
+/// ```
+/// use streamdown_ansi::color::rgb_to_ansi16;
+/// assert_eq!(rgb_to_ansi16(250, 10, 10), 9);
+/// assert_eq!(rgb_to_ansi16(20, 20, 20), 0);
+/// ```
+pub fn rgb_to_ansi16(r: u8, g: u8, b: u8) -> u8 {
+    (0..ANSI16_PALETTE.len())
+        .min_by_key(|&i| distance((r, g, b), ANSI16_PALETTE[i]))
+        .unwrap_or(0) as u8
+}
+
+/// Rewrite the 24-bit (and, for [`ColorDepth::Ansi16`], 256-color) colors
+/// of SGR sequences in `s` for a terminal that only displays `depth` colors.
+///
+/// Other sequences and text are left alone; with [`ColorDepth::TrueColor`]
+/// `s` is returned as is, with [`ColorDepth::Monochrome`] SGR sequences are
+/// removed.
+///
+/// # Example
+///
+/// ```
+/// use streamdown_ansi::color::{ColorDepth, downgrade_colors};
+/// let s = "\x1b[1;38;2;255;0;0mred\x1b[0m";
+/// assert_eq!(downgrade_colors(s, ColorDepth::Ansi256), "\x1b[1;38;5;196mred\x1b[0m");
+/// assert_eq!(downgrade_colors(s, ColorDepth::Ansi16), "\x1b[1;91mred\x1b[0m");
+/// assert_eq!(downgrade_colors(s, ColorDepth::Monochrome), "red");
+/// ```
+pub fn downgrade_colors(s: &str, depth: ColorDepth) -> Cow<'_, str> {
+    let unchanged = match depth {
+        ColorDepth::TrueColor => true,
+        ColorDepth::Monochrome => !s.contains("\x1b["),
+        ColorDepth::Ansi16 => !s.contains(";2;") && !s.contains(";5;"),
+        ColorDepth::Ansi256 => !s.contains(";2;"),
+    };
+    if unchanged {
+        return Cow::Borrowed(s);
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find("\x1b[") {
+        out.push_str(&rest[..start]);
+        rest = &rest[start + 2..];
+        let len = rest
+            .bytes()
+            .take_while(|&b| b.is_ascii_digit() || b == b';')
+            .count();
+        let sgr = rest[len..].starts_with('m');
+        if sgr && depth == ColorDepth::Monochrome {
+            rest = &rest[len + 1..];
+            continue;
+        }
+        out.push_str("\x1b[");
+        if sgr {
+            downgrade_params(&rest[..len], depth, &mut out);
+            rest = &rest[len..];
+        }
+    }
+    out.push_str(rest);
+    Cow::Owned(out)
+}
+
+/// Push SGR parameters with their colors downgraded to `depth`.
+fn downgrade_params(params: &str, depth: ColorDepth, out: &mut String) {
+    let parts: Vec<&str> = params.split(';').collect();
+    let mut i = 0;
+    while i < parts.len() {
+        if i > 0 {
+            out.push(';');
+        }
+        let rgb = match parts.get(i..i + 5) {
+            Some([target @ ("38" | "48"), "2", r, g, b]) => {
+                match (r.parse::<u8>(), g.parse::<u8>(), b.parse::<u8>()) {
+                    (Ok(r), Ok(g), Ok(b)) => Some((*target == "48", r, g, b, 5)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        // Palette colors only need downgrading for 16-color terminals
+        let rgb = rgb.or_else(|| match parts.get(i..i + 3) {
+            Some([target @ ("38" | "48"), "5", n]) if depth == ColorDepth::Ansi16 => {
+                let (r, g, b) = ansi256_to_rgb(n.parse().ok()?);
+                Some((*target == "48", r, g, b, 3))
+            }
+            _ => None,
+        });
+        let Some((background, r, g, b, len)) = rgb else {
+            out.push_str(parts[i]);
+            i += 1;
+            continue;
+        };
+        match depth {
+            ColorDepth::Ansi16 => {
+                let index = rgb_to_ansi16(r, g, b);
+                let base = match (background, index < 8) {
+                    (false, true) => 30,
+                    (false, false) => 90 - 8,
+                    (true, true) => 40,
+                    (true, false) => 100 - 8,
+                };
+                out.push_str(&(base + u16::from(index)).to_string());
+            }
+            _ => {
+                let target = if background { "48" } else { "38" };
+                out.push_str(&format!("{};5;{}", target, rgb_to_ansi256(r, g, b)));
+            }
+        }
+        i += len;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_ansi256_cube_and_greys() {
+        assert_eq!(rgb_to_ansi256(0, 0, 0), 16);
+        assert_eq!(rgb_to_ansi256(255, 255, 255), 231);
+        assert_eq!(rgb_to_ansi256(95, 135, 175), 67);
+        assert_eq!(rgb_to_ansi256(48, 48, 48), 236);
+        assert_eq!(ansi256_to_rgb(67), (95, 135, 175));
+        assert_eq!(ansi256_to_rgb(9), ANSI16_PALETTE[9]);
+    }
+
+    #[test]
+    fn test_downgrade_palette_colors() {
+        assert_eq!(
+            downgrade_colors("\x1b[1;38;5;196mx", ColorDepth::Ansi16),
+            "\x1b[1;91mx"
+        );
+        assert_eq!(
+            downgrade_colors("\x1b[38;5;196mx", ColorDepth::Ansi256),
+            "\x1b[38;5;196mx"
+        );
+    }
+
+    #[test]
+    fn test_downgrade_background_and_passthrough() {
+        assert_eq!(
+            downgrade_colors("\x1b[48;2;0;0;0m x", ColorDepth::Ansi16),
+            "\x1b[40m x"
+        );
+        assert_eq!(
+            downgrade_colors("\x1b[2K\x1b[1mx\x1b[22m", ColorDepth::Monochrome),
+            "\x1b[2Kx"
+        );
+        // Non-SGR sequences and malformed colors are kept
+        for s in ["\x1b[2;2;2H", "\x1b[38;2;300;0;0m", "plain;2;text", "\x1b["] {
+            assert_eq!(downgrade_colors(s, ColorDepth::Ansi256), s);
+        }
+        let s = "\x1b[38;2;1;2;3m";
+        assert!(matches!(
+            downgrade_colors(s, ColorDepth::TrueColor),
+            Cow::Borrowed(_)
+        ));
+    }
+
     #[test]
     fn test_ansi2hex() {
         assert_eq!(ansi2hex("255;0;0"), Some("#ff0000".to_string()));
@@ -305,6 +581,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "std")]
     fn test_apply_multipliers() {
         let mut styles = HashMap::new();
         styles.insert(
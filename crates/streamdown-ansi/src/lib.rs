@@ -9,7 +9,9 @@
 //! - [`style`] - Style pairs for toggleable formatting
 //! - [`color`] - HSV/RGB color manipulation
 //! - [`utils`] - Text processing utilities (visible length, ANSI stripping, etc.)
+//! - [`linebreak`] - Where wrapped lines may break
 //! - [`sanitize`] - Security utilities for safe terminal output
+//! - [`width`] - Display width model ([`WidthOracle`])
 //!
 //! # Example
 //!
@@ -23,15 +25,34 @@
 //! let visible_len = utils::visible_length(&text);
 //! assert_eq!(visible_len, 9); // "bold text"
 //! ```
+//!
+//! # Features
+//!
+//! The text logic (widths, wrapping, escape scanning, sanitizing) needs only
+//! `alloc`, so with default features off the crate is `no_std` and can run
+//! in wasm workers or plugins:
+//!
+//! - `std` (default) - installing a [`WidthOracle`] on a thread with
+//!   [`width::OracleScope`] and the color helpers keyed by `HashMap`
+//! - `terminal` (default) - [`Style`] built from crossterm colors
+//! - `linebreak` - Unicode line breaking (UAX #14) for wrapping, instead of
+//!   breaking only at whitespace
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
 pub mod codes;
 pub mod color;
+pub mod linebreak;
 pub mod sanitize;
 pub mod style;
 pub mod utils;
+pub mod width;
 
 pub use codes::*;
 pub use color::*;
 pub use sanitize::*;
 pub use style::*;
 pub use utils::*;
+pub use width::*;
@@ -0,0 +1,122 @@
+//! Line break opportunities.
+//!
+//! [`split_breakable`] cuts ANSI-formatted text into the pieces a wrapper
+//! may put on separate lines. By default a line may only break after a
+//! space or a tab (not a no-break space). With the `linebreak` feature, breaks follow the Unicode line
+//! breaking algorithm (UAX #14): text also breaks after hyphens and between
+//! ideographs, and never before closing punctuation such as `)` or `!`,
+//! even after a space.
+//!
+//! # Example
+//!
+//! ```
+//! use streamdown_ansi::linebreak::split_breakable;
+//!
+//! let pieces = split_breakable("\x1b[1mbold\x1b[22m text");
+//! assert_eq!(pieces, ["\x1b[1mbold\x1b[22m ", "text"]);
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::utils::any_escape_len;
+
+/// Cut `text` into pieces that each end where a line may break.
+///
+/// The pieces concatenate back to `text`; whitespace stays at the end of
+/// the piece before the break. Escapes between the last character of one
+/// piece and the first of the next go with the next piece, so a style that
+/// opens there opens on the new line.
+pub fn split_breakable(text: &str) -> Vec<&str> {
+    let bytes = text.as_bytes();
+    let mut plain = String::with_capacity(text.len());
+    // For each visible character: its offset in `plain`, and where in
+    // `text` a piece starting with it begins
+    let mut starts = Vec::new();
+    let mut piece_start = 0;
+    let mut pos = 0;
+    while let Some(ch) = text[pos..].chars().next() {
+        if ch == '\x1b'
+            && let Some(len) = any_escape_len(&bytes[pos..])
+        {
+            pos += len;
+            continue;
+        }
+        starts.push((plain.len(), piece_start));
+        plain.push(ch);
+        pos += ch.len_utf8();
+        piece_start = pos;
+    }
+
+    let mut pieces = Vec::new();
+    let mut from = 0;
+    let mut next = starts.iter();
+    for at in break_opportunities(&plain).filter(|&at| at > 0 && at < plain.len()) {
+        if let Some(&(_, cut)) = next.find(|&&(offset, _)| offset == at) {
+            pieces.push(&text[from..cut]);
+            from = cut;
+        }
+    }
+    if from < text.len() || pieces.is_empty() {
+        pieces.push(&text[from..]);
+    }
+    pieces
+}
+
+/// Offsets into `plain` where a new line may start.
+#[cfg(feature = "linebreak")]
+fn break_opportunities(plain: &str) -> impl Iterator<Item = usize> + '_ {
+    unicode_linebreak::linebreaks(plain).map(|(at, _)| at)
+}
+
+/// Offsets into `plain` where a new line may start: after spaces and tabs.
+#[cfg(not(feature = "linebreak"))]
+fn break_opportunities(plain: &str) -> impl Iterator<Item = usize> + '_ {
+    let is_space = |ch: char| ch == ' ' || ch == '\t';
+    let mut after_space = false;
+    plain.char_indices().filter_map(move |(i, ch)| {
+        let at = (after_space && !is_space(ch)).then_some(i);
+        after_space = is_space(ch);
+        at
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_breakable_keeps_text() {
+        let text = "a \x1b[1mbold\x1b[0m word, (quoted) and well-known";
+        assert_eq!(split_breakable(text).concat(), text);
+        assert_eq!(split_breakable(""), [""]);
+        assert_eq!(split_breakable("word"), ["word"]);
+    }
+
+    #[test]
+    fn test_escapes_go_with_the_next_piece() {
+        assert_eq!(split_breakable("a \x1b[1mb"), ["a ", "\x1b[1mb"]);
+    }
+
+    #[cfg(not(feature = "linebreak"))]
+    #[test]
+    fn test_breaks_after_whitespace() {
+        assert_eq!(
+            split_breakable("well-known ( x )"),
+            ["well-known ", "( ", "x ", ")"]
+        );
+        assert_eq!(split_breakable("10\u{a0}km away"), ["10\u{a0}km ", "away"]);
+    }
+
+    #[cfg(feature = "linebreak")]
+    #[test]
+    fn test_unicode_breaks() {
+        // After hyphens
+        assert_eq!(split_breakable("well-known"), ["well-", "known"]);
+        // Not before closing punctuation, even after a space
+        assert_eq!(split_breakable("( x )"), ["( x )"]);
+        assert_eq!(split_breakable("wow !"), ["wow !"]);
+        // Between ideographs, but not before their closing punctuation
+        assert_eq!(split_breakable("日本語。"), ["日", "本", "語。"]);
+    }
+}
@@ -3,6 +3,8 @@
 //! This module provides functions to sanitize strings for safe terminal output,
 //! preventing escape sequence injection attacks and validating URLs for hyperlinks.
 
+use alloc::string::{String, ToString};
+
 /// Sanitize a string for safe terminal output.
 ///
 /// Removes control characters except newline and tab.
@@ -53,48 +55,82 @@ pub fn sanitize_for_terminal(s: &str) -> String {
 /// assert!(!s.contains("\x1b"));
 /// ```
 pub fn sanitize_preserving_ansi(s: &str) -> String {
+    filter_escapes(s, true)
+}
+
+/// Remove escape sequences and control characters (except newline and tab)
+/// from text.
+///
+/// Unlike [`sanitize_for_terminal`], whole sequences are removed, so
+/// pre-colored text reads as plain text rather than leaving `[31m` behind.
+///
+/// # Example
+/// ```
+/// use streamdown_ansi::sanitize::strip_escape_sequences;
+///
+/// let s = strip_escape_sequences("\x1b[31mRed\x1b[0m\x1b]0;title\x07!");
+/// assert_eq!(s, "Red!");
+/// ```
+pub fn strip_escape_sequences(s: &str) -> String {
+    filter_escapes(s, false)
+}
+
+/// Drop control characters and escape sequences, keeping SGR sequences if
+/// `keep_sgr`.
+fn filter_escapes(s: &str, keep_sgr: bool) -> String {
+    let bytes = s.as_bytes();
     let mut result = String::with_capacity(s.len());
-    let mut chars = s.chars().peekable();
-
-    while let Some(c) = chars.next() {
-        if c == '\x1b' {
-            // Check if this is a valid SGR sequence (\x1b[...m)
-            if chars.peek() == Some(&'[') {
-                let mut seq = String::from(c);
-                seq.push(chars.next().unwrap()); // consume '['
-
-                // Collect the sequence until we hit a letter
-                let mut valid_sgr = true;
-                while let Some(&next) = chars.peek() {
-                    seq.push(chars.next().unwrap());
-                    if next.is_ascii_alphabetic() {
-                        // Only allow 'm' (SGR) sequences for safety
-                        if next != 'm' {
-                            valid_sgr = false;
-                        }
-                        break;
-                    }
-                    // SGR parameters should only contain digits and semicolons
-                    if !next.is_ascii_digit() && next != ';' {
-                        valid_sgr = false;
-                    }
-                }
-
-                if valid_sgr {
-                    result.push_str(&seq);
-                }
-                // Otherwise, skip the whole sequence
+    let mut pos = 0;
+    while pos < s.len() {
+        if bytes[pos] == 0x1b {
+            let len = escape_sequence_len(&bytes[pos..]);
+            let seq = &s[pos..pos + len];
+            if keep_sgr && is_sgr(seq) {
+                result.push_str(seq);
             }
-            // Skip bare escape characters
-        } else if c.is_control() && c != '\n' && c != '\t' {
-            // Skip other control characters
-        } else {
+            pos += len;
+            continue;
+        }
+        let c = s[pos..].chars().next().unwrap_or_default();
+        if !c.is_control() || c == '\n' || c == '\t' {
             result.push(c);
         }
+        pos += c.len_utf8();
     }
     result
 }
 
+/// Length of the escape sequence at the start of `bytes`, which begins
+/// with ESC. Unterminated sequences run to the end of the text.
+fn escape_sequence_len(bytes: &[u8]) -> usize {
+    match bytes.get(1) {
+        // CSI: parameters up to a final byte
+        Some(b'[') => bytes[2..]
+            .iter()
+            .position(|b| (0x40..=0x7e).contains(b))
+            .map_or(bytes.len(), |end| end + 3),
+        // OSC and the other string sequences: up to BEL or ST (ESC \)
+        Some(b']' | b'P' | b'X' | b'^' | b'_') => {
+            let body = &bytes[2..];
+            match body.iter().position(|&b| b == 0x07 || b == 0x1b) {
+                Some(end) if body[end] == 0x07 => end + 3,
+                Some(end) if body.get(end + 1) == Some(&b'\\') => end + 4,
+                Some(end) => end + 2,
+                None => bytes.len(),
+            }
+        }
+        Some(b) if b.is_ascii() => 2,
+        _ => 1,
+    }
+}
+
+/// Whether `seq` is an SGR (color/style) sequence.
+fn is_sgr(seq: &str) -> bool {
+    seq.strip_prefix("\x1b[")
+        .and_then(|rest| rest.strip_suffix('m'))
+        .is_some_and(|params| params.bytes().all(|b| b.is_ascii_digit() || b == b';'))
+}
+
 /// Check if a URL is safe for OSC 8 hyperlinks.
 ///
 /// A safe URL:
@@ -226,6 +262,24 @@ mod tests {
         assert!(!output.contains("\x07"));
     }
 
+    #[test]
+    fn test_sanitize_preserving_ansi_strips_osc_body() {
+        let input = "a\x1b]0;evil title\x07b\x1b]2;x\x1b\\c";
+        assert_eq!(sanitize_preserving_ansi(input), "abc");
+    }
+
+    #[test]
+    fn test_strip_escape_sequences() {
+        assert_eq!(
+            strip_escape_sequences("\x1b[1;32mok\x1b[0m \x1b[2Kdone\x07\x1b"),
+            "ok done"
+        );
+        assert_eq!(
+            strip_escape_sequences("caf\u{e9}\x1b\u{e9}"),
+            "caf\u{e9}\u{e9}"
+        );
+    }
+
     #[test]
     fn test_sanitize_preserving_ansi_strips_cursor_movement() {
         // Cursor movement (\x1b[H) should be stripped
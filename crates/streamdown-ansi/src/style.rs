@@ -3,7 +3,11 @@
 //! Each style is represented as a tuple of (on_code, off_code)
 //! for easy toggling of formatting states.
 
+#[cfg(feature = "terminal")]
+use alloc::{format, string::String, vec::Vec};
+
 use crate::codes;
+#[cfg(feature = "terminal")]
 use crossterm::style::{Attribute, Color};
 
 /// A style pair consisting of (enable_code, disable_code).
@@ -38,6 +42,7 @@ pub const REVERSE: StylePair = (codes::REVERSE_ON, codes::REVERSE_OFF);
 pub const LINK: StylePair = (codes::LINK_START, codes::LINK_END);
 
 /// Represents a complete text style with colors and attributes.
+#[cfg(feature = "terminal")]
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct Style {
     /// Foreground color
@@ -48,6 +53,7 @@ pub struct Style {
     pub attributes: Vec<Attribute>,
 }
 
+#[cfg(feature = "terminal")]
 impl Style {
     /// Create a new empty style.
     pub fn new() -> Self {
@@ -134,6 +140,7 @@ impl Style {
 }
 
 /// Builder for creating formatted text with ANSI codes.
+#[cfg(feature = "terminal")]
 #[derive(Debug, Clone, Default)]
 pub struct StyledText {
     /// The text content
@@ -142,6 +149,7 @@ pub struct StyledText {
     pub styles: Vec<(usize, usize, Style)>,
 }
 
+#[cfg(feature = "terminal")]
 impl StyledText {
     /// Create a new styled text builder.
     pub fn new(text: impl Into<String>) -> Self {
@@ -178,6 +186,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "terminal")]
     fn test_style_builder() {
         let style = Style::new().bold().fg(Color::Rgb { r: 255, g: 0, b: 0 });
 
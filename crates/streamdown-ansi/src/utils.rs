@@ -3,10 +3,19 @@
 //! This module provides functions for working with ANSI-formatted text,
 //! including visible length calculation, escape code extraction, and
 //! code deduplication.
+//!
+//! Escapes are found by small hand-written scanners rather than regexes, so
+//! the module needs only `alloc`. The regex forms of what they match are
+//! kept as [`ESCAPE`] and [`ANSIESCAPE`] for callers with their own engine.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use memchr::memchr;
 
-use regex::Regex;
-use std::sync::LazyLock;
-use unicode_width::UnicodeWidthStr;
+use crate::width::str_width;
 
 /// Regex pattern for basic ANSI escape sequences (SGR codes).
 pub const ESCAPE: &str = r"\x1b\[[0-9;]*[mK]";
@@ -18,14 +27,146 @@ pub const ESCAPE: &str = r"\x1b\[[0-9;]*[mK]";
 /// - Simple escapes: \x1b)
 pub const ANSIESCAPE: &str = r"\x1b(?:\[[0-9;?]*[a-zA-Z]|\][0-9]*;;.*?\\|\))";
 
-/// Compiled regex for ESCAPE pattern.
-static ESCAPE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(ESCAPE).unwrap());
+/// Length of the [`ANSIESCAPE`] match at the start of `bytes` (which starts
+/// with ESC), if any.
+pub(crate) fn any_escape_len(bytes: &[u8]) -> Option<usize> {
+    match bytes.get(1)? {
+        b'[' => {
+            let params = bytes[2..]
+                .iter()
+                .take_while(|b| b.is_ascii_digit() || matches!(b, b';' | b'?'))
+                .count();
+            bytes
+                .get(2 + params)
+                .filter(|b| b.is_ascii_alphabetic())
+                .map(|_| 3 + params)
+        }
+        b']' => {
+            let digits = bytes[2..].iter().take_while(|b| b.is_ascii_digit()).count();
+            let body = 2 + digits;
+            if !bytes[body..].starts_with(b";;") {
+                return None;
+            }
+            let rest = &bytes[body + 2..];
+            let end = rest.iter().position(|&b| b == b'\\' || b == b'\n')?;
+            (rest[end] == b'\\').then_some(body + 2 + end + 1)
+        }
+        b')' => Some(2),
+        _ => None,
+    }
+}
 
-/// Compiled regex for ANSIESCAPE pattern.
-static ANSIESCAPE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(ANSIESCAPE).unwrap());
+/// Length of the [`ESCAPE`] (SGR or erase-line) match at the start of
+/// `bytes`, if any.
+fn sgr_escape_len(bytes: &[u8]) -> Option<usize> {
+    if !bytes.starts_with(b"\x1b[") {
+        return None;
+    }
+    let params = bytes[2..]
+        .iter()
+        .take_while(|b| b.is_ascii_digit() || **b == b';')
+        .count();
+    bytes
+        .get(2 + params)
+        .filter(|b| matches!(b, b'm' | b'K'))
+        .map(|_| 3 + params)
+}
 
-/// Compiled regex for splitting text into ANSI/non-ANSI segments.
-static SPLIT_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(\x1b[^m]*m|[^\x1b]+)").unwrap());
+/// Byte ranges of the escapes in `text` matched by `escape_len`.
+fn escape_ranges(
+    text: &str,
+    escape_len: fn(&[u8]) -> Option<usize>,
+) -> impl Iterator<Item = (usize, usize)> + '_ {
+    let bytes = text.as_bytes();
+    let mut pos = 0;
+    core::iter::from_fn(move || {
+        while let Some(offset) = memchr(0x1b, &bytes[pos..]) {
+            let start = pos + offset;
+            match escape_len(&bytes[start..]) {
+                Some(len) => {
+                    pos = start + len;
+                    return Some((start, pos));
+                }
+                None => pos = start + 1,
+            }
+        }
+        None
+    })
+}
+
+/// A piece of ANSI-formatted text, as cut by [`tokens`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Token<'a> {
+    /// An escape sequence matched by [`ANSIESCAPE`]: SGR and other CSI
+    /// sequences, OSC 8 hyperlinks
+    Escape(&'a str),
+    /// Text between escape sequences
+    Text(&'a str),
+}
+
+impl<'a> Token<'a> {
+    /// The piece of text.
+    pub fn as_str(&self) -> &'a str {
+        match *self {
+            Token::Escape(s) | Token::Text(s) => s,
+        }
+    }
+}
+
+/// Cut `text` into its escape sequences and the text between them, in
+/// order, with the byte offset of each piece.
+///
+/// Escapes are those [`visible`] and [`visible_length`] skip; an ESC that
+/// starts none of them is text.
+///
+/// # Example
+///
+/// ```
+/// use streamdown_ansi::utils::{Token, tokens};
+///
+/// let link = "\x1b]8;;https://example.com\x1b\\site\x1b]8;;\x1b\\";
+/// let pieces: Vec<Token> = tokens(link).map(|(_, token)| token).collect();
+/// assert_eq!(
+///     pieces,
+///     [
+///         Token::Escape("\x1b]8;;https://example.com\x1b\\"),
+///         Token::Text("site"),
+///         Token::Escape("\x1b]8;;\x1b\\"),
+///     ]
+/// );
+/// ```
+pub fn tokens(text: &str) -> impl Iterator<Item = (usize, Token<'_>)> + '_ {
+    let bytes = text.as_bytes();
+    let mut pos = 0;
+    core::iter::from_fn(move || {
+        let start = pos;
+        if start == bytes.len() {
+            return None;
+        }
+        if bytes[start] == 0x1b
+            && let Some(len) = any_escape_len(&bytes[start..])
+        {
+            pos += len;
+            return Some((start, Token::Escape(&text[start..pos])));
+        }
+        // Text runs to the next ESC that starts an escape
+        pos = start + 1;
+        loop {
+            match memchr(0x1b, &bytes[pos..]) {
+                Some(i) if any_escape_len(&bytes[pos + i..]).is_some() => {
+                    pos += i;
+                    break;
+                }
+                Some(i) => pos += i + 1,
+                None => {
+                    pos = bytes.len();
+                    break;
+                }
+            }
+        }
+        Some((start, Token::Text(&text[start..pos])))
+    })
+}
 
 /// Remove all ANSI escape sequences from text.
 ///
@@ -43,13 +184,41 @@ static SPLIT_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(\x1b[^m]*m|[^\
 /// assert_eq!(visible(text), "Bold text");
 /// ```
 pub fn visible(text: &str) -> String {
-    ANSIESCAPE_RE.replace_all(text, "").to_string()
+    // Fast path: escape-free text (the common case) skips the scan
+    if !has_escape(text) {
+        return text.to_string();
+    }
+    let mut result = String::with_capacity(text.len());
+    let mut last = 0;
+    for (start, end) in escape_ranges(text, any_escape_len) {
+        result.push_str(&text[last..start]);
+        last = end;
+    }
+    result.push_str(&text[last..]);
+    result
+}
+
+/// Check whether text contains an escape character.
+///
+/// Uses a SIMD-accelerated byte search, so it is much cheaper than
+/// scanning plain text for escapes.
+///
+/// # Example
+///
+/// ```
+/// use streamdown_ansi::utils::has_escape;
+/// assert!(has_escape("\x1b[1mBold"));
+/// assert!(!has_escape("plain"));
+/// ```
+pub fn has_escape(text: &str) -> bool {
+    memchr(0x1b, text.as_bytes()).is_some()
 }
 
 /// Calculate the visible display width of text.
 ///
 /// This removes all ANSI escape sequences and calculates the
-/// width using Unicode character widths (handling CJK characters, etc.).
+/// width with the installed [`WidthOracle`](crate::width::WidthOracle)
+/// (handling CJK characters, etc.).
 ///
 /// # Arguments
 ///
@@ -71,7 +240,10 @@ pub fn visible(text: &str) -> String {
 /// assert_eq!(visible_length(cjk), 4);
 /// ```
 pub fn visible_length(text: &str) -> usize {
-    visible(text).width()
+    if !has_escape(text) {
+        return str_width(text);
+    }
+    str_width(&visible(text))
 }
 
 /// Extract all ANSI escape codes from text.
@@ -91,9 +263,8 @@ pub fn visible_length(text: &str) -> usize {
 /// assert_eq!(codes, vec!["\x1b[1m", "\x1b[0m"]);
 /// ```
 pub fn extract_ansi_codes(text: &str) -> Vec<String> {
-    ESCAPE_RE
-        .find_iter(text)
-        .map(|m| m.as_str().to_string())
+    escape_ranges(text, sgr_escape_len)
+        .map(|(start, end)| text[start..end].to_string())
         .collect()
 }
 
@@ -140,11 +311,26 @@ pub fn remove_ansi(line: &str, code_list: &[String]) -> String {
 /// assert_eq!(parts, vec!["\x1b[1m", "Bold", "\x1b[0m", " text"]);
 /// ```
 pub fn split_up(line: &str) -> Vec<String> {
-    SPLIT_RE
-        .find_iter(line)
-        .map(|m| m.as_str().to_string())
-        .filter(|s| !s.is_empty())
-        .collect()
+    let bytes = line.as_bytes();
+    let mut parts = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        if bytes[pos] == 0x1b {
+            // An escape runs to the next `m`; an unterminated one is dropped
+            match memchr(b'm', &bytes[pos..]) {
+                Some(end) => {
+                    parts.push(line[pos..pos + end + 1].to_string());
+                    pos += end + 1;
+                }
+                None => pos += 1,
+            }
+        } else {
+            let end = memchr(0x1b, &bytes[pos..]).map_or(bytes.len(), |i| pos + i);
+            parts.push(line[pos..end].to_string());
+            pos = end;
+        }
+    }
+    parts
 }
 
 /// Check if a string is an ANSI escape code.
@@ -260,30 +446,26 @@ pub fn ansi_collapse(code_list: &[String], _inp: &str) -> Vec<String> {
                 23 => italic = false,
                 24 => underline = false,
                 29 => strikeout = false,
-                38 => {
-                    // Foreground color
-                    if i + 4 < params.len() && params[i + 1] == 2 {
-                        fg_color = Some(format!(
-                            "\x1b[38;2;{};{};{}m",
-                            params[i + 2],
-                            params[i + 3],
-                            params[i + 4]
-                        ));
-                        i += 4;
-                    }
+                // Foreground color
+                38 if i + 4 < params.len() && params[i + 1] == 2 => {
+                    fg_color = Some(format!(
+                        "\x1b[38;2;{};{};{}m",
+                        params[i + 2],
+                        params[i + 3],
+                        params[i + 4]
+                    ));
+                    i += 4;
                 }
                 39 => fg_color = None, // Reset foreground
-                48 => {
-                    // Background color
-                    if i + 4 < params.len() && params[i + 1] == 2 {
-                        bg_color = Some(format!(
-                            "\x1b[48;2;{};{};{}m",
-                            params[i + 2],
-                            params[i + 3],
-                            params[i + 4]
-                        ));
-                        i += 4;
-                    }
+                // Background color
+                48 if i + 4 < params.len() && params[i + 1] == 2 => {
+                    bg_color = Some(format!(
+                        "\x1b[48;2;{};{};{}m",
+                        params[i + 2],
+                        params[i + 3],
+                        params[i + 4]
+                    ));
+                    i += 4;
                 }
                 49 => bg_color = None, // Reset background
                 _ => {}
@@ -331,6 +513,9 @@ pub fn ansi_collapse(code_list: &[String], _inp: &str) -> Vec<String> {
 
 /// Wrap text to a specified width, preserving ANSI codes.
 ///
+/// Lines break where [`linebreak`](crate::linebreak) allows: after
+/// whitespace, or at any UAX #14 opportunity with the `linebreak` feature.
+///
 /// # Arguments
 ///
 /// * `text` - Text to wrap (may contain ANSI codes)
@@ -358,38 +543,35 @@ pub fn wrap_ansi(text: &str, width: usize) -> Vec<String> {
     let mut current_width = 0;
     let mut active_codes: Vec<String> = Vec::new();
 
-    for segment in split_up(text) {
-        if is_ansi_code(&segment) {
-            // Track active formatting
-            let params = parse_sgr_params(&segment);
-            if params.contains(&0) {
-                active_codes.clear();
-            } else {
-                active_codes.push(segment.clone());
+    // Pieces end where a line may break (see `linebreak`)
+    for piece in crate::linebreak::split_breakable(text) {
+        let piece_width = visible_length(piece);
+        if current_width + piece_width > width && current_width > 0 {
+            // Need to wrap
+            // Reset codes at end of line
+            if !active_codes.is_empty() {
+                current_line.push_str(crate::codes::RESET);
             }
-            current_line.push_str(&segment);
-        } else {
-            // Regular text - may need wrapping
-            for word in segment.split_inclusive(' ') {
-                let word_width = word.width();
-
-                if current_width + word_width > width && current_width > 0 {
-                    // Need to wrap
-                    // Reset codes at end of line
-                    if !active_codes.is_empty() {
-                        current_line.push_str(crate::codes::RESET);
-                    }
-                    lines.push(current_line);
-
-                    // Start new line with active codes
-                    current_line = active_codes.join("");
-                    current_width = 0;
-                }
+            lines.push(current_line);
 
-                current_line.push_str(word);
-                current_width += word_width;
+            // Start new line with active codes
+            current_line = active_codes.join("");
+            current_width = 0;
+        }
+
+        for segment in split_up(piece) {
+            if is_ansi_code(&segment) {
+                // Track active formatting
+                let params = parse_sgr_params(&segment);
+                if params.contains(&0) {
+                    active_codes.clear();
+                } else {
+                    active_codes.push(segment.clone());
+                }
             }
+            current_line.push_str(&segment);
         }
+        current_width += piece_width;
     }
 
     if !current_line.is_empty() || lines.is_empty() {
@@ -410,6 +592,23 @@ mod tests {
         assert_eq!(visible("\x1b[38;2;255;0;0mRed\x1b[0m"), "Red");
     }
 
+    #[test]
+    fn test_tokens() {
+        let text = "a\x1b[1mb\x1b\x1b[0m";
+        let pieces: Vec<(usize, Token)> = tokens(text).collect();
+        assert_eq!(
+            pieces,
+            [
+                (0, Token::Text("a")),
+                (1, Token::Escape("\x1b[1m")),
+                (5, Token::Text("b\x1b")),
+                (7, Token::Escape("\x1b[0m")),
+            ]
+        );
+        assert_eq!(tokens("").count(), 0);
+        assert_eq!(tokens("日本").next(), Some((0, Token::Text("日本"))));
+    }
+
     #[test]
     fn test_visible_length() {
         assert_eq!(visible_length("\x1b[1mHello\x1b[0m"), 5);
@@ -433,6 +632,28 @@ mod tests {
         assert_eq!(result, "Bold\x1b[0m");
     }
 
+    #[test]
+    fn test_scanners_match_patterns() {
+        let any = regex::Regex::new(ANSIESCAPE).unwrap();
+        let sgr = regex::Regex::new(ESCAPE).unwrap();
+        let split = regex::Regex::new(r"(\x1b[^m]*m|[^\x1b]+)").unwrap();
+        let inputs = [
+            "plain",
+            "\x1b[1mBold\x1b[0m and \x1b[2K erased",
+            "\x1b]8;;https://x.y\x1b\\link\x1b]8;;\x1b\\ done",
+            "\x1b]8;;no end\nnext \x1b)x \x1b[?25l cursor",
+            "lone \x1b escape, \x1b[12;x bad, \x1b[",
+            "\x1b]52;c;Zm9v\x07 \x1b[38;2;1;2;3m日本\x1b[m",
+        ];
+        for input in inputs {
+            assert_eq!(visible(input), any.replace_all(input, ""), "{input:?}");
+            let codes: Vec<_> = sgr.find_iter(input).map(|m| m.as_str()).collect();
+            assert_eq!(extract_ansi_codes(input), codes, "{input:?}");
+            let parts: Vec<_> = split.find_iter(input).map(|m| m.as_str()).collect();
+            assert_eq!(split_up(input), parts, "{input:?}");
+        }
+    }
+
     #[test]
     fn test_split_up() {
         let parts = split_up("\x1b[1mBold\x1b[0m text");
@@ -472,4 +693,13 @@ mod tests {
         let lines = wrap_ansi(text, 6);
         assert!(lines.len() >= 2);
     }
+
+    #[test]
+    fn test_wrap_ansi_carries_style() {
+        let lines = wrap_ansi("\x1b[1mbold words\x1b[0m end", 6);
+        assert_eq!(
+            lines,
+            ["\x1b[1mbold \x1b[0m", "\x1b[1mwords\x1b[0m ", "end"]
+        );
+    }
 }
@@ -0,0 +1,315 @@
+//! Display width model.
+//!
+//! Every column count in streamdown (wrapping, table layout, truncation)
+//! goes through a [`WidthOracle`]. The default oracle follows Unicode East
+//! Asian Width: ambiguous characters are narrow and emoji are wide. Terminals
+//! disagree on both, so hosts can pick the policies their terminal uses, or
+//! substitute per-character measurements entirely.
+//!
+//! Each renderer owns its oracle and installs it on the current thread with
+//! [`OracleScope`] while it draws; [`char_width`] and [`str_width`] measure
+//! with the installed oracle, or the standard one outside a scope. Without
+//! the `std` feature there are no scopes and other oracles are used directly.
+//!
+//! # Example
+//!
+//! ```
+//! use streamdown_ansi::width::{AmbiguousWidth, WidthOracle};
+//!
+//! let oracle = WidthOracle::new().with_ambiguous(AmbiguousWidth::Wide);
+//! assert_eq!(oracle.str_width("±1"), 3);
+//! assert_eq!(WidthOracle::new().str_width("±1"), 2);
+//! ```
+
+use alloc::sync::Arc;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::cell::RefCell;
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Width of East Asian "ambiguous" characters (`±`, `─`, `…`, Greek, ...).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AmbiguousWidth {
+    /// One column (Western terminals)
+    #[default]
+    Narrow,
+    /// Two columns (CJK locales)
+    Wide,
+}
+
+/// Width of emoji with default emoji presentation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmojiWidth {
+    /// Two columns, as Unicode specifies
+    #[default]
+    Wide,
+    /// One column (older terminals and fonts)
+    Narrow,
+}
+
+/// Per-character width override: `Some(width)` replaces the computed width,
+/// `None` falls back to the policies.
+pub type WidthOverride = Arc<dyn Fn(char) -> Option<usize> + Send + Sync>;
+
+/// The width model used to measure text.
+#[derive(Clone, Default)]
+pub struct WidthOracle {
+    /// Width of ambiguous characters
+    ambiguous: AmbiguousWidth,
+    /// Width of emoji
+    emoji: EmojiWidth,
+    /// Host-supplied measurements, consulted first
+    overrides: Option<WidthOverride>,
+}
+
+impl WidthOracle {
+    /// Create the standard oracle (narrow ambiguous, wide emoji).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the ambiguous-width policy.
+    pub fn with_ambiguous(mut self, ambiguous: AmbiguousWidth) -> Self {
+        self.ambiguous = ambiguous;
+        self
+    }
+
+    /// Set the emoji-width policy.
+    pub fn with_emoji(mut self, emoji: EmojiWidth) -> Self {
+        self.emoji = emoji;
+        self
+    }
+
+    /// Consult `overrides` before the policies for every character, e.g. with
+    /// widths measured on a specific terminal.
+    pub fn with_overrides(
+        mut self,
+        overrides: impl Fn(char) -> Option<usize> + Send + Sync + 'static,
+    ) -> Self {
+        self.overrides = Some(Arc::new(overrides));
+        self
+    }
+
+    /// Get the ambiguous-width policy.
+    pub fn ambiguous(&self) -> AmbiguousWidth {
+        self.ambiguous
+    }
+
+    /// Get the emoji-width policy.
+    pub fn emoji(&self) -> EmojiWidth {
+        self.emoji
+    }
+
+    /// Whether this oracle measures exactly like `unicode-width`.
+    pub fn is_standard(&self) -> bool {
+        self.ambiguous == AmbiguousWidth::Narrow
+            && self.emoji == EmojiWidth::Wide
+            && self.overrides.is_none()
+    }
+
+    /// Width of a single character in columns.
+    ///
+    /// Returns `None` for control characters, like
+    /// [`UnicodeWidthChar::width`].
+    pub fn char_width(&self, ch: char) -> Option<usize> {
+        if let Some(width) = self.overrides.as_ref().and_then(|f| f(ch)) {
+            return Some(width);
+        }
+        let width = match self.ambiguous {
+            AmbiguousWidth::Narrow => ch.width(),
+            AmbiguousWidth::Wide => ch.width_cjk(),
+        }?;
+        if self.emoji == EmojiWidth::Narrow && width == 2 && is_emoji(ch) {
+            return Some(1);
+        }
+        Some(width)
+    }
+
+    /// Width of plain text (no escape sequences) in columns.
+    ///
+    /// A zero-width joiner glues the next character into the current
+    /// grapheme, so joined emoji sequences count once, and a variation
+    /// selector 16 widens the character before it like `unicode-width` does.
+    pub fn str_width(&self, text: &str) -> usize {
+        if self.overrides.is_none() && self.emoji == EmojiWidth::Wide {
+            return match self.ambiguous {
+                AmbiguousWidth::Narrow => text.width(),
+                AmbiguousWidth::Wide => text.width_cjk(),
+            };
+        }
+
+        let mut width = 0;
+        let mut joined = false;
+        let mut prev = None;
+        for ch in text.chars() {
+            match ch {
+                '\u{200d}' => joined = true,
+                '\u{fe0f}' => {
+                    if let Some(base) = prev.take() {
+                        width += self.presentation_extra(base);
+                    }
+                }
+                _ if core::mem::take(&mut joined) => prev = None,
+                _ => {
+                    width += self.char_width(ch).unwrap_or(0);
+                    prev = Some(ch);
+                }
+            }
+        }
+        width
+    }
+
+    /// Extra columns `base` takes when followed by a variation selector 16.
+    fn presentation_extra(&self, base: char) -> usize {
+        if self.emoji == EmojiWidth::Narrow
+            || self.overrides.as_ref().is_some_and(|f| f(base).is_some())
+        {
+            return 0;
+        }
+        let mut buf = [0u8; 8];
+        let len = base.encode_utf8(&mut buf).len();
+        '\u{fe0f}'.encode_utf8(&mut buf[len..]);
+        let pair = core::str::from_utf8(&buf[..len + 3]).unwrap_or_default();
+        let (pair, single) = match self.ambiguous {
+            AmbiguousWidth::Narrow => (pair.width(), base.width()),
+            AmbiguousWidth::Wide => (pair.width_cjk(), base.width_cjk()),
+        };
+        pair.saturating_sub(single.unwrap_or(0))
+    }
+}
+
+impl fmt::Debug for WidthOracle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WidthOracle")
+            .field("ambiguous", &self.ambiguous)
+            .field("emoji", &self.emoji)
+            .field("overrides", &self.overrides.is_some())
+            .finish()
+    }
+}
+
+/// Whether `ch` is an emoji with default emoji presentation.
+fn is_emoji(ch: char) -> bool {
+    matches!(ch as u32, 0x1F000..=0x1FAFF | 0x2600..=0x27BF | 0x2B00..=0x2BFF)
+}
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    /// The oracle installed on this thread (None = standard).
+    static ORACLE: RefCell<Option<WidthOracle>> = const { RefCell::new(None) };
+}
+
+/// Installs an oracle on the current thread until dropped, then restores
+/// the previous one.
+///
+/// ```
+/// use streamdown_ansi::width::{AmbiguousWidth, OracleScope, WidthOracle, str_width};
+///
+/// let wide = WidthOracle::new().with_ambiguous(AmbiguousWidth::Wide);
+/// {
+///     let _scope = OracleScope::new(&wide);
+///     assert_eq!(str_width("±"), 2);
+/// }
+/// assert_eq!(str_width("±"), 1);
+/// ```
+#[cfg(feature = "std")]
+#[must_use = "the oracle is uninstalled when the scope is dropped"]
+pub struct OracleScope {
+    /// Oracle to restore on drop
+    previous: Option<WidthOracle>,
+}
+
+#[cfg(feature = "std")]
+impl OracleScope {
+    /// Install `oracle` on the current thread.
+    pub fn new(oracle: &WidthOracle) -> Self {
+        let installed = (!oracle.is_standard()).then(|| oracle.clone());
+        Self {
+            previous: ORACLE.replace(installed),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Drop for OracleScope {
+    fn drop(&mut self) {
+        ORACLE.set(self.previous.take());
+    }
+}
+
+/// Width of a character under the installed oracle.
+pub fn char_width(ch: char) -> Option<usize> {
+    #[cfg(feature = "std")]
+    if let Some(width) = ORACLE.with_borrow(|o| o.as_ref().map(|o| o.char_width(ch))) {
+        return width;
+    }
+    ch.width()
+}
+
+/// Width of plain text under the installed oracle.
+pub fn str_width(text: &str) -> usize {
+    #[cfg(feature = "std")]
+    if let Some(width) = ORACLE.with_borrow(|o| o.as_ref().map(|o| o.str_width(text))) {
+        return width;
+    }
+    text.width()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policies() {
+        let standard = WidthOracle::new();
+        assert!(standard.is_standard());
+        assert_eq!(standard.str_width("a你😀"), 5);
+        assert_eq!(standard.char_width('─'), Some(1));
+        assert_eq!(standard.char_width('\x07'), None);
+
+        let wide = WidthOracle::new().with_ambiguous(AmbiguousWidth::Wide);
+        assert_eq!(wide.char_width('─'), Some(2));
+        assert_eq!(wide.str_width("a"), 1);
+
+        let narrow = WidthOracle::new().with_emoji(EmojiWidth::Narrow);
+        assert_eq!(narrow.str_width("a你😀"), 4);
+        assert_eq!(narrow.str_width("👩\u{200d}💻"), 1);
+    }
+
+    #[test]
+    fn test_slow_path_matches_fast_path() {
+        let standard = WidthOracle::new();
+        let slow = WidthOracle::new().with_overrides(|_| None);
+        for text in [
+            "❤\u{fe0f}",
+            "a❤\u{fe0f}b",
+            "#\u{fe0f}\u{20e3}",
+            "👩\u{200d}💻",
+            "a你😀",
+        ] {
+            assert_eq!(slow.str_width(text), standard.str_width(text), "{text:?}");
+        }
+        let narrow = WidthOracle::new().with_emoji(EmojiWidth::Narrow);
+        assert_eq!(narrow.str_width("❤\u{fe0f}"), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_overrides_and_scope() {
+        let oracle = WidthOracle::new().with_overrides(|ch| (ch == '\u{e000}').then_some(2));
+        assert_eq!(oracle.str_width("\u{e000}x"), 3);
+
+        {
+            let _scope = OracleScope::new(&oracle);
+            assert_eq!(str_width("\u{e000}"), 2);
+            assert_eq!(char_width('\u{e000}'), Some(2));
+            {
+                let _inner = OracleScope::new(&WidthOracle::new());
+                assert_eq!(str_width("\u{e000}"), 1);
+            }
+            assert_eq!(str_width("\u{e000}"), 2);
+        }
+        assert_eq!(str_width("\u{e000}"), 1);
+    }
+}
@@ -5,10 +5,27 @@
 //!
 //! # Overview
 //!
-//! Configuration is loaded from platform-specific locations:
-//! - Linux: `~/.config/streamdown/config.toml`
-//! - macOS: `~/Library/Application Support/streamdown/config.toml`
-//! - Windows: `%APPDATA%\streamdown\config.toml`
+//! Configuration is loaded from the first of these files that exists (see
+//! [`Config::search_paths`]):
+//! 1. `$XDG_CONFIG_HOME/streamdown/config.toml`
+//! 2. `~/.config/streamdown/config.toml`
+//! 3. `~/.streamdown.toml`
+//! 4. the platform config directory: `~/Library/Application
+//!    Support/streamdown/config.toml` on macOS,
+//!    `%APPDATA%\streamdown\config.toml` on Windows
+//!
+//! Besides the `[features]` and `[style]` sections, the `[render]` section
+//! ([`RenderConfig`]) picks the theme, border style, colors and renderer
+//! toggles; `streamdown-render` turns a [`Config`] into a ready style and
+//! options.
+//!
+//! # Environment
+//!
+//! [`Config::apply_env`] overrides the loaded file with
+//! `STREAMDOWN_THEME` (the `[render]` theme), `STREAMDOWN_WIDTH` (the
+//! `[style]` width) and `STREAMDOWN_BORDERS` (the `[render]` border
+//! style). Settings resolve in this order, first wins: explicit API calls
+//! and command-line flags, the environment, the config file, the defaults.
 //!
 //! # Example
 //!
@@ -24,16 +41,27 @@
 
 mod computed;
 mod features;
+mod render;
 mod style;
 
 pub use computed::ComputedStyle;
 pub use features::FeaturesConfig;
+pub use render::RenderConfig;
 pub use style::{HsvMultiplier, StyleConfig};
 
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use streamdown_core::{Result, StreamdownError};
 
+/// Environment variable overriding the `[render]` theme.
+pub const ENV_THEME: &str = "STREAMDOWN_THEME";
+
+/// Environment variable overriding the `[style]` width.
+pub const ENV_WIDTH: &str = "STREAMDOWN_WIDTH";
+
+/// Environment variable overriding the `[render]` border style.
+pub const ENV_BORDERS: &str = "STREAMDOWN_BORDERS";
+
 /// Default TOML configuration string.
 ///
 /// This matches the Python implementation's default_toml exactly.
@@ -74,6 +102,10 @@ pub struct Config {
     /// Style configuration
     #[serde(default)]
     pub style: StyleConfig,
+
+    /// Renderer configuration
+    #[serde(default)]
+    pub render: RenderConfig,
 }
 
 impl Default for Config {
@@ -116,6 +148,34 @@ impl Config {
             .map(|dirs| dirs.config_dir().join("config.toml"))
     }
 
+    /// Returns the config file locations, in lookup order.
+    ///
+    /// `$XDG_CONFIG_HOME/streamdown/config.toml`,
+    /// `~/.config/streamdown/config.toml`, `~/.streamdown.toml`, then the
+    /// platform-specific [`config_path`](Self::config_path) if different.
+    pub fn search_paths() -> Vec<PathBuf> {
+        let xdg = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from);
+        let home = directories::BaseDirs::new().map(|dirs| dirs.home_dir().to_path_buf());
+        search_paths_in(xdg, home, Self::config_path())
+    }
+
+    /// Returns the first config file that exists, if any.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use streamdown_config::Config;
+    /// match Config::discover() {
+    ///     Some(path) => println!("Using {}", path.display()),
+    ///     None => println!("Using defaults"),
+    /// }
+    /// ```
+    pub fn discover() -> Option<PathBuf> {
+        Self::search_paths().into_iter().find(|path| path.is_file())
+    }
+
     /// Returns the platform-specific configuration directory.
     pub fn config_dir() -> Option<PathBuf> {
         directories::ProjectDirs::from("", "", "streamdown")
@@ -155,7 +215,8 @@ impl Config {
         Ok(config_path)
     }
 
-    /// Load configuration from the default platform-specific path.
+    /// Load configuration from the first config file found by
+    /// [`discover`](Self::discover).
     ///
     /// If no config file exists, returns the default configuration.
     ///
@@ -166,16 +227,11 @@ impl Config {
     /// let config = Config::load().unwrap();
     /// ```
     pub fn load() -> Result<Self> {
-        if let Some(config_path) = Self::config_path()
-            && config_path.exists()
-        {
-            let content = std::fs::read_to_string(&config_path)?;
-            return toml::from_str(&content)
-                .map_err(|e| StreamdownError::Config(format!("Parse error: {}", e)));
+        match Self::discover() {
+            Some(config_path) => Self::load_from(&config_path),
+            // Return defaults if no config found
+            None => Ok(Self::default()),
         }
-
-        // Return defaults if no config found
-        Ok(Self::default())
     }
 
     /// Load configuration from a specific path.
@@ -273,6 +329,66 @@ impl Config {
     pub fn merge(&mut self, other: &Config) {
         self.features.merge(&other.features);
         self.style.merge(&other.style);
+        self.render.merge(&other.render);
+    }
+
+    /// Apply the `STREAMDOWN_*` environment overrides.
+    ///
+    /// See [`apply_env_with`](Self::apply_env_with).
+    pub fn apply_env(&mut self) -> Result<()> {
+        self.apply_env_with(|name| std::env::var(name).ok())
+    }
+
+    /// Apply the environment overrides, given an environment lookup
+    /// function.
+    ///
+    /// Empty variables are ignored. A relative theme path is made absolute
+    /// against the current directory, since it did not come from the
+    /// config file. Fails, changing nothing, if `STREAMDOWN_WIDTH` is not
+    /// a number.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use streamdown_config::Config;
+    ///
+    /// let mut config = Config::default();
+    /// config
+    ///     .apply_env_with(|name| match name {
+    ///         "STREAMDOWN_WIDTH" => Some("72".to_string()),
+    ///         "STREAMDOWN_BORDERS" => Some("ascii".to_string()),
+    ///         _ => None,
+    ///     })
+    ///     .unwrap();
+    /// assert_eq!(config.style.width, 72);
+    /// assert_eq!(config.render.border_style.as_deref(), Some("ascii"));
+    /// ```
+    pub fn apply_env_with<F>(&mut self, var: F) -> Result<()>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let var = |name| var(name).filter(|value| !value.trim().is_empty());
+        let width = match var(ENV_WIDTH) {
+            Some(width) => Some(width.trim().parse::<usize>().map_err(|e| {
+                StreamdownError::Config(format!("{}={:?}: {}", ENV_WIDTH, width, e))
+            })?),
+            None => None,
+        };
+
+        if let Some(theme) = var(ENV_THEME) {
+            self.render.theme = Some(match theme.as_str() {
+                "dark" | "light" => theme,
+                path => std::path::absolute(path)
+                    .map_or(theme.clone(), |path| path.to_string_lossy().into_owned()),
+            });
+        }
+        if let Some(width) = width {
+            self.style.width = width;
+        }
+        if let Some(borders) = var(ENV_BORDERS) {
+            self.render.border_style = Some(borders.trim().to_string());
+        }
+        Ok(())
     }
 
     /// Save configuration to a file.
@@ -304,6 +420,27 @@ impl Config {
     }
 }
 
+/// Config file locations given the XDG config dir, home dir and platform
+/// config file, skipping duplicates.
+fn search_paths_in(
+    xdg: Option<PathBuf>,
+    home: Option<PathBuf>,
+    platform: Option<PathBuf>,
+) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if let Some(xdg) = xdg {
+        paths.push(xdg.join("streamdown").join("config.toml"));
+    }
+    if let Some(home) = home {
+        paths.push(home.join(".config").join("streamdown").join("config.toml"));
+        paths.push(home.join(".streamdown.toml"));
+    }
+    paths.extend(platform);
+    let mut seen = std::collections::HashSet::new();
+    paths.retain(|path| seen.insert(path.clone()));
+    paths
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -352,6 +489,62 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_search_paths_order() {
+        let paths = search_paths_in(
+            Some(PathBuf::from("/xdg")),
+            Some(PathBuf::from("/home/u")),
+            Some(PathBuf::from("/home/u/.config/streamdown/config.toml")),
+        );
+        assert_eq!(
+            paths,
+            [
+                PathBuf::from("/xdg/streamdown/config.toml"),
+                PathBuf::from("/home/u/.config/streamdown/config.toml"),
+                PathBuf::from("/home/u/.streamdown.toml"),
+            ]
+        );
+        assert!(search_paths_in(None, None, None).is_empty());
+    }
+
+    #[test]
+    fn test_render_section() {
+        let config: Config =
+            toml::from_str("[render]\nTheme = \"light\"\nLineNumbers = true").unwrap();
+        assert_eq!(config.render.theme.as_deref(), Some("light"));
+        assert_eq!(config.render.line_numbers, Some(true));
+        assert_eq!(Config::default().render, RenderConfig::default());
+    }
+
+    #[test]
+    fn test_apply_env() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, v)| v.to_string())
+            }
+        };
+        let mut config: Config =
+            toml::from_str("[style]\nWidth = 100\n[render]\nBorderStyle = \"heavy\"").unwrap();
+        config
+            .apply_env_with(env(&[(ENV_THEME, "light"), (ENV_BORDERS, "")]))
+            .unwrap();
+        assert_eq!(config.render.theme.as_deref(), Some("light"));
+        assert_eq!(config.render.border_style.as_deref(), Some("heavy"));
+        assert_eq!(config.style.width, 100);
+
+        config
+            .apply_env_with(env(&[(ENV_THEME, "mine.toml"), (ENV_WIDTH, "60")]))
+            .unwrap();
+        assert!(Path::new(config.render.theme.as_deref().unwrap()).is_absolute());
+        assert_eq!(config.style.width, 60);
+
+        let error = config.apply_env_with(env(&[(ENV_WIDTH, "wide"), (ENV_BORDERS, "ascii")]));
+        assert!(error.is_err());
+        assert_eq!(config.render.border_style.as_deref(), Some("heavy"));
+    }
+
     #[test]
     fn test_computed_style() {
         let config = Config::default();
@@ -0,0 +1,127 @@
+//! Renderer configuration.
+//!
+//! This module contains the `RenderConfig` struct, the `[render]` section
+//! choosing the theme, border style, colors and renderer feature toggles.
+//! Every field is optional: unset fields keep the renderer's defaults, and
+//! merging only overrides the fields the other config sets.
+//!
+//! Values are kept as strings here; `streamdown-render` parses them when
+//! it builds its style and options from the config.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Renderer configuration (`[render]` section).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct RenderConfig {
+    /// Theme: `"dark"`, `"light"` or the path of a TOML/JSON theme file
+    /// or a `.tmTheme` color scheme (which also becomes the highlight
+    /// theme), relative to the config file's directory.
+    /// Default: derived from the `[style]` HSV palette
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme: Option<String>,
+
+    /// Syntax highlighting theme: a built-in name (e.g.
+    /// `"base16-ocean.dark"`) or the path of a `.tmTheme` file, relative to
+    /// the config file's directory. Independent of `Theme`, unless that is a
+    /// `.tmTheme` and this is unset.
+    /// Default: base16-ocean.dark
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub highlight_theme: Option<String>,
+
+    /// Frame glyphs: `"light"`, `"heavy"`, `"double"`, `"rounded"` or
+    /// `"ascii"`.
+    /// Default: light
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub border_style: Option<String>,
+
+    /// When to write colors: `"auto"`, `"always"` or `"never"`.
+    /// Default: auto
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+
+    /// Syntax-highlight fenced code blocks.
+    /// Default: true
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub highlight: Option<bool>,
+
+    /// Show line numbers in code blocks.
+    /// Default: false
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_numbers: Option<bool>,
+
+    /// List link URLs at the end instead of after each link.
+    /// Default: false
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link_endnotes: Option<bool>,
+
+    /// Add a color swatch after `#rrggbb` inline code.
+    /// Default: false
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub swatches: Option<bool>,
+
+    /// Repaint streaming tables when their columns widen, and code blocks
+    /// when their language is detected.
+    /// Default: false
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub live_blocks: Option<bool>,
+
+    /// Render only the document outline.
+    /// Default: false
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outline: Option<bool>,
+
+    /// Extra fence language aliases, e.g. `{ hs = "haskell" }`, on top of
+    /// the built-in ones (`js`, `sh`, `py`...).
+    /// Default: none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language_aliases: Option<BTreeMap<String, String>>,
+}
+
+impl RenderConfig {
+    /// Merge another RenderConfig into this one.
+    ///
+    /// Fields set in `other` take precedence; unset fields keep this
+    /// config's values.
+    pub fn merge(&mut self, other: &RenderConfig) {
+        fn take<T: Clone>(mine: &mut Option<T>, theirs: &Option<T>) {
+            if theirs.is_some() {
+                mine.clone_from(theirs);
+            }
+        }
+        take(&mut self.theme, &other.theme);
+        take(&mut self.highlight_theme, &other.highlight_theme);
+        take(&mut self.border_style, &other.border_style);
+        take(&mut self.color, &other.color);
+        take(&mut self.highlight, &other.highlight);
+        take(&mut self.line_numbers, &other.line_numbers);
+        take(&mut self.link_endnotes, &other.link_endnotes);
+        take(&mut self.swatches, &other.swatches);
+        take(&mut self.live_blocks, &other.live_blocks);
+        take(&mut self.outline, &other.outline);
+        take(&mut self.language_aliases, &other.language_aliases);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_keeps_unset_fields() {
+        let mut base = RenderConfig {
+            theme: Some("light".to_string()),
+            outline: Some(true),
+            ..Default::default()
+        };
+        let other: RenderConfig =
+            toml::from_str("Outline = false\nBorderStyle = \"ascii\"").unwrap();
+        base.merge(&other);
+        assert_eq!(base.theme.as_deref(), Some("light"));
+        assert_eq!(base.border_style.as_deref(), Some("ascii"));
+        assert_eq!(base.outline, Some(false));
+        assert_eq!(base.highlight, None);
+    }
+}
@@ -23,8 +23,8 @@ pub enum Code {
     Flush,
 }
 
-impl std::fmt::Display for Code {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for Code {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             Code::Spaces => write!(f, "spaces"),
             Code::Backtick => write!(f, "backtick"),
@@ -44,8 +44,8 @@ pub enum ListType {
     Ordered,
 }
 
-impl std::fmt::Display for ListType {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for ListType {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             ListType::Bullet => write!(f, "bullet"),
             ListType::Ordered => write!(f, "ordered"),
@@ -62,8 +62,8 @@ pub enum TableState {
     Body,
 }
 
-impl std::fmt::Display for TableState {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for TableState {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             TableState::Header => write!(f, "header"),
             TableState::Body => write!(f, "body"),
@@ -80,8 +80,8 @@ pub enum BlockType {
     Think,
 }
 
-impl std::fmt::Display for BlockType {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for BlockType {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             BlockType::Quote => write!(f, "quote"),
             BlockType::Think => write!(f, "think"),
@@ -103,8 +103,8 @@ pub enum EmitFlag {
     Flush,
 }
 
-impl std::fmt::Display for EmitFlag {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for EmitFlag {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             EmitFlag::Header1 => write!(f, "header1"),
             EmitFlag::Header2 => write!(f, "header2"),
@@ -1,11 +1,13 @@
 //! Error types for streamdown
 
+use alloc::string::String;
 use thiserror::Error;
 
 /// Main error type for streamdown operations
 #[derive(Error, Debug)]
 pub enum StreamdownError {
     /// IO error during file operations
+    #[cfg(feature = "std")]
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
@@ -27,4 +29,4 @@ pub enum StreamdownError {
 }
 
 /// Result type alias for streamdown operations
-pub type Result<T> = std::result::Result<T, StreamdownError>;
+pub type Result<T> = core::result::Result<T, StreamdownError>;
@@ -0,0 +1,165 @@
+//! Fence language names.
+//!
+//! One table of the languages code fences are commonly labeled with: each
+//! language's canonical fence name, the syntect syntax that highlights it
+//! and the other names authors use for it. `streamdown-syntax` picks
+//! syntaxes from it and `streamdown-render` makes fence labels canonical
+//! with it, so the two always agree.
+//!
+//! ```
+//! use streamdown_core::languages::find_language;
+//!
+//! let language = find_language("JS").unwrap();
+//! assert_eq!(language.name, "javascript");
+//! assert_eq!(language.syntax, "JavaScript");
+//! assert!(find_language("unknown").is_none());
+//! ```
+
+/// A fence language.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Language {
+    /// Canonical fence name (lowercase)
+    pub name: &'static str,
+    /// Name of the syntect syntax highlighting it
+    pub syntax: &'static str,
+    /// Other fence names for it (lowercase)
+    pub aliases: &'static [&'static str],
+}
+
+impl Language {
+    const fn new(
+        name: &'static str,
+        syntax: &'static str,
+        aliases: &'static [&'static str],
+    ) -> Self {
+        Self {
+            name,
+            syntax,
+            aliases,
+        }
+    }
+
+    /// The canonical name followed by the aliases.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> {
+        core::iter::once(self.name).chain(self.aliases.iter().copied())
+    }
+}
+
+/// Known fence languages.
+pub const LANGUAGES: &[Language] = &[
+    Language::new("python", "Python", &["py", "python3", "py3"]),
+    Language::new("javascript", "JavaScript", &["js", "node", "nodejs"]),
+    Language::new("typescript", "TypeScript", &["ts"]),
+    Language::new("rust", "Rust", &["rs"]),
+    Language::new(
+        "bash",
+        "Bourne Again Shell (bash)",
+        &["sh", "shell", "zsh", "fish"],
+    ),
+    Language::new("c", "C", &["h"]),
+    Language::new("cpp", "C++", &["c++", "cc", "cxx", "hpp", "hxx"]),
+    Language::new("csharp", "C#", &["cs"]),
+    Language::new("go", "Go", &["golang"]),
+    Language::new("java", "Java", &[]),
+    Language::new("kotlin", "Kotlin", &["kt"]),
+    Language::new("swift", "Swift", &[]),
+    Language::new("ruby", "Ruby", &["rb"]),
+    Language::new("php", "PHP", &[]),
+    Language::new("perl", "Perl", &["pl"]),
+    Language::new("lua", "Lua", &[]),
+    Language::new("r", "R", &[]),
+    Language::new("scala", "Scala", &[]),
+    Language::new("haskell", "Haskell", &["hs"]),
+    Language::new("ocaml", "OCaml", &["ml"]),
+    Language::new("fsharp", "F#", &["fs"]),
+    Language::new("elixir", "Elixir", &["ex", "exs"]),
+    Language::new("erlang", "Erlang", &["erl"]),
+    Language::new("clojure", "Clojure", &["clj"]),
+    Language::new("sql", "SQL", &["mysql", "postgresql", "postgres", "sqlite"]),
+    Language::new("html", "HTML", &["htm", "xhtml"]),
+    Language::new("css", "CSS", &[]),
+    Language::new("scss", "SCSS", &[]),
+    Language::new("sass", "Sass", &[]),
+    Language::new("less", "Less", &[]),
+    Language::new("json", "JSON", &["jsonc"]),
+    Language::new("yaml", "YAML", &["yml"]),
+    Language::new("toml", "TOML", &[]),
+    Language::new("xml", "XML", &["xsl", "xslt", "svg"]),
+    Language::new("markdown", "Markdown", &["md", "mdown"]),
+    Language::new("latex", "LaTeX", &[]),
+    Language::new("tex", "TeX", &[]),
+    Language::new("makefile", "Makefile", &["make", "mk"]),
+    Language::new("dockerfile", "Dockerfile", &["docker"]),
+    Language::new("nginx", "nginx", &[]),
+    Language::new("ini", "INI", &["conf", "cfg"]),
+    Language::new("diff", "Diff", &["patch"]),
+    Language::new("git", "Git Commit", &["gitcommit"]),
+    Language::new("gitignore", "Git Ignore", &[]),
+    Language::new("lisp", "Lisp", &["elisp", "emacs-lisp", "commonlisp", "cl"]),
+    Language::new("scheme", "Scheme", &["racket"]),
+    Language::new("dart", "Dart", &[]),
+    Language::new("vue", "Vue Component", &[]),
+    Language::new("graphql", "GraphQL", &["gql"]),
+    Language::new("protobuf", "Protocol Buffers", &["proto"]),
+    Language::new("terraform", "Terraform", &["tf", "hcl"]),
+    Language::new("asm", "Assembly (x86_64)", &["assembly", "nasm"]),
+    Language::new("text", "Plain Text", &["txt", "plain"]),
+    Language::new("objc", "Objective-C", &["objective-c", "objectivec"]),
+    Language::new("objcpp", "Objective-C++", &["objective-c++"]),
+    Language::new("pascal", "Pascal", &["delphi"]),
+    Language::new("groovy", "Groovy", &[]),
+    Language::new("powershell", "PowerShell", &["ps1", "pwsh"]),
+    Language::new("batch", "Batch File", &["bat", "cmd"]),
+    Language::new("regex", "Regular Expression", &["regexp"]),
+    Language::new("applescript", "AppleScript", &[]),
+    Language::new("jsx", "JavaScript (Babel)", &[]),
+    Language::new("tsx", "TypeScript", &[]),
+    Language::new("coffeescript", "CoffeeScript", &["coffee"]),
+    Language::new("d", "D", &["dlang"]),
+    Language::new("nim", "Nim", &["nimrod"]),
+    Language::new("zig", "Zig", &[]),
+    Language::new("crystal", "Crystal", &["cr"]),
+    Language::new("julia", "Julia", &["jl"]),
+    Language::new("solidity", "Solidity", &["sol"]),
+    Language::new("vyper", "Vyper", &["vy"]),
+    Language::new("fortran", "Fortran (Modern)", &["f90", "f95", "f03"]),
+    Language::new("cobol", "COBOL", &["cob"]),
+    Language::new("actionscript", "ActionScript", &["as"]),
+    Language::new("handlebars", "Handlebars", &["hbs", "mustache"]),
+    Language::new("jinja", "Jinja", &["jinja2"]),
+    Language::new("puppet", "Puppet", &["pp"]),
+    Language::new("rst", "reStructuredText", &["restructuredtext", "rest"]),
+    Language::new("asciidoc", "AsciiDoc", &["adoc"]),
+    Language::new("org", "orgmode", &["orgmode"]),
+];
+
+/// The language `name` refers to, by its canonical name or an alias in any
+/// case.
+pub fn find_language(name: &str) -> Option<&'static Language> {
+    LANGUAGES.iter().find(|language| {
+        language
+            .names()
+            .any(|known| known.eq_ignore_ascii_case(name))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names_are_unique_and_lowercase() {
+        let mut seen = std::collections::HashSet::new();
+        for name in LANGUAGES.iter().flat_map(Language::names) {
+            assert_eq!(name, name.to_lowercase());
+            assert!(seen.insert(name), "{name} listed twice");
+        }
+    }
+
+    #[test]
+    fn test_find_language() {
+        assert_eq!(find_language("Py").unwrap().name, "python");
+        assert_eq!(find_language("cc").unwrap().syntax, "C++");
+        assert_eq!(find_language("jsx").unwrap().name, "jsx");
+    }
+}
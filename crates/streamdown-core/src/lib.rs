@@ -10,9 +10,20 @@
 //! - [`Code`], [`ListType`], [`TableState`], [`BlockType`], [`EmitFlag`] - State enums
 //! - [`StreamdownError`] - Error types
 //! - [`Position`], [`Span`] - Source location types
+//! - [`languages`] - Fence language names and aliases
+//!
+//! # Features
+//!
+//! - `std` (default) - `std::io::Error` conversion and the compiled prompt
+//!   regex. Without it the crate is `no_std` and needs only `alloc`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
 pub mod enums;
 pub mod error;
+pub mod languages;
 pub mod state;
 pub mod types;
 
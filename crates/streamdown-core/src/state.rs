@@ -4,6 +4,9 @@
 //! streaming markdown input incrementally.
 
 use crate::enums::{BlockType, Code, EmitFlag, ListType};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 
@@ -62,6 +65,7 @@ pub struct ParseState {
     /// Whether we might be at a shell prompt
     pub maybe_prompt: bool,
     /// Compiled regex for prompt detection
+    #[cfg(feature = "std")]
     pub prompt_regex: Option<Regex>,
     /// Current emit flag for special output handling
     pub emit_flag: Option<EmitFlag>,
@@ -195,6 +199,7 @@ impl ParseState {
             is_pty: false,
             is_exec: false,
             maybe_prompt: false,
+            #[cfg(feature = "std")]
             prompt_regex: None,
             emit_flag: None,
             scrape: None,
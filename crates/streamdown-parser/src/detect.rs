@@ -0,0 +1,153 @@
+//! Language detection for unlabeled code blocks.
+//!
+//! Lightweight heuristics over single lines: shebangs, keywords and syntax
+//! that reliably identify a language. A streaming parser only sees a block's
+//! first lines before they must be rendered, so guesses are made line by
+//! line and a line that doesn't identify anything returns `None`.
+//!
+//! ```
+//! use streamdown_parser::detect_language;
+//!
+//! assert_eq!(detect_language("#!/usr/bin/env python3"), Some("python"));
+//! assert_eq!(detect_language("fn main() {"), Some("rust"));
+//! assert_eq!(detect_language("hello world"), None);
+//! ```
+
+/// Lines of an unlabeled block tried before giving up.
+pub const DETECT_MAX_LINES: usize = 3;
+
+/// Guess the language of a code line.
+pub fn detect_language(line: &str) -> Option<&'static str> {
+    let line = line.trim();
+    if line.is_empty() {
+        return None;
+    }
+
+    if let Some(interpreter) = line.strip_prefix("#!") {
+        return detect_shebang(interpreter);
+    }
+
+    let starts = |prefixes: &[&str]| prefixes.iter().any(|p| line.starts_with(p));
+    let upper = line.to_ascii_uppercase();
+    // `export NAME=value` sets a shell variable; other exports are modules
+    let shell_export = line
+        .strip_prefix("export ")
+        .and_then(|rest| rest.split_whitespace().next())
+        .is_some_and(|word| word.contains('='));
+
+    let language = if starts(&["<?php"]) {
+        "php"
+    } else if starts(&["<?xml"]) {
+        "xml"
+    } else if upper.starts_with("<!DOCTYPE HTML") || starts(&["<html", "<div", "<head"]) {
+        "html"
+    } else if starts(&["diff --git", "--- a/", "+++ b/", "@@ -"]) {
+        "diff"
+    } else if starts(&["#include <iostream>", "std::", "template<", "template <"]) {
+        "cpp"
+    } else if starts(&["#include", "int main("]) {
+        "c"
+    } else if starts(&[
+        "fn ",
+        "pub fn ",
+        "pub struct ",
+        "impl ",
+        "use std::",
+        "let mut ",
+    ]) || line.starts_with("#[") && line.ends_with(']')
+    {
+        "rust"
+    } else if starts(&["package main", "func "]) {
+        "go"
+    } else if starts(&["public class ", "import java.", "public static void "]) {
+        "java"
+    } else if starts(&["def ", "from ", "elif ", "print("])
+        || line.starts_with("import ") && !line.ends_with(';') && !line.contains(" from ")
+    {
+        "python"
+    } else if starts(&["function ", "const ", "console.log(", "=> "])
+        || line.starts_with("export ") && !shell_export
+        || line.starts_with("import ") && line.contains(" from ")
+    {
+        "javascript"
+    } else if [
+        "SELECT ",
+        "INSERT INTO ",
+        "CREATE TABLE ",
+        "UPDATE ",
+        "DELETE FROM ",
+    ]
+    .iter()
+    .any(|p| upper.starts_with(p))
+    {
+        "sql"
+    } else if starts(&["$ ", "sudo ", "cd ", "echo ", "apt ", "brew ", "npm "]) || shell_export {
+        "bash"
+    } else if starts(&["FROM ", "RUN "]) {
+        "dockerfile"
+    } else if line.starts_with('{') && (line.len() == 1 || line[1..].trim_start().starts_with('"'))
+    {
+        "json"
+    } else if line.starts_with('[') && line.ends_with(']') && !line.contains(' ') {
+        "toml"
+    } else {
+        return None;
+    };
+    Some(language)
+}
+
+/// Language of a shebang interpreter (`/usr/bin/env python3`).
+fn detect_shebang(interpreter: &str) -> Option<&'static str> {
+    let mut words = interpreter.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        program = words.find(|w| !w.starts_with('-'))?;
+    }
+    let program = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    Some(match program {
+        "python" => "python",
+        "node" | "deno" | "bun" => "javascript",
+        "sh" | "bash" | "zsh" | "dash" | "ksh" => "bash",
+        "ruby" => "ruby",
+        "perl" => "perl",
+        "php" => "php",
+        "lua" => "lua",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shebangs() {
+        assert_eq!(detect_language("#!/bin/bash"), Some("bash"));
+        assert_eq!(
+            detect_language("#!/usr/bin/env -S node"),
+            Some("javascript")
+        );
+        assert_eq!(detect_language("#!/usr/bin/python3.12"), Some("python"));
+        assert_eq!(detect_language("#!/usr/bin/awk"), None);
+    }
+
+    #[test]
+    fn test_keywords() {
+        assert_eq!(detect_language("#[derive(Debug)]"), Some("rust"));
+        assert_eq!(detect_language("import os"), Some("python"));
+        assert_eq!(detect_language("import x from 'y'"), Some("javascript"));
+        assert_eq!(detect_language("select * from t"), Some("sql"));
+        assert_eq!(detect_language("{\"key\": 1}"), Some("json"));
+        assert_eq!(detect_language("[dependencies]"), Some("toml"));
+        assert_eq!(detect_language("#include <stdio.h>"), Some("c"));
+        assert_eq!(detect_language("export PATH=$HOME/bin"), Some("bash"));
+        assert_eq!(detect_language("export default App;"), Some("javascript"));
+    }
+
+    #[test]
+    fn test_prose_is_not_code() {
+        for line in ["", "   ", "Some text here", "{ not json }", "[a link] here"] {
+            assert_eq!(detect_language(line), None, "{line:?}");
+        }
+    }
+}
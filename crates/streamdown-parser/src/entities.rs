@@ -1,66 +1,64 @@
 //! HTML entity decoding
 
-use std::collections::HashMap;
-use std::sync::LazyLock;
+use alloc::string::{String, ToString};
 
-/// Common HTML entities mapping
-static HTML_ENTITIES: LazyLock<HashMap<&'static str, &'static str>> = LazyLock::new(|| {
-    let mut m = HashMap::new();
+/// Common HTML entities, in the order they are replaced
+const HTML_ENTITIES: &[(&str, &str)] = &[
     // Copyright, trademark, registered
-    m.insert("&copy;", "©");
-    m.insert("&trade;", "™");
-    m.insert("&reg;", "®");
+    ("&copy;", "©"),
+    ("&trade;", "™"),
+    ("&reg;", "®"),
     // Common symbols
-    m.insert("&amp;", "&");
-    m.insert("&lt;", "<");
-    m.insert("&gt;", ">");
-    m.insert("&quot;", "\"");
-    m.insert("&apos;", "'");
-    m.insert("&nbsp;", " ");
+    ("&lt;", "<"),
+    ("&gt;", ">"),
+    ("&quot;", "\""),
+    ("&apos;", "'"),
+    ("&nbsp;", " "),
     // Dashes and spaces
-    m.insert("&mdash;", "—");
-    m.insert("&ndash;", "–");
-    m.insert("&hellip;", "…");
+    ("&mdash;", "—"),
+    ("&ndash;", "–"),
+    ("&hellip;", "…"),
     // Arrows
-    m.insert("&larr;", "←");
-    m.insert("&rarr;", "→");
-    m.insert("&uarr;", "↑");
-    m.insert("&darr;", "↓");
+    ("&larr;", "←"),
+    ("&rarr;", "→"),
+    ("&uarr;", "↑"),
+    ("&darr;", "↓"),
     // Math
-    m.insert("&times;", "×");
-    m.insert("&divide;", "÷");
-    m.insert("&plusmn;", "±");
-    m.insert("&ne;", "≠");
-    m.insert("&le;", "≤");
-    m.insert("&ge;", "≥");
-    m.insert("&infin;", "∞");
+    ("&times;", "×"),
+    ("&divide;", "÷"),
+    ("&plusmn;", "±"),
+    ("&ne;", "≠"),
+    ("&le;", "≤"),
+    ("&ge;", "≥"),
+    ("&infin;", "∞"),
     // Currency
-    m.insert("&euro;", "€");
-    m.insert("&pound;", "£");
-    m.insert("&yen;", "¥");
-    m.insert("&cent;", "¢");
+    ("&euro;", "€"),
+    ("&pound;", "£"),
+    ("&yen;", "¥"),
+    ("&cent;", "¢"),
     // Other common
-    m.insert("&deg;", "°");
-    m.insert("&para;", "¶");
-    m.insert("&sect;", "§");
-    m.insert("&bull;", "•");
-    m.insert("&middot;", "·");
-    m.insert("&laquo;", "«");
-    m.insert("&raquo;", "»");
-    m.insert("&dagger;", "†");
-    m.insert("&Dagger;", "‡");
-    m.insert("&permil;", "‰");
-    m.insert("&prime;", "′");
-    m.insert("&Prime;", "″");
-    m
-});
+    ("&deg;", "°"),
+    ("&para;", "¶"),
+    ("&sect;", "§"),
+    ("&bull;", "•"),
+    ("&middot;", "·"),
+    ("&laquo;", "«"),
+    ("&raquo;", "»"),
+    ("&dagger;", "†"),
+    ("&Dagger;", "‡"),
+    ("&permil;", "‰"),
+    ("&prime;", "′"),
+    ("&Prime;", "″"),
+    // Last, so `&amp;lt;` decodes to `&lt;` rather than `<`
+    ("&amp;", "&"),
+];
 
 /// Decode HTML entities in a string
 pub fn decode_html_entities(text: &str) -> String {
     let mut result = text.to_string();
 
     // Replace named entities
-    for (entity, replacement) in HTML_ENTITIES.iter() {
+    for (entity, replacement) in HTML_ENTITIES {
         result = result.replace(entity, replacement);
     }
 
@@ -103,6 +101,7 @@ mod tests {
         assert_eq!(decode_html_entities("&trade;"), "™");
         assert_eq!(decode_html_entities("&reg;"), "®");
         assert_eq!(decode_html_entities("&amp;"), "&");
+        assert_eq!(decode_html_entities("&amp;lt;"), "&lt;");
     }
 
     #[test]
@@ -2,13 +2,22 @@
 //!
 //! This module handles parsing of inline markdown formatting including
 //! bold, italic, underline, strikethrough, inline code, links, images,
-//! and footnotes.
+//! footnotes, `<kbd>` key caps, and (opt-in) `||spoilers||` and `[[Ctrl]]`
+//! key shorthand.
+//!
+//! Lines can be parsed whole with [`InlineParser::parse`], or fed in chunks
+//! with [`InlineParser::push`]: formatting state is kept across chunks and
+//! each completed word is emitted as soon as it arrives, so long streamed
+//! paragraphs aren't re-scanned from the start of the line.
 
 use crate::tokenizer::{Token, Tokenizer};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 use streamdown_ansi::codes::digit_to_superscript;
 
 /// Result of parsing inline content.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum InlineElement {
     /// Plain text
     Text(String),
@@ -30,6 +39,10 @@ pub enum InlineElement {
     Image { alt: String, url: String },
     /// Footnote reference (as superscript)
     Footnote(String),
+    /// Spoiler text (`||hidden||`)
+    Spoiler(String),
+    /// Keyboard key (`<kbd>Ctrl</kbd>`, or `[[Ctrl]]` when enabled)
+    Kbd(String),
 }
 
 /// State for tracking active formatting.
@@ -54,7 +67,6 @@ impl FormatState {
         Self::default()
     }
 
-    #[allow(dead_code)]
     fn any_active(&self) -> bool {
         self.bold
             || self.italic
@@ -84,6 +96,12 @@ pub struct InlineParser {
     pub process_links: bool,
     /// Whether to process images  
     pub process_images: bool,
+    /// Whether to process `||spoiler||` markers
+    pub process_spoilers: bool,
+    /// Whether to read `[[Ctrl]]` as a key, like `<kbd>Ctrl</kbd>`
+    pub process_kbd_shorthand: bool,
+    /// Text pushed for the current line but not yet parsed
+    pending: String,
 }
 
 impl Default for InlineParser {
@@ -100,6 +118,9 @@ impl InlineParser {
             state: FormatState::new(),
             process_links: true,
             process_images: true,
+            process_spoilers: false,
+            process_kbd_shorthand: false,
+            pending: String::new(),
         }
     }
 
@@ -110,20 +131,100 @@ impl InlineParser {
             state: FormatState::new(),
             process_links,
             process_images,
+            process_spoilers: false,
+            process_kbd_shorthand: false,
+            pending: String::new(),
         }
     }
 
     /// Parse a line of markdown and return inline elements.
     ///
-    /// This is the main entry point for inline parsing.
+    /// This is the main entry point for inline parsing. Text buffered by
+    /// [`push`](Self::push) is not involved.
     pub fn parse(&mut self, line: &str) -> Vec<InlineElement> {
-        let tokens = self.tokenizer.tokenize(line);
-        self.parse_tokens(&tokens)
+        let mut elements = Vec::new();
+        self.parse_segment(line, &mut elements);
+        self.end_line(&mut elements);
+        elements
     }
 
-    /// Parse a sequence of tokens into inline elements.
-    fn parse_tokens(&mut self, tokens: &[Token]) -> Vec<InlineElement> {
+    /// Feed a chunk of the current line.
+    ///
+    /// Returns elements for the words completed so far. A trailing partial
+    /// word, and anything from an unclosed code span, link or spoiler
+    /// onwards, stays buffered (see [`pending`](Self::pending)) until more
+    /// input or [`finish_line`](Self::finish_line) arrives.
+    pub fn push(&mut self, chunk: &str) -> Vec<InlineElement> {
+        self.pending.push_str(chunk);
+        let stable = stable_prefix_len(&self.pending, self.process_spoilers);
+        let mut elements = Vec::new();
+        if stable > 0 {
+            let segment: String = self.pending.drain(..stable).collect();
+            self.parse_segment(&segment, &mut elements);
+        }
+        elements
+    }
+
+    /// Finish the current line: parse any buffered text and reset formatting.
+    pub fn finish_line(&mut self) -> Vec<InlineElement> {
+        let rest = core::mem::take(&mut self.pending);
         let mut elements = Vec::new();
+        self.parse_segment(&rest, &mut elements);
+        self.end_line(&mut elements);
+        elements
+    }
+
+    /// Text pushed for the current line that hasn't been parsed yet.
+    pub fn pending(&self) -> &str {
+        &self.pending
+    }
+
+    /// Whether an emphasis or code span opened by pushed text is still open,
+    /// so its text is held until the span closes or the line finishes.
+    pub fn in_span(&self) -> bool {
+        self.state.any_active()
+    }
+
+    /// Parse a segment of a line, keeping formatting state open at its end.
+    fn parse_segment(&mut self, segment: &str, elements: &mut Vec<InlineElement>) {
+        if self.process_spoilers && segment.contains("||") {
+            for (is_spoiler, part) in split_spoilers(segment) {
+                if is_spoiler {
+                    elements.push(InlineElement::Spoiler(part.to_string()));
+                } else {
+                    self.parse_text(part, elements);
+                }
+            }
+            return;
+        }
+
+        self.parse_text(segment, elements);
+    }
+
+    /// Parse text outside spoilers, picking out key caps.
+    fn parse_text(&mut self, text: &str, elements: &mut Vec<InlineElement>) {
+        let shorthand = self.process_kbd_shorthand;
+        if contains_ignore_case(text, "<kbd>") || (shorthand && text.contains("[[")) {
+            for (is_key, part) in split_keys(text, shorthand) {
+                if is_key {
+                    elements.push(InlineElement::Kbd(part.trim().to_string()));
+                } else {
+                    let tokens = self.tokenizer.tokenize(part);
+                    self.parse_tokens(&tokens, elements);
+                }
+            }
+            return;
+        }
+
+        let tokens = self.tokenizer.tokenize(text);
+        self.parse_tokens(&tokens, elements);
+    }
+
+    /// Parse a sequence of tokens into inline elements.
+    ///
+    /// Formatting state carries over to the next call until
+    /// [`end_line`](Self::end_line).
+    fn parse_tokens(&mut self, tokens: &[Token], elements: &mut Vec<InlineElement>) {
         let mut buffer = String::new();
         let mut i = 0;
 
@@ -135,7 +236,7 @@ impl InlineParser {
                 match token {
                     Token::Backticks(n) if *n == expected_backticks => {
                         // End of inline code
-                        let code = std::mem::take(&mut self.state.code_buffer);
+                        let code = core::mem::take(&mut self.state.code_buffer);
                         // Trim single leading/trailing space (Markdown spec)
                         let code = code.strip_prefix(' ').unwrap_or(&code);
                         let code = code.strip_suffix(' ').unwrap_or(code);
@@ -169,7 +270,7 @@ impl InlineParser {
                 Token::Backticks(n) => {
                     // Flush buffer
                     if !buffer.is_empty() {
-                        self.emit_formatted(&mut elements, std::mem::take(&mut buffer));
+                        self.emit_formatted(elements, core::mem::take(&mut buffer));
                     }
                     // Start inline code
                     self.state.code_backticks = Some(*n);
@@ -178,7 +279,7 @@ impl InlineParser {
                 Token::TripleAsterisk => {
                     // Flush buffer first
                     if !buffer.is_empty() {
-                        self.emit_formatted(&mut elements, std::mem::take(&mut buffer));
+                        self.emit_formatted(elements, core::mem::take(&mut buffer));
                     }
 
                     if self.state.bold && self.state.italic {
@@ -197,14 +298,14 @@ impl InlineParser {
 
                 Token::DoubleAsterisk => {
                     if !buffer.is_empty() {
-                        self.emit_formatted(&mut elements, std::mem::take(&mut buffer));
+                        self.emit_formatted(elements, core::mem::take(&mut buffer));
                     }
                     self.state.bold = !self.state.bold;
                 }
 
                 Token::Asterisk => {
                     if !buffer.is_empty() {
-                        self.emit_formatted(&mut elements, std::mem::take(&mut buffer));
+                        self.emit_formatted(elements, core::mem::take(&mut buffer));
                     }
                     self.state.italic = !self.state.italic;
                 }
@@ -212,7 +313,7 @@ impl InlineParser {
                 Token::DoubleAsteriskUnderscore => {
                     // **_ = start bold + start italic
                     if !buffer.is_empty() {
-                        self.emit_formatted(&mut elements, std::mem::take(&mut buffer));
+                        self.emit_formatted(elements, core::mem::take(&mut buffer));
                     }
                     if !self.state.bold {
                         self.state.bold = true;
@@ -223,7 +324,7 @@ impl InlineParser {
                 Token::UnderscoreDoubleAsterisk => {
                     // _** = end italic + end bold
                     if !buffer.is_empty() {
-                        self.emit_formatted(&mut elements, std::mem::take(&mut buffer));
+                        self.emit_formatted(elements, core::mem::take(&mut buffer));
                     }
                     self.state.italic = false;
                     self.state.bold = false;
@@ -231,7 +332,7 @@ impl InlineParser {
 
                 Token::TripleUnderscore => {
                     if !buffer.is_empty() {
-                        self.emit_formatted(&mut elements, std::mem::take(&mut buffer));
+                        self.emit_formatted(elements, core::mem::take(&mut buffer));
                     }
 
                     if self.state.underline && self.state.italic {
@@ -247,7 +348,7 @@ impl InlineParser {
 
                 Token::DoubleUnderscore => {
                     if !buffer.is_empty() {
-                        self.emit_formatted(&mut elements, std::mem::take(&mut buffer));
+                        self.emit_formatted(elements, core::mem::take(&mut buffer));
                     }
                     self.state.underline = !self.state.underline;
                 }
@@ -266,7 +367,7 @@ impl InlineParser {
                         buffer.push('_');
                     } else {
                         if !buffer.is_empty() {
-                            self.emit_formatted(&mut elements, std::mem::take(&mut buffer));
+                            self.emit_formatted(elements, core::mem::take(&mut buffer));
                         }
                         self.state.italic = !self.state.italic;
                     }
@@ -274,14 +375,14 @@ impl InlineParser {
 
                 Token::DoubleTilde => {
                     if !buffer.is_empty() {
-                        self.emit_formatted(&mut elements, std::mem::take(&mut buffer));
+                        self.emit_formatted(elements, core::mem::take(&mut buffer));
                     }
                     self.state.strikeout = !self.state.strikeout;
                 }
 
                 Token::Link { text, url } => {
                     if !buffer.is_empty() {
-                        self.emit_formatted(&mut elements, std::mem::take(&mut buffer));
+                        self.emit_formatted(elements, core::mem::take(&mut buffer));
                     }
                     elements.push(InlineElement::Link {
                         text: text.clone(),
@@ -291,7 +392,7 @@ impl InlineParser {
 
                 Token::Image { alt, url } => {
                     if !buffer.is_empty() {
-                        self.emit_formatted(&mut elements, std::mem::take(&mut buffer));
+                        self.emit_formatted(elements, core::mem::take(&mut buffer));
                     }
                     elements.push(InlineElement::Image {
                         alt: alt.clone(),
@@ -301,7 +402,7 @@ impl InlineParser {
 
                 Token::Footnote(num) => {
                     if !buffer.is_empty() {
-                        self.emit_formatted(&mut elements, std::mem::take(&mut buffer));
+                        self.emit_formatted(elements, core::mem::take(&mut buffer));
                     }
                     // Convert number to superscript
                     let superscript = number_to_superscript(*num);
@@ -314,12 +415,15 @@ impl InlineParser {
 
         // Flush remaining buffer
         if !buffer.is_empty() {
-            self.emit_formatted(&mut elements, buffer);
+            self.emit_formatted(elements, buffer);
         }
+    }
 
+    /// Close the line: flush unclosed code and reset formatting.
+    fn end_line(&mut self, elements: &mut Vec<InlineElement>) {
         // Flush any unclosed code block
         if self.state.code_backticks.is_some() {
-            let code = std::mem::take(&mut self.state.code_buffer);
+            let code = core::mem::take(&mut self.state.code_buffer);
             if !code.is_empty() {
                 elements.push(InlineElement::Code(code));
             }
@@ -328,8 +432,6 @@ impl InlineParser {
 
         // Reset state for next line
         self.state.reset();
-
-        elements
     }
 
     /// Emit formatted text based on current state.
@@ -356,11 +458,182 @@ impl InlineParser {
     /// Reset the parser state.
     pub fn reset(&mut self) {
         self.state.reset();
+        self.pending.clear();
+    }
+}
+
+/// Length of the prefix of `text` that can be parsed without more input.
+///
+/// The prefix ends after whitespace that is outside any code span, link
+/// brackets, `<kbd>` tag or (with `spoilers`) open `||` pair, so no construct straddles
+/// the boundary and underscore context is preserved on both sides.
+fn stable_prefix_len(text: &str, spoilers: bool) -> usize {
+    let bytes = text.as_bytes();
+    let mut stable = 0;
+    let mut code_run: Option<usize> = None;
+    let mut brackets = 0usize;
+    let mut spoiler_open = false;
+    let mut kbd_open = false;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'`' => {
+                let run = bytes[i..].iter().take_while(|&&b| b == b'`').count();
+                code_run = match code_run {
+                    Some(n) if n == run => None,
+                    None => Some(run),
+                    other => other,
+                };
+                i += run;
+                continue;
+            }
+            _ if code_run.is_some() => {}
+            b'[' => brackets += 1,
+            // A link's `](` keeps it open until the closing paren
+            b']' if bytes.get(i + 1) != Some(&b'(') => brackets = brackets.saturating_sub(1),
+            b')' => brackets = brackets.saturating_sub(1),
+            b'|' if spoilers && bytes.get(i + 1) == Some(&b'|') => {
+                spoiler_open = !spoiler_open;
+                i += 2;
+                continue;
+            }
+            b'<' if starts_with_ignore_case(&bytes[i..], b"<kbd>") => kbd_open = true,
+            b'<' if starts_with_ignore_case(&bytes[i..], b"</kbd>") => kbd_open = false,
+            b if b.is_ascii_whitespace() && brackets == 0 && !spoiler_open && !kbd_open => {
+                stable = i + 1
+            }
+            _ => {}
+        }
+        i += 1;
     }
+    stable
+}
+
+/// Split a line into `(is_spoiler, text)` segments on `||` pairs.
+///
+/// Markers inside inline code spans are ignored, as are empty pairs.
+fn split_spoilers(line: &str) -> Vec<(bool, &str)> {
+    let bytes = line.as_bytes();
+    let mut segments = Vec::new();
+    let mut segment_start = 0;
+    let mut open: Option<usize> = None;
+    let mut code_run: Option<usize> = None;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'`' {
+            let run = bytes[i..].iter().take_while(|&&b| b == b'`').count();
+            code_run = match code_run {
+                Some(n) if n == run => None,
+                None => Some(run),
+                other => other,
+            };
+            i += run;
+            continue;
+        }
+
+        if code_run.is_none() && bytes[i] == b'|' && bytes.get(i + 1) == Some(&b'|') {
+            match open.take() {
+                None => open = Some(i),
+                Some(start) => {
+                    let inner = &line[start + 2..i];
+                    if !inner.trim().is_empty() {
+                        if start > segment_start {
+                            segments.push((false, &line[segment_start..start]));
+                        }
+                        segments.push((true, inner));
+                        segment_start = i + 2;
+                    }
+                }
+            }
+            i += 2;
+            continue;
+        }
+
+        i += 1;
+    }
+
+    if segment_start < line.len() {
+        segments.push((false, &line[segment_start..]));
+    }
+    segments
+}
+
+/// Split text into `(is_key, text)` segments on `<kbd>...</kbd>` tags and,
+/// with `shorthand`, `[[...]]` pairs.
+///
+/// Tags inside inline code spans are ignored, as are empty keys and
+/// shorthand keys containing brackets.
+fn split_keys(text: &str, shorthand: bool) -> Vec<(bool, &str)> {
+    let bytes = text.as_bytes();
+    let mut segments = Vec::new();
+    let mut segment_start = 0;
+    let mut code_run: Option<usize> = None;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'`' {
+            let run = bytes[i..].iter().take_while(|&&b| b == b'`').count();
+            code_run = match code_run {
+                Some(n) if n == run => None,
+                None => Some(run),
+                other => other,
+            };
+            i += run;
+            continue;
+        }
+
+        let key = match bytes[i] {
+            _ if code_run.is_some() => None,
+            b'<' if starts_with_ignore_case(&bytes[i..], b"<kbd>") => {
+                find_ignore_case(&bytes[i + 5..], b"</kbd>")
+                    .map(|len| (i + 5, i + 5 + len, i + 11 + len))
+            }
+            b'[' if shorthand && bytes[i..].starts_with(b"[[") => text[i + 2..]
+                .find("]]")
+                .map(|len| (i + 2, i + 2 + len, i + 4 + len))
+                .filter(|&(start, end, _)| !text[start..end].contains(['[', ']'])),
+            _ => None,
+        };
+        match key {
+            Some((start, end, next)) if !text[start..end].trim().is_empty() => {
+                if i > segment_start {
+                    segments.push((false, &text[segment_start..i]));
+                }
+                segments.push((true, &text[start..end]));
+                segment_start = next;
+                i = next;
+            }
+            _ => i += 1,
+        }
+    }
+
+    if segment_start < text.len() {
+        segments.push((false, &text[segment_start..]));
+    }
+    segments
+}
+
+/// Whether `bytes` starts with the ASCII `prefix`, ignoring case.
+pub(crate) fn starts_with_ignore_case(bytes: &[u8], prefix: &[u8]) -> bool {
+    bytes
+        .get(..prefix.len())
+        .is_some_and(|head| head.eq_ignore_ascii_case(prefix))
+}
+
+/// Offset of the first occurrence of the ASCII `needle`, ignoring case.
+pub(crate) fn find_ignore_case(bytes: &[u8], needle: &[u8]) -> Option<usize> {
+    (0..bytes.len()).find(|&i| starts_with_ignore_case(&bytes[i..], needle))
+}
+
+/// Whether `text` contains the ASCII `needle`, ignoring case.
+fn contains_ignore_case(text: &str, needle: &str) -> bool {
+    find_ignore_case(text.as_bytes(), needle.as_bytes()).is_some()
 }
 
 /// Convert a number to superscript string.
-fn number_to_superscript(num: u32) -> String {
+pub fn number_to_superscript(num: u32) -> String {
     num.to_string()
         .chars()
         .map(|c| {
@@ -437,6 +710,18 @@ pub fn format_line(line: &str, process_links: bool, process_images: bool) -> Str
             InlineElement::Footnote(s) => {
                 result.push_str(&s);
             }
+            InlineElement::Spoiler(s) => {
+                result.push_str(DIM_ON);
+                result.push_str(&s);
+                result.push_str(DIM_OFF);
+            }
+            InlineElement::Kbd(s) => {
+                result.push_str(REVERSE.0);
+                result.push(' ');
+                result.push_str(&s);
+                result.push(' ');
+                result.push_str(REVERSE.1);
+            }
         }
     }
 
@@ -685,6 +970,114 @@ mod tests {
         );
     }
 
+    /// Merge adjacent elements of the same kind so chunked and whole-line
+    /// output can be compared.
+    fn coalesce(elements: Vec<InlineElement>) -> Vec<InlineElement> {
+        use InlineElement::*;
+        let mut out: Vec<InlineElement> = Vec::new();
+        for element in elements {
+            match (out.last_mut(), element) {
+                (Some(Text(a)), Text(b))
+                | (Some(Bold(a)), Bold(b))
+                | (Some(Italic(a)), Italic(b))
+                | (Some(BoldItalic(a)), BoldItalic(b)) => a.push_str(&b),
+                (_, element) => out.push(element),
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn test_parse_kbd() {
+        let key = |k: &str| InlineElement::Kbd(k.to_string());
+        let mut parser = InlineParser::new();
+        assert_eq!(
+            parser.parse("<kbd>Ctrl</kbd>+<kbd>C</kbd> copies"),
+            vec![
+                key("Ctrl"),
+                InlineElement::Text("+".to_string()),
+                key("C"),
+                InlineElement::Text(" copies".to_string()),
+            ]
+        );
+        // Not inside code, and the shorthand is opt-in
+        assert_eq!(
+            parser.parse("`<kbd>x</kbd>` [[Esc]]"),
+            vec![
+                InlineElement::Code("<kbd>x</kbd>".to_string()),
+                InlineElement::Text(" [[Esc]]".to_string()),
+            ]
+        );
+        parser.process_kbd_shorthand = true;
+        assert_eq!(
+            parser.parse("[[Esc]] or [[a [b]]"),
+            vec![key("Esc"), InlineElement::Text(" or [[a [b]]".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_push_matches_parse() {
+        let lines = [
+            "some **bold text across words** and *italic* too",
+            "a `code span with spaces` then [a link](http://x.y) end",
+            "snake_case_name and _real italic_ here",
+            "***both at once*** plain",
+            "press <kbd>Page Up</kbd> then <KBD>q</KBD> to quit",
+        ];
+        for line in lines {
+            let whole = InlineParser::new().parse(line);
+            for size in [1, 3, 7] {
+                let mut parser = InlineParser::new();
+                let mut chunked = Vec::new();
+                let chars: Vec<char> = line.chars().collect();
+                for chunk in chars.chunks(size) {
+                    chunked.extend(parser.push(&chunk.iter().collect::<String>()));
+                }
+                chunked.extend(parser.finish_line());
+                assert_eq!(
+                    coalesce(chunked),
+                    coalesce(whole.clone()),
+                    "{line:?} / {size}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_push_emits_completed_words() {
+        let mut parser = InlineParser::new();
+        assert!(parser.push("**bol").is_empty());
+        assert_eq!(parser.pending(), "**bol");
+
+        assert!(!parser.in_span());
+
+        let elements = parser.push("d text** more");
+        assert_eq!(
+            elements,
+            vec![
+                InlineElement::Bold("bold text".to_string()),
+                InlineElement::Text(" ".to_string()),
+            ]
+        );
+        assert_eq!(parser.pending(), "more");
+        assert!(!parser.in_span());
+
+        let mut open = InlineParser::new();
+        open.push("an *open span");
+        assert!(open.in_span());
+        open.finish_line();
+        assert!(!open.in_span());
+
+        // Open code spans and links wait for their closing marker
+        parser.push(" `a b");
+        assert_eq!(parser.pending(), "`a b");
+        assert_eq!(
+            parser.finish_line().last(),
+            Some(&InlineElement::Code("a b".to_string()))
+        );
+        assert!(parser.pending().is_empty());
+    }
+
     #[test]
     fn test_format_line() {
         let result = format_line("Hello **bold** world", true, true);
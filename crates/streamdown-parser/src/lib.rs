@@ -25,53 +25,209 @@
 //!     }
 //! }
 //! ```
+//!
+//! # Features
+//!
+//! - `std` (default) - build against the standard library. Without it the
+//!   crate is `no_std` and needs only `alloc`.
+//! - `serde` - Serialize/Deserialize for [`ParseEvent`] and its parts.
+
+#![cfg_attr(not(feature = "std"), no_std)]
 
+extern crate alloc;
+
+pub mod detect;
 pub mod entities;
 pub mod inline;
 pub mod tokenizer;
 
+pub use detect::{DETECT_MAX_LINES, detect_language};
 pub use entities::decode_html_entities;
-pub use inline::{InlineElement, InlineParser, format_line};
+pub use inline::{InlineElement, InlineParser, format_line, number_to_superscript};
 pub use tokenizer::{Token, Tokenizer, cjk_count, is_cjk, not_text};
 
-use regex::Regex;
-use std::sync::LazyLock;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use inline::{find_ignore_case, starts_with_ignore_case};
 use streamdown_core::{BlockType, Code, ListType, ParseState};
 
 // =============================================================================
-// Regex patterns
+// Line patterns
 // =============================================================================
 
-/// Regex for code fence: ``` or ~~~ or <pre>
-static CODE_FENCE_RE: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"^\s*(```+|~~~+|<pre>)\s*([^\s]*)\s*$").unwrap());
+/// Summary used when a `<details>` block has no `<summary>`
+pub const DEFAULT_DETAILS_SUMMARY: &str = "Details";
+
+/// A fence marker at the start of `text`: three or more backticks or tildes,
+/// or the `tag` (`<pre>` or `</pre>`). Returns the marker and what follows.
+fn fence_marker<'a>(text: &'a str, tag: &str) -> Option<(&'a str, &'a str)> {
+    for mark in ['`', '~'] {
+        let len = text.len() - text.trim_start_matches(mark).len();
+        if len >= 3 {
+            return Some(text.split_at(len));
+        }
+    }
+    text.strip_prefix(tag)
+        .map(|rest| (&text[..tag.len()], rest))
+}
+
+/// Code fence opener: ``` or ~~~ or <pre>, then an optional language word
+/// and nothing else. Returns the fence and the (possibly empty) language.
+fn code_fence(line: &str) -> Option<(&str, &str)> {
+    let (fence, rest) = fence_marker(line.trim_start(), "<pre>")?;
+    let rest = rest.trim_start();
+    let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+    rest[end..]
+        .trim_start()
+        .is_empty()
+        .then_some((fence, &rest[..end]))
+}
+
+/// Code fence closer (also matches </pre>). Returns the fence.
+fn code_fence_end(line: &str) -> Option<&str> {
+    let (fence, rest) = fence_marker(line.trim_start(), "</pre>")?;
+    rest.trim().is_empty().then_some(fence)
+}
+
+/// Space-indented code: 4+ spaces, not starting with * for lists
+fn is_space_code(line: &str) -> bool {
+    line.strip_prefix("    ")
+        .and_then(|rest| rest.trim_start().chars().next())
+        .is_some_and(|c| c != '*')
+}
+
+/// Heading: 1-6 `#`, whitespace, content. Returns the hashes and content.
+fn heading(line: &str) -> Option<(&str, &str)> {
+    let hashes = line.len() - line.trim_start_matches('#').len();
+    let rest = &line[hashes..];
+    ((1..=6).contains(&hashes) && rest.starts_with(char::is_whitespace))
+        .then(|| (&line[..hashes], rest.trim_start()))
+}
+
+/// List item: handles -, *, +, +---, and 1. style. Returns the indent, the
+/// bullet and the content.
+fn list_item(line: &str) -> Option<(&str, &str, &str)> {
+    let body = line.trim_start();
+    let indent = &line[..line.len() - body.len()];
+    let dashes = |rest: &str| rest.len() - rest.trim_start_matches('-').len();
+    let digits = body.len() - body.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let bullets = [
+        body.starts_with(['+', '*', '-']).then_some(1),
+        body.strip_prefix('+')
+            .map(dashes)
+            .filter(|&n| n > 0)
+            .map(|n| n + 1),
+        (digits > 0 && body[digits..].starts_with('.')).then_some(digits + 1),
+    ];
+    let len = bullets
+        .into_iter()
+        .flatten()
+        .find(|&len| body[len..].starts_with(char::is_whitespace))?;
+    Some((indent, &body[..len], body[len..].trim_start()))
+}
+
+/// Length of `.?think` at the start of `text`: any one character, or none,
+/// then `think`.
+fn any_then_think(text: &str) -> Option<usize> {
+    let first = text.chars().next()?.len_utf8();
+    if text[first..].starts_with("think") {
+        Some(first + "think".len())
+    } else {
+        text.starts_with("think").then_some("think".len())
+    }
+}
 
-/// Regex for code fence end (also matches </pre>)
-static CODE_FENCE_END_RE: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"^\s*(```+|~~~+|</pre>)\s*$").unwrap());
+/// Blockquote markers (`>`, `> >`) and think tags (including unicode
+/// variants). Returns the marker and the content after it.
+fn block_marker(line: &str) -> Option<(&str, &str)> {
+    let text = line.trim_start();
+    let is_close = |c: char| c == '>' || c == '▷';
+    let len = if text.starts_with('>') {
+        let mut rest = text;
+        while let Some(after) = rest.strip_prefix('>') {
+            rest = after.trim_start();
+        }
+        Some(text.len() - rest.len())
+    } else {
+        // `[◁<].?think[>▷]`
+        let open = text.chars().next().filter(|&c| c == '◁' || c == '<');
+        let tag = open.and_then(|c| {
+            let after = &text[c.len_utf8()..];
+            let len = c.len_utf8() + any_then_think(after)?;
+            let close = text[len..].chars().next().filter(|&c| is_close(c))?;
+            Some(len + close.len_utf8())
+        });
+        // `</?.?think[>▷]?`
+        tag.or_else(|| {
+            let after = text.strip_prefix('<')?;
+            let len = 1 + after
+                .strip_prefix('/')
+                .and_then(any_then_think)
+                .map(|len| len + 1)
+                .or_else(|| any_then_think(after))?;
+            let close = text[len..].chars().next().filter(|&c| is_close(c));
+            Some(len + close.map_or(0, char::len_utf8))
+        })
+    }?;
+    Some(text.split_at(len))
+}
 
-/// Regex for space-indented code (4+ spaces, not starting with * for lists)
-static SPACE_CODE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^    \s*[^\s*]").unwrap());
+/// `text` after the ASCII `prefix`, ignoring case.
+fn strip_prefix_ignore_case<'a>(text: &'a str, prefix: &str) -> Option<&'a str> {
+    starts_with_ignore_case(text.as_bytes(), prefix.as_bytes()).then(|| &text[prefix.len()..])
+}
 
-/// Regex for headings
-static HEADING_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(#{1,6})\s+(.*)$").unwrap());
+/// `<details>` opener (optionally `open`), with trailing content. Returns
+/// whether it is open and the content.
+fn details_start(line: &str) -> Option<(bool, &str)> {
+    let rest = strip_prefix_ignore_case(line.trim_start(), "<details")?;
+    let trimmed = rest.trim_start();
+    let open = (trimmed.len() < rest.len())
+        .then(|| strip_prefix_ignore_case(trimmed, "open"))
+        .flatten()
+        .and_then(|after| after.trim_start().strip_prefix('>'));
+    match open {
+        Some(content) => Some((true, content.trim_start())),
+        None => Some((false, trimmed.strip_prefix('>')?.trim_start())),
+    }
+}
 
-/// Regex for list items: handles -, *, +, +---, and 1. style
-static LIST_ITEM_RE: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"^(\s*)([+*-]|\+-+|\d+\.)\s+(.*)$").unwrap());
+/// `<summary>...</summary>`, with trailing content. Returns the summary and
+/// the content.
+fn summary(line: &str) -> Option<(&str, &str)> {
+    let rest = strip_prefix_ignore_case(line.trim_start(), "<summary>")?;
+    let end = find_ignore_case(rest.as_bytes(), b"</summary>")?;
+    Some((&rest[..end], rest[end + "</summary>".len()..].trim_start()))
+}
 
-/// Regex for blockquotes and think blocks (including unicode variants)
-static BLOCK_RE: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"^\s*((>\s*)+|[◁<].?think[>▷]|</?.?think[>▷]?)(.*)$").unwrap());
+/// `</details>`
+fn is_details_end(line: &str) -> bool {
+    line.trim().eq_ignore_ascii_case("</details>")
+}
 
-/// Regex for horizontal rules
-static HR_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(---+|\*\*\*+|___+)\s*$").unwrap());
+/// Horizontal rule: three or more of one of `-`, `*` or `_`
+fn is_hr(line: &str) -> bool {
+    let line = line.trim();
+    line.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|&c| line.chars().all(|x| x == c))
+}
 
-/// Regex for table rows
-static TABLE_ROW_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*\|(.+)\|\s*$").unwrap());
+/// Table row: `|` ... `|`. Returns what is between the outer pipes.
+fn table_row(line: &str) -> Option<&str> {
+    let inner = line.trim().strip_prefix('|')?.strip_suffix('|')?;
+    (!inner.is_empty()).then_some(inner)
+}
 
-/// Regex for table separator (only contains |, -, :, spaces)
-static TABLE_SEP_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[\s|:-]+$").unwrap());
+/// Table separator (only contains |, -, :, spaces)
+fn is_table_sep(inner: &str) -> bool {
+    !inner.is_empty()
+        && inner
+            .chars()
+            .all(|c| c.is_whitespace() || matches!(c, '|' | ':' | '-'))
+}
 
 // =============================================================================
 // Types
@@ -79,6 +235,7 @@ static TABLE_SEP_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[\s|:-]+$"
 
 /// List bullet type.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ListBullet {
     /// Dash bullet: -
     Dash,
@@ -128,6 +285,7 @@ pub enum TableState {
 
 /// Events emitted by the parser.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ParseEvent {
     // === Inline elements ===
     Text(String),
@@ -146,6 +304,8 @@ pub enum ParseEvent {
         url: String,
     },
     Footnote(String),
+    Spoiler(String),
+    Kbd(String),
 
     // === Block-level elements ===
     Heading {
@@ -156,6 +316,9 @@ pub enum ParseEvent {
         language: Option<String>,
         indent: usize,
     },
+    /// Language guessed for an unlabeled code block, sent before the line
+    /// it was guessed from
+    CodeBlockLanguage(String),
     CodeBlockLine(String),
     CodeBlockEnd,
     ListItem {
@@ -176,6 +339,11 @@ pub enum ParseEvent {
     ThinkBlockStart,
     ThinkBlockLine(String),
     ThinkBlockEnd,
+    DetailsStart {
+        summary: String,
+        open: bool,
+    },
+    DetailsEnd,
     HorizontalRule,
     EmptyLine,
     Newline,
@@ -183,6 +351,25 @@ pub enum ParseEvent {
     InlineElements(Vec<InlineElement>),
 }
 
+impl From<InlineElement> for ParseEvent {
+    fn from(element: InlineElement) -> Self {
+        match element {
+            InlineElement::Text(s) => ParseEvent::Text(s),
+            InlineElement::Bold(s) => ParseEvent::Bold(s),
+            InlineElement::Italic(s) => ParseEvent::Italic(s),
+            InlineElement::BoldItalic(s) => ParseEvent::BoldItalic(s),
+            InlineElement::Underline(s) => ParseEvent::Underline(s),
+            InlineElement::Strikeout(s) => ParseEvent::Strikeout(s),
+            InlineElement::Code(s) => ParseEvent::InlineCode(s),
+            InlineElement::Link { text, url } => ParseEvent::Link { text, url },
+            InlineElement::Image { alt, url } => ParseEvent::Image { alt, url },
+            InlineElement::Footnote(s) => ParseEvent::Footnote(s),
+            InlineElement::Spoiler(s) => ParseEvent::Spoiler(s),
+            InlineElement::Kbd(s) => ParseEvent::Kbd(s),
+        }
+    }
+}
+
 impl ParseEvent {
     pub fn is_block(&self) -> bool {
         !self.is_inline()
@@ -201,6 +388,8 @@ impl ParseEvent {
                 | ParseEvent::Link { .. }
                 | ParseEvent::Image { .. }
                 | ParseEvent::Footnote(_)
+                | ParseEvent::Spoiler(_)
+                | ParseEvent::Kbd(_)
         )
     }
 }
@@ -209,6 +398,41 @@ impl ParseEvent {
 // Parser
 // =============================================================================
 
+/// Parser settings, as set by the `set_*` methods.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct ParserOptions {
+    /// Parse links
+    pub links: bool,
+    /// Parse images
+    pub images: bool,
+    /// Parse `||spoiler||` syntax
+    pub spoilers: bool,
+    /// Read `[[Ctrl]]` as a keyboard key
+    pub kbd_shorthand: bool,
+    /// Guess the language of unlabeled code blocks
+    pub detect_languages: bool,
+    /// Space-indented code blocks
+    pub code_spaces: bool,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        Self {
+            links: true,
+            images: true,
+            spoilers: false,
+            kbd_shorthand: false,
+            detect_languages: false,
+            code_spaces: false,
+        }
+    }
+}
+
 /// Streaming markdown parser.
 #[derive(Debug)]
 pub struct Parser {
@@ -219,6 +443,14 @@ pub struct Parser {
     events: Vec<ParseEvent>,
     /// Track previous empty line for collapsing
     prev_was_empty: bool,
+    /// Number of open `<details>` blocks
+    details_depth: usize,
+    /// `<details>` seen, waiting for its `<summary>` (value: `open` attribute)
+    pending_details: Option<bool>,
+    /// Guess the language of unlabeled code blocks
+    detect_languages: bool,
+    /// Lines of the current unlabeled code block left to guess from
+    detect_remaining: usize,
 }
 
 impl Default for Parser {
@@ -237,6 +469,10 @@ impl Parser {
             table_state: None,
             events: Vec::new(),
             prev_was_empty: false,
+            details_depth: 0,
+            pending_details: None,
+            detect_languages: false,
+            detect_remaining: 0,
         }
     }
 
@@ -250,9 +486,42 @@ impl Parser {
             table_state: None,
             events: Vec::new(),
             prev_was_empty: false,
+            details_depth: 0,
+            pending_details: None,
+            detect_languages: false,
+            detect_remaining: 0,
         }
     }
 
+    /// Create a parser with `options`.
+    pub fn with_options(options: ParserOptions) -> Self {
+        let mut parser = Self::new();
+        parser.set_options(options);
+        parser
+    }
+
+    /// The current settings.
+    pub fn options(&self) -> ParserOptions {
+        ParserOptions {
+            links: self.state.links,
+            images: self.state.images,
+            spoilers: self.inline_parser.process_spoilers,
+            kbd_shorthand: self.inline_parser.process_kbd_shorthand,
+            detect_languages: self.detect_languages,
+            code_spaces: self.state.code_spaces,
+        }
+    }
+
+    /// Apply all settings at once.
+    pub fn set_options(&mut self, options: ParserOptions) {
+        self.set_process_links(options.links);
+        self.set_process_images(options.images);
+        self.set_process_spoilers(options.spoilers);
+        self.set_process_kbd_shorthand(options.kbd_shorthand);
+        self.set_detect_languages(options.detect_languages);
+        self.set_code_spaces(options.code_spaces);
+    }
+
     pub fn state(&self) -> &ParseState {
         &self.state
     }
@@ -270,6 +539,23 @@ impl Parser {
         self.inline_parser.process_images = enabled;
     }
 
+    /// Enable `||spoiler||` syntax (off by default).
+    pub fn set_process_spoilers(&mut self, enabled: bool) {
+        self.inline_parser.process_spoilers = enabled;
+    }
+
+    /// Read `[[Ctrl]]` as a keyboard key like `<kbd>Ctrl</kbd>` (off by
+    /// default).
+    pub fn set_process_kbd_shorthand(&mut self, enabled: bool) {
+        self.inline_parser.process_kbd_shorthand = enabled;
+    }
+
+    /// Guess the language of code blocks without an info string (off by
+    /// default); guesses arrive as [`ParseEvent::CodeBlockLanguage`].
+    pub fn set_detect_languages(&mut self, enabled: bool) {
+        self.detect_languages = enabled;
+    }
+
     /// Enable space-indented code blocks (4 spaces = code).
     pub fn set_code_spaces(&mut self, enabled: bool) {
         self.state.code_spaces = enabled;
@@ -282,20 +568,35 @@ impl Parser {
         // Handle code blocks first (they consume everything)
         if self.state.is_in_code() {
             self.parse_in_code_block(line);
-            return std::mem::take(&mut self.events);
+            return core::mem::take(&mut self.events);
         }
 
         // Handle think blocks
         if self.state.block_type == Some(BlockType::Think) {
             self.parse_in_think_block(line);
-            return std::mem::take(&mut self.events);
+            return core::mem::take(&mut self.events);
         }
 
         // Check for empty line (with collapsing) - BEFORE indent stripping
         if line.trim().is_empty() {
+            if self.pending_details.is_some() {
+                return vec![];
+            }
             return self.handle_empty_line();
         }
 
+        // A <details> without a <summary> line gets the default summary
+        if let Some(open) = self.pending_details.take() {
+            if let Some((summary, rest)) = summary(line) {
+                self.start_details(summary.trim(), open);
+                if !rest.trim().is_empty() {
+                    self.parse_inline_content(rest);
+                }
+                return self.take_events();
+            }
+            self.start_details(DEFAULT_DETAILS_SUMMARY, open);
+        }
+
         // Track that previous line wasn't empty
         let was_prev_empty = self.prev_was_empty;
         self.prev_was_empty = false;
@@ -314,6 +615,9 @@ impl Parser {
         if self.try_parse_code_fence(&line) {
             return self.take_events();
         }
+        if self.try_parse_details(&line) {
+            return self.take_events();
+        }
         if self.try_parse_block(&line) {
             return self.take_events();
         }
@@ -339,7 +643,7 @@ impl Parser {
     }
 
     fn take_events(&mut self) -> Vec<ParseEvent> {
-        std::mem::take(&mut self.events)
+        core::mem::take(&mut self.events)
     }
 
     /// Strip first-indent from line if configured.
@@ -419,9 +723,8 @@ impl Parser {
     fn parse_in_code_block(&mut self, line: &str) {
         // Check for closing fence
         if let Some(ref fence) = self.code_fence.clone()
-            && let Some(caps) = CODE_FENCE_END_RE.captures(line)
+            && let Some(end_fence) = code_fence_end(line)
         {
-            let end_fence = caps.get(1).map(|m| m.as_str()).unwrap_or("");
             // Match fence type: ``` with ```, </pre> with <pre>
             let matches = (fence.starts_with('`') && end_fence.starts_with('`'))
                 || (fence.starts_with('~') && end_fence.starts_with('~'))
@@ -431,6 +734,7 @@ impl Parser {
                 self.events.push(ParseEvent::CodeBlockEnd);
                 self.state.exit_code_block();
                 self.code_fence = None;
+                self.detect_remaining = 0;
                 return;
             }
         }
@@ -455,17 +759,32 @@ impl Parser {
             line.to_string()
         };
 
+        // Unlabeled block: try to guess its language from its first lines
+        if self.detect_remaining > 0 && !code_line.trim().is_empty() {
+            self.detect_remaining -= 1;
+            if let Some(language) = detect_language(&code_line) {
+                self.detect_remaining = 0;
+                self.state.code_language = Some(language.to_string());
+                self.events
+                    .push(ParseEvent::CodeBlockLanguage(language.to_string()));
+            }
+        }
+
         self.events.push(ParseEvent::CodeBlockLine(code_line));
     }
 
     fn try_parse_code_fence(&mut self, line: &str) -> bool {
-        if let Some(caps) = CODE_FENCE_RE.captures(line) {
-            let fence = caps.get(1).map(|m| m.as_str()).unwrap_or("```");
-            let lang = caps.get(2).map(|m| m.as_str()).filter(|s| !s.is_empty());
+        if let Some((fence, lang)) = code_fence(line) {
+            let lang = Some(lang).filter(|s| !s.is_empty());
             let indent = line.chars().take_while(|c| c.is_whitespace()).count();
 
             self.code_fence = Some(fence.to_string());
             self.state.code_indent = indent;
+            self.detect_remaining = if self.detect_languages && lang.is_none() {
+                DETECT_MAX_LINES
+            } else {
+                0
+            };
             self.state.enter_code_block(
                 Code::Backtick,
                 lang.map(|s| s.to_string())
@@ -493,7 +812,7 @@ impl Parser {
             return false;
         }
 
-        if SPACE_CODE_RE.is_match(line) {
+        if is_space_code(line) {
             self.state
                 .enter_code_block(Code::Spaces, Some("text".to_string()));
             self.events.push(ParseEvent::CodeBlockStart {
@@ -526,10 +845,7 @@ impl Parser {
     }
 
     fn try_parse_block(&mut self, line: &str) -> bool {
-        if let Some(caps) = BLOCK_RE.captures(line) {
-            let marker = caps.get(1).map(|m| m.as_str()).unwrap_or("");
-            let content = caps.get(3).map(|m| m.as_str()).unwrap_or("");
-
+        if let Some((marker, content)) = block_marker(line) {
             // Check for think block variants
             if marker.contains("think") {
                 if marker.contains('/') {
@@ -583,14 +899,53 @@ impl Parser {
         false
     }
 
+    // =========================================================================
+    // Details parsing
+    // =========================================================================
+
+    fn try_parse_details(&mut self, line: &str) -> bool {
+        if let Some((open, rest)) = details_start(line) {
+            self.exit_block_contexts();
+
+            if let Some((summary, after)) = summary(rest) {
+                self.start_details(summary.trim(), open);
+                if !after.trim().is_empty() {
+                    self.parse_inline_content(after);
+                }
+            } else if rest.trim().is_empty() {
+                // Summary may follow on the next line
+                self.pending_details = Some(open);
+            } else {
+                self.start_details(DEFAULT_DETAILS_SUMMARY, open);
+                self.parse_inline_content(rest);
+            }
+            return true;
+        }
+
+        if self.details_depth > 0 && is_details_end(line) {
+            self.exit_block_contexts();
+            self.details_depth -= 1;
+            self.events.push(ParseEvent::DetailsEnd);
+            return true;
+        }
+
+        false
+    }
+
+    fn start_details(&mut self, summary: &str, open: bool) {
+        self.details_depth += 1;
+        self.events.push(ParseEvent::DetailsStart {
+            summary: summary.to_string(),
+            open,
+        });
+    }
+
     // =========================================================================
     // Other block parsing
     // =========================================================================
 
     fn try_parse_heading(&mut self, line: &str) -> bool {
-        if let Some(caps) = HEADING_RE.captures(line) {
-            let hashes = caps.get(1).map(|m| m.as_str()).unwrap_or("");
-            let content = caps.get(2).map(|m| m.as_str()).unwrap_or("");
+        if let Some((hashes, content)) = heading(line) {
             let level = hashes.len().min(6) as u8;
 
             self.events.push(ParseEvent::Heading {
@@ -604,7 +959,7 @@ impl Parser {
     }
 
     fn try_parse_hr(&mut self, line: &str) -> bool {
-        if HR_RE.is_match(line.trim()) {
+        if is_hr(line) {
             self.events.push(ParseEvent::HorizontalRule);
             true
         } else {
@@ -613,11 +968,7 @@ impl Parser {
     }
 
     fn try_parse_list_item(&mut self, line: &str) -> bool {
-        if let Some(caps) = LIST_ITEM_RE.captures(line) {
-            let indent_str = caps.get(1).map(|m| m.as_str()).unwrap_or("");
-            let bullet_str = caps.get(2).map(|m| m.as_str()).unwrap_or("");
-            let content = caps.get(3).map(|m| m.as_str()).unwrap_or("");
-
+        if let Some((indent_str, bullet_str, content)) = list_item(line) {
             // Use character count, not byte length, for proper multi-byte whitespace handling
             let indent = indent_str.chars().count();
             let bullet = ListBullet::parse(bullet_str).unwrap_or(ListBullet::Dash);
@@ -678,11 +1029,9 @@ impl Parser {
     }
 
     fn try_parse_table(&mut self, line: &str) -> bool {
-        if let Some(caps) = TABLE_ROW_RE.captures(line) {
-            let inner = caps.get(1).map(|m| m.as_str()).unwrap_or("");
-
+        if let Some(inner) = table_row(line) {
             // Check if this is a separator row
-            if TABLE_SEP_RE.is_match(inner) && self.table_state == Some(TableState::Header) {
+            if is_table_sep(inner) && self.table_state == Some(TableState::Header) {
                 self.table_state = Some(TableState::Body);
                 self.state.in_table = Some(Code::Body);
                 self.events.push(ParseEvent::TableSeparator);
@@ -723,21 +1072,8 @@ impl Parser {
     fn parse_inline_content(&mut self, line: &str) {
         let elements = self.inline_parser.parse(line);
 
-        for element in elements {
-            let event = match element {
-                InlineElement::Text(s) => ParseEvent::Text(s),
-                InlineElement::Bold(s) => ParseEvent::Bold(s),
-                InlineElement::Italic(s) => ParseEvent::Italic(s),
-                InlineElement::BoldItalic(s) => ParseEvent::BoldItalic(s),
-                InlineElement::Underline(s) => ParseEvent::Underline(s),
-                InlineElement::Strikeout(s) => ParseEvent::Strikeout(s),
-                InlineElement::Code(s) => ParseEvent::InlineCode(s),
-                InlineElement::Link { text, url } => ParseEvent::Link { text, url },
-                InlineElement::Image { alt, url } => ParseEvent::Image { alt, url },
-                InlineElement::Footnote(s) => ParseEvent::Footnote(s),
-            };
-            self.events.push(event);
-        }
+        self.events
+            .extend(elements.into_iter().map(ParseEvent::from));
 
         self.events.push(ParseEvent::Newline);
     }
@@ -784,6 +1120,14 @@ impl Parser {
             self.events.push(ParseEvent::TableEnd);
         }
 
+        if let Some(open) = self.pending_details.take() {
+            self.start_details(DEFAULT_DETAILS_SUMMARY, open);
+        }
+        while self.details_depth > 0 {
+            self.details_depth -= 1;
+            self.events.push(ParseEvent::DetailsEnd);
+        }
+
         self.take_events()
     }
 
@@ -795,6 +1139,8 @@ impl Parser {
         self.table_state = None;
         self.events.clear();
         self.prev_was_empty = false;
+        self.details_depth = 0;
+        self.pending_details = None;
     }
 }
 
@@ -806,6 +1152,48 @@ impl Parser {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_options_roundtrip() {
+        assert_eq!(Parser::new().options(), ParserOptions::default());
+        let options = ParserOptions {
+            links: false,
+            kbd_shorthand: true,
+            code_spaces: true,
+            ..ParserOptions::default()
+        };
+        let mut parser = Parser::with_options(options);
+        assert_eq!(parser.options(), options);
+        assert_eq!(
+            parser.parse_line("[[Ctrl]]"),
+            vec![ParseEvent::Kbd("Ctrl".to_string()), ParseEvent::Newline]
+        );
+    }
+
+    #[test]
+    fn test_detect_language_of_unlabeled_fence() {
+        let mut parser = Parser::new();
+        parser.set_detect_languages(true);
+        parser.parse_line("```");
+        assert_eq!(
+            parser.parse_line(""),
+            vec![ParseEvent::CodeBlockLine(String::new())]
+        );
+        assert_eq!(
+            parser.parse_line("def f():"),
+            vec![
+                ParseEvent::CodeBlockLanguage("python".to_string()),
+                ParseEvent::CodeBlockLine("def f():".to_string()),
+            ]
+        );
+        // One guess per block
+        assert_eq!(parser.parse_line("fn x() {}").len(), 1);
+        parser.parse_line("```");
+
+        // Labeled blocks are left alone
+        parser.parse_line("```text");
+        assert_eq!(parser.parse_line("fn main() {}").len(), 1);
+    }
+
     #[test]
     fn test_parse_heading() {
         let mut parser = Parser::new();
@@ -989,6 +1377,64 @@ mod tests {
         assert!(e3.iter().any(|e| matches!(e, ParseEvent::ThinkBlockEnd)));
     }
 
+    #[test]
+    fn test_parse_details() {
+        let mut parser = Parser::new();
+        assert!(parser.parse_line("<details>").is_empty());
+        let e1 = parser.parse_line("<summary>Stack trace</summary>");
+        assert!(e1.iter().any(|e| matches!(
+            e,
+            ParseEvent::DetailsStart { summary, open: false } if summary == "Stack trace"
+        )));
+        let e2 = parser.parse_line("line 1");
+        assert!(
+            e2.iter()
+                .any(|e| matches!(e, ParseEvent::Text(s) if s == "line 1"))
+        );
+        let e3 = parser.parse_line("</details>");
+        assert!(e3.iter().any(|e| matches!(e, ParseEvent::DetailsEnd)));
+    }
+
+    #[test]
+    fn test_parse_details_inline_summary_and_default() {
+        let mut parser = Parser::new();
+        let events = parser.parse_line("<details open><summary>More</summary>");
+        assert!(events.iter().any(|e| matches!(
+            e,
+            ParseEvent::DetailsStart { summary, open: true } if summary == "More"
+        )));
+
+        let mut parser = Parser::new();
+        parser.parse_line("<details>");
+        let events = parser.parse_line("no summary here");
+        assert!(events.iter().any(|e| matches!(
+            e,
+            ParseEvent::DetailsStart { summary, .. } if summary == DEFAULT_DETAILS_SUMMARY
+        )));
+        let events = parser.finalize();
+        assert!(events.iter().any(|e| matches!(e, ParseEvent::DetailsEnd)));
+    }
+
+    #[test]
+    fn test_parse_spoiler_opt_in() {
+        let mut parser = Parser::new();
+        let events = parser.parse_line("the ||butler|| did it");
+        assert!(!events.iter().any(|e| matches!(e, ParseEvent::Spoiler(_))));
+
+        parser.set_process_spoilers(true);
+        let events = parser.parse_line("the ||butler|| did it, `a || b`");
+        let spoilers: Vec<_> = events
+            .iter()
+            .filter(|e| matches!(e, ParseEvent::Spoiler(_)))
+            .collect();
+        assert_eq!(spoilers, vec![&ParseEvent::Spoiler("butler".to_string())]);
+        assert!(
+            events
+                .iter()
+                .any(|e| matches!(e, ParseEvent::InlineCode(s) if s == "a || b"))
+        );
+    }
+
     #[test]
     fn test_first_indent_stripping() {
         let mut parser = Parser::new();
@@ -1186,4 +1632,42 @@ mod tests {
             "Should have exited code block with only 2-char indent"
         );
     }
+
+    #[test]
+    fn test_line_patterns() {
+        assert_eq!(code_fence("  ```rust  "), Some(("```", "rust")));
+        assert_eq!(code_fence("~~~~"), Some(("~~~~", "")));
+        assert_eq!(code_fence("```rust extra"), None);
+        assert_eq!(code_fence("``"), None);
+        assert_eq!(code_fence_end(" </pre> "), Some("</pre>"));
+
+        assert_eq!(heading("###### six"), Some(("######", "six")));
+        assert_eq!(heading("####### seven"), None);
+        assert_eq!(heading("#tag"), None);
+
+        assert_eq!(list_item("  - item"), Some(("  ", "-", "item")));
+        assert_eq!(list_item("+-- item"), Some(("", "+--", "item")));
+        assert_eq!(list_item("12. item"), Some(("", "12.", "item")));
+        assert_eq!(list_item("-item"), None);
+
+        assert_eq!(block_marker("> > quote"), Some(("> > ", "quote")));
+        assert_eq!(block_marker("<think>rest"), Some(("<think>", "rest")));
+        assert_eq!(block_marker("◁think▷"), Some(("◁think▷", "")));
+        assert_eq!(block_marker("</think"), Some(("</think", "")));
+        assert_eq!(block_marker("<div>"), None);
+
+        assert_eq!(details_start("<DETAILS open> body"), Some((true, "body")));
+        assert_eq!(details_start("<details>"), Some((false, "")));
+        assert_eq!(details_start("<detailsopen>"), None);
+        assert_eq!(
+            summary("<summary>Sum</SUMMARY> after"),
+            Some(("Sum", "after"))
+        );
+
+        assert!(is_hr(" *** "));
+        assert!(!is_hr("-*-"));
+        assert_eq!(table_row(" | a | b | "), Some(" a | b "));
+        assert_eq!(table_row("||"), None);
+        assert!(is_table_sep(" :-- | --: "));
+    }
 }
@@ -3,36 +3,95 @@
 //! This module provides tokenization of markdown inline content,
 //! breaking text into tokens for formatting markers, text, and special elements.
 
-use regex::Regex;
-use std::sync::LazyLock;
-
-/// Regex for tokenizing inline markdown content.
-/// Matches formatting markers (**, *, _, ~~, `) and regular text.
-static INLINE_TOKEN_RE: LazyLock<Regex> = LazyLock::new(|| {
-    // Match formatting markers OR runs of non-marker text
-    // Order matters: longer markers first
-    Regex::new(r"(```+|~~|\*\*\*|\*\*_|_\*\*|\*\*|\*|___|__|_|`+|[^~_*`]+)").unwrap()
-});
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+/// Formatting markers, longest first where one is a prefix of another.
+/// Backtick runs of any length are markers too.
+const MARKERS: &[&str] = &["~~", "***", "**_", "_**", "**", "*", "___", "__", "_"];
+
+/// Byte length of the inline token at the start of `text`: a backtick run,
+/// a formatting marker or a run of text without marker characters. `None`
+/// for a lone `~`, which is dropped.
+fn inline_token_len(text: &str) -> Option<usize> {
+    if text.starts_with('`') {
+        return Some(text.len() - text.trim_start_matches('`').len());
+    }
+    if let Some(marker) = MARKERS.iter().find(|marker| text.starts_with(**marker)) {
+        return Some(marker.len());
+    }
+    match text.find(['~', '_', '*', '`']) {
+        Some(0) => None,
+        Some(end) => Some(end),
+        None => Some(text.len()),
+    }
+}
 
-/// Regex for matching links: [text](url)
-static LINK_RE: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"\[([^\]]+)\]\(([^\)]+)\)").unwrap());
+/// Every non-overlapping match of `at` in `line`, leftmost first, as byte
+/// ranges with the matched value. `at` returns the match length and value
+/// for a match starting exactly at the start of its argument.
+fn find_all<'a, T>(
+    line: &'a str,
+    at: impl Fn(&'a str) -> Option<(usize, T)>,
+) -> Vec<(usize, usize, T)> {
+    let mut found = Vec::new();
+    let mut start = 0;
+    while start < line.len() {
+        if !line.is_char_boundary(start) {
+            start += 1;
+            continue;
+        }
+        match at(&line[start..]) {
+            Some((len, value)) => {
+                found.push((start, start + len, value));
+                start += len;
+            }
+            None => start += 1,
+        }
+    }
+    found
+}
 
-/// Regex for matching images: ![alt](url)
-static IMAGE_RE: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"!\[([^\]]*)\]\(([^\)]+)\)").unwrap());
+/// `[label](url)` at the start of `text`: the label runs to the first `]`
+/// and the url to the first `)`. The url is never empty, the label only
+/// when `empty_label` allows it.
+fn bracketed_link(text: &str, empty_label: bool) -> Option<(usize, (&str, &str))> {
+    let rest = text.strip_prefix('[')?;
+    let close = rest.find(']')?;
+    let target = rest[close + 1..].strip_prefix('(')?;
+    let end = target.find(')')?;
+    if (close == 0 && !empty_label) || end == 0 {
+        return None;
+    }
+    Some((close + end + 4, (&rest[..close], &target[..end])))
+}
 
-/// Regex for matching footnotes: `[^1]` or `[^1]:`
-static FOOTNOTE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\[\^(\d+)\]:?").unwrap());
+/// A footnote reference, `[^1]` or `[^1]:`, at the start of `text`.
+fn footnote(text: &str) -> Option<(usize, &str)> {
+    let rest = text.strip_prefix("[^")?;
+    let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if digits == 0 || !rest[digits..].starts_with(']') {
+        return None;
+    }
+    let colon = usize::from(rest[digits + 1..].starts_with(':'));
+    Some((digits + 3 + colon, &rest[..digits]))
+}
 
-/// Regex for matching inline code spans: `code` or ``code``
-static CODE_SPAN_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"``[^`]+``|`[^`]+`").unwrap());
+/// An inline code span, `` `code` `` or ``` ``code`` ```, at the start of
+/// `text`.
+fn code_span(text: &str) -> Option<(usize, ())> {
+    ["``", "`"].into_iter().find_map(|fence| {
+        let body = text.strip_prefix(fence)?;
+        let len = body.find('`').unwrap_or(body.len());
+        (len > 0 && body[len..].starts_with(fence)).then_some((len + 2 * fence.len(), ()))
+    })
+}
 
 /// Find byte ranges of inline code spans in a line.
 fn find_code_regions(line: &str) -> Vec<(usize, usize)> {
-    CODE_SPAN_RE
-        .find_iter(line)
-        .map(|m| (m.start(), m.end()))
+    find_all(line, code_span)
+        .into_iter()
+        .map(|(start, end, ())| (start, end))
         .collect()
 }
 
@@ -72,7 +131,7 @@ pub enum Token {
     /// Backticks (variable count)
     Backticks(usize),
 
-    /// A link: [text](url)
+    /// A link: `[text](url)`
     Link { text: String, url: String },
 
     /// An image: ![alt](url)
@@ -149,8 +208,14 @@ impl Tokenizer {
 
     /// Tokenize inline content for formatting markers.
     pub fn tokenize_inline(&self, text: &str, tokens: &mut Vec<Token>) {
-        for cap in INLINE_TOKEN_RE.find_iter(text) {
-            let s = cap.as_str();
+        let mut start = 0;
+        while start < text.len() {
+            let Some(len) = inline_token_len(&text[start..]) else {
+                start += 1;
+                continue;
+            };
+            let s = &text[start..start + len];
+            start += len;
             let token = match s {
                 "***" => Token::TripleAsterisk,
                 "**" => Token::DoubleAsterisk,
@@ -178,13 +243,13 @@ impl Tokenizer {
 
         // Find all images
         if self.process_images {
-            for cap in IMAGE_RE.captures_iter(line) {
-                let m = cap.get(0).unwrap();
-                let alt = cap.get(1).map(|m| m.as_str()).unwrap_or("");
-                let url = cap.get(2).map(|m| m.as_str()).unwrap_or("");
+            let images = find_all(line, |text| {
+                bracketed_link(text.strip_prefix('!')?, true).map(|(len, link)| (len + 1, link))
+            });
+            for (start, end, (alt, url)) in images {
                 extractions.push((
-                    m.start(),
-                    m.end(),
+                    start,
+                    end,
                     Token::Image {
                         alt: alt.to_string(),
                         url: url.to_string(),
@@ -195,17 +260,14 @@ impl Tokenizer {
 
         // Find all links (that aren't part of images)
         if self.process_links {
-            for cap in LINK_RE.captures_iter(line) {
-                let m = cap.get(0).unwrap();
+            for (start, end, (text, url)) in find_all(line, |text| bracketed_link(text, false)) {
                 // Check if this is part of an image (preceded by !)
-                if m.start() > 0 && line.as_bytes().get(m.start() - 1) == Some(&b'!') {
+                if start > 0 && line.as_bytes().get(start - 1) == Some(&b'!') {
                     continue;
                 }
-                let text = cap.get(1).map(|m| m.as_str()).unwrap_or("");
-                let url = cap.get(2).map(|m| m.as_str()).unwrap_or("");
                 extractions.push((
-                    m.start(),
-                    m.end(),
+                    start,
+                    end,
                     Token::Link {
                         text: text.to_string(),
                         url: url.to_string(),
@@ -215,12 +277,9 @@ impl Tokenizer {
         }
 
         // Find all footnotes
-        for cap in FOOTNOTE_RE.captures_iter(line) {
-            let m = cap.get(0).unwrap();
-            if let Some(num_match) = cap.get(1)
-                && let Ok(num) = num_match.as_str().parse::<u32>()
-            {
-                extractions.push((m.start(), m.end(), Token::Footnote(num)));
+        for (start, end, digits) in find_all(line, footnote) {
+            if let Ok(num) = digits.parse::<u32>() {
+                extractions.push((start, end, Token::Footnote(num)));
             }
         }
 
@@ -258,24 +317,6 @@ impl Tokenizer {
             self.tokenize_inline(&line[last_end..], tokens);
         }
     }
-
-    // Note: These extraction methods are kept for potential future use
-    // when we need to process links/images/footnotes separately.
-
-    #[allow(dead_code)]
-    fn extract_images(&self, text: &str) -> String {
-        IMAGE_RE.replace_all(text, "").to_string()
-    }
-
-    #[allow(dead_code)]
-    fn extract_links(&self, text: &str) -> String {
-        LINK_RE.replace_all(text, "").to_string()
-    }
-
-    #[allow(dead_code)]
-    fn extract_footnotes(&self, text: &str) -> String {
-        FOOTNOTE_RE.replace_all(text, "").to_string()
-    }
 }
 
 /// Check if a character is CJK (Chinese, Japanese, Korean).
@@ -485,4 +526,22 @@ mod tests {
         // Link inside backticks should NOT be extracted
         assert!(!tokens.iter().any(|t| matches!(t, Token::Link { .. })));
     }
+
+    #[test]
+    fn test_tokenize_lone_tilde_and_empty_alt() {
+        let tokenizer = Tokenizer::new();
+        let mut tokens = Vec::new();
+        tokenizer.tokenize_inline("a~b", &mut tokens);
+        assert_eq!(
+            tokens,
+            vec![Token::Text("a".to_string()), Token::Text("b".to_string())]
+        );
+
+        let tokens = tokenizer.tokenize("![](x.png) [^12]:");
+        assert!(tokens.contains(&Token::Image {
+            alt: String::new(),
+            url: "x.png".to_string()
+        }));
+        assert!(tokens.contains(&Token::Footnote(12)));
+    }
 }
@@ -9,32 +9,177 @@
 //! - Relations: `\leq` → ≤, `\geq` → ≥, `\neq` → ≠
 //! - Subscripts: `x_1` → x₁, `x_{10}` → x₁₀
 //! - Superscripts: `x^2` → x², `x^{10}` → x¹⁰
-//! - Fractions: `\frac{a}{b}` → a/b
+//! - Fractions: `\frac{a}{b}` → a/b, `\frac{1}{2}` → 1⁄2
 //! - Common symbols: `\infty` → ∞, `\pm` → ±, etc.
+//!
+//! # Math backends
+//!
+//! Conversion goes through the [`MathRenderer`] trait. [`UnicodeMath`] (the
+//! built-in approximation above) is the default; [`CommandMath`] pipes
+//! expressions through an external converter such as `utftex`, and custom
+//! backends can be plugged in with [`LatexPlugin::with_renderer`].
+//!
+//! [`UnicodeMath`] only converts expressions it can spell out completely
+//! (see [`try_latex_to_unicode`]); anything else, such as `x^{\alpha}` or
+//! `\sqrt{2}`, is shown as written, `$` delimiters included, rather than
+//! half-converted.
 
 use crate::{Plugin, ProcessResult};
 use regex::Regex;
 use std::collections::HashMap;
-use std::sync::LazyLock;
+use std::io::Read;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::{LazyLock, mpsc};
+use std::time::Duration;
 use streamdown_config::ComputedStyle;
 use streamdown_core::state::ParseState;
 
+/// Backend converting LaTeX math to terminal text.
+pub trait MathRenderer: Send + Sync {
+    /// Render an inline expression (between `$` delimiters) as one line.
+    fn render_inline(&self, latex: &str) -> String;
+
+    /// Render a display expression (between `$$` delimiters).
+    ///
+    /// May return several lines for two-dimensional layouts. Defaults to
+    /// the inline rendering.
+    fn render_display(&self, latex: &str) -> Vec<String> {
+        vec![self.render_inline(latex)]
+    }
+}
+
+/// Built-in Unicode approximation (see [`try_latex_to_unicode`]), keeping
+/// expressions it can't convert verbatim.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UnicodeMath;
+
+impl MathRenderer for UnicodeMath {
+    fn render_inline(&self, latex: &str) -> String {
+        try_latex_to_unicode(latex).unwrap_or_else(|| format!("${}$", latex))
+    }
+
+    fn render_display(&self, latex: &str) -> Vec<String> {
+        vec![try_latex_to_unicode(latex).unwrap_or_else(|| latex.trim().to_string())]
+    }
+}
+
+/// How long [`CommandMath`] waits for its converter by default.
+pub const COMMAND_MATH_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Backend that pipes expressions through an external command.
+///
+/// The expression is written to the command's stdin and its stdout is used
+/// as the rendering. If the command fails, prints nothing or doesn't finish
+/// within the timeout (it is killed then), the built-in Unicode
+/// approximation is used instead.
+#[derive(Debug, Clone)]
+pub struct CommandMath {
+    /// Program to run
+    program: String,
+    /// Arguments passed before the expression is piped in
+    args: Vec<String>,
+    /// Longest wait for the command's output
+    timeout: Duration,
+}
+
+impl CommandMath {
+    /// Create a backend running `program` with `args`.
+    pub fn new(program: impl Into<String>, args: Vec<String>) -> Self {
+        Self {
+            program: program.into(),
+            args,
+            timeout: COMMAND_MATH_TIMEOUT,
+        }
+    }
+
+    /// Set how long to wait for the command (default
+    /// [`COMMAND_MATH_TIMEOUT`]).
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Run the command on an expression, returning its output lines.
+    fn run(&self, latex: &str) -> Option<Vec<String>> {
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+        child.stdin.take()?.write_all(latex.as_bytes()).ok()?;
+
+        // Read on a thread so a hung converter can be given up on
+        let mut stdout = child.stdout.take()?;
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut output = Vec::new();
+            let _ = tx.send(stdout.read_to_end(&mut output).map(|_| output));
+        });
+        let output = match rx.recv_timeout(self.timeout) {
+            Ok(output) => output.ok(),
+            Err(_) => {
+                let _ = child.kill();
+                None
+            }
+        };
+        let status = child.wait().ok()?;
+        let output = output?;
+        if !status.success() {
+            return None;
+        }
+
+        let text = String::from_utf8_lossy(&output);
+        let lines: Vec<String> = text.trim_end().lines().map(str::to_string).collect();
+        (!lines.is_empty()).then_some(lines)
+    }
+}
+
+impl MathRenderer for CommandMath {
+    fn render_inline(&self, latex: &str) -> String {
+        match self.run(latex) {
+            Some(lines) => lines.join(" "),
+            None => UnicodeMath.render_inline(latex),
+        }
+    }
+
+    fn render_display(&self, latex: &str) -> Vec<String> {
+        self.run(latex)
+            .unwrap_or_else(|| UnicodeMath.render_display(latex))
+    }
+}
+
 /// LaTeX plugin for converting math to Unicode.
 pub struct LatexPlugin {
     /// Whether we're inside a $$ block
     in_block: bool,
     /// Buffer for multi-line expressions
     buffer: String,
+    /// Math backend
+    renderer: Box<dyn MathRenderer>,
 }
 
 impl LatexPlugin {
-    /// Create a new LaTeX plugin.
+    /// Create a new LaTeX plugin using the Unicode approximation.
     pub fn new() -> Self {
+        Self::with_renderer(Box::new(UnicodeMath))
+    }
+
+    /// Create a LaTeX plugin using a custom math backend.
+    pub fn with_renderer(renderer: Box<dyn MathRenderer>) -> Self {
         Self {
             in_block: false,
             buffer: String::new(),
+            renderer,
         }
     }
+
+    /// Replace the math backend.
+    pub fn set_renderer(&mut self, renderer: Box<dyn MathRenderer>) {
+        self.renderer = renderer;
+    }
 }
 
 impl Default for LatexPlugin {
@@ -57,7 +202,7 @@ impl Plugin for LatexPlugin {
         // Handle inline $...$ first (single line)
         if !self.in_block && line.contains('$') && !line.contains("$$") {
             // Check for inline math
-            let converted = convert_inline_math(line);
+            let converted = convert_inline_math_with(line, self.renderer.as_ref());
             if converted != line {
                 return Some(ProcessResult::Lines(vec![converted]));
             }
@@ -77,8 +222,7 @@ impl Plugin for LatexPlugin {
                     // Single line expression
                     self.in_block = false;
                     let expr = &after[..end_idx];
-                    let converted = latex_to_unicode(expr);
-                    return Some(ProcessResult::Lines(vec![converted]));
+                    return Some(ProcessResult::Lines(self.renderer.render_display(expr)));
                 }
 
                 // Multi-line: start buffering
@@ -94,10 +238,10 @@ impl Plugin for LatexPlugin {
             self.in_block = false;
             self.buffer.push_str(&line[..idx]);
 
-            let converted = latex_to_unicode(&self.buffer);
+            let converted = self.renderer.render_display(&self.buffer);
             self.buffer.clear();
 
-            return Some(ProcessResult::Lines(vec![converted]));
+            return Some(ProcessResult::Lines(converted));
         }
 
         // Continue buffering
@@ -134,12 +278,61 @@ impl Plugin for LatexPlugin {
 }
 
 /// Convert inline math ($...$) in a line.
-fn convert_inline_math(line: &str) -> String {
-    static INLINE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\$([^$]+)\$").unwrap());
+pub fn convert_inline_math(line: &str) -> String {
+    convert_inline_math_with(line, &UnicodeMath)
+}
 
-    INLINE_RE
-        .replace_all(line, |caps: &regex::Captures| latex_to_unicode(&caps[1]))
-        .to_string()
+/// Convert inline math ($...$) in a line with a given backend.
+fn convert_inline_math_with(line: &str, renderer: &dyn MathRenderer) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut from = 0;
+    for span in inline_math_spans(line) {
+        out.push_str(&line[from..span.start - 1]);
+        out.push_str(&renderer.render_inline(&line[span.clone()]));
+        from = span.end + 1;
+    }
+    out.push_str(&line[from..]);
+    out
+}
+
+/// Byte ranges of the expressions between `$` delimiters in `line`.
+///
+/// As in pandoc, the opening `$` must be followed by a non-space and the
+/// closing one preceded by a non-space and not followed by a digit, so
+/// prices like `$5 and $10` stay text. Code spans are skipped.
+fn inline_math_spans(line: &str) -> Vec<std::ops::Range<usize>> {
+    let bytes = line.as_bytes();
+    let mut spans = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'`' => {
+                let run = bytes[i..].iter().take_while(|&&b| b == b'`').count();
+                let fence = &line[i..i + run];
+                i += run;
+                if let Some(len) = line[i..].find(fence) {
+                    i += len + run;
+                }
+            }
+            b'$' if bytes.get(i + 1).is_some_and(|b| !b.is_ascii_whitespace()) => {
+                let start = i + 1;
+                let close = (start + 1..bytes.len()).find(|&j| {
+                    bytes[j] == b'$'
+                        && !bytes[j - 1].is_ascii_whitespace()
+                        && !bytes.get(j + 1).is_some_and(u8::is_ascii_digit)
+                });
+                match close {
+                    Some(end) if !line[start..end].contains('$') => {
+                        spans.push(start..end);
+                        i = end + 1;
+                    }
+                    _ => i += 1,
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    spans
 }
 
 /// Convert LaTeX expression to Unicode.
@@ -156,6 +349,34 @@ pub fn latex_to_unicode(latex: &str) -> String {
     result
 }
 
+/// Convert a LaTeX expression to Unicode, or None if part of it has no
+/// Unicode spelling: an unknown command, a sub- or superscript character
+/// without a script form, or a nested group.
+///
+/// Fractions of plain numbers or letters use the fraction slash
+/// (`\frac{1}{2}` → 1⁄2).
+pub fn try_latex_to_unicode(latex: &str) -> Option<String> {
+    static SIMPLE_FRAC_RE: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"\\frac\{([0-9A-Za-z]+)\}\{([0-9A-Za-z]+)\}").unwrap());
+    static SCRIPT_RE: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"([_^])(?:\{([^}]*)\}|(.))").unwrap());
+
+    let latex = SIMPLE_FRAC_RE.replace_all(latex, "$1\u{2044}$2");
+    for caps in SCRIPT_RE.captures_iter(&latex) {
+        let scripts = if &caps[1] == "_" {
+            &*SUBSCRIPT_DIGITS
+        } else {
+            &*SUPERSCRIPT_CHARS
+        };
+        let content = caps.get(2).or(caps.get(3)).map_or("", |m| m.as_str());
+        if content.is_empty() || !content.chars().all(|c| scripts.contains_key(&c)) {
+            return None;
+        }
+    }
+    let result = latex_to_unicode(&latex);
+    (!result.contains(['\\', '{', '}', '^', '_'])).then_some(result)
+}
+
 /// Greek letters and symbols mapping.
 static GREEK_LETTERS: LazyLock<HashMap<&'static str, &'static str>> = LazyLock::new(|| {
     let mut m = HashMap::new();
@@ -425,16 +646,23 @@ fn convert_commands(input: &str) -> String {
         .to_string()
 }
 
-/// Convert fractions \frac{a}{b} → a/b.
+/// Convert fractions \frac{a}{b} → (a/b); a numerator or denominator
+/// that is more than a number or a name is grouped, \frac{a+b}{c} →
+/// ((a+b)/c).
 fn convert_fractions(input: &str) -> String {
     static FRAC_RE: LazyLock<Regex> =
         LazyLock::new(|| Regex::new(r"\\frac\{([^}]*)\}\{([^}]*)\}").unwrap());
 
+    let group = |part: &str| {
+        if part.chars().all(char::is_alphanumeric) {
+            part.to_string()
+        } else {
+            format!("({})", part)
+        }
+    };
     FRAC_RE
         .replace_all(input, |caps: &regex::Captures| {
-            let num = &caps[1];
-            let den = &caps[2];
-            format!("({}/{})", num, den)
+            format!("({}/{})", group(&caps[1]), group(&caps[2]))
         })
         .to_string()
 }
@@ -569,6 +797,26 @@ mod tests {
     fn test_fractions() {
         assert_eq!(latex_to_unicode(r"\frac{a}{b}"), "(a/b)");
         assert_eq!(latex_to_unicode(r"\frac{1}{2}"), "(1/2)");
+        assert_eq!(latex_to_unicode(r"\frac{a+b}{c}"), "((a+b)/c)");
+        assert_eq!(latex_to_unicode(r"\frac{1}{2n-1}"), "(1/(2n-1))");
+    }
+
+    #[test]
+    fn test_try_latex_to_unicode() {
+        assert_eq!(
+            try_latex_to_unicode(r"x^2 + \alpha").as_deref(),
+            Some("x² + α")
+        );
+        assert_eq!(
+            try_latex_to_unicode(r"\frac{1}{2} + \frac{a+b}{c}").as_deref(),
+            Some("1⁄2 + ((a+b)/c)")
+        );
+        assert_eq!(try_latex_to_unicode("x_{i+1}").as_deref(), Some("xᵢ₊₁"));
+        // No script form, unknown command, nested group
+        assert_eq!(try_latex_to_unicode("x_{b}"), None);
+        assert_eq!(try_latex_to_unicode(r"\sqrt{2}"), None);
+        assert_eq!(try_latex_to_unicode(r"x^{\alpha}"), None);
+        assert_eq!(try_latex_to_unicode("x^Q"), None);
     }
 
     #[test]
@@ -590,6 +838,15 @@ mod tests {
             convert_inline_math("We have $\\alpha$ and $\\beta$"),
             "We have α and β"
         );
+        // Unconvertible expressions are kept as written
+        assert_eq!(
+            convert_inline_math(r"root $\sqrt{x}$ and $y_{b}$"),
+            r"root $\sqrt{x}$ and $y_{b}$"
+        );
+        // Prices, spaced dollars and code spans aren't math
+        assert_eq!(convert_inline_math("$5 and $10"), "$5 and $10");
+        assert_eq!(convert_inline_math("a $ b $ c"), "a $ b $ c");
+        assert_eq!(convert_inline_math("run `echo $x^2$`"), "run `echo $x^2$`");
     }
 
     #[test]
@@ -673,4 +930,53 @@ mod tests {
         plugin.reset();
         assert!(!plugin.is_active());
     }
+
+    /// Backend that boxes expressions, for checking the plugin wiring
+    struct BracketMath;
+
+    impl MathRenderer for BracketMath {
+        fn render_inline(&self, latex: &str) -> String {
+            format!("[{}]", latex)
+        }
+
+        fn render_display(&self, latex: &str) -> Vec<String> {
+            vec!["┌".to_string(), format!("│{}", latex), "└".to_string()]
+        }
+    }
+
+    #[test]
+    fn test_custom_math_renderer() {
+        let mut plugin = LatexPlugin::with_renderer(Box::new(BracketMath));
+        let state = ParseState::new();
+        let style = ComputedStyle::default();
+
+        let result = plugin.process_line("say $x^2$ now", &state, &style);
+        assert_eq!(result, Some(ProcessResult::line("say [x^2] now")));
+
+        let result = plugin.process_line("$$a+b$$", &state, &style);
+        assert!(matches!(result, Some(ProcessResult::Lines(ref l)) if l.len() == 3));
+    }
+
+    #[test]
+    fn test_command_math_falls_back() {
+        let math = CommandMath::new("streamdown-no-such-converter", vec![]);
+        assert_eq!(math.render_inline("\\alpha"), "α");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_command_math_times_out() {
+        let math = CommandMath::new("sleep", vec!["10".to_string()])
+            .with_timeout(Duration::from_millis(100));
+        let start = std::time::Instant::now();
+        assert_eq!(math.render_inline("\\alpha"), "α");
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_command_math_uses_output() {
+        let math = CommandMath::new("tr", vec!["a-z".to_string(), "A-Z".to_string()]);
+        assert_eq!(math.render_display("x + y"), vec!["X + Y".to_string()]);
+    }
 }
@@ -0,0 +1,142 @@
+//! Render hot path benchmarks.
+//!
+//! Streams code block and think block lines through the renderer. Run once
+//! with and once without the `arena` feature to compare:
+//!
+//! ```sh
+//! cargo bench -p streamdown-render --bench hot_path
+//! cargo bench -p streamdown-render --bench hot_path --features arena
+//! ```
+//!
+//! Besides timings, the allocation count per rendered line is printed, which
+//! is where the arena shows up.
+//!
+//! The `split_text` group compares the memchr-based splitter with the
+//! previous char-by-char loop.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use streamdown_parser::ParseEvent;
+use streamdown_render::{Renderer, split_text};
+
+/// Global allocator that counts allocations.
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const LINES: usize = 1000;
+
+fn code_events() -> Vec<ParseEvent> {
+    let mut events = vec![ParseEvent::CodeBlockStart {
+        language: None,
+        indent: 0,
+    }];
+    events.extend(
+        (0..LINES).map(|i| ParseEvent::CodeBlockLine(format!("let value_{} = {} * 2;", i, i))),
+    );
+    events.push(ParseEvent::CodeBlockEnd);
+    events
+}
+
+fn think_events() -> Vec<ParseEvent> {
+    let mut events = vec![ParseEvent::ThinkBlockStart];
+    events
+        .extend((0..LINES).map(|i| ParseEvent::ThinkBlockLine(format!("step {} of the plan", i))));
+    events.push(ParseEvent::ThinkBlockEnd);
+    events
+}
+
+fn render_all(events: &[ParseEvent]) {
+    let mut renderer = Renderer::new(io::sink(), 80);
+    renderer.set_highlight(false);
+    renderer.render(black_box(events)).unwrap();
+}
+
+fn report_allocations(name: &str, events: &[ParseEvent]) {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    render_all(events);
+    let count = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    println!(
+        "{}: {:.2} allocations/line",
+        name,
+        count as f64 / LINES as f64
+    );
+}
+
+fn bench_hot_path(c: &mut Criterion) {
+    let code = code_events();
+    let think = think_events();
+
+    report_allocations("code_block_lines", &code);
+    report_allocations("think_block_lines", &think);
+
+    c.bench_function("code_block_lines", |b| b.iter(|| render_all(&code)));
+    c.bench_function("think_block_lines", |b| b.iter(|| render_all(&think)));
+}
+
+/// The char-by-char splitter `split_text` used before memchr scanning.
+fn split_text_per_char(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_escape = false;
+
+    for ch in text.chars() {
+        if in_escape {
+            current.push(ch);
+            in_escape = ch != 'm';
+            continue;
+        }
+        if ch == '\x1b' {
+            in_escape = true;
+            current.push(ch);
+        } else if ch.is_whitespace() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+        } else {
+            current.push(ch);
+        }
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+fn bench_split_text(c: &mut Criterion) {
+    let plain = "The quick brown fox jumps over the lazy dog. ".repeat(20);
+    let styled = "The \x1b[1mquick\x1b[0m brown \x1b[3mfox\x1b[0m jumps. ".repeat(20);
+    assert_eq!(split_text(&styled), split_text_per_char(&styled));
+
+    let mut group = c.benchmark_group("split_text");
+    for (name, text) in [("plain", &plain), ("styled", &styled)] {
+        group.bench_function(format!("per_char/{}", name), |b| {
+            b.iter(|| split_text_per_char(black_box(text)))
+        });
+        group.bench_function(format!("memchr/{}", name), |b| {
+            b.iter(|| split_text(black_box(text)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_hot_path, bench_split_text);
+criterion_main!(benches);
@@ -0,0 +1,266 @@
+//! Timelines for dated task lists.
+//!
+//! [`AgendaProcessor`] is a [`BlockProcessor`] for task lists whose items
+//! start with an ISO date, optionally a range:
+//!
+//! ```text
+//! - [x] 2024-07-01 design
+//! - [ ] 2024-07-02..2024-07-05 build
+//! ```
+//!
+//! Such lists are drawn as a compact gantt view, one row per task sorted by
+//! date, with a bar placed on a shared day axis. Lists with any other item
+//! are rendered as usual.
+//!
+//! ```
+//! use std::sync::Arc;
+//! use streamdown_parser::Parser;
+//! use streamdown_render::{AgendaProcessor, Renderer};
+//!
+//! let mut renderer = Renderer::new(Vec::new(), 60);
+//! renderer.add_block_processor(Arc::new(AgendaProcessor));
+//! let mut parser = Parser::new();
+//! for line in ["- [x] 2024-07-01 design", "- [ ] 2024-07-02..2024-07-05 build", ""] {
+//!     renderer.render(&parser.parse_line(line)).unwrap();
+//! }
+//! let output = String::from_utf8(renderer.into_writer()).unwrap();
+//! assert!(output.contains("2024-07-02 "));
+//! assert!(output.contains("█"));
+//! ```
+
+use streamdown_ansi::utils::visible_length;
+use streamdown_parser::ParseEvent;
+
+use crate::processor::BlockProcessor;
+use crate::text::truncate_to_visible;
+use crate::{DIM_OFF, DIM_ON, RESET, RenderStyle, fg_color};
+
+/// Columns of the date column, with its gap.
+const DATE_COLUMNS: usize = 11;
+
+/// A dated task.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AgendaItem {
+    /// Whether the task is done
+    pub done: bool,
+    /// First day, as days since 1970-01-01
+    pub start: i64,
+    /// Last day (the same as `start` for a single day)
+    pub end: i64,
+    /// First day as written, e.g. "2024-07-01"
+    pub date: String,
+    /// Last day as written
+    pub end_date: String,
+    /// The task text
+    pub title: String,
+}
+
+impl AgendaItem {
+    /// Parse list item content like `[ ] 2024-07-01..2024-07-03 title`.
+    pub fn parse(content: &str) -> Option<Self> {
+        let (done, rest) = match content.get(..4)? {
+            "[ ] " => (false, &content[4..]),
+            "[x] " | "[X] " => (true, &content[4..]),
+            _ => return None,
+        };
+        let (dates, title) = rest.split_once(' ').unwrap_or((rest, ""));
+        let (first, last) = dates.split_once("..").unwrap_or((dates, dates));
+        let (start, end) = (parse_date(first)?, parse_date(last)?);
+        (start <= end).then(|| Self {
+            done,
+            start,
+            end,
+            date: first.to_string(),
+            end_date: last.to_string(),
+            title: title.trim().to_string(),
+        })
+    }
+}
+
+/// Parse an ISO `YYYY-MM-DD` date into days since 1970-01-01.
+pub fn parse_date(date: &str) -> Option<i64> {
+    let bytes = date.as_bytes();
+    if bytes.len() != 10 || bytes[4] != b'-' || bytes[7] != b'-' {
+        return None;
+    }
+    let year: i64 = date[..4].parse().ok()?;
+    let month: i64 = date[5..7].parse().ok()?;
+    let day: i64 = date[8..].parse().ok()?;
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in_month = match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        1..=12 => 31,
+        _ => return None,
+    };
+    if !(1..=days_in_month).contains(&day) {
+        return None;
+    }
+
+    // Days from civil (proleptic Gregorian), shifted so March starts a year
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    Some(era * 146_097 + day_of_era - 719_468)
+}
+
+/// Draws dated task lists as a timeline.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AgendaProcessor;
+
+impl AgendaProcessor {
+    /// Lay out `items` in `width` columns: a header with the first and last
+    /// day, then one row per task.
+    pub fn timeline(items: &[AgendaItem], width: usize, style: &RenderStyle) -> Vec<String> {
+        let mut items = items.to_vec();
+        items.sort_by_key(|item| (item.start, item.end));
+        let (Some(first), Some(last)) = (
+            items.iter().map(|item| item.start).min(),
+            items.iter().map(|item| item.end).max(),
+        ) else {
+            return Vec::new();
+        };
+        // Whole columns per day when the days fit
+        let days = (last - first) as usize + 1;
+        let bar_width = (width / 3).clamp(8, 40);
+        let bar_width = if days <= bar_width {
+            bar_width / days * days
+        } else {
+            bar_width
+        };
+        let column = |day: i64| (day - first) as usize * bar_width / days;
+
+        let mut lines = Vec::with_capacity(items.len() + 1);
+        let from = &items[0].date[5..];
+        let to = items
+            .iter()
+            .max_by_key(|item| item.end)
+            .map_or("", |item| &item.end_date[5..]);
+        let axis = if bar_width > from.len() + to.len() {
+            format!("{}{:>w$}", from, to, w = bar_width - from.len())
+        } else {
+            from.to_string()
+        };
+        lines.push(format!(
+            "{}{}{}{}",
+            " ".repeat(DATE_COLUMNS),
+            DIM_ON,
+            axis,
+            DIM_OFF
+        ));
+
+        let title_width = width.saturating_sub(DATE_COLUMNS + bar_width + 3);
+        for item in &items {
+            let from = column(item.start);
+            let to = (column(item.end + 1).max(from + 1) - 1).min(bar_width - 1);
+            let color = if item.done {
+                &style.task_checked
+            } else {
+                &style.bullet
+            };
+            let glyph = if item.done {
+                &style.task_checked_glyph
+            } else {
+                &style.task_unchecked_glyph
+            };
+            let title = if visible_length(&item.title) > title_width {
+                format!(
+                    "{}…",
+                    truncate_to_visible(&item.title, title_width.saturating_sub(1))
+                )
+            } else {
+                item.title.clone()
+            };
+            lines.push(format!(
+                "{} {}{}{}{}{} {} {}",
+                item.date,
+                " ".repeat(from),
+                fg_color(color),
+                "█".repeat(to - from + 1),
+                RESET,
+                " ".repeat(bar_width - to - 1),
+                glyph,
+                title
+            ));
+        }
+        lines
+    }
+}
+
+impl BlockProcessor for AgendaProcessor {
+    fn claims(&self, first: &ParseEvent) -> bool {
+        matches!(
+            first,
+            ParseEvent::ListItem { content, .. } if AgendaItem::parse(content).is_some()
+        )
+    }
+
+    fn process(
+        &self,
+        events: &[ParseEvent],
+        width: usize,
+        style: &RenderStyle,
+    ) -> Option<Vec<String>> {
+        let mut items = Vec::new();
+        for event in events {
+            match event {
+                ParseEvent::ListItem { content, .. } => items.push(AgendaItem::parse(content)?),
+                ParseEvent::ListEnd | ParseEvent::Newline | ParseEvent::EmptyLine => {}
+                _ => return None,
+            }
+        }
+        Some(Self::timeline(&items, width, style))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(parse_date("1970-01-01"), Some(0));
+        assert_eq!(parse_date("2024-03-01"), Some(19_783));
+        assert_eq!(parse_date("2024-02-29"), Some(19_782));
+        assert_eq!(parse_date("2023-02-29"), None);
+        assert_eq!(parse_date("2024-13-01"), None);
+    }
+
+    #[test]
+    fn test_parse_item() {
+        let item = AgendaItem::parse("[x] 2024-07-01..2024-07-03 ship it").unwrap();
+        assert!(item.done);
+        assert_eq!(item.end - item.start, 2);
+        assert_eq!(item.title, "ship it");
+        assert!(AgendaItem::parse("[ ] someday").is_none());
+        assert!(AgendaItem::parse("[ ] 2024-07-03..2024-07-01 backwards").is_none());
+    }
+
+    #[test]
+    fn test_timeline_bars() {
+        let items = [
+            AgendaItem::parse("[ ] 2024-07-05 b").unwrap(),
+            AgendaItem::parse("[x] 2024-07-01..2024-07-02 a").unwrap(),
+        ];
+        let lines = AgendaProcessor::timeline(&items, 60, &RenderStyle::default());
+        let plain: Vec<String> = lines
+            .iter()
+            .map(|l| streamdown_ansi::utils::visible(l))
+            .collect();
+        // Five days of four columns each
+        assert_eq!(plain[0].trim(), format!("07-01{}07-05", " ".repeat(10)));
+        let bar = |row: &str| row[11..].chars().take(20).collect::<String>();
+        assert_eq!(
+            bar(&plain[1]),
+            format!("{}{}", "█".repeat(8), " ".repeat(12))
+        );
+        assert_eq!(
+            bar(&plain[2]),
+            format!("{}{}", " ".repeat(16), "█".repeat(4))
+        );
+        assert!(plain[2].ends_with(" b"));
+    }
+}
@@ -0,0 +1,65 @@
+//! Bump arena for per-line temporary strings.
+//!
+//! Rendering a line assembles several short-lived pieces (margin, colors,
+//! content, padding) into one output string. With the `arena` feature the
+//! renderer builds that string in a bump arena that is reset after every
+//! event, so high-throughput streaming stops hitting the global allocator
+//! once per line.
+
+use bumpalo::Bump;
+use bumpalo::collections::String as BumpString;
+
+/// Arena for strings that live for a single render event.
+#[derive(Debug, Default)]
+pub struct LineArena {
+    /// Backing bump allocator
+    bump: Bump,
+}
+
+impl LineArena {
+    /// Create an empty arena.
+    pub fn new() -> Self {
+        Self { bump: Bump::new() }
+    }
+
+    /// Concatenate parts into a string allocated in the arena.
+    pub fn concat(&self, parts: &[&str]) -> &str {
+        let len = parts.iter().map(|p| p.len()).sum();
+        let mut s = BumpString::with_capacity_in(len, &self.bump);
+        for part in parts {
+            s.push_str(part);
+        }
+        s.into_bump_str()
+    }
+
+    /// Free all strings at once, keeping the largest chunk for reuse.
+    pub fn reset(&mut self) {
+        self.bump.reset();
+    }
+
+    /// Bytes currently allocated from the arena.
+    pub fn allocated_bytes(&self) -> usize {
+        self.bump.allocated_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_concat() {
+        let arena = LineArena::new();
+        assert_eq!(arena.concat(&["a", "bc", "", "d"]), "abcd");
+    }
+
+    #[test]
+    fn test_reset_reuses_chunk() {
+        let mut arena = LineArena::new();
+        arena.concat(&["x"; 64]);
+        let before = arena.allocated_bytes();
+        arena.reset();
+        arena.concat(&["x"; 64]);
+        assert_eq!(arena.allocated_bytes(), before);
+    }
+}
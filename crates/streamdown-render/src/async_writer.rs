@@ -0,0 +1,141 @@
+//! Async writer support.
+//!
+//! [`AsyncRenderer`] renders into a tokio `AsyncWrite` (a socket, a
+//! `tokio::io::Stdout`, a TUI pipe), so async chat servers and terminal apps
+//! can drive the renderer without bridging through a blocking writer.
+//!
+//! ```
+//! # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+//! use streamdown_parser::ParseEvent;
+//! use streamdown_render::AsyncRenderer;
+//!
+//! let mut renderer = AsyncRenderer::new(Vec::new(), 80);
+//! renderer.render_event(&ParseEvent::Text("hello".to_string())).await.unwrap();
+//! renderer.finish().await.unwrap();
+//! assert!(!renderer.into_writer().is_empty());
+//! # });
+//! ```
+
+use std::io;
+
+use streamdown_parser::ParseEvent;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::{RenderResult, Renderer};
+
+/// Renderer writing to an async writer.
+///
+/// Each event is rendered into an in-memory buffer by a regular
+/// [`Renderer`], then written to the async writer and flushed, so output
+/// shows up as soon as the event has been handled.
+pub struct AsyncRenderer<W> {
+    /// Synchronous renderer producing the bytes
+    renderer: Renderer<Vec<u8>>,
+    /// Destination of the rendered output
+    writer: W,
+}
+
+impl<W: AsyncWrite + Unpin> AsyncRenderer<W> {
+    /// Create a renderer writing to `writer` at `width` columns with the
+    /// default style.
+    pub fn new(writer: W, width: usize) -> Self {
+        Self::with_renderer(writer, Renderer::new(Vec::new(), width))
+    }
+
+    /// Create a renderer around a preconfigured synchronous renderer.
+    ///
+    /// Anything already in the renderer's buffer is written with the first
+    /// event.
+    pub fn with_renderer(writer: W, renderer: Renderer<Vec<u8>>) -> Self {
+        Self { renderer, writer }
+    }
+
+    /// Get a mutable reference to the synchronous renderer (e.g. to change
+    /// style or features).
+    pub fn renderer_mut(&mut self) -> &mut Renderer<Vec<u8>> {
+        &mut self.renderer
+    }
+
+    /// Get a mutable reference to the async writer.
+    pub fn writer_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Consume the renderer and return the async writer.
+    pub fn into_writer(self) -> W {
+        self.writer
+    }
+
+    /// Render a single event and write its output.
+    pub async fn render_event(&mut self, event: &ParseEvent) -> RenderResult<()> {
+        self.renderer.render_event(event)?;
+        Ok(self.write_pending().await?)
+    }
+
+    /// Render a batch of events and write their output in one write.
+    pub async fn render_events(&mut self, events: &[ParseEvent]) -> RenderResult<()> {
+        self.renderer.render_events(events)?;
+        Ok(self.write_pending().await?)
+    }
+
+    /// Close open blocks, write trailers and flush (see [`Renderer::finish`]).
+    pub async fn finish(&mut self) -> RenderResult<()> {
+        self.renderer.finish()?;
+        Ok(self.write_pending().await?)
+    }
+
+    /// Reset for the next message (see [`Renderer::reset`]).
+    pub fn reset(&mut self) {
+        self.renderer.reset();
+    }
+
+    /// Move the buffered output to the async writer.
+    async fn write_pending(&mut self) -> io::Result<()> {
+        let output = std::mem::take(self.renderer.writer_mut());
+        if output.is_empty() {
+            return Ok(());
+        }
+        self.writer.write_all(&output).await?;
+        self.writer.flush().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RenderFeatures;
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn test_async_output_matches_sync() {
+        let events = streamdown_parser::Parser::new().parse_document("# Title\n\nSome *text*\n");
+
+        let mut sync = Renderer::new(Vec::new(), 80);
+        sync.set_features(RenderFeatures::plain());
+        for event in &events {
+            sync.render_event(event).unwrap();
+        }
+        sync.finish().unwrap();
+
+        let output = block_on(async {
+            let mut renderer = AsyncRenderer::new(Vec::new(), 80);
+            renderer
+                .renderer_mut()
+                .set_features(RenderFeatures::plain());
+            for event in &events {
+                renderer.render_event(event).await.unwrap();
+            }
+            renderer.finish().await.unwrap();
+            renderer.into_writer()
+        });
+
+        assert_eq!(output, sync.into_writer());
+        assert!(String::from_utf8_lossy(&output).contains("Title"));
+    }
+}
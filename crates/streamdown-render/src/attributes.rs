@@ -0,0 +1,197 @@
+//! Text attributes for styled elements.
+//!
+//! [`TextAttributes`] is the attribute half of an element's style (the color
+//! half being a [`Color`](crate::Color) string). In theme files it is written
+//! as the attribute names separated by spaces, e.g. `"bold underline"`, with
+//! `""` for none.
+
+use std::fmt;
+use std::str::FromStr;
+
+use streamdown_ansi::codes::{
+    BOLD_OFF, BOLD_ON, DIM_OFF, DIM_ON, ITALIC_OFF, ITALIC_ON, UNDERLINE_OFF, UNDERLINE_ON,
+};
+
+/// Attribute names, in the order they are written.
+const NAMES: [&str; 4] = ["bold", "italic", "underline", "dim"];
+
+/// Bold, italic, underline and dim toggles for an element.
+///
+/// ```
+/// use streamdown_render::TextAttributes;
+///
+/// let attrs: TextAttributes = "italic dim".parse().unwrap();
+/// assert!(attrs.italic && attrs.dim && !attrs.bold);
+/// assert_eq!(attrs.to_string(), "italic dim");
+/// assert_eq!(TextAttributes::BOLD.apply("hi"), "\x1b[1mhi\x1b[22m");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+pub struct TextAttributes {
+    /// Bold text
+    pub bold: bool,
+    /// Italic text
+    pub italic: bool,
+    /// Underlined text
+    pub underline: bool,
+    /// Dim (faint) text
+    pub dim: bool,
+}
+
+impl TextAttributes {
+    /// No attributes.
+    pub const PLAIN: Self = Self {
+        bold: false,
+        italic: false,
+        underline: false,
+        dim: false,
+    };
+
+    /// Bold only.
+    pub const BOLD: Self = Self {
+        bold: true,
+        ..Self::PLAIN
+    };
+
+    /// Italic only.
+    pub const ITALIC: Self = Self {
+        italic: true,
+        ..Self::PLAIN
+    };
+
+    /// Underline only.
+    pub const UNDERLINE: Self = Self {
+        underline: true,
+        ..Self::PLAIN
+    };
+
+    /// Dim only.
+    pub const DIM: Self = Self {
+        dim: true,
+        ..Self::PLAIN
+    };
+
+    /// Whether no attribute is set.
+    pub fn is_plain(&self) -> bool {
+        *self == Self::PLAIN
+    }
+
+    /// Escape sequences turning the attributes on.
+    pub fn on(&self) -> String {
+        self.codes([BOLD_ON, ITALIC_ON, UNDERLINE_ON, DIM_ON])
+    }
+
+    /// Escape sequences turning the attributes off again.
+    pub fn off(&self) -> String {
+        self.codes([BOLD_OFF, ITALIC_OFF, UNDERLINE_OFF, DIM_OFF])
+    }
+
+    /// `text` between [`on`](Self::on) and [`off`](Self::off).
+    pub fn apply(&self, text: &str) -> String {
+        if self.is_plain() {
+            return text.to_string();
+        }
+        format!("{}{}{}", self.on(), text, self.off())
+    }
+
+    /// Flags in [`NAMES`] order.
+    fn flags(&self) -> [bool; 4] {
+        [self.bold, self.italic, self.underline, self.dim]
+    }
+
+    /// Concatenate the codes of the set attributes.
+    fn codes(&self, codes: [&str; 4]) -> String {
+        self.flags()
+            .iter()
+            .zip(codes)
+            .filter(|(set, _)| **set)
+            .map(|(_, code)| code)
+            .collect()
+    }
+}
+
+impl FromStr for TextAttributes {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut attrs = Self::PLAIN;
+        for word in s.split_whitespace() {
+            let flag = match word {
+                "bold" => &mut attrs.bold,
+                "italic" => &mut attrs.italic,
+                "underline" => &mut attrs.underline,
+                "dim" => &mut attrs.dim,
+                other => {
+                    return Err(format!(
+                        "unknown text attribute {:?} (expected {})",
+                        other,
+                        NAMES.join(", ")
+                    ));
+                }
+            };
+            *flag = true;
+        }
+        Ok(attrs)
+    }
+}
+
+impl fmt::Display for TextAttributes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = self
+            .flags()
+            .iter()
+            .zip(NAMES)
+            .filter(|(set, _)| **set)
+            .map(|(_, name)| name)
+            .collect();
+        f.write_str(&names.join(" "))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for TextAttributes {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for TextAttributes {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <String as serde::Deserialize>::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_display() {
+        let attrs: TextAttributes = " bold  underline ".parse().unwrap();
+        assert_eq!(
+            attrs,
+            TextAttributes {
+                bold: true,
+                underline: true,
+                ..TextAttributes::PLAIN
+            }
+        );
+        assert_eq!(attrs.to_string(), "bold underline");
+        assert_eq!("".parse(), Ok(TextAttributes::PLAIN));
+        assert!("blink".parse::<TextAttributes>().is_err());
+    }
+
+    #[test]
+    fn test_codes() {
+        assert_eq!(TextAttributes::PLAIN.apply("x"), "x");
+        let attrs = TextAttributes {
+            italic: true,
+            dim: true,
+            ..TextAttributes::PLAIN
+        };
+        assert_eq!(attrs.on(), format!("{}{}", ITALIC_ON, DIM_ON));
+        assert_eq!(attrs.off(), format!("{}{}", ITALIC_OFF, DIM_OFF));
+    }
+}
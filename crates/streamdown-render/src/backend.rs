@@ -0,0 +1,576 @@
+//! Pluggable render backends.
+//!
+//! A [`RenderBackend`] turns parse events into some output format, one
+//! method per element. The terminal [`Renderer`] is the default backend;
+//! other formats (HTML, test harnesses, GUI toolkits) implement the trait
+//! and are driven the same way, by [`RenderBackend::render_event`] or
+//! [`render_str`].
+//!
+//! ```
+//! use std::io;
+//! use streamdown_render::{RenderBackend, render_str};
+//!
+//! /// Collects the document's headings.
+//! #[derive(Default)]
+//! struct Outline(Vec<String>);
+//!
+//! impl RenderBackend for Outline {
+//!     fn heading(&mut self, level: u8, content: &str) -> io::Result<()> {
+//!         self.0.push(format!("{} {}", "#".repeat(level as usize), content));
+//!         Ok(())
+//!     }
+//! }
+//!
+//! let mut outline = Outline::default();
+//! render_str(&mut outline, "# Guide\n\ntext\n\n## Setup\n").unwrap();
+//! assert_eq!(outline.0, ["# Guide", "## Setup"]);
+//! ```
+//!
+//! [`Renderer`]: crate::Renderer
+
+use std::io::{self, Write};
+
+use streamdown_parser::{InlineElement, ListBullet, ParseEvent, Parser};
+
+use crate::Renderer;
+
+/// Output format for parse events.
+///
+/// Block elements arrive as they stream (a code block is a start, its lines
+/// and an end); inline content arrives as [`InlineElement`]s. Every method
+/// has a default, so a backend implements only the elements it draws: the
+/// rest do nothing or fall back to a close relative (think blocks render as
+/// blockquotes).
+pub trait RenderBackend {
+    /// Render a heading.
+    fn heading(&mut self, _level: u8, _content: &str) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Start a code block.
+    fn code_block_start(&mut self, _language: Option<&str>, _indent: usize) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Language guessed for an unlabeled code block.
+    fn code_block_language(&mut self, _language: &str) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Render a code block line.
+    fn code_block_line(&mut self, _line: &str) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// End a code block.
+    fn code_block_end(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Render a list item.
+    fn list_item(
+        &mut self,
+        _indent: usize,
+        _bullet: &ListBullet,
+        _content: &str,
+    ) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// End a list.
+    fn list_end(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Start a table with its header row.
+    fn table_header(&mut self, _cells: &[String]) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Render a table body row.
+    fn table_row(&mut self, _cells: &[String]) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Separator between a table's header and body.
+    fn table_separator(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// End a table.
+    fn table_end(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Start a blockquote.
+    fn blockquote_start(&mut self, _depth: usize) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Render a blockquote line.
+    fn blockquote_line(&mut self, _text: &str) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// End a blockquote.
+    fn blockquote_end(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Start a `<think>` block.
+    fn think_block_start(&mut self) -> io::Result<()> {
+        self.blockquote_start(1)
+    }
+
+    /// Render a `<think>` block line.
+    fn think_block_line(&mut self, text: &str) -> io::Result<()> {
+        self.blockquote_line(text)
+    }
+
+    /// End a `<think>` block.
+    fn think_block_end(&mut self) -> io::Result<()> {
+        self.blockquote_end()
+    }
+
+    /// Start a `<details>` block.
+    fn details_start(&mut self, _summary: &str, _open: bool) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// End a `<details>` block.
+    fn details_end(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Render a horizontal rule.
+    fn horizontal_rule(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Blank line between blocks.
+    fn empty_line(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// End of a line of inline content.
+    fn newline(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Render a shell prompt.
+    fn prompt(&mut self, prompt: &str) -> io::Result<()> {
+        self.inline(&InlineElement::Text(prompt.to_string()))
+    }
+
+    /// Render inline content.
+    fn inline(&mut self, _element: &InlineElement) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Render one event by calling the matching method.
+    fn render_event(&mut self, event: &ParseEvent) -> io::Result<()> {
+        dispatch(self, event)
+    }
+
+    /// Called once the document is complete.
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Call the [`RenderBackend`] method matching `event`.
+pub fn dispatch<B: RenderBackend + ?Sized>(backend: &mut B, event: &ParseEvent) -> io::Result<()> {
+    match event {
+        ParseEvent::Text(_)
+        | ParseEvent::InlineCode(_)
+        | ParseEvent::Bold(_)
+        | ParseEvent::Italic(_)
+        | ParseEvent::Underline(_)
+        | ParseEvent::Strikeout(_)
+        | ParseEvent::BoldItalic(_)
+        | ParseEvent::Link { .. }
+        | ParseEvent::Image { .. }
+        | ParseEvent::Footnote(_)
+        | ParseEvent::Spoiler(_)
+        | ParseEvent::Kbd(_) => {
+            inline_element(event).map_or(Ok(()), |element| backend.inline(&element))
+        }
+        ParseEvent::Heading { level, content } => backend.heading(*level, content),
+        ParseEvent::CodeBlockStart { language, indent } => {
+            backend.code_block_start(language.as_deref(), *indent)
+        }
+        ParseEvent::CodeBlockLanguage(language) => backend.code_block_language(language),
+        ParseEvent::CodeBlockLine(line) => backend.code_block_line(line),
+        ParseEvent::CodeBlockEnd => backend.code_block_end(),
+        ParseEvent::ListItem {
+            indent,
+            bullet,
+            content,
+        } => backend.list_item(*indent, bullet, content),
+        ParseEvent::ListEnd => backend.list_end(),
+        ParseEvent::TableHeader(cells) => backend.table_header(cells),
+        ParseEvent::TableRow(cells) => backend.table_row(cells),
+        ParseEvent::TableSeparator => backend.table_separator(),
+        ParseEvent::TableEnd => backend.table_end(),
+        ParseEvent::BlockquoteStart { depth } => backend.blockquote_start(*depth),
+        ParseEvent::BlockquoteLine(text) => backend.blockquote_line(text),
+        ParseEvent::BlockquoteEnd => backend.blockquote_end(),
+        ParseEvent::ThinkBlockStart => backend.think_block_start(),
+        ParseEvent::ThinkBlockLine(text) => backend.think_block_line(text),
+        ParseEvent::ThinkBlockEnd => backend.think_block_end(),
+        ParseEvent::DetailsStart { summary, open } => backend.details_start(summary, *open),
+        ParseEvent::DetailsEnd => backend.details_end(),
+        ParseEvent::HorizontalRule => backend.horizontal_rule(),
+        ParseEvent::EmptyLine => backend.empty_line(),
+        ParseEvent::Newline => backend.newline(),
+        ParseEvent::Prompt(prompt) => backend.prompt(prompt),
+        ParseEvent::InlineElements(elements) => {
+            for element in elements {
+                backend.inline(element)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// The inline element carried by an inline event.
+pub fn inline_element(event: &ParseEvent) -> Option<InlineElement> {
+    Some(match event {
+        ParseEvent::Text(s) => InlineElement::Text(s.clone()),
+        ParseEvent::InlineCode(s) => InlineElement::Code(s.clone()),
+        ParseEvent::Bold(s) => InlineElement::Bold(s.clone()),
+        ParseEvent::Italic(s) => InlineElement::Italic(s.clone()),
+        ParseEvent::Underline(s) => InlineElement::Underline(s.clone()),
+        ParseEvent::Strikeout(s) => InlineElement::Strikeout(s.clone()),
+        ParseEvent::BoldItalic(s) => InlineElement::BoldItalic(s.clone()),
+        ParseEvent::Link { text, url } => InlineElement::Link {
+            text: text.clone(),
+            url: url.clone(),
+        },
+        ParseEvent::Image { alt, url } => InlineElement::Image {
+            alt: alt.clone(),
+            url: url.clone(),
+        },
+        ParseEvent::Footnote(s) => InlineElement::Footnote(s.clone()),
+        ParseEvent::Spoiler(s) => InlineElement::Spoiler(s.clone()),
+        ParseEvent::Kbd(s) => InlineElement::Kbd(s.clone()),
+        _ => return None,
+    })
+}
+
+/// Parse a complete markdown document and render it with `backend`,
+/// finishing it at the end.
+pub fn render_str<B: RenderBackend + ?Sized>(backend: &mut B, markdown: &str) -> io::Result<()> {
+    let mut parser = Parser::new();
+    for line in markdown.lines() {
+        for event in parser.parse_line(line) {
+            backend.render_event(&event)?;
+        }
+    }
+    for event in parser.finalize() {
+        backend.render_event(&event)?;
+    }
+    backend.finish()
+}
+
+/// The terminal backend: ANSI output through the [`Renderer`] pipeline.
+///
+/// The renderer draws whole events, so it takes them through
+/// [`render_event`](RenderBackend::render_event) directly; the per-element
+/// methods rebuild the matching event and draw that.
+impl<W: Write> RenderBackend for Renderer<W> {
+    fn heading(&mut self, level: u8, content: &str) -> io::Result<()> {
+        draw(
+            self,
+            ParseEvent::Heading {
+                level,
+                content: content.to_string(),
+            },
+        )
+    }
+
+    fn code_block_start(&mut self, language: Option<&str>, indent: usize) -> io::Result<()> {
+        draw(
+            self,
+            ParseEvent::CodeBlockStart {
+                language: language.map(str::to_string),
+                indent,
+            },
+        )
+    }
+
+    fn code_block_language(&mut self, language: &str) -> io::Result<()> {
+        draw(self, ParseEvent::CodeBlockLanguage(language.to_string()))
+    }
+
+    fn code_block_line(&mut self, line: &str) -> io::Result<()> {
+        draw(self, ParseEvent::CodeBlockLine(line.to_string()))
+    }
+
+    fn code_block_end(&mut self) -> io::Result<()> {
+        draw(self, ParseEvent::CodeBlockEnd)
+    }
+
+    fn list_item(&mut self, indent: usize, bullet: &ListBullet, content: &str) -> io::Result<()> {
+        draw(
+            self,
+            ParseEvent::ListItem {
+                indent,
+                bullet: *bullet,
+                content: content.to_string(),
+            },
+        )
+    }
+
+    fn list_end(&mut self) -> io::Result<()> {
+        draw(self, ParseEvent::ListEnd)
+    }
+
+    fn table_header(&mut self, cells: &[String]) -> io::Result<()> {
+        draw(self, ParseEvent::TableHeader(cells.to_vec()))
+    }
+
+    fn table_row(&mut self, cells: &[String]) -> io::Result<()> {
+        draw(self, ParseEvent::TableRow(cells.to_vec()))
+    }
+
+    fn table_separator(&mut self) -> io::Result<()> {
+        draw(self, ParseEvent::TableSeparator)
+    }
+
+    fn table_end(&mut self) -> io::Result<()> {
+        draw(self, ParseEvent::TableEnd)
+    }
+
+    fn blockquote_start(&mut self, depth: usize) -> io::Result<()> {
+        draw(self, ParseEvent::BlockquoteStart { depth })
+    }
+
+    fn blockquote_line(&mut self, text: &str) -> io::Result<()> {
+        draw(self, ParseEvent::BlockquoteLine(text.to_string()))
+    }
+
+    fn blockquote_end(&mut self) -> io::Result<()> {
+        draw(self, ParseEvent::BlockquoteEnd)
+    }
+
+    fn think_block_start(&mut self) -> io::Result<()> {
+        draw(self, ParseEvent::ThinkBlockStart)
+    }
+
+    fn think_block_line(&mut self, text: &str) -> io::Result<()> {
+        draw(self, ParseEvent::ThinkBlockLine(text.to_string()))
+    }
+
+    fn think_block_end(&mut self) -> io::Result<()> {
+        draw(self, ParseEvent::ThinkBlockEnd)
+    }
+
+    fn details_start(&mut self, summary: &str, open: bool) -> io::Result<()> {
+        draw(
+            self,
+            ParseEvent::DetailsStart {
+                summary: summary.to_string(),
+                open,
+            },
+        )
+    }
+
+    fn details_end(&mut self) -> io::Result<()> {
+        draw(self, ParseEvent::DetailsEnd)
+    }
+
+    fn horizontal_rule(&mut self) -> io::Result<()> {
+        draw(self, ParseEvent::HorizontalRule)
+    }
+
+    fn empty_line(&mut self) -> io::Result<()> {
+        draw(self, ParseEvent::EmptyLine)
+    }
+
+    fn newline(&mut self) -> io::Result<()> {
+        draw(self, ParseEvent::Newline)
+    }
+
+    fn prompt(&mut self, prompt: &str) -> io::Result<()> {
+        draw(self, ParseEvent::Prompt(prompt.to_string()))
+    }
+
+    fn inline(&mut self, element: &InlineElement) -> io::Result<()> {
+        draw(self, ParseEvent::from(element.clone()))
+    }
+
+    /// Render `event`, carrying a [`RenderError`](crate::RenderError)
+    /// inside the `io::Error` on failure.
+    fn render_event(&mut self, event: &ParseEvent) -> io::Result<()> {
+        Ok(Renderer::render_event(self, event)?)
+    }
+
+    /// Close open blocks and write the trailers; see [`Renderer::finish`].
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(Renderer::finish(self)?)
+    }
+}
+
+/// Draw one event with the terminal renderer.
+fn draw<W: Write>(renderer: &mut Renderer<W>, event: ParseEvent) -> io::Result<()> {
+    Ok(Renderer::render_event(renderer, &event)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records which backend methods were called.
+    #[derive(Default)]
+    struct Trace(Vec<String>);
+
+    impl RenderBackend for Trace {
+        fn heading(&mut self, level: u8, content: &str) -> io::Result<()> {
+            self.0.push(format!("h{} {}", level, content));
+            Ok(())
+        }
+        fn code_block_start(&mut self, language: Option<&str>, _: usize) -> io::Result<()> {
+            self.0.push(format!("code {}", language.unwrap_or("-")));
+            Ok(())
+        }
+        fn code_block_line(&mut self, line: &str) -> io::Result<()> {
+            self.0.push(format!("| {}", line));
+            Ok(())
+        }
+        fn code_block_end(&mut self) -> io::Result<()> {
+            self.0.push("/code".to_string());
+            Ok(())
+        }
+        fn list_item(&mut self, _: usize, _: &ListBullet, content: &str) -> io::Result<()> {
+            self.0.push(format!("- {}", content));
+            Ok(())
+        }
+        fn list_end(&mut self) -> io::Result<()> {
+            self.0.push("/list".to_string());
+            Ok(())
+        }
+        fn table_header(&mut self, cells: &[String]) -> io::Result<()> {
+            self.0.push(format!("th {}", cells.join(",")));
+            Ok(())
+        }
+        fn table_row(&mut self, cells: &[String]) -> io::Result<()> {
+            self.0.push(format!("tr {}", cells.join(",")));
+            Ok(())
+        }
+        fn table_end(&mut self) -> io::Result<()> {
+            self.0.push("/table".to_string());
+            Ok(())
+        }
+        fn blockquote_start(&mut self, depth: usize) -> io::Result<()> {
+            self.0.push(format!("quote {}", depth));
+            Ok(())
+        }
+        fn blockquote_line(&mut self, text: &str) -> io::Result<()> {
+            self.0.push(format!("> {}", text));
+            Ok(())
+        }
+        fn blockquote_end(&mut self) -> io::Result<()> {
+            self.0.push("/quote".to_string());
+            Ok(())
+        }
+        fn horizontal_rule(&mut self) -> io::Result<()> {
+            self.0.push("hr".to_string());
+            Ok(())
+        }
+        fn empty_line(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+        fn newline(&mut self) -> io::Result<()> {
+            self.0.push("nl".to_string());
+            Ok(())
+        }
+        fn inline(&mut self, element: &InlineElement) -> io::Result<()> {
+            self.0.push(format!("{:?}", element));
+            Ok(())
+        }
+        fn finish(&mut self) -> io::Result<()> {
+            self.0.push("end".to_string());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_dispatch_calls_element_methods() {
+        let mut trace = Trace::default();
+        render_str(
+            &mut trace,
+            "# T\n\n- a\n\n> q\n\n<think>\nhmm\n</think>\n\n```rust\nx\n```\n**b**\n",
+        )
+        .unwrap();
+        let calls = trace.0;
+        for expected in [
+            "h1 T",
+            "- a",
+            "quote 1",
+            "> q",
+            "> hmm",
+            "code rust",
+            "| x",
+            "/code",
+            "Bold(\"b\")",
+            "end",
+        ] {
+            assert!(calls.iter().any(|c| c == expected), "{expected}: {calls:?}");
+        }
+        assert_eq!(calls.last().map(String::as_str), Some("end"));
+    }
+
+    #[test]
+    fn test_renderer_backend_matches_events() {
+        let markdown = "# Title\n\nSome *text* and `code`.\n\n| a | b |\n|---|---|\n| 1 | 2 |\n";
+
+        let mut direct = Renderer::new(Vec::new(), 40);
+        let mut parser = Parser::new();
+        for line in markdown.lines() {
+            for event in parser.parse_line(line) {
+                direct.render_event(&event).unwrap();
+            }
+        }
+        for event in parser.finalize() {
+            direct.render_event(&event).unwrap();
+        }
+        Renderer::finish(&mut direct).unwrap();
+
+        let mut via_trait = Renderer::new(Vec::new(), 40);
+        render_str(&mut via_trait, markdown).unwrap();
+
+        assert_eq!(direct.into_writer(), via_trait.into_writer());
+    }
+
+    #[test]
+    fn test_renderer_element_methods_draw() {
+        let mut direct = Renderer::new(Vec::new(), 40);
+        for event in [
+            ParseEvent::Heading {
+                level: 1,
+                content: "Title".to_string(),
+            },
+            ParseEvent::EmptyLine,
+            ParseEvent::ListItem {
+                indent: 0,
+                bullet: ListBullet::Dash,
+                content: "item".to_string(),
+            },
+            ParseEvent::ListEnd,
+        ] {
+            direct.render_event(&event).unwrap();
+        }
+        Renderer::finish(&mut direct).unwrap();
+
+        let mut by_element = Renderer::new(Vec::new(), 40);
+        let backend: &mut dyn RenderBackend = &mut by_element;
+        backend.heading(1, "Title").unwrap();
+        backend.empty_line().unwrap();
+        backend.list_item(0, &ListBullet::Dash, "item").unwrap();
+        backend.list_end().unwrap();
+        backend.finish().unwrap();
+
+        assert_eq!(direct.into_writer(), by_element.into_writer());
+    }
+}
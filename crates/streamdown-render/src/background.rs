@@ -0,0 +1,155 @@
+//! Terminal background detection.
+//!
+//! The default [`RenderStyle`] is made for dark terminals;
+//! [`RenderStyle::light`] is its counterpart for light ones. Hosts pick
+//! between them with a [`Background`], found by sending [`OSC11_QUERY`] to
+//! the terminal and parsing the reply with [`Background::from_osc11_response`], or from the
+//! `COLORFGBG` variable some terminals set. Talking to the terminal (raw
+//! mode, a timeout) is left to the host, as it owns the TTY.
+//!
+//! Terminals that don't know OSC 11 stay silent, so hosts send
+//! [`DA1_QUERY`] right after it as a sentinel: every terminal answers it,
+//! in order, so once [`has_da1_reply`] holds any OSC 11 reply has been read
+//! and none is left to leak into the input later.
+//!
+//! ```
+//! use streamdown_render::Background;
+//!
+//! let reply = b"\x1b]11;rgb:ffff/ffff/dddd\x1b\\";
+//! assert_eq!(Background::from_osc11_response(reply), Some(Background::Light));
+//! assert_eq!(Background::from_colorfgbg("15;0"), Some(Background::Dark));
+//! ```
+
+use crate::RenderStyle;
+
+/// Query for the terminal's background color (OSC 11).
+pub const OSC11_QUERY: &str = "\x1b]11;?\x1b\\";
+
+/// Primary device attributes query (DA1), answered by every terminal.
+pub const DA1_QUERY: &str = "\x1b[c";
+
+/// Whether `response` contains a complete reply to [`DA1_QUERY`]
+/// (`ESC [ ? <params> c`).
+pub fn has_da1_reply(response: &[u8]) -> bool {
+    response.windows(3).enumerate().any(|(i, start)| {
+        start == b"\x1b[?"
+            && response[i + 3..]
+                .iter()
+                .find(|b| !(b.is_ascii_digit() || **b == b';'))
+                .is_some_and(|&b| b == b'c')
+    })
+}
+
+/// Brightness of the terminal background.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Background {
+    /// Dark background, light text
+    #[default]
+    Dark,
+    /// Light background, dark text
+    Light,
+}
+
+impl Background {
+    /// Classify a background color by its relative luminance.
+    pub fn from_rgb(r: u8, g: u8, b: u8) -> Self {
+        let luminance = 0.2126 * f64::from(r) + 0.7152 * f64::from(g) + 0.0722 * f64::from(b);
+        if luminance > 127.5 {
+            Background::Light
+        } else {
+            Background::Dark
+        }
+    }
+
+    /// Classify the reply to [`OSC11_QUERY`] (`ESC ] 11 ; rgb:RRRR/GGGG/BBBB`
+    /// ended by BEL or ST), ignoring anything around it.
+    pub fn from_osc11_response(response: &[u8]) -> Option<Self> {
+        let response = std::str::from_utf8(response).ok()?;
+        let start = response.find("]11;")? + 4;
+        let body = response[start..].split(['\x07', '\x1b']).next()?;
+        let (r, g, b) = parse_rgb_spec(body)?;
+        Some(Self::from_rgb(r, g, b))
+    }
+
+    /// Classify a `COLORFGBG` value (`fg;bg`, colors 0-15).
+    pub fn from_colorfgbg(value: &str) -> Option<Self> {
+        let bg: u8 = value.rsplit(';').next()?.trim().parse().ok()?;
+        match bg {
+            0..=6 | 8 => Some(Background::Dark),
+            7 | 9..=15 => Some(Background::Light),
+            _ => None,
+        }
+    }
+
+    /// Guess from an environment lookup function (`COLORFGBG`).
+    pub fn from_env<F>(var: F) -> Option<Self>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        Self::from_colorfgbg(&var("COLORFGBG")?)
+    }
+
+    /// Default style for this background.
+    pub fn style(self) -> RenderStyle {
+        match self {
+            Background::Dark => RenderStyle::default(),
+            Background::Light => RenderStyle::light(),
+        }
+    }
+}
+
+/// Parse an X11 color spec `rgb:R/G/B` with 1-4 hex digits per channel.
+fn parse_rgb_spec(spec: &str) -> Option<(u8, u8, u8)> {
+    let mut channels = spec.strip_prefix("rgb:")?.split('/').map(|hex| {
+        let digits = hex.len();
+        if !(1..=4).contains(&digits) {
+            return None;
+        }
+        let value = u32::from_str_radix(hex, 16).ok()?;
+        let max = (1u32 << (4 * digits)) - 1;
+        Some((value * 255 / max) as u8)
+    });
+    let rgb = (channels.next()??, channels.next()??, channels.next()??);
+    channels.next().is_none().then_some(rgb)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rgb_spec() {
+        assert_eq!(parse_rgb_spec("rgb:ffff/8080/0000"), Some((255, 128, 0)));
+        assert_eq!(parse_rgb_spec("rgb:f/8/0"), Some((255, 136, 0)));
+        assert_eq!(parse_rgb_spec("rgb:ff/ff"), None);
+        assert_eq!(parse_rgb_spec("#ffffff"), None);
+    }
+
+    #[test]
+    fn test_osc11_response() {
+        let dark = b"noise\x1b]11;rgb:1c1c/1c1c/1c1c\x07";
+        assert_eq!(
+            Background::from_osc11_response(dark),
+            Some(Background::Dark)
+        );
+        assert_eq!(Background::from_osc11_response(b"\x1b[?1;2c"), None);
+    }
+
+    #[test]
+    fn test_da1_reply() {
+        assert!(has_da1_reply(b"\x1b]11;rgb:0/0/0\x07\x1b[?62;22c"));
+        assert!(has_da1_reply(b"\x1b[?1;2c"));
+        assert!(!has_da1_reply(b"\x1b[?62;2"));
+        assert!(!has_da1_reply(b"\x1b]11;rgb:0/0/0\x07"));
+    }
+
+    #[test]
+    fn test_colorfgbg() {
+        assert_eq!(
+            Background::from_colorfgbg("0;default;15"),
+            Some(Background::Light)
+        );
+        assert_eq!(Background::from_colorfgbg("0;7"), Some(Background::Light));
+        assert_eq!(Background::from_colorfgbg("default"), None);
+    }
+}
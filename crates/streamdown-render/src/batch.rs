@@ -0,0 +1,72 @@
+//! Output batching.
+//!
+//! The renderer flushes after every event so streamed output shows up at
+//! once. When a caller already holds many events, [`BatchWriter`] collects
+//! the output instead and hands it to the real writer in one write and one
+//! flush at the end of the batch.
+
+use std::io::{self, Write};
+
+/// Writer that can hold output back until the end of a batch.
+#[derive(Debug)]
+pub(crate) struct BatchWriter<W: Write> {
+    /// The real writer
+    pub(crate) inner: W,
+    /// Output held back during a batch (None = not batching)
+    batch: Option<Vec<u8>>,
+}
+
+impl<W: Write> BatchWriter<W> {
+    /// Wrap a writer, not batching.
+    pub(crate) fn new(inner: W) -> Self {
+        Self { inner, batch: None }
+    }
+
+    /// Start holding output back. Returns false if a batch is already open,
+    /// in which case the caller must not end it.
+    pub(crate) fn begin_batch(&mut self) -> bool {
+        if self.batch.is_some() {
+            return false;
+        }
+        self.batch = Some(Vec::new());
+        true
+    }
+
+    /// Write out everything held back and flush.
+    pub(crate) fn end_batch(&mut self) -> io::Result<()> {
+        if let Some(batch) = self.batch.take() {
+            self.inner.write_all(&batch)?;
+        }
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Write for BatchWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match &mut self.batch {
+            Some(batch) => {
+                batch.extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            None => self.inner.write(buf),
+        }
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        match &mut self.batch {
+            Some(batch) => {
+                batch.extend_from_slice(buf);
+                Ok(())
+            }
+            None => self.inner.write_all(buf),
+        }
+    }
+
+    /// Flushes are deferred to the end of a batch.
+    fn flush(&mut self) -> io::Result<()> {
+        match self.batch {
+            Some(_) => Ok(()),
+            None => self.inner.flush(),
+        }
+    }
+}
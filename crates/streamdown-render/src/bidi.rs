@@ -0,0 +1,214 @@
+//! Bidirectional text.
+//!
+//! Terminals put characters in cells from left to right, so Arabic and
+//! Hebrew come out backwards unless the terminal reorders them itself. With
+//! [`RenderFeatures::bidi`](crate::RenderFeatures::bidi) the renderer wraps
+//! prose at logical boundaries as usual, then puts each line of paragraphs,
+//! headings, list items and block quotes into visual order with the Unicode
+//! Bidirectional Algorithm (UAX #9). Lines keep a left-to-right base
+//! direction, so margins, bullets and borders stay where they are.
+//!
+//! ```
+//! use streamdown_render::bidi::reorder_line;
+//!
+//! assert_eq!(reorder_line("say שלום (hi)"), "say םולש (hi)");
+//! assert_eq!(reorder_line("plain"), "plain");
+//! ```
+
+use std::borrow::Cow;
+
+use streamdown_ansi::utils::{Token, parse_sgr_params, tokens};
+use unicode_bidi::{BidiClass, Level, ParagraphBidiInfo, bidi_class};
+
+/// `line` in visual order, or as is if it has no right-to-left text.
+///
+/// Styles move with the characters they apply to; switching between them
+/// turns off only the attributes the line itself set, so a line drawn
+/// inside dim or italic text stays that way. Lines with escapes other than
+/// SGR (hyperlinks, for one) can't be split that way and are left as they
+/// are. Brackets in right-to-left runs are mirrored.
+pub fn reorder_line(line: &str) -> Cow<'_, str> {
+    if !line
+        .chars()
+        .any(|ch| matches!(bidi_class(ch), BidiClass::R | BidiClass::AL))
+    {
+        return Cow::Borrowed(line);
+    }
+
+    // The visible text, and the style each of its characters is drawn in
+    let mut plain = String::with_capacity(line.len());
+    let mut styles = vec![String::new()];
+    let mut char_styles = Vec::new();
+    for (_, token) in tokens(line) {
+        let escape = match token {
+            Token::Text(text) => {
+                for ch in text.chars() {
+                    char_styles.push(styles.len() - 1);
+                    plain.push(ch);
+                }
+                continue;
+            }
+            Token::Escape(escape) if is_sgr(escape) => escape,
+            Token::Escape(_) => return Cow::Borrowed(line),
+        };
+        let params = parse_sgr_params(escape);
+        let mut style = styles.last().cloned().unwrap_or_default();
+        if params.is_empty() || params.contains(&0) {
+            style.clear();
+        }
+        if params.iter().any(|&p| p != 0) {
+            style.push_str(escape);
+        }
+        styles.push(style);
+    }
+
+    let info = ParagraphBidiInfo::new(&plain, Some(Level::ltr()));
+    let (levels, runs) = info.visual_runs(0..plain.len());
+    // Style index of the character starting at each byte offset
+    let mut style_at = vec![0; plain.len()];
+    for ((i, _), style) in plain.char_indices().zip(&char_styles) {
+        style_at[i] = *style;
+    }
+
+    let mut result = String::with_capacity(line.len() + 16);
+    let mut current = 0;
+    let mut push = |i: usize, ch: char| {
+        if style_at[i] != current {
+            result.push_str(&switch_style(&styles[current], &styles[style_at[i]]));
+            current = style_at[i];
+        }
+        result.push(ch);
+    };
+    for run in runs {
+        let text = &plain[run.clone()];
+        if levels[run.start].is_rtl() {
+            for (i, ch) in text.char_indices().rev() {
+                push(run.start + i, mirror(ch));
+            }
+        } else {
+            for (i, ch) in text.char_indices() {
+                push(run.start + i, ch);
+            }
+        }
+    }
+
+    // Leave the style the line ended in for what follows
+    let last = styles.len() - 1;
+    if styles[current] != styles[last] {
+        result.push_str(&switch_style(&styles[current], &styles[last]));
+    }
+    Cow::Owned(result)
+}
+
+/// Escapes switching from style `from` to style `to` (each a run of SGR
+/// sequences): the `*_OFF` codes of what `from` turned on, then `to`.
+fn switch_style(from: &str, to: &str) -> String {
+    if let Some(added) = to.strip_prefix(from) {
+        return added.to_string();
+    }
+    let mut off: Vec<u32> = Vec::new();
+    for (_, token) in tokens(from) {
+        let params = parse_sgr_params(token.as_str());
+        let mut i = 0;
+        while let Some(&param) = params.get(i) {
+            // Extended colors take their arguments with them
+            i += match (param, params.get(i + 1)) {
+                (38 | 48 | 58, Some(5)) => 3,
+                (38 | 48 | 58, Some(2)) => 5,
+                _ => 1,
+            };
+            if let Some(code) = off_code(param)
+                && !off.contains(&code)
+            {
+                off.push(code);
+            }
+        }
+    }
+    let mut escapes = String::new();
+    if !off.is_empty() {
+        let codes: Vec<String> = off.iter().map(u32::to_string).collect();
+        escapes = format!("\x1b[{}m", codes.join(";"));
+    }
+    escapes.push_str(to);
+    escapes
+}
+
+/// The SGR parameter turning off what `param` turns on.
+fn off_code(param: u32) -> Option<u32> {
+    match param {
+        1 | 2 => Some(22),
+        3 => Some(23),
+        4 | 21 => Some(24),
+        5 | 6 => Some(25),
+        7 => Some(27),
+        8 => Some(28),
+        9 => Some(29),
+        30..=38 | 90..=97 => Some(39),
+        40..=48 | 100..=107 => Some(49),
+        53 => Some(55),
+        58 => Some(59),
+        _ => None,
+    }
+}
+
+/// Whether `escape` is a complete SGR sequence.
+fn is_sgr(escape: &str) -> bool {
+    escape
+        .strip_prefix("\x1b[")
+        .and_then(|rest| rest.strip_suffix('m'))
+        .is_some_and(|params| params.bytes().all(|b| b.is_ascii_digit() || b == b';'))
+}
+
+/// The mirror image of a bracket, shown in a right-to-left run.
+fn mirror(ch: char) -> char {
+    match ch {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        '«' => '»',
+        '»' => '«',
+        _ => ch,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use streamdown_ansi::codes::RESET;
+    use streamdown_ansi::utils::visible;
+
+    #[test]
+    fn test_reorder_line() {
+        assert!(matches!(reorder_line("left to right"), Cow::Borrowed(_)));
+        assert_eq!(reorder_line("אבג"), "גבא");
+        // Numbers keep their order inside a right-to-left run
+        assert_eq!(reorder_line("אב 12 גד"), "דג 12 בא");
+        // The left-to-right frame stays put
+        assert_eq!(reorder_line("  • שלום"), "  • םולש");
+        // Brackets are mirrored inside right-to-left text
+        assert_eq!(reorder_line("א(ב)"), "(ב)א");
+    }
+
+    #[test]
+    fn test_styles_move_with_text() {
+        let line = "\x1b[1mאב\x1b[0m ג";
+        let reordered = reorder_line(line);
+        assert_eq!(visible(&reordered), "ג בא");
+        assert!(reordered.contains("\x1b[1mבא"), "{reordered:?}");
+        // Style switches don't reset attributes from around the line
+        let line = "אב \x1b[4;38;5;0mג\x1b[24;39m ד";
+        let reordered = reorder_line(line);
+        assert_eq!(visible(&reordered), "ד ג בא");
+        assert!(!reordered.contains(RESET), "{reordered:?}");
+        assert!(reordered.contains("\x1b[24;39m"), "{reordered:?}");
+
+        // Hyperlinks can't be split, so the line is kept
+        let link = "\x1b]8;;https://example.com\x1b\\אב\x1b]8;;\x1b\\";
+        assert_eq!(reorder_line(link), link);
+    }
+}
@@ -0,0 +1,172 @@
+//! Border glyph sets.
+//!
+//! Code blocks, tables, blockquotes, think blocks and trace panels draw their
+//! frames from the glyphs of one [`BorderStyle`], so a font without
+//! box-drawing characters can switch everything to pure ASCII at once.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Which glyphs frames are drawn with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum BorderStyle {
+    /// `+`, `-` and `|`
+    Ascii,
+    /// Thin box-drawing lines (`┌─│`)
+    #[default]
+    Light,
+    /// Thin lines with rounded corners (`╭─│`)
+    Rounded,
+    /// Thick lines (`┏━┃`)
+    Heavy,
+    /// Double lines (`╔═║`)
+    Double,
+    /// Blank frames that keep the layout
+    None,
+}
+
+/// The glyphs of a [`BorderStyle`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BorderGlyphs {
+    /// Horizontal line
+    pub horizontal: &'static str,
+    /// Vertical line
+    pub vertical: &'static str,
+    /// Top-left corner
+    pub top_left: &'static str,
+    /// Bottom-left corner
+    pub bottom_left: &'static str,
+    /// Code block top edge
+    pub code_top: &'static str,
+    /// Code block bottom edge
+    pub code_bottom: &'static str,
+}
+
+impl BorderStyle {
+    /// All styles, in documentation order.
+    pub const ALL: [BorderStyle; 6] = [
+        BorderStyle::Ascii,
+        BorderStyle::Light,
+        BorderStyle::Rounded,
+        BorderStyle::Heavy,
+        BorderStyle::Double,
+        BorderStyle::None,
+    ];
+
+    /// Get the glyphs for this style.
+    ///
+    /// Box-drawing styles keep the half-block (`▄`/`▀`) code block edges.
+    pub fn glyphs(self) -> BorderGlyphs {
+        let (horizontal, vertical, top_left, bottom_left) = match self {
+            BorderStyle::Ascii => ("-", "|", "+", "+"),
+            BorderStyle::Light => ("─", "│", "┌", "└"),
+            BorderStyle::Rounded => ("─", "│", "╭", "╰"),
+            BorderStyle::Heavy => ("━", "┃", "┏", "┗"),
+            BorderStyle::Double => ("═", "║", "╔", "╚"),
+            BorderStyle::None => (" ", " ", " ", " "),
+        };
+        let (code_top, code_bottom) = match self {
+            BorderStyle::Ascii => ("-", "-"),
+            BorderStyle::None => (" ", " "),
+            _ => ("▄", "▀"),
+        };
+        BorderGlyphs {
+            horizontal,
+            vertical,
+            top_left,
+            bottom_left,
+            code_top,
+            code_bottom,
+        }
+    }
+
+    /// Style name, as accepted by [`FromStr`].
+    pub fn name(self) -> &'static str {
+        match self {
+            BorderStyle::Ascii => "ascii",
+            BorderStyle::Light => "light",
+            BorderStyle::Rounded => "rounded",
+            BorderStyle::Heavy => "heavy",
+            BorderStyle::Double => "double",
+            BorderStyle::None => "none",
+        }
+    }
+}
+
+impl fmt::Display for BorderStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for BorderStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        BorderStyle::ALL
+            .into_iter()
+            .find(|style| style.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                let names: Vec<_> = BorderStyle::ALL.iter().map(|s| s.name()).collect();
+                format!(
+                    "unknown border style '{}' (expected one of: {})",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unicode_width::UnicodeWidthStr;
+
+    #[test]
+    fn test_parse_roundtrip() {
+        for style in BorderStyle::ALL {
+            assert_eq!(style.name().parse::<BorderStyle>(), Ok(style));
+        }
+        assert_eq!("ASCII".parse::<BorderStyle>(), Ok(BorderStyle::Ascii));
+        assert!("fancy".parse::<BorderStyle>().is_err());
+    }
+
+    #[test]
+    fn test_glyphs_single_column() {
+        for style in BorderStyle::ALL {
+            let g = style.glyphs();
+            for glyph in [
+                g.horizontal,
+                g.vertical,
+                g.top_left,
+                g.bottom_left,
+                g.code_top,
+                g.code_bottom,
+            ] {
+                assert_eq!(glyph.width(), 1, "{style}: {glyph:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_ascii_is_ascii() {
+        let g = BorderStyle::Ascii.glyphs();
+        assert!(
+            [
+                g.horizontal,
+                g.vertical,
+                g.top_left,
+                g.bottom_left,
+                g.code_top,
+                g.code_bottom
+            ]
+            .iter()
+            .all(|s| s.is_ascii())
+        );
+    }
+}
@@ -0,0 +1,133 @@
+//! Current-section breadcrumb.
+//!
+//! A [`Breadcrumb`] follows heading events and keeps the headings that
+//! enclose the current position (H1 › H2 › H3). The renderer keeps one
+//! ([`Renderer::breadcrumb`]) for hosts that show it in their own status
+//! bar; it can also be fed events directly:
+//!
+//! ```
+//! use streamdown_parser::ParseEvent;
+//! use streamdown_render::Breadcrumb;
+//!
+//! let mut crumbs = Breadcrumb::new();
+//! for (level, title) in [(1, "Guide"), (2, "Setup"), (3, "Linux"), (2, "Usage")] {
+//!     crumbs.update(&ParseEvent::Heading { level, content: title.to_string() });
+//! }
+//! assert_eq!(crumbs.to_string(), "Guide › Usage");
+//! ```
+//!
+//! [`Renderer::breadcrumb`]: crate::Renderer::breadcrumb
+
+use std::fmt;
+
+use streamdown_ansi::utils::visible;
+use streamdown_parser::ParseEvent;
+
+/// Default separator between breadcrumb levels.
+pub const BREADCRUMB_SEPARATOR: &str = " › ";
+
+/// Headings enclosing the current position, outermost first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Breadcrumb {
+    /// (level, plain title) pairs with strictly increasing levels
+    sections: Vec<(u8, String)>,
+}
+
+impl Breadcrumb {
+    /// Create an empty breadcrumb.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Follow an event; returns whether the breadcrumb changed.
+    pub fn update(&mut self, event: &ParseEvent) -> bool {
+        match event {
+            ParseEvent::Heading { level, content } => {
+                self.enter(*level, &visible(content));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Enter a section: drop sections at `level` or deeper, then add it.
+    pub fn enter(&mut self, level: u8, title: &str) {
+        self.sections.retain(|(l, _)| *l < level);
+        self.sections.push((level, title.to_string()));
+    }
+
+    /// Get the enclosing sections as (level, title) pairs.
+    pub fn sections(&self) -> &[(u8, String)] {
+        &self.sections
+    }
+
+    /// Get the innermost section title.
+    pub fn current(&self) -> Option<&str> {
+        self.sections.last().map(|(_, title)| title.as_str())
+    }
+
+    /// Whether no heading has been seen yet.
+    pub fn is_empty(&self) -> bool {
+        self.sections.is_empty()
+    }
+
+    /// Join the titles of sections up to `max_level` with `separator`.
+    pub fn format(&self, max_level: u8, separator: &str) -> String {
+        self.sections
+            .iter()
+            .filter(|(level, _)| *level <= max_level)
+            .map(|(_, title)| title.as_str())
+            .collect::<Vec<_>>()
+            .join(separator)
+    }
+
+    /// Forget all sections.
+    pub fn clear(&mut self) {
+        self.sections.clear();
+    }
+}
+
+impl fmt::Display for Breadcrumb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.format(u8::MAX, BREADCRUMB_SEPARATOR))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levels_replace_siblings_and_children() {
+        let mut crumbs = Breadcrumb::new();
+        crumbs.enter(1, "A");
+        crumbs.enter(3, "A.x");
+        assert_eq!(crumbs.to_string(), "A › A.x");
+        crumbs.enter(2, "B");
+        assert_eq!(crumbs.to_string(), "A › B");
+        crumbs.enter(1, "C");
+        assert_eq!(crumbs.to_string(), "C");
+        assert_eq!(crumbs.current(), Some("C"));
+    }
+
+    #[test]
+    fn test_format_max_level() {
+        let mut crumbs = Breadcrumb::new();
+        crumbs.enter(1, "A");
+        crumbs.enter(2, "B");
+        crumbs.enter(3, "C");
+        assert_eq!(crumbs.format(2, " / "), "A / B");
+    }
+
+    #[test]
+    fn test_update_ignores_other_events() {
+        let mut crumbs = Breadcrumb::new();
+        assert!(!crumbs.update(&ParseEvent::Text("x".to_string())));
+        assert!(crumbs.is_empty());
+        assert!(crumbs.update(&ParseEvent::Heading {
+            level: 2,
+            content: "\x1b[1mStyled\x1b[22m".to_string(),
+        }));
+        assert_eq!(crumbs.current(), Some("Styled"));
+    }
+}
@@ -0,0 +1,209 @@
+//! Validating builder for [`RenderStyle`].
+//!
+//! Setting style fields directly accepts any string, so a typo in a color
+//! only shows up as missing color at render time. [`RenderStyleBuilder`]
+//! checks every color when the style is built and keeps the defaults for
+//! fields that weren't set:
+//!
+//! ```
+//! use streamdown_render::{RenderError, RenderStyle};
+//!
+//! let style = RenderStyle::builder().h1("#00ff80").bullet("yellow").build().unwrap();
+//! assert_eq!(style.h1, "#00ff80");
+//! assert_eq!(style.h2, RenderStyle::default().h2);
+//!
+//! let err = RenderStyle::builder().h2("#00ff8").build().unwrap_err();
+//! assert!(matches!(err, RenderError::InvalidColor { .. }));
+//! ```
+
+use crate::{HeadingAlign, RenderResult, RenderStyle, TextAttributes, UnderlineSpan};
+
+/// Builder for [`RenderStyle`], validating colors in [`build`](Self::build).
+#[derive(Debug, Clone, Default)]
+pub struct RenderStyleBuilder {
+    /// Style built so far
+    style: RenderStyle,
+}
+
+/// Generate a setter for each color field.
+macro_rules! color_setters {
+    ($($field:ident => $doc:literal),* $(,)?) => {
+        $(
+            #[doc = $doc]
+            pub fn $field(mut self, color: impl Into<String>) -> Self {
+                self.style.$field = color.into();
+                self
+            }
+        )*
+    };
+}
+
+impl RenderStyleBuilder {
+    /// Start from the default style.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    color_setters! {
+        h1 => "Color for h1 headings.",
+        h2 => "Color for h2 headings.",
+        h3 => "Color for h3 headings.",
+        h4 => "Color for h4 headings.",
+        h5 => "Color for h5 headings.",
+        h6 => "Color for h6 headings.",
+        code_bg => "Background color for code blocks.",
+        code_label => "Color for code block language labels.",
+        bullet => "Color for list bullet markers.",
+        task_checked => "Color for checked task list boxes.",
+        task_unchecked => "Color for unchecked task list boxes.",
+        table_header_bg => "Background color for table headers.",
+        table_border => "Color for table borders.",
+        blockquote_border => "Color for blockquote borders.",
+        think_border => "Color for think block borders.",
+        hr => "Color for horizontal rules.",
+        link_url => "Color for link URLs.",
+        image_marker => "Color for image markers.",
+        footnote => "Color for footnote markers.",
+        spoiler => "Color for spoiler markers and concealed spoilers.",
+        details_marker => "Color for `<details>` summary markers.",
+        kbd => "Color of keyboard key caps.",
+        diff_add => "Color for lines added in `diff` blocks.",
+        diff_remove => "Color for lines removed in `diff` blocks.",
+    }
+
+    /// Bullet glyphs by nesting depth, cycled.
+    pub fn bullets<I, S>(mut self, bullets: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.style.bullets = bullets.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Glyphs for checked and unchecked task list boxes.
+    pub fn task_glyphs(mut self, checked: impl Into<String>, unchecked: impl Into<String>) -> Self {
+        self.style.task_checked_glyph = checked.into();
+        self.style.task_unchecked_glyph = unchecked.into();
+        self
+    }
+
+    /// Text attributes for the heading at `level` (1-6; other levels are
+    /// ignored).
+    pub fn heading_attrs(mut self, level: usize, attrs: TextAttributes) -> Self {
+        if let Some(index) = level.checked_sub(1).filter(|&i| i < 6) {
+            self.style.heading_attrs[index] = attrs;
+        }
+        self
+    }
+
+    /// Text attributes for blockquote text.
+    pub fn blockquote_attrs(mut self, attrs: TextAttributes) -> Self {
+        self.style.blockquote_attrs = attrs;
+        self
+    }
+
+    /// Text attributes for think block text.
+    pub fn think_attrs(mut self, attrs: TextAttributes) -> Self {
+        self.style.think_attrs = attrs;
+        self
+    }
+
+    /// Text attributes for link text.
+    pub fn link_attrs(mut self, attrs: TextAttributes) -> Self {
+        self.style.link_attrs = attrs;
+        self
+    }
+
+    /// Text attributes for blockquote lines quoting earlier transcript text.
+    pub fn quoted_attrs(mut self, attrs: TextAttributes) -> Self {
+        self.style.quoted_attrs = attrs;
+        self
+    }
+
+    /// Alignment of h1-h6 headings.
+    pub fn heading_align(mut self, align: [HeadingAlign; 6]) -> Self {
+        self.style.heading_align = align;
+        self
+    }
+
+    /// Underline glyph and extent for the heading at `level` (1-6; other
+    /// levels are ignored).
+    pub fn heading_underline(
+        mut self,
+        level: usize,
+        glyph: impl Into<String>,
+        span: UnderlineSpan,
+    ) -> Self {
+        if let Some(index) = level.checked_sub(1).filter(|&i| i < 6) {
+            self.style.heading_underline[index] = glyph.into();
+            self.style.heading_underline_span[index] = span;
+        }
+        self
+    }
+
+    /// Check every color and return the style.
+    ///
+    /// Fails with [`RenderError::InvalidColor`](crate::RenderError) for the
+    /// first color that doesn't parse as a [`Color`](crate::Color).
+    pub fn build(self) -> RenderResult<RenderStyle> {
+        self.style.validate()?;
+        Ok(self.style)
+    }
+}
+
+impl From<RenderStyle> for RenderStyleBuilder {
+    /// Start from an existing style (e.g. [`RenderStyle::light`]).
+    fn from(style: RenderStyle) -> Self {
+        Self { style }
+    }
+}
+
+impl RenderStyle {
+    /// Start building a style from the defaults.
+    pub fn builder() -> RenderStyleBuilder {
+        RenderStyleBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RenderError;
+
+    #[test]
+    fn test_builder_validates_and_keeps_defaults() {
+        let style = RenderStyle::builder()
+            .h1("#00ff80")
+            .kbd("cyan")
+            .bullets(["-", "+"])
+            .heading_underline(2, "─", UnderlineSpan::Full)
+            .heading_underline(7, "x", UnderlineSpan::Full)
+            .build()
+            .unwrap();
+        assert_eq!(style.h1, "#00ff80");
+        assert_eq!(style.kbd, "cyan");
+        assert_eq!(style.bullets, ["-", "+"]);
+        assert_eq!(style.heading_underline[1], "─");
+        assert_eq!(style.code_bg, RenderStyle::default().code_bg);
+
+        match RenderStyle::builder().table_border("gray").build() {
+            Err(RenderError::InvalidColor { key, value }) => {
+                assert_eq!(key, "table_border");
+                assert_eq!(value, "gray");
+            }
+            other => panic!("expected invalid color, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_builder_from_style() {
+        let light = RenderStyle::light();
+        let style = RenderStyleBuilder::from(light.clone())
+            .h1("red")
+            .build()
+            .unwrap();
+        assert_eq!(style.h1, "red");
+        assert_eq!(style.h2, light.h2);
+    }
+}
@@ -0,0 +1,291 @@
+//! Paragraph-level wrap caching.
+//!
+//! Reflowing a long transcript (on resize or theme change) re-wraps every
+//! paragraph, even though most of them are unchanged. `WrapCache` memoizes
+//! `text_wrap` results keyed by the text, the target width and the style
+//! inputs (prefixes, indent, flags), and `flow_text` results keyed by the
+//! text, width and starting column, so only paragraphs whose key changed
+//! are wrapped again.
+
+use std::collections::HashMap;
+
+use crate::text::{FlowedText, WrappedText, flow_text, text_wrap};
+
+/// Default maximum number of cached paragraphs.
+pub const DEFAULT_WRAP_CACHE_CAPACITY: usize = 4096;
+
+/// Key identifying a single wrap computation.
+///
+/// The text itself is part of the key, so two paragraphs only share an
+/// entry when they are equal, not merely when their hashes collide.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct WrapKey {
+    /// Paragraph text
+    text: String,
+    /// Target width
+    width: usize,
+    /// Indentation for continuation lines
+    indent: usize,
+    /// Prefix for the first line
+    first_prefix: String,
+    /// Prefix for subsequent lines
+    next_prefix: String,
+    /// Truncate overlong lines
+    force_truncate: bool,
+    /// Keep formatting open at line ends
+    preserve_format: bool,
+}
+
+/// Key identifying a single flow computation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct FlowKey {
+    /// Span text
+    text: String,
+    /// Line width
+    width: usize,
+    /// Column the span starts at
+    column: usize,
+}
+
+/// Cache of wrapped paragraphs.
+#[derive(Debug, Clone)]
+pub struct WrapCache {
+    /// Cached results
+    entries: HashMap<WrapKey, WrappedText>,
+    /// Cached flowed spans
+    flows: HashMap<FlowKey, FlowedText>,
+    /// Maximum number of entries before the cache is flushed
+    capacity: usize,
+    /// Number of lookups served from the cache
+    hits: usize,
+    /// Number of lookups that required wrapping
+    misses: usize,
+}
+
+impl Default for WrapCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WrapCache {
+    /// Create a cache with the default capacity.
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_WRAP_CACHE_CAPACITY)
+    }
+
+    /// Create a cache holding at most `capacity` paragraphs.
+    ///
+    /// A capacity of 0 disables caching.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            flows: HashMap::new(),
+            capacity,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Wrap text, reusing a previous result when the inputs are unchanged.
+    ///
+    /// Takes the same arguments as [`text_wrap`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn wrap(
+        &mut self,
+        text: &str,
+        width: usize,
+        indent: usize,
+        first_prefix: &str,
+        next_prefix: &str,
+        force_truncate: bool,
+        preserve_format: bool,
+    ) -> WrappedText {
+        if self.capacity == 0 {
+            self.misses += 1;
+            return text_wrap(
+                text,
+                width,
+                indent,
+                first_prefix,
+                next_prefix,
+                force_truncate,
+                preserve_format,
+            );
+        }
+
+        let key = WrapKey {
+            text: text.to_string(),
+            width,
+            indent,
+            first_prefix: first_prefix.to_string(),
+            next_prefix: next_prefix.to_string(),
+            force_truncate,
+            preserve_format,
+        };
+
+        if let Some(wrapped) = self.entries.get(&key) {
+            self.hits += 1;
+            return wrapped.clone();
+        }
+
+        self.misses += 1;
+        let wrapped = text_wrap(
+            text,
+            width,
+            indent,
+            first_prefix,
+            next_prefix,
+            force_truncate,
+            preserve_format,
+        );
+
+        // Simple bound: drop everything once full rather than tracking recency.
+        if self.entries.len() >= self.capacity {
+            self.entries.clear();
+        }
+        self.entries.insert(key, wrapped.clone());
+        wrapped
+    }
+
+    /// Flow a span of text, reusing a previous result when the inputs are
+    /// unchanged.
+    ///
+    /// Takes the same arguments as [`flow_text`].
+    pub fn flow(&mut self, text: &str, width: usize, column: usize) -> FlowedText {
+        if self.capacity == 0 {
+            self.misses += 1;
+            return flow_text(text, width, column);
+        }
+
+        let key = FlowKey {
+            text: text.to_string(),
+            width,
+            column,
+        };
+
+        if let Some(flowed) = self.flows.get(&key) {
+            self.hits += 1;
+            return flowed.clone();
+        }
+
+        self.misses += 1;
+        let flowed = flow_text(text, width, column);
+        if self.flows.len() >= self.capacity {
+            self.flows.clear();
+        }
+        self.flows.insert(key, flowed.clone());
+        flowed
+    }
+
+    /// Remove all cached entries.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.flows.clear();
+    }
+
+    /// Number of cached paragraphs and spans.
+    pub fn len(&self) -> usize {
+        self.entries.len() + self.flows.len()
+    }
+
+    /// Check if the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty() && self.flows.is_empty()
+    }
+
+    /// Number of lookups served from the cache.
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// Number of lookups that required wrapping.
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_hit() {
+        let mut cache = WrapCache::new();
+        let first = cache.wrap("hello world foo bar", 10, 0, "", "", false, false);
+        let second = cache.wrap("hello world foo bar", 10, 0, "", "", false, false);
+        assert_eq!(first.lines, second.lines);
+        assert_eq!(cache.hits(), 1);
+        assert_eq!(cache.misses(), 1);
+    }
+
+    #[test]
+    fn test_cache_width_change_misses() {
+        let mut cache = WrapCache::new();
+        cache.wrap("hello world foo bar", 10, 0, "", "", false, false);
+        let wide = cache.wrap("hello world foo bar", 40, 0, "", "", false, false);
+        assert_eq!(wide.lines.len(), 1);
+        assert_eq!(cache.misses(), 2);
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn test_cache_style_change_misses() {
+        let mut cache = WrapCache::new();
+        cache.wrap("hello", 20, 0, "> ", "> ", false, false);
+        let styled = cache.wrap("hello", 20, 0, "| ", "| ", false, false);
+        assert!(styled.lines[0].starts_with("| "));
+        assert_eq!(cache.hits(), 0);
+    }
+
+    #[test]
+    fn test_cache_matches_text_wrap() {
+        let mut cache = WrapCache::new();
+        let text = "The quick brown fox jumps over the lazy dog";
+        let cached = cache.wrap(text, 12, 2, "", "", false, false);
+        let direct = text_wrap(text, 12, 2, "", "", false, false);
+        assert_eq!(cached.lines, direct.lines);
+    }
+
+    #[test]
+    fn test_cache_capacity_bound() {
+        let mut cache = WrapCache::with_capacity(2);
+        cache.wrap("a", 10, 0, "", "", false, false);
+        cache.wrap("b", 10, 0, "", "", false, false);
+        cache.wrap("c", 10, 0, "", "", false, false);
+        assert!(cache.len() <= 2);
+    }
+
+    #[test]
+    fn test_cache_compares_text() {
+        let mut cache = WrapCache::new();
+        let first = cache.wrap("one two", 4, 0, "", "", false, false);
+        let second = cache.wrap("three four", 4, 0, "", "", false, false);
+        assert_ne!(first.lines, second.lines);
+        assert_eq!(cache.hits(), 0);
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn test_cache_flow() {
+        let mut cache = WrapCache::new();
+        let first = cache.flow("hello world foo", 10, 4);
+        assert_eq!(first, flow_text("hello world foo", 10, 4));
+        assert_eq!(first.rows, ["hello ", "world foo"]);
+        assert_eq!(first.column, 9);
+        assert_eq!(cache.flow("hello world foo", 10, 4), first);
+        assert_eq!(cache.hits(), 1);
+        // A different starting column is a different layout
+        cache.flow("hello world foo", 10, 0);
+        assert_eq!(cache.misses(), 2);
+    }
+
+    #[test]
+    fn test_cache_disabled() {
+        let mut cache = WrapCache::with_capacity(0);
+        cache.wrap("a", 10, 0, "", "", false, false);
+        cache.wrap("a", 10, 0, "", "", false, false);
+        assert!(cache.is_empty());
+        assert_eq!(cache.hits(), 0);
+    }
+}
@@ -0,0 +1,221 @@
+//! Terminal capability detection.
+//!
+//! Guesses what the attached terminal supports from environment variables
+//! (`TERM`, `TERM_PROGRAM`, `KITTY_WINDOW_ID`, ...). Detection is explicit:
+//! a `Renderer` starts with conservative defaults and hosts opt in by calling
+//! [`TerminalCapabilities::detect`] and passing the result along.
+
+pub use streamdown_ansi::color::ColorDepth;
+
+/// Inline graphics protocol supported by the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GraphicsProtocol {
+    /// No inline graphics; images render as a text marker
+    #[default]
+    None,
+    /// Kitty graphics protocol (kitty, Ghostty, WezTerm)
+    Kitty,
+}
+
+/// Desktop notification escape understood by the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NotificationProtocol {
+    /// No notifications
+    #[default]
+    None,
+    /// `OSC 9` (iTerm2, kitty, WezTerm, Windows Terminal)
+    Osc9,
+    /// `OSC 777;notify` (VTE-based terminals, foot, urxvt, Ghostty)
+    Osc777,
+}
+
+/// What the attached terminal is able to display.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TerminalCapabilities {
+    /// Inline image protocol
+    pub graphics: GraphicsProtocol,
+    /// Desktop notification escape
+    pub notifications: NotificationProtocol,
+    /// Colors the terminal displays; 24-bit theme colors are downgraded to it
+    pub colors: ColorDepth,
+}
+
+impl TerminalCapabilities {
+    /// Detect capabilities from the process environment.
+    pub fn detect() -> Self {
+        Self::from_env(|name| std::env::var(name).ok())
+    }
+
+    /// Detect capabilities from an environment lookup function.
+    ///
+    /// Useful for hosts that know the client's environment (e.g. `TERM` sent
+    /// over an SSH PTY request) rather than their own.
+    pub fn from_env<F>(var: F) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        Self {
+            graphics: detect_graphics(&var),
+            notifications: detect_notifications(&var),
+            colors: detect_colors(&var),
+        }
+    }
+}
+
+/// Guess the graphics protocol from the environment.
+fn detect_graphics<F>(var: &F) -> GraphicsProtocol
+where
+    F: Fn(&str) -> Option<String>,
+{
+    if var("KITTY_WINDOW_ID").is_some() {
+        return GraphicsProtocol::Kitty;
+    }
+
+    let term = var("TERM").unwrap_or_default();
+    if term == "xterm-kitty" || term == "xterm-ghostty" {
+        return GraphicsProtocol::Kitty;
+    }
+
+    match var("TERM_PROGRAM").as_deref() {
+        Some("WezTerm") | Some("ghostty") => GraphicsProtocol::Kitty,
+        _ => GraphicsProtocol::None,
+    }
+}
+
+/// Guess the notification escape from the environment.
+fn detect_notifications<F>(var: &F) -> NotificationProtocol
+where
+    F: Fn(&str) -> Option<String>,
+{
+    match var("TERM_PROGRAM").as_deref() {
+        Some("iTerm.app") | Some("WezTerm") => return NotificationProtocol::Osc9,
+        Some("ghostty") => return NotificationProtocol::Osc777,
+        _ => {}
+    }
+    if var("KITTY_WINDOW_ID").is_some() || var("WT_SESSION").is_some() {
+        return NotificationProtocol::Osc9;
+    }
+    if var("VTE_VERSION").is_some() {
+        return NotificationProtocol::Osc777;
+    }
+
+    let term = var("TERM").unwrap_or_default();
+    if term == "xterm-kitty" {
+        NotificationProtocol::Osc9
+    } else if term == "xterm-ghostty" || term.starts_with("foot") || term.starts_with("rxvt") {
+        NotificationProtocol::Osc777
+    } else {
+        NotificationProtocol::None
+    }
+}
+
+/// Guess the color depth from the environment.
+///
+/// `COLORTERM` is authoritative when set; otherwise the terminfo name in
+/// `TERM` decides (`-direct`, `-256color`), with terminals known for 24-bit
+/// color recognized by name. Without a `TERM` (not a terminal) colors are
+/// left alone.
+fn detect_colors<F>(var: &F) -> ColorDepth
+where
+    F: Fn(&str) -> Option<String>,
+{
+    if let Some("truecolor" | "24bit") = var("COLORTERM").as_deref() {
+        return ColorDepth::TrueColor;
+    }
+    if matches!(
+        var("TERM_PROGRAM").as_deref(),
+        Some("iTerm.app" | "WezTerm" | "ghostty" | "vscode")
+    ) || var("KITTY_WINDOW_ID").is_some()
+        || var("WT_SESSION").is_some()
+    {
+        return ColorDepth::TrueColor;
+    }
+
+    let Some(term) = var("TERM").filter(|t| !t.is_empty()) else {
+        return ColorDepth::TrueColor;
+    };
+    if term.ends_with("-direct")
+        || term.contains("truecolor")
+        || term == "xterm-kitty"
+        || term == "xterm-ghostty"
+        || term.starts_with("foot")
+        || term.starts_with("alacritty")
+    {
+        ColorDepth::TrueColor
+    } else if term.contains("256col") {
+        ColorDepth::Ansi256
+    } else {
+        ColorDepth::Ansi16
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(pairs: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let pairs: Vec<(String, String)> = pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| {
+            pairs
+                .iter()
+                .find(|(k, _)| k == name)
+                .map(|(_, v)| v.clone())
+        }
+    }
+
+    #[test]
+    fn test_detect_kitty() {
+        let caps = TerminalCapabilities::from_env(env(&[("TERM", "xterm-kitty")]));
+        assert_eq!(caps.graphics, GraphicsProtocol::Kitty);
+
+        let caps = TerminalCapabilities::from_env(env(&[("KITTY_WINDOW_ID", "1")]));
+        assert_eq!(caps.graphics, GraphicsProtocol::Kitty);
+    }
+
+    #[test]
+    fn test_detect_wezterm() {
+        let caps = TerminalCapabilities::from_env(env(&[("TERM_PROGRAM", "WezTerm")]));
+        assert_eq!(caps.graphics, GraphicsProtocol::Kitty);
+    }
+
+    #[test]
+    fn test_detect_notifications() {
+        let caps = TerminalCapabilities::from_env(env(&[("TERM_PROGRAM", "iTerm.app")]));
+        assert_eq!(caps.notifications, NotificationProtocol::Osc9);
+
+        let caps = TerminalCapabilities::from_env(env(&[("VTE_VERSION", "7600")]));
+        assert_eq!(caps.notifications, NotificationProtocol::Osc777);
+
+        let caps = TerminalCapabilities::from_env(env(&[("TERM", "foot")]));
+        assert_eq!(caps.notifications, NotificationProtocol::Osc777);
+    }
+
+    #[test]
+    fn test_detect_plain_terminal() {
+        let caps = TerminalCapabilities::from_env(env(&[("TERM", "xterm-256color")]));
+        assert_eq!(caps.graphics, GraphicsProtocol::None);
+        assert_eq!(
+            caps,
+            TerminalCapabilities {
+                colors: ColorDepth::Ansi256,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_detect_colors() {
+        let colors = |pairs: &[(&str, &str)]| TerminalCapabilities::from_env(env(pairs)).colors;
+        assert_eq!(colors(&[]), ColorDepth::TrueColor);
+        assert_eq!(colors(&[("TERM", "linux")]), ColorDepth::Ansi16);
+        assert_eq!(colors(&[("TERM", "screen-256color")]), ColorDepth::Ansi256);
+        assert_eq!(colors(&[("TERM", "xterm-direct")]), ColorDepth::TrueColor);
+        assert_eq!(
+            colors(&[("TERM", "screen-256color"), ("COLORTERM", "truecolor")]),
+            ColorDepth::TrueColor
+        );
+    }
+}
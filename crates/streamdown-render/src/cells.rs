@@ -0,0 +1,249 @@
+//! Table cell formatting.
+//!
+//! A [`CellFormatter`] gets each table body cell before it is wrapped and may
+//! replace its text and alignment. [`LocaleFormatter`] is the built-in one:
+//! it groups the digits of numbers and rewrites ISO dates the way a locale
+//! writes them, right-aligned so columns of figures line up.
+//!
+//! ```
+//! use streamdown_render::{CellAlign, CellFormatter, LocaleFormatter};
+//!
+//! let de = LocaleFormatter::from_tag("de-DE");
+//! let cell = de.format("1234567.5").unwrap();
+//! assert_eq!(cell.text, "1.234.567,5");
+//! assert_eq!(cell.align, CellAlign::Right);
+//! assert_eq!(de.format("2024-03-09").unwrap().text, "09.03.2024");
+//! assert!(de.format("apples").is_none());
+//! ```
+
+use std::fmt;
+
+/// Horizontal alignment of a cell's content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CellAlign {
+    /// Flush left
+    #[default]
+    Left,
+    /// Flush right
+    Right,
+    /// Centered
+    Center,
+}
+
+/// Replacement text and alignment for a cell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormattedCell {
+    /// Text to show instead of the cell's markdown
+    pub text: String,
+    /// Alignment within the column
+    pub align: CellAlign,
+}
+
+/// Formats table body cells (header cells are left alone).
+pub trait CellFormatter: fmt::Debug + Send + Sync {
+    /// Format a cell's raw markdown, or return None to render it as usual.
+    fn format(&self, cell: &str) -> Option<FormattedCell>;
+}
+
+/// Order of the fields in a formatted date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateOrder {
+    /// `2024-03-09`
+    YearMonthDay,
+    /// `03/09/2024`
+    MonthDayYear,
+    /// `09.03.2024` (or with the locale's separator)
+    DayMonthYear,
+}
+
+/// Number and date conventions of a locale.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocaleFormatter {
+    /// Separator between groups of three digits
+    pub group: String,
+    /// Decimal separator
+    pub decimal: char,
+    /// Field order of dates
+    pub date_order: DateOrder,
+    /// Separator between date fields
+    pub date_separator: char,
+}
+
+impl LocaleFormatter {
+    /// Conventions for a locale tag such as `de-DE`, `fr_FR.UTF-8` or `en`.
+    ///
+    /// Unknown locales get English (US) conventions.
+    pub fn from_tag(tag: &str) -> Self {
+        let tag = tag.split('.').next().unwrap_or(tag).replace('_', "-");
+        let (language, region) = match tag.split_once('-') {
+            Some((l, r)) => (l.to_ascii_lowercase(), r.to_ascii_uppercase()),
+            None => (tag.to_ascii_lowercase(), String::new()),
+        };
+        let (group, decimal, date_order, date_separator) =
+            match (language.as_str(), region.as_str()) {
+                ("en", "US") | ("en", "") => (",", '.', DateOrder::MonthDayYear, '/'),
+                ("en", _) => (",", '.', DateOrder::DayMonthYear, '/'),
+                ("de" | "it" | "fr", "CH") => ("'", '.', DateOrder::DayMonthYear, '.'),
+                ("de" | "da" | "nb" | "fi" | "tr", _) => (".", ',', DateOrder::DayMonthYear, '.'),
+                ("nl" | "es" | "it" | "pt" | "id", _) => (".", ',', DateOrder::DayMonthYear, '/'),
+                ("fr" | "pl" | "ru" | "uk" | "cs" | "sv", _) => {
+                    ("\u{202f}", ',', DateOrder::DayMonthYear, '.')
+                }
+                ("ja" | "zh" | "ko" | "hu" | "lt", _) => (",", '.', DateOrder::YearMonthDay, '-'),
+                _ => (",", '.', DateOrder::MonthDayYear, '/'),
+            };
+        Self {
+            group: group.to_string(),
+            decimal,
+            date_order,
+            date_separator,
+        }
+    }
+
+    /// Conventions for the process locale (`LC_ALL`, `LC_NUMERIC`, `LANG`).
+    pub fn from_env() -> Self {
+        let tag = ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|v| !v.is_empty() && v != "C" && v != "POSIX")
+            .unwrap_or_else(|| "en-US".to_string());
+        Self::from_tag(&tag)
+    }
+
+    /// Group the digits of a plain number (`-12345.5`, `12%`).
+    ///
+    /// Integer parts of four digits or fewer stay ungrouped, so years read
+    /// as years, and zero-padded values such as IDs are left alone.
+    fn format_number(&self, cell: &str) -> Option<String> {
+        let (sign, rest) = match cell.strip_prefix(['-', '+']) {
+            Some(rest) => (&cell[..1], rest),
+            None => ("", cell),
+        };
+        let (number, suffix) = match rest.strip_suffix('%') {
+            Some(number) => (number, "%"),
+            None => (rest, ""),
+        };
+        let (int, frac) = match number.split_once('.') {
+            Some((int, frac)) => (int, Some(frac)),
+            None => (number, None),
+        };
+        let all_digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+        if !all_digits(int) || frac.is_some_and(|f| !all_digits(f)) {
+            return None;
+        }
+        if int.len() > 1 && int.starts_with('0') {
+            return None;
+        }
+
+        let mut grouped = String::new();
+        for (i, digit) in int.chars().enumerate() {
+            if int.len() > 4 && i > 0 && (int.len() - i) % 3 == 0 {
+                grouped.push_str(&self.group);
+            }
+            grouped.push(digit);
+        }
+        if let Some(frac) = frac {
+            grouped.push(self.decimal);
+            grouped.push_str(frac);
+        }
+        Some(format!("{}{}{}", sign, grouped, suffix))
+    }
+
+    /// Rewrite an ISO `YYYY-MM-DD` date.
+    fn format_date(&self, cell: &str) -> Option<String> {
+        let bytes = cell.as_bytes();
+        if bytes.len() != 10 || bytes[4] != b'-' || bytes[7] != b'-' {
+            return None;
+        }
+        let (year, month, day) = (&cell[..4], &cell[5..7], &cell[8..]);
+        let year_num: u16 = year.parse().ok()?;
+        let month_num: u8 = month.parse().ok()?;
+        let day_num: u8 = day.parse().ok()?;
+        let leap = year_num.is_multiple_of(4)
+            && (!year_num.is_multiple_of(100) || year_num.is_multiple_of(400));
+        let days = match month_num {
+            2 if leap => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            1..=12 => 31,
+            _ => return None,
+        };
+        if !(1..=days).contains(&day_num) {
+            return None;
+        }
+        let sep = self.date_separator;
+        Some(match self.date_order {
+            DateOrder::YearMonthDay => format!("{year}{sep}{month}{sep}{day}"),
+            DateOrder::MonthDayYear => format!("{month}{sep}{day}{sep}{year}"),
+            DateOrder::DayMonthYear => format!("{day}{sep}{month}{sep}{year}"),
+        })
+    }
+}
+
+impl CellFormatter for LocaleFormatter {
+    fn format(&self, cell: &str) -> Option<FormattedCell> {
+        let cell = cell.trim();
+        let text = self
+            .format_number(cell)
+            .or_else(|| self.format_date(cell))?;
+        Some(FormattedCell {
+            text,
+            align: CellAlign::Right,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grouping() {
+        let en = LocaleFormatter::from_tag("en-US");
+        assert_eq!(en.format_number("1234567").as_deref(), Some("1,234,567"));
+        assert_eq!(en.format_number("-10000.25").as_deref(), Some("-10,000.25"));
+        assert_eq!(en.format_number("999").as_deref(), Some("999"));
+        assert_eq!(en.format_number("12.5%").as_deref(), Some("12.5%"));
+        assert_eq!(en.format_number("2024").as_deref(), Some("2024"));
+        assert_eq!(en.format_number("0").as_deref(), Some("0"));
+        assert_eq!(en.format_number("0.5").as_deref(), Some("0.5"));
+        assert!(en.format_number("00123").is_none());
+
+        let fr = LocaleFormatter::from_tag("fr_FR.UTF-8");
+        assert_eq!(
+            fr.format_number("12345.5").as_deref(),
+            Some("12\u{202f}345,5")
+        );
+        assert_eq!(fr.format_number("1234.5").as_deref(), Some("1234,5"));
+    }
+
+    #[test]
+    fn test_not_numbers() {
+        let en = LocaleFormatter::from_tag("en");
+        for cell in ["", "-", "1.2.3", "12a", "v1.0", ".5", "1,000"] {
+            assert!(en.format_number(cell).is_none(), "{cell:?}");
+        }
+    }
+
+    #[test]
+    fn test_dates() {
+        let us = LocaleFormatter::from_tag("en-US");
+        assert_eq!(us.format_date("2024-03-09").as_deref(), Some("03/09/2024"));
+        let ja = LocaleFormatter::from_tag("ja-JP");
+        assert_eq!(ja.format_date("2024-03-09").as_deref(), Some("2024-03-09"));
+        assert!(us.format_date("2024-13-01").is_none());
+        assert!(us.format_date("2024-04-31").is_none());
+        assert!(us.format_date("2023-02-29").is_none());
+        assert!(us.format_date("2024-02-29").is_some());
+        assert!(us.format_date("2024/03/09").is_none());
+    }
+
+    #[test]
+    fn test_trait_trims_and_aligns() {
+        let en = LocaleFormatter::from_tag("en-GB");
+        let cell = en.format(" 25000 ").unwrap();
+        assert_eq!(cell.text, "25,000");
+        assert_eq!(cell.align, CellAlign::Right);
+        assert!(en.format("**bold**").is_none());
+    }
+}
@@ -0,0 +1,221 @@
+//! Line-buffered parse/render pipeline.
+//!
+//! Shared by the adapters that receive markdown in arbitrary chunks
+//! (`RenderStream`, `SshRenderSession`, `Multiplexer`).
+
+use std::ops::Range;
+
+use streamdown_parser::Parser;
+
+use crate::meta::LineMeta;
+use crate::{RenderResult, Renderer, SpanMeta};
+
+/// Line-buffering parse/render pipeline shared by the async adapters.
+///
+/// Chunks may split lines anywhere; a line is rendered once its newline
+/// arrives, and the trailing partial line is rendered by [`finish`]. In a
+/// code block with [`ProgressLines::InPlace`](crate::ProgressLines::InPlace),
+/// each carriage-return state of a partial line is drawn as it arrives (see
+/// [`Renderer::draw_partial_line`]).
+///
+/// [`finish`]: ChunkRenderer::finish
+pub(crate) struct ChunkRenderer {
+    /// Streaming parser
+    pub(crate) parser: Parser,
+    /// Renderer writing into an in-memory buffer
+    pub(crate) renderer: Renderer<Vec<u8>>,
+    /// Input received after the last newline
+    pending: String,
+    /// Metadata of byte ranges of `pending`
+    pending_meta: Vec<(Range<usize>, SpanMeta)>,
+}
+
+impl ChunkRenderer {
+    /// Create a pipeline around `renderer`.
+    pub(crate) fn new(renderer: Renderer<Vec<u8>>) -> Self {
+        Self {
+            parser: Parser::new(),
+            renderer,
+            pending: String::new(),
+            pending_meta: Vec::new(),
+        }
+    }
+
+    /// Add a chunk of markdown, rendering every line it completes.
+    pub(crate) fn push(&mut self, chunk: &str) -> RenderResult<()> {
+        self.push_with_meta(chunk, None)
+    }
+
+    /// Add a chunk of markdown whose text carries `meta`, rendering every
+    /// line it completes.
+    pub(crate) fn push_with_meta(
+        &mut self,
+        chunk: &str,
+        meta: Option<SpanMeta>,
+    ) -> RenderResult<()> {
+        let start = self.pending.len();
+        self.pending.push_str(chunk);
+        if let Some(meta) = meta {
+            self.pending_meta.push((start..self.pending.len(), meta));
+        }
+        if let Some(end) = self.pending.rfind('\n') {
+            let complete: String = self.pending.drain(..=end).collect();
+            let spans = std::mem::take(&mut self.pending_meta);
+            self.pending_meta = slice_spans(&spans, end + 1..usize::MAX);
+            let mut offset = 0;
+            for line in complete[..end].split('\n') {
+                let line_spans = slice_spans(&spans, offset..offset + line.len());
+                self.render_line(line, line_spans)?;
+                offset += line.len() + 1;
+            }
+        }
+        self.draw_partial()
+    }
+
+    /// Paint the partial line: its progress states in code blocks, the
+    /// line itself elsewhere (if enabled).
+    fn draw_partial(&mut self) -> RenderResult<()> {
+        let prose = self.renderer.features().partial_lines && !self.pending.contains('\r');
+        if self.pending.is_empty() || !(self.parser.state().is_in_code() || prose) {
+            return Ok(());
+        }
+        Ok(self.renderer.draw_partial_line(&self.pending)?)
+    }
+
+    /// Render the trailing partial line, close open blocks, and write the
+    /// link endnotes, stats footer and completion notification (if enabled).
+    pub(crate) fn finish(&mut self) -> RenderResult<()> {
+        if !self.pending.is_empty() {
+            let line = std::mem::take(&mut self.pending);
+            let spans = std::mem::take(&mut self.pending_meta);
+            self.render_line(&line, spans)?;
+        }
+        let events = self.parser.finalize();
+        self.render_events(&events)?;
+        self.renderer.finish()
+    }
+
+    /// Take everything rendered so far.
+    pub(crate) fn take_output(&mut self) -> Vec<u8> {
+        std::mem::take(self.renderer.writer_mut())
+    }
+
+    /// Parse and render one complete line, with the metadata of its byte
+    /// ranges (if any) set around the events drawn from them.
+    fn render_line(
+        &mut self,
+        line: &str,
+        spans: Vec<(Range<usize>, SpanMeta)>,
+    ) -> RenderResult<()> {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        let events = self.parser.parse_line(line);
+        if spans.is_empty() {
+            return self.render_events(&events);
+        }
+        let mut meta = LineMeta::new(line, spans);
+        let mut result = Ok(());
+        for event in &events {
+            self.renderer.set_span_meta(meta.lookup(event));
+            result = self.render_events(std::slice::from_ref(event));
+            if result.is_err() {
+                break;
+            }
+        }
+        self.renderer.set_span_meta(None);
+        result
+    }
+
+    /// Render events, stopping at the first error.
+    fn render_events(&mut self, events: &[streamdown_parser::ParseEvent]) -> RenderResult<()> {
+        events
+            .iter()
+            .try_for_each(|event| self.renderer.render_event(event))
+    }
+}
+
+/// The parts of `spans` within `range`, relative to its start.
+fn slice_spans(
+    spans: &[(Range<usize>, SpanMeta)],
+    range: Range<usize>,
+) -> Vec<(Range<usize>, SpanMeta)> {
+    spans
+        .iter()
+        .filter_map(|(span, meta)| {
+            let (start, end) = (span.start.max(range.start), span.end.min(range.end));
+            (start < end).then(|| (start - range.start..end - range.start, meta.clone()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ProgressLines, RenderFeatures};
+
+    #[test]
+    fn test_progress_drawn_as_it_arrives() {
+        let features = RenderFeatures {
+            progress_lines: ProgressLines::InPlace,
+            ..Default::default()
+        };
+        let mut chunks = ChunkRenderer::new(Renderer::with_features(Vec::new(), 40, features));
+        chunks.push("```\n").unwrap();
+        chunks.take_output();
+
+        chunks.push("fetch 10%\rfetch 5").unwrap();
+        let first = String::from_utf8(chunks.take_output()).unwrap();
+        assert!(first.contains("fetch 10%") && first.ends_with('\r'));
+
+        // No new state yet: nothing is redrawn
+        chunks.push("0%").unwrap();
+        assert!(chunks.take_output().is_empty());
+
+        chunks.push("\rfetch 100%\n```\n").unwrap();
+        let rest = String::from_utf8(chunks.take_output()).unwrap();
+        assert!(rest.contains("fetch 50%") && rest.contains("fetch 100%"));
+    }
+
+    #[test]
+    fn test_chunk_meta_styles_its_spans() {
+        use std::sync::Arc;
+        use streamdown_ansi::codes::DIM_ON;
+
+        let mut renderer = Renderer::new(Vec::new(), 40);
+        renderer.set_meta_styler(Some(Arc::new(crate::ConfidenceStyler::default())));
+        let mut chunks = ChunkRenderer::new(renderer);
+        let unsure = SpanMeta::new().with("confidence", 0.1);
+        chunks.push("sure text and ").unwrap();
+        chunks.push_with_meta("**a guess**", Some(unsure)).unwrap();
+        chunks.push("\nnext line\n").unwrap();
+        let output = String::from_utf8(chunks.take_output()).unwrap();
+        assert!(!output.contains(&format!("{DIM_ON}sure")));
+        assert!(output.contains(&format!("{DIM_ON}\x1b[1ma guess")));
+        assert_eq!(output.matches(DIM_ON).count(), 1);
+    }
+
+    #[test]
+    fn test_partial_line_painted_then_replaced() {
+        let features = RenderFeatures {
+            partial_lines: true,
+            ..Default::default()
+        };
+        let mut chunks = ChunkRenderer::new(Renderer::with_features(Vec::new(), 40, features));
+        chunks.push("Hello **wor").unwrap();
+        let partial = String::from_utf8(chunks.take_output()).unwrap();
+        assert_eq!(partial, "\r\x1b[2KHello wor");
+
+        // The closed span is re-styled in place
+        chunks.push("ld**").unwrap();
+        let partial = String::from_utf8(chunks.take_output()).unwrap();
+        assert_eq!(
+            partial,
+            format!("\r\x1b[6C\x1b[0m{}world\x1b[22m\x1b[K", crate::BOLD_ON)
+        );
+
+        chunks.push(" now\n").unwrap();
+        let line = String::from_utf8(chunks.take_output()).unwrap();
+        assert!(line.starts_with("\r\x1b[2K"), "{line:?}");
+        assert!(line.contains(&format!("{}world", crate::BOLD_ON)));
+        assert!(!line.contains("**"));
+    }
+}
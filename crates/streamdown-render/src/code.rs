@@ -1,20 +1,32 @@
 //! Code block rendering.
 //!
 //! Renders fenced code blocks with:
-//! - Syntax highlighting
+//! - Syntax highlighting (with the `syntax-highlighting` feature)
 //! - Pretty padding (▄▄▄ / ▀▀▀ borders) or space-based borders
 //! - Line wrapping for long lines (optional)
+//! - Line numbers in a dim gutter (optional)
+//! - Carriage-return progress lines collapsed or redrawn in place
+//! - `diff` blocks colored line by line, without the highlighting engine
+//! - Pre-colored lines (captured CLI output) passed through as written
 //! - Language labels
 
-use crate::{RenderStyle, bg_color, fg_color};
-use streamdown_ansi::codes::RESET;
+use crate::{BorderStyle, CodeOverflow, RenderStyle, bg_color, fg_color};
+use streamdown_ansi::codes::{BOLD_OFF, BOLD_ON, DIM_OFF, DIM_ON, FGRESET, RESET};
+use streamdown_ansi::sanitize::sanitize_preserving_ansi;
+use streamdown_ansi::utils::{is_ansi_code, parse_sgr_params, split_up, visible_length};
+use streamdown_ansi::width::{char_width, str_width};
+#[cfg(feature = "syntax-highlighting")]
 use streamdown_syntax::{HighlightState, Highlighter};
 
 /// Characters for pretty code block borders.
 pub const CODEPAD_TOP: char = '▄'; // Lower half block
 pub const CODEPAD_BOTTOM: char = '▀'; // Upper half block
 
+/// Marker ending a code row continued on the next one.
+pub const CONTINUATION: char = '↩';
+
 /// Code block rendering state.
+#[cfg(feature = "syntax-highlighting")]
 pub struct CodeBlockState<'a> {
     /// The syntax highlighter
     pub highlighter: &'a Highlighter,
@@ -34,6 +46,7 @@ pub struct CodeBlockState<'a> {
     pub raw_buffer: String,
 }
 
+#[cfg(feature = "syntax-highlighting")]
 impl<'a> CodeBlockState<'a> {
     /// Create a new code block state.
     pub fn new(highlighter: &'a Highlighter) -> Self {
@@ -88,6 +101,7 @@ impl<'a> CodeBlockState<'a> {
 /// * `left_margin` - Left margin string
 /// * `style` - Render style
 /// * `pretty_pad` - Whether to use pretty padding (▄▄▄)
+/// * `border` - Glyphs for the pretty padding
 ///
 /// # Returns
 /// Vector of lines for the code block header
@@ -97,8 +111,10 @@ pub fn render_code_start(
     left_margin: &str,
     style: &RenderStyle,
     pretty_pad: bool,
+    border: BorderStyle,
 ) -> Vec<String> {
     let mut lines = Vec::new();
+    let code_top = border.glyphs().code_top;
     let bg = bg_color(&style.code_bg);
     let fg = fg_color(&style.table_border);
 
@@ -111,14 +127,14 @@ pub fn render_code_start(
         // Pretty top border: ▄▄▄▄▄ with optional language label embedded
         if let Some(label) = lang_label {
             let label_fg = fg_color(&style.code_label);
-            let label_width = unicode_width::UnicodeWidthStr::width(label.as_str());
+            let label_width = str_width(&label);
 
             // First character (column 0)
-            let first_char = CODEPAD_TOP.to_string();
+            let first_char = code_top;
             // Label starts at column 1 (second position)
             // Remaining border characters fill the rest
             let remaining_width = width.saturating_sub(1 + label_width);
-            let remaining_border = CODEPAD_TOP.to_string().repeat(remaining_width);
+            let remaining_border = code_top.repeat(remaining_width);
 
             lines.push(format!(
                 "{}{}{}{}{}{}{}{}{}",
@@ -126,14 +142,14 @@ pub fn render_code_start(
             ));
         } else {
             // No language label, just border
-            let border = CODEPAD_TOP.to_string().repeat(width);
+            let border = code_top.repeat(width);
             lines.push(format!("{}{}{}{}{}", left_margin, fg, bg, border, RESET));
         }
     } else {
         // Simple border with spaces (copy-paste friendly)
         if let Some(label) = lang_label {
             let label_fg = fg_color(&style.code_label);
-            let label_width = unicode_width::UnicodeWidthStr::width(label.as_str());
+            let label_width = str_width(&label);
             let padding = width.saturating_sub(1 + label_width);
 
             lines.push(format!(
@@ -172,6 +188,7 @@ pub fn render_code_start(
 ///
 /// # Returns
 /// Vector of rendered lines (may be multiple if wrapped)
+#[cfg(feature = "syntax-highlighting")]
 pub fn render_code_line(
     line: &str,
     state: &mut CodeBlockState<'_>,
@@ -238,11 +255,13 @@ pub fn render_code_line(
 /// * `left_margin` - Left margin string
 /// * `style` - Render style
 /// * `pretty_pad` - Whether to use pretty padding (▀▀▀)
+/// * `border` - Glyphs for the pretty padding
 pub fn render_code_end(
     width: usize,
     left_margin: &str,
     style: &RenderStyle,
     pretty_pad: bool,
+    border: BorderStyle,
 ) -> Vec<String> {
     let mut lines = Vec::new();
     let bg = bg_color(&style.code_bg);
@@ -250,7 +269,7 @@ pub fn render_code_end(
 
     if pretty_pad {
         // Pretty bottom border: ▀▀▀▀▀
-        let border = CODEPAD_BOTTOM.to_string().repeat(width);
+        let border = border.glyphs().code_bottom.repeat(width);
         lines.push(format!("{}{}{}{}{}", left_margin, fg, bg, border, RESET));
     } else {
         // Simple border with spaces
@@ -266,6 +285,259 @@ pub fn render_code_end(
     lines
 }
 
+/// Minimum number of digits in the line number gutter.
+const MIN_GUTTER_DIGITS: usize = 3;
+
+/// Width of the line number gutter for a line number (digits plus ` │ `).
+///
+/// Numbers get at least three digits; the gutter grows for longer ones.
+pub fn gutter_width(line_number: usize) -> usize {
+    line_number.to_string().len().max(MIN_GUTTER_DIGITS) + 3
+}
+
+/// Render the line number gutter, `width` columns wide.
+///
+/// `None` renders a blank gutter for wrapped continuation rows.
+pub fn line_number_gutter(line_number: Option<usize>, width: usize, border: BorderStyle) -> String {
+    let digits = width.saturating_sub(3);
+    let number = line_number.map(|n| n.to_string()).unwrap_or_default();
+    let vertical = border.glyphs().vertical;
+    format!("{}{:>digits$} {} {}", DIM_ON, number, vertical, DIM_OFF)
+}
+
+/// Whether a code block language is a unified diff (`diff` or `patch`).
+pub fn is_diff_language(language: &str) -> bool {
+    language.eq_ignore_ascii_case("diff") || language.eq_ignore_ascii_case("patch")
+}
+
+/// Whether a diff is inside a hunk after `line`, given whether it was
+/// before: a `@@` header opens a hunk and a `diff ` line starts the next
+/// file's headers.
+pub fn diff_in_hunk(line: &str, in_hunk: bool) -> bool {
+    if line.starts_with("@@") {
+        true
+    } else if line.starts_with("diff ") {
+        false
+    } else {
+        in_hunk
+    }
+}
+
+/// Color a line of a unified diff: added lines in `style.diff_add`,
+/// removed lines in `style.diff_remove`, hunk headers dim and file headers
+/// bold. `---`/`+++` lines are file headers only before the first hunk;
+/// inside one they are a removed `--` or added `++` line. Colors are reset
+/// with foreground/attribute codes so the code background stays.
+///
+/// ```
+/// use streamdown_render::RenderStyle;
+/// use streamdown_render::code::diff_line;
+///
+/// let style = RenderStyle::default();
+/// assert!(diff_line("+added", true, &style).ends_with("+added\x1b[39m"));
+/// assert_eq!(diff_line(" context", true, &style), " context");
+/// ```
+pub fn diff_line(line: &str, in_hunk: bool, style: &RenderStyle) -> String {
+    let file_header = ["+++ ", "--- ", "diff ", "index "];
+    if !in_hunk && file_header.iter().any(|prefix| line.starts_with(prefix)) {
+        format!("{}{}{}", BOLD_ON, line, BOLD_OFF)
+    } else if line.starts_with("@@") {
+        format!("{}{}{}", DIM_ON, line, DIM_OFF)
+    } else if line.starts_with('+') {
+        format!("{}{}{}", fg_color(&style.diff_add), line, FGRESET)
+    } else if line.starts_with('-') {
+        format!("{}{}{}", fg_color(&style.diff_remove), line, FGRESET)
+    } else {
+        line.to_string()
+    }
+}
+
+/// Split a line written with carriage returns into its successive states.
+///
+/// Progress bars (pip, cargo) redraw their line after each `\r`; empty
+/// states (from a trailing `\r` or `\r\r`) are skipped.
+pub fn progress_states(line: &str) -> Vec<&str> {
+    line.split('\r').filter(|s| !s.is_empty()).collect()
+}
+
+/// Fence languages of command output, whose carriage-return lines are
+/// always drawn in place.
+const OUTPUT_LANGUAGES: &[&str] = &[
+    "console",
+    "log",
+    "output",
+    "shell-session",
+    "terminal",
+    "text",
+];
+
+/// Whether the states of a carriage-return line in a block fenced as
+/// `language` are drawn in place with
+/// [`ProgressLines::InPlace`](crate::ProgressLines::InPlace): in unlabeled
+/// and command output blocks, or when the line looks like a progress bar
+/// (a percentage, or a run of bar characters).
+///
+/// ```
+/// use streamdown_render::code::progress_in_place;
+///
+/// assert!(progress_in_place(None, "a\rb"));
+/// assert!(progress_in_place(Some("python"), "fetch 10%\rfetch 20%"));
+/// assert!(!progress_in_place(Some("python"), "a = 1\rb = 2"));
+/// ```
+pub fn progress_in_place(language: Option<&str>, line: &str) -> bool {
+    let output = language.is_none_or(|language| {
+        OUTPUT_LANGUAGES
+            .iter()
+            .any(|output| output.eq_ignore_ascii_case(language))
+    });
+    output || looks_like_progress(line)
+}
+
+/// Whether `line` has a percentage or three bar characters in a row.
+fn looks_like_progress(line: &str) -> bool {
+    let bytes = line.as_bytes();
+    let percent = bytes
+        .windows(2)
+        .any(|pair| pair[0].is_ascii_digit() && pair[1] == b'%');
+    let mut run = 0;
+    let bar = line.chars().any(|c| {
+        run = if matches!(
+            c,
+            '#' | '=' | '█' | '━' | '▉' | '▊' | '▋' | '▌' | '▍' | '▎' | '▏'
+        ) {
+            run + 1
+        } else {
+            0
+        };
+        run >= 3
+    });
+    percent || bar
+}
+
+/// Final state of a carriage-return progress line (the line itself if it
+/// has no `\r`).
+pub fn final_progress_state(line: &str) -> &str {
+    progress_states(line).last().copied().unwrap_or("")
+}
+
+/// Keep the color codes of a pre-colored code line, for
+/// [`RenderFeatures::ansi_passthrough`](crate::RenderFeatures::ansi_passthrough).
+///
+/// Escape sequences other than SGR (title changes, cursor moves) are
+/// dropped, and `background` is restored after each code that resets the
+/// background, so the block stays filled to its right edge.
+///
+/// ```
+/// use streamdown_render::code::passthrough_line;
+///
+/// let line = passthrough_line("\x1b[31mok\x1b[0m \x1b]0;title\x07done", "<bg>");
+/// assert_eq!(line, "\x1b[31mok\x1b[0m<bg> done");
+/// ```
+pub fn passthrough_line(line: &str, background: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    for part in split_up(&sanitize_preserving_ansi(line)) {
+        out.push_str(&part);
+        if is_ansi_code(&part) && resets_background(&parse_sgr_params(&part)) {
+            out.push_str(background);
+        }
+    }
+    out
+}
+
+/// Whether SGR `params` reset the background: a `0` or `49` of their own,
+/// not a component of a `38`/`48`/`58` extended color.
+fn resets_background(params: &[u32]) -> bool {
+    let mut i = 0;
+    while let Some(&param) = params.get(i) {
+        match (param, params.get(i + 1)) {
+            (38 | 48 | 58, Some(5)) => i += 3,
+            (38 | 48 | 58, Some(2)) => i += 5,
+            (0 | 49, _) => return true,
+            _ => i += 1,
+        }
+    }
+    false
+}
+
+/// Fit a (possibly highlighted) code line into `width` columns.
+///
+/// With `wrap`, the line is split into rows of at most `width` columns and
+/// active colors are carried over to each row. Otherwise it is truncated
+/// with `…`. ANSI codes don't count toward the width; wide characters count
+/// as two columns.
+pub fn fit_code_line(line: &str, width: usize, wrap: bool) -> Vec<String> {
+    if width == 0 {
+        return vec![String::new()];
+    }
+    let limit = if wrap { width } else { width - 1 };
+
+    let mut rows = Vec::new();
+    let mut current = String::new();
+    let mut current_width = 0;
+    let mut active: Vec<String> = Vec::new();
+
+    for segment in split_up(line) {
+        if is_ansi_code(&segment) {
+            if parse_sgr_params(&segment).contains(&0) {
+                active.clear();
+            } else {
+                active.push(segment.clone());
+            }
+            current.push_str(&segment);
+            continue;
+        }
+        for ch in segment.chars() {
+            let ch_width = char_width(ch).unwrap_or(0);
+            if current_width + ch_width > limit && current_width > 0 {
+                if !wrap {
+                    current.push('…');
+                    return vec![current];
+                }
+                rows.push(std::mem::replace(&mut current, active.concat()));
+                current_width = 0;
+            }
+            current.push(ch);
+            current_width += ch_width;
+        }
+    }
+    rows.push(current);
+    rows
+}
+
+/// Rows drawing a (possibly highlighted) code line in `width` columns
+/// under an overflow `policy` (with [`CodeOverflow::Auto`] resolved).
+///
+/// Wrapped rows but the last are padded and end with a dim
+/// [`CONTINUATION`] marker in the last column, so every row but the
+/// last is exactly `width` columns wide.
+///
+/// ```
+/// use streamdown_render::CodeOverflow;
+/// use streamdown_render::code::overflow_rows;
+///
+/// assert_eq!(overflow_rows("abcdefgh", 5, CodeOverflow::Truncate), ["abcd…"]);
+/// assert_eq!(overflow_rows("abcdefgh", 5, CodeOverflow::Overflow), ["abcdefgh"]);
+/// ```
+pub fn overflow_rows(line: &str, width: usize, policy: CodeOverflow) -> Vec<String> {
+    if visible_length(line) <= width {
+        return vec![line.to_string()];
+    }
+    match policy {
+        CodeOverflow::Truncate => fit_code_line(line, width, false),
+        CodeOverflow::Wrap if width > 1 => {
+            let mut rows = fit_code_line(line, width - 1, true);
+            let last = rows.len() - 1;
+            for row in &mut rows[..last] {
+                let padding = " ".repeat((width - 1).saturating_sub(visible_length(row)));
+                row.push_str(&format!("{}{}{}{}", padding, DIM_ON, CONTINUATION, DIM_OFF));
+            }
+            rows
+        }
+        CodeOverflow::Wrap => fit_code_line(line, width, true),
+        CodeOverflow::Auto | CodeOverflow::Overflow => vec![line.to_string()],
+    }
+}
+
 /// Wrap a code line if it exceeds the width.
 ///
 /// Unlike text wrapping, code wrapping preserves indentation
@@ -341,6 +613,110 @@ pub fn code_wrap(text: &str, width: usize, pretty_broken: bool) -> (usize, Vec<S
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_diff_line() {
+        assert!(is_diff_language("Patch") && !is_diff_language("rust"));
+        let style = RenderStyle {
+            diff_add: "blue".to_string(),
+            ..RenderStyle::default()
+        };
+        assert!(diff_line("-old", true, &style).starts_with(&fg_color("red")));
+        assert!(diff_line("+new", true, &style).starts_with(&fg_color("blue")));
+        assert_eq!(
+            diff_line("@@ -1 +1 @@", true, &style),
+            format!("{}@@ -1 +1 @@{}", DIM_ON, DIM_OFF)
+        );
+        // File headers are not additions or removals
+        assert!(diff_line("--- a/x", false, &style).starts_with(BOLD_ON));
+        assert!(diff_line("+++ b/x", false, &style).starts_with(BOLD_ON));
+        // Inside a hunk they are: a removed `-- x` line
+        assert!(diff_line("--- x", true, &style).starts_with(&fg_color("red")));
+
+        let mut in_hunk = false;
+        for (line, expected) in [
+            ("diff --git a/x b/x", false),
+            ("--- a/x", false),
+            ("@@ -1 +1 @@", true),
+            ("--- x", true),
+            ("diff --git a/y b/y", false),
+        ] {
+            in_hunk = diff_in_hunk(line, in_hunk);
+            assert_eq!(in_hunk, expected, "{line}");
+        }
+    }
+
+    #[test]
+    fn test_gutter_width() {
+        assert_eq!(gutter_width(1), 6);
+        assert_eq!(gutter_width(999), 6);
+        assert_eq!(gutter_width(1000), 7);
+        let gutter = line_number_gutter(Some(7), 6, BorderStyle::Light);
+        assert_eq!(streamdown_ansi::utils::visible(&gutter), "  7 │ ");
+        let blank = line_number_gutter(None, 6, BorderStyle::Light);
+        assert_eq!(streamdown_ansi::utils::visible_length(&blank), 6);
+    }
+
+    #[test]
+    fn test_progress_states() {
+        assert_eq!(
+            progress_states("10%\r50%\r\r100%\r"),
+            ["10%", "50%", "100%"]
+        );
+        assert_eq!(final_progress_state("done"), "done");
+        assert_eq!(final_progress_state("\r"), "");
+    }
+
+    #[test]
+    fn test_progress_in_place() {
+        assert!(progress_in_place(Some("Console"), "a\rb"));
+        assert!(progress_in_place(Some("rust"), "[###   ]\r[######]"));
+        assert!(progress_in_place(Some("rust"), "██▌\r████"));
+        assert!(!progress_in_place(Some("rust"), "x%\ry"));
+    }
+
+    #[test]
+    fn test_passthrough_line() {
+        let bg = "\x1b[48;5;236m";
+        assert_eq!(
+            passthrough_line("\x1b[1;32mok\x1b[m \x1b[41mred\x1b[49m.", bg),
+            format!("\x1b[1;32mok\x1b[m{bg} \x1b[41mred\x1b[49m{bg}.")
+        );
+        // Cursor moves are dropped
+        assert_eq!(passthrough_line("a\x1b[2Jb", bg), "ab");
+        // Zero color components are not resets
+        for colored in [
+            "\x1b[48;2;0;0;255mX",
+            "\x1b[38;5;0;1mX",
+            "\x1b[38;2;0;49;0mX",
+        ] {
+            assert_eq!(passthrough_line(colored, bg), colored);
+        }
+        assert_eq!(
+            passthrough_line("\x1b[38;5;0;0mX", bg),
+            format!("\x1b[38;5;0;0m{bg}X")
+        );
+    }
+
+    #[test]
+    fn test_overflow_rows() {
+        let rows = overflow_rows("\x1b[31mabcdefgh", 4, CodeOverflow::Wrap);
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0], format!("\x1b[31mabc{DIM_ON}↩{DIM_OFF}"));
+        assert_eq!(rows[2], "\x1b[31mgh");
+        assert_eq!(overflow_rows("abc", 3, CodeOverflow::Truncate), ["abc"]);
+    }
+
+    #[test]
+    fn test_fit_code_line() {
+        assert_eq!(fit_code_line("short", 10, true), vec!["short"]);
+        assert_eq!(fit_code_line("abcdefgh", 3, true), vec!["abc", "def", "gh"]);
+        assert_eq!(fit_code_line("abcdefgh", 4, false), vec!["abc…"]);
+
+        // Colors carry over to continuation rows
+        let rows = fit_code_line("\x1b[31mabcdef", 3, true);
+        assert_eq!(rows[1], "\x1b[31mdef");
+    }
+
     fn default_style() -> RenderStyle {
         RenderStyle::default()
     }
@@ -385,7 +761,14 @@ mod tests {
 
     #[test]
     fn test_render_code_start_pretty() {
-        let lines = render_code_start(Some("rust"), 80, "", &default_style(), true);
+        let lines = render_code_start(
+            Some("rust"),
+            80,
+            "",
+            &default_style(),
+            true,
+            BorderStyle::Light,
+        );
         assert!(!lines.is_empty());
         // Should have ▄ border
         assert!(lines[0].contains(CODEPAD_TOP));
@@ -393,7 +776,14 @@ mod tests {
 
     #[test]
     fn test_render_code_start_not_pretty() {
-        let lines = render_code_start(Some("rust"), 80, "", &default_style(), false);
+        let lines = render_code_start(
+            Some("rust"),
+            80,
+            "",
+            &default_style(),
+            false,
+            BorderStyle::Light,
+        );
         assert!(!lines.is_empty());
         // Should NOT have ▄ border (space-based instead)
         assert!(!lines[0].contains(CODEPAD_TOP));
@@ -401,18 +791,19 @@ mod tests {
 
     #[test]
     fn test_render_code_end_pretty() {
-        let lines = render_code_end(80, "", &default_style(), true);
+        let lines = render_code_end(80, "", &default_style(), true, BorderStyle::Light);
         assert_eq!(lines.len(), 1);
         assert!(lines[0].contains(CODEPAD_BOTTOM));
     }
 
     #[test]
     fn test_render_code_end_not_pretty() {
-        let lines = render_code_end(80, "", &default_style(), false);
+        let lines = render_code_end(80, "", &default_style(), false, BorderStyle::Light);
         assert_eq!(lines.len(), 1);
         assert!(!lines[0].contains(CODEPAD_BOTTOM));
     }
 
+    #[cfg(feature = "syntax-highlighting")]
     #[test]
     fn test_code_block_state_raw_buffer() {
         let highlighter = Highlighter::new();
@@ -530,7 +921,7 @@ mod tests {
         // With display-based: padding = 40 - (6 + 2) = 32
         // The difference is 3 extra spaces with byte-based
 
-        let lines = render_code_start(Some(lang), width, "", &style, false);
+        let lines = render_code_start(Some(lang), width, "", &style, false, BorderStyle::Light);
 
         // Find the language label line (contains "[日本語]")
         let label_line = lines
@@ -541,7 +932,7 @@ mod tests {
         // The label line should have correct width (40 display width)
         // Strip ANSI codes and check width
         let visible = streamdown_ansi::utils::visible(label_line);
-        let visible_width = unicode_width::UnicodeWidthStr::width(visible.as_str());
+        let visible_width = str_width(&visible);
 
         assert_eq!(
             visible_width, width,
@@ -0,0 +1,208 @@
+//! Collapsing long blocks to one-line summaries.
+//!
+//! With [`CollapseOptions`] set in [`RenderFeatures::collapse`], think blocks
+//! are held until they end and then drawn as a single summary line with an
+//! expansion hint (`▶ thinking (12 lines) [+]`), which keeps chat transcripts
+//! compact. Code blocks stream as usual up to the threshold; the lines past it
+//! are summarized after the block (`▶ rust code (39 more lines) [+]`).
+//! [`Renderer::expand`] draws a collapsed block in full on request.
+//!
+//! ```
+//! use streamdown_render::{CollapseOptions, RenderFeatures, Renderer};
+//! use streamdown_parser::Parser;
+//!
+//! let features = RenderFeatures {
+//!     collapse: CollapseOptions { think: true, code_over: Some(2) },
+//!     ..Default::default()
+//! };
+//! let mut renderer = Renderer::with_features(Vec::new(), 60, features);
+//! let mut parser = Parser::new();
+//! for line in ["```rust", "a();", "b();", "c();", "```"] {
+//!     for event in parser.parse_line(line) {
+//!         renderer.render_event(&event).unwrap();
+//!     }
+//! }
+//! let output = String::from_utf8(renderer.into_writer()).unwrap();
+//! assert!(output.contains("rust code (1 more line)"));
+//! ```
+//!
+//! [`RenderFeatures::collapse`]: crate::RenderFeatures::collapse
+//! [`Renderer::expand`]: crate::Renderer::expand
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use streamdown_parser::ParseEvent;
+
+use crate::processor::{BlockProcessor, ends_block};
+
+/// Which blocks to collapse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct CollapseOptions {
+    /// Collapse `<think>` blocks
+    pub think: bool,
+    /// Collapse code blocks with more than this many lines (None = never)
+    pub code_over: Option<usize>,
+}
+
+impl CollapseOptions {
+    /// Whether `event` starts a block that may be collapsed.
+    pub(crate) fn starts_collapsible(&self, event: &ParseEvent) -> bool {
+        match event {
+            ParseEvent::CodeBlockStart { .. } => self.code_over.is_some(),
+            ParseEvent::ThinkBlockStart => self.think,
+            _ => false,
+        }
+    }
+}
+
+/// A block held back until its end.
+#[derive(Debug, Clone)]
+pub(crate) struct HeldBlock {
+    /// The block's events, starting with its start event
+    pub(crate) events: Vec<ParseEvent>,
+    /// Processor drawing the block (None = collapsed or drawn as usual)
+    pub(crate) processor: Option<Arc<dyn BlockProcessor>>,
+    /// When holding started
+    pub(crate) since: Instant,
+}
+
+impl HeldBlock {
+    /// Start holding a block.
+    pub(crate) fn new(start: ParseEvent) -> Self {
+        Self {
+            events: vec![start],
+            processor: None,
+            since: Instant::now(),
+        }
+    }
+
+    /// Start holding a block for `processor`.
+    pub(crate) fn for_processor(start: ParseEvent, processor: Arc<dyn BlockProcessor>) -> Self {
+        Self {
+            events: vec![start],
+            processor: Some(processor),
+            since: Instant::now(),
+        }
+    }
+
+    /// Whether `event` ends the held block.
+    pub(crate) fn is_end(&self, event: &ParseEvent) -> bool {
+        match &self.processor {
+            Some(processor) => processor.is_end(&self.events[0], event),
+            None => ends_block(&self.events[0], event),
+        }
+    }
+
+    /// Number of content lines.
+    pub(crate) fn lines(&self) -> usize {
+        self.events
+            .iter()
+            .filter(|e| {
+                matches!(
+                    e,
+                    ParseEvent::CodeBlockLine(_) | ParseEvent::ThinkBlockLine(_)
+                )
+            })
+            .count()
+    }
+
+    /// Whether the block should be drawn as a summary.
+    pub(crate) fn collapses(&self, options: &CollapseOptions) -> bool {
+        match &self.events[0] {
+            ParseEvent::CodeBlockStart { .. } => {
+                options.code_over.is_some_and(|max| self.lines() > max)
+            }
+            _ => true,
+        }
+    }
+
+    /// Summary label, e.g. "rust code (42 lines)".
+    pub(crate) fn label(&self) -> String {
+        let lines = self.lines();
+        let plural = if lines == 1 { "" } else { "s" };
+        format!("{} ({} line{})", self.kind(), lines, plural)
+    }
+
+    /// Label for the lines past the first `shown`, e.g.
+    /// "rust code (39 more lines)".
+    pub(crate) fn hidden_label(&self, shown: usize) -> String {
+        let hidden = self.lines().saturating_sub(shown);
+        let plural = if hidden == 1 { "" } else { "s" };
+        format!("{} ({} more line{})", self.kind(), hidden, plural)
+    }
+
+    /// Kind of block, e.g. "rust code" or "thinking".
+    fn kind(&self) -> String {
+        let guessed = self.events.iter().find_map(|e| match e {
+            ParseEvent::CodeBlockLanguage(lang) => Some(lang),
+            _ => None,
+        });
+        match &self.events[0] {
+            ParseEvent::CodeBlockStart {
+                language: Some(lang),
+                ..
+            } => format!("{} code", lang),
+            ParseEvent::CodeBlockStart { .. } => match guessed {
+                Some(lang) => format!("{} code", lang),
+                None => "code".to_string(),
+            },
+            _ => "thinking".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code_block(lines: usize) -> HeldBlock {
+        let mut block = HeldBlock::new(ParseEvent::CodeBlockStart {
+            language: Some("rust".to_string()),
+            indent: 0,
+        });
+        for i in 0..lines {
+            block.events.push(ParseEvent::CodeBlockLine(i.to_string()));
+        }
+        block
+    }
+
+    #[test]
+    fn test_code_collapses_over_threshold() {
+        let options = CollapseOptions {
+            think: false,
+            code_over: Some(3),
+        };
+        assert!(!code_block(3).collapses(&options));
+        assert!(code_block(4).collapses(&options));
+        assert_eq!(code_block(4).label(), "rust code (4 lines)");
+        assert_eq!(code_block(4).hidden_label(3), "rust code (1 more line)");
+    }
+
+    #[test]
+    fn test_think_label() {
+        let mut block = HeldBlock::new(ParseEvent::ThinkBlockStart);
+        block
+            .events
+            .push(ParseEvent::ThinkBlockLine("hmm".to_string()));
+        assert!(block.is_end(&ParseEvent::ThinkBlockEnd));
+        assert!(!block.is_end(&ParseEvent::CodeBlockEnd));
+        assert_eq!(block.label(), "thinking (1 line)");
+    }
+
+    #[test]
+    fn test_starts_collapsible() {
+        let options = CollapseOptions::default();
+        assert!(!options.starts_collapsible(&ParseEvent::ThinkBlockStart));
+        let options = CollapseOptions {
+            think: true,
+            code_over: None,
+        };
+        assert!(options.starts_collapsible(&ParseEvent::ThinkBlockStart));
+    }
+}
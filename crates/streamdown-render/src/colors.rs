@@ -1,9 +1,12 @@
 //! Colodore color palette and resolution.
 //!
 //! Provides named color presets based on the Colodore palette
-//! (Commodore 64/128 inspired colors by Pepto).
+//! (Commodore 64/128 inspired colors by Pepto), the [`Color`] type style
+//! colors parse into, and swatches for hex color literals.
 
 use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
 use std::sync::LazyLock;
 
 /// Colodore color palette - Commodore 64/128 inspired colors.
@@ -38,6 +41,174 @@ pub fn resolve_color(color: &str) -> &str {
     COLODORE.get(color).copied().unwrap_or(color)
 }
 
+/// Names of the 16 basic terminal colors, by palette index.
+pub const ANSI_COLOR_NAMES: [&str; 16] = [
+    "black",
+    "red",
+    "green",
+    "yellow",
+    "blue",
+    "magenta",
+    "cyan",
+    "white",
+    "bright_black",
+    "bright_red",
+    "bright_green",
+    "bright_yellow",
+    "bright_blue",
+    "bright_magenta",
+    "bright_cyan",
+    "bright_white",
+];
+
+/// A style color.
+///
+/// Parsed from one of:
+/// - a Colodore preset name (`"yellow"`) or `#rrggbb`, giving [`Color::Rgb`]
+/// - a basic terminal color name (`"magenta"`, `"bright_blue"`), giving
+///   [`Color::Ansi16`]; where a name is also a preset, the preset wins
+/// - `ansi:NAME` with a basic terminal color name (`"ansi:red"`), giving
+///   [`Color::Ansi16`] even for names that are also presets
+/// - `ansi256:N` with N in 0-255, giving [`Color::Ansi256`]
+///
+/// The palette forms let a theme target 16- and 256-color terminals
+/// directly instead of relying on downgrading.
+///
+/// ```
+/// use streamdown_render::Color;
+///
+/// assert_eq!("#ff8000".parse(), Ok(Color::Rgb(255, 128, 0)));
+/// assert_eq!("bright_blue".parse(), Ok(Color::Ansi16(12)));
+/// assert_eq!("ansi:red".parse(), Ok(Color::Ansi16(1)));
+/// assert_eq!(Color::Ansi16(1).fg(), "\x1b[31m");
+/// assert_eq!("ansi256:203".parse(), Ok(Color::Ansi256(203)));
+/// assert_eq!(Color::Ansi256(203).fg(), "\x1b[38;5;203m");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Color {
+    /// 24-bit color
+    Rgb(u8, u8, u8),
+    /// One of the 16 basic colors (0-7 normal, 8-15 bright)
+    Ansi16(u8),
+    /// Index into the xterm 256-color palette
+    Ansi256(u8),
+}
+
+impl Color {
+    /// Parse a color string, or None if it isn't a color.
+    pub fn parse(color: &str) -> Option<Self> {
+        if let Some(index) = color.strip_prefix("ansi256:") {
+            return index.parse().ok().map(Color::Ansi256);
+        }
+        if let Some(name) = color.strip_prefix("ansi:") {
+            return ansi16(name);
+        }
+        let hex = resolve_color(color);
+        if hex.starts_with('#') {
+            let (r, g, b) = parse_hex_literal(hex).filter(|_| hex.len() == 7)?;
+            return Some(Color::Rgb(r, g, b));
+        }
+        ansi16(color)
+    }
+
+    /// Escape sequence selecting this color as the foreground.
+    pub fn fg(self) -> String {
+        match self {
+            Color::Rgb(r, g, b) => format!("\x1b[38;2;{};{};{}m", r, g, b),
+            Color::Ansi16(n @ 0..=7) => format!("\x1b[{}m", 30 + n),
+            Color::Ansi16(n) => format!("\x1b[{}m", 90 + (n & 7)),
+            Color::Ansi256(n) => format!("\x1b[38;5;{}m", n),
+        }
+    }
+
+    /// Escape sequence selecting this color as the background.
+    pub fn bg(self) -> String {
+        match self {
+            Color::Rgb(r, g, b) => format!("\x1b[48;2;{};{};{}m", r, g, b),
+            Color::Ansi16(n @ 0..=7) => format!("\x1b[{}m", 40 + n),
+            Color::Ansi16(n) => format!("\x1b[{}m", 100 + (n & 7)),
+            Color::Ansi256(n) => format!("\x1b[48;5;{}m", n),
+        }
+    }
+
+    /// Escape sequence selecting this color for underlines (ignored by
+    /// terminals without colored underlines).
+    pub fn underline(self) -> String {
+        match self {
+            Color::Rgb(r, g, b) => format!("\x1b[58;2;{};{};{}m", r, g, b),
+            Color::Ansi16(n) | Color::Ansi256(n) => format!("\x1b[58;5;{}m", n),
+        }
+    }
+}
+
+/// The basic terminal color called `name`.
+fn ansi16(name: &str) -> Option<Color> {
+    ANSI_COLOR_NAMES
+        .iter()
+        .position(|&known| known == name)
+        .map(|index| Color::Ansi16(index as u8))
+}
+
+/// Error returned when a string is not a [`Color`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseColorError(pub String);
+
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid color {:?} (expected a color name, #rrggbb, ansi:NAME or ansi256:N)",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ParseColorError {}
+
+impl FromStr for Color {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Color::parse(s).ok_or_else(|| ParseColorError(s.to_string()))
+    }
+}
+
+impl fmt::Display for Color {
+    /// Canonical string form, which parses back to a color that renders the
+    /// same.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Color::Rgb(r, g, b) => write!(f, "#{:02x}{:02x}{:02x}", r, g, b),
+            Color::Ansi16(n) => match ANSI_COLOR_NAMES.get(usize::from(n)) {
+                // Names shadowed by a preset need the namespace
+                Some(name) if COLODORE.contains_key(name) => write!(f, "ansi:{}", name),
+                Some(name) => f.write_str(name),
+                None => write!(f, "ansi256:{}", n),
+            },
+            Color::Ansi256(n) => write!(f, "ansi256:{}", n),
+        }
+    }
+}
+
+/// Glyphs drawn in a swatch's color.
+pub const SWATCH: &str = "██";
+
+/// Parse a `#rrggbb` color literal, ignoring surrounding whitespace.
+pub fn parse_hex_literal(text: &str) -> Option<(u8, u8, u8)> {
+    let hex = text.trim().strip_prefix('#')?;
+    if hex.len() != 6 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// A [`SWATCH`] in the color of a `#rrggbb` literal, or None for other text.
+pub fn swatch(text: &str) -> Option<String> {
+    let (r, g, b) = parse_hex_literal(text)?;
+    Some(format!("\x1b[38;2;{};{};{}m{}\x1b[39m", r, g, b, SWATCH))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -60,6 +231,54 @@ mod tests {
         assert_eq!(resolve_color("not_a_color"), "not_a_color");
     }
 
+    #[test]
+    fn test_swatch() {
+        assert_eq!(parse_hex_literal(" #FF8000 "), Some((255, 128, 0)));
+        assert_eq!(parse_hex_literal("#fff"), None);
+        assert_eq!(parse_hex_literal("#gg0000"), None);
+        assert_eq!(swatch("#010203").unwrap(), "\x1b[38;2;1;2;3m██\x1b[39m");
+        assert!(swatch("red").is_none());
+    }
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(Color::parse("#0a0B0c"), Some(Color::Rgb(10, 11, 12)));
+        assert_eq!(Color::parse("yellow"), Some(Color::Rgb(0xed, 0xf1, 0x71)));
+        assert_eq!(Color::parse("magenta"), Some(Color::Ansi16(5)));
+        assert_eq!(Color::parse("bright_white"), Some(Color::Ansi16(15)));
+        assert_eq!(Color::parse("ansi:red"), Some(Color::Ansi16(1)));
+        assert_eq!(Color::parse("ansi:bright_red"), Some(Color::Ansi16(9)));
+        assert_eq!(Color::parse("ansi:purple"), None);
+        assert_eq!(Color::parse("ansi256:0"), Some(Color::Ansi256(0)));
+        assert_eq!(Color::parse("ansi256:256"), None);
+        assert_eq!(Color::parse("#fff"), None);
+        assert_eq!(Color::parse(" #ffffff"), None);
+        assert_eq!(Color::parse("gray"), None);
+        assert!("ansi256:x".parse::<Color>().is_err());
+    }
+
+    #[test]
+    fn test_color_escapes_and_display() {
+        assert_eq!(Color::Ansi16(1).fg(), "\x1b[31m");
+        assert_eq!(Color::Ansi16(12).fg(), "\x1b[94m");
+        assert_eq!(Color::Ansi16(12).bg(), "\x1b[104m");
+        assert_eq!(Color::Ansi256(17).bg(), "\x1b[48;5;17m");
+        assert_eq!(Color::Rgb(1, 2, 3).bg(), "\x1b[48;2;1;2;3m");
+
+        for color in [
+            Color::Rgb(255, 0, 16),
+            Color::Ansi16(1),
+            Color::Ansi16(5),
+            Color::Ansi16(13),
+            Color::Ansi256(203),
+        ] {
+            assert_eq!(color.to_string().parse(), Ok(color));
+        }
+        assert_eq!(Color::Ansi16(13).to_string(), "bright_magenta");
+        // "red" is a preset, so basic red is written with the namespace
+        assert_eq!(Color::Ansi16(1).to_string(), "ansi:red");
+    }
+
     #[test]
     fn test_all_colodore_colors() {
         assert_eq!(COLODORE.len(), 16);
@@ -0,0 +1,92 @@
+//! Copy mode.
+//!
+//! With [`RenderFeatures::copy_mode`](crate::RenderFeatures::copy_mode) the
+//! output is drawn so that selecting it in the terminal copies what was
+//! written: lines carry no trailing padding, code lines start at column 0
+//! without line numbers, wrap markers or block-quote borders, and code
+//! blocks have no half-block frame. Code sent through the raw-content
+//! channels (OSC 52 clipboard, copy links, savebrace) is re-fenced, so a
+//! pasted block keeps its language. The mode can be switched while
+//! streaming with [`Renderer::set_copy_mode`](crate::Renderer::set_copy_mode).
+//!
+//! ```
+//! use streamdown_render::copy::{fence, trim_padding};
+//!
+//! assert_eq!(trim_padding("\x1b[40mcode   \x1b[0m"), "\x1b[40mcode\x1b[0m");
+//! assert_eq!(fence("let a = 1;", Some("rust")), "```rust\nlet a = 1;\n```");
+//! ```
+
+/// Remove the spaces at the end of each line of `line`, keeping the
+/// escape codes that follow them.
+pub fn trim_padding(line: &str) -> String {
+    line.split('\n')
+        .map(trim_line)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Remove trailing spaces from one line, keeping trailing SGR codes.
+fn trim_line(line: &str) -> String {
+    let mut rest = line;
+    let mut codes = Vec::new();
+    loop {
+        if let Some(trimmed) = rest.strip_suffix(' ') {
+            rest = trimmed;
+        } else if let Some(start) = trailing_csi(rest) {
+            codes.push(&rest[start..]);
+            rest = &rest[..start];
+        } else {
+            break;
+        }
+    }
+    let mut trimmed = rest.to_string();
+    trimmed.extend(codes.into_iter().rev());
+    trimmed
+}
+
+/// Start of a CSI sequence (`ESC [ params final`) ending `text`.
+fn trailing_csi(text: &str) -> Option<usize> {
+    let start = text.rfind("\x1b[")?;
+    let body = &text.as_bytes()[start + 2..];
+    let (&last, params) = body.split_last()?;
+    let valid =
+        params.iter().all(|&b| b.is_ascii_digit() || b == b';') && (0x40..=0x7e).contains(&last);
+    valid.then_some(start)
+}
+
+/// Wrap `code` in a fenced code block tagged with `language`, using a fence
+/// longer than any run of backticks in the code.
+pub fn fence(code: &str, language: Option<&str>) -> String {
+    let longest = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!("{}{}\n{}\n{}", fence, language.unwrap_or(""), code, fence)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trim_padding() {
+        assert_eq!(trim_padding("text  "), "text");
+        assert_eq!(
+            trim_padding("\x1b[48;5;0m  a b \x1b[48;5;0m    \x1b[0m\nc  "),
+            "\x1b[48;5;0m  a b\x1b[48;5;0m\x1b[0m\nc"
+        );
+        // Other escapes end the trimming
+        assert_eq!(trim_padding("a \x1b]8;;\x1b\\"), "a \x1b]8;;\x1b\\");
+        assert_eq!(trim_padding("   "), "");
+        // Text after the last escape may end in any character
+        assert_eq!(trim_padding("\x1b[1mcafé \x1b[0m"), "\x1b[1mcafé\x1b[0m");
+        assert_eq!(trim_padding("\x1b[2m──── "), "\x1b[2m────");
+    }
+
+    #[test]
+    fn test_fence() {
+        assert_eq!(fence("a", None), "```\na\n```");
+        assert_eq!(
+            fence("```md\nx\n```", Some("md")),
+            "````md\n```md\nx\n```\n````"
+        );
+    }
+}
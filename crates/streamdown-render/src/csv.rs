@@ -0,0 +1,287 @@
+//! Tables for ```` ```csv ```` and ```` ```tsv ```` fences.
+//!
+//! [`CsvHandler`] parses delimited records (with RFC 4180 quoting) and draws
+//! them with the table renderer, so data snippets come out as aligned
+//! columns; body cells are shown as written, with numbers right-aligned.
+//! Whether the first record is a header is guessed from the
+//! columns, as spreadsheet importers do. The handler is opt-in: register it
+//! with [`Renderer::register_fence`](crate::Renderer::register_fence).
+//! Fences that aren't tabular (a single column, or rows of very different
+//! lengths) are drawn as code.
+//!
+//! ```
+//! use streamdown_render::csv::{has_header, parse_records};
+//!
+//! let records = parse_records("name,qty\n\"Smith, J\",3\n", ',');
+//! assert_eq!(records[1], ["Smith, J", "3"]);
+//! assert!(has_header(&records));
+//! ```
+
+use std::sync::Arc;
+
+use crate::RenderStyle;
+use crate::cells::{CellAlign, CellFormatter, FormattedCell};
+use crate::fence::FenceHandler;
+use crate::table::{TableState, render_table_row, render_table_separator};
+
+/// Split `text` into records of fields separated by `delimiter`. Fields may
+/// be quoted with `"`, with `""` for a quote inside; quoted fields may span
+/// lines. Blank lines are skipped.
+pub fn parse_records(text: &str, delimiter: char) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    // Whether the field started with a quote (so an empty one still counts)
+    let mut was_quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    field.push('"');
+                    chars.next();
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() && !was_quoted => {
+                quoted = true;
+                was_quoted = true;
+            }
+            c if quoted => field.push(c),
+            c if c == delimiter => {
+                record.push(std::mem::take(&mut field));
+                was_quoted = false;
+            }
+            '\r' => {}
+            '\n' => {
+                if !field.is_empty() || was_quoted || !record.is_empty() {
+                    record.push(std::mem::take(&mut field));
+                    records.push(std::mem::take(&mut record));
+                }
+                was_quoted = false;
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || was_quoted || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+/// Whether a field reads as a number (`-1,200.5`, `42%`, `$3`).
+fn is_numeric(field: &str) -> bool {
+    let trimmed = field
+        .trim()
+        .trim_start_matches(['$', '€', '£'])
+        .trim_end_matches('%')
+        .replace(',', "");
+    !trimmed.is_empty() && trimmed.parse::<f64>().is_ok()
+}
+
+/// Guess whether the first of `records` is a header. Each column votes:
+/// a column of numbers under a non-number, or of equally long values
+/// under one of another length, says yes; the opposite says no. Without a
+/// verdict, a first row of distinct non-numeric labels is a header.
+pub fn has_header(records: &[Vec<String>]) -> bool {
+    let Some((first, body)) = records.split_first() else {
+        return false;
+    };
+    if body.is_empty() {
+        return false;
+    }
+    let mut votes = 0i32;
+    for (column, label) in first.iter().enumerate() {
+        let values: Vec<&str> = body
+            .iter()
+            .filter_map(|record| record.get(column).map(String::as_str))
+            .filter(|value| !value.is_empty())
+            .collect();
+        if values.is_empty() {
+            continue;
+        }
+        if values.iter().all(|value| is_numeric(value)) {
+            votes += if is_numeric(label) { -1 } else { 1 };
+            continue;
+        }
+        let length = values[0].chars().count();
+        if values.iter().all(|value| value.chars().count() == length) {
+            votes += if label.chars().count() == length {
+                -1
+            } else {
+                1
+            };
+        }
+    }
+    if votes != 0 {
+        return votes > 0;
+    }
+    let distinct = first
+        .iter()
+        .enumerate()
+        .all(|(i, label)| !first[..i].contains(label));
+    distinct
+        && first
+            .iter()
+            .all(|label| !label.trim().is_empty() && !is_numeric(label))
+}
+
+/// Draw `records` as a table within `width` columns, or None if they
+/// don't look tabular.
+pub fn render_records(
+    records: &[Vec<String>],
+    width: usize,
+    style: &RenderStyle,
+) -> Option<Vec<String>> {
+    let columns = records.iter().map(Vec::len).max()?;
+    // Rows may drop trailing empty fields, but not most of the row
+    let tabular = columns > 1 && records.iter().all(|record| record.len() * 2 > columns);
+    if !tabular {
+        return None;
+    }
+    let rows: Vec<Vec<String>> = records
+        .iter()
+        .map(|record| {
+            let mut row: Vec<String> = record
+                .iter()
+                .map(|field| field.trim().replace('\n', " "))
+                .collect();
+            row.resize(columns, String::new());
+            row
+        })
+        .collect();
+    let header = has_header(records);
+    let mut state = TableState::new();
+    state.formatter = Some(Arc::new(Verbatim));
+    state.calculate_widths_for(&rows[0], width);
+    for row in &rows[1..] {
+        state.widen(row, width);
+    }
+    state.is_header = header;
+    let mut lines = Vec::new();
+    for (i, row) in rows.iter().enumerate() {
+        lines.extend(render_table_row(row, &mut state, width, "", style, false));
+        if i == 0 && header {
+            lines.push(render_table_separator(&state, width, "", style));
+            state.end_header();
+        }
+    }
+    Some(lines)
+}
+
+/// Draws body cells as written, numbers right-aligned.
+#[derive(Debug)]
+struct Verbatim;
+
+impl CellFormatter for Verbatim {
+    fn format(&self, cell: &str) -> Option<FormattedCell> {
+        let align = if is_numeric(cell) {
+            CellAlign::Right
+        } else {
+            CellAlign::Left
+        };
+        Some(FormattedCell {
+            text: cell.to_string(),
+            align,
+        })
+    }
+}
+
+/// Handler for delimited data fences.
+#[derive(Debug, Clone, Copy)]
+pub struct CsvHandler {
+    delimiter: char,
+}
+
+impl CsvHandler {
+    /// Handler for comma-separated values.
+    pub fn csv() -> Self {
+        Self { delimiter: ',' }
+    }
+
+    /// Handler for tab-separated values.
+    pub fn tsv() -> Self {
+        Self { delimiter: '\t' }
+    }
+}
+
+impl FenceHandler for CsvHandler {
+    fn render(&self, text: &str, width: usize, style: &RenderStyle) -> Option<Vec<String>> {
+        render_records(&parse_records(text, self.delimiter), width, style)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use streamdown_ansi::utils::visible;
+
+    fn records(rows: &[&[&str]]) -> Vec<Vec<String>> {
+        rows.iter()
+            .map(|row| row.iter().map(|s| s.to_string()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_records() {
+        assert_eq!(
+            parse_records("a,\"b \"\"c\"\"\",\n\n\"multi\nline\",,x\r\n", ','),
+            records(&[&["a", "b \"c\"", ""], &["multi\nline", "", "x"]])
+        );
+        assert_eq!(parse_records("a\tb", '\t'), records(&[&["a", "b"]]));
+        assert!(parse_records("", ',').is_empty());
+    }
+
+    #[test]
+    fn test_has_header() {
+        assert!(has_header(&records(&[
+            &["city", "pop"],
+            &["Oslo", "709000"]
+        ])));
+        assert!(!has_header(&records(&[&["1", "2"], &["3", "4"]])));
+        // Codes of equal length under a longer label
+        assert!(has_header(&records(&[&["code"], &["NO"], &["SE"]])));
+        // No votes: distinct labels read as a header
+        assert!(has_header(&records(&[
+            &["first", "last"],
+            &["Ada", "Lovelace"],
+            &["Alan", "Turing"]
+        ])));
+        assert!(!has_header(&records(&[
+            &["a", "a"],
+            &["Ada", "Lovelace"],
+            &["Al", "Turing"]
+        ])));
+        assert!(!has_header(&records(&[&["only", "row"]])));
+    }
+
+    #[test]
+    fn test_render_records() {
+        let style = RenderStyle::default();
+        let lines = CsvHandler::csv()
+            .render("name,qty\nwidget_a,3\n*b*,12", 30, &style)
+            .unwrap();
+        let lines: Vec<String> = lines.iter().map(|line| visible(line)).collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with(" name"));
+        assert!(lines[1].chars().all(|c| c == '─'));
+        // Body cells are shown as written, numbers right-aligned
+        assert!(lines[2].starts_with(" widget_a "));
+        assert!(lines[3].starts_with(" *b* "));
+        assert!(lines[3].trim_end().ends_with("12"));
+
+        // Headerless data has no rule
+        let lines = CsvHandler::tsv().render("1\t2\n3\t4", 30, &style).unwrap();
+        assert_eq!(lines.len(), 2);
+        // Not tabular
+        assert!(
+            CsvHandler::csv()
+                .render("just\nlines", 30, &style)
+                .is_none()
+        );
+        assert!(CsvHandler::csv().render("a,b,c,d\nx", 30, &style).is_none());
+    }
+}
@@ -0,0 +1,131 @@
+//! Render error type.
+//!
+//! [`RenderError`] separates layout and configuration problems, which an
+//! embedder can recover from (pick a valid color, give the renderer more
+//! room), from failures of the underlying writer.
+//!
+//! It converts to and from `io::Error`, so code written against
+//! `io::Result` keeps working with `?`; the original [`RenderError`] can be
+//! recovered from the `io::Error` with [`RenderError::from_io`].
+
+use std::io;
+
+use streamdown_core::StreamdownError;
+use thiserror::Error;
+
+/// Narrowest output the renderer lays out, in columns.
+pub const MIN_RENDER_WIDTH: usize = 4;
+
+/// Error produced while rendering.
+#[derive(Error, Debug)]
+pub enum RenderError {
+    /// The writer failed
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    /// A style color is not a color name, `#rrggbb` or `ansi256:N`
+    #[error("invalid color for `{key}`: {value:?} (expected a color name, #rrggbb or ansi256:N)")]
+    InvalidColor {
+        /// Style key holding the color
+        key: String,
+        /// The rejected value
+        value: String,
+    },
+
+    /// The output is too narrow to lay out
+    #[error("width {width} is too small (at least {minimum} columns needed)")]
+    WidthTooSmall {
+        /// Width available
+        width: usize,
+        /// Width needed
+        minimum: usize,
+    },
+
+    /// A syntax theme file cannot be loaded
+    #[error("{0}")]
+    InvalidTheme(String),
+
+    /// The document has no link with this number
+    #[error("no link {0}")]
+    NoSuchLink(usize),
+
+    /// The policy does not allow opening this URL
+    #[error("policy does not allow opening {0}")]
+    LinkNotAllowed(String),
+}
+
+/// Result type alias for rendering operations.
+pub type RenderResult<T> = std::result::Result<T, RenderError>;
+
+impl RenderError {
+    /// Whether the error is a layout or configuration problem rather than a
+    /// failed write; rendering can continue after fixing the cause.
+    pub fn is_recoverable(&self) -> bool {
+        !matches!(self, RenderError::Io(_))
+    }
+
+    /// Recover the render error carried by an `io::Error`, if any.
+    pub fn from_io(error: io::Error) -> Self {
+        if error
+            .get_ref()
+            .is_some_and(|inner| inner.is::<RenderError>())
+        {
+            let inner = error.into_inner().expect("checked above");
+            return *inner.downcast::<RenderError>().expect("checked above");
+        }
+        RenderError::Io(error)
+    }
+}
+
+impl From<RenderError> for io::Error {
+    fn from(error: RenderError) -> Self {
+        match error {
+            RenderError::Io(e) => e,
+            RenderError::InvalidColor { .. }
+            | RenderError::WidthTooSmall { .. }
+            | RenderError::InvalidTheme(_) => io::Error::new(io::ErrorKind::InvalidInput, error),
+            RenderError::NoSuchLink(_) => io::Error::new(io::ErrorKind::NotFound, error),
+            RenderError::LinkNotAllowed(_) => {
+                io::Error::new(io::ErrorKind::PermissionDenied, error)
+            }
+        }
+    }
+}
+
+impl From<RenderError> for StreamdownError {
+    fn from(error: RenderError) -> Self {
+        match error {
+            RenderError::Io(e) => StreamdownError::Io(e),
+            RenderError::InvalidColor { .. } | RenderError::InvalidTheme(_) => {
+                StreamdownError::Config(error.to_string())
+            }
+            other => StreamdownError::Render(other.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_io_roundtrip() {
+        let error = RenderError::WidthTooSmall {
+            width: 2,
+            minimum: MIN_RENDER_WIDTH,
+        };
+        assert!(error.is_recoverable());
+
+        let io_error: io::Error = error.into();
+        assert_eq!(io_error.kind(), io::ErrorKind::InvalidInput);
+        assert!(matches!(
+            RenderError::from_io(io_error),
+            RenderError::WidthTooSmall { width: 2, .. }
+        ));
+
+        let write = RenderError::from_io(io::Error::from(io::ErrorKind::BrokenPipe));
+        assert!(!write.is_recoverable());
+        let io_error: io::Error = write.into();
+        assert_eq!(io_error.kind(), io::ErrorKind::BrokenPipe);
+    }
+}
@@ -4,11 +4,15 @@
 //!
 //! - **Clipboard (OSC 52)**: Copy code blocks to system clipboard via terminal
 //! - **Savebrace**: Save code blocks to a temp file for shell access
+//! - **Copy links**: Clickable OSC 8 "copy" anchors that open a code block
 //! - **Terminal size**: Dynamic terminal width detection
 
+use crate::{BorderStyle, CollapseOptions, ControlChars, TextAlign};
+use std::fmt;
 use std::fs::OpenOptions;
 use std::io::{self, Write};
 use std::path::PathBuf;
+use std::str::FromStr;
 
 /// OSC 52 clipboard operation.
 ///
@@ -151,41 +155,314 @@ pub fn savebrace_last() -> io::Result<Option<String>> {
     Ok(blocks.into_iter().last())
 }
 
+/// Save a code block to its own file for a copy link.
+///
+/// Files live in a `sd-snippets` temp directory and are named by content
+/// hash, so re-rendering the same block reuses its file.
+pub fn save_snippet(code: &str) -> io::Result<PathBuf> {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let dir = snippet_dir();
+    std::fs::create_dir_all(&dir)?;
+
+    let mut hasher = DefaultHasher::new();
+    code.hash(&mut hasher);
+    let path = dir.join(format!("{:016x}.txt", hasher.finish()));
+
+    // Security: same symlink check as savebrace
+    if let Ok(meta) = std::fs::symlink_metadata(&path)
+        && meta.file_type().is_symlink()
+    {
+        return Err(io::Error::other("Refusing to write to symlink"));
+    }
+
+    std::fs::write(&path, code)?;
+    Ok(path)
+}
+
+/// Get the directory copy-link snippets are saved to.
+pub fn snippet_dir() -> PathBuf {
+    std::env::temp_dir().join("sd-snippets")
+}
+
+/// Format a clickable OSC 8 anchor labelled `label` that opens `path`.
+pub fn file_link(path: &std::path::Path, label: &str) -> String {
+    format!(
+        "\x1b]8;;file://{}\x1b\\{}\x1b]8;;\x1b\\",
+        percent_encode_path(&path.to_string_lossy()),
+        label
+    )
+}
+
+/// Percent-encode `path` for a URL, keeping `/` and unreserved characters.
+fn percent_encode_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-._~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// Where link URLs are shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum LinkStyle {
+    /// In parentheses after the link text
+    #[default]
+    Inline,
+    /// As a numbered list at the end, referenced by superscript indices
+    Endnotes,
+}
+
+/// How code lines redrawn with carriage returns (progress bars) are shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum ProgressLines {
+    /// Only the final state
+    #[default]
+    Collapse,
+    /// Every state, each redrawn over the previous one on the same row
+    InPlace,
+}
+
+/// What happens to code lines wider than their block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum CodeOverflow {
+    /// Wrap with `pretty_broken`, truncate next to a line number gutter,
+    /// and otherwise leave the line to the terminal
+    #[default]
+    Auto,
+    /// Soft-wrap inside the block, marking each broken row with `↩`
+    Wrap,
+    /// Cut at the block edge with `…`
+    Truncate,
+    /// Leave the line to the terminal, past the block edge
+    Overflow,
+}
+
+impl CodeOverflow {
+    /// All policies, in declaration order.
+    pub const ALL: [CodeOverflow; 4] = [
+        CodeOverflow::Auto,
+        CodeOverflow::Wrap,
+        CodeOverflow::Truncate,
+        CodeOverflow::Overflow,
+    ];
+
+    /// Name used on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            CodeOverflow::Auto => "auto",
+            CodeOverflow::Wrap => "wrap",
+            CodeOverflow::Truncate => "truncate",
+            CodeOverflow::Overflow => "overflow",
+        }
+    }
+}
+
+impl fmt::Display for CodeOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for CodeOverflow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        CodeOverflow::ALL
+            .into_iter()
+            .find(|policy| policy.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                format!(
+                    "unknown code overflow policy '{}' (expected auto, wrap, truncate or overflow)",
+                    s
+                )
+            })
+    }
+}
+
 /// Feature flags for rendering.
-#[derive(Debug, Clone)]
+///
+/// With the `serde` feature, features can be saved and loaded (e.g. as user
+/// layout preferences); missing fields take their defaults.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 pub struct RenderFeatures {
     /// Use ▄/▀ characters for code block borders (pretty but may not copy well)
     pub pretty_pad: bool,
 
+    /// Glyph set for code block, table, blockquote and think block borders
+    pub border_style: BorderStyle,
+
     /// Wrap long code lines visually (pretty but breaks copy-paste)
     pub pretty_broken: bool,
 
+    /// What happens to code lines wider than their block
+    pub code_overflow: CodeOverflow,
+
     /// Enable clipboard integration (OSC 52)
     pub clipboard: bool,
 
     /// Enable savebrace (save code to temp file)
     pub savebrace: bool,
 
+    /// Add a clickable OSC 8 "copy" link under each code block
+    pub copy_link: bool,
+
+    /// Copy mode: no trailing padding, code lines at column 0 without
+    /// decorations, and re-fenced code in the clipboard, copy links and
+    /// savebrace (see [`crate::copy`])
+    pub copy_mode: bool,
+
+    /// Syntax-highlight fenced code blocks with a known language
+    pub highlight: bool,
+
+    /// Keep the color codes of pre-colored code lines (captured terminal
+    /// output) instead of stripping them; such lines aren't highlighted
+    pub ansi_passthrough: bool,
+
+    /// Show line numbers in a gutter inside code blocks
+    pub line_numbers: bool,
+
+    /// Collapse `<details>` blocks and conceal spoilers (interactive hosts);
+    /// streaming output shows them expanded with a marked summary. Details
+    /// blocks are raw HTML, so they only collapse when the policy allows it
+    /// ([`Policy::raw_html`](crate::Policy::raw_html)); untrusted content
+    /// can't hide text that way
+    pub collapse_details: bool,
+
+    /// Where link URLs are shown
+    pub link_style: LinkStyle,
+
+    /// Draw a QR code of each link URL after the line it is on
+    pub qr_links: bool,
+
+    /// Mark each link with its number for
+    /// [`Renderer::open_link`](crate::Renderer::open_link), e.g. `docs[1]`
+    pub numbered_links: bool,
+
+    /// Add a color swatch after inline code that is a `#rrggbb` literal
+    pub color_swatches: bool,
+
+    /// Blocks drawn as one-line summaries (think blocks, long code blocks)
+    pub collapse: CollapseOptions,
+
+    /// Draw only the document outline: headings, the first line of each
+    /// section and a summary line per code block
+    pub outline: bool,
+
+    /// How carriage-return progress lines in code blocks are shown
+    pub progress_lines: ProgressLines,
+
+    /// Paint the line being received as it arrives, before it is rendered
+    pub partial_lines: bool,
+
+    /// Repaint a streaming table in place when a row needs wider columns,
+    /// and a code block when its language is detected after its first
+    /// lines. Needs the terminal height (see [`Renderer::set_height`]);
+    /// blocks whose start has scrolled off screen are drawn on by appending
+    ///
+    /// [`Renderer::set_height`]: crate::Renderer::set_height
+    pub live_blocks: bool,
+
     /// Allow terminal to handle line wrapping
     pub width_wrap: bool,
 
+    /// Placement of paragraph lines in the content column; anything but
+    /// left alignment has the renderer break paragraph lines itself
+    pub paragraph_align: TextAlign,
+
+    /// Put right-to-left text (Arabic, Hebrew) in paragraphs, headings,
+    /// list items and block quotes into visual order, for terminals that
+    /// don't; needs the `bidi` cargo feature (see `crate::bidi`)
+    pub bidi: bool,
+
+    /// Break words wider than the line with a hyphen instead of letting
+    /// them overflow (see [`Renderer::set_hyphenator`](crate::Renderer::set_hyphenator))
+    pub hyphenate: bool,
+
     /// Fixed width (None = auto-detect from terminal)
     pub fixed_width: Option<usize>,
 
     /// Margin on each side
     pub margin: usize,
+
+    /// Blank columns before the content column
+    pub left_margin: usize,
+
+    /// Columns kept free after the content column
+    pub right_margin: usize,
+
+    /// Cap on the content column width; a narrower column is centered
+    /// (None = use the full width)
+    pub max_width: Option<usize>,
+
+    /// Write plain text: Unicode structure only, no escape sequences
+    pub plain: bool,
+
+    /// Whether control characters in the source are removed or shown as
+    /// symbols (see [`crate::sanitize`])
+    pub control_chars: ControlChars,
 }
 
 impl Default for RenderFeatures {
     fn default() -> Self {
         Self {
             pretty_pad: true,
+            border_style: BorderStyle::default(),
             pretty_broken: false,
+            code_overflow: CodeOverflow::default(),
             clipboard: false,
             savebrace: false,
+            copy_link: false,
+            copy_mode: false,
+            highlight: true,
+            ansi_passthrough: false,
+            line_numbers: false,
+            collapse_details: false,
+            collapse: CollapseOptions::default(),
+            outline: false,
+            link_style: LinkStyle::default(),
+            qr_links: false,
+            numbered_links: false,
+            color_swatches: false,
+            progress_lines: ProgressLines::default(),
+            partial_lines: false,
+            live_blocks: false,
             width_wrap: true,
+            paragraph_align: TextAlign::default(),
+            bidi: false,
+            hyphenate: false,
             fixed_width: None,
             margin: 1,
+            left_margin: 0,
+            right_margin: 0,
+            max_width: None,
+            plain: false,
+            control_chars: ControlChars::default(),
         }
     }
 }
@@ -209,6 +486,30 @@ impl RenderFeatures {
         }
     }
 
+    /// Create features for plain text output (logs, pipes into tools
+    /// that don't understand ANSI).
+    pub fn plain() -> Self {
+        Self {
+            plain: true,
+            pretty_pad: false,
+            highlight: false,
+            ..Default::default()
+        }
+    }
+
+    /// The code overflow policy in effect, with
+    /// [`Auto`](CodeOverflow::Auto) resolved; copy mode always leaves long
+    /// lines whole.
+    pub fn code_overflow_policy(&self) -> CodeOverflow {
+        match self.code_overflow {
+            _ if self.copy_mode => CodeOverflow::Overflow,
+            CodeOverflow::Auto if self.pretty_broken => CodeOverflow::Wrap,
+            CodeOverflow::Auto if self.line_numbers => CodeOverflow::Truncate,
+            CodeOverflow::Auto => CodeOverflow::Overflow,
+            policy => policy,
+        }
+    }
+
     /// Calculate the effective width.
     pub fn effective_width(&self) -> usize {
         let base = self.fixed_width.unwrap_or_else(terminal_width);
@@ -239,6 +540,22 @@ mod tests {
         assert!(width > 0);
     }
 
+    #[test]
+    fn test_code_overflow_policy() {
+        let mut features = RenderFeatures::default();
+        assert_eq!(features.code_overflow_policy(), CodeOverflow::Overflow);
+        features.line_numbers = true;
+        assert_eq!(features.code_overflow_policy(), CodeOverflow::Truncate);
+        features.pretty_broken = true;
+        assert_eq!(features.code_overflow_policy(), CodeOverflow::Wrap);
+        features.code_overflow = CodeOverflow::Truncate;
+        assert_eq!(features.code_overflow_policy(), CodeOverflow::Truncate);
+        features.copy_mode = true;
+        assert_eq!(features.code_overflow_policy(), CodeOverflow::Overflow);
+        assert_eq!("WRAP".parse::<CodeOverflow>(), Ok(CodeOverflow::Wrap));
+        assert!("fold".parse::<CodeOverflow>().is_err());
+    }
+
     #[test]
     fn test_is_tty() {
         // In test environment, this might be false
@@ -307,6 +624,22 @@ mod tests {
         savebrace_clear().unwrap();
     }
 
+    #[test]
+    fn test_save_snippet() {
+        let path = save_snippet("echo snippet").unwrap();
+        assert!(path.starts_with(snippet_dir()));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "echo snippet");
+        // Same content, same file
+        assert_eq!(save_snippet("echo snippet").unwrap(), path);
+
+        let link = file_link(&path, "copy");
+        assert!(link.starts_with("\x1b]8;;file://"));
+        assert!(link.ends_with("copy\x1b]8;;\x1b\\"));
+
+        let link = file_link(std::path::Path::new("/tmp/a b#1é"), "copy");
+        assert!(link.starts_with("\x1b]8;;file:///tmp/a%20b%231%C3%A9\x1b\\"));
+    }
+
     #[test]
     fn test_copy_to_clipboard() {
         let mut output = Vec::new();
@@ -0,0 +1,125 @@
+//! Handlers for fenced blocks of a given language.
+//!
+//! Some fences hold data rather than code (```` ```sparkline ````). A
+//! [`FenceHandler`] draws such a block from its text, and [`FenceHandlers`]
+//! maps fence languages to handlers. The renderer keeps a registry with the
+//! built-in handlers, extended with
+//! [`Renderer::register_fence`](crate::Renderer::register_fence).
+//!
+//! ```
+//! use std::sync::Arc;
+//! use streamdown_parser::Parser;
+//! use streamdown_render::{FenceHandler, RenderStyle, Renderer};
+//!
+//! #[derive(Debug)]
+//! struct Shout;
+//!
+//! impl FenceHandler for Shout {
+//!     fn render(&self, text: &str, _: usize, _: &RenderStyle) -> Option<Vec<String>> {
+//!         Some(vec![text.to_uppercase()])
+//!     }
+//! }
+//!
+//! let mut renderer = Renderer::new(Vec::new(), 40);
+//! renderer.register_fence("shout", Arc::new(Shout));
+//! let mut parser = Parser::new();
+//! for line in ["```shout", "hello", "```"] {
+//!     renderer.render(&parser.parse_line(line)).unwrap();
+//! }
+//! let output = String::from_utf8(renderer.into_writer()).unwrap();
+//! assert!(output.contains("HELLO"));
+//! ```
+
+use std::fmt;
+use std::sync::Arc;
+
+use streamdown_parser::ParseEvent;
+
+use crate::RenderStyle;
+use crate::mermaid::MermaidHandler;
+use crate::processor::BlockProcessor;
+use crate::qr::QrHandler;
+use crate::sparkline::SparklineHandler;
+
+/// Draws the text of a fenced block.
+pub trait FenceHandler: fmt::Debug + Send + Sync {
+    /// Lines drawing `text` (the block's lines joined by newlines) within
+    /// `width` columns, or None to render it as a code block.
+    fn render(&self, text: &str, width: usize, style: &RenderStyle) -> Option<Vec<String>>;
+}
+
+/// Fence handlers by language.
+#[derive(Debug, Clone, Default)]
+pub struct FenceHandlers {
+    handlers: Vec<(String, Arc<dyn FenceHandler>)>,
+}
+
+impl FenceHandlers {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a registry with the built-in handlers (`sparkline`,
+    /// `mermaid`, `qr`).
+    pub fn with_builtins() -> Self {
+        let mut handlers = Self::new();
+        handlers.register("sparkline", Arc::new(SparklineHandler));
+        handlers.register("mermaid", Arc::new(MermaidHandler::new()));
+        handlers.register("qr", Arc::new(QrHandler));
+        handlers
+    }
+
+    /// Handle fences of `language` (case-insensitive) with `handler`,
+    /// replacing any handler registered for it.
+    pub fn register(&mut self, language: &str, handler: Arc<dyn FenceHandler>) {
+        let language = language.to_ascii_lowercase();
+        self.handlers.retain(|(lang, _)| *lang != language);
+        self.handlers.push((language, handler));
+    }
+
+    /// Handler for `language`, if any.
+    pub fn get(&self, language: &str) -> Option<&Arc<dyn FenceHandler>> {
+        self.handlers
+            .iter()
+            .find(|(lang, _)| lang.eq_ignore_ascii_case(language))
+            .map(|(_, handler)| handler)
+    }
+
+    /// Languages with a handler, in registration order.
+    pub fn languages(&self) -> impl Iterator<Item = &str> {
+        self.handlers.iter().map(|(lang, _)| lang.as_str())
+    }
+}
+
+impl BlockProcessor for FenceHandlers {
+    fn claims(&self, first: &ParseEvent) -> bool {
+        matches!(
+            first,
+            ParseEvent::CodeBlockStart { language: Some(lang), .. } if self.get(lang).is_some()
+        )
+    }
+
+    fn process(
+        &self,
+        events: &[ParseEvent],
+        width: usize,
+        style: &RenderStyle,
+    ) -> Option<Vec<String>> {
+        let ParseEvent::CodeBlockStart {
+            language: Some(lang),
+            ..
+        } = events.first()?
+        else {
+            return None;
+        };
+        let text: Vec<&str> = events
+            .iter()
+            .filter_map(|event| match event {
+                ParseEvent::CodeBlockLine(line) => Some(line.as_str()),
+                _ => None,
+            })
+            .collect();
+        self.get(lang)?.render(&text.join("\n"), width, style)
+    }
+}
@@ -0,0 +1,233 @@
+//! Per-message statistics footer.
+//!
+//! With stats enabled ([`Renderer::set_stats`]) the renderer counts the
+//! characters and words of the text it draws and times the message from its
+//! first event. [`Renderer::render_stats_footer`] then prints them as a dim
+//! line, with a tokens/sec rate when the host supplies a token count:
+//!
+//! ```
+//! use streamdown_parser::Parser;
+//! use streamdown_render::Renderer;
+//!
+//! let mut renderer = Renderer::new(Vec::new(), 60);
+//! renderer.set_stats(true);
+//! let mut parser = Parser::new();
+//! for event in parser.parse_line("Hello *streaming* world") {
+//!     renderer.render_event(&event).unwrap();
+//! }
+//! renderer.stats_mut().unwrap().set_tokens(5);
+//! assert_eq!(renderer.stats_mut().unwrap().words(), 3);
+//! renderer.render_stats_footer().unwrap();
+//! ```
+//!
+//! [`Renderer::set_stats`]: crate::Renderer::set_stats
+//! [`Renderer::render_stats_footer`]: crate::Renderer::render_stats_footer
+
+use std::time::{Duration, Instant};
+
+use streamdown_parser::{InlineElement, ParseEvent};
+
+/// Character, word and timing counts for one message.
+#[derive(Debug, Clone)]
+pub struct MessageStats {
+    /// Characters of rendered text
+    chars: usize,
+    /// Words of rendered text
+    words: usize,
+    /// Token count supplied by the host
+    tokens: Option<usize>,
+    /// Time of the first recorded event
+    started: Option<Instant>,
+    /// Whether the last recorded character was part of a word
+    in_word: bool,
+}
+
+impl Default for MessageStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MessageStats {
+    /// Create empty stats; the clock starts at the first recorded event.
+    pub fn new() -> Self {
+        Self {
+            chars: 0,
+            words: 0,
+            tokens: None,
+            started: None,
+            in_word: false,
+        }
+    }
+
+    /// Count the text of a rendered event.
+    pub fn record_event(&mut self, event: &ParseEvent) {
+        self.started.get_or_insert_with(Instant::now);
+        let mut any = false;
+        for_each_text(event, |text, line| {
+            any = true;
+            if line {
+                self.record_line(text);
+            } else {
+                self.record(text);
+            }
+        });
+        if !any {
+            self.in_word = false;
+        }
+    }
+
+    /// Count a run of text; words may continue across calls.
+    pub fn record(&mut self, text: &str) {
+        for c in text.chars() {
+            self.chars += 1;
+            let word_char = !c.is_whitespace();
+            if word_char && !self.in_word {
+                self.words += 1;
+            }
+            self.in_word = word_char;
+        }
+    }
+
+    /// Set the number of tokens the message was generated from.
+    pub fn set_tokens(&mut self, tokens: usize) {
+        self.tokens = Some(tokens);
+    }
+
+    /// Characters counted so far.
+    pub fn chars(&self) -> usize {
+        self.chars
+    }
+
+    /// Words counted so far.
+    pub fn words(&self) -> usize {
+        self.words
+    }
+
+    /// Time since the first recorded event.
+    pub fn elapsed(&self) -> Duration {
+        self.started.map(|s| s.elapsed()).unwrap_or_default()
+    }
+
+    /// Format the footer text for a message that took `elapsed`.
+    ///
+    /// The rate is only shown when a token count was supplied.
+    pub fn summary(&self, elapsed: Duration) -> String {
+        let mut parts = vec![
+            format!("{} chars", self.chars),
+            format!("{} words", self.words),
+            format!("{:.1}s", elapsed.as_secs_f64()),
+        ];
+        if let Some(tokens) = self.tokens {
+            let secs = elapsed.as_secs_f64();
+            if secs > 0.0 {
+                parts.push(format!("{:.1} tok/s", tokens as f64 / secs));
+            } else {
+                parts.push(format!("{} tokens", tokens));
+            }
+        }
+        parts.join(" · ")
+    }
+
+    /// Count a complete line (words do not continue across lines).
+    fn record_line(&mut self, text: &str) {
+        self.in_word = false;
+        self.record(text);
+        self.in_word = false;
+    }
+}
+
+/// Call `f` with each run of visible text in an event.
+///
+/// The flag is set for complete lines (headings, code lines, table cells),
+/// as opposed to inline runs that may continue a word.
+pub(crate) fn for_each_text(event: &ParseEvent, mut f: impl FnMut(&str, bool)) {
+    match event {
+        ParseEvent::Text(text)
+        | ParseEvent::InlineCode(text)
+        | ParseEvent::Bold(text)
+        | ParseEvent::Italic(text)
+        | ParseEvent::Underline(text)
+        | ParseEvent::Strikeout(text)
+        | ParseEvent::BoldItalic(text)
+        | ParseEvent::Footnote(text)
+        | ParseEvent::Spoiler(text)
+        | ParseEvent::Kbd(text)
+        | ParseEvent::Link { text, .. }
+        | ParseEvent::Image { alt: text, .. } => f(text, false),
+        ParseEvent::Heading { content, .. } | ParseEvent::ListItem { content, .. } => {
+            f(content, true)
+        }
+        ParseEvent::CodeBlockLine(line)
+        | ParseEvent::BlockquoteLine(line)
+        | ParseEvent::ThinkBlockLine(line) => f(line, true),
+        ParseEvent::TableHeader(cells) | ParseEvent::TableRow(cells) => {
+            for cell in cells {
+                f(cell, true);
+            }
+        }
+        ParseEvent::DetailsStart { summary, .. } => f(summary, true),
+        ParseEvent::InlineElements(elements) => {
+            for element in elements {
+                f(inline_text(element), false);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Visible text of an inline element.
+pub(crate) fn inline_text(element: &InlineElement) -> &str {
+    match element {
+        InlineElement::Text(text)
+        | InlineElement::Bold(text)
+        | InlineElement::Italic(text)
+        | InlineElement::BoldItalic(text)
+        | InlineElement::Underline(text)
+        | InlineElement::Strikeout(text)
+        | InlineElement::Code(text)
+        | InlineElement::Footnote(text)
+        | InlineElement::Spoiler(text)
+        | InlineElement::Kbd(text)
+        | InlineElement::Link { text, .. }
+        | InlineElement::Image { alt: text, .. } => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_words_span_events() {
+        let mut stats = MessageStats::new();
+        stats.record_event(&ParseEvent::Text("hello wo".to_string()));
+        stats.record_event(&ParseEvent::Bold("rld".to_string()));
+        assert_eq!(stats.words(), 2);
+        assert_eq!(stats.chars(), 11);
+
+        stats.record_event(&ParseEvent::Newline);
+        stats.record_event(&ParseEvent::Text("again".to_string()));
+        assert_eq!(stats.words(), 3);
+    }
+
+    #[test]
+    fn test_lines_end_words() {
+        let mut stats = MessageStats::new();
+        stats.record_event(&ParseEvent::CodeBlockLine("let x".to_string()));
+        stats.record_event(&ParseEvent::CodeBlockLine("= 1;".to_string()));
+        assert_eq!(stats.words(), 4);
+    }
+
+    #[test]
+    fn test_summary() {
+        let mut stats = MessageStats::new();
+        stats.record("two words");
+        assert_eq!(
+            stats.summary(Duration::from_secs(2)),
+            "9 chars · 2 words · 2.0s"
+        );
+        stats.set_tokens(10);
+        assert!(stats.summary(Duration::from_secs(2)).ends_with("5.0 tok/s"));
+    }
+}
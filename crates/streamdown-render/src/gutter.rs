@@ -0,0 +1,62 @@
+//! Right-hand gutter for per-line annotations.
+//!
+//! With a gutter set ([`Renderer::set_gutter`]), the renderer keeps the last
+//! columns of the terminal free and draws annotations supplied by the host
+//! there (timestamps, source line numbers), right-aligned and dim. Content is
+//! wrapped to the remaining width. An annotation applies to the next output
+//! line ([`Renderer::annotate`]).
+//!
+//! ```
+//! use streamdown_render::gutter_cell;
+//!
+//! // Content is 20 columns wide; the gutter starts after a one-column gap
+//! assert_eq!(gutter_cell(20, 6, "12:04"), "\x1b[22G\x1b[2m 12:04\x1b[22m");
+//! ```
+//!
+//! [`Renderer::set_gutter`]: crate::Renderer::set_gutter
+//! [`Renderer::annotate`]: crate::Renderer::annotate
+
+use streamdown_ansi::codes::{DIM_OFF, DIM_ON};
+use streamdown_ansi::utils::visible_length;
+
+/// Columns between the content and the gutter.
+pub const GUTTER_GAP: usize = 1;
+
+/// Columns taken from the content by a gutter `width` wide (0 = none).
+pub fn gutter_columns(width: usize) -> usize {
+    if width == 0 { 0 } else { width + GUTTER_GAP }
+}
+
+/// Draw `text` right-aligned in a gutter `width` wide, after content
+/// `content_width` wide.
+///
+/// The cursor is moved to the gutter's first column, so the cell can follow
+/// content of any length. Text longer than the gutter is cut.
+pub fn gutter_cell(content_width: usize, width: usize, text: &str) -> String {
+    let text: String = text
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(width)
+        .collect();
+    let pad = width.saturating_sub(visible_length(&text));
+    format!(
+        "\x1b[{}G{}{}{}{}",
+        content_width + GUTTER_GAP + 1,
+        DIM_ON,
+        " ".repeat(pad),
+        text,
+        DIM_OFF
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_long_text_is_cut() {
+        assert_eq!(gutter_cell(0, 3, "L1234"), "\x1b[2G\x1b[2mL12\x1b[22m");
+        assert_eq!(gutter_columns(0), 0);
+        assert_eq!(gutter_columns(8), 9);
+    }
+}
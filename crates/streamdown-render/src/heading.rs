@@ -7,15 +7,53 @@
 //! - h4: Bold, h4 color
 //! - h5: h5 color (no bold)
 //! - h6: h6 color (muted)
+//!
+//! Attributes, alignment and underlines are set per level in [`RenderStyle`].
 
 use crate::RenderStyle;
 use crate::fg_color;
 use crate::text::simple_wrap;
-use streamdown_ansi::codes::{BOLD_OFF, BOLD_ON, RESET};
+use streamdown_ansi::codes::RESET;
 use streamdown_ansi::utils::visible_length;
 
+/// Horizontal placement of heading text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum HeadingAlign {
+    /// Flush with the left margin
+    #[default]
+    Left,
+    /// Centered in the available width
+    Center,
+    /// Flush with the right edge
+    Right,
+}
+
+/// How far a heading underline extends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum UnderlineSpan {
+    /// Under the heading text only
+    #[default]
+    Text,
+    /// Across the full width
+    Full,
+}
+
 /// Render a heading with appropriate styling.
 ///
+/// Attributes, alignment and underlines come from the style's
+/// `heading_attrs`, `heading_align`, `heading_underline` and
+/// `heading_underline_span` entries for the level.
+///
 /// # Arguments
 /// * `level` - Heading level (1-6)
 /// * `text` - The heading text (already inline-formatted)
@@ -32,72 +70,85 @@ pub fn render_heading(
     left_margin: &str,
     style: &RenderStyle,
 ) -> Vec<String> {
+    let index = usize::from(level.clamp(1, 6)) - 1;
+    let align = style.heading_align[index];
+    let color = match level {
+        1 => &style.h1,
+        2 => &style.h2,
+        3 => &style.h3,
+        4 => &style.h4,
+        5 => &style.h5,
+        // h6 and beyond: muted
+        _ => &style.h6,
+    };
+    let fg = fg_color(color);
+    let attrs = style.heading_attrs[index];
+    let (attrs_on, attrs_off) = (attrs.on(), attrs.off());
+
     // Wrap text if needed
     let lines = simple_wrap(text, width);
     let mut result = Vec::new();
+    let mut text_width = 0;
 
-    for line in lines {
-        let line_width = visible_length(&line);
-        let spaces_to_center = (width.saturating_sub(line_width)) / 2;
-        let center_pad = " ".repeat(spaces_to_center);
-
-        let rendered = match level {
-            1 => {
-                // h1: Bold, colored, centered
-                let fg = fg_color(&style.h1);
-                format!(
-                    "{}\n{}{}{}{}{}{}{}",
-                    left_margin, left_margin, BOLD_ON, fg, center_pad, line, BOLD_OFF, RESET
-                )
-            }
-            2 => {
-                // h2: Bold, colored, centered
-                let fg = fg_color(&style.h2);
-                let spaces_right = width
-                    .saturating_sub(line_width)
-                    .saturating_sub(spaces_to_center);
-                format!(
-                    "{}\n{}{}{}{}{}{}{}{}",
-                    left_margin,
-                    left_margin,
-                    BOLD_ON,
-                    fg,
-                    center_pad,
-                    line,
-                    " ".repeat(spaces_right),
-                    BOLD_OFF,
-                    RESET
-                )
-            }
-            3 => {
-                // h3: Bold, colored
-                let fg = fg_color(&style.h3);
-                format!(
-                    "{}{}{}{}{}{}",
-                    left_margin, BOLD_ON, fg, line, BOLD_OFF, RESET
-                )
-            }
-            4 => {
-                // h4: Bold, colored
-                let fg = fg_color(&style.h4);
-                format!(
-                    "{}{}{}{}{}{}",
-                    left_margin, BOLD_ON, fg, line, BOLD_OFF, RESET
-                )
-            }
-            5 => {
-                // h5: Colored (no bold)
-                let fg = fg_color(&style.h5);
-                format!("{}{}{}{}", left_margin, fg, line, RESET)
-            }
-            _ => {
-                // h6 and beyond: Colored (muted)
-                let fg = fg_color(&style.h6);
-                format!("{}{}{}{}", left_margin, fg, line, RESET)
-            }
+    for (i, line) in lines.iter().enumerate() {
+        let line_width = visible_length(line);
+        text_width = text_width.max(line_width);
+        let spare = width.saturating_sub(line_width);
+        let pad_left = match align {
+            HeadingAlign::Left => 0,
+            HeadingAlign::Center => spare / 2,
+            HeadingAlign::Right => spare,
+        };
+        // h2 fills the rest of the line when not left-aligned
+        let pad_right = if level == 2 && align != HeadingAlign::Left {
+            spare - pad_left
+        } else {
+            0
+        };
+        // h1 and h2 are set off by a blank line
+        let lead = if i == 0 && level <= 2 {
+            format!("{}\n", left_margin)
+        } else {
+            String::new()
         };
 
-        result.push(rendered);
+        result.push(format!(
+            "{}{}{}{}{}{}{}{}{}",
+            lead,
+            left_margin,
+            attrs_on,
+            fg,
+            " ".repeat(pad_left),
+            line,
+            " ".repeat(pad_right),
+            attrs_off,
+            RESET
+        ));
+    }
+
+    let glyph = &style.heading_underline[index];
+    if !glyph.is_empty() {
+        let (pad_left, span) = match style.heading_underline_span[index] {
+            UnderlineSpan::Full => (0, width),
+            UnderlineSpan::Text => {
+                let spare = width.saturating_sub(text_width);
+                let pad = match align {
+                    HeadingAlign::Left => 0,
+                    HeadingAlign::Center => spare / 2,
+                    HeadingAlign::Right => spare,
+                };
+                (pad, text_width)
+            }
+        };
+        let count = span / visible_length(glyph).max(1);
+        result.push(format!(
+            "{}{}{}{}{}",
+            left_margin,
+            " ".repeat(pad_left),
+            fg,
+            glyph.repeat(count),
+            RESET
+        ));
     }
 
     result
@@ -106,6 +157,7 @@ pub fn render_heading(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use streamdown_ansi::codes::BOLD_ON;
 
     fn default_style() -> RenderStyle {
         RenderStyle::default()
@@ -155,4 +207,41 @@ mod tests {
         let lines = render_heading(1, long_text, 20, "", &default_style());
         assert!(!lines.is_empty());
     }
+
+    #[test]
+    fn test_alignment() {
+        let mut style = default_style();
+        style.heading_align[2] = HeadingAlign::Right;
+        let lines = render_heading(3, "End", 20, "", &style);
+        assert_eq!(visible_length(&lines[0]), 20);
+        assert!(streamdown_ansi::utils::visible(&lines[0]).ends_with("End"));
+
+        style.heading_align[0] = HeadingAlign::Left;
+        let lines = render_heading(1, "Title", 20, "", &style);
+        assert!(streamdown_ansi::utils::visible(&lines[0]).ends_with("\nTitle"));
+    }
+
+    #[test]
+    fn test_underline() {
+        let mut style = default_style();
+        style.heading_underline[0] = "═".to_string();
+        style.heading_underline_span[0] = UnderlineSpan::Full;
+        let lines = render_heading(1, "Title", 30, "", &style);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(streamdown_ansi::utils::visible(&lines[1]), "═".repeat(30));
+
+        style.heading_underline[2] = "-".to_string();
+        let lines = render_heading(3, "Section", 30, "", &style);
+        assert_eq!(streamdown_ansi::utils::visible(&lines[1]), "-".repeat(7));
+    }
+
+    #[test]
+    fn test_no_underline_by_default() {
+        for level in 1..=6 {
+            assert_eq!(
+                render_heading(level, "Title", 30, "", &default_style()).len(),
+                1
+            );
+        }
+    }
 }
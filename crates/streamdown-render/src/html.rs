@@ -0,0 +1,456 @@
+//! Incremental HTML backend.
+//!
+//! [`HtmlBackend`] renders parse events as HTML while they stream, for
+//! server-sent streaming to browsers. Like the terminal renderer it writes
+//! each line as soon as it arrives: a block's opening tag goes out when the
+//! block starts, its lines follow, and the closing tag is written when the
+//! block ends. The writer is flushed after every event.
+//!
+//! Chunks split the document at element boundaries, never inside a tag or
+//! an entity. At any point [`HtmlBackend::closing_tags`] gives the tags that
+//! would make the output so far well-formed, so a client can show a preview
+//! of a partial document; [`finish`](RenderBackend::finish) writes them.
+//!
+//! ```
+//! use streamdown_render::{HtmlBackend, RenderBackend};
+//! use streamdown_parser::Parser;
+//!
+//! let mut html = HtmlBackend::new(Vec::new());
+//! let mut parser = Parser::new();
+//! for line in ["# Hi", "", "- one", "- *two*"] {
+//!     for event in parser.parse_line(line) {
+//!         html.render_event(&event).unwrap();
+//!     }
+//! }
+//! // The list is still open
+//! assert_eq!(html.closing_tags(), "</li></ul>\n");
+//!
+//! html.finish().unwrap();
+//! let output = String::from_utf8(html.into_writer()).unwrap();
+//! assert_eq!(output, "<h1>Hi</h1>\n<ul>\n<li>one</li>\n<li><em>two</em></li></ul>\n");
+//! ```
+
+use std::io::{self, Write};
+
+use streamdown_ansi::sanitize;
+use streamdown_parser::{InlineElement, InlineParser, ListBullet, ParseEvent};
+
+use crate::backend::{RenderBackend, dispatch};
+
+/// Escape text for HTML content and attribute values.
+pub fn html_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// HTML for an inline element.
+pub fn inline_html(element: &InlineElement) -> String {
+    let wrap = |tag: &str, text: &str| format!("<{tag}>{}</{tag}>", html_escape(text));
+    match element {
+        InlineElement::Text(text) => html_escape(text),
+        InlineElement::Bold(text) => wrap("strong", text),
+        InlineElement::Italic(text) => wrap("em", text),
+        InlineElement::BoldItalic(text) => {
+            format!("<strong><em>{}</em></strong>", html_escape(text))
+        }
+        InlineElement::Underline(text) => wrap("u", text),
+        InlineElement::Strikeout(text) => wrap("del", text),
+        InlineElement::Code(text) => wrap("code", text),
+        InlineElement::Footnote(text) => wrap("sup", text),
+        InlineElement::Kbd(text) => wrap("kbd", text),
+        InlineElement::Spoiler(text) => {
+            format!("<span class=\"spoiler\">{}</span>", html_escape(text))
+        }
+        InlineElement::Link { text, url } if is_safe_url(url) => {
+            format!("<a href=\"{}\">{}</a>", html_escape(url), html_escape(text))
+        }
+        InlineElement::Link { text, .. } => html_escape(text),
+        InlineElement::Image { alt, url } if is_safe_url(url) => {
+            format!(
+                "<img src=\"{}\" alt=\"{}\">",
+                html_escape(url),
+                html_escape(alt)
+            )
+        }
+        InlineElement::Image { alt, .. } => html_escape(alt),
+    }
+}
+
+/// Whether a URL can't run script when followed: relative, or with a
+/// scheme [`sanitize::is_safe_url`] allows.
+///
+/// Browsers drop tabs and newlines anywhere in a URL and leading control
+/// characters and spaces, so the scheme is read as they would read it; any
+/// other control character makes the URL unsafe.
+fn is_safe_url(url: &str) -> bool {
+    let url: String = url
+        .trim_start_matches(|c: char| c <= ' ')
+        .chars()
+        .filter(|c| !matches!(c, '\t' | '\n' | '\r'))
+        .collect();
+    if url.chars().any(char::is_control) {
+        return false;
+    }
+    let scheme_end = url.find([':', '/', '?', '#']);
+    match scheme_end {
+        Some(end) if url[end..].starts_with(':') => sanitize::is_safe_url(&url),
+        _ => true,
+    }
+}
+
+/// Renders parse events as incremental HTML.
+#[derive(Debug)]
+pub struct HtmlBackend<W: Write> {
+    writer: W,
+    /// Open elements, outermost first
+    open: Vec<&'static str>,
+    /// Indents of the open lists, outermost first
+    list_indents: Vec<usize>,
+    /// Parser for markdown content of block events
+    inline: InlineParser,
+}
+
+impl<W: Write> HtmlBackend<W> {
+    /// Create a backend writing to `writer`.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            open: Vec::new(),
+            list_indents: Vec::new(),
+            inline: InlineParser::new(),
+        }
+    }
+
+    /// Get a mutable reference to the output writer.
+    pub fn writer_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Consume the backend, returning the output writer.
+    pub fn into_writer(self) -> W {
+        self.writer
+    }
+
+    /// Elements open at this point of the output, outermost first.
+    pub fn open_elements(&self) -> &[&'static str] {
+        &self.open
+    }
+
+    /// Tags closing the open elements, innermost first.
+    pub fn closing_tags(&self) -> String {
+        let mut tags: String = self
+            .open
+            .iter()
+            .rev()
+            .map(|tag| format!("</{tag}>"))
+            .collect();
+        if !tags.is_empty() {
+            tags.push('\n');
+        }
+        tags
+    }
+
+    /// Open `tag`, written with `attrs` (e.g. ` class="x"`).
+    fn open(&mut self, tag: &'static str, attrs: &str) -> io::Result<()> {
+        self.open.push(tag);
+        write!(self.writer, "<{tag}{attrs}>")
+    }
+
+    /// Close the innermost open element.
+    fn close(&mut self) -> io::Result<()> {
+        match self.open.pop() {
+            Some(tag) => {
+                if tag == "ul" || tag == "ol" {
+                    self.list_indents.pop();
+                }
+                write!(self.writer, "</{tag}>")
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Close elements until `tag` is closed; nothing if it isn't open.
+    fn close_through(&mut self, tag: &str) -> io::Result<()> {
+        if self.open.contains(&tag) {
+            while let Some(top) = self.open.last() {
+                let done = *top == tag;
+                self.close()?;
+                if done {
+                    break;
+                }
+            }
+            writeln!(self.writer)?;
+        }
+        Ok(())
+    }
+
+    /// Close a paragraph, if one is open.
+    fn close_paragraph(&mut self) -> io::Result<()> {
+        if self.open.last() == Some(&"p") {
+            self.close()?;
+            writeln!(self.writer)?;
+        }
+        Ok(())
+    }
+
+    /// Close open paragraphs and lists before a new block.
+    fn close_flow(&mut self) -> io::Result<()> {
+        self.close_paragraph()?;
+        if let Some(&outer) = self.open.iter().find(|tag| matches!(**tag, "ul" | "ol")) {
+            self.close_through(outer)?;
+        }
+        Ok(())
+    }
+
+    /// Write markdown `content` as inline HTML.
+    fn write_inline(&mut self, content: &str) -> io::Result<()> {
+        let html: String = self.inline.parse(content).iter().map(inline_html).collect();
+        self.writer.write_all(html.as_bytes())
+    }
+}
+
+impl<W: Write> RenderBackend for HtmlBackend<W> {
+    fn heading(&mut self, level: u8, content: &str) -> io::Result<()> {
+        self.close_flow()?;
+        let level = level.clamp(1, 6);
+        write!(self.writer, "<h{level}>")?;
+        self.write_inline(content)?;
+        writeln!(self.writer, "</h{level}>")
+    }
+
+    fn code_block_start(&mut self, language: Option<&str>, _indent: usize) -> io::Result<()> {
+        self.close_paragraph()?;
+        let attrs = language
+            .map(|l| format!(" class=\"language-{}\"", html_escape(l)))
+            .unwrap_or_default();
+        self.open("pre", "")?;
+        self.open("code", &attrs)
+    }
+
+    fn code_block_line(&mut self, line: &str) -> io::Result<()> {
+        writeln!(self.writer, "{}", html_escape(line))
+    }
+
+    fn code_block_end(&mut self) -> io::Result<()> {
+        self.close_through("pre")
+    }
+
+    fn list_item(&mut self, indent: usize, bullet: &ListBullet, content: &str) -> io::Result<()> {
+        self.close_paragraph()?;
+        while self.list_indents.last().is_some_and(|&open| open > indent) {
+            let tag = self
+                .open
+                .iter()
+                .rev()
+                .find(|tag| matches!(**tag, "ul" | "ol"));
+            let Some(&tag) = tag else { break };
+            self.close_through(tag)?;
+        }
+        if self.list_indents.last() == Some(&indent) {
+            if self.open.last() == Some(&"li") {
+                self.close()?;
+                writeln!(self.writer)?;
+            }
+        } else {
+            let (tag, attrs) = match bullet {
+                ListBullet::Ordered(1) => ("ol", String::new()),
+                ListBullet::Ordered(start) => ("ol", format!(" start=\"{start}\"")),
+                _ => ("ul", String::new()),
+            };
+            if self.open.last() == Some(&"li") {
+                writeln!(self.writer)?;
+            }
+            self.open(tag, &attrs)?;
+            writeln!(self.writer)?;
+            self.list_indents.push(indent);
+        }
+        self.open("li", "")?;
+        self.write_inline(content)
+    }
+
+    fn list_end(&mut self) -> io::Result<()> {
+        self.close_flow()
+    }
+
+    fn table_header(&mut self, cells: &[String]) -> io::Result<()> {
+        self.close_flow()?;
+        self.open("table", "")?;
+        write!(self.writer, "\n<thead><tr>")?;
+        for cell in cells {
+            self.writer.write_all(b"<th>")?;
+            self.write_inline(cell)?;
+            self.writer.write_all(b"</th>")?;
+        }
+        writeln!(self.writer, "</tr></thead>")?;
+        self.open("tbody", "")?;
+        writeln!(self.writer)
+    }
+
+    fn table_row(&mut self, cells: &[String]) -> io::Result<()> {
+        self.writer.write_all(b"<tr>")?;
+        for cell in cells {
+            self.writer.write_all(b"<td>")?;
+            self.write_inline(cell)?;
+            self.writer.write_all(b"</td>")?;
+        }
+        writeln!(self.writer, "</tr>")
+    }
+
+    fn table_end(&mut self) -> io::Result<()> {
+        self.close_through("table")
+    }
+
+    fn blockquote_start(&mut self, _depth: usize) -> io::Result<()> {
+        self.close_flow()?;
+        self.open("blockquote", "")?;
+        writeln!(self.writer)
+    }
+
+    fn blockquote_line(&mut self, text: &str) -> io::Result<()> {
+        self.writer.write_all(b"<p>")?;
+        self.write_inline(text)?;
+        writeln!(self.writer, "</p>")
+    }
+
+    fn blockquote_end(&mut self) -> io::Result<()> {
+        self.close_through("blockquote")
+    }
+
+    fn think_block_start(&mut self) -> io::Result<()> {
+        self.close_flow()?;
+        self.open("blockquote", " class=\"think\"")?;
+        writeln!(self.writer)
+    }
+
+    fn details_start(&mut self, summary: &str, open: bool) -> io::Result<()> {
+        self.close_flow()?;
+        self.open("details", if open { " open" } else { "" })?;
+        self.writer.write_all(b"<summary>")?;
+        self.write_inline(summary)?;
+        writeln!(self.writer, "</summary>")
+    }
+
+    fn details_end(&mut self) -> io::Result<()> {
+        self.close_flow()?;
+        self.close_through("details")
+    }
+
+    fn horizontal_rule(&mut self) -> io::Result<()> {
+        self.close_flow()?;
+        writeln!(self.writer, "<hr>")
+    }
+
+    fn empty_line(&mut self) -> io::Result<()> {
+        self.close_paragraph()
+    }
+
+    fn newline(&mut self) -> io::Result<()> {
+        if self.open.last() == Some(&"p") {
+            writeln!(self.writer)?;
+        }
+        Ok(())
+    }
+
+    fn inline(&mut self, element: &InlineElement) -> io::Result<()> {
+        if !matches!(self.open.last(), Some(&"p") | Some(&"li")) {
+            self.open("p", "")?;
+        }
+        self.writer.write_all(inline_html(element).as_bytes())
+    }
+
+    fn render_event(&mut self, event: &ParseEvent) -> io::Result<()> {
+        dispatch(self, event)?;
+        self.writer.flush()
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        let tags = self.closing_tags();
+        self.open.clear();
+        self.list_indents.clear();
+        self.writer.write_all(tags.as_bytes())?;
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::render_str;
+
+    fn html(markdown: &str) -> String {
+        let mut backend = HtmlBackend::new(Vec::new());
+        render_str(&mut backend, markdown).unwrap();
+        assert!(backend.open_elements().is_empty());
+        String::from_utf8(backend.into_writer()).unwrap()
+    }
+
+    #[test]
+    fn test_blocks() {
+        let output = html("text **bold**\n\n```rust\nlet x = 1 < 2;\n```\n\n---\n");
+        assert_eq!(
+            output,
+            "<p>text <strong>bold</strong>\n</p>\n\
+             <pre><code class=\"language-rust\">let x = 1 &lt; 2;\n</code></pre>\n\
+             <hr>\n"
+        );
+    }
+
+    #[test]
+    fn test_nested_lists_and_tables() {
+        let output = html("1. a\n   - b\n2. c\n\n| x |\n|---|\n| 1 |\n");
+        assert_eq!(
+            output,
+            "<ol>\n<li>a\n<ul>\n<li>b</li></ul>\n</li>\n<li>c</li></ol>\n\
+             <table>\n<thead><tr><th>x</th></tr></thead>\n<tbody>\n\
+             <tr><td>1</td></tr>\n</tbody></table>\n"
+        );
+    }
+
+    #[test]
+    fn test_partial_document_closes() {
+        let mut backend = HtmlBackend::new(Vec::new());
+        backend.blockquote_start(1).unwrap();
+        backend.code_block_start(None, 0).unwrap();
+        assert_eq!(backend.open_elements(), ["blockquote", "pre", "code"]);
+        assert_eq!(backend.closing_tags(), "</code></pre></blockquote>\n");
+    }
+
+    #[test]
+    fn test_unsafe_urls_are_dropped() {
+        let link = InlineElement::Link {
+            text: "x".to_string(),
+            url: "JavaScript:alert(1)".to_string(),
+        };
+        assert_eq!(inline_html(&link), "x");
+        // Browsers strip tabs and newlines, and leading control characters
+        for url in [
+            "java\tscript:alert(1)",
+            "java\nscript:alert(1)",
+            "\x01javascript:alert(1)",
+            " data:text/html,x",
+            "vbscript:x",
+        ] {
+            assert!(!is_safe_url(url), "{url:?}");
+        }
+        assert!(is_safe_url("docs/guide.md#setup"));
+        assert!(is_safe_url("mailto:a@b.c"));
+        let image = InlineElement::Image {
+            alt: "a\"b".to_string(),
+            url: "https://e.com/i.png".to_string(),
+        };
+        assert_eq!(
+            inline_html(&image),
+            "<img src=\"https://e.com/i.png\" alt=\"a&quot;b\">"
+        );
+    }
+}
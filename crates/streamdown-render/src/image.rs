@@ -0,0 +1,342 @@
+//! Inline image rendering.
+//!
+//! On terminals that speak the Kitty graphics protocol, local PNG images are
+//! transmitted inline and scaled to the content width. Elsewhere, with the
+//! `image` feature and colors enabled, they are downsampled to colored
+//! half-block cells (`▀` with foreground/background colors, two pixels per
+//! cell, quantized like all output to the terminal's color depth). Anything
+//! else (remote URLs, other formats, monochrome output) falls back to the
+//! text marker.
+
+use std::path::Path;
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+#[cfg(feature = "image")]
+use streamdown_ansi::codes::RESET;
+
+/// Maximum image file size to transmit (10MB).
+const MAX_IMAGE_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Maximum base64 payload per Kitty escape (protocol limit).
+const KITTY_CHUNK_SIZE: usize = 4096;
+
+/// PNG file signature.
+const PNG_MAGIC: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// Maximum height of a half-block image in terminal rows.
+#[cfg(feature = "image")]
+pub const MAX_HALFBLOCK_ROWS: usize = 40;
+
+/// Alpha below which a pixel is treated as transparent.
+#[cfg(feature = "image")]
+const ALPHA_THRESHOLD: u8 = 128;
+
+/// Resolve an image URL to a local path.
+///
+/// Accepts plain paths and `file://` URLs; anything with another scheme is
+/// treated as remote and rejected.
+pub fn local_image_path(url: &str) -> Option<&Path> {
+    let path = url.strip_prefix("file://").unwrap_or(url);
+    if path.is_empty() || path.contains("://") {
+        return None;
+    }
+    Some(Path::new(path))
+}
+
+/// Read a local PNG image, if the URL points at one.
+pub fn load_png(url: &str) -> Option<Vec<u8>> {
+    let path = local_image_path(url)?;
+    let meta = std::fs::metadata(path).ok()?;
+    if !meta.is_file() || meta.len() > MAX_IMAGE_SIZE {
+        return None;
+    }
+    let data = std::fs::read(path).ok()?;
+    data.starts_with(PNG_MAGIC).then_some(data)
+}
+
+/// Encode PNG data as Kitty graphics protocol escapes.
+///
+/// The image is displayed at the cursor, `columns` cells wide; the terminal
+/// derives the height from the aspect ratio. Responses are suppressed
+/// (`q=2`) so nothing is echoed back into the input stream.
+pub fn kitty_image(png: &[u8], columns: usize) -> String {
+    let encoded = STANDARD.encode(png);
+    let chunks: Vec<&[u8]> = encoded.as_bytes().chunks(KITTY_CHUNK_SIZE).collect();
+    let mut out = String::with_capacity(encoded.len() + chunks.len() * 16);
+
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = if i + 1 < chunks.len() { 1 } else { 0 };
+        // Chunks are slices of base64 text, so always valid UTF-8
+        let data = std::str::from_utf8(chunk).unwrap_or_default();
+        if i == 0 {
+            out.push_str(&format!(
+                "\x1b_Ga=T,f=100,q=2,c={},m={};{}\x1b\\",
+                columns.max(1),
+                more,
+                data
+            ));
+        } else {
+            out.push_str(&format!("\x1b_Gm={};{}\x1b\\", more, data));
+        }
+    }
+
+    out
+}
+
+/// Decoded image with 8-bit RGBA pixels in row-major order.
+#[cfg(feature = "image")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RgbaImage {
+    /// Width in pixels
+    pub width: usize,
+    /// Height in pixels
+    pub height: usize,
+    /// Pixel data, `width * height` entries
+    pub pixels: Vec<[u8; 4]>,
+}
+
+#[cfg(feature = "image")]
+impl RgbaImage {
+    /// Pixel at `(x, y)`.
+    fn pixel(&self, x: usize, y: usize) -> [u8; 4] {
+        self.pixels[y * self.width + x]
+    }
+
+    /// Average color of the pixel box `[x0, x1) x [y0, y1)`.
+    ///
+    /// Colors are weighted by alpha so transparent edges do not darken.
+    fn average(&self, x0: usize, x1: usize, y0: usize, y1: usize) -> [u8; 4] {
+        let (mut r, mut g, mut b, mut a, mut n) = (0u64, 0u64, 0u64, 0u64, 0u64);
+        for y in y0..y1.max(y0 + 1).min(self.height) {
+            for x in x0..x1.max(x0 + 1).min(self.width) {
+                let [pr, pg, pb, pa] = self.pixel(x, y);
+                let pa = pa as u64;
+                r += pr as u64 * pa;
+                g += pg as u64 * pa;
+                b += pb as u64 * pa;
+                a += pa;
+                n += 1;
+            }
+        }
+        if a == 0 {
+            return [0, 0, 0, 0];
+        }
+        [
+            (r / a) as u8,
+            (g / a) as u8,
+            (b / a) as u8,
+            (a / n.max(1)) as u8,
+        ]
+    }
+}
+
+/// Decode PNG data into RGBA pixels.
+#[cfg(feature = "image")]
+pub fn decode_png(data: &[u8]) -> Option<RgbaImage> {
+    let mut decoder = png::Decoder::new(data);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().ok()?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).ok()?;
+    let bytes = &buf[..info.buffer_size()];
+
+    let pixels: Vec<[u8; 4]> = match info.color_type {
+        png::ColorType::Rgba => bytes
+            .chunks_exact(4)
+            .map(|p| [p[0], p[1], p[2], p[3]])
+            .collect(),
+        png::ColorType::Rgb => bytes
+            .chunks_exact(3)
+            .map(|p| [p[0], p[1], p[2], 255])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => bytes
+            .chunks_exact(2)
+            .map(|p| [p[0], p[0], p[0], p[1]])
+            .collect(),
+        png::ColorType::Grayscale => bytes.iter().map(|&v| [v, v, v, 255]).collect(),
+        png::ColorType::Indexed => return None,
+    };
+
+    let (width, height) = (info.width as usize, info.height as usize);
+    (pixels.len() == width * height && width > 0 && height > 0).then_some(RgbaImage {
+        width,
+        height,
+        pixels,
+    })
+}
+
+/// Render an image as rows of half-block cells.
+///
+/// Each cell shows two vertically stacked pixels: the upper one as the
+/// foreground of `▀`, the lower one as the background. The image is
+/// box-filtered down to at most `max_cols` columns and
+/// [`MAX_HALFBLOCK_ROWS`] rows, keeping its aspect ratio, and is never
+/// scaled up. Transparent pixels show the terminal background.
+#[cfg(feature = "image")]
+pub fn halfblock_lines(image: &RgbaImage, max_cols: usize) -> Vec<String> {
+    if max_cols == 0 {
+        return Vec::new();
+    }
+
+    // Target size in pixels; a cell is one pixel wide and two tall
+    let scale = (image.width as f64 / max_cols as f64)
+        .max(image.height as f64 / (MAX_HALFBLOCK_ROWS * 2) as f64)
+        .max(1.0);
+    let cols = ((image.width as f64 / scale).round() as usize).max(1);
+    let px_rows = ((image.height as f64 / scale).round() as usize).max(1);
+
+    let sample = |x: usize, y: usize| -> [u8; 4] {
+        let x0 = (x as f64 * scale) as usize;
+        let x1 = ((x + 1) as f64 * scale) as usize;
+        let y0 = (y as f64 * scale) as usize;
+        let y1 = ((y + 1) as f64 * scale) as usize;
+        image.average(x0, x1, y0, y1)
+    };
+
+    let mut lines = Vec::with_capacity(px_rows.div_ceil(2));
+    for row in (0..px_rows).step_by(2) {
+        let mut line = String::new();
+        for x in 0..cols {
+            let top = sample(x, row);
+            let bottom = if row + 1 < px_rows {
+                sample(x, row + 1)
+            } else {
+                [0, 0, 0, 0]
+            };
+            push_cell(&mut line, top, bottom);
+        }
+        line.push_str(RESET);
+        lines.push(line);
+    }
+    lines
+}
+
+/// Append one half-block cell for a pair of pixels.
+#[cfg(feature = "image")]
+fn push_cell(line: &mut String, top: [u8; 4], bottom: [u8; 4]) {
+    let opaque = |p: [u8; 4]| p[3] >= ALPHA_THRESHOLD;
+    match (opaque(top), opaque(bottom)) {
+        (true, true) => line.push_str(&format!(
+            "\x1b[38;2;{};{};{};48;2;{};{};{}m\u{2580}",
+            top[0], top[1], top[2], bottom[0], bottom[1], bottom[2]
+        )),
+        (true, false) => line.push_str(&format!(
+            "\x1b[49;38;2;{};{};{}m\u{2580}",
+            top[0], top[1], top[2]
+        )),
+        (false, true) => line.push_str(&format!(
+            "\x1b[49;38;2;{};{};{}m\u{2584}",
+            bottom[0], bottom[1], bottom[2]
+        )),
+        (false, false) => line.push_str("\x1b[0m "),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_image_path() {
+        assert_eq!(local_image_path("img/a.png"), Some(Path::new("img/a.png")));
+        assert_eq!(
+            local_image_path("file:///tmp/a.png"),
+            Some(Path::new("/tmp/a.png"))
+        );
+        assert_eq!(local_image_path("https://example.com/a.png"), None);
+        assert_eq!(local_image_path(""), None);
+    }
+
+    #[test]
+    fn test_kitty_image_single_chunk() {
+        let seq = kitty_image(PNG_MAGIC, 40);
+        assert!(seq.starts_with("\x1b_Ga=T,f=100,q=2,c=40,m=0;"));
+        assert!(seq.ends_with("\x1b\\"));
+        assert_eq!(seq.matches("\x1b_G").count(), 1);
+    }
+
+    #[test]
+    fn test_kitty_image_chunked() {
+        let data = vec![0u8; 10_000];
+        let seq = kitty_image(&data, 80);
+        let chunks = seq.matches("\x1b_G").count();
+        assert!(chunks > 1);
+        assert!(seq.contains("m=1;"));
+        assert!(seq.contains("\x1b_Gm=0;"));
+    }
+
+    #[test]
+    fn test_load_png_rejects_non_png() {
+        let path = std::env::temp_dir().join(format!("sd-not-png-{}.png", std::process::id()));
+        std::fs::write(&path, b"GIF89a").unwrap();
+        assert!(load_png(path.to_str().unwrap()).is_none());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "image")]
+    fn encode_png(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut encoder = png::Encoder::new(&mut out, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(rgba).unwrap();
+        writer.finish().unwrap();
+        out
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_decode_png() {
+        let png = encode_png(2, 1, &[255, 0, 0, 255, 0, 0, 255, 0]);
+        let image = decode_png(&png).unwrap();
+        assert_eq!((image.width, image.height), (2, 1));
+        assert_eq!(image.pixels, vec![[255, 0, 0, 255], [0, 0, 255, 0]]);
+        assert!(decode_png(b"not a png").is_none());
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_halfblock_cells() {
+        // Red over green, one column
+        let png = encode_png(1, 2, &[255, 0, 0, 255, 0, 255, 0, 255]);
+        let lines = halfblock_lines(&decode_png(&png).unwrap(), 80);
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].contains("\x1b[38;2;255;0;0;48;2;0;255;0m\u{2580}"));
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_halfblock_downsamples_to_width() {
+        let image = RgbaImage {
+            width: 200,
+            height: 100,
+            pixels: vec![[10, 20, 30, 255]; 200 * 100],
+        };
+        let lines = halfblock_lines(&image, 50);
+        assert_eq!(lines.len(), 13);
+        for line in &lines {
+            assert_eq!(streamdown_ansi::utils::visible_length(line), 50);
+        }
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_halfblock_transparent() {
+        let image = RgbaImage {
+            width: 1,
+            height: 2,
+            pixels: vec![[0, 0, 0, 0], [9, 9, 9, 255]],
+        };
+        let lines = halfblock_lines(&image, 10);
+        assert!(lines[0].contains("\u{2584}"));
+    }
+
+    #[test]
+    fn test_load_png() {
+        let path = std::env::temp_dir().join(format!("sd-png-{}.png", std::process::id()));
+        std::fs::write(&path, PNG_MAGIC).unwrap();
+        assert!(load_png(path.to_str().unwrap()).is_some());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
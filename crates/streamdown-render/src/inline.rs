@@ -0,0 +1,154 @@
+//! Handlers for inline extensions in text.
+//!
+//! An [`InlineHandler`] recognizes spans of its own syntax in paragraphs,
+//! list items, quotes and table cells (e.g. `[####----] 50%`) and draws
+//! them in place. [`InlineHandlers`]
+//! holds the handlers the renderer tries, registered with
+//! [`Renderer::register_inline`](crate::Renderer::register_inline); none
+//! are registered by default.
+//!
+//! ```
+//! use std::ops::Range;
+//! use std::sync::Arc;
+//! use streamdown_parser::Parser;
+//! use streamdown_render::{InlineHandler, RenderStyle, Renderer};
+//!
+//! #[derive(Debug)]
+//! struct Shout;
+//!
+//! impl InlineHandler for Shout {
+//!     fn find(&self, text: &str) -> Option<Range<usize>> {
+//!         let start = text.find("!!")?;
+//!         let end = start + 2 + text[start + 2..].find("!!")? + 2;
+//!         Some(start..end)
+//!     }
+//!
+//!     fn render(&self, span: &str, _: usize, _: &RenderStyle) -> Option<String> {
+//!         Some(span.trim_matches('!').to_uppercase())
+//!     }
+//! }
+//!
+//! let mut renderer = Renderer::new(Vec::new(), 40);
+//! renderer.register_inline("shout", Arc::new(Shout));
+//! let mut parser = Parser::new();
+//! renderer.render(&parser.parse_line("say !!hello!! twice")).unwrap();
+//! let output = String::from_utf8(renderer.into_writer()).unwrap();
+//! assert!(output.contains("say HELLO twice"));
+//! ```
+
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+
+use crate::RenderStyle;
+
+/// Recognizes and draws spans of an inline extension.
+pub trait InlineHandler: fmt::Debug + Send + Sync {
+    /// Byte range of the first span in `text`, if any.
+    fn find(&self, text: &str) -> Option<Range<usize>>;
+
+    /// Text drawing `span` with `width` columns of content width, or None
+    /// to leave it as written.
+    fn render(&self, span: &str, width: usize, style: &RenderStyle) -> Option<String>;
+}
+
+/// Inline handlers by name.
+#[derive(Debug, Clone, Default)]
+pub struct InlineHandlers {
+    handlers: Vec<(String, Arc<dyn InlineHandler>)>,
+}
+
+impl InlineHandlers {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `handler` under `name`, replacing any handler registered under
+    /// it. Earlier handlers win when spans start at the same place.
+    pub fn register(&mut self, name: &str, handler: Arc<dyn InlineHandler>) {
+        self.handlers.retain(|(n, _)| n != name);
+        self.handlers.push((name.to_string(), handler));
+    }
+
+    /// Remove the handler registered under `name`.
+    pub fn unregister(&mut self, name: &str) {
+        self.handlers.retain(|(n, _)| n != name);
+    }
+
+    /// Whether no handler is registered.
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
+    }
+
+    /// Names of the registered handlers, in registration order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.handlers.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// `text` with every recognized span drawn by its handler.
+    pub fn apply(&self, text: &str, width: usize, style: &RenderStyle) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        while !rest.is_empty() {
+            // Earliest span of any handler
+            let next = self
+                .handlers
+                .iter()
+                .filter_map(|(_, handler)| {
+                    let range = handler.find(rest)?;
+                    (range.start < range.end && range.end <= rest.len()).then_some((range, handler))
+                })
+                .min_by_key(|(range, _)| range.start);
+            let Some((range, handler)) = next else {
+                break;
+            };
+            out.push_str(&rest[..range.start]);
+            let span = &rest[range.clone()];
+            match handler.render(span, width, style) {
+                Some(drawn) => out.push_str(&drawn),
+                None => out.push_str(span),
+            }
+            rest = &rest[range.end..];
+        }
+        out.push_str(rest);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Draws `<n>` as n stars.
+    #[derive(Debug)]
+    struct Stars;
+
+    impl InlineHandler for Stars {
+        fn find(&self, text: &str) -> Option<Range<usize>> {
+            let start = text.find('<')?;
+            Some(start..start + text[start..].find('>')? + 1)
+        }
+
+        fn render(&self, span: &str, _: usize, _: &RenderStyle) -> Option<String> {
+            let n: usize = span[1..span.len() - 1].parse().ok()?;
+            Some("*".repeat(n))
+        }
+    }
+
+    #[test]
+    fn test_apply() {
+        let style = RenderStyle::default();
+        let mut handlers = InlineHandlers::new();
+        assert_eq!(handlers.apply("a <3> b", 40, &style), "a <3> b");
+
+        handlers.register("stars", Arc::new(Stars));
+        assert_eq!(handlers.apply("a <3> b <1>", 40, &style), "a *** b *");
+        // Spans the handler declines are kept
+        assert_eq!(handlers.apply("<x> <2>", 40, &style), "<x> **");
+        assert_eq!(handlers.names().collect::<Vec<_>>(), ["stars"]);
+
+        handlers.unregister("stars");
+        assert!(handlers.is_empty());
+    }
+}
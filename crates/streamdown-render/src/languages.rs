@@ -0,0 +1,144 @@
+//! Fence language aliases.
+//!
+//! Authors spell fence languages many ways (` ```js `, ` ```JavaScript `,
+//! ` ```node `). [`LanguageAliases`] maps each alias to one canonical name
+//! before a code block is drawn, so its label, its highlighting and the
+//! [fence handler](crate::FenceHandler) chosen for it are the same whatever
+//! spelling was used. The renderer starts with the aliases of the shared
+//! [language table](streamdown_core::languages), which also picks the
+//! highlighting syntax; hosts add their own with
+//! [`Renderer::alias_language`](crate::Renderer::alias_language), and the
+//! `sd` config file with `[render] LanguageAliases`.
+//!
+//! ```
+//! use streamdown_render::LanguageAliases;
+//!
+//! let mut aliases = LanguageAliases::with_builtins();
+//! assert_eq!(aliases.resolve("JS"), "javascript");
+//! assert_eq!(aliases.resolve("Dockerfile"), "Dockerfile");
+//! aliases.insert("hs", "haskell");
+//! assert_eq!(aliases.resolve("hs"), "haskell");
+//! ```
+
+use std::collections::BTreeMap;
+
+use streamdown_core::languages::LANGUAGES;
+use streamdown_parser::ParseEvent;
+
+/// Canonical fence language names by alias.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LanguageAliases {
+    /// Lowercase alias to canonical name
+    aliases: BTreeMap<String, String>,
+}
+
+impl LanguageAliases {
+    /// Create an empty table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a table with the aliases of the known languages (`js`, `sh`,
+    /// `py`, `yml`...).
+    pub fn with_builtins() -> Self {
+        let mut aliases = Self::new();
+        for language in LANGUAGES {
+            for alias in language.aliases {
+                aliases.insert(alias, language.name);
+            }
+        }
+        aliases
+    }
+
+    /// Map `alias` (in any case) to `language`, replacing any earlier
+    /// mapping of it.
+    pub fn insert(&mut self, alias: &str, language: &str) {
+        self.aliases
+            .insert(alias.to_lowercase(), language.to_string());
+    }
+
+    /// Remove the mapping of `alias`.
+    pub fn remove(&mut self, alias: &str) {
+        self.aliases.remove(&alias.to_lowercase());
+    }
+
+    /// The canonical name of `language`: its mapping if it is an alias,
+    /// otherwise `language` as written.
+    pub fn resolve(&self, language: &str) -> String {
+        match self.aliases.get(&language.to_lowercase()) {
+            Some(canonical) => canonical.clone(),
+            None => language.to_string(),
+        }
+    }
+
+    /// Aliases and their canonical names, in alias order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.aliases
+            .iter()
+            .map(|(alias, language)| (alias.as_str(), language.as_str()))
+    }
+
+    /// `event` with its fence language made canonical, or None if it has
+    /// none or it already is.
+    pub fn canonical_event(&self, event: &ParseEvent) -> Option<ParseEvent> {
+        match event {
+            ParseEvent::CodeBlockStart {
+                language: Some(language),
+                indent,
+            } => {
+                let canonical = self.resolve(language);
+                (canonical != *language).then_some(ParseEvent::CodeBlockStart {
+                    language: Some(canonical),
+                    indent: *indent,
+                })
+            }
+            ParseEvent::CodeBlockLanguage(language) => {
+                let canonical = self.resolve(language);
+                (canonical != *language).then_some(ParseEvent::CodeBlockLanguage(canonical))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        let mut aliases = LanguageAliases::with_builtins();
+        assert_eq!(aliases.resolve("sh"), "bash");
+        assert_eq!(aliases.resolve("Py"), "python");
+        assert_eq!(aliases.resolve("CC"), "cpp");
+        assert_eq!(aliases.resolve("nodejs"), "javascript");
+        assert_eq!(aliases.resolve("fish"), "bash");
+        // Names that aren't aliases keep the author's casing
+        assert_eq!(aliases.resolve("Rust"), "Rust");
+        assert_eq!(aliases.resolve("Dockerfile"), "Dockerfile");
+        aliases.insert("SH", "zsh");
+        assert_eq!(aliases.resolve("sh"), "zsh");
+        aliases.remove("sh");
+        assert_eq!(aliases.resolve("sh"), "sh");
+        assert!(LanguageAliases::new().iter().next().is_none());
+    }
+
+    #[test]
+    fn test_canonical_event() {
+        let aliases = LanguageAliases::with_builtins();
+        let start = |language: &str| ParseEvent::CodeBlockStart {
+            language: Some(language.to_string()),
+            indent: 2,
+        };
+        assert_eq!(
+            aliases.canonical_event(&start("js")),
+            Some(start("javascript"))
+        );
+        assert_eq!(aliases.canonical_event(&start("javascript")), None);
+        assert_eq!(
+            aliases.canonical_event(&ParseEvent::CodeBlockLanguage("yml".to_string())),
+            Some(ParseEvent::CodeBlockLanguage("yaml".to_string()))
+        );
+        assert_eq!(aliases.canonical_event(&ParseEvent::EmptyLine), None);
+    }
+}
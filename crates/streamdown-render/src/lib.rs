@@ -6,7 +6,8 @@
 //! # Features
 //!
 //! - **Styled headings** - 6 levels with different colors and alignment
-//! - **Syntax highlighting** - Code blocks with language detection
+//! - **Syntax highlighting** - Code blocks colored by fence language
+//!   (`syntax-highlighting` feature, on by default)
 //! - **Pretty tables** - With column width calculation and cell wrapping
 //! - **ANSI-aware text wrapping** - Preserves formatting across line breaks
 //! - **Nested lists** - With cycling bullet styles
@@ -27,66 +28,242 @@
 //! }).unwrap();
 //! ```
 
+pub mod agenda;
+#[cfg(feature = "arena")]
+pub mod arena;
+#[cfg(feature = "tokio")]
+pub mod async_writer;
+pub mod attributes;
+pub mod backend;
+pub mod background;
+mod batch;
+#[cfg(feature = "bidi")]
+pub mod bidi;
+pub mod border;
+pub mod breadcrumb;
+pub mod builder;
+pub mod cache;
+pub mod capabilities;
+pub mod cells;
+mod chunk;
 pub mod code;
+pub mod collapse;
 pub mod colors;
+pub mod copy;
+pub mod csv;
+pub mod error;
 pub mod features;
+pub mod fence;
+pub mod footer;
+pub mod gutter;
 pub mod heading;
+pub mod html;
+pub mod image;
+pub mod inline;
+pub mod languages;
 pub mod list;
+pub mod live;
+pub mod mermaid;
+pub mod meta;
+pub mod multiplex;
+pub mod navigation;
+pub mod notify;
+pub mod opener;
+pub mod options;
+pub mod outline;
+pub mod pin;
+pub mod policy;
+pub mod pretty;
+pub mod processor;
+pub mod progress;
+pub mod qr;
+pub mod quota;
+pub mod quote;
+pub mod random;
+pub mod roff;
+pub mod sanitize;
+pub mod settings;
+pub mod sparkline;
+pub mod speech;
+pub mod split;
+#[cfg(feature = "ssh")]
+pub mod ssh;
+#[cfg(feature = "stream")]
+pub mod stream;
 pub mod table;
+pub mod terms;
 pub mod text;
+pub mod theme;
+pub mod trace;
+#[cfg(feature = "serde")]
+pub mod transcript;
 
-pub use code::{CODEPAD_BOTTOM, CODEPAD_TOP, CodeBlockState, code_wrap};
-pub use colors::{COLODORE, resolve_color};
+pub use agenda::{AgendaItem, AgendaProcessor};
+#[cfg(feature = "arena")]
+pub use arena::LineArena;
+#[cfg(feature = "tokio")]
+pub use async_writer::AsyncRenderer;
+pub use attributes::TextAttributes;
+pub use backend::{RenderBackend, dispatch, inline_element, render_str};
+pub use background::{Background, DA1_QUERY, OSC11_QUERY, has_da1_reply};
+pub use border::{BorderGlyphs, BorderStyle};
+pub use breadcrumb::{BREADCRUMB_SEPARATOR, Breadcrumb};
+pub use builder::RenderStyleBuilder;
+pub use cache::{DEFAULT_WRAP_CACHE_CAPACITY, WrapCache};
+pub use capabilities::{ColorDepth, GraphicsProtocol, NotificationProtocol, TerminalCapabilities};
+pub use cells::{CellAlign, CellFormatter, DateOrder, FormattedCell, LocaleFormatter};
+#[cfg(feature = "syntax-highlighting")]
+pub use code::CodeBlockState;
+pub use code::{CODEPAD_BOTTOM, CODEPAD_TOP, code_wrap};
+pub use collapse::CollapseOptions;
+pub use colors::{
+    ANSI_COLOR_NAMES, COLODORE, Color, ParseColorError, SWATCH, parse_hex_literal, resolve_color,
+    swatch,
+};
+pub use csv::CsvHandler;
+pub use error::{MIN_RENDER_WIDTH, RenderError, RenderResult};
 pub use features::{
-    RenderFeatures, copy_to_clipboard, is_tty, savebrace, savebrace_clear, savebrace_last,
-    savebrace_path, savebrace_read, terminal_size, terminal_width,
+    CodeOverflow, LinkStyle, ProgressLines, RenderFeatures, copy_to_clipboard, is_tty, savebrace,
+    savebrace_clear, savebrace_last, savebrace_path, savebrace_read, terminal_size, terminal_width,
+};
+pub use fence::{FenceHandler, FenceHandlers};
+pub use footer::MessageStats;
+pub use gutter::{GUTTER_GAP, gutter_cell, gutter_columns};
+pub use heading::{HeadingAlign, UnderlineSpan, render_heading};
+pub use html::{HtmlBackend, html_escape, inline_html};
+pub use inline::{InlineHandler, InlineHandlers};
+pub use languages::LanguageAliases;
+pub use list::{
+    ASCII_BULLETS, ASCII_TASK_GLYPHS, BULLETS, InlineDraw, ListState, TASK_GLYPHS, render_list_item,
 };
-pub use heading::render_heading;
-pub use list::{BULLETS, ListState, render_list_item};
+pub use mermaid::MermaidHandler;
+pub use meta::{ConfidenceStyler, MetaStyler, SpanMeta};
+pub use multiplex::{ChannelId, Multiplexer, MuxLayout};
+pub use navigation::{
+    BlockId, BlockKind, BlockPatch, LineMeta, NavCommand, NavDirection, next_block, previous_block,
+};
+pub use notify::{NOTIFY_TITLE, NotifyOptions, notification_sequence};
+pub use opener::LinkOpener;
+pub use options::{ColorChoice, RendererOptions};
+pub use pin::PinnedHeader;
+pub use policy::Policy;
+#[cfg(feature = "serde")]
+pub use pretty::JsonHandler;
+pub use pretty::YamlHandler;
+pub use processor::BlockProcessor;
+pub use progress::ProgressHandler;
+pub use qr::{QR_COLORS, QrCode, QrHandler};
+pub use quota::{MAX_HELD_EVENTS, RenderQuota};
+pub use quote::{QuoteMatcher, TranscriptMatcher};
+pub use roff::{RoffBackend, inline_roff, roff_escape};
+pub use sanitize::ControlChars;
+pub use settings::RenderSettings;
+pub use sparkline::SparklineHandler;
+pub use speech::{BareMarkup, SpeechBackend, SpeechMarkup, SsmlMarkup, ssml_escape};
+pub use split::{Section, SectionSplitter};
+#[cfg(feature = "ssh")]
+pub use ssh::{PtyRequest, SshRenderSession};
+#[cfg(feature = "stream")]
+pub use stream::RenderStream;
 pub use table::{TableState, render_table_row, render_table_separator};
-pub use text::{WrappedText, simple_wrap, split_text, text_wrap};
+pub use terms::{PatternPass, TermStyle, TextPass, TextPasses};
+pub use text::{
+    FlowedText, Hyphenator, TextAlign, WidthHyphenator, WrappedText, align_line, cjk_breaks,
+    flow_text, hyphenate_long_words, simple_wrap, split_text, text_wrap,
+};
+pub use theme::{ThemeFormat, is_valid_color};
+pub use trace::{PanelStyle, TraceLine, TraceProfile};
+#[cfg(feature = "serde")]
+pub use transcript::{
+    RecordingWriter, TRANSCRIPT_VERSION, Transcript, TranscriptChunk, TranscriptMessage,
+    TranscriptRecorder,
+};
 
+use std::borrow::Cow;
 use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
 
-use serde::{Deserialize, Serialize};
 use streamdown_ansi::codes::{
-    BOLD_OFF, BOLD_ON, DIM_ON, ITALIC_OFF, ITALIC_ON, RESET, STRIKEOUT_OFF, STRIKEOUT_ON,
+    BOLD_OFF, BOLD_ON, DIM_OFF, DIM_ON, ITALIC_OFF, ITALIC_ON, RESET, STRIKEOUT_OFF, STRIKEOUT_ON,
     UNDERLINE_OFF, UNDERLINE_ON,
 };
-use streamdown_ansi::color::hex2rgb;
+use streamdown_ansi::color::{ansi2hex, downgrade_colors};
+use streamdown_ansi::utils::{
+    ansi_collapse, extract_ansi_codes, has_escape, visible, visible_length,
+};
+use streamdown_ansi::width::{OracleScope, WidthOracle, char_width};
+use streamdown_config::ComputedStyle;
 
 /// Generate foreground color escape code from color string.
 ///
-/// Accepts either a Colodore preset name (e.g., "yellow") or a hex value (e.g., "#edf171").
+/// Accepts anything [`Color::parse`] does: a Colodore preset name (e.g.,
+/// "yellow"), a hex value (e.g., "#edf171"), a basic terminal color name
+/// (e.g., "bright_blue", or "ansi:red" where the name is also a preset) or
+/// a 256-color index (e.g., "ansi256:203").
 pub fn fg_color(color: &str) -> String {
-    let hex = colors::resolve_color(color);
-    if let Some((r, g, b)) = hex2rgb(hex) {
-        format!("\x1b[38;2;{};{};{}m", r, g, b)
-    } else {
-        String::new()
-    }
+    Color::parse(color).map_or_else(String::new, Color::fg)
 }
 
 /// Generate background color escape code from color string.
 ///
-/// Accepts either a Colodore preset name (e.g., "yellow") or a hex value (e.g., "#edf171").
+/// Accepts the same forms as [`fg_color`].
 pub fn bg_color(color: &str) -> String {
-    let hex = colors::resolve_color(color);
-    if let Some((r, g, b)) = hex2rgb(hex) {
-        format!("\x1b[48;2;{};{};{}m", r, g, b)
-    } else {
-        String::new()
+    Color::parse(color).map_or_else(String::new, Color::bg)
+}
+
+/// Write `s`, without its escape sequences in plain text mode (None) and
+/// with colors downgraded to the terminal's depth otherwise.
+fn emit(writer: &mut impl Write, depth: Option<ColorDepth>, s: &str) -> std::io::Result<()> {
+    match depth {
+        None if has_escape(s) => writer.write_all(visible(s).as_bytes()),
+        Some(depth) => writer.write_all(downgrade_colors(s, depth).as_bytes()),
+        None => writer.write_all(s.as_bytes()),
+    }
+}
+
+/// Write the line `s` like [`emit`]; in plain text and copy mode also drop
+/// the padding left where backgrounds would have been. `gutter` is drawn at
+/// the end of the first line of `s` unless in plain text mode.
+fn emit_line(
+    writer: &mut impl Write,
+    depth: Option<ColorDepth>,
+    s: &str,
+    gutter: &str,
+    copy_mode: bool,
+) -> std::io::Result<()> {
+    let Some(depth) = depth else {
+        return writeln!(writer, "{}", visible(s).trim_end_matches(' '));
+    };
+    let mut s = downgrade_colors(s, depth);
+    if copy_mode {
+        s = copy::trim_padding(&s).into();
     }
+    let (first, rest) = s.split_at(memchr::memchr(b'\n', s.as_bytes()).unwrap_or(s.len()));
+    writeln!(writer, "{}{}{}", first, gutter, rest)
 }
-use streamdown_parser::{InlineElement, ParseEvent};
-use streamdown_syntax::Highlighter;
+use streamdown_parser::{InlineElement, ParseEvent, number_to_superscript};
+#[cfg(feature = "syntax-highlighting")]
+use streamdown_syntax::{Highlighter, LineState};
 
 /// Render style configuration.
 ///
 /// Contains color values for each styled element.
 /// Colors can be specified as hex values (e.g., "#edf171") or as
 /// Colodore preset names (e.g., "yellow", "cyan").
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Bold, italic, underline and dim are set per element with
+/// [`TextAttributes`].
+///
+/// Themes can also be loaded from TOML/JSON files (`serde` feature), see
+/// [`theme`], or built with colors checked up front, see
+/// [`RenderStyle::builder`].
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 pub struct RenderStyle {
     // Heading colors
     /// Color for h1 headings
@@ -111,6 +288,16 @@ pub struct RenderStyle {
     // Lists
     /// Color for list bullet markers
     pub bullet: String,
+    /// Bullet glyphs by nesting depth, cycled (empty = [`BULLETS`])
+    pub bullets: Vec<String>,
+    /// Color for checked task list boxes
+    pub task_checked: String,
+    /// Color for unchecked task list boxes
+    pub task_unchecked: String,
+    /// Glyph for checked task list boxes
+    pub task_checked_glyph: String,
+    /// Glyph for unchecked task list boxes
+    pub task_unchecked_glyph: String,
 
     // Tables
     /// Background color for table headers
@@ -133,6 +320,40 @@ pub struct RenderStyle {
     pub image_marker: String,
     /// Color for footnote markers
     pub footnote: String,
+
+    // Collapsible content
+    /// Color for spoiler markers and concealed spoilers
+    pub spoiler: String,
+    /// Color for `<details>` summary markers
+    pub details_marker: String,
+    /// Color of keyboard key caps (`<kbd>`)
+    pub kbd: String,
+
+    // Diffs
+    /// Color for lines added in `diff` blocks
+    pub diff_add: String,
+    /// Color for lines removed in `diff` blocks
+    pub diff_remove: String,
+
+    // Text attributes
+    /// Attributes of h1-h6 heading text
+    pub heading_attrs: [TextAttributes; 6],
+    /// Attributes of blockquote text
+    pub blockquote_attrs: TextAttributes,
+    /// Attributes of think block text
+    pub think_attrs: TextAttributes,
+    /// Attributes of link text
+    pub link_attrs: TextAttributes,
+    /// Attributes of blockquote lines quoting earlier transcript text
+    pub quoted_attrs: TextAttributes,
+
+    // Heading layout
+    /// Alignment of h1-h6 headings
+    pub heading_align: [HeadingAlign; 6],
+    /// Underline glyph drawn below h1-h6 headings (empty = none)
+    pub heading_underline: [String; 6],
+    /// Extent of h1-h6 underlines
+    pub heading_underline_span: [UnderlineSpan; 6],
 }
 
 impl Default for RenderStyle {
@@ -148,6 +369,11 @@ impl Default for RenderStyle {
             code_bg: "black".to_string(),
             code_label: "cyan".to_string(),
             bullet: "cyan".to_string(),
+            bullets: BULLETS.map(String::from).to_vec(),
+            task_checked: "green".to_string(),
+            task_unchecked: "grey".to_string(),
+            task_checked_glyph: TASK_GLYPHS[0].to_string(),
+            task_unchecked_glyph: TASK_GLYPHS[1].to_string(),
             table_header_bg: "blue".to_string(),
             table_border: "grey".to_string(),
             blockquote_border: "grey".to_string(),
@@ -156,28 +382,159 @@ impl Default for RenderStyle {
             link_url: "grey".to_string(),
             image_marker: "cyan".to_string(),
             footnote: "cyan".to_string(),
+            spoiler: "grey".to_string(),
+            details_marker: "cyan".to_string(),
+            kbd: "light_grey".to_string(),
+            diff_add: "green".to_string(),
+            diff_remove: "red".to_string(),
+            heading_attrs: [
+                TextAttributes::BOLD,
+                TextAttributes::BOLD,
+                TextAttributes::BOLD,
+                TextAttributes::BOLD,
+                TextAttributes::PLAIN,
+                TextAttributes::PLAIN,
+            ],
+            blockquote_attrs: TextAttributes::PLAIN,
+            think_attrs: TextAttributes::PLAIN,
+            link_attrs: TextAttributes::UNDERLINE,
+            quoted_attrs: TextAttributes {
+                italic: true,
+                dim: true,
+                ..TextAttributes::PLAIN
+            },
+            heading_align: [
+                HeadingAlign::Center,
+                HeadingAlign::Center,
+                HeadingAlign::Left,
+                HeadingAlign::Left,
+                HeadingAlign::Left,
+                HeadingAlign::Left,
+            ],
+            heading_underline: Default::default(),
+            heading_underline_span: Default::default(),
+        }
+    }
+}
+
+impl RenderStyle {
+    /// Default style for terminals with a light background.
+    pub fn light() -> Self {
+        Self {
+            h1: "black".to_string(),
+            h2: "orange".to_string(),
+            h3: "#2e7d32".to_string(),
+            h4: "blue".to_string(),
+            h5: "dark_grey".to_string(),
+            h6: "grey".to_string(),
+            code_bg: "#eeeeee".to_string(),
+            code_label: "blue".to_string(),
+            bullet: "blue".to_string(),
+            task_checked: "#2e7d32".to_string(),
+            table_header_bg: "#d8d8ef".to_string(),
+            hr: "light_grey".to_string(),
+            link_url: "blue".to_string(),
+            image_marker: "purple".to_string(),
+            footnote: "purple".to_string(),
+            details_marker: "blue".to_string(),
+            kbd: "dark_grey".to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Build a render style from the HSV-derived config palette.
+    ///
+    /// Each palette slot ("r;g;bm") is converted to a hex color; slots that
+    /// fail to parse keep the Colodore default.
+    pub fn from_computed(computed: &ComputedStyle) -> Self {
+        let defaults = Self::default();
+        let pick =
+            |ansi: &str, fallback: &str| ansi2hex(ansi).unwrap_or_else(|| fallback.to_string());
+
+        Self {
+            h1: pick(&computed.bright, &defaults.h1),
+            h2: pick(&computed.head, &defaults.h2),
+            h3: pick(&computed.head, &defaults.h3),
+            h4: pick(&computed.symbol, &defaults.h4),
+            h5: pick(&computed.mid, &defaults.h5),
+            h6: pick(&computed.grey, &defaults.h6),
+            code_bg: pick(&computed.dark, &defaults.code_bg),
+            code_label: pick(&computed.symbol, &defaults.code_label),
+            bullet: pick(&computed.symbol, &defaults.bullet),
+            task_unchecked: pick(&computed.grey, &defaults.task_unchecked),
+            table_header_bg: pick(&computed.mid, &defaults.table_header_bg),
+            table_border: pick(&computed.grey, &defaults.table_border),
+            blockquote_border: pick(&computed.grey, &defaults.blockquote_border),
+            think_border: pick(&computed.grey, &defaults.think_border),
+            hr: pick(&computed.grey, &defaults.hr),
+            link_url: pick(&computed.bright, &defaults.link_url),
+            image_marker: pick(&computed.symbol, &defaults.image_marker),
+            footnote: pick(&computed.symbol, &defaults.footnote),
+            spoiler: pick(&computed.grey, &defaults.spoiler),
+            details_marker: pick(&computed.symbol, &defaults.details_marker),
+            kbd: pick(&computed.mid, &defaults.kbd),
+            ..defaults
+        }
+    }
+
+    /// Get the bullet glyph for a list nesting depth (0 = outermost).
+    pub fn bullet_glyph(&self, level: usize) -> &str {
+        if self.bullets.is_empty() {
+            BULLETS[level % BULLETS.len()]
+        } else {
+            &self.bullets[level % self.bullets.len()]
         }
     }
+
+    /// Switch to ASCII-only list glyphs ([`ASCII_BULLETS`] and
+    /// [`ASCII_TASK_GLYPHS`]).
+    pub fn ascii_glyphs(mut self) -> Self {
+        self.bullets = ASCII_BULLETS.map(String::from).to_vec();
+        self.task_checked_glyph = ASCII_TASK_GLYPHS[0].to_string();
+        self.task_unchecked_glyph = ASCII_TASK_GLYPHS[1].to_string();
+        self
+    }
 }
 
 /// Terminal renderer for markdown.
 pub struct Renderer<W: Write> {
     /// Output writer
-    writer: W,
+    writer: batch::BatchWriter<W>,
     /// Terminal width
     width: usize,
     /// Syntax highlighter
+    #[cfg(feature = "syntax-highlighting")]
     highlighter: Highlighter,
+    /// Highlight state for the current code block (None = plain)
+    #[cfg(feature = "syntax-highlighting")]
+    code_highlight: Option<LineState>,
     /// Render style
     style: RenderStyle,
     /// Feature flags
     features: RenderFeatures,
+    /// What the terminal can display
+    capabilities: TerminalCapabilities,
+    /// Output options
+    options: RendererOptions,
+    /// Whether colors are written, resolved from the options
+    colors_enabled: bool,
+    /// What the document is allowed to do
+    policy: Policy,
     /// Current column position
     column: usize,
+    /// SGR codes left open by the flowed text of the current row, closed
+    /// before a break and reopened after the next row's margin
+    flow_style: Vec<String>,
     /// Current code language
     code_language: Option<String>,
     /// Raw code buffer (for clipboard/savebrace)
     code_buffer: String,
+    /// Number of the last line rendered in the current code block
+    code_line_number: usize,
+    /// Whether the current diff block has reached its first hunk header
+    diff_in_hunk: bool,
+    /// Link URLs of the current line, drawn as QR codes after it
+    qr_pending: Vec<String>,
     /// Table state
     table_state: TableState,
     /// List state
@@ -186,24 +543,215 @@ pub struct Renderer<W: Write> {
     in_blockquote: bool,
     /// Blockquote depth
     blockquote_depth: usize,
+    /// Open `<details>` blocks; `true` where the block is collapsed
+    details_stack: Vec<bool>,
+    /// Open trace panels, outermost first
+    panels: Vec<PanelStyle>,
+    /// Agent trace profile (None = plain markdown)
+    trace: Option<TraceProfile>,
+    /// Whether nothing has been written on the current output line
+    at_line_start: bool,
+    /// Paragraph line held until it ends, to be aligned or reordered (see
+    /// [`RenderFeatures::paragraph_align`] and [`RenderFeatures::bidi`])
+    held_line: Option<String>,
+    /// Whether the current row shows a partial input line
+    partial_drawn: bool,
+    /// What the partial input line's row shows
+    partial_row: live::LinePainter,
+    /// The partial input line, parsed as far as it has settled
+    partial_line: live::PartialLine,
+    /// Length of the partial code line up to its last progress state drawn
+    /// in place
+    progress_drawn: usize,
+    /// Per-line block metadata (None = not recorded)
+    line_meta: Option<Vec<LineMeta>>,
+    /// Block that output is currently attributed to
+    current_block: LineMeta,
+    /// ID of each block so far, by ordinal
+    block_ids: Vec<BlockId>,
+    /// Whether the current block has ended (the next one gets a new ordinal)
+    block_ended: bool,
+    /// Number of blocks entered so far
+    blocks_started: usize,
+    /// Width to switch to when the next block starts
+    pending_width: Option<usize>,
+    /// Style to switch to when the next block starts
+    pending_style: Option<RenderStyle>,
+    /// Width of the annotation gutter (0 = none)
+    gutter: usize,
+    /// Annotation for the next output line
+    annotation: Option<String>,
+    /// Number of display rows written so far
+    lines_written: usize,
+    /// Columns written on the current output row, to count the rows a line
+    /// wider than the terminal wraps onto
+    row_width: usize,
+    /// Output limits (None = unlimited)
+    quota: Option<RenderQuota>,
+    /// Lines and blocks already used when the quota was set
+    quota_start: (usize, usize),
+    /// Events held back after the quota ran out
+    truncation: Option<quota::Truncation>,
+    /// Message statistics (None = not collected)
+    stats: Option<MessageStats>,
+    /// Desktop notification state (None = no notifications)
+    notifier: Option<notify::Notifier>,
+    /// Headings enclosing the current position
+    breadcrumb: Breadcrumb,
+    /// Pinned header region (None = not pinned)
+    pinned: Option<PinnedHeader>,
+    /// Collapsible or processed block held until its end
+    held_block: Option<collapse::HeldBlock>,
+    /// Code block drawn as it streams until it passes the collapse threshold
+    streamed_block: Option<collapse::HeldBlock>,
+    /// Events of blocks drawn as summaries, for [`Renderer::expand`]
+    collapsed: Vec<Vec<ParseEvent>>,
+    /// Held block given up on by the block timeout, drawn as it streams
+    /// until its end
+    timed_out_block: Option<collapse::HeldBlock>,
+    /// Section progress in outline mode
+    outline: outline::OutlineFilter,
+    /// How long a block may be held before it is drawn as is (None = no limit)
+    block_timeout: Option<Duration>,
+    /// Processors that may draw whole blocks, tried in order
+    block_processors: Vec<Arc<dyn BlockProcessor>>,
+    /// Handlers for fences of data languages, tried before the processors
+    fences: Arc<FenceHandlers>,
+    /// Canonical fence language names by alias
+    language_aliases: Arc<LanguageAliases>,
+    /// Where overlong words are broken when hyphenating
+    hyphenator: Arc<dyn Hyphenator>,
+    /// Width model for every column count while drawing
+    width_oracle: WidthOracle,
+    /// Handlers for inline extensions in text
+    inline_handlers: Arc<InlineHandlers>,
+    /// Passes marking terms in prose text
+    text_passes: Arc<TextPasses>,
+    /// Maps span metadata to style modulation
+    meta_styler: Option<Arc<dyn MetaStyler>>,
+    /// Metadata of the inline spans being drawn
+    span_meta: Option<SpanMeta>,
+    /// Start and end codes of the styler's modulation of the event being
+    /// drawn, wrapped around each piece of its flowed text
+    modulation: Option<(String, String)>,
+    /// Link URLs awaiting endnotes, in order of first use
+    link_notes: Vec<String>,
+    /// Distinct link URLs of the document, numbered from 1
+    links: Vec<String>,
+    /// Launches URLs for [`open_link`](Self::open_link)
+    link_opener: LinkOpener,
+    /// Formatter for table body cells
+    cell_formatter: Option<Arc<dyn CellFormatter>>,
+    /// Recognizes blockquote lines quoting earlier messages
+    quote_matcher: Option<Arc<dyn QuoteMatcher>>,
+    /// Cache of wrapped paragraphs, reused across reflows
+    wrap_cache: WrapCache,
+    /// Events kept for re-rendering (None = not retained)
+    retained: Option<Vec<ParseEvent>>,
+    /// Output line where the retained document starts
+    retained_from: usize,
+    /// Ordinal of the first retained block
+    retained_block_from: usize,
+    /// Events of the table or code block being streamed, kept to repaint it
+    /// in place
+    live_block: Option<Vec<ParseEvent>>,
+    /// Output line where the live block starts
+    live_block_from: usize,
+    /// Terminal height in rows, bounding in-place repaints (None = unknown,
+    /// never repaint)
+    height: Option<usize>,
+    /// Arena for per-event temporary strings
+    #[cfg(feature = "arena")]
+    arena: LineArena,
 }
 
 impl<W: Write> Renderer<W> {
     /// Create a new renderer with default style.
     pub fn new(writer: W, width: usize) -> Self {
         Self {
-            writer,
+            writer: batch::BatchWriter::new(writer),
             width,
+            #[cfg(feature = "syntax-highlighting")]
             highlighter: Highlighter::default(),
+            #[cfg(feature = "syntax-highlighting")]
+            code_highlight: None,
             style: RenderStyle::default(),
             features: RenderFeatures::default(),
+            capabilities: TerminalCapabilities::default(),
+            options: RendererOptions::default(),
+            colors_enabled: true,
+            policy: Policy::default(),
             column: 0,
+            flow_style: Vec::new(),
             code_language: None,
             code_buffer: String::new(),
+            code_line_number: 0,
+            diff_in_hunk: false,
+            qr_pending: Vec::new(),
             table_state: TableState::new(),
             list_state: ListState::new(),
             in_blockquote: false,
             blockquote_depth: 0,
+            details_stack: Vec::new(),
+            panels: Vec::new(),
+            trace: None,
+            at_line_start: true,
+            held_line: None,
+            partial_drawn: false,
+            partial_row: live::LinePainter::new(),
+            partial_line: live::PartialLine::new(),
+            progress_drawn: 0,
+            line_meta: None,
+            current_block: LineMeta {
+                kind: BlockKind::Paragraph,
+                block: 0,
+            },
+            block_ids: Vec::new(),
+            block_ended: false,
+            blocks_started: 0,
+            pending_width: None,
+            pending_style: None,
+            gutter: 0,
+            annotation: None,
+            lines_written: 0,
+            row_width: 0,
+            quota: None,
+            quota_start: (0, 0),
+            truncation: None,
+            stats: None,
+            notifier: None,
+            breadcrumb: Breadcrumb::new(),
+            pinned: None,
+            held_block: None,
+            streamed_block: None,
+            collapsed: Vec::new(),
+            timed_out_block: None,
+            outline: outline::OutlineFilter::default(),
+            block_timeout: None,
+            block_processors: Vec::new(),
+            fences: Arc::new(FenceHandlers::with_builtins()),
+            language_aliases: Arc::new(LanguageAliases::with_builtins()),
+            hyphenator: Arc::new(WidthHyphenator),
+            width_oracle: WidthOracle::new(),
+            inline_handlers: Arc::new(InlineHandlers::new()),
+            text_passes: Arc::new(TextPasses::new()),
+            meta_styler: None,
+            span_meta: None,
+            modulation: None,
+            link_notes: Vec::new(),
+            links: Vec::new(),
+            link_opener: LinkOpener::default(),
+            cell_formatter: None,
+            quote_matcher: None,
+            wrap_cache: WrapCache::new(),
+            retained: None,
+            retained_from: 0,
+            retained_block_from: 0,
+            live_block: None,
+            live_block_from: 0,
+            height: None,
+            #[cfg(feature = "arena")]
+            arena: LineArena::new(),
         }
     }
 
@@ -222,13 +770,55 @@ impl<W: Write> Renderer<W> {
     }
 
     /// Set the syntax highlighting theme.
+    ///
+    /// Has no effect without the `syntax-highlighting` feature.
     pub fn set_theme(&mut self, theme: &str) {
+        #[cfg(feature = "syntax-highlighting")]
         self.highlighter.set_theme(theme);
+        #[cfg(not(feature = "syntax-highlighting"))]
+        let _ = theme;
+    }
+
+    /// Set the syntax highlighting theme to a built-in theme name or the
+    /// path of a `.tmTheme` file (a Sublime Text or bat color scheme),
+    /// independently of the [`RenderStyle`].
+    ///
+    /// Fails if the theme file cannot be loaded. Has no effect without the
+    /// `syntax-highlighting` feature.
+    pub fn set_highlight_theme(&mut self, theme: &str) -> RenderResult<()> {
+        if !theme::is_tm_theme(std::path::Path::new(theme)) {
+            self.set_theme(theme);
+            return Ok(());
+        }
+        #[cfg(feature = "syntax-highlighting")]
+        self.highlighter
+            .load_theme_file(std::path::Path::new(theme))
+            .map_err(|e| match e {
+                streamdown_core::StreamdownError::Config(message) => {
+                    RenderError::InvalidTheme(message)
+                }
+                other => RenderError::InvalidTheme(other.to_string()),
+            })?;
+        Ok(())
+    }
+
+    /// Enable or disable syntax highlighting of code blocks.
+    pub fn set_highlight(&mut self, enabled: bool) {
+        self.features.highlight = enabled;
     }
 
     /// Set the render style.
+    ///
+    /// Mid-stream the switch waits until the current block has ended, so a
+    /// block being drawn (a table, a list) keeps one look; this lets a host
+    /// toggle light and dark themes while a response streams in.
     pub fn set_style(&mut self, style: RenderStyle) {
-        self.style = style;
+        if self.block_ended || self.blocks_started == 0 {
+            self.pending_style = None;
+            self.style = style;
+        } else {
+            self.pending_style = Some(style);
+        }
     }
 
     /// Set the feature flags.
@@ -236,6 +826,44 @@ impl<W: Write> Renderer<W> {
         self.features = features;
     }
 
+    /// Set the terminal capabilities (e.g. from [`TerminalCapabilities::detect`]).
+    pub fn set_capabilities(&mut self, capabilities: TerminalCapabilities) {
+        self.capabilities = capabilities;
+    }
+
+    /// Set the output options.
+    ///
+    /// [`ColorChoice::Auto`] is resolved against the environment here.
+    pub fn set_options(&mut self, options: RendererOptions) {
+        self.colors_enabled = options.color.enabled();
+        self.options = options;
+    }
+
+    /// Get the output options.
+    pub fn options(&self) -> &RendererOptions {
+        &self.options
+    }
+
+    /// Set what the document is allowed to do (e.g. [`Policy::untrusted`]).
+    pub fn set_policy(&mut self, policy: Policy) {
+        self.policy = policy;
+    }
+
+    /// Get the unsafe-content policy.
+    pub fn policy(&self) -> &Policy {
+        &self.policy
+    }
+
+    /// Set the agent trace profile used by hosts to classify input lines.
+    pub fn set_trace_profile(&mut self, profile: Option<TraceProfile>) {
+        self.trace = profile;
+    }
+
+    /// Get the agent trace profile, if any.
+    pub fn trace_profile(&self) -> Option<&TraceProfile> {
+        self.trace.as_ref()
+    }
+
     /// Enable or disable pretty code block padding.
     pub fn set_pretty_pad(&mut self, enabled: bool) {
         self.features.pretty_pad = enabled;
@@ -251,11 +879,58 @@ impl<W: Write> Renderer<W> {
         self.features.savebrace = enabled;
     }
 
-    /// Get the current width.
+    /// Switch copy mode on or off (see [`copy`]); takes effect from the
+    /// next line drawn, so a host can toggle it while a response streams.
+    pub fn set_copy_mode(&mut self, enabled: bool) {
+        self.features.copy_mode = enabled;
+    }
+
+    /// Get the paragraph wrap cache.
+    pub fn wrap_cache(&self) -> &WrapCache {
+        &self.wrap_cache
+    }
+
+    /// Drop all cached paragraph wraps.
+    pub fn clear_wrap_cache(&mut self) {
+        self.wrap_cache.clear();
+    }
+
+    /// Set the terminal width used for subsequent output.
+    pub fn set_width(&mut self, width: usize) {
+        self.width = width.saturating_sub(gutter_columns(self.gutter));
+    }
+
+    /// Change the width once the current block has ended, so a block being
+    /// drawn (a code frame, a table) keeps its columns. Meant for terminal
+    /// resizes mid-stream; [`set_width`](Self::set_width) applies at once.
+    pub fn resize(&mut self, width: usize) {
+        if self.block_ended || self.blocks_started == 0 {
+            self.pending_width = None;
+            self.set_width(width);
+        } else {
+            self.pending_width = Some(width);
+        }
+    }
+
+    /// Get the current width (excluding the annotation gutter).
     pub fn width(&self) -> usize {
         self.width
     }
 
+    /// Set the terminal height in rows (None = unknown).
+    ///
+    /// Live blocks (see [`RenderFeatures::live_blocks`]) are only repainted
+    /// while their first row is still on screen, so without a height they
+    /// are always drawn by appending.
+    pub fn set_height(&mut self, height: Option<usize>) {
+        self.height = height;
+    }
+
+    /// Get the terminal height, if known.
+    pub fn height(&self) -> Option<usize> {
+        self.height
+    }
+
     /// Get the style.
     pub fn style(&self) -> &RenderStyle {
         &self.style
@@ -266,582 +941,4834 @@ impl<W: Write> Renderer<W> {
         &self.features
     }
 
-    /// Calculate the left margin based on current state.
-    fn left_margin(&self) -> String {
-        if self.in_blockquote {
-            let border = format!("{}│{} ", fg_color(&self.style.blockquote_border), RESET);
-            border.repeat(self.blockquote_depth)
-        } else {
-            String::new()
-        }
+    /// Get a mutable reference to the output writer.
+    pub fn writer_mut(&mut self) -> &mut W {
+        &mut self.writer.inner
     }
 
-    /// Calculate the current available width.
-    fn current_width(&self) -> usize {
-        let margin_width = if self.in_blockquote {
-            self.blockquote_depth * 3 // "│ " = 2 chars per level, plus color codes
-        } else {
-            0
-        };
-        self.width.saturating_sub(margin_width)
+    /// Consume the renderer, returning the output writer.
+    ///
+    /// A pinned header's scroll region is released first if
+    /// [`finish`](Self::finish) has not already done so.
+    pub fn into_writer(mut self) -> W {
+        if let Some(old) = self.pinned.take() {
+            let _ = write!(self.writer, "{}", old.leave_sequence());
+            let _ = self.writer.flush();
+        }
+        self.writer.inner
     }
 
-    /// Write a string to the output.
-    fn write(&mut self, s: &str) -> std::io::Result<()> {
-        write!(self.writer, "{}", s)
+    /// Get the terminal capabilities.
+    pub fn capabilities(&self) -> &TerminalCapabilities {
+        &self.capabilities
     }
 
-    /// Write a line to the output.
-    fn writeln(&mut self, s: &str) -> std::io::Result<()> {
-        writeln!(self.writer, "{}", s)?;
-        self.column = 0;
-        Ok(())
+    /// Reserve `width` columns on the right for line annotations (0 = none).
+    ///
+    /// The gutter is taken from the current width. Annotations aren't drawn
+    /// in plain text mode.
+    pub fn set_gutter(&mut self, width: usize) {
+        let total = self.width + gutter_columns(self.gutter);
+        self.gutter = width;
+        self.set_width(total);
     }
 
-    /// Render a single parse event.
-    pub fn render_event(&mut self, event: &ParseEvent) -> std::io::Result<()> {
-        match event {
-            // === Inline elements ===
-            ParseEvent::Text(text) => {
-                // Decode HTML entities like &copy; -> ©
-                let decoded = streamdown_parser::decode_html_entities(text);
-                self.write(&decoded)?;
-                self.column += streamdown_ansi::utils::visible_length(&decoded);
-            }
-
-            ParseEvent::InlineCode(code) => {
-                let bg = bg_color(&self.style.code_bg);
-                self.write(&format!("{}{} {} {}", bg, DIM_ON, code, RESET))?;
-            }
+    /// Annotate the next output line (a timestamp, a source line number).
+    ///
+    /// Replaces an annotation not drawn yet; ignored without a gutter.
+    pub fn annotate(&mut self, text: impl Into<String>) {
+        if self.gutter > 0 {
+            self.annotation = Some(text.into());
+        }
+    }
 
-            ParseEvent::Bold(text) => {
-                self.write(&format!("{}{}{}", BOLD_ON, text, BOLD_OFF))?;
-            }
+    /// Color depth output is written in (None = plain text).
+    fn color_depth(&self) -> Option<ColorDepth> {
+        match (self.features.plain, self.colors_enabled) {
+            (true, _) => None,
+            (false, true) => Some(self.capabilities.colors),
+            (false, false) => Some(ColorDepth::Monochrome),
+        }
+    }
 
-            ParseEvent::Italic(text) => {
-                self.write(&format!("{}{}{}", ITALIC_ON, text, ITALIC_OFF))?;
-            }
+    /// Gutter cell for the line being ended, taking the annotation.
+    fn take_annotation(&mut self) -> String {
+        match self.annotation.take() {
+            Some(text) if !self.features.plain => gutter_cell(self.width, self.gutter, &text),
+            _ => String::new(),
+        }
+    }
 
-            ParseEvent::BoldItalic(text) => {
-                self.write(&format!(
-                    "{}{}{}{}{}",
-                    BOLD_ON, ITALIC_ON, text, ITALIC_OFF, BOLD_OFF
-                ))?;
-            }
+    /// Record block metadata for every output line.
+    ///
+    /// Off by default since it grows with the output; retained hosts (pagers,
+    /// TUIs) turn it on to navigate between blocks. Disabling clears it.
+    pub fn set_line_metadata(&mut self, enabled: bool) {
+        self.line_meta = enabled.then(Vec::new);
+    }
 
-            ParseEvent::Underline(text) => {
-                self.write(&format!("{}{}{}", UNDERLINE_ON, text, UNDERLINE_OFF))?;
-            }
+    /// Block metadata for each completed output line (empty if not recorded).
+    pub fn line_metadata(&self) -> &[LineMeta] {
+        self.line_meta.as_deref().unwrap_or_default()
+    }
 
-            ParseEvent::Strikeout(text) => {
-                self.write(&format!("{}{}{}", STRIKEOUT_ON, text, STRIKEOUT_OFF))?;
-            }
+    /// Block that following output is attributed to.
+    ///
+    /// Lets hosts consuming the event stream tag what they receive without
+    /// recording line metadata.
+    pub fn current_block(&self) -> LineMeta {
+        self.current_block
+    }
 
-            ParseEvent::Link { text, url } => {
-                // Render as: underlined text (url)
-                // Also include OSC 8 hyperlink for terminals that support it
-                let fg = fg_color(&self.style.link_url);
+    /// ID of the block with ordinal `block` ([`LineMeta::block`]).
+    pub fn block_id(&self, block: usize) -> Option<BlockId> {
+        self.block_ids.get(block).copied()
+    }
 
-                // OSC 8 start
-                self.write("\x1b]8;;")?;
-                self.write(url)?;
-                self.write("\x1b\\")?;
+    /// ID of the block being rendered, as far as it has arrived.
+    pub fn current_block_id(&self) -> Option<BlockId> {
+        self.block_ids.last().copied()
+    }
 
-                // Underlined text
-                self.write(&format!("{}{}{}", UNDERLINE_ON, text, UNDERLINE_OFF))?;
+    /// IDs of all blocks so far, by ordinal.
+    pub fn block_ids(&self) -> &[BlockId] {
+        &self.block_ids
+    }
 
-                // OSC 8 end
-                self.write("\x1b]8;;\x1b\\")?;
+    /// Attribute following output to a block of `kind`.
+    ///
+    /// Continues the current block if it is of the same kind and still open.
+    fn enter_block(&mut self, kind: BlockKind) {
+        if self.blocks_started == 0 || self.block_ended || self.current_block.kind != kind {
+            if self.blocks_started > 0 {
+                self.current_block.block += 1;
+            }
+            if let Some(width) = self.pending_width.take() {
+                self.set_width(width);
+            }
+            if let Some(style) = self.pending_style.take() {
+                self.style = style;
+            }
+            self.blocks_started += 1;
+            self.current_block.kind = kind;
+            self.block_ended = false;
+        }
+        if self.block_ids.len() == self.current_block.block {
+            self.block_ids
+                .push(BlockId::new(kind, self.current_block.block));
+        }
+    }
 
-                // Show URL in parentheses (dimmed)
-                self.write(&format!(" {}({}){}", fg, url, RESET))?;
+    /// Track block boundaries and IDs before rendering `event`.
+    fn track_block(&mut self, event: &ParseEvent) {
+        self.track_block_kind(event);
+        if let Some(id) = self.block_ids.last_mut() {
+            if let ParseEvent::CodeBlockStart { language, .. } = event {
+                id.feed(language.as_deref().unwrap_or_default().as_bytes());
             }
+            footer::for_each_text(event, |text, _| id.feed(text.as_bytes()));
+        }
+    }
 
-            ParseEvent::Image { alt, url: _ } => {
-                let fg = fg_color(&self.style.image_marker);
-                self.write(&format!("{}[\u{1F5BC} {}]{}", fg, alt, RESET))?;
+    /// Track block boundaries before rendering `event`.
+    fn track_block_kind(&mut self, event: &ParseEvent) {
+        let kind = match event {
+            ParseEvent::Heading { .. } => {
+                self.block_ended = true;
+                BlockKind::Heading
+            }
+            ParseEvent::HorizontalRule => {
+                self.block_ended = true;
+                BlockKind::Rule
+            }
+            ParseEvent::DetailsStart { .. } => {
+                self.block_ended = true;
+                BlockKind::Details
+            }
+            ParseEvent::CodeBlockStart { .. } => {
+                self.block_ended = true;
+                BlockKind::Code
+            }
+            ParseEvent::TableHeader(_) => {
+                self.block_ended = true;
+                BlockKind::Table
             }
+            ParseEvent::ThinkBlockStart => {
+                self.block_ended = true;
+                BlockKind::Think
+            }
+            ParseEvent::ListItem { .. } => BlockKind::List,
+            ParseEvent::BlockquoteStart { .. } | ParseEvent::BlockquoteLine(_) => {
+                BlockKind::Blockquote
+            }
+            ParseEvent::EmptyLine
+            | ParseEvent::Newline
+            | ParseEvent::DetailsEnd
+            | ParseEvent::CodeBlockLanguage(_)
+            | ParseEvent::CodeBlockLine(_)
+            | ParseEvent::CodeBlockEnd
+            | ParseEvent::TableRow(_)
+            | ParseEvent::TableSeparator
+            | ParseEvent::TableEnd
+            | ParseEvent::ListEnd
+            | ParseEvent::BlockquoteEnd
+            | ParseEvent::ThinkBlockLine(_)
+            | ParseEvent::ThinkBlockEnd => return,
+            // Inline content starts or continues a paragraph
+            _ => BlockKind::Paragraph,
+        };
+        self.enter_block(kind);
+    }
 
-            ParseEvent::Footnote(superscript) => {
-                let fg = fg_color(&self.style.footnote);
-                self.write(&format!("{}{}{}", fg, superscript, RESET))?;
+    /// Mark block ends after rendering `event`.
+    fn end_block(&mut self, event: &ParseEvent) {
+        match event {
+            ParseEvent::Heading { .. }
+            | ParseEvent::HorizontalRule
+            | ParseEvent::DetailsStart { .. }
+            | ParseEvent::CodeBlockEnd
+            | ParseEvent::TableEnd
+            | ParseEvent::ListEnd
+            | ParseEvent::BlockquoteEnd
+            | ParseEvent::ThinkBlockEnd => self.block_ended = true,
+            ParseEvent::EmptyLine if self.current_block.kind == BlockKind::Paragraph => {
+                self.block_ended = true
             }
+            _ => {}
+        }
+    }
 
-            // === Block elements ===
-            ParseEvent::Heading { level, content } => {
-                let lines = render_heading(
-                    *level,
-                    content,
-                    self.current_width(),
-                    &self.left_margin(),
-                    &self.style,
-                );
-                for line in lines {
-                    self.writeln(&line)?;
-                }
+    /// Record the display rows completed by writing `s`, which ends the
+    /// current row if `ends_row`. A line wider than the terminal takes a row
+    /// for each time it wraps.
+    fn record_text(&mut self, s: &str, ends_row: bool) {
+        let mut rows = 0;
+        let mut segments = s.split('\n').peekable();
+        while let Some(segment) = segments.next() {
+            self.row_width += visible_length(segment);
+            if segments.peek().is_some() || ends_row {
+                rows += self.row_width.div_ceil(self.width.max(1)).max(1);
+                self.row_width = 0;
             }
+        }
+        self.record_lines(rows);
+    }
 
-            ParseEvent::CodeBlockStart { language, .. } => {
-                self.code_language = language.clone();
-                self.code_buffer.clear();
+    /// Record metadata for `count` completed output rows.
+    fn record_lines(&mut self, count: usize) {
+        self.lines_written += count;
+        if let Some(meta) = &mut self.line_meta {
+            meta.extend(std::iter::repeat_n(self.current_block, count));
+        }
+    }
 
-                let lines = code::render_code_start(
-                    language.as_deref(),
-                    self.current_width(),
-                    &self.left_margin(),
-                    &self.style,
-                    self.features.pretty_pad,
-                );
-                for line in lines {
-                    self.writeln(&line)?;
-                }
-            }
+    /// Limit how much is rendered from now on (None = unlimited).
+    ///
+    /// Events after the quota runs out are held back until
+    /// [`resume`](Self::resume). Setting a quota doesn't release events
+    /// already held.
+    pub fn set_quota(&mut self, quota: Option<RenderQuota>) {
+        self.quota = quota;
+        self.quota_start = (self.lines_written, self.blocks_started);
+    }
 
-            ParseEvent::CodeBlockLine(line) => {
-                // Buffer raw code for clipboard/savebrace
-                if !self.code_buffer.is_empty() {
-                    self.code_buffer.push('\n');
-                }
-                self.code_buffer.push_str(line);
+    /// Whether output has been truncated by the quota.
+    pub fn is_truncated(&self) -> bool {
+        self.truncation.is_some()
+    }
 
-                let lang = self.code_language.as_deref().unwrap_or("text");
-                let highlighted = self.highlighter.highlight(line, Some(lang));
+    /// Number of blocks held back by the quota so far.
+    pub fn remaining_blocks(&self) -> usize {
+        self.truncation
+            .as_ref()
+            .map_or(0, |t| t.remaining_blocks(self.blocks_started))
+    }
 
-                // Render with background
-                let bg = bg_color(&self.style.code_bg);
-                let margin = self.left_margin();
-                let trimmed = highlighted.trim_end();
-                let padding_needed = self
-                    .current_width()
-                    .saturating_sub(streamdown_ansi::utils::visible_length(trimmed));
+    /// Write a "truncated, N blocks remaining" line if output was truncated.
+    pub fn render_truncation_trailer(&mut self) -> std::io::Result<()> {
+        let _scope = OracleScope::new(&self.width_oracle);
+        let remaining = self.remaining_blocks();
+        if !self.is_truncated() || remaining == 0 {
+            return Ok(());
+        }
+        if !self.at_line_start {
+            self.writeln("")?;
+        }
+        let margin = self.left_margin();
+        let plural = if remaining == 1 { "" } else { "s" };
+        let trailer = format!("… truncated, {} block{} remaining", remaining, plural);
+        self.writeln_parts(&[&margin, DIM_ON, &trailer, RESET])?;
+        self.writer.flush()
+    }
 
-                self.writeln(&format!(
-                    "{}{}{}{}{}{}",
-                    margin,
-                    bg,
-                    trimmed,
-                    bg,
-                    " ".repeat(padding_needed),
-                    RESET
-                ))?;
-            }
+    /// Enable or disable collecting message statistics.
+    ///
+    /// Enabling starts a new message; see [`footer`].
+    pub fn set_stats(&mut self, enabled: bool) {
+        self.stats = enabled.then(MessageStats::new);
+    }
 
-            ParseEvent::CodeBlockEnd => {
-                let lines = code::render_code_end(
-                    self.current_width(),
-                    &self.left_margin(),
-                    &self.style,
-                    self.features.pretty_pad,
-                );
-                for line in lines {
-                    self.writeln(&line)?;
-                }
+    /// Get the message statistics, if collected (e.g. to supply a token count).
+    pub fn stats_mut(&mut self) -> Option<&mut MessageStats> {
+        self.stats.as_mut()
+    }
 
-                // Handle clipboard integration (OSC 52)
-                if self.features.clipboard && !self.code_buffer.is_empty() {
-                    let _ = copy_to_clipboard(&self.code_buffer, &mut self.writer);
-                }
+    /// Write a dim statistics footer if stats are collected.
+    pub fn render_stats_footer(&mut self) -> std::io::Result<()> {
+        let _scope = OracleScope::new(&self.width_oracle);
+        let Some(stats) = &self.stats else {
+            return Ok(());
+        };
+        let summary = stats.summary(stats.elapsed());
+        if !self.at_line_start {
+            self.writeln("")?;
+        }
+        let margin = self.left_margin();
+        self.writeln_parts(&[&margin, DIM_ON, &summary, RESET])?;
+        self.writer.flush()
+    }
 
-                // Handle savebrace
-                if self.features.savebrace && !self.code_buffer.is_empty() {
-                    let _ = savebrace(&self.code_buffer);
-                }
+    /// Set when to send desktop notifications (None = never).
+    ///
+    /// Notifications are only sent on terminals with a supported escape
+    /// (see [`TerminalCapabilities::notifications`]).
+    pub fn set_notifications(&mut self, options: Option<NotifyOptions>) {
+        self.notifier = options.map(notify::Notifier::new);
+    }
 
-                self.code_language = None;
-                self.code_buffer.clear();
-            }
+    /// Send the completion notification, if enabled and the document took
+    /// long enough.
+    pub fn notify_finished(&mut self) -> std::io::Result<()> {
+        let body = self
+            .notifier
+            .as_ref()
+            .and_then(|n| n.finished(self.lines_written));
+        match body {
+            Some(body) => self.send_notification(&body),
+            None => Ok(()),
+        }
+    }
 
-            ParseEvent::ListItem {
-                indent,
-                bullet,
-                content,
-            } => {
-                let lines = render_list_item(
-                    *indent,
-                    bullet,
-                    content,
-                    self.current_width(),
-                    &self.left_margin(),
-                    &self.style,
-                    &mut self.list_state,
-                );
-                for line in lines {
-                    self.writeln(&line)?;
-                }
-            }
+    /// Write a notification escape for the terminal's protocol.
+    fn send_notification(&mut self, body: &str) -> std::io::Result<()> {
+        if !self.policy.osc || self.features.plain {
+            return Ok(());
+        }
+        if let Some(seq) =
+            notification_sequence(self.capabilities.notifications, NOTIFY_TITLE, body)
+        {
+            write!(self.writer, "{}", seq)?;
+            self.writer.flush()?;
+        }
+        Ok(())
+    }
 
-            ParseEvent::ListEnd => {
-                list::render_list_end(&mut self.list_state);
-            }
+    /// Pin (or with None, unpin) a header row showing the current H1/H2.
+    ///
+    /// Pinning confines output to the rows below the header; see [`pin`].
+    /// [`finish`](Self::finish) releases the scroll region again.
+    pub fn set_pinned_header(&mut self, pinned: Option<PinnedHeader>) -> RenderResult<()> {
+        let _scope = OracleScope::new(&self.width_oracle);
+        if let Some(old) = self.pinned.take() {
+            write!(self.writer, "{}", old.leave_sequence())?;
+        }
+        if let Some(new) = pinned.filter(|_| !self.features.plain) {
+            write!(self.writer, "{}", new.enter_sequence())?;
+            self.pinned = Some(new);
+            self.column = 0;
+            self.flow_style.clear();
+            self.at_line_start = true;
+            self.draw_pinned_header()?;
+        }
+        Ok(self.writer.flush()?)
+    }
 
-            ParseEvent::TableHeader(cells) => {
-                self.table_state.reset();
-                self.table_state.is_header = true;
+    /// Limit how long a block is held back waiting for its end (collapsible
+    /// blocks, processed blocks and data fences); None waits indefinitely.
+    ///
+    /// Past the limit the held part is drawn as it would be without
+    /// holding, and the rest of the block follows as it arrives. The limit
+    /// is checked as events arrive and by
+    /// [`release_stale_block`](Self::release_stale_block).
+    pub fn set_block_timeout(&mut self, timeout: Option<Duration>) {
+        self.block_timeout = timeout;
+    }
 
-                let width = self.current_width();
-                let margin = self.left_margin();
-                let style = self.style.clone();
-                let lines =
-                    render_table_row(cells, &mut self.table_state, width, &margin, &style, false);
-                for line in lines {
-                    self.writeln(&line)?;
-                }
-            }
+    /// Draw a block that has been held longer than the block timeout, so a
+    /// stalled stream doesn't leave it hidden; hosts call this when input
+    /// goes quiet. Returns whether a block was released.
+    pub fn release_stale_block(&mut self) -> std::io::Result<bool> {
+        let _scope = OracleScope::new(&self.width_oracle);
+        let stale = match (&self.held_block, self.block_timeout) {
+            (Some(held), Some(timeout)) => held.since.elapsed() >= timeout,
+            _ => false,
+        };
+        if !stale {
+            return Ok(false);
+        }
+        let mut held = self.held_block.take().expect("held block");
+        for event in &held.events {
+            self.draw_event(event)?;
+        }
+        held.events.truncate(1);
+        self.timed_out_block = Some(held);
+        self.writer.flush()?;
+        Ok(true)
+    }
 
-            ParseEvent::TableRow(cells) => {
-                let width = self.current_width();
-                let margin = self.left_margin();
-                let style = self.style.clone();
-                let lines =
-                    render_table_row(cells, &mut self.table_state, width, &margin, &style, false);
-                for line in lines {
-                    self.writeln(&line)?;
-                }
-            }
+    /// Add a processor that may draw whole blocks; the first processor
+    /// claiming a block gets it.
+    pub fn add_block_processor(&mut self, processor: Arc<dyn BlockProcessor>) {
+        self.block_processors.push(processor);
+    }
 
-            ParseEvent::TableSeparator => {
-                let sep = render_table_separator(
-                    &self.table_state,
-                    self.current_width(),
-                    &self.left_margin(),
-                    &self.style,
-                );
-                self.writeln(&sep)?;
-                self.table_state.end_header();
-            }
+    /// Draw fences of `language` with `handler` instead of as code.
+    pub fn register_fence(&mut self, language: &str, handler: Arc<dyn FenceHandler>) {
+        Arc::make_mut(&mut self.fences).register(language, handler);
+    }
 
-            ParseEvent::TableEnd => {
-                self.table_state.reset();
-            }
+    /// Handlers for fences of data languages.
+    pub fn fence_handlers(&self) -> &FenceHandlers {
+        &self.fences
+    }
 
-            ParseEvent::BlockquoteStart { depth } => {
-                self.in_blockquote = true;
-                self.blockquote_depth = *depth;
-            }
+    /// Treat fences of `alias` as `language`: same label, highlighting and
+    /// fence handler.
+    pub fn alias_language(&mut self, alias: &str, language: &str) {
+        Arc::make_mut(&mut self.language_aliases).insert(alias, language);
+    }
 
-            ParseEvent::BlockquoteLine(text) => {
-                let margin = self.left_margin();
-                // Wrap text to fit
-                let wrapped = text_wrap(
-                    text,
-                    self.current_width(),
-                    0,
-                    &margin,
-                    &margin,
-                    false,
-                    false,
-                );
-                for line in wrapped.lines {
-                    self.writeln(&line)?;
-                }
-            }
+    /// Canonical fence language names by alias.
+    pub fn language_aliases(&self) -> &LanguageAliases {
+        &self.language_aliases
+    }
 
-            ParseEvent::BlockquoteEnd => {
-                self.in_blockquote = false;
-                self.blockquote_depth = 0;
-            }
+    /// Break overlong words at the syllables `hyphenator` finds when
+    /// [`RenderFeatures::hyphenate`] is set.
+    pub fn set_hyphenator(&mut self, hyphenator: Arc<dyn Hyphenator>) {
+        self.hyphenator = hyphenator;
+    }
 
-            ParseEvent::ThinkBlockStart => {
-                let fg = fg_color(&self.style.think_border);
-                self.writeln(&format!("{}┌─ thinking ─{}", fg, RESET))?;
-                self.in_blockquote = true;
-                self.blockquote_depth = 1;
-            }
+    /// Measure text with `oracle` (e.g. matched to the terminal's ambiguous
+    /// and emoji widths) when wrapping, laying out tables and truncating.
+    pub fn set_width_oracle(&mut self, oracle: WidthOracle) {
+        self.width_oracle = oracle;
+        self.wrap_cache.clear();
+    }
 
-            ParseEvent::ThinkBlockLine(text) => {
-                let fg = fg_color(&self.style.think_border);
-                self.writeln(&format!("{}│{} {}", fg, RESET, text))?;
-            }
+    /// Width model used for column counts.
+    pub fn width_oracle(&self) -> &WidthOracle {
+        &self.width_oracle
+    }
 
-            ParseEvent::ThinkBlockEnd => {
-                let fg = fg_color(&self.style.think_border);
-                self.writeln(&format!("{}└{}", fg, RESET))?;
-                self.in_blockquote = false;
-                self.blockquote_depth = 0;
-            }
+    /// Draw spans `handler` recognizes in paragraphs, list items, quotes and
+    /// table cells (e.g.
+    /// [`ProgressHandler`]), replacing any handler registered as `name`.
+    pub fn register_inline(&mut self, name: &str, handler: Arc<dyn InlineHandler>) {
+        Arc::make_mut(&mut self.inline_handlers).register(name, handler);
+    }
 
-            ParseEvent::HorizontalRule => {
-                let fg = fg_color(&self.style.hr);
-                let rule = "─".repeat(self.current_width());
-                self.writeln(&format!("{}{}{}{}", self.left_margin(), fg, rule, RESET))?;
-            }
+    /// Handlers for inline extensions.
+    pub fn inline_handlers(&self) -> &InlineHandlers {
+        &self.inline_handlers
+    }
 
-            ParseEvent::EmptyLine => {
-                self.writeln("")?;
+    /// Mark the spans `pass` finds in paragraph, block quote and list text
+    /// (e.g. a [`PatternPass`]), replacing any pass registered as `name`.
+    pub fn register_text_pass(&mut self, name: &str, pass: Arc<dyn TextPass>) {
+        Arc::make_mut(&mut self.text_passes).register(name, pass);
+    }
+
+    /// Passes marking terms in prose text.
+    pub fn text_passes(&self) -> &TextPasses {
+        &self.text_passes
+    }
+
+    /// Modulate the style of inline spans by their metadata (see
+    /// [`set_span_meta`](Self::set_span_meta)), or stop with None.
+    pub fn set_meta_styler(&mut self, styler: Option<Arc<dyn MetaStyler>>) {
+        self.meta_styler = styler;
+    }
+
+    /// Set the metadata of the inline spans drawn from now on (e.g. the
+    /// model's confidence in them), or clear it with None.
+    pub fn set_span_meta(&mut self, meta: Option<SpanMeta>) {
+        self.span_meta = meta;
+    }
+
+    /// Set a formatter for table body cells (e.g. [`LocaleFormatter`]).
+    ///
+    /// Takes effect from the next table.
+    pub fn set_cell_formatter(&mut self, formatter: Option<Arc<dyn CellFormatter>>) {
+        self.cell_formatter = formatter;
+    }
+
+    /// Set a matcher for quoted history (e.g. [`TranscriptMatcher`]).
+    ///
+    /// Blockquote lines it recognizes are drawn muted.
+    pub fn set_quote_matcher(&mut self, matcher: Option<Arc<dyn QuoteMatcher>>) {
+        self.quote_matcher = matcher;
+    }
+
+    /// Get the headings enclosing the current position.
+    ///
+    /// Updated as heading events are rendered; `to_string()` gives
+    /// "H1 › H2 › H3" for a status line.
+    pub fn breadcrumb(&self) -> &Breadcrumb {
+        &self.breadcrumb
+    }
+
+    /// Redraw the pinned header row from the breadcrumb.
+    fn draw_pinned_header(&mut self) -> std::io::Result<()> {
+        let Some(pinned) = self.pinned else {
+            return Ok(());
+        };
+        let text = self
+            .breadcrumb
+            .format(pin::PIN_MAX_LEVEL, BREADCRUMB_SEPARATOR);
+        let seq = pinned.draw_sequence(&text, self.width, &self.style.h1);
+        write!(
+            self.writer,
+            "{}",
+            downgrade_colors(&seq, self.color_depth().unwrap_or_default())
+        )
+    }
+
+    /// Number of blocks drawn as `[+]` summaries since the last reset.
+    pub fn collapsed_blocks(&self) -> usize {
+        self.collapsed.len()
+    }
+
+    /// Draw the `index`th collapsed block in full at the current position.
+    ///
+    /// Returns `false` if fewer blocks have been collapsed.
+    pub fn expand(&mut self, index: usize) -> RenderResult<bool> {
+        let _scope = OracleScope::new(&self.width_oracle);
+        let Some(events) = self.collapsed.get(index).cloned() else {
+            return Ok(false);
+        };
+        for event in &events {
+            self.draw_event(event)?;
+        }
+        self.writer.flush()?;
+        Ok(true)
+    }
+
+    /// Render the events held back by the quota, under a new quota.
+    pub fn resume(&mut self, quota: Option<RenderQuota>) -> RenderResult<()> {
+        let _scope = OracleScope::new(&self.width_oracle);
+        let Some(truncation) = self.truncation.take() else {
+            self.set_quota(quota);
+            return Ok(());
+        };
+        let snapshot = truncation.snapshot;
+        self.current_block = snapshot.current_block;
+        self.block_ended = snapshot.block_ended;
+        self.blocks_started = snapshot.blocks_started;
+        let (len, id) = snapshot.block_ids;
+        self.block_ids.truncate(len);
+        if let (Some(last), Some(id)) = (self.block_ids.last_mut(), id) {
+            *last = id;
+        }
+        self.set_quota(quota);
+
+        if truncation.closed_code {
+            self.draw_code_frame(true)?;
+        }
+        for event in &truncation.events {
+            self.draw_event(event)?;
+        }
+        Ok(())
+    }
+
+    /// Glyphs for the configured border style.
+    fn glyphs(&self) -> BorderGlyphs {
+        self.features.border_style.glyphs()
+    }
+
+    /// Glyphs for trace panel frames, which keep their rounded corners
+    /// under the default style.
+    fn panel_glyphs(&self) -> BorderGlyphs {
+        match self.features.border_style {
+            BorderStyle::Light => BorderStyle::Rounded.glyphs(),
+            style => style.glyphs(),
+        }
+    }
+
+    /// Calculate the left margin based on current state.
+    fn left_margin(&self) -> String {
+        let mut margin = self.panel_margin();
+        if self.in_blockquote {
+            let vertical = self.glyphs().vertical;
+            let border = format!(
+                "{}{}{} ",
+                fg_color(&self.style.blockquote_border),
+                vertical,
+                RESET
+            );
+            margin.push_str(&border.repeat(self.blockquote_depth));
+        }
+        margin
+    }
+
+    /// Outer margin followed by the borders of the open trace panels.
+    fn panel_margin(&self) -> String {
+        let mut margin = " ".repeat(self.outer_margin());
+        for panel in &self.panels {
+            margin.push_str(&format!(
+                "{}{}{} ",
+                fg_color(&panel.color),
+                self.glyphs().vertical,
+                RESET
+            ));
+        }
+        margin
+    }
+
+    /// Width of the content column: the width less the outer margins,
+    /// capped at `max_width`.
+    fn content_width(&self) -> usize {
+        let available = self
+            .width
+            .saturating_sub(self.features.left_margin + self.features.right_margin);
+        self.features
+            .max_width
+            .map_or(available, |max| available.min(max))
+            .max(1)
+    }
+
+    /// Blank columns before the content column; a column capped by
+    /// `max_width` is centered in the space between the margins.
+    fn outer_margin(&self) -> usize {
+        let available = self
+            .width
+            .saturating_sub(self.features.left_margin + self.features.right_margin);
+        self.features.left_margin + available.saturating_sub(self.content_width()) / 2
+    }
+
+    /// Calculate the current available width.
+    fn current_width(&self) -> usize {
+        let margin_width = if self.in_blockquote {
+            self.blockquote_depth * 3 // "│ " = 2 chars per level, plus color codes
+        } else {
+            0
+        };
+        self.content_width()
+            .saturating_sub(margin_width)
+            .saturating_sub(self.panels.len() * 2)
+    }
+
+    /// Prepare highlighting for a code block in `language`.
+    ///
+    /// Unknown or missing languages leave the block plain.
+    #[cfg(feature = "syntax-highlighting")]
+    fn start_code_highlight(&mut self, language: Option<&str>) {
+        self.code_highlight = if self.features.highlight {
+            language.and_then(|lang| self.highlighter.line_state(lang))
+        } else {
+            None
+        };
+    }
+
+    #[cfg(not(feature = "syntax-highlighting"))]
+    fn start_code_highlight(&mut self, _language: Option<&str>) {}
+
+    /// Highlight one line of the current code block.
+    #[cfg(feature = "syntax-highlighting")]
+    fn highlight_code_line(&mut self, line: &str) -> String {
+        match self.code_highlight.as_mut() {
+            Some(state) => self.highlighter.highlight_line_owned(line, state),
+            None => line.to_string(),
+        }
+    }
+
+    #[cfg(not(feature = "syntax-highlighting"))]
+    fn highlight_code_line(&mut self, line: &str) -> String {
+        line.to_string()
+    }
+
+    /// Write a string to the output.
+    ///
+    /// Inline content starting a new line inside a trace panel gets the
+    /// panel borders first.
+    fn write(&mut self, s: &str) -> std::io::Result<()> {
+        if let Some(line) = &mut self.held_line {
+            line.push_str(s);
+            self.at_line_start = false;
+            return Ok(());
+        }
+        let depth = self.color_depth();
+        if self.at_line_start {
+            let margin = self.panel_margin();
+            if !margin.is_empty() {
+                emit(&mut self.writer, depth, &margin)?;
+                self.record_text(&margin, false);
             }
+        }
+        self.at_line_start = false;
+        self.record_text(s, false);
+        if self.annotation.is_some()
+            && let Some(end) = memchr::memchr(b'\n', s.as_bytes())
+        {
+            let gutter = self.take_annotation();
+            emit(&mut self.writer, depth, &s[..end])?;
+            emit(&mut self.writer, depth, &gutter)?;
+            return emit(&mut self.writer, depth, &s[end..]);
+        }
+        emit(&mut self.writer, depth, s)
+    }
 
-            ParseEvent::Newline => {
-                self.writeln("")?;
+    /// Write inline text, tracking the column.
+    ///
+    /// When margins or `max_width` narrow the content column, in plain text
+    /// mode, or when lines are held to be aligned or reordered, lines are
+    /// broken at spaces so text stays inside it; otherwise wrapping is left
+    /// to the terminal.
+    fn write_flow(&mut self, s: &str) -> std::io::Result<()> {
+        let hold = self.features.paragraph_align != TextAlign::Left
+            || (cfg!(feature = "bidi") && self.features.bidi);
+        if self.content_width() >= self.width && !self.features.plain && !hold {
+            self.column += visible_length(s);
+            return self.write_modulated(s);
+        }
+        let width = self.current_width();
+        let s = self.hyphenate(s, width);
+        // Cached, so reflows skip unchanged spans
+        let flowed = self.wrap_cache.flow(&s, width, self.column);
+        for (i, row) in flowed.rows.iter().enumerate() {
+            if i > 0 {
+                self.break_flow()?;
+            }
+            if hold && self.at_line_start && self.held_line.is_none() {
+                self.held_line = Some(String::new());
+            }
+            if !row.is_empty() {
+                if self.column == 0 && !self.flow_style.is_empty() {
+                    let style = self.flow_style.concat();
+                    self.write(&style)?;
+                }
+                self.write_modulated(row)?;
+                self.column += visible_length(row);
+                if has_escape(row) {
+                    let mut codes = std::mem::take(&mut self.flow_style);
+                    codes.extend(extract_ansi_codes(row));
+                    self.flow_style = ansi_collapse(&codes, "");
+                }
             }
+        }
+        Ok(())
+    }
 
-            ParseEvent::Prompt(prompt) => {
-                self.write(prompt)?;
+    /// End a row of flowed text, closing the styles it left open so the
+    /// line break and the next row's margin are drawn plain.
+    fn break_flow(&mut self) -> std::io::Result<()> {
+        let style = std::mem::take(&mut self.flow_style);
+        if !style.is_empty() {
+            self.write(RESET)?;
+        }
+        self.flush_held_line(false)?;
+        self.writeln("")?;
+        self.flow_style = style;
+        Ok(())
+    }
+
+    /// Write a piece of flowed text inside the current modulation, if any,
+    /// so it never spans a line break and the margins stay unstyled.
+    fn write_modulated(&mut self, s: &str) -> std::io::Result<()> {
+        match self.modulation.take() {
+            Some((start, end)) => {
+                let result = self.write(&format!("{}{}{}", start, s, end));
+                self.modulation = Some((start, end));
+                result
             }
+            None => self.write(s),
+        }
+    }
 
-            ParseEvent::InlineElements(elements) => {
-                for element in elements {
-                    self.render_inline_element(element)?;
+    /// `text` with its words wider than `width` hyphenated, if
+    /// [`RenderFeatures::hyphenate`] is set.
+    fn hyphenate<'a>(&self, text: &'a str, width: usize) -> Cow<'a, str> {
+        if !self.features.hyphenate || text.split(' ').all(|word| visible_length(word) <= width) {
+            return Cow::Borrowed(text);
+        }
+        text.split_inclusive(' ')
+            .map(|piece| {
+                let word = piece.trim_end_matches(' ');
+                if visible_length(word) <= width {
+                    return piece.to_string();
                 }
+                let spaces = &piece[word.len()..];
+                hyphenate_long_words(word, width, &*self.hyphenator) + spaces
+            })
+            .collect()
+    }
+
+    /// Write the held paragraph line, aligned and in visual order; `last`
+    /// if it ends the paragraph.
+    fn flush_held_line(&mut self, last: bool) -> std::io::Result<()> {
+        match self.held_line.take() {
+            Some(line) => {
+                let width = self.current_width();
+                let line = align_line(&line, width, self.features.paragraph_align, last);
+                self.at_line_start = true;
+                self.write(&self.visual(&line))
             }
+            None => Ok(()),
         }
+    }
 
-        self.writer.flush()
+    /// `line` in visual order if [`RenderFeatures::bidi`] is set.
+    #[cfg(feature = "bidi")]
+    fn visual<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        if self.features.bidi {
+            bidi::reorder_line(line)
+        } else {
+            Cow::Borrowed(line)
+        }
     }
 
-    /// Render an inline element.
-    fn render_inline_element(&mut self, element: &InlineElement) -> std::io::Result<()> {
-        match element {
-            InlineElement::Text(s) => self.write(s)?,
-            InlineElement::Bold(s) => self.write(&format!("{}{}{}", BOLD_ON, s, BOLD_OFF))?,
-            InlineElement::Italic(s) => self.write(&format!("{}{}{}", ITALIC_ON, s, ITALIC_OFF))?,
-            InlineElement::BoldItalic(s) => self.write(&format!(
-                "{}{}{}{}{}",
-                BOLD_ON, ITALIC_ON, s, ITALIC_OFF, BOLD_OFF
-            ))?,
-            InlineElement::Underline(s) => {
-                self.write(&format!("{}{}{}", UNDERLINE_ON, s, UNDERLINE_OFF))?
-            }
-            InlineElement::Strikeout(s) => {
-                self.write(&format!("{}{}{}", STRIKEOUT_ON, s, STRIKEOUT_OFF))?
-            }
-            InlineElement::Code(s) => {
-                let bg = bg_color(&self.style.code_bg);
-                self.write(&format!("{} {} {}", bg, s, RESET))?
-            }
-            InlineElement::Link { text, url } => {
-                let fg = fg_color(&self.style.link_url);
-                // OSC 8 start
-                self.write("\x1b]8;;")?;
-                self.write(url)?;
-                self.write("\x1b\\")?;
-                // Underlined text
-                self.write(&format!("{}{}{}", UNDERLINE_ON, text, UNDERLINE_OFF))?;
-                // OSC 8 end
-                self.write("\x1b]8;;\x1b\\")?;
-                // Show URL in parentheses (dimmed)
-                self.write(&format!(" {}({}){}", fg, url, RESET))?;
-            }
-            InlineElement::Image { alt, .. } => {
-                let fg = fg_color(&self.style.image_marker);
-                self.write(&format!("{}[\u{1F5BC} {}]{}", fg, alt, RESET))?
+    #[cfg(not(feature = "bidi"))]
+    fn visual<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        Cow::Borrowed(line)
+    }
+
+    /// Write a line to the output.
+    fn writeln(&mut self, s: &str) -> std::io::Result<()> {
+        self.flush_held_line(true)?;
+        let depth = self.color_depth();
+        let gutter = self.take_annotation();
+        emit_line(&mut self.writer, depth, s, &gutter, self.features.copy_mode)?;
+        self.record_text(s, true);
+        self.column = 0;
+        self.flow_style.clear();
+        self.at_line_start = true;
+        Ok(())
+    }
+
+    /// Write a line assembled from parts.
+    ///
+    /// With the `arena` feature the line is built in the per-event arena
+    /// instead of a fresh heap string.
+    fn writeln_parts(&mut self, parts: &[&str]) -> std::io::Result<()> {
+        self.flush_held_line(true)?;
+        let depth = self.color_depth();
+        let gutter = self.take_annotation();
+        #[cfg(feature = "arena")]
+        emit_line(
+            &mut self.writer,
+            depth,
+            self.arena.concat(parts),
+            &gutter,
+            self.features.copy_mode,
+        )?;
+        #[cfg(not(feature = "arena"))]
+        emit_line(
+            &mut self.writer,
+            depth,
+            &parts.concat(),
+            &gutter,
+            self.features.copy_mode,
+        )?;
+        for part in parts {
+            self.record_text(part, false);
+        }
+        self.record_text("", true);
+        self.column = 0;
+        self.flow_style.clear();
+        self.at_line_start = true;
+        Ok(())
+    }
+
+    /// Render a trace marker from [`TraceProfile::classify`].
+    ///
+    /// `Content` segments are markdown for the parser and are ignored here.
+    pub fn render_trace_marker(&mut self, marker: &TraceLine) -> RenderResult<()> {
+        match marker {
+            TraceLine::Open(panel) => self.open_panel(panel)?,
+            TraceLine::Close => self.close_panel()?,
+            TraceLine::Role(role) => self.render_role(role)?,
+            TraceLine::Content(_) => {}
+        }
+        Ok(self.writer.flush()?)
+    }
+
+    /// Start a nested trace panel.
+    pub fn open_panel(&mut self, panel: &PanelStyle) -> std::io::Result<()> {
+        let _scope = OracleScope::new(&self.width_oracle);
+        if !self.at_line_start {
+            self.writeln("")?;
+        }
+        let margin = self.left_margin();
+        let fg = fg_color(&panel.color);
+        let glyphs = self.panel_glyphs();
+        let title = format!("{}{} {} ", glyphs.top_left, glyphs.horizontal, panel.title);
+        let fill = self
+            .current_width()
+            .saturating_sub(streamdown_ansi::utils::visible_length(&title));
+        let rule = glyphs.horizontal.repeat(fill);
+        self.writeln_parts(&[&margin, &fg, BOLD_ON, &title, BOLD_OFF, &rule, RESET])?;
+        self.panels.push(panel.clone());
+        Ok(())
+    }
+
+    /// Close the innermost trace panel.
+    ///
+    /// Does nothing if no panel is open.
+    pub fn close_panel(&mut self) -> std::io::Result<()> {
+        let _scope = OracleScope::new(&self.width_oracle);
+        let Some(panel) = self.panels.pop() else {
+            return Ok(());
+        };
+        if !self.at_line_start {
+            self.writeln("")?;
+        }
+        let margin = self.left_margin();
+        let fg = fg_color(&panel.color);
+        let glyphs = self.panel_glyphs();
+        let rule = glyphs
+            .horizontal
+            .repeat(self.current_width().saturating_sub(1));
+        self.writeln_parts(&[&margin, &fg, glyphs.bottom_left, &rule, RESET])
+    }
+
+    /// Render a role label (e.g. `User`) on its own line.
+    pub fn render_role(&mut self, role: &PanelStyle) -> std::io::Result<()> {
+        let _scope = OracleScope::new(&self.width_oracle);
+        if !self.at_line_start {
+            self.writeln("")?;
+        }
+        let margin = self.left_margin();
+        let fg = fg_color(&role.color);
+        self.writeln_parts(&[&margin, &fg, BOLD_ON, "▍", &role.title, BOLD_OFF, RESET])
+    }
+
+    /// Keep every event rendered from now on, so the document can be drawn
+    /// again with [`rerender`](Self::rerender).
+    ///
+    /// Off by default since it grows with the input; embedders with a
+    /// resizable pane turn it on. Disabling drops the kept events.
+    pub fn set_retain_events(&mut self, enabled: bool) {
+        self.retained = enabled.then(Vec::new);
+        self.retained_from = self.lines_written;
+        // Retained events start a block of their own
+        self.retained_block_from = self.block_ids.len();
+        self.block_ended = true;
+    }
+
+    /// Events kept for re-rendering (empty if not retained).
+    pub fn retained_events(&self) -> &[ParseEvent] {
+        self.retained.as_deref().unwrap_or_default()
+    }
+
+    /// Clear the retained document's output and draw it again at `width`.
+    ///
+    /// The cursor is moved back over the lines written since retention
+    /// began; lines already scrolled off screen can't be cleared. Plain text
+    /// output can't move the cursor, so the document is written again below.
+    pub fn rerender(&mut self, width: usize) -> RenderResult<()> {
+        let _scope = OracleScope::new(&self.width_oracle);
+        let Some(events) = self.retained.take() else {
+            self.set_width(width);
+            return Ok(());
+        };
+        if !self.features.plain {
+            self.erase_lines_since(self.retained_from)?;
+        } else {
+            if !self.at_line_start {
+                self.writeln("")?;
             }
-            InlineElement::Footnote(s) => {
-                let fg = fg_color(&self.style.footnote);
-                self.write(&format!("{}{}{}", fg, s, RESET))?
+            if let Some(meta) = &mut self.line_meta {
+                meta.truncate(self.retained_from);
             }
+            self.lines_written = self.retained_from;
+        }
+        self.reset_document();
+        self.set_width(width);
+
+        // Replay without counting or notifying twice
+        let stats = self.stats.take();
+        let notifier = self.notifier.take();
+        let result = self.render(&events);
+        self.stats = stats;
+        self.notifier = notifier;
+        self.retained = Some(events);
+        result
+    }
+
+    /// Move the cursor back to the start of output line `from` and clear
+    /// everything below it, forgetting the erased lines.
+    fn erase_lines_since(&mut self, from: usize) -> std::io::Result<()> {
+        let lines = self.lines_written - from;
+        if !self.at_line_start {
+            self.writer.write_all(b"\r")?;
         }
+        if lines > 0 {
+            write!(self.writer, "\x1b[{}F", lines)?;
+        }
+        self.writer.write_all(b"\x1b[J")?;
+        if let Some(meta) = &mut self.line_meta {
+            meta.truncate(from);
+        }
+        self.lines_written = from;
+        self.row_width = 0;
+        self.column = 0;
+        self.flow_style.clear();
+        self.at_line_start = true;
         Ok(())
     }
 
-    /// Render multiple events.
-    pub fn render(&mut self, events: &[ParseEvent]) -> std::io::Result<()> {
-        for event in events {
-            self.render_event(event)?;
+    /// Render `markdown` in place of the block `id` of the retained
+    /// document, at the stored width, without touching the output.
+    /// `parser` parses it: a new parser set up like the document's.
+    ///
+    /// Returns None if events aren't retained or no retained block has that
+    /// ID. The blank line ending the old block is kept. Apply the patch with
+    /// [`apply_block_patch`](Self::apply_block_patch), then redraw with
+    /// [`rerender`](Self::rerender) or splice [`BlockPatch::lines`] into a
+    /// host's own buffer.
+    pub fn rerender_block(
+        &self,
+        id: BlockId,
+        markdown: &str,
+        mut parser: streamdown_parser::Parser,
+    ) -> RenderResult<Option<BlockPatch>> {
+        let (Some(events), Some(block)) =
+            (&self.retained, self.block_ids.iter().position(|&b| b == id))
+        else {
+            return Ok(None);
+        };
+        let Some(ordinal) = block.checked_sub(self.retained_block_from) else {
+            return Ok(None);
+        };
+        let blocks = self.retained_blocks(events);
+        let (Some(start), Some(last)) = (
+            blocks.iter().position(|&b| b == ordinal),
+            blocks.iter().rposition(|&b| b == ordinal),
+        ) else {
+            return Ok(None);
+        };
+
+        let mut replacement = Vec::new();
+        for line in markdown.lines() {
+            replacement.extend(parser.parse_line(line));
+        }
+        replacement.extend(parser.finalize());
+        if events[last] == ParseEvent::EmptyLine
+            && replacement.last() != Some(&ParseEvent::EmptyLine)
+        {
+            replacement.push(ParseEvent::EmptyLine);
+        }
+
+        let mut detached = self.detached();
+        detached.render(&replacement)?;
+        if !detached.at_line_start {
+            detached.writeln("")?;
+        }
+        let output = detached.into_writer();
+        let lines = String::from_utf8_lossy(&output)
+            .lines()
+            .map(String::from)
+            .collect();
+        Ok(Some(BlockPatch {
+            id,
+            block,
+            events: start..last + 1,
+            replacement,
+            lines,
+        }))
+    }
+
+    /// Splice a patch from [`rerender_block`](Self::rerender_block) into the
+    /// retained events. Returns false, changing nothing, if it no longer
+    /// fits them or was already applied.
+    pub fn apply_block_patch(&mut self, patch: &BlockPatch) -> bool {
+        let Some(events) = &mut self.retained else {
+            return false;
+        };
+        let applied = patch.events.start..patch.events.start + patch.replacement.len();
+        if patch.events.end > events.len()
+            || events.get(applied) == Some(patch.replacement.as_slice())
+        {
+            return false;
+        }
+        events.splice(patch.events.clone(), patch.replacement.iter().cloned());
+        true
+    }
+
+    /// Ordinal of the block each of `events` belongs to, rendering them
+    /// from the start of a document.
+    fn retained_blocks(&self, events: &[ParseEvent]) -> Vec<usize> {
+        let mut scan = Renderer::new(std::io::sink(), self.width);
+        events
+            .iter()
+            .map(|event| {
+                scan.track_block_kind(event);
+                scan.end_block(event);
+                scan.current_block.block
+            })
+            .collect()
+    }
+
+    /// A renderer with the same settings, writing to memory.
+    fn detached(&self) -> Renderer<Vec<u8>> {
+        let mut renderer = Renderer::with_features(Vec::new(), self.width, self.features.clone());
+        #[cfg(feature = "syntax-highlighting")]
+        {
+            let theme = self.highlighter.theme_name();
+            if !renderer.highlighter.has_theme(theme) {
+                renderer
+                    .highlighter
+                    .add_theme(theme, self.highlighter.theme().clone());
+            }
+            renderer.highlighter.set_theme(theme);
+            renderer
+                .highlighter
+                .set_background(self.highlighter.background());
+        }
+        renderer.style = self.style.clone();
+        renderer.capabilities = self.capabilities.clone();
+        renderer.options = self.options.clone();
+        renderer.colors_enabled = self.colors_enabled;
+        renderer.policy = self.policy.clone();
+        renderer.trace = self.trace.clone();
+        renderer.gutter = self.gutter;
+        renderer.block_processors = self.block_processors.clone();
+        renderer.fences = Arc::clone(&self.fences);
+        renderer.language_aliases = Arc::clone(&self.language_aliases);
+        renderer.hyphenator = Arc::clone(&self.hyphenator);
+        renderer.width_oracle = self.width_oracle.clone();
+        renderer.inline_handlers = Arc::clone(&self.inline_handlers);
+        renderer.text_passes = Arc::clone(&self.text_passes);
+        renderer.cell_formatter = self.cell_formatter.clone();
+        renderer.meta_styler = self.meta_styler.clone();
+        renderer.span_meta = self.span_meta.clone();
+        renderer.quote_matcher = self.quote_matcher.clone();
+        renderer.links = self.links.clone();
+        renderer.link_opener = self.link_opener.clone();
+        renderer
+    }
+
+    /// Forget everything about the document drawn so far, keeping settings
+    /// (style, features, width, processors), so one renderer can draw many
+    /// messages in turn.
+    ///
+    /// Open blocks are dropped without being closed (see
+    /// [`finish`](Self::finish)); stats, notifications, line metadata and
+    /// retained events start afresh. Nothing is written.
+    pub fn reset(&mut self) {
+        self.lines_written = 0;
+        self.row_width = 0;
+        self.reset_document();
+        self.panels.clear();
+        self.partial_drawn = false;
+        self.partial_row.clear();
+        self.partial_line.clear();
+        self.progress_drawn = 0;
+        if let Some(meta) = &mut self.line_meta {
+            meta.clear();
+        }
+        if let Some(events) = &mut self.retained {
+            events.clear();
+        }
+        self.retained_from = 0;
+        self.retained_block_from = 0;
+        if self.stats.is_some() {
+            self.stats = Some(MessageStats::new());
+        }
+        if let Some(notifier) = &mut self.notifier {
+            *notifier = notify::Notifier::new(notifier.options.clone());
+        }
+    }
+
+    /// Forget the state of the document being drawn, keeping settings.
+    fn reset_document(&mut self) {
+        self.column = 0;
+        self.flow_style.clear();
+        self.at_line_start = true;
+        self.held_line = None;
+        self.code_language = None;
+        self.code_buffer.clear();
+        self.code_line_number = 0;
+        self.diff_in_hunk = false;
+        self.qr_pending.clear();
+        self.start_code_highlight(None);
+        self.table_state.reset();
+        self.live_block = None;
+        self.list_state.reset();
+        self.in_blockquote = false;
+        self.blockquote_depth = 0;
+        self.details_stack.clear();
+        self.current_block = LineMeta {
+            kind: BlockKind::Paragraph,
+            block: 0,
+        };
+        self.block_ids.clear();
+        self.retained_block_from = 0;
+        self.block_ended = false;
+        self.blocks_started = 0;
+        if let Some(width) = self.pending_width.take() {
+            self.set_width(width);
+        }
+        if let Some(style) = self.pending_style.take() {
+            self.style = style;
+        }
+        self.annotation = None;
+        self.truncation = None;
+        self.breadcrumb = Breadcrumb::new();
+        self.held_block = None;
+        self.streamed_block = None;
+        self.collapsed.clear();
+        self.timed_out_block = None;
+        self.outline = outline::OutlineFilter::default();
+        self.link_notes.clear();
+        self.links.clear();
+        let quota = self.quota.take();
+        self.set_quota(quota);
+    }
+
+    /// Paint `text`, the input line still being received, on the current
+    /// row; it is erased before the next event is rendered. Closed spans
+    /// are styled and unclosed markers hidden (see [`live`]), and only the
+    /// part of the row that changed since the last paint is rewritten. A
+    /// line wider than the content column shows its end, unstyled.
+    ///
+    /// In an open code block, only the latest complete carriage-return
+    /// state is drawn, in place (see [`ProgressLines::InPlace`]).
+    ///
+    /// Does nothing in plain text mode, while a block is held back, or when
+    /// the row already has output.
+    pub fn draw_partial_line(&mut self, text: &str) -> std::io::Result<()> {
+        let _scope = OracleScope::new(&self.width_oracle);
+        if self.features.plain || !self.at_line_start || self.held_block.is_some() {
+            return Ok(());
+        }
+        if self.current_block.kind == BlockKind::Code && !self.block_ended {
+            return self.draw_partial_progress(text);
+        }
+        let width = self.current_width();
+        let text = if self.policy.source_escapes {
+            Cow::Owned(sanitize::sanitize_controls(
+                text,
+                self.features.control_chars,
+            ))
+        } else {
+            Cow::Owned(sanitize::neutralize_escapes(
+                text,
+                self.features.control_chars,
+            ))
+        };
+        let mut partial_line = std::mem::take(&mut self.partial_line);
+        let styled = partial_line.style(&text, |event| self.inline_span(event));
+        self.partial_line = partial_line;
+        let length = visible_length(&styled);
+        let text = visible(&styled);
+        let shown = if length <= width {
+            styled
+        } else {
+            // The end of the line that fits beside the ellipsis
+            let mut skip = text.len();
+            let mut tail_width = 0;
+            for (i, ch) in text.char_indices().rev() {
+                tail_width += char_width(ch).unwrap_or(0);
+                if tail_width >= width {
+                    break;
+                }
+                skip = i;
+            }
+            format!("…{}", &text[skip..])
+        };
+        let margin = self.left_margin();
+        let update = self.partial_row.paint(&format!("{}{}", margin, shown));
+        let depth = self.color_depth();
+        emit(&mut self.writer, depth, &update)?;
+        self.partial_drawn = true;
+        self.writer.flush()
+    }
+
+    /// Draw the latest complete progress state of a partial code line in
+    /// place, if it has not been drawn yet.
+    fn draw_partial_progress(&mut self, text: &str) -> std::io::Result<()> {
+        if self.features.progress_lines != ProgressLines::InPlace {
+            return Ok(());
+        }
+        let Some(end) = text.rfind('\r') else {
+            return Ok(());
+        };
+        if end <= self.progress_drawn
+            || !code::progress_in_place(self.code_language.as_deref(), &text[..end])
+        {
+            return Ok(());
+        }
+        self.progress_drawn = end;
+        if let Some(state) = code::progress_states(&text[..end]).last() {
+            self.draw_progress_row(state)?;
+        }
+        self.writer.flush()
+    }
+
+    /// Erase a partial line painted by [`draw_partial_line`](Self::draw_partial_line),
+    /// e.g. before writing to the terminal directly.
+    pub fn clear_partial_line(&mut self) -> std::io::Result<()> {
+        if std::mem::take(&mut self.partial_drawn) {
+            self.partial_row.clear();
+            self.partial_line.clear();
+            self.writer.write_all(b"\r\x1b[2K")?;
+        }
+        Ok(())
+    }
+
+    /// Render a single parse event.
+    ///
+    /// Fails with [`RenderError::WidthTooSmall`] without drawing anything
+    /// when the output is narrower than [`MIN_RENDER_WIDTH`]; rendering can
+    /// continue after a [`resize`](Self::resize).
+    pub fn render_event(&mut self, event: &ParseEvent) -> RenderResult<()> {
+        let _scope = OracleScope::new(&self.width_oracle);
+        if self.width < MIN_RENDER_WIDTH {
+            return Err(RenderError::WidthTooSmall {
+                width: self.width,
+                minimum: MIN_RENDER_WIDTH,
+            });
+        }
+        Ok(self.handle_event(event)?)
+    }
+
+    /// Render a single parse event at a usable width.
+    fn handle_event(&mut self, event: &ParseEvent) -> std::io::Result<()> {
+        let canonical = self.language_aliases.canonical_event(event);
+        let event = canonical.as_ref().unwrap_or(event);
+        let sanitized = sanitize::sanitize_event(
+            event,
+            self.features.control_chars,
+            self.policy.source_escapes,
+        );
+        let event = sanitized.as_ref().unwrap_or(event);
+        self.clear_partial_line()?;
+        if let Some(events) = &mut self.retained {
+            events.push(event.clone());
+        }
+        if let Some(stats) = &mut self.stats {
+            stats.record_event(event);
+        }
+        if let Some(notifier) = &mut self.notifier {
+            for body in notifier.check_event(event) {
+                self.send_notification(&body)?;
+            }
+        }
+
+        // The rest of a block released by the timeout is drawn as is
+        self.release_stale_block()?;
+        if let Some(block) = &self.timed_out_block {
+            if block.is_end(event) {
+                self.timed_out_block = None;
+            }
+            return self.draw_event(event);
+        }
+
+        // Collapsible blocks are held until they end
+        if let Some(held) = &mut self.held_block {
+            held.events.push(event.clone());
+            if held.is_end(event) {
+                let held = self.held_block.take().expect("held block");
+                return self.release_held_block(held);
+            }
+            return Ok(());
+        }
+        // Collapsible code blocks stream until they pass the threshold
+        if let Some(streamed) = &mut self.streamed_block {
+            streamed.events.push(event.clone());
+            if streamed.is_end(event) {
+                let streamed = self.streamed_block.take().expect("streamed block");
+                return self.finish_streamed_block(streamed);
+            }
+            if streamed.collapses(&self.features.collapse) && self.truncation.is_none() {
+                return Ok(());
+            }
+            return self.draw_event(event);
+        }
+        // Outlines summarize every code block and skip most other events
+        if self.features.outline {
+            if matches!(event, ParseEvent::CodeBlockStart { .. }) {
+                self.held_block = Some(collapse::HeldBlock::new(event.clone()));
+                return Ok(());
+            }
+            if !self.outline.keeps(event) {
+                return Ok(());
+            }
+        }
+        if self.fences.claims(event) {
+            let fences: Arc<dyn BlockProcessor> = self.fences.clone();
+            self.held_block = Some(collapse::HeldBlock::for_processor(event.clone(), fences));
+            return Ok(());
+        }
+        if let Some(processor) = self.block_processors.iter().find(|p| p.claims(event)) {
+            let held = collapse::HeldBlock::for_processor(event.clone(), Arc::clone(processor));
+            self.held_block = Some(held);
+            return Ok(());
+        }
+        if self.features.collapse.starts_collapsible(event) {
+            if matches!(event, ParseEvent::CodeBlockStart { .. }) {
+                self.streamed_block = Some(collapse::HeldBlock::new(event.clone()));
+                return self.draw_event(event);
+            }
+            self.held_block = Some(collapse::HeldBlock::new(event.clone()));
+            return Ok(());
+        }
+
+        self.draw_event(event)
+    }
+
+    /// Draw a held block, as a summary line if it collapses.
+    fn release_held_block(&mut self, held: collapse::HeldBlock) -> std::io::Result<()> {
+        if let Some(processor) = &held.processor {
+            let lines = if self.truncation.is_none() {
+                processor.process(&held.events, self.current_width(), &self.style)
+            } else {
+                None
+            };
+            let Some(lines) = lines else {
+                for event in &held.events {
+                    self.draw_event(event)?;
+                }
+                return Ok(());
+            };
+            self.track_block(&held.events[0]);
+            if !self.at_line_start {
+                self.writeln("")?;
+            }
+            let margin = self.left_margin();
+            for line in &lines {
+                self.writeln_parts(&[&margin, line])?;
+            }
+            if let Some(end) = held.events.last() {
+                self.end_block(end);
+            }
+            return self.writer.flush();
+        }
+        let collapses = self.features.outline || held.collapses(&self.features.collapse);
+        if self.truncation.is_some() || !collapses {
+            for event in &held.events {
+                self.draw_event(event)?;
+            }
+            return Ok(());
+        }
+
+        self.track_block(&held.events[0]);
+        if !self.at_line_start {
+            self.writeln("")?;
+        }
+        let label = held.label();
+        // Outlines have nothing to expand
+        let hint = if self.features.outline { "" } else { " [+]" };
+        self.draw_summary(&label, hint)?;
+        if let Some(end) = held.events.last() {
+            self.end_block(end);
+        }
+        if !self.features.outline {
+            self.collapsed.push(held.events);
+        }
+        self.writer.flush()
+    }
+
+    /// End a streamed code block, summarizing the lines past the threshold.
+    fn finish_streamed_block(&mut self, streamed: collapse::HeldBlock) -> std::io::Result<()> {
+        if let Some(end) = streamed.events.last() {
+            self.draw_event(end)?;
+        }
+        if self.truncation.is_some() || !streamed.collapses(&self.features.collapse) {
+            return Ok(());
+        }
+        let shown = self.features.collapse.code_over.unwrap_or_default();
+        if !self.at_line_start {
+            self.writeln("")?;
+        }
+        self.draw_summary(&streamed.hidden_label(shown), " [+]")?;
+        self.collapsed.push(streamed.events);
+        self.writer.flush()
+    }
+
+    /// Write a `▶ label` summary line for a collapsed block.
+    fn draw_summary(&mut self, label: &str, hint: &str) -> std::io::Result<()> {
+        let margin = self.left_margin();
+        let fg = fg_color(&self.style.details_marker);
+        self.writeln_parts(&[&margin, &fg, "▶", RESET, " ", DIM_ON, label, hint, RESET])
+    }
+
+    /// Draw one event, subject to the quota and collapsed `<details>`.
+    fn draw_event(&mut self, event: &ParseEvent) -> std::io::Result<()> {
+        // Past the quota: hold everything, counting blocks
+        if let Some(truncation) = &mut self.truncation {
+            truncation.hold(event);
+            self.track_block(event);
+            self.end_block(event);
+            return Ok(());
+        }
+
+        // Content of collapsed <details> blocks is hidden
+        if self.details_stack.iter().any(|&collapsed| collapsed)
+            && !matches!(
+                event,
+                ParseEvent::DetailsStart { .. } | ParseEvent::DetailsEnd
+            )
+        {
+            return Ok(());
+        }
+
+        let snapshot = quota::BlockSnapshot {
+            current_block: self.current_block,
+            block_ended: self.block_ended,
+            blocks_started: self.blocks_started,
+            block_ids: (self.block_ids.len(), self.block_ids.last().copied()),
+        };
+        self.track_block(event);
+
+        if let Some(quota) = self.quota {
+            let (lines, blocks) = self.quota_start;
+            if quota.exceeded(self.lines_written - lines, self.blocks_started - blocks) {
+                // Close the frame of a code block cut off in the middle
+                let closed_code = snapshot.current_block.kind == BlockKind::Code
+                    && !snapshot.block_ended
+                    && !matches!(event, ParseEvent::CodeBlockEnd);
+                if closed_code {
+                    self.draw_code_frame(false)?;
+                }
+                self.truncation = Some(quota::Truncation {
+                    events: vec![event.clone()],
+                    snapshot,
+                    mid_block: self.blocks_started == snapshot.blocks_started,
+                    closed_code,
+                });
+                self.end_block(event);
+                return Ok(());
+            }
+        }
+
+        self.modulation = match (&self.meta_styler, &self.span_meta) {
+            (Some(styler), Some(meta)) if event.is_inline() => styler.modulate(meta, &self.style),
+            _ => None,
+        };
+
+        match event {
+            // === Inline elements ===
+            ParseEvent::Link { text, url } => {
+                self.render_link(text, url)?;
+            }
+
+            ParseEvent::Image { alt, url } => {
+                self.render_image(alt, url)?;
+            }
+
+            ParseEvent::Text(_)
+            | ParseEvent::InlineCode(_)
+            | ParseEvent::Bold(_)
+            | ParseEvent::Italic(_)
+            | ParseEvent::BoldItalic(_)
+            | ParseEvent::Underline(_)
+            | ParseEvent::Strikeout(_)
+            | ParseEvent::Footnote(_)
+            | ParseEvent::Spoiler(_)
+            | ParseEvent::Kbd(_) => {
+                let span = self.inline_span(event);
+                self.write_flow(&span)?;
+            }
+
+            // === Block elements ===
+            ParseEvent::Heading { level, content } => {
+                self.breadcrumb.update(event);
+                if *level <= pin::PIN_MAX_LEVEL {
+                    self.draw_pinned_header()?;
+                }
+
+                let content = self.text_passes.apply(content);
+                let lines = render_heading(
+                    *level,
+                    &content,
+                    self.current_width(),
+                    &self.left_margin(),
+                    &self.style,
+                );
+                for line in lines {
+                    self.writeln(&self.visual(&line))?;
+                }
+            }
+
+            ParseEvent::CodeBlockStart { language, .. } => {
+                self.start_live_block(event);
+                self.draw_code_start(language.as_deref())?;
+            }
+
+            ParseEvent::CodeBlockLanguage(language) => {
+                // Guessed language: redraw the block highlighted if it is
+                // live, otherwise highlight from here on
+                if let Some(events) = &mut self.live_block
+                    && let Some(ParseEvent::CodeBlockStart {
+                        language: start, ..
+                    }) = events.first_mut()
+                {
+                    *start = Some(language.clone());
+                }
+                if !self.repaint_live_block()? {
+                    self.code_language = Some(language.clone());
+                    self.start_code_highlight(Some(language));
+                }
+            }
+
+            ParseEvent::CodeBlockLine(line) => {
+                self.extend_live_block(event);
+                self.draw_code_line(line)?;
+            }
+
+            ParseEvent::CodeBlockEnd => {
+                self.live_block = None;
+                let lines = code::render_code_end(
+                    self.current_width(),
+                    &self.left_margin(),
+                    &self.style,
+                    self.features.pretty_pad && !self.features.copy_mode,
+                    self.features.border_style,
+                );
+                for line in lines {
+                    self.writeln(&line)?;
+                }
+
+                // Copied code keeps its fence in copy mode
+                if self.features.copy_mode && !self.code_buffer.is_empty() {
+                    self.code_buffer =
+                        copy::fence(&self.code_buffer, self.code_language.as_deref());
+                }
+
+                // Handle clipboard integration (OSC 52)
+                if self.features.clipboard
+                    && self.policy.clipboard
+                    && !self.features.plain
+                    && !self.code_buffer.is_empty()
+                {
+                    let _ = copy_to_clipboard(&self.code_buffer, &mut self.writer);
+                }
+
+                // Clickable link to the block's saved text
+                if self.features.copy_link
+                    && !self.features.plain
+                    && self.policy.file_links
+                    && self.policy.osc
+                    && !self.code_buffer.is_empty()
+                {
+                    match features::save_snippet(&self.code_buffer) {
+                        Ok(path) => {
+                            let margin = self.left_margin();
+                            let fg = fg_color(&self.style.code_label);
+                            let link = features::file_link(&path, "⧉ copy");
+                            self.writeln_parts(&[&margin, &fg, &link, RESET])?;
+                        }
+                        Err(e) => log::warn!("could not save code snippet: {}", e),
+                    }
+                }
+
+                // Handle savebrace
+                if self.features.savebrace && !self.code_buffer.is_empty() {
+                    let _ = savebrace(&self.code_buffer);
+                }
+
+                self.code_language = None;
+                self.code_buffer.clear();
+                self.start_code_highlight(None);
+            }
+
+            ParseEvent::ListItem {
+                indent,
+                bullet,
+                content,
+            } => {
+                let width = self.current_width();
+                let lines = render_list_item(
+                    *indent,
+                    bullet,
+                    content,
+                    width,
+                    &self.left_margin(),
+                    &self.style,
+                    &mut self.list_state,
+                    &mut self.wrap_cache,
+                    self.features.hyphenate.then_some(&*self.hyphenator),
+                    &mut InlineContext {
+                        features: &self.features,
+                        policy: &self.policy,
+                        style: &self.style,
+                        text_passes: &self.text_passes,
+                        links: &mut self.links,
+                        link_notes: &mut self.link_notes,
+                        qr_pending: &mut self.qr_pending,
+                        inline_handlers: &self.inline_handlers,
+                        width,
+                    },
+                );
+                for line in lines {
+                    self.writeln(&self.visual(&line))?;
+                }
+            }
+
+            ParseEvent::ListEnd => {
+                list::render_list_end(&mut self.list_state);
+            }
+
+            ParseEvent::TableHeader(cells) => {
+                self.table_state.reset();
+                self.table_state.is_header = true;
+                self.table_state.border = self.features.border_style;
+                self.table_state.formatter = self.cell_formatter.clone();
+                self.table_state.text_passes = Arc::clone(&self.text_passes);
+                self.table_state.inline_handlers = Arc::clone(&self.inline_handlers);
+
+                self.start_live_block(event);
+                self.draw_table_row(cells)?;
+            }
+
+            ParseEvent::TableRow(cells) => {
+                self.extend_live_block(event);
+                let width = self.current_width();
+                let repainted = self.live_block.is_some()
+                    && self.table_state.widen(cells, width)
+                    && self.repaint_live_block()?;
+                if !repainted {
+                    self.draw_table_row(cells)?;
+                }
+            }
+
+            ParseEvent::TableSeparator => {
+                self.extend_live_block(event);
+                self.draw_table_separator()?;
+            }
+
+            ParseEvent::TableEnd => {
+                self.table_state.reset();
+                self.live_block = None;
+            }
+
+            ParseEvent::BlockquoteStart { depth } => {
+                self.in_blockquote = true;
+                self.blockquote_depth = *depth;
+            }
+
+            ParseEvent::BlockquoteLine(text) => {
+                let margin = self.left_margin();
+                let width = self.current_width();
+                let marked = self.text_passes.apply(text);
+                let drawn = if self.inline_handlers.is_empty() {
+                    marked
+                } else {
+                    self.inline_handlers.apply(&marked, width, &self.style)
+                };
+                // Wrap text to fit (cached, so reflows skip unchanged paragraphs)
+                let hyphenated = self.hyphenate(&drawn, width);
+                let wrapped =
+                    self.wrap_cache
+                        .wrap(&hyphenated, width, 0, &margin, &margin, false, false);
+                let muted = self
+                    .quote_matcher
+                    .as_ref()
+                    .is_some_and(|m| m.is_quoted(text));
+                let attrs = if muted {
+                    self.style.quoted_attrs
+                } else {
+                    self.style.blockquote_attrs
+                };
+                for line in wrapped.lines {
+                    // Only the text is put in visual order, after the borders
+                    match line.strip_prefix(margin.as_str()) {
+                        Some(body) if !attrs.is_plain() => {
+                            self.writeln_parts(&[&margin, &attrs.apply(&self.visual(body))])?
+                        }
+                        Some(body) => self.writeln_parts(&[&margin, &self.visual(body)])?,
+                        None => self.writeln(&self.visual(&line))?,
+                    }
+                }
+            }
+
+            ParseEvent::BlockquoteEnd => {
+                self.in_blockquote = false;
+                self.blockquote_depth = 0;
+            }
+
+            ParseEvent::ThinkBlockStart => {
+                let fg = fg_color(&self.style.think_border);
+                let glyphs = self.glyphs();
+                self.writeln(&format!(
+                    "{}{}{} thinking {}{}",
+                    fg, glyphs.top_left, glyphs.horizontal, glyphs.horizontal, RESET
+                ))?;
+                self.in_blockquote = true;
+                self.blockquote_depth = 1;
+            }
+
+            ParseEvent::ThinkBlockLine(text) => {
+                let fg = fg_color(&self.style.think_border);
+                let vertical = self.glyphs().vertical;
+                let text = self.style.think_attrs.apply(text);
+                self.writeln_parts(&[&fg, vertical, RESET, " ", &text])?;
+            }
+
+            ParseEvent::ThinkBlockEnd => {
+                let fg = fg_color(&self.style.think_border);
+                self.writeln(&format!("{}{}{}", fg, self.glyphs().bottom_left, RESET))?;
+                self.in_blockquote = false;
+                self.blockquote_depth = 0;
+            }
+
+            ParseEvent::DetailsStart { summary, open } => {
+                if self.details_stack.iter().any(|&collapsed| collapsed) {
+                    // Nested inside a collapsed block: stay hidden
+                    self.details_stack.push(true);
+                } else {
+                    // Folding is raw HTML behavior, so untrusted content
+                    // stays expanded (see `Policy::raw_html`)
+                    let collapsed = self.features.collapse_details && self.policy.raw_html && !open;
+                    self.render_details_summary(summary, collapsed)?;
+                    self.details_stack.push(collapsed);
+                }
+            }
+
+            ParseEvent::DetailsEnd => {
+                self.details_stack.pop();
+            }
+
+            ParseEvent::HorizontalRule => {
+                let fg = fg_color(&self.style.hr);
+                let rule = self.glyphs().horizontal.repeat(self.current_width());
+                let margin = self.left_margin();
+                self.writeln_parts(&[&margin, &fg, &rule, RESET])?;
+            }
+
+            ParseEvent::EmptyLine => {
+                let margin = self.panel_margin();
+                self.writeln(margin.trim_end())?;
+            }
+
+            ParseEvent::Newline => {
+                self.writeln("")?;
+                for url in std::mem::take(&mut self.qr_pending) {
+                    self.draw_qr(&url)?;
+                }
+            }
+
+            ParseEvent::Prompt(prompt) => {
+                self.write(prompt)?;
+            }
+
+            ParseEvent::InlineElements(elements) => {
+                for element in elements {
+                    self.render_inline_element(element)?;
+                }
+            }
+        }
+
+        self.modulation = None;
+
+        self.end_block(event);
+
+        #[cfg(feature = "arena")]
+        self.arena.reset();
+
+        self.writer.flush()
+    }
+
+    /// Draw an intermediate state of a progress line as the next code row,
+    /// returning to its start so the next state overwrites it.
+    pub(crate) fn draw_progress_row(&mut self, text: &str) -> std::io::Result<()> {
+        if self.features.plain {
+            return Ok(());
+        }
+        let bg = bg_color(&self.style.code_bg);
+        let margin = self.left_margin();
+        let (gutter, gutter_width) = if self.features.line_numbers {
+            let number = self.code_line_number + 1;
+            let width = code::gutter_width(number);
+            let gutter = code::line_number_gutter(Some(number), width, self.features.border_style);
+            (gutter, width)
+        } else {
+            (String::new(), 0)
+        };
+        let width = self.current_width().saturating_sub(gutter_width);
+        let row = code::fit_code_line(text, width, false).swap_remove(0);
+        let padding = " ".repeat(width.saturating_sub(visible_length(&row)));
+        let depth = self.color_depth();
+        emit(
+            &mut self.writer,
+            depth,
+            &format!(
+                "{}{}{}{}{}{}{}{}\r",
+                margin, bg, gutter, bg, row, bg, padding, RESET
+            ),
+        )
+    }
+
+    /// Draw one table row with the current column widths.
+    fn draw_table_row(&mut self, cells: &[String]) -> std::io::Result<()> {
+        let width = self.current_width();
+        let margin = self.left_margin();
+        let style = self.style.clone();
+        let lines = render_table_row(cells, &mut self.table_state, width, &margin, &style, false);
+        for line in lines {
+            self.writeln(&line)?;
+        }
+        Ok(())
+    }
+
+    /// Draw the rule under the table header.
+    fn draw_table_separator(&mut self) -> std::io::Result<()> {
+        let sep = render_table_separator(
+            &self.table_state,
+            self.current_width(),
+            &self.left_margin(),
+            &self.style,
+        );
+        self.writeln(&sep)?;
+        self.table_state.end_header();
+        Ok(())
+    }
+
+    /// Draw the top of a code block and start highlighting it.
+    fn draw_code_start(&mut self, language: Option<&str>) -> std::io::Result<()> {
+        self.code_language = language.map(str::to_string);
+        self.code_buffer.clear();
+        self.code_line_number = 0;
+        self.diff_in_hunk = false;
+        self.start_code_highlight(language);
+
+        let lines = code::render_code_start(
+            language,
+            self.current_width(),
+            &self.left_margin(),
+            &self.style,
+            self.features.pretty_pad && !self.features.copy_mode,
+            self.features.border_style,
+        );
+        for line in lines {
+            self.writeln(&line)?;
+        }
+        Ok(())
+    }
+
+    /// Draw the top (`start`) or bottom frame of the current code block
+    /// alone, leaving its state as it is.
+    fn draw_code_frame(&mut self, start: bool) -> std::io::Result<()> {
+        let margin = self.left_margin();
+        let pad = self.features.pretty_pad && !self.features.copy_mode;
+        let lines = if start {
+            code::render_code_start(
+                self.code_language.as_deref(),
+                self.current_width(),
+                &margin,
+                &self.style,
+                pad,
+                self.features.border_style,
+            )
+        } else {
+            self.live_block = None;
+            code::render_code_end(
+                self.current_width(),
+                &margin,
+                &self.style,
+                pad,
+                self.features.border_style,
+            )
+        };
+        for line in lines {
+            self.writeln(&line)?;
+        }
+        Ok(())
+    }
+
+    /// Draw one line of the current code block.
+    fn draw_code_line(&mut self, line: &str) -> std::io::Result<()> {
+        // Progress bars: draw the intermediate states in place (if
+        // asked to), then render the final one as the line
+        self.progress_drawn = 0;
+        let line = if line.contains('\r') {
+            let states = code::progress_states(line);
+            if self.features.progress_lines == ProgressLines::InPlace
+                && code::progress_in_place(self.code_language.as_deref(), line)
+            {
+                for state in states.iter().rev().skip(1).rev() {
+                    self.draw_progress_row(state)?;
+                }
+            }
+            states.last().copied().unwrap_or("")
+        } else {
+            line
+        };
+
+        // Escape codes in the source are dropped unless asked to
+        // pass them through; copied code never carries them
+        let colored = streamdown_ansi::utils::has_escape(line);
+        let plain_line = if colored {
+            streamdown_ansi::sanitize::strip_escape_sequences(line)
+        } else {
+            line.to_string()
+        };
+        let passthrough = colored && self.features.ansi_passthrough;
+        let source = line;
+        let line = plain_line.as_str();
+
+        // Buffer raw code for clipboard/savebrace
+        if !self.code_buffer.is_empty() {
+            self.code_buffer.push('\n');
+        }
+        self.code_buffer.push_str(line);
+
+        // Diffs are colored without the highlighting engine
+        let diff = self.features.highlight
+            && self
+                .code_language
+                .as_deref()
+                .is_some_and(code::is_diff_language);
+        let highlighted = if passthrough {
+            code::passthrough_line(source, &bg_color(&self.style.code_bg))
+        } else if diff {
+            self.diff_in_hunk = code::diff_in_hunk(line, self.diff_in_hunk);
+            code::diff_line(line, self.diff_in_hunk, &self.style)
+        } else {
+            self.highlight_code_line(line)
+        };
+        self.code_line_number += 1;
+
+        // Render with background, after the line number gutter; in
+        // copy mode the code starts at column 0 without decorations
+        let bg = bg_color(&self.style.code_bg);
+        let copy_mode = self.features.copy_mode;
+        let margin = if copy_mode {
+            String::new()
+        } else {
+            self.left_margin()
+        };
+        let trimmed = highlighted.trim_end();
+        let line_numbers = self.features.line_numbers && !copy_mode;
+        let gutter_width = if line_numbers {
+            code::gutter_width(self.code_line_number)
+        } else {
+            0
+        };
+        let content_width = self.current_width().saturating_sub(gutter_width);
+
+        let rows =
+            code::overflow_rows(trimmed, content_width, self.features.code_overflow_policy());
+
+        for (i, row) in rows.iter().enumerate() {
+            let gutter = if line_numbers {
+                let number = (i == 0).then_some(self.code_line_number);
+                code::line_number_gutter(number, gutter_width, self.features.border_style)
+            } else {
+                String::new()
+            };
+            let padding = " "
+                .repeat(content_width.saturating_sub(streamdown_ansi::utils::visible_length(row)));
+            self.writeln_parts(&[&margin, &bg, &gutter, &bg, row, &bg, &padding, RESET])?;
+        }
+        Ok(())
+    }
+
+    /// Start keeping `event`'s block (a table or code block) to repaint it
+    /// in place, if live blocks are on.
+    fn start_live_block(&mut self, event: &ParseEvent) {
+        let live = self.features.live_blocks && !self.features.plain && self.at_line_start;
+        self.live_block = live.then(|| vec![event.clone()]);
+        self.live_block_from = self.lines_written;
+    }
+
+    /// Add `event` to the live block, or stop keeping the block once its
+    /// start has scrolled out of reach.
+    fn extend_live_block(&mut self, event: &ParseEvent) {
+        if !self.live_block_reachable() {
+            self.live_block = None;
+        }
+        if let Some(events) = &mut self.live_block {
+            events.push(event.clone());
+        }
+    }
+
+    /// Whether the cursor can still move back to the live block's first
+    /// row: it must be on screen, below a pinned header.
+    fn live_block_reachable(&self) -> bool {
+        let rows = self
+            .height
+            .map(|height| height.saturating_sub(usize::from(self.pinned.is_some())));
+        rows.is_some_and(|rows| self.lines_written - self.live_block_from < rows)
+    }
+
+    /// Redraw the live block over its earlier output, after a table row
+    /// widened its columns or a code block's language was detected.
+    ///
+    /// Returns false, drawing nothing, when there is no live block or its
+    /// start has scrolled off screen; the block is then drawn on by
+    /// appending.
+    fn repaint_live_block(&mut self) -> std::io::Result<bool> {
+        if !self.live_block_reachable() {
+            self.live_block = None;
+        }
+        let Some(events) = self.live_block.take() else {
+            return Ok(false);
+        };
+        self.erase_lines_since(self.live_block_from)?;
+        if matches!(events.first(), Some(ParseEvent::TableHeader(_))) {
+            self.table_state.is_header = true;
+        }
+        for event in &events {
+            match event {
+                ParseEvent::TableHeader(cells) | ParseEvent::TableRow(cells) => {
+                    self.draw_table_row(cells)?
+                }
+                ParseEvent::TableSeparator => self.draw_table_separator()?,
+                ParseEvent::CodeBlockStart { language, .. } => {
+                    self.draw_code_start(language.as_deref())?
+                }
+                ParseEvent::CodeBlockLine(line) => self.draw_code_line(line)?,
+                _ => {}
+            }
+        }
+        self.live_block = Some(events);
+        Ok(true)
+    }
+
+    /// Swatch to follow inline `code` that is a hex color literal, if
+    /// swatches are enabled and colors are written.
+    fn swatch_for(&self, code: &str) -> String {
+        if !self.features.color_swatches || !self.colors_enabled || self.features.plain {
+            return String::new();
+        }
+        colors::swatch(code).map_or_else(String::new, |swatch| format!(" {}", swatch))
+    }
+
+    /// Render a link: text in the link attributes (with an OSC 8 hyperlink for
+    /// terminals that support it), then the URL in parentheses or, in
+    /// endnote mode, a superscript index into [`render_link_endnotes`].
+    ///
+    /// [`render_link_endnotes`]: Self::render_link_endnotes
+    fn render_link(&mut self, text: &str, url: &str) -> std::io::Result<()> {
+        let hyperlink = !self.features.plain && self.policy.allows_link(url);
+
+        // OSC 8 start
+        if hyperlink {
+            self.write("\x1b]8;;")?;
+            self.write(url)?;
+            self.write("\x1b\\")?;
+        }
+
+        self.write_flow(&self.style.link_attrs.apply(text))?;
+
+        // OSC 8 end
+        if hyperlink {
+            self.write("\x1b]8;;\x1b\\")?;
+        }
+
+        let marks = InlineContext {
+            features: &self.features,
+            policy: &self.policy,
+            style: &self.style,
+            text_passes: &self.text_passes,
+            links: &mut self.links,
+            link_notes: &mut self.link_notes,
+            qr_pending: &mut self.qr_pending,
+            inline_handlers: &self.inline_handlers,
+            width: self.width,
+        }
+        .link_marks(url);
+        self.write_flow(&marks)
+    }
+
+    /// Draw `payload` as a QR code on its own lines, or as text if it is too
+    /// long or the code would not fit the width.
+    fn draw_qr(&mut self, payload: &str) -> std::io::Result<()> {
+        let margin = self.left_margin();
+        for line in qr::payload_lines(payload, self.current_width(), &self.style) {
+            self.writeln_parts(&[&margin, &line])?;
+        }
+        Ok(())
+    }
+
+    /// End the document, leaving the terminal in a sane state even if the
+    /// stream stopped mid-block.
+    ///
+    /// Draws a held block, closes an open code block frame, table, think
+    /// block, blockquote or list, ends a partial line, then writes the link
+    /// endnotes, truncation trailer and stats footer, sends the completion
+    /// notification (each if enabled), resets text attributes and releases
+    /// a pinned header's scroll region. Call it once, after the last event.
+    pub fn finish(&mut self) -> RenderResult<()> {
+        let _scope = OracleScope::new(&self.width_oracle);
+        self.clear_partial_line()?;
+        if let Some(held) = self.held_block.take() {
+            self.release_held_block(held)?;
+        }
+        if let Some(mut streamed) = self.streamed_block.take() {
+            streamed.events.push(ParseEvent::CodeBlockEnd);
+            self.finish_streamed_block(streamed)?;
+        }
+        // Close open blocks from the innermost out
+        if !self.block_ended {
+            let close = match self.current_block.kind {
+                BlockKind::Code => Some(ParseEvent::CodeBlockEnd),
+                BlockKind::Table => Some(ParseEvent::TableEnd),
+                BlockKind::Think => Some(ParseEvent::ThinkBlockEnd),
+                _ => None,
+            };
+            if let Some(event) = close {
+                self.render_event(&event)?;
+            }
+        }
+        if self.in_blockquote {
+            self.render_event(&ParseEvent::BlockquoteEnd)?;
+        }
+        if self.list_state.level() > 0 {
+            self.render_event(&ParseEvent::ListEnd)?;
+        }
+        for _ in 0..self.details_stack.len() {
+            self.render_event(&ParseEvent::DetailsEnd)?;
+        }
+        if !self.at_line_start {
+            self.writeln("")?;
+        }
+
+        self.render_link_endnotes()?;
+        self.render_truncation_trailer()?;
+        self.render_stats_footer()?;
+        self.notify_finished()?;
+        if self.colors_enabled && !self.features.plain && self.lines_written > 0 {
+            self.writer.write_all(RESET.as_bytes())?;
+        }
+        if let Some(old) = self.pinned.take() {
+            write!(self.writer, "{}", old.leave_sequence())?;
+        }
+        Ok(self.writer.flush()?)
+    }
+
+    /// Distinct link URLs of the document so far, in order of appearance;
+    /// link `n` (as marked with
+    /// [`numbered_links`](RenderFeatures::numbered_links)) is `links()[n - 1]`.
+    pub fn links(&self) -> &[String] {
+        &self.links
+    }
+
+    /// Set the program [`open_link`](Self::open_link) launches.
+    pub fn set_link_opener(&mut self, opener: LinkOpener) {
+        self.link_opener = opener;
+    }
+
+    /// Open link `n` (from 1) of the document with the link opener,
+    /// returning its URL.
+    ///
+    /// Fails if there is no such link, the policy does not allow opening it
+    /// (see [`Policy::allows_open`]) or the opener cannot be started.
+    pub fn open_link(&self, n: usize) -> RenderResult<String> {
+        let url = n
+            .checked_sub(1)
+            .and_then(|i| self.links.get(i))
+            .ok_or(RenderError::NoSuchLink(n))?;
+        if !self.policy.allows_open(url) {
+            return Err(RenderError::LinkNotAllowed(url.clone()));
+        }
+        self.link_opener.open(url)?;
+        Ok(url.clone())
+    }
+
+    /// Write the numbered list of link URLs collected in endnote mode, and
+    /// start a new list.
+    pub fn render_link_endnotes(&mut self) -> std::io::Result<()> {
+        let _scope = OracleScope::new(&self.width_oracle);
+        if self.link_notes.is_empty() {
+            return Ok(());
+        }
+        if !self.at_line_start {
+            self.writeln("")?;
+        }
+        let margin = self.left_margin();
+        let fg = fg_color(&self.style.link_url);
+        self.writeln(margin.trim_end())?;
+        for (i, url) in std::mem::take(&mut self.link_notes).iter().enumerate() {
+            let number = format!("{}. ", i + 1);
+            self.writeln_parts(&[&margin, DIM_ON, &number, RESET, &fg, url, RESET])?;
+        }
+        self.writer.flush()
+    }
+
+    /// Render an image, inline if the terminal supports it.
+    ///
+    /// Local PNGs are drawn on their own lines at the content width: with the
+    /// Kitty graphics protocol when available, otherwise as half-block cells
+    /// (`image` feature) if colors are written. Everything else gets a text
+    /// marker.
+    fn render_image(&mut self, alt: &str, url: &str) -> std::io::Result<()> {
+        let local = image::local_image_path(url).is_some();
+        // The file is only read if the policy allows it
+        let png = self
+            .policy
+            .file_links
+            .then(|| image::load_png(url))
+            .flatten();
+        if let Some(png) = &png
+            && self.capabilities.graphics == GraphicsProtocol::Kitty
+            && !self.features.plain
+        {
+            if !self.at_line_start {
+                self.writeln("")?;
+            }
+            let margin = self.left_margin();
+            let seq = image::kitty_image(png, self.current_width());
+            return self.writeln_parts(&[&margin, &seq]);
+        }
+
+        #[cfg(feature = "image")]
+        if let Some(decoded) = png
+            .as_deref()
+            .and_then(image::decode_png)
+            .filter(|_| !self.features.plain && self.colors_enabled)
+        {
+            if !self.at_line_start {
+                self.writeln("")?;
+            }
+            let margin = self.left_margin();
+            for line in image::halfblock_lines(&decoded, self.current_width()) {
+                self.writeln_parts(&[&margin, &line])?;
+            }
+            return Ok(());
+        }
+
+        let fg = fg_color(&self.style.image_marker);
+        let marker = format!("{}[\u{1F5BC} {}]{}", fg, alt, RESET);
+        if !local
+            && !self.features.plain
+            && self.policy.remote_images
+            && self.policy.allows_link(url)
+        {
+            self.write(&format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, marker))
+        } else {
+            self.write(&marker)
+        }
+    }
+
+    /// Render a `<details>` summary line.
+    ///
+    /// Collapsed blocks get `▶` and an expand hint; expanded ones get `▼`.
+    fn render_details_summary(&mut self, summary: &str, collapsed: bool) -> std::io::Result<()> {
+        if !self.at_line_start {
+            self.writeln("")?;
+        }
+        let margin = self.left_margin();
+        let fg = fg_color(&self.style.details_marker);
+        let (marker, hint) = if collapsed {
+            ("▶", format!(" {}[+]{}", DIM_ON, RESET))
+        } else {
+            ("▼", String::new())
+        };
+        self.writeln_parts(&[
+            &margin, &fg, marker, RESET, " ", BOLD_ON, summary, BOLD_OFF, &hint,
+        ])
+    }
+
+    /// Format spoiler text.
+    ///
+    /// When collapsing, the text is concealed (foreground matches the
+    /// background, so selecting it reveals it); otherwise it is shown between
+    /// `‖` markers.
+    fn format_spoiler(&self, text: &str) -> String {
+        if self.features.collapse_details {
+            format!(
+                "{}{}{}{}",
+                bg_color(&self.style.spoiler),
+                fg_color(&self.style.spoiler),
+                text,
+                RESET
+            )
+        } else {
+            list::format_spoiler(text, &self.style)
+        }
+    }
+
+    /// How an inline event is drawn in the text flow. Links and images give
+    /// only what they show there: [`render_link`](Self::render_link) and
+    /// [`render_image`](Self::render_image) also number the link or draw the
+    /// picture.
+    fn inline_span(&self, event: &ParseEvent) -> String {
+        match event {
+            ParseEvent::Text(text) => {
+                // Decode HTML entities like &copy; -> ©
+                let decoded = streamdown_parser::decode_html_entities(text);
+                let decoded = self.text_passes.apply(&decoded);
+                if self.inline_handlers.is_empty() {
+                    decoded
+                } else {
+                    let width = self.current_width();
+                    self.inline_handlers.apply(&decoded, width, &self.style)
+                }
+            }
+            ParseEvent::InlineCode(code) => {
+                let bg = bg_color(&self.style.code_bg);
+                let swatch = self.swatch_for(code);
+                format!("{}{} {} {}{}", bg, DIM_ON, code, RESET, swatch)
+            }
+            ParseEvent::Bold(text) => {
+                let text = self.text_passes.apply(text);
+                format!("{}{}{}", BOLD_ON, text, BOLD_OFF)
+            }
+            ParseEvent::Italic(text) => {
+                let text = self.text_passes.apply(text);
+                format!("{}{}{}", ITALIC_ON, text, ITALIC_OFF)
+            }
+            ParseEvent::BoldItalic(text) => {
+                let text = self.text_passes.apply(text);
+                format!("{}{}{}{}{}", BOLD_ON, ITALIC_ON, text, ITALIC_OFF, BOLD_OFF)
+            }
+            ParseEvent::Underline(text) => {
+                let text = self.text_passes.apply(text);
+                format!("{}{}{}", UNDERLINE_ON, text, UNDERLINE_OFF)
+            }
+            ParseEvent::Strikeout(text) => {
+                let text = self.text_passes.apply(text);
+                format!("{}{}{}", STRIKEOUT_ON, text, STRIKEOUT_OFF)
+            }
+            ParseEvent::Link { text, .. } => self.style.link_attrs.apply(text),
+            ParseEvent::Image { alt, .. } => {
+                let fg = fg_color(&self.style.image_marker);
+                format!("{}[\u{1F5BC} {}]{}", fg, alt, RESET)
+            }
+            ParseEvent::Footnote(superscript) => {
+                let fg = fg_color(&self.style.footnote);
+                format!("{}{}{}", fg, superscript, RESET)
+            }
+            ParseEvent::Spoiler(text) => self.format_spoiler(text),
+            ParseEvent::Kbd(key) => list::format_key(key, &self.style),
+            _ => String::new(),
+        }
+    }
+
+    /// Render an inline element like the event the parser makes of it.
+    fn render_inline_element(&mut self, element: &InlineElement) -> std::io::Result<()> {
+        match ParseEvent::from(element.clone()) {
+            ParseEvent::Link { text, url } => self.render_link(&text, &url),
+            ParseEvent::Image { alt, url } => self.render_image(&alt, &url),
+            event => {
+                let span = self.inline_span(&event);
+                self.write_flow(&span)
+            }
+        }
+    }
+
+    /// Render multiple events.
+    pub fn render(&mut self, events: &[ParseEvent]) -> RenderResult<()> {
+        for event in events {
+            self.render_event(event)?;
+        }
+        Ok(())
+    }
+
+    /// Render a slice of events, handing the output to the writer in one
+    /// write and one flush instead of flushing after each event.
+    ///
+    /// For callers that already hold whole lines or documents; streamed
+    /// output should keep using [`render_event`](Self::render_event).
+    pub fn render_events(&mut self, events: &[ParseEvent]) -> RenderResult<()> {
+        if !self.writer.begin_batch() {
+            return self.render(events);
+        }
+        let rendered = self.render(events);
+        let written = self.writer.end_batch();
+        rendered?;
+        Ok(written?)
+    }
+}
+
+/// The renderer state links and list item text are drawn with, borrowed
+/// field by field so that list items can be drawn while the list state is
+/// borrowed.
+struct InlineContext<'a> {
+    features: &'a RenderFeatures,
+    policy: &'a Policy,
+    style: &'a RenderStyle,
+    text_passes: &'a TextPasses,
+    links: &'a mut Vec<String>,
+    link_notes: &'a mut Vec<String>,
+    qr_pending: &'a mut Vec<String>,
+    inline_handlers: &'a InlineHandlers,
+    /// Content width the inline handlers draw for
+    width: usize,
+}
+
+impl InlineContext<'_> {
+    /// What follows the text of a link to `url`: its number in
+    /// [`Renderer::links`] (with numbered links), then the URL in
+    /// parentheses or its endnote mark.
+    fn link_marks(&mut self, url: &str) -> String {
+        let fg = fg_color(&self.style.link_url);
+        let mut marks = String::new();
+        let number = match self.links.iter().position(|u| u == url) {
+            Some(i) => i + 1,
+            None => {
+                self.links.push(url.to_string());
+                self.links.len()
+            }
+        };
+        if self.features.numbered_links {
+            marks.push_str(&format!("{}{}[{}]{}", fg, DIM_ON, number, RESET));
+        }
+
+        if self.features.qr_links && !self.qr_pending.iter().any(|u| u == url) {
+            self.qr_pending.push(url.to_string());
+        }
+
+        match self.features.link_style {
+            LinkStyle::Inline => {
+                // Show URL in parentheses (dimmed)
+                marks.push_str(&format!(" {}({}){}", fg, url, RESET));
+            }
+            LinkStyle::Endnotes => {
+                let index = match self.link_notes.iter().position(|u| u == url) {
+                    Some(i) => i + 1,
+                    None => {
+                        self.link_notes.push(url.to_string());
+                        self.link_notes.len()
+                    }
+                };
+                let mark = number_to_superscript(index as u32);
+                marks.push_str(&format!("{}{}{}", fg, mark, RESET));
+            }
+        }
+        marks
+    }
+}
+
+impl InlineDraw for InlineContext<'_> {
+    fn text(&mut self, text: &str) -> String {
+        let text = self.text_passes.apply(text);
+        if self.inline_handlers.is_empty() {
+            text
+        } else {
+            self.inline_handlers.apply(&text, self.width, self.style)
+        }
+    }
+
+    fn link(&mut self, text: &str, url: &str, style: &RenderStyle) -> String {
+        let mut drawn = style.link_attrs.apply(text);
+        if !self.features.plain && self.policy.allows_link(url) {
+            drawn = format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, drawn);
+        }
+        drawn + &self.link_marks(url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use streamdown_ansi::width::str_width;
+    use streamdown_parser::{ListBullet, Parser};
+
+    #[test]
+    fn test_render_heading() {
+        let mut output = Vec::new();
+        let mut renderer = Renderer::new(&mut output, 80);
+
+        renderer
+            .render_event(&ParseEvent::Heading {
+                level: 1,
+                content: "Title".to_string(),
+            })
+            .unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        assert!(result.contains("Title"));
+        assert!(result.contains(BOLD_ON));
+    }
+
+    #[test]
+    fn test_render_h2_colored() {
+        let mut output = Vec::new();
+        let mut renderer = Renderer::new(&mut output, 80);
+
+        renderer
+            .render_event(&ParseEvent::Heading {
+                level: 2,
+                content: "Subtitle".to_string(),
+            })
+            .unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        assert!(result.contains("Subtitle"));
+        assert!(result.contains("\x1b[38;2;")); // Color code
+    }
+
+    #[test]
+    fn test_render_code_block() {
+        let mut output = Vec::new();
+        let mut renderer = Renderer::new(&mut output, 80);
+
+        renderer
+            .render_event(&ParseEvent::CodeBlockStart {
+                language: Some("rust".to_string()),
+                indent: 0,
+            })
+            .unwrap();
+        renderer
+            .render_event(&ParseEvent::CodeBlockLine("let x = 1;".to_string()))
+            .unwrap();
+        renderer.render_event(&ParseEvent::CodeBlockEnd).unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        // The code block contains the code (may have ANSI formatting)
+        let visible = streamdown_ansi::utils::visible(&result);
+        assert!(visible.contains("let x = 1;") || visible.contains("let"));
+    }
+
+    #[cfg(feature = "syntax-highlighting")]
+    #[test]
+    fn test_render_code_block_highlighted() {
+        let mut output = Vec::new();
+        let mut renderer = Renderer::new(&mut output, 80);
+
+        renderer
+            .render_event(&ParseEvent::CodeBlockStart {
+                language: Some("rust".to_string()),
+                indent: 0,
+            })
+            .unwrap();
+        renderer
+            .render_event(&ParseEvent::CodeBlockLine("fn main() {}".to_string()))
+            .unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        let code_line = result.lines().last().unwrap();
+        // Token colors, not just the block background
+        assert!(code_line.contains("\x1b[38;2;"));
+    }
+
+    #[cfg(feature = "syntax-highlighting")]
+    #[test]
+    fn test_render_code_block_guessed_language() {
+        let mut output = Vec::new();
+        let mut renderer = Renderer::new(&mut output, 80);
+
+        for event in [
+            ParseEvent::CodeBlockStart {
+                language: None,
+                indent: 0,
+            },
+            ParseEvent::CodeBlockLanguage("rust".to_string()),
+            ParseEvent::CodeBlockLine("fn main() {}".to_string()),
+        ] {
+            renderer.render_event(&event).unwrap();
+        }
+
+        let result = String::from_utf8(output).unwrap();
+        let code_line = result.lines().last().unwrap();
+        assert!(code_line.contains("\x1b[38;2;"));
+    }
+
+    #[test]
+    fn test_render_code_block_unknown_language_plain() {
+        let mut output = Vec::new();
+        let mut renderer = Renderer::new(&mut output, 80);
+
+        renderer
+            .render_event(&ParseEvent::CodeBlockStart {
+                language: Some("unknown-lang-xyz".to_string()),
+                indent: 0,
+            })
+            .unwrap();
+        renderer
+            .render_event(&ParseEvent::CodeBlockLine("fn main() {}".to_string()))
+            .unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        let code_line = result.lines().last().unwrap();
+        assert!(code_line.contains("fn main() {}"));
+        assert!(!code_line.contains("\x1b[38;2;"));
+    }
+
+    #[test]
+    fn test_render_code_block_highlight_disabled() {
+        let mut output = Vec::new();
+        let mut renderer = Renderer::new(&mut output, 80);
+        renderer.set_highlight(false);
+
+        renderer
+            .render_event(&ParseEvent::CodeBlockStart {
+                language: Some("rust".to_string()),
+                indent: 0,
+            })
+            .unwrap();
+        renderer
+            .render_event(&ParseEvent::CodeBlockLine("fn main() {}".to_string()))
+            .unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        let code_line = result.lines().last().unwrap();
+        assert!(code_line.contains("fn main() {}"));
+        assert!(!code_line.contains("\x1b[38;2;"));
+    }
+
+    #[test]
+    fn test_render_code_block_pretty_pad() {
+        let mut output = Vec::new();
+        let mut renderer = Renderer::new(&mut output, 80);
+        renderer.set_pretty_pad(true);
+
+        renderer
+            .render_event(&ParseEvent::CodeBlockStart {
+                language: Some("rust".to_string()),
+                indent: 0,
+            })
+            .unwrap();
+        renderer.render_event(&ParseEvent::CodeBlockEnd).unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        assert!(result.contains(CODEPAD_TOP));
+        assert!(result.contains(CODEPAD_BOTTOM));
+    }
+
+    #[test]
+    fn test_render_list() {
+        let mut output = Vec::new();
+        let mut renderer = Renderer::new(&mut output, 80);
+
+        renderer
+            .render_event(&ParseEvent::ListItem {
+                indent: 0,
+                bullet: ListBullet::Dash,
+                content: "Item".to_string(),
+            })
+            .unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        assert!(result.contains("•")); // Bullet
+        assert!(result.contains("Item"));
+    }
+
+    #[test]
+    fn test_render_ordered_list() {
+        let mut output = Vec::new();
+        let mut renderer = Renderer::new(&mut output, 80);
+
+        renderer
+            .render_event(&ParseEvent::ListItem {
+                indent: 0,
+                bullet: ListBullet::Ordered(1),
+                content: "First".to_string(),
+            })
+            .unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        assert!(result.contains("1.")); // Number
+        assert!(result.contains("First"));
+    }
+
+    #[test]
+    fn test_render_table() {
+        let mut output = Vec::new();
+        let mut renderer = Renderer::new(&mut output, 80);
+
+        renderer
+            .render_event(&ParseEvent::TableHeader(vec![
+                "A".to_string(),
+                "B".to_string(),
+            ]))
+            .unwrap();
+        renderer.render_event(&ParseEvent::TableSeparator).unwrap();
+        renderer
+            .render_event(&ParseEvent::TableRow(vec![
+                "1".to_string(),
+                "2".to_string(),
+            ]))
+            .unwrap();
+        renderer.render_event(&ParseEvent::TableEnd).unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        assert!(result.contains("A"));
+        assert!(result.contains("1"));
+    }
+
+    #[test]
+    fn test_render_blockquote() {
+        let mut output = Vec::new();
+        let mut renderer = Renderer::new(&mut output, 80);
+
+        renderer
+            .render_event(&ParseEvent::BlockquoteStart { depth: 1 })
+            .unwrap();
+        renderer
+            .render_event(&ParseEvent::BlockquoteLine("Quote text".to_string()))
+            .unwrap();
+        renderer.render_event(&ParseEvent::BlockquoteEnd).unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        assert!(result.contains("Quote text"));
+    }
+
+    #[test]
+    fn test_render_think_block() {
+        let mut output = Vec::new();
+        let mut renderer = Renderer::new(&mut output, 80);
+
+        renderer.render_event(&ParseEvent::ThinkBlockStart).unwrap();
+        renderer
+            .render_event(&ParseEvent::ThinkBlockLine("Thinking...".to_string()))
+            .unwrap();
+        renderer.render_event(&ParseEvent::ThinkBlockEnd).unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        assert!(result.contains("thinking"));
+        assert!(result.contains("Thinking..."));
+    }
+
+    #[test]
+    fn test_render_horizontal_rule() {
+        let mut output = Vec::new();
+        let mut renderer = Renderer::new(&mut output, 40);
+
+        renderer.render_event(&ParseEvent::HorizontalRule).unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        assert!(result.contains("─"));
+    }
+
+    #[test]
+    fn test_render_link() {
+        let mut output = Vec::new();
+        let mut renderer = Renderer::new(&mut output, 80);
+
+        renderer
+            .render_event(&ParseEvent::Link {
+                text: "Click here".to_string(),
+                url: "https://example.com".to_string(),
+            })
+            .unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        assert!(result.contains("Click here"));
+        assert!(result.contains("example.com"));
+    }
+
+    #[test]
+    fn test_render_image_marker_fallback() {
+        let mut output = Vec::new();
+        let mut renderer = Renderer::new(&mut output, 80);
+
+        renderer
+            .render_event(&ParseEvent::Image {
+                alt: "diagram".to_string(),
+                url: "https://example.com/a.png".to_string(),
+            })
+            .unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        assert!(result.contains("[\u{1F5BC} diagram]"));
+        assert!(!result.contains("\x1b_G"));
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_render_image_halfblock_needs_color() {
+        let path = std::env::temp_dir().join(format!("sd-mosaic-{}.png", std::process::id()));
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, 1, 2);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&[255, 0, 0, 0, 0, 255]).unwrap();
+        writer.finish().unwrap();
+        std::fs::write(&path, &png).unwrap();
+
+        let render = |color| {
+            let mut renderer = Renderer::new(Vec::new(), 40);
+            renderer.set_options(RendererOptions { color });
+            renderer
+                .render_event(&ParseEvent::Image {
+                    alt: "mosaic".to_string(),
+                    url: path.display().to_string(),
+                })
+                .unwrap();
+            String::from_utf8(renderer.into_writer()).unwrap()
+        };
+        let colored = render(ColorChoice::Always);
+        let monochrome = render(ColorChoice::Never);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(colored.contains('▀') && !colored.contains("mosaic"));
+        assert!(!monochrome.contains('▀') && monochrome.contains("mosaic"));
+    }
+
+    #[test]
+    fn test_render_image_kitty() {
+        let path = std::env::temp_dir().join(format!("sd-render-{}.png", std::process::id()));
+        std::fs::write(&path, b"\x89PNG\r\n\x1a\n").unwrap();
+
+        let mut output = Vec::new();
+        let mut renderer = Renderer::new(&mut output, 60);
+        renderer.set_capabilities(TerminalCapabilities {
+            graphics: GraphicsProtocol::Kitty,
+            ..Default::default()
+        });
+        renderer
+            .render_event(&ParseEvent::Image {
+                alt: "logo".to_string(),
+                url: format!("file://{}", path.display()),
+            })
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        assert!(result.contains("\x1b_Ga=T,f=100,q=2,c=60,"));
+        assert!(!result.contains("logo"));
+    }
+
+    #[test]
+    fn test_code_progress_lines() {
+        let lines = [
+            "```",
+            "Downloading 10%\rDownloading 60%\rDownloading 100%",
+            "```",
+        ];
+
+        let mut renderer = Renderer::new(Vec::new(), 40);
+        render_markdown(&mut renderer, &lines);
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        assert!(!output.contains('\r'));
+        assert!(!output.contains("60%"));
+        assert!(output.contains("Downloading 100%"));
+
+        let features = RenderFeatures {
+            progress_lines: ProgressLines::InPlace,
+            ..Default::default()
+        };
+        let mut renderer = Renderer::with_features(Vec::new(), 40, features.clone());
+        render_markdown(&mut renderer, &lines);
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        assert_eq!(output.matches('\r').count(), 2);
+        assert!(output.contains("60%"));
+        // All states share one row
+        let plain = streamdown_ansi::utils::visible(&output);
+        assert_eq!(
+            plain.lines().filter(|l| l.contains("Downloading")).count(),
+            1
+        );
+
+        // Code that is not progress output shows its final state only
+        let mut renderer = Renderer::with_features(Vec::new(), 40, features);
+        render_markdown(&mut renderer, &["```python", "a = 1\rb = 2", "```"]);
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        assert!(!output.contains('\r'));
+    }
+
+    #[test]
+    fn test_partial_code_line_progress() {
+        let features = RenderFeatures {
+            progress_lines: ProgressLines::InPlace,
+            ..Default::default()
+        };
+        let mut renderer = Renderer::with_features(Vec::new(), 40, features);
+        render_markdown(&mut renderer, &["```"]);
+        renderer.writer_mut().clear();
+        renderer.draw_partial_line("fetch 10%\rfetch 5").unwrap();
+        let first = String::from_utf8(renderer.writer_mut().clone()).unwrap();
+        assert!(first.contains("fetch 10%") && first.ends_with('\r'));
+        // No new state yet: nothing is redrawn
+        renderer.writer_mut().clear();
+        renderer.draw_partial_line("fetch 50%").unwrap();
+        assert!(renderer.writer_mut().is_empty());
+
+        // Without colors the row has no background
+        renderer.set_options(RendererOptions {
+            color: ColorChoice::Never,
+        });
+        renderer
+            .draw_partial_line("fetch 10%\rfetch 50%\rfetch 7")
+            .unwrap();
+        let row = String::from_utf8(renderer.into_writer()).unwrap();
+        assert!(
+            row.starts_with("fetch 50%") && !row.contains('\x1b'),
+            "{row:?}"
+        );
+    }
+
+    #[test]
+    fn test_gutter_annotations() {
+        let mut renderer = Renderer::new(Vec::new(), 40);
+        renderer.set_gutter(8);
+        assert_eq!(renderer.width(), 31);
+        renderer.annotate("L12");
+        render_markdown(&mut renderer, &["# Title", "body"]);
+        renderer.set_width(50);
+        assert_eq!(renderer.width(), 41);
+
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(lines[0], gutter_cell(31, 8, "L12"));
+        assert_eq!(output.matches("L12").count(), 1);
+    }
+
+    #[test]
+    fn test_colors_downgrade_to_terminal_depth() {
+        let render = |colors| {
+            let mut renderer = Renderer::new(Vec::new(), 40);
+            renderer.set_capabilities(TerminalCapabilities {
+                colors,
+                ..Default::default()
+            });
+            render_markdown(&mut renderer, &["## Title", "```", "code", "```"]);
+            String::from_utf8(renderer.into_writer()).unwrap()
+        };
+        let truecolor = render(ColorDepth::TrueColor);
+        assert!(truecolor.contains("38;2;"));
+        for depth in [ColorDepth::Ansi256, ColorDepth::Ansi16] {
+            let output = render(depth);
+            assert!(!output.contains(";2;"), "{depth:?}: {output:?}");
+            assert_eq!(visible(&output), visible(&truecolor));
+        }
+    }
+
+    #[test]
+    fn test_no_color_keeps_layout() {
+        let render = |color| {
+            let mut renderer = Renderer::new(Vec::new(), 30);
+            renderer.set_options(RendererOptions { color });
+            render_markdown(&mut renderer, &["## **Title**", "```", "code", "```"]);
+            String::from_utf8(renderer.into_writer()).unwrap()
+        };
+        let colored = render(ColorChoice::Always);
+        let output = render(ColorChoice::Never);
+        assert!(!output.contains("\x1b[3"), "{output:?}");
+        assert_eq!(output, visible(&colored));
+        // Code blocks keep their padding to full width
+        assert!(output.lines().any(|l| l.starts_with(' ') && l.len() >= 30));
+    }
+
+    #[test]
+    fn test_light_style_is_valid() {
+        let light = RenderStyle::light();
+        for (key, color) in light.colors() {
+            assert!(is_valid_color(color), "{key}: {color}");
+        }
+        assert_ne!(light.h1, RenderStyle::default().h1);
+        assert_eq!(Background::Light.style().code_bg, light.code_bg);
+    }
+
+    #[test]
+    fn test_render_qr_fence_and_links() {
+        let mut renderer = Renderer::with_features(Vec::new(), 40, RenderFeatures::plain());
+        render_markdown(&mut renderer, &["```qr", "https://example.com", "```"]);
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        let expected = QrCode::encode(b"https://example.com").unwrap().lines();
+        assert_eq!(output.lines().count(), expected.len());
+        assert!(output.contains(&expected[1]));
+
+        let features = RenderFeatures {
+            qr_links: true,
+            ..RenderFeatures::plain()
+        };
+        let mut renderer = Renderer::with_features(Vec::new(), 40, features);
+        render_markdown(&mut renderer, &["see [docs](https://e.com) now", ""]);
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        let qr = QrCode::encode(b"https://e.com").unwrap().lines();
+        assert!(output.contains(&qr[3]), "{output}");
+
+        // Too wide for the column: the payload is shown instead
+        let mut renderer = Renderer::with_features(Vec::new(), 12, RenderFeatures::plain());
+        render_markdown(&mut renderer, &["```qr", "hello", "```"]);
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        assert!(output.contains("hello") && !output.contains('█'));
+    }
+
+    #[test]
+    fn test_resize_waits_for_block_end() {
+        let mut renderer = Renderer::new(Vec::new(), 40);
+        renderer.set_options(RendererOptions {
+            color: ColorChoice::Never,
+        });
+        let mut parser = streamdown_parser::Parser::new();
+        for line in ["```", "one", "two", "```", "", "---"] {
+            if line == "two" {
+                renderer.resize(20);
+            }
+            for event in parser.parse_line(line) {
+                renderer.render_event(&event).unwrap();
+            }
+        }
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        let width = |needle| {
+            output
+                .lines()
+                .find(|l| l.contains(needle))
+                .map_or(0, |l| l.chars().count())
+        };
+        // The code block keeps its frame; the rule after it is narrower
+        assert_eq!(width("two"), 40);
+        assert_eq!(width("─"), 20, "{output}");
+    }
+
+    #[test]
+    fn test_resize_in_block_survives_reset() {
+        let mut renderer = Renderer::new(Vec::new(), 40);
+        render_markdown(&mut renderer, &["```", "one"]);
+        renderer.resize(30);
+        assert_eq!(renderer.width(), 40);
+        renderer.reset();
+        assert_eq!(renderer.width(), 30);
+    }
+
+    #[test]
+    fn test_set_style_waits_for_block_end() {
+        let mut renderer = Renderer::new(Vec::new(), 40);
+        let mut parser = streamdown_parser::Parser::new();
+        let light = RenderStyle::light();
+        for line in ["| a |", "|---|", "| 1 |", "| 2 |", "", "| b |", "|---|"] {
+            if line == "| 2 |" {
+                renderer.set_style(light.clone());
+                assert_ne!(renderer.style().table_header_bg, light.table_header_bg);
+            }
+            for event in parser.parse_line(line) {
+                renderer.render_event(&event).unwrap();
+            }
+        }
+        renderer.finish().unwrap();
+        assert_eq!(renderer.style().table_header_bg, light.table_header_bg);
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        // The first table keeps its look; the second uses the new style
+        let new_header = bg_color(&light.table_header_bg);
+        let row = |cell| {
+            output
+                .lines()
+                .find(|line| visible(line).contains(cell))
+                .unwrap()
+        };
+        assert!(!row(" a ").contains(&new_header));
+        assert!(row(" b ").contains(&new_header), "{output:?}");
+    }
+
+    #[test]
+    fn test_rerender_at_new_width() {
+        let mut renderer = Renderer::new(Vec::new(), 30);
+        renderer.set_options(RendererOptions {
+            color: ColorChoice::Never,
+        });
+        renderer.set_retain_events(true);
+        render_markdown(&mut renderer, &["# Title", "", "---"]);
+        let first = String::from_utf8(std::mem::take(renderer.writer_mut())).unwrap();
+        let lines = first.lines().count();
+
+        renderer.rerender(12).unwrap();
+        let second = String::from_utf8(renderer.into_writer()).unwrap();
+        let redraw = format!("\x1b[{}F\x1b[J", lines);
+        let body = second.strip_prefix(&redraw).expect(&second);
+        assert_eq!(body.lines().count(), lines);
+        assert!(body.contains(&"─".repeat(12)) && !body.contains(&"─".repeat(13)));
+    }
+
+    #[test]
+    fn test_rerender_erases_wrapped_rows() {
+        let mut renderer = Renderer::new(Vec::new(), 10);
+        renderer.set_retain_events(true);
+        // Left to the terminal to wrap: one line, three rows
+        render_markdown(&mut renderer, &["abcdefghij klmnopqrst uvwxy"]);
+        renderer.writer_mut().clear();
+
+        renderer.rerender(40).unwrap();
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        assert!(output.starts_with("\x1b[3F\x1b[J"), "{output:?}");
+    }
+
+    #[test]
+    fn test_live_table_repaints_when_columns_widen() {
+        let features = RenderFeatures {
+            live_blocks: true,
+            ..Default::default()
+        };
+        let mut renderer = Renderer::with_features(Vec::new(), 40, features);
+        renderer.set_options(RendererOptions {
+            color: ColorChoice::Never,
+        });
+        renderer.set_height(Some(24));
+        let mut parser = streamdown_parser::Parser::new();
+        let mut feed = |renderer: &mut Renderer<Vec<u8>>, line: &str| {
+            renderer.render(&parser.parse_line(line)).unwrap();
+            String::from_utf8(std::mem::take(renderer.writer_mut())).unwrap()
+        };
+        feed(&mut renderer, "| a | b |");
+        feed(&mut renderer, "|---|---|");
+        let row = feed(&mut renderer, "| x | y |");
+        assert!(!row.contains("\x1b[J"));
+
+        // A long cell rebalances the columns: header, rule and rows again
+        let repaint = feed(&mut renderer, "| a much longer first cell | z |");
+        let body = repaint.strip_prefix("\x1b[3F\x1b[J").expect(&repaint);
+        assert_eq!(body.lines().count(), 4);
+        assert!(body.lines().next().unwrap().starts_with(" a "));
+    }
+
+    #[test]
+    fn test_live_blocks_append_once_off_screen() {
+        let features = RenderFeatures {
+            live_blocks: true,
+            ..Default::default()
+        };
+        let lines = [
+            "| a | b |",
+            "|---|---|",
+            "| x | y |",
+            "| a much longer cell | z |",
+        ];
+        for height in [None, Some(3)] {
+            let mut renderer = Renderer::with_features(Vec::new(), 40, features.clone());
+            renderer.set_height(height);
+            render_markdown(&mut renderer, &lines);
+            let output = String::from_utf8(renderer.into_writer()).unwrap();
+            assert!(!output.contains("\x1b[J"), "{height:?}: {output:?}");
+        }
+    }
+
+    #[test]
+    fn test_live_code_block_repaints_with_detected_language() {
+        let features = RenderFeatures {
+            live_blocks: true,
+            ..Default::default()
+        };
+        let mut renderer = Renderer::with_features(Vec::new(), 40, features);
+        renderer.set_options(RendererOptions {
+            color: ColorChoice::Never,
+        });
+        renderer.set_height(Some(24));
+        for event in [
+            ParseEvent::CodeBlockStart {
+                language: None,
+                indent: 0,
+            },
+            ParseEvent::CodeBlockLine("x".to_string()),
+        ] {
+            renderer.render_event(&event).unwrap();
+        }
+        let before = std::mem::take(renderer.writer_mut());
+        let drawn = String::from_utf8(before).unwrap().lines().count();
+
+        renderer
+            .render_event(&ParseEvent::CodeBlockLanguage("rust".to_string()))
+            .unwrap();
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        let body = output
+            .strip_prefix(&format!("\x1b[{}F\x1b[J", drawn))
+            .expect(&output);
+        assert_eq!(body.lines().count(), drawn);
+        assert!(visible(body).contains('x'));
+    }
+
+    #[test]
+    fn test_render_kbd() {
+        let mut renderer = Renderer::with_features(Vec::new(), 40, RenderFeatures::plain());
+        render_markdown(&mut renderer, &["<kbd>Ctrl</kbd>+<kbd>C</kbd> to stop"]);
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        assert_eq!(output.trim(), "▐Ctrl▌+▐C▌ to stop");
+    }
+
+    #[test]
+    fn test_finish_closes_open_code_block() {
+        let mut renderer = Renderer::with_features(Vec::new(), 20, RenderFeatures::plain());
+        render_markdown(&mut renderer, &["```rust", "let x = 1;"]);
+        renderer.finish().unwrap();
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        let mut closed = Renderer::with_features(Vec::new(), 20, RenderFeatures::plain());
+        render_markdown(&mut closed, &["```rust", "let x = 1;", "```"]);
+        assert_eq!(output, String::from_utf8(closed.into_writer()).unwrap());
+
+        // A line cut off mid-paragraph is ended, and attributes reset
+        let mut renderer = Renderer::new(Vec::new(), 20);
+        renderer.set_options(RendererOptions {
+            color: ColorChoice::Always,
+        });
+        renderer
+            .render_event(&ParseEvent::Bold("cut".to_string()))
+            .unwrap();
+        renderer.finish().unwrap();
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        assert!(output.ends_with(&format!("\n{}", RESET)), "{output:?}");
+    }
+
+    #[test]
+    fn test_finish_closes_nested_blocks() {
+        let mut renderer = Renderer::with_features(Vec::new(), 30, RenderFeatures::plain());
+        let events = [
+            ParseEvent::ListItem {
+                indent: 0,
+                bullet: ListBullet::Dash,
+                content: "item".to_string(),
+            },
+            ParseEvent::BlockquoteStart { depth: 2 },
+            ParseEvent::BlockquoteLine("quoted".to_string()),
+            ParseEvent::CodeBlockStart {
+                language: None,
+                indent: 0,
+            },
+            ParseEvent::CodeBlockLine("code".to_string()),
+        ];
+        renderer.render(&events).unwrap();
+        renderer.finish().unwrap();
+        assert!(renderer.block_ended);
+        assert!(!renderer.in_blockquote);
+        assert_eq!(renderer.list_state.level(), 0);
+    }
+
+    #[test]
+    fn test_block_timeout_releases_held_block() {
+        let features = RenderFeatures {
+            collapse: CollapseOptions {
+                think: true,
+                code_over: None,
+            },
+            ..RenderFeatures::plain()
+        };
+        let mut renderer = Renderer::with_features(Vec::new(), 40, features);
+        let mut parser = streamdown_parser::Parser::new();
+        for line in ["<think>", "pondering"] {
+            renderer.render(&parser.parse_line(line)).unwrap();
+        }
+        assert!(!renderer.release_stale_block().unwrap());
+        assert!(renderer.writer_mut().is_empty());
+
+        renderer.set_block_timeout(Some(Duration::ZERO));
+        assert!(renderer.release_stale_block().unwrap());
+        for line in ["still", "</think>", "after"] {
+            renderer.render(&parser.parse_line(line)).unwrap();
+        }
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        assert!(output.contains("thinking") && output.contains("pondering"));
+        assert!(output.contains("still") && output.contains("after"));
+        assert!(!output.contains("[+]"));
+    }
+
+    #[test]
+    fn test_reset_between_messages() {
+        let fresh = || Renderer::with_features(Vec::new(), 30, RenderFeatures::plain());
+        let second = ["2. item", "", "text"];
+
+        let mut renderer = fresh();
+        renderer.set_stats(true);
+        render_markdown(&mut renderer, &["> quoted", "1. one", "```", "open"]);
+        renderer.reset();
+        renderer.writer_mut().clear();
+        render_markdown(&mut renderer, &second);
+        assert_eq!(renderer.stats_mut().unwrap().words(), 2);
+
+        let mut expected = fresh();
+        render_markdown(&mut expected, &second);
+        assert_eq!(renderer.into_writer(), expected.into_writer());
+    }
+
+    #[test]
+    fn test_render_events_writes_once() {
+        /// Counts writes and flushes.
+        #[derive(Default)]
+        struct Counting(Vec<u8>, usize, usize);
+        impl Write for Counting {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.extend_from_slice(buf);
+                self.1 += 1;
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                self.2 += 1;
+                Ok(())
+            }
+        }
+
+        let mut parser = streamdown_parser::Parser::new();
+        let mut events = Vec::new();
+        for line in ["# Title", "", "- a", "- b", "", "```", "x", "```"] {
+            events.extend(parser.parse_line(line));
+        }
+        let mut renderer =
+            Renderer::with_features(Counting::default(), 30, RenderFeatures::plain());
+        renderer.render_events(&events).unwrap();
+        let batched = renderer.into_writer();
+        assert_eq!((batched.1, batched.2), (1, 1));
+
+        let mut renderer =
+            Renderer::with_features(Counting::default(), 30, RenderFeatures::plain());
+        renderer.render(&events).unwrap();
+        assert_eq!(renderer.into_writer().0, batched.0);
+    }
+
+    #[test]
+    fn test_rerender_block_by_id() {
+        let mut renderer = Renderer::with_features(Vec::new(), 30, RenderFeatures::plain());
+        renderer.set_retain_events(true);
+        let mut parser = streamdown_parser::Parser::new();
+        for line in ["# Title", "", "first draft", "", "last words"] {
+            renderer.render(&parser.parse_line(line)).unwrap();
+        }
+        let id = renderer.block_id(1).unwrap();
+        let draft = || renderer.rerender_block(id, "second **draft**", Parser::new());
+        let patch = draft().unwrap().unwrap();
+        assert_eq!(patch.block, 1);
+        assert_eq!(patch.lines, ["second draft", ""]);
+        assert_eq!(draft().unwrap(), Some(patch.clone()));
+
+        assert!(renderer.apply_block_patch(&patch));
+        assert!(!renderer.apply_block_patch(&patch));
+        renderer.writer_mut().clear();
+        renderer.rerender(30).unwrap();
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        assert!(output.contains("second draft\n\nlast words"), "{output:?}");
+        assert!(!output.contains("first"));
+    }
+
+    #[test]
+    fn test_rerender_block_retained_late() {
+        let mut renderer = Renderer::with_features(Vec::new(), 30, RenderFeatures::plain());
+        let mut parser = streamdown_parser::Parser::new();
+        renderer.render(&parser.parse_line("# Before")).unwrap();
+        renderer.set_retain_events(true);
+        for line in ["first", "", "second"] {
+            renderer.render(&parser.parse_line(line)).unwrap();
+        }
+        let id = renderer.block_id(2).unwrap();
+        let patch = renderer
+            .rerender_block(id, "2nd", Parser::new())
+            .unwrap()
+            .unwrap();
+        assert_eq!(patch.block, 2);
+        assert_eq!(patch.events, 3..5);
+        // Blocks from before retention can't be patched
+        let before = renderer.block_id(0).unwrap();
+        assert_eq!(
+            renderer.rerender_block(before, "x", Parser::new()).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_width_too_small_is_recoverable() {
+        let mut renderer = Renderer::new(Vec::new(), 2);
+        let err = renderer
+            .render_event(&ParseEvent::Text("hi".to_string()))
+            .unwrap_err();
+        assert!(matches!(err, RenderError::WidthTooSmall { width: 2, .. }));
+        assert!(err.is_recoverable());
+        assert!(renderer.writer_mut().is_empty());
+
+        renderer.resize(20);
+        renderer
+            .render_event(&ParseEvent::Text("hi".to_string()))
+            .unwrap();
+        assert!(!renderer.writer_mut().is_empty());
+    }
+
+    #[test]
+    fn test_text_attributes_from_style() {
+        let style = RenderStyle::builder()
+            .heading_attrs(1, TextAttributes::ITALIC)
+            .blockquote_attrs(TextAttributes::ITALIC)
+            .think_attrs(TextAttributes::DIM)
+            .link_attrs(TextAttributes::PLAIN)
+            .build()
+            .unwrap();
+        let mut output = Vec::new();
+        let mut renderer = Renderer::with_style(&mut output, 80, style);
+        renderer
+            .render_events(&[
+                ParseEvent::Heading {
+                    level: 1,
+                    content: "Title".to_string(),
+                },
+                ParseEvent::BlockquoteStart { depth: 1 },
+                ParseEvent::BlockquoteLine("quoted".to_string()),
+                ParseEvent::BlockquoteEnd,
+                ParseEvent::ThinkBlockStart,
+                ParseEvent::ThinkBlockLine("hmm".to_string()),
+                ParseEvent::ThinkBlockEnd,
+                ParseEvent::Link {
+                    text: "docs".to_string(),
+                    url: "https://example.com".to_string(),
+                },
+            ])
+            .unwrap();
+        renderer.finish().unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        assert!(result.contains(ITALIC_ON) && !result.contains(BOLD_ON));
+        assert!(result.contains(&format!("{}quoted", ITALIC_ON)));
+        assert!(result.contains(&format!("{}hmm{}", DIM_ON, DIM_OFF)));
+        assert!(!result.contains(UNDERLINE_ON));
+    }
+
+    #[test]
+    fn test_outline_mode() {
+        let features = RenderFeatures {
+            outline: true,
+            ..Default::default()
+        };
+        let mut renderer = Renderer::with_features(Vec::new(), 40, features);
+        render_markdown(
+            &mut renderer,
+            &[
+                "# Guide",
+                "Read this first.",
+                "Then this.",
+                "- a list item",
+                "## Build",
+                "```rust",
+                "fn main() {}",
+                "```",
+                "Run the tests.",
+                "> quoted",
+            ],
+        );
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        let plain = streamdown_ansi::utils::visible(&output);
+        assert!(plain.contains("Guide") && plain.contains("Build"));
+        assert!(plain.contains("Read this first."));
+        assert!(plain.contains("▶ rust code (1 line)\n"));
+        assert!(plain.contains("Run the tests."));
+        for skipped in ["Then this.", "a list item", "fn main", "quoted"] {
+            assert!(!plain.contains(skipped), "{skipped}");
+        }
+    }
+
+    #[test]
+    fn test_render_inline_progress_bar() {
+        let mut renderer = Renderer::new(Vec::new(), 40);
+        renderer.register_inline("progress", Arc::new(ProgressHandler));
+        render_markdown(
+            &mut renderer,
+            &["Build [######--] 75% done", "", "Tests progress:0.5"],
+        );
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        let output = streamdown_ansi::utils::visible(&output);
+        assert!(output.contains("Build ███████▌░░ 75% done"));
+        assert!(output.contains("Tests █████░░░░░ 50%"));
+    }
+
+    #[test]
+    fn test_inline_handlers_outside_paragraphs() {
+        let mut renderer = Renderer::new(Vec::new(), 60);
+        renderer.register_inline("progress", Arc::new(ProgressHandler));
+        render_markdown(
+            &mut renderer,
+            &[
+                "- build progress:0.5",
+                "",
+                "> tests progress:1",
+                "",
+                "| step | done |",
+                "| --- | --- |",
+                "| lint | progress:0 |",
+                "",
+            ],
+        );
+        let output = visible(&String::from_utf8(renderer.into_writer()).unwrap());
+        assert!(!output.contains("progress:"), "{output}");
+        for percent in [" 50%", " 100%", " 0%"] {
+            assert!(output.contains(percent), "{percent} in {output}");
+        }
+    }
+
+    #[test]
+    fn test_text_pass_marks_survive_wrapping() {
+        let features = RenderFeatures {
+            max_width: Some(20),
+            ..Default::default()
+        };
+        let mut renderer = Renderer::with_features(Vec::new(), 40, features);
+        let style = TermStyle {
+            attributes: TextAttributes::UNDERLINE,
+            ..Default::default()
+        };
+        let pass = PatternPass::new().term("rate limit", style.clone());
+        renderer.register_text_pass("glossary", Arc::new(pass));
+        render_markdown(
+            &mut renderer,
+            &["We hit the new rate limit today", "", "- **rate limit** x"],
+        );
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        // The term is broken across lines and styled on both
+        assert!(lines[0].contains("new \x1b[4mrate\x1b[24m"), "{:?}", output);
+        assert!(!lines[0].contains("limit"));
+        assert!(lines[1].contains("\x1b[4mlimit\x1b[24m today"));
+        assert!(output.contains("\x1b[1m\x1b[4mrate\x1b[24m"));
+        assert!(output.contains("\x1b[4mlimit\x1b[24m\x1b[22m x"));
+    }
+
+    #[test]
+    fn test_text_passes_in_blockquotes_and_styles() {
+        let mut renderer = Renderer::new(Vec::new(), 60);
+        let style = TermStyle {
+            attributes: TextAttributes::UNDERLINE,
+            ..Default::default()
+        };
+        let pass = PatternPass::new().term("TODO", style);
+        renderer.register_text_pass("todo", Arc::new(pass));
+        render_markdown(
+            &mut renderer,
+            &["> a TODO here", "", "***TODO*** and ~~TODO~~ too"],
+        );
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        assert!(output.contains("a \x1b[4mTODO\x1b[24m here"), "{output:?}");
+        assert!(
+            output.contains(&format!("{ITALIC_ON}\x1b[4mTODO\x1b[24m{ITALIC_OFF}")),
+            "{output:?}"
+        );
+        assert!(
+            output.contains(&format!("{STRIKEOUT_ON}\x1b[4mTODO\x1b[24m{STRIKEOUT_OFF}")),
+            "{output:?}"
+        );
+    }
+
+    #[test]
+    fn test_text_passes_skip_markup() {
+        let mut renderer = Renderer::new(Vec::new(), 60);
+        let style = TermStyle {
+            attributes: TextAttributes::UNDERLINE,
+            ..Default::default()
+        };
+        let pass = PatternPass::new().term("docs", style);
+        renderer.register_text_pass("glossary", Arc::new(pass));
+        render_markdown(
+            &mut renderer,
+            &[
+                "# The docs",
+                "",
+                "- the docs at [site](https://example.com/docs)",
+                "",
+                "| docs |",
+                "|------|",
+                "| docs at [site](https://example.com/docs) |",
+            ],
+        );
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        assert!(output.contains("The \x1b[4mdocs\x1b[24m"), "{output:?}");
+        assert!(output.contains("the \x1b[4mdocs\x1b[24m"), "{output:?}");
+        // URLs are left alone
+        assert!(output.contains("(https://example.com/docs)"), "{output:?}");
+        assert!(!output.contains("/\x1b[4mdocs"), "{output:?}");
+        // Table cells are marked, header and body
+        assert_eq!(
+            output.matches("\x1b[4mdocs\x1b[24m").count(),
+            4,
+            "{output:?}"
+        );
+    }
+
+    #[test]
+    fn test_numbered_links() {
+        let mut renderer = Renderer::with_features(
+            Vec::new(),
+            60,
+            RenderFeatures {
+                numbered_links: true,
+                ..Default::default()
+            },
+        );
+        renderer.set_policy(Policy::untrusted());
+        render_markdown(
+            &mut renderer,
+            &["[a](https://a.example) [b](javascript:x) [again](https://a.example)"],
+        );
+        assert_eq!(renderer.links(), ["https://a.example", "javascript:x"]);
+        let error = renderer.open_link(2).unwrap_err();
+        assert!(matches!(error, RenderError::LinkNotAllowed(url) if url == "javascript:x"));
+        assert!(matches!(
+            renderer.open_link(0).unwrap_err(),
+            RenderError::NoSuchLink(0)
+        ));
+
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        let output = streamdown_ansi::utils::visible(&output);
+        assert!(output.contains("a[1] (https://a.example) b[2]"), "{output}");
+        assert!(output.contains("again[1]"));
+    }
+
+    #[test]
+    fn test_list_item_links_are_numbered() {
+        let mut renderer = Renderer::with_features(
+            Vec::new(),
+            60,
+            RenderFeatures {
+                numbered_links: true,
+                ..Default::default()
+            },
+        );
+        render_markdown(
+            &mut renderer,
+            &[
+                "See [a](https://a.example).",
+                "",
+                "- [b](https://b.example) and [a](https://a.example)",
+            ],
+        );
+        assert_eq!(renderer.links(), ["https://a.example", "https://b.example"]);
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        assert!(
+            output.contains("\x1b]8;;https://b.example\x1b\\"),
+            "{output:?}"
+        );
+        let output = visible(&output);
+        assert!(
+            output.contains("b[2] (https://b.example) and a[1]"),
+            "{output}"
+        );
+    }
+
+    #[test]
+    fn test_render_sparkline_fence() {
+        let mut renderer = Renderer::with_features(Vec::new(), 20, RenderFeatures::plain());
+        render_markdown(&mut renderer, &["```sparkline", "1 2 3", "4 8", "```"]);
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        assert_eq!(output.trim(), "▁▂▃▄█");
+
+        // Text that isn't data stays a code block
+        let mut renderer = Renderer::with_features(Vec::new(), 20, RenderFeatures::plain());
+        render_markdown(&mut renderer, &["```sparkline", "soon", "```"]);
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        assert!(output.contains("soon"));
+    }
+
+    #[test]
+    fn test_color_swatches() {
+        let render = |color_swatches, color| {
+            let features = RenderFeatures {
+                color_swatches,
+                ..Default::default()
+            };
+            let mut renderer = Renderer::with_features(Vec::new(), 40, features);
+            renderer.set_options(RendererOptions { color });
+            render_markdown(&mut renderer, &["use `#ff0000` or `red`", ""]);
+            String::from_utf8(renderer.into_writer()).unwrap()
+        };
+        let output = render(true, ColorChoice::Always);
+        assert_eq!(output.matches(SWATCH).count(), 1);
+        assert!(output.contains("\x1b[38;2;255;0;0m██"));
+        assert!(!render(false, ColorChoice::Always).contains(SWATCH));
+        assert!(!render(true, ColorChoice::Never).contains(SWATCH));
+    }
+
+    #[test]
+    fn test_plain_mode_has_no_escapes() {
+        let mut renderer = Renderer::with_features(Vec::new(), 24, RenderFeatures::plain());
+        renderer.set_capabilities(TerminalCapabilities {
+            notifications: NotificationProtocol::Osc9,
+            ..Default::default()
+        });
+        renderer.set_notifications(Some(NotifyOptions {
+            patterns: vec!["**".to_string()],
+            ..Default::default()
+        }));
+        render_markdown(
+            &mut renderer,
+            &[
+                "# Title",
+                "",
+                "Some **bold** text with a [link](https://example.com) that wraps.",
+                "",
+                "- item",
+                "",
+                "```",
+                "code",
+                "```",
+                "",
+                "---",
+            ],
+        );
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        assert!(!output.contains('\x1b'), "{output:?}");
+        assert!(output.contains("• item"));
+        assert!(output.contains("code"));
+        assert!(output.lines().all(|l| visible_length(l) <= 24), "{output}");
+        assert!(output.contains("\ncode\n"));
+    }
+
+    #[test]
+    fn test_quoted_history_is_muted() {
+        let mut transcript = TranscriptMatcher::new();
+        transcript.push_message("Is the cache warm?");
+        let mut output = Vec::new();
+        let mut renderer = Renderer::new(&mut output, 60);
+        renderer.set_quote_matcher(Some(Arc::new(transcript)));
+        for event in [
+            ParseEvent::BlockquoteStart { depth: 1 },
+            ParseEvent::BlockquoteLine("is the cache warm?".to_string()),
+            ParseEvent::BlockquoteLine("new text".to_string()),
+            ParseEvent::BlockquoteEnd,
+        ] {
+            renderer.render_event(&event).unwrap();
+        }
+
+        let result = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = result.lines().collect();
+        let quoted = RenderStyle::default().quoted_attrs;
+        assert!(lines[0].contains(&format!("{}is the cache", quoted.on())));
+        assert!(!lines[1].contains(DIM_ON));
+    }
+
+    #[test]
+    fn test_untrusted_policy() {
+        let path = std::env::temp_dir().join(format!("sd-policy-{}.png", std::process::id()));
+        std::fs::write(&path, b"\x89PNG\r\n\x1a\n").unwrap();
+
+        let mut output = Vec::new();
+        let mut renderer = Renderer::new(&mut output, 60);
+        renderer.set_capabilities(TerminalCapabilities {
+            graphics: GraphicsProtocol::Kitty,
+            notifications: NotificationProtocol::Osc9,
+            ..Default::default()
+        });
+        renderer.set_features(RenderFeatures {
+            collapse_details: true,
+            ..Default::default()
+        });
+        renderer.set_policy(Policy::untrusted());
+        renderer.set_notifications(Some(NotifyOptions {
+            patterns: vec!["secret".to_string()],
+            ..Default::default()
+        }));
+        let events = [
+            ParseEvent::Link {
+                text: "docs".to_string(),
+                url: "https://example.com".to_string(),
+            },
+            ParseEvent::Image {
+                alt: "logo".to_string(),
+                url: path.display().to_string(),
+            },
+            ParseEvent::DetailsStart {
+                summary: "More".to_string(),
+                open: false,
+            },
+            ParseEvent::Text("secret".to_string()),
+            ParseEvent::DetailsEnd,
+        ];
+        for event in &events {
+            renderer.render_event(event).unwrap();
+        }
+        std::fs::remove_file(&path).unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        assert!(!result.contains("\x1b]"), "{result:?}");
+        assert!(!result.contains("\x1b_G"));
+        assert!(result.contains("[\u{1F5BC} logo]"));
+        assert!(result.contains("secret"));
+    }
+
+    #[test]
+    fn test_render_trace_panels() {
+        let mut output = Vec::new();
+        let mut renderer = Renderer::new(&mut output, 40);
+        let tool = PanelStyle::new("tool call", "yellow");
+
+        renderer
+            .render_role(&PanelStyle::new("User", "cyan"))
+            .unwrap();
+        renderer.open_panel(&tool).unwrap();
+        renderer.open_panel(&tool).unwrap();
+        renderer
+            .render_event(&ParseEvent::Text("nested".to_string()))
+            .unwrap();
+        renderer.render_event(&ParseEvent::Newline).unwrap();
+        renderer.close_panel().unwrap();
+        renderer.close_panel().unwrap();
+        renderer.close_panel().unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        let plain = streamdown_ansi::utils::visible(&result);
+        assert!(plain.contains("▍User"));
+        assert!(plain.contains("╭─ tool call "));
+        assert!(plain.contains("│ │ nested"));
+        assert_eq!(plain.matches('╰').count(), 2);
+        assert!(
+            plain
+                .lines()
+                .all(|l| unicode_width::UnicodeWidthStr::width(l) <= 40)
+        );
+    }
+
+    #[test]
+    fn test_render_code_block_line_numbers() {
+        let mut output = Vec::new();
+        let mut renderer = Renderer::new(&mut output, 20);
+        renderer.set_features(RenderFeatures {
+            line_numbers: true,
+            pretty_broken: true,
+            highlight: false,
+            ..Default::default()
+        });
+        renderer
+            .render_event(&ParseEvent::CodeBlockStart {
+                language: None,
+                indent: 0,
+            })
+            .unwrap();
+        for line in ["let x = 1;", "let long_name = compute(a, b, c);"] {
+            renderer
+                .render_event(&ParseEvent::CodeBlockLine(line.to_string()))
+                .unwrap();
+        }
+        renderer.render_event(&ParseEvent::CodeBlockEnd).unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        let plain = streamdown_ansi::utils::visible(&result);
+        assert!(plain.contains("  1 │ let x = 1;"));
+        assert!(plain.contains("  2 │ let long_name"));
+        // Wrapped continuation rows have a blank gutter
+        assert!(plain.contains("    │ "));
+        assert!(
+            plain
+                .lines()
+                .all(|l| unicode_width::UnicodeWidthStr::width(l) <= 20)
+        );
+    }
+
+    #[test]
+    fn test_render_code_block_line_numbers_truncate() {
+        let mut output = Vec::new();
+        let mut renderer = Renderer::new(&mut output, 20);
+        renderer.set_features(RenderFeatures {
+            line_numbers: true,
+            highlight: false,
+            ..Default::default()
+        });
+        renderer
+            .render_event(&ParseEvent::CodeBlockStart {
+                language: None,
+                indent: 0,
+            })
+            .unwrap();
+        renderer
+            .render_event(&ParseEvent::CodeBlockLine("x".repeat(40)))
+            .unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        let plain = streamdown_ansi::utils::visible(&result);
+        let row = plain.lines().find(|l| l.contains('│')).unwrap();
+        assert!(row.ends_with('…'));
+        assert_eq!(unicode_width::UnicodeWidthStr::width(row), 20);
+    }
+
+    #[test]
+    fn test_control_chars_are_stripped_or_shown() {
+        for (mode, expected) in [
+            (ControlChars::Strip, "ding dong"),
+            (ControlChars::Show, "ding␇ do␈ng"),
+        ] {
+            let mut renderer = Renderer::new(Vec::new(), 40);
+            renderer.set_features(RenderFeatures {
+                control_chars: mode,
+                ..Default::default()
+            });
+            render_markdown(
+                &mut renderer,
+                &["# ding\x07 do\x08ng", "- ding\x07 do\x08ng"],
+            );
+            let output = String::from_utf8(renderer.into_writer()).unwrap();
+            assert!(!output.contains(['\x07', '\x08']), "{output:?}");
+            assert_eq!(visible(&output).matches(expected).count(), 2, "{output:?}");
+        }
+    }
+
+    #[test]
+    fn test_untrusted_source_escapes_are_neutralized() {
+        let injected = "**hi**\x1b]0;pwned\x07 there\x1b[2J\x1b[31m";
+        for policy in [Policy::trusted(), Policy::untrusted()] {
+            let trusted = policy.source_escapes;
+            let mut renderer = Renderer::new(Vec::new(), 40);
+            renderer.set_policy(policy);
+            render_markdown(&mut renderer, &[injected, "```", injected, "```"]);
+            let output = String::from_utf8(renderer.into_writer()).unwrap();
+            assert_eq!(output.contains("]0;pwned"), trusted, "{output:?}");
+            assert_eq!(output.contains("\x1b[2J"), trusted, "{output:?}");
+            assert_eq!(output.contains("\x1b[31m"), trusted, "{output:?}");
+            // The renderer's own styling is still written
+            assert!(output.contains(BOLD_ON));
+            if !trusted {
+                assert!(visible(&output).contains("hi there"));
+            }
+        }
+    }
+
+    #[test]
+    fn test_untrusted_partial_line_is_neutralized() {
+        let mut renderer = Renderer::new(Vec::new(), 40);
+        renderer.set_policy(Policy::untrusted());
+        renderer
+            .draw_partial_line("hi \x1b]0;pwned\x1b\\ there\x1b[2J")
+            .unwrap();
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        assert!(!output.contains("pwned"), "{output:?}");
+        assert!(!output.contains("\x1b[2J"), "{output:?}");
+        assert!(visible(&output).contains("hi  there"), "{output:?}");
+    }
+
+    #[test]
+    fn test_partial_line_controls_are_sanitized() {
+        let mut renderer = Renderer::new(Vec::new(), 40);
+        renderer
+            .draw_partial_line("ding\x07 back\x08 c1\u{9b}2J")
+            .unwrap();
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        assert!(!output.contains(['\x07', '\x08', '\u{9b}']), "{output:?}");
+        assert!(visible(&output).contains("ding back c12J"), "{output:?}");
+    }
+
+    #[test]
+    fn test_partial_line_without_colors() {
+        let mut renderer = Renderer::new(Vec::new(), 40);
+        renderer.set_options(RendererOptions {
+            color: ColorChoice::Never,
+        });
+        renderer.draw_partial_line("run `cargo` and **wo").unwrap();
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        // Only the cursor controls are left, no SGR codes
+        assert_eq!(output, "\r\x1b[2Krun  cargo  and wo");
+    }
+
+    #[test]
+    fn test_partial_line_draws_like_the_final_line() {
+        let mut renderer = Renderer::new(Vec::new(), 60);
+        let style = RenderStyle {
+            link_attrs: TextAttributes {
+                bold: true,
+                ..TextAttributes::PLAIN
+            },
+            ..Default::default()
+        };
+        renderer.set_style(style.clone());
+        renderer
+            .draw_partial_line("see [docs](https://x.org) and <kbd>Ctrl</kbd> **wo")
+            .unwrap();
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        assert!(
+            output.contains(&style.link_attrs.apply("docs")),
+            "{output:?}"
+        );
+        assert!(
+            output.contains(&list::format_key("Ctrl", &style)),
+            "{output:?}"
+        );
+        assert!(output.ends_with(" wo"), "{output:?}");
+    }
+
+    #[test]
+    fn test_long_partial_line_keeps_its_end() {
+        let mut renderer = Renderer::new(Vec::new(), 20);
+        let text = format!("{}日本語 end", "x".repeat(200));
+        renderer.draw_partial_line(&text).unwrap();
+        let output = visible(&String::from_utf8(renderer.into_writer()).unwrap());
+        let row = output.trim_start_matches(['\r', ' ']);
+        assert!(
+            row.starts_with('…') && row.ends_with("日本語 end"),
+            "{row:?}"
+        );
+        assert_eq!(str_width(row), 20);
+    }
+
+    #[test]
+    fn test_fence_language_aliases() {
+        let draw = |language: &str, alias: Option<(&str, &str)>| {
+            let mut renderer = Renderer::new(Vec::new(), 40);
+            if let Some((alias, language)) = alias {
+                renderer.alias_language(alias, language);
+            }
+            let fence = format!("```{language}");
+            render_markdown(&mut renderer, &[&fence, "const x = 1;", "```"]);
+            String::from_utf8(renderer.into_writer()).unwrap()
+        };
+        let canonical = draw("javascript", None);
+        assert!(visible(&canonical).contains("[javascript]"));
+        assert_eq!(draw("js", None), canonical);
+        assert_eq!(draw("JS", None), canonical);
+        assert_eq!(draw("ecma", Some(("ecma", "javascript"))), canonical);
+    }
+
+    #[cfg(feature = "bidi")]
+    #[test]
+    fn test_bidi_reorders_prose() {
+        for (source, bidi) in [
+            ("say שלום there", true),
+            ("- say שלום there", true),
+            ("> say שלום there", true),
+            ("## say שלום there", true),
+            ("say שלום there", false),
+        ] {
+            let mut renderer = Renderer::new(Vec::new(), 40);
+            renderer.set_features(RenderFeatures {
+                bidi,
+                ..Default::default()
+            });
+            render_markdown(&mut renderer, &[source]);
+            let output = visible(&String::from_utf8(renderer.into_writer()).unwrap());
+            let expected = if bidi {
+                "say םולש there"
+            } else {
+                "say שלום there"
+            };
+            assert!(output.contains(expected), "{source}: {output}");
         }
-        Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use streamdown_parser::ListBullet;
+    #[test]
+    fn test_paragraph_align() {
+        let draw = |align: TextAlign| {
+            let mut renderer = Renderer::new(Vec::new(), 20);
+            renderer.set_features(RenderFeatures {
+                paragraph_align: align,
+                ..Default::default()
+            });
+            render_markdown(
+                &mut renderer,
+                &["The quick **brown fox** jumps over the lazy dog again"],
+            );
+            let output = String::from_utf8(renderer.into_writer()).unwrap();
+            visible(&output)
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| line.trim_end().to_string())
+                .collect::<Vec<_>>()
+        };
+        let right = draw(TextAlign::Right);
+        assert!(right.iter().all(|line| str_width(line) == 20), "{right:?}");
+        let center = draw(TextAlign::Center);
+        assert!(
+            center.iter().any(|line| line.starts_with(' ')),
+            "{center:?}"
+        );
+        let justify = draw(TextAlign::Justify);
+        let (last, rest) = justify.split_last().unwrap();
+        assert!(rest.iter().all(|line| str_width(line) == 20), "{justify:?}");
+        assert!(!last.contains("  "), "{justify:?}");
+        assert_eq!(
+            justify
+                .iter()
+                .map(|line| line.split_whitespace().count())
+                .sum::<usize>(),
+            10
+        );
+    }
 
     #[test]
-    fn test_render_heading() {
-        let mut output = Vec::new();
-        let mut renderer = Renderer::new(&mut output, 80);
+    fn test_cjk_paragraph_wraps() {
+        let mut renderer = Renderer::new(Vec::new(), 12);
+        renderer.set_features(RenderFeatures {
+            plain: true,
+            ..Default::default()
+        });
+        render_markdown(&mut renderer, &["日本語の文章には空白がありません。"]);
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        let lines: Vec<_> = output.lines().filter(|line| !line.is_empty()).collect();
+        assert!(lines.len() > 1, "{output}");
+        assert!(lines.iter().all(|line| str_width(line) <= 12), "{output}");
+        assert!(lines.iter().all(|line| !line.starts_with('。')), "{output}");
+    }
 
-        renderer
-            .render_event(&ParseEvent::Heading {
-                level: 1,
-                content: "Title".to_string(),
-            })
-            .unwrap();
+    #[test]
+    fn test_width_oracle_is_per_renderer() {
+        let source = "±±±±±±±± ±±±±±±±± ±±±±±±±±";
+        let render = |oracle: WidthOracle| {
+            let mut renderer = Renderer::new(Vec::new(), 30);
+            renderer.set_features(RenderFeatures {
+                plain: true,
+                ..Default::default()
+            });
+            renderer.set_width_oracle(oracle);
+            render_markdown(&mut renderer, &[source]);
+            String::from_utf8(renderer.into_writer()).unwrap()
+        };
+        let wide =
+            render(WidthOracle::new().with_ambiguous(streamdown_ansi::width::AmbiguousWidth::Wide));
+        let narrow = render(WidthOracle::new());
+        assert_eq!(narrow.trim_end().lines().count(), 1, "{narrow}");
+        assert!(wide.trim_end().lines().count() > 1, "{wide}");
+        // Outside a render the standard oracle measures again
+        assert_eq!(str_width("±"), 1);
+    }
 
-        let result = String::from_utf8(output).unwrap();
-        assert!(result.contains("Title"));
-        assert!(result.contains(BOLD_ON));
+    #[test]
+    fn test_hyphenate_overlong_words() {
+        let word = "pneumonoultramicroscopicsilicovolcanoconiosis";
+        for source in [
+            format!("see {word} here"),
+            format!("- see {word} here"),
+            format!("> see {word} here"),
+        ] {
+            let mut renderer = Renderer::new(Vec::new(), 20);
+            renderer.set_features(RenderFeatures {
+                hyphenate: true,
+                plain: true,
+                ..Default::default()
+            });
+            render_markdown(&mut renderer, &[&source]);
+            let output = String::from_utf8(renderer.into_writer()).unwrap();
+            assert!(
+                output.lines().all(|line| str_width(line.trim_end()) <= 20),
+                "{output}"
+            );
+            assert!(output.contains("pneumonoul"), "{output}");
+            assert!(
+                output.lines().any(|line| line.trim_end().ends_with('-')),
+                "{output}"
+            );
+        }
     }
 
     #[test]
-    fn test_render_h2_colored() {
-        let mut output = Vec::new();
-        let mut renderer = Renderer::new(&mut output, 80);
+    fn test_code_overflow_rows_fill_the_width() {
+        let long = "let 名前 = compute(alpha, beta, gamma); // 完了";
+        for policy in [CodeOverflow::Wrap, CodeOverflow::Truncate] {
+            for line_numbers in [false, true] {
+                for width in 12..=30 {
+                    let mut renderer = Renderer::new(Vec::new(), width);
+                    renderer.set_features(RenderFeatures {
+                        code_overflow: policy,
+                        line_numbers,
+                        ..Default::default()
+                    });
+                    render_markdown(&mut renderer, &["```", "short", long, "```"]);
+                    let output = visible(&String::from_utf8(renderer.into_writer()).unwrap());
+                    for row in output.lines().filter(|row| !row.is_empty()) {
+                        assert_eq!(
+                            unicode_width::UnicodeWidthStr::width(row),
+                            width,
+                            "{policy} at {width}: {row:?}"
+                        );
+                    }
+                    let marked = output.contains(code::CONTINUATION);
+                    assert_eq!(marked, policy == CodeOverflow::Wrap);
+                    assert_eq!(output.contains('…'), policy == CodeOverflow::Truncate);
+                }
+            }
+        }
 
-        renderer
-            .render_event(&ParseEvent::Heading {
-                level: 2,
-                content: "Subtitle".to_string(),
-            })
-            .unwrap();
+        // Overflow leaves the line whole, gutter or not
+        for line_numbers in [false, true] {
+            let mut renderer = Renderer::new(Vec::new(), 20);
+            renderer.set_features(RenderFeatures {
+                code_overflow: CodeOverflow::Overflow,
+                line_numbers,
+                ..Default::default()
+            });
+            render_markdown(&mut renderer, &["```", long, "```"]);
+            let output = visible(&String::from_utf8(renderer.into_writer()).unwrap());
+            assert!(output.contains(long));
+        }
+    }
 
-        let result = String::from_utf8(output).unwrap();
-        assert!(result.contains("Subtitle"));
-        assert!(result.contains("\x1b[38;2;")); // Color code
+    #[test]
+    fn test_render_code_block_ansi_passthrough() {
+        let colored = "\x1b[32mok\x1b[0m done\x1b]0;title\x07";
+        for passthrough in [false, true] {
+            let mut renderer = Renderer::new(Vec::new(), 20);
+            renderer.set_features(RenderFeatures {
+                ansi_passthrough: passthrough,
+                ..Default::default()
+            });
+            render_markdown(&mut renderer, &["```sh", colored, "```"]);
+            let output = String::from_utf8(renderer.into_writer()).unwrap();
+            assert_eq!(output.contains("\x1b[32mok"), passthrough, "{output:?}");
+            assert!(!output.contains("title"));
+            // The frame is still filled to the full width
+            let row = visible(&output)
+                .lines()
+                .find(|l| l.contains("ok done"))
+                .unwrap()
+                .to_string();
+            assert_eq!(unicode_width::UnicodeWidthStr::width(row.as_str()), 20);
+            if passthrough {
+                let bg = bg_color(&RenderStyle::default().code_bg);
+                assert!(output.contains(&format!("\x1b[0m{bg} done")));
+            }
+        }
     }
 
     #[test]
-    fn test_render_code_block() {
+    fn test_render_code_block_copy_link() {
         let mut output = Vec::new();
-        let mut renderer = Renderer::new(&mut output, 80);
-
+        let mut renderer = Renderer::new(&mut output, 40);
+        renderer.set_features(RenderFeatures {
+            copy_link: true,
+            ..Default::default()
+        });
         renderer
             .render_event(&ParseEvent::CodeBlockStart {
-                language: Some("rust".to_string()),
+                language: None,
                 indent: 0,
             })
             .unwrap();
         renderer
-            .render_event(&ParseEvent::CodeBlockLine("let x = 1;".to_string()))
+            .render_event(&ParseEvent::CodeBlockLine("make install".to_string()))
             .unwrap();
         renderer.render_event(&ParseEvent::CodeBlockEnd).unwrap();
 
         let result = String::from_utf8(output).unwrap();
-        // The code block contains the code (may have ANSI formatting)
-        let visible = streamdown_ansi::utils::visible(&result);
-        assert!(visible.contains("let x = 1;") || visible.contains("let"));
+        assert!(result.contains("\x1b]8;;file://"));
+        assert!(result.contains("⧉ copy"));
     }
 
     #[test]
-    fn test_render_code_block_pretty_pad() {
-        let mut output = Vec::new();
-        let mut renderer = Renderer::new(&mut output, 80);
-        renderer.set_pretty_pad(true);
+    fn test_render_diff_block() {
+        let mut renderer = Renderer::new(Vec::new(), 40);
+        render_markdown(
+            &mut renderer,
+            &[
+                "```diff",
+                "--- a/x",
+                "@@ -1 +1 @@",
+                "-old",
+                "--- rule",
+                "+new",
+                "```",
+            ],
+        );
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        assert!(output.contains(&format!("{}--- a/x", BOLD_ON)));
+        assert!(output.contains(&format!("{}--- rule", fg_color("red"))));
+        assert!(output.contains(&format!("{}-old", fg_color("red"))));
+        assert!(output.contains(&format!("{}+new", fg_color("green"))));
+        assert!(output.contains("\x1b[2m@@ -1 +1 @@"));
+    }
 
-        renderer
-            .render_event(&ParseEvent::CodeBlockStart {
-                language: Some("rust".to_string()),
-                indent: 0,
-            })
+    #[test]
+    fn test_copy_mode() {
+        use base64::Engine;
+
+        let mut renderer = Renderer::new(Vec::new(), 40);
+        renderer.set_features(RenderFeatures {
+            line_numbers: true,
+            clipboard: true,
+            left_margin: 2,
+            ..Default::default()
+        });
+        renderer.set_copy_mode(true);
+        render_markdown(&mut renderer, &["text", "```rust", "let a = 1;", "```"]);
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        let rows: Vec<String> = output.lines().map(visible).collect();
+        // Code starts at column 0, without gutter, border or padding
+        assert!(rows.iter().any(|row| row == "let a = 1;"), "{rows:?}");
+        assert!(rows.iter().all(|row| !row.ends_with(' ')));
+        assert!(!output.contains(CODEPAD_TOP));
+        // The clipboard gets the block with its fence
+        let start = output.find("\x1b]52;c;").unwrap() + 7;
+        let end = start + output[start..].find('\x07').unwrap();
+        let copied = base64::engine::general_purpose::STANDARD
+            .decode(&output[start..end])
             .unwrap();
-        renderer.render_event(&ParseEvent::CodeBlockEnd).unwrap();
+        assert_eq!(copied, b"```rust\nlet a = 1;\n```");
+    }
 
-        let result = String::from_utf8(output).unwrap();
-        assert!(result.contains(CODEPAD_TOP));
-        assert!(result.contains(CODEPAD_BOTTOM));
+    #[test]
+    fn test_copy_mode_non_ascii_and_rules() {
+        let mut renderer = Renderer::new(Vec::new(), 40);
+        renderer.set_copy_mode(true);
+        render_markdown(
+            &mut renderer,
+            &[
+                "# Übergrößé",
+                "",
+                "> café",
+                "",
+                "- [x] é",
+                "",
+                "| a |",
+                "|---|",
+                "| ü |",
+                "",
+                "---",
+            ],
+        );
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        let rows: Vec<String> = output.lines().map(visible).collect();
+        assert!(rows.iter().all(|row| !row.ends_with(' ')), "{rows:?}");
+        assert!(rows.iter().any(|row| row.contains("café")));
+        assert!(rows.iter().any(|row| row.contains('─')));
+    }
+
+    fn render_markdown<W: Write>(renderer: &mut Renderer<W>, lines: &[&str]) {
+        let mut parser = streamdown_parser::Parser::new();
+        for line in lines {
+            for event in parser.parse_line(line) {
+                renderer.render_event(&event).unwrap();
+            }
+        }
     }
 
     #[test]
-    fn test_render_list() {
-        let mut output = Vec::new();
-        let mut renderer = Renderer::new(&mut output, 80);
+    fn test_max_width_centers_and_wraps() {
+        let features = RenderFeatures {
+            max_width: Some(20),
+            ..Default::default()
+        };
+        let mut renderer = Renderer::with_features(Vec::new(), 60, features);
+        render_markdown(
+            &mut renderer,
+            &[
+                "some words that are far too long for a twenty column text block",
+                "",
+                "---",
+            ],
+        );
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        let lines: Vec<String> = output
+            .lines()
+            .map(streamdown_ansi::utils::visible)
+            .filter(|l| !l.trim().is_empty())
+            .collect();
+        assert!(lines.len() > 3);
+        for line in &lines {
+            // Centered: 20 columns of margin, then at most 20 of content
+            assert!(line.starts_with(&" ".repeat(20)), "{line:?}");
+            assert!(visible_length(line.trim_end()) <= 40, "{line:?}");
+        }
+    }
 
+    #[test]
+    fn test_wrapped_span_leaves_margin_plain() {
+        let features = RenderFeatures {
+            left_margin: 4,
+            ..Default::default()
+        };
+        let mut renderer = Renderer::with_features(Vec::new(), 40, features);
         renderer
-            .render_event(&ParseEvent::ListItem {
-                indent: 0,
-                bullet: ListBullet::Dash,
-                content: "Item".to_string(),
-            })
+            .open_panel(&PanelStyle::new("tool", "yellow"))
             .unwrap();
+        render_markdown(
+            &mut renderer,
+            &["para with **bold words spanning the wrap here** and more"],
+        );
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        let rows: Vec<&str> = output.lines().skip(1).collect();
+        // The break closes the span, and it reopens after the border
+        assert!(rows[0].ends_with(RESET), "{:?}", rows[0]);
+        let border = format!("{}│{} ", fg_color("yellow"), RESET);
+        assert!(
+            rows[1].starts_with(&format!("    {}{}", border, BOLD_ON)),
+            "{:?}",
+            rows[1]
+        );
+    }
 
-        let result = String::from_utf8(output).unwrap();
-        assert!(result.contains("•")); // Bullet
-        assert!(result.contains("Item"));
+    #[test]
+    fn test_margins_narrow_content() {
+        let features = RenderFeatures {
+            left_margin: 4,
+            right_margin: 6,
+            ..Default::default()
+        };
+        let mut renderer = Renderer::with_features(Vec::new(), 40, features);
+        render_markdown(&mut renderer, &["---"]);
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        let rule = streamdown_ansi::utils::visible(output.lines().next().unwrap());
+        assert_eq!(rule, format!("    {}", "─".repeat(30)));
     }
 
     #[test]
-    fn test_render_ordered_list() {
-        let mut output = Vec::new();
-        let mut renderer = Renderer::new(&mut output, 80);
+    fn test_pattern_notification() {
+        let mut renderer = Renderer::new(Vec::new(), 40);
+        renderer.set_capabilities(TerminalCapabilities {
+            notifications: NotificationProtocol::Osc9,
+            ..Default::default()
+        });
+        renderer.set_notifications(Some(NotifyOptions {
+            on_finish: false,
+            patterns: vec!["ERROR".to_string()],
+            ..Default::default()
+        }));
+        render_markdown(&mut renderer, &["all good", "ERROR: build failed"]);
+        renderer.notify_finished().unwrap();
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        assert_eq!(output.matches("\x1b]9;").count(), 1);
+        assert!(output.contains("\x1b]9;streamdown: ERROR: build failed\x07"));
+    }
+
+    #[test]
+    fn test_breadcrumb_tracks_rendered_headings() {
+        let mut renderer = Renderer::new(Vec::new(), 40);
+        assert!(renderer.breadcrumb().is_empty());
+        render_markdown(&mut renderer, &["# Guide", "## Setup", "text", "### Linux"]);
+        assert_eq!(renderer.breadcrumb().to_string(), "Guide › Setup › Linux");
+        render_markdown(&mut renderer, &["## Usage"]);
+        assert_eq!(renderer.breadcrumb().to_string(), "Guide › Usage");
+    }
 
+    #[test]
+    fn test_pinned_header_follows_headings() {
+        let mut renderer = Renderer::new(Vec::new(), 40);
         renderer
-            .render_event(&ParseEvent::ListItem {
-                indent: 0,
-                bullet: ListBullet::Ordered(1),
-                content: "First".to_string(),
-            })
+            .set_pinned_header(Some(PinnedHeader::new(20)))
             .unwrap();
+        render_markdown(
+            &mut renderer,
+            &["# Guide", "## Setup", "### Detail", "## Usage"],
+        );
+        renderer.set_pinned_header(None).unwrap();
 
-        let result = String::from_utf8(output).unwrap();
-        assert!(result.contains("1.")); // Number
-        assert!(result.contains("First"));
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        assert!(output.starts_with("\x1b[2;20r"));
+        let headers: Vec<String> = output
+            .split("\x1b[1;1H\x1b[2K")
+            .skip(1)
+            .map(|s| streamdown_ansi::utils::visible(&s[..s.find("\x1b8").unwrap()]))
+            .collect();
+        // Redrawn for H1/H2 only; H3 doesn't change the pinned row
+        assert_eq!(headers, ["", "Guide", "Guide › Setup", "Guide › Usage"]);
+        assert!(output.ends_with("\x1b[r\x1b8"));
+        assert_eq!(output.matches("\x1b[r").count(), 1);
     }
 
     #[test]
-    fn test_render_table() {
-        let mut output = Vec::new();
-        let mut renderer = Renderer::new(&mut output, 80);
-
-        renderer
-            .render_event(&ParseEvent::TableHeader(vec![
-                "A".to_string(),
-                "B".to_string(),
-            ]))
-            .unwrap();
-        renderer.render_event(&ParseEvent::TableSeparator).unwrap();
+    fn test_pinned_header_released_on_finish() {
+        let mut buf = Vec::new();
+        let mut renderer = Renderer::new(&mut buf, 40);
         renderer
-            .render_event(&ParseEvent::TableRow(vec![
-                "1".to_string(),
-                "2".to_string(),
-            ]))
+            .set_pinned_header(Some(PinnedHeader::new(20)))
             .unwrap();
-        renderer.render_event(&ParseEvent::TableEnd).unwrap();
+        render_markdown(&mut renderer, &["# Guide"]);
+        renderer.finish().unwrap();
+        renderer.set_pinned_header(None).unwrap();
+        let output = String::from_utf8(buf).unwrap();
+        assert!(output.ends_with("\x1b7\x1b[r\x1b8"), "{output:?}");
+    }
 
-        let result = String::from_utf8(output).unwrap();
-        assert!(result.contains("A"));
-        assert!(result.contains("1"));
+    #[test]
+    fn test_collapse_think_and_short_code() {
+        let features = RenderFeatures {
+            collapse: CollapseOptions {
+                think: true,
+                code_over: Some(5),
+            },
+            ..Default::default()
+        };
+        let mut renderer = Renderer::with_features(Vec::new(), 40, features);
+        render_markdown(
+            &mut renderer,
+            &[
+                "<think>",
+                "pondering",
+                "more",
+                "</think>",
+                "```",
+                "short()",
+                "```",
+            ],
+        );
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        let plain = streamdown_ansi::utils::visible(&output);
+        assert!(plain.contains("▶ thinking (2 lines) [+]"));
+        assert!(!plain.contains("pondering"));
+        // Short enough to draw in full
+        assert!(plain.contains("short()"));
+        assert!(!plain.contains("code ("));
     }
 
     #[test]
-    fn test_render_blockquote() {
-        let mut output = Vec::new();
-        let mut renderer = Renderer::new(&mut output, 80);
+    fn test_collapse_streams_code_up_to_threshold() {
+        let features = RenderFeatures {
+            collapse: CollapseOptions {
+                think: false,
+                code_over: Some(1),
+            },
+            ..Default::default()
+        };
+        let mut renderer = Renderer::with_features(Vec::new(), 40, features);
+        let mut parser = streamdown_parser::Parser::new();
+        for (i, line) in ["```rust", "first()", "second()", "third()", "```"]
+            .iter()
+            .enumerate()
+        {
+            for event in parser.parse_line(line) {
+                renderer.render_event(&event).unwrap();
+            }
+            if i == 1 {
+                let so_far = String::from_utf8(renderer.writer_mut().clone()).unwrap();
+                assert!(so_far.contains("first"));
+            }
+        }
+        assert_eq!(renderer.collapsed_blocks(), 1);
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        let plain = streamdown_ansi::utils::visible(&output);
+        assert!(plain.contains("▶ rust code (2 more lines) [+]"));
+        assert!(!plain.contains("second"));
+    }
 
-        renderer
-            .render_event(&ParseEvent::BlockquoteStart { depth: 1 })
-            .unwrap();
-        renderer
-            .render_event(&ParseEvent::BlockquoteLine("Quote text".to_string()))
-            .unwrap();
-        renderer.render_event(&ParseEvent::BlockquoteEnd).unwrap();
+    #[test]
+    fn test_expand_collapsed_block() {
+        let features = RenderFeatures {
+            collapse: CollapseOptions {
+                think: true,
+                code_over: None,
+            },
+            ..Default::default()
+        };
+        let mut renderer = Renderer::with_features(Vec::new(), 40, features);
+        render_markdown(&mut renderer, &["<think>", "pondering", "</think>"]);
+        assert_eq!(renderer.collapsed_blocks(), 1);
+        assert!(!renderer.expand(1).unwrap());
+        assert!(renderer.expand(0).unwrap());
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        let plain = streamdown_ansi::utils::visible(&output);
+        assert!(plain.contains("▶ thinking (1 line) [+]"));
+        assert!(plain.contains("pondering"));
+    }
 
-        let result = String::from_utf8(output).unwrap();
-        assert!(result.contains("Quote text"));
+    #[test]
+    fn test_link_endnotes() {
+        let features = RenderFeatures {
+            link_style: LinkStyle::Endnotes,
+            ..Default::default()
+        };
+        let mut renderer = Renderer::with_features(Vec::new(), 60, features);
+        render_markdown(
+            &mut renderer,
+            &[
+                "See [docs](https://a.example) and [more](https://b.example).",
+                "Again [docs](https://a.example).",
+            ],
+        );
+        renderer.render_link_endnotes().unwrap();
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        let plain = streamdown_ansi::utils::visible(&output);
+        assert!(plain.contains("docs¹"));
+        assert!(plain.contains("more²"));
+        assert!(plain.contains("Again docs¹"));
+        assert!(!plain.contains("(https://"));
+        assert!(plain.contains("1. https://a.example"));
+        assert!(plain.contains("2. https://b.example"));
+        assert!(!plain.contains("3. "));
     }
 
     #[test]
-    fn test_render_think_block() {
-        let mut output = Vec::new();
-        let mut renderer = Renderer::new(&mut output, 80);
+    fn test_quota_blocks_and_resume() {
+        let mut renderer = Renderer::new(Vec::new(), 40);
+        renderer.set_quota(Some(RenderQuota::blocks(2)));
+        render_markdown(
+            &mut renderer,
+            &[
+                "# One",
+                "first para",
+                "",
+                "# Two",
+                "",
+                "# Three",
+                "last para",
+            ],
+        );
 
-        renderer.render_event(&ParseEvent::ThinkBlockStart).unwrap();
+        assert!(renderer.is_truncated());
+        assert_eq!(renderer.remaining_blocks(), 3);
+        renderer.render_truncation_trailer().unwrap();
+        let preview = String::from_utf8(renderer.writer_mut().clone()).unwrap();
+        assert!(preview.contains("One"));
+        assert!(preview.contains("first para"));
+        assert!(!preview.contains("Two"));
+        assert!(preview.contains("truncated, 3 blocks remaining"));
+
+        renderer.resume(None).unwrap();
+        assert!(!renderer.is_truncated());
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        assert!(output.contains("Three"));
+        assert!(output.contains("last para"));
+    }
+
+    #[test]
+    fn test_quota_lines() {
+        let mut renderer = Renderer::new(Vec::new(), 40);
+        renderer.set_quota(Some(RenderQuota::lines(3)));
+        render_markdown(
+            &mut renderer,
+            &["```", "1", "2", "3", "4", "5", "```", "after"],
+        );
+
+        assert!(renderer.is_truncated());
+        // The rest of the code block plus the paragraph
+        assert_eq!(renderer.remaining_blocks(), 2);
+        let lines = renderer.lines_written;
+        assert!((3..=5).contains(&lines));
+        // The cut-off code block is closed, and reopened on resuming
+        let preview = String::from_utf8(renderer.writer_mut().clone()).unwrap();
+        let rows = |text: &str, glyph| text.lines().filter(|l| l.contains(glyph)).count();
+        assert_eq!(rows(&preview, '▀'), 1);
+        renderer.resume(None).unwrap();
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        assert_eq!(rows(&output, '▄'), 2);
+        assert_eq!(rows(&output, '▀'), 2);
+        assert!(output.contains("after"));
+    }
+
+    #[test]
+    fn test_line_metadata_navigation() {
+        let mut renderer = Renderer::new(Vec::new(), 40);
+        renderer.set_line_metadata(true);
+        let mut parser = streamdown_parser::Parser::new();
+        for line in [
+            "# Title", "intro", "", "```", "a", "```", "middle", "", "```", "b", "```",
+        ] {
+            for event in parser.parse_line(line) {
+                renderer.render_event(&event).unwrap();
+            }
+        }
+
+        let lines = renderer.line_metadata().to_vec();
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        assert_eq!(lines.len(), output.matches('\n').count());
+        assert_eq!(lines[0].kind, BlockKind::Heading);
+
+        let first = next_block(&lines, 0, Some(BlockKind::Code)).unwrap();
+        let second = next_block(&lines, first, Some(BlockKind::Code)).unwrap();
+        assert_ne!(lines[first].block, lines[second].block);
+        assert_eq!(
+            previous_block(&lines, second, Some(BlockKind::Code)),
+            Some(first)
+        );
+        assert_eq!(next_block(&lines, second, Some(BlockKind::Code)), None);
+        assert!(next_block(&lines, 0, Some(BlockKind::Paragraph)).unwrap() < first);
+    }
+
+    #[test]
+    fn test_block_ids_are_stable() {
+        let render = |lines: &[&str]| {
+            let mut renderer = Renderer::new(Vec::new(), 40);
+            renderer.set_line_metadata(true);
+            render_markdown(&mut renderer, lines);
+            let ids: Vec<_> = renderer
+                .line_metadata()
+                .iter()
+                .map(|meta| renderer.block_id(meta.block).unwrap())
+                .collect();
+            (renderer.block_ids().to_vec(), ids)
+        };
+        let doc = ["# Title", "intro", "", "```", "a", "```", "end"];
+        let (blocks, lines) = render(&doc);
+        assert_eq!(render(&doc), (blocks.clone(), lines));
+        assert_eq!(blocks.len(), 4);
+        let unique: std::collections::HashSet<_> = blocks.iter().collect();
+        assert_eq!(unique.len(), blocks.len());
+
+        // Editing the code block changes only its ID
+        let (edited, _) = render(&["# Title", "intro", "", "```", "b", "```", "end"]);
+        let changed: Vec<_> = (0..4).filter(|&i| edited[i] != blocks[i]).collect();
+        assert_eq!(changed, [2]);
+    }
+
+    #[test]
+    fn test_current_block_id() {
+        let mut renderer = Renderer::new(Vec::new(), 40);
+        assert_eq!(renderer.current_block_id(), None);
+        render_markdown(&mut renderer, &["# Title", "intro"]);
+        assert_eq!(renderer.current_block_id(), renderer.block_id(1));
+    }
+
+    #[test]
+    fn test_line_metadata_off_by_default() {
+        let mut renderer = Renderer::new(Vec::new(), 40);
         renderer
-            .render_event(&ParseEvent::ThinkBlockLine("Thinking...".to_string()))
+            .render_event(&ParseEvent::Text("x".to_string()))
             .unwrap();
-        renderer.render_event(&ParseEvent::ThinkBlockEnd).unwrap();
-
-        let result = String::from_utf8(output).unwrap();
-        assert!(result.contains("thinking"));
-        assert!(result.contains("Thinking..."));
+        renderer.render_event(&ParseEvent::Newline).unwrap();
+        assert!(renderer.line_metadata().is_empty());
     }
 
     #[test]
-    fn test_render_horizontal_rule() {
+    fn test_modulation_stays_inside_wrapped_rows() {
+        let mut renderer = Renderer::new(Vec::new(), 20);
+        renderer.set_features(RenderFeatures {
+            left_margin: 2,
+            ..Default::default()
+        });
+        renderer.set_meta_styler(Some(Arc::new(ConfidenceStyler::default())));
+        renderer.set_span_meta(Some(SpanMeta::new().with("confidence", 0.1)));
+        render_markdown(&mut renderer, &["one two three four five six seven"]);
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        let rows: Vec<&str> = output.lines().filter(|row| !row.is_empty()).collect();
+        assert!(rows.len() > 1, "{output:?}");
+        for row in rows {
+            // Each row starts with its unstyled margin and closes the dimming
+            assert!(row.starts_with("  \x1b[2m"), "{row:?}");
+            assert!(row.trim_end().ends_with(DIM_OFF), "{row:?}");
+        }
+    }
+
+    fn render_details(collapse: bool) -> String {
         let mut output = Vec::new();
         let mut renderer = Renderer::new(&mut output, 40);
+        renderer.set_features(RenderFeatures {
+            collapse_details: collapse,
+            ..Default::default()
+        });
 
-        renderer.render_event(&ParseEvent::HorizontalRule).unwrap();
+        for event in [
+            ParseEvent::DetailsStart {
+                summary: "Stack trace".to_string(),
+                open: false,
+            },
+            ParseEvent::Text("hidden body".to_string()),
+            ParseEvent::Newline,
+            ParseEvent::DetailsEnd,
+            ParseEvent::Spoiler("secret".to_string()),
+            ParseEvent::Newline,
+        ] {
+            renderer.render_event(&event).unwrap();
+        }
+        String::from_utf8(output).unwrap()
+    }
 
-        let result = String::from_utf8(output).unwrap();
-        assert!(result.contains("─"));
+    #[test]
+    fn test_render_details_expanded() {
+        let result = render_details(false);
+        let plain = streamdown_ansi::utils::visible(&result);
+        assert!(plain.contains("▼ Stack trace"));
+        assert!(plain.contains("hidden body"));
+        assert!(plain.contains("‖secret‖"));
     }
 
     #[test]
-    fn test_render_link() {
-        let mut output = Vec::new();
-        let mut renderer = Renderer::new(&mut output, 80);
+    fn test_render_details_collapsed() {
+        let result = render_details(true);
+        let plain = streamdown_ansi::utils::visible(&result);
+        assert!(plain.contains("▶ Stack trace [+]"));
+        assert!(!plain.contains("hidden body"));
+        // Concealed, not removed
+        assert!(plain.contains("secret"));
+        assert!(!plain.contains('‖'));
+    }
 
+    #[test]
+    fn test_render_ascii_borders() {
+        let mut output = Vec::new();
+        let mut renderer = Renderer::new(&mut output, 40);
+        renderer.set_features(RenderFeatures {
+            border_style: BorderStyle::Ascii,
+            ..Default::default()
+        });
+        let events = [
+            ParseEvent::CodeBlockStart {
+                language: None,
+                indent: 0,
+            },
+            ParseEvent::CodeBlockLine("x".to_string()),
+            ParseEvent::CodeBlockEnd,
+            ParseEvent::TableHeader(vec!["A".to_string(), "B".to_string()]),
+            ParseEvent::TableSeparator,
+            ParseEvent::TableRow(vec!["1".to_string(), "2".to_string()]),
+            ParseEvent::TableEnd,
+            ParseEvent::BlockquoteStart { depth: 1 },
+            ParseEvent::BlockquoteLine("quoted".to_string()),
+            ParseEvent::BlockquoteEnd,
+            ParseEvent::ThinkBlockStart,
+            ParseEvent::ThinkBlockLine("hmm".to_string()),
+            ParseEvent::ThinkBlockEnd,
+            ParseEvent::HorizontalRule,
+        ];
+        for event in &events {
+            renderer.render_event(event).unwrap();
+        }
         renderer
-            .render_event(&ParseEvent::Link {
-                text: "Click here".to_string(),
-                url: "https://example.com".to_string(),
-            })
+            .open_panel(&PanelStyle::new("tool", "yellow"))
             .unwrap();
+        renderer.close_panel().unwrap();
 
         let result = String::from_utf8(output).unwrap();
-        assert!(result.contains("Click here"));
-        assert!(result.contains("example.com"));
+        let plain = streamdown_ansi::utils::visible(&result);
+        assert!(plain.is_ascii(), "{plain}");
+        assert!(plain.contains("| quoted"));
+        assert!(plain.contains("+- thinking -"));
+        assert!(plain.contains("+- tool "));
     }
 
     #[test]
@@ -851,6 +5778,54 @@ mod tests {
         assert!(!style.code_bg.is_empty());
     }
 
+    #[test]
+    fn test_render_style_from_computed() {
+        let computed = streamdown_config::Config::default().computed_style();
+        let style = RenderStyle::from_computed(&computed);
+        assert!(style.code_bg.starts_with('#'));
+        assert!(streamdown_ansi::color::hex2rgb(&style.h1).is_some());
+
+        let fallback = RenderStyle::from_computed(&ComputedStyle::default());
+        assert_eq!(fallback.h2, RenderStyle::default().h2);
+    }
+
+    #[test]
+    fn test_blockquote_wrap_cached() {
+        let mut output = Vec::new();
+        let mut renderer = Renderer::new(&mut output, 40);
+
+        renderer
+            .render_event(&ParseEvent::BlockquoteStart { depth: 1 })
+            .unwrap();
+        for _ in 0..2 {
+            renderer
+                .render_event(&ParseEvent::BlockquoteLine("Quote text".to_string()))
+                .unwrap();
+        }
+        assert_eq!(renderer.wrap_cache().hits(), 1);
+
+        renderer.clear_wrap_cache();
+        assert!(renderer.wrap_cache().is_empty());
+    }
+
+    #[test]
+    fn test_paragraph_and_list_wrap_cached() {
+        let features = RenderFeatures {
+            max_width: Some(30),
+            ..Default::default()
+        };
+        let mut renderer = Renderer::with_features(Vec::new(), 40, features);
+        let lines = ["- an item", "", "a paragraph"];
+        render_markdown(&mut renderer, &lines);
+        let misses = renderer.wrap_cache().misses();
+        assert!(!renderer.wrap_cache().is_empty());
+
+        renderer.reset();
+        render_markdown(&mut renderer, &lines);
+        assert_eq!(renderer.wrap_cache().misses(), misses);
+        assert!(renderer.wrap_cache().hits() >= 2);
+    }
+
     #[test]
     fn test_render_with_custom_style() {
         let style = RenderStyle {
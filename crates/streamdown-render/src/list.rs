@@ -3,17 +3,20 @@
 //! Renders markdown lists with:
 //! - Bullet points (•, ◦, ▪)
 //! - Ordered numbers with configurable style
+//! - Task list checkboxes (`[ ]`/`[x]`)
 //! - Nested indentation
 //! - Proper text wrapping for long items
 //! - Inline formatting (bold, italic, strikethrough, etc.)
 
 use crate::RenderStyle;
-use crate::text::text_wrap;
+use crate::cache::WrapCache;
+use crate::text::{Hyphenator, hyphenate_long_words};
 use crate::{bg_color, fg_color};
 use streamdown_ansi::codes::{
-    BOLD_OFF, BOLD_ON, DIM_ON, ITALIC_OFF, ITALIC_ON, RESET, STRIKEOUT_OFF, STRIKEOUT_ON,
-    UNDERLINE_OFF, UNDERLINE_ON,
+    BOLD_OFF, BOLD_ON, DIM_ON, ITALIC_OFF, ITALIC_ON, RESET, REVERSE_OFF, REVERSE_ON,
+    STRIKEOUT_OFF, STRIKEOUT_ON, UNDERLINE_OFF, UNDERLINE_ON,
 };
+use streamdown_ansi::width::str_width;
 use streamdown_parser::{InlineElement, InlineParser, ListBullet, decode_html_entities};
 
 /// Bullet characters for different nesting levels.
@@ -24,6 +27,15 @@ pub const BULLETS: [&str; 4] = [
     "‣", // Level 3: Triangular bullet
 ];
 
+/// ASCII-only bullet characters, for terminals without Unicode glyphs.
+pub const ASCII_BULLETS: [&str; 3] = ["*", "-", "+"];
+
+/// Task list boxes: checked, unchecked.
+pub const TASK_GLYPHS: [&str; 2] = ["☑", "☐"];
+
+/// ASCII-only task list boxes: checked, unchecked.
+pub const ASCII_TASK_GLYPHS: [&str; 2] = ["[x]", "[ ]"];
+
 /// List rendering state.
 #[derive(Debug, Clone, Default)]
 pub struct ListState {
@@ -96,11 +108,29 @@ impl ListState {
     }
 }
 
+/// Drawing of list item elements that depends on renderer state, such as
+/// text passes and link numbers. `()` draws them from the style alone.
+pub trait InlineDraw {
+    /// Draw decoded text: by default as it is.
+    fn text(&mut self, text: &str) -> String {
+        text.to_string()
+    }
+
+    /// Draw a link to `url` showing `text`: by default the text, then the
+    /// URL in parentheses.
+    fn link(&mut self, text: &str, url: &str, style: &RenderStyle) -> String {
+        let fg = fg_color(&style.link_url);
+        format!("{} {}({}){}", style.link_attrs.apply(text), fg, url, RESET)
+    }
+}
+
+impl InlineDraw for () {}
+
 /// Render inline elements to a string with ANSI codes.
 ///
 /// Parses markdown inline formatting (bold, italic, strikethrough, etc.)
 /// and renders them with appropriate ANSI escape codes.
-fn render_inline_content(content: &str, style: &RenderStyle) -> String {
+fn render_inline_content(content: &str, style: &RenderStyle, draw: &mut dyn InlineDraw) -> String {
     let mut parser = InlineParser::new();
     let elements = parser.parse(content);
 
@@ -109,33 +139,33 @@ fn render_inline_content(content: &str, style: &RenderStyle) -> String {
     for element in elements {
         match element {
             InlineElement::Text(text) => {
-                result.push_str(&decode_html_entities(&text));
+                result.push_str(&draw.text(&decode_html_entities(&text)));
             }
             InlineElement::Bold(text) => {
                 result.push_str(BOLD_ON);
-                result.push_str(&decode_html_entities(&text));
+                result.push_str(&draw.text(&decode_html_entities(&text)));
                 result.push_str(BOLD_OFF);
             }
             InlineElement::Italic(text) => {
                 result.push_str(ITALIC_ON);
-                result.push_str(&decode_html_entities(&text));
+                result.push_str(&draw.text(&decode_html_entities(&text)));
                 result.push_str(ITALIC_OFF);
             }
             InlineElement::BoldItalic(text) => {
                 result.push_str(BOLD_ON);
                 result.push_str(ITALIC_ON);
-                result.push_str(&decode_html_entities(&text));
+                result.push_str(&draw.text(&decode_html_entities(&text)));
                 result.push_str(ITALIC_OFF);
                 result.push_str(BOLD_OFF);
             }
             InlineElement::Strikeout(text) => {
                 result.push_str(STRIKEOUT_ON);
-                result.push_str(&decode_html_entities(&text));
+                result.push_str(&draw.text(&decode_html_entities(&text)));
                 result.push_str(STRIKEOUT_OFF);
             }
             InlineElement::Underline(text) => {
                 result.push_str(UNDERLINE_ON);
-                result.push_str(&decode_html_entities(&text));
+                result.push_str(&draw.text(&decode_html_entities(&text)));
                 result.push_str(UNDERLINE_OFF);
             }
             InlineElement::Code(text) => {
@@ -149,12 +179,7 @@ fn render_inline_content(content: &str, style: &RenderStyle) -> String {
                 result.push_str(RESET);
             }
             InlineElement::Link { text, url } => {
-                // Underlined text with URL in parens
-                let fg = fg_color(&style.link_url);
-                result.push_str(UNDERLINE_ON);
-                result.push_str(&decode_html_entities(&text));
-                result.push_str(UNDERLINE_OFF);
-                result.push_str(&format!(" {}({}){}", fg, url, RESET));
+                result.push_str(&draw.link(&decode_html_entities(&text), &url, style));
             }
             InlineElement::Image { alt, .. } => {
                 let fg = fg_color(&style.image_marker);
@@ -164,12 +189,48 @@ fn render_inline_content(content: &str, style: &RenderStyle) -> String {
                 let fg = fg_color(&style.footnote);
                 result.push_str(&format!("{}{}{}", fg, text, RESET));
             }
+            InlineElement::Spoiler(text) => {
+                result.push_str(&format_spoiler(&text, style));
+            }
+            InlineElement::Kbd(text) => {
+                result.push_str(&format_key(&text, style));
+            }
         }
     }
 
     result
 }
 
+/// Format expanded spoiler text between `‖` markers.
+pub(crate) fn format_spoiler(text: &str, style: &RenderStyle) -> String {
+    let fg = fg_color(&style.spoiler);
+    format!("{}‖{}{}{}‖{}", fg, RESET, text, fg, RESET)
+}
+
+/// Format a keyboard key as a cap: reverse video between half blocks in
+/// the key color, so it still reads as a cap without colors.
+pub(crate) fn format_key(key: &str, style: &RenderStyle) -> String {
+    let fg = fg_color(&style.kbd);
+    format!("{}▐{}{}{}▌{}", fg, REVERSE_ON, key, REVERSE_OFF, RESET)
+}
+
+/// Split a task list marker (`[ ] `, `[x] `) off item content.
+///
+/// Returns whether the task is checked and the rest of the content.
+fn split_task(content: &str) -> Option<(bool, &str)> {
+    let checked = match content.get(..3)? {
+        "[ ]" => false,
+        "[x]" | "[X]" => true,
+        _ => return None,
+    };
+    let rest = &content[3..];
+    if rest.is_empty() {
+        Some((checked, rest))
+    } else {
+        rest.strip_prefix(' ').map(|rest| (checked, rest))
+    }
+}
+
 /// Render a list item.
 ///
 /// # Arguments
@@ -180,9 +241,14 @@ fn render_inline_content(content: &str, style: &RenderStyle) -> String {
 /// * `left_margin` - Left margin string
 /// * `style` - Render style
 /// * `list_state` - List state for tracking numbers
+/// * `wrap_cache` - Cache the wrapped content is looked up in
+/// * `hyphenator` - Hyphenate words wider than the content with this
+///   (None = leave them whole)
+/// * `draw` - Draws links and other renderer-dependent elements
 ///
 /// # Returns
 /// Vector of rendered lines (may be multiple if content wraps)
+#[allow(clippy::too_many_arguments)]
 pub fn render_list_item(
     indent: usize,
     bullet: &ListBullet,
@@ -191,6 +257,9 @@ pub fn render_list_item(
     left_margin: &str,
     style: &RenderStyle,
     list_state: &mut ListState,
+    wrap_cache: &mut WrapCache,
+    hyphenator: Option<&dyn Hyphenator>,
+    draw: &mut dyn InlineDraw,
 ) -> Vec<String> {
     // Adjust list state for current indent
     let ordered = matches!(bullet, ListBullet::Ordered(_));
@@ -207,25 +276,42 @@ pub fn render_list_item(
         ListBullet::PlusExpand => "⊞".to_string(), // Squared plus
         _ => {
             // Cycle through bullet styles based on level
-            BULLETS[level % BULLETS.len()].to_string()
+            style.bullet_glyph(level).to_string()
+        }
+    };
+
+    // Color the marker, followed by the task box if the item is a task
+    let marker_fg = fg_color(&style.bullet);
+    let mut colored_marker = format!("{}{}{}", marker_fg, marker, RESET);
+    let mut marker_width = str_width(&marker);
+    let content = match split_task(content) {
+        Some((checked, rest)) => {
+            let (glyph, color, dim) = if checked {
+                (&style.task_checked_glyph, &style.task_checked, "")
+            } else {
+                (&style.task_unchecked_glyph, &style.task_unchecked, DIM_ON)
+            };
+            colored_marker.push_str(&format!(" {}{}{}{}", fg_color(color), dim, glyph, RESET));
+            marker_width += 1 + str_width(glyph);
+            rest
         }
+        None => content,
     };
 
     // Calculate indentation
     let indent_spaces = indent * 2;
-    let marker_width = unicode_width::UnicodeWidthStr::width(marker.as_str());
     let content_indent = indent_spaces + marker_width + 1; // +1 for space after marker
 
-    // Color the marker
-    let marker_fg = fg_color(&style.bullet);
-    let colored_marker = format!("{}{}{}", marker_fg, marker, RESET);
-
     // Parse and render inline content with formatting (bold, italic, strikethrough, etc.)
-    let rendered_content = render_inline_content(content, style);
+    let rendered_content = render_inline_content(content, style, draw);
 
     // Calculate content width (use visible length for margin with ANSI codes)
     let margin_width = streamdown_ansi::utils::visible_length(left_margin);
     let content_width = width.saturating_sub(margin_width + content_indent);
+    let rendered_content = match hyphenator {
+        Some(hyphenator) => hyphenate_long_words(&rendered_content, content_width, hyphenator),
+        None => rendered_content,
+    };
 
     // Wrap the content
     let first_prefix = format!(
@@ -237,7 +323,7 @@ pub fn render_list_item(
     let next_prefix = format!("{}{}", left_margin, " ".repeat(content_indent));
 
     // Note: text_wrap handles ANSI codes properly via strip_ansi option
-    let wrapped = text_wrap(
+    let wrapped = wrap_cache.wrap(
         &rendered_content,
         content_width,
         0,
@@ -307,6 +393,9 @@ mod tests {
             "",
             &default_style(),
             &mut state,
+            &mut WrapCache::new(),
+            None,
+            &mut (),
         );
 
         assert!(!lines.is_empty());
@@ -325,6 +414,9 @@ mod tests {
             "",
             &default_style(),
             &mut state,
+            &mut WrapCache::new(),
+            None,
+            &mut (),
         );
 
         assert!(!lines.is_empty());
@@ -345,6 +437,9 @@ mod tests {
             "",
             &default_style(),
             &mut state,
+            &mut WrapCache::new(),
+            None,
+            &mut (),
         );
         assert!(lines1[0].contains("•"));
 
@@ -357,6 +452,9 @@ mod tests {
             "",
             &default_style(),
             &mut state,
+            &mut WrapCache::new(),
+            None,
+            &mut (),
         );
         // Second level should use different bullet or more indent
         assert!(lines2[0].contains("Level 2"));
@@ -384,6 +482,9 @@ mod tests {
             "",
             &default_style(),
             &mut state,
+            &mut WrapCache::new(),
+            None,
+            &mut (),
         );
 
         // Should wrap to multiple lines
@@ -398,6 +499,72 @@ mod tests {
         assert_eq!(BULLETS[2], "▪");
     }
 
+    #[test]
+    fn test_split_task() {
+        assert_eq!(split_task("[ ] todo"), Some((false, "todo")));
+        assert_eq!(split_task("[X] done"), Some((true, "done")));
+        assert_eq!(split_task("[x]"), Some((true, "")));
+        assert_eq!(split_task("[x]done"), None);
+        assert_eq!(split_task("[link](url)"), None);
+        assert_eq!(split_task("é"), None);
+    }
+
+    #[test]
+    fn test_task_item_glyphs() {
+        let style = default_style().ascii_glyphs();
+        let mut state = ListState::new();
+        let lines = render_list_item(
+            0,
+            &ListBullet::Dash,
+            "[x] ship it and then write the release notes",
+            24,
+            "",
+            &style,
+            &mut state,
+            &mut WrapCache::new(),
+            None,
+            &mut (),
+        );
+        let plain: Vec<String> = lines
+            .iter()
+            .map(|l| streamdown_ansi::utils::visible(l))
+            .collect();
+        assert!(plain[0].starts_with("* [x] ship"), "{plain:?}");
+        // Continuation lines align with the text after the box
+        assert!(plain[1].starts_with("      "), "{plain:?}");
+        assert!(lines[0].contains(&fg_color(&style.task_checked)));
+    }
+
+    #[test]
+    fn test_configured_bullets_by_depth() {
+        let mut style = default_style();
+        style.bullets = vec!["-".to_string(), "+".to_string()];
+        let mut state = ListState::new();
+        let markers: Vec<String> = [0, 2, 4]
+            .iter()
+            .map(|&indent| {
+                let line = render_list_item(
+                    indent,
+                    &ListBullet::Dash,
+                    "item",
+                    80,
+                    "",
+                    &style,
+                    &mut state,
+                    &mut WrapCache::new(),
+                    None,
+                    &mut (),
+                );
+                streamdown_ansi::utils::visible(&line[0]).trim().to_string()
+            })
+            .collect();
+        assert_eq!(markers, ["- item", "+ item", "- item"]);
+
+        style.bullets.clear();
+        assert_eq!(style.bullet_glyph(1), BULLETS[1]);
+        assert_eq!(RenderStyle::default().ascii_glyphs().bullet_glyph(0), "*");
+    }
+
     #[test]
     fn test_list_item_with_ansi_margin() {
         // BUG: left_margin.len() uses byte length instead of visible length.
@@ -433,6 +600,9 @@ mod tests {
             margin,
             &default_style(),
             &mut state,
+            &mut WrapCache::new(),
+            None,
+            &mut (),
         );
 
         // With correct calculation, 30 chars should fit in 34 char width (1 line)
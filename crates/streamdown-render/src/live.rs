@@ -0,0 +1,372 @@
+//! Live styling of the line being received.
+//!
+//! With [`RenderFeatures::partial_lines`](crate::RenderFeatures::partial_lines)
+//! the line still streaming in is painted before its newline arrives. Spans
+//! that are already closed (`**bold**`, `` `code` ``) are drawn styled,
+//! while emphasis and code markers still waiting for their partner are
+//! hidden and their text shown plain, so neither raw asterisks nor held-back
+//! words appear. A [`LinePainter`] repaints the row from the first cell that
+//! changed, so when a closing marker arrives only the affected span is
+//! re-styled in place.
+//!
+//! A [`PartialLine`] feeds the line to an [`InlineParser`] as words
+//! complete, so the part of a long line with no span left open is parsed and
+//! drawn once rather than on every update.
+//!
+//! Closed spans are drawn by the caller, so the renderer draws them exactly
+//! as the finished line will look:
+//!
+//! ```
+//! use streamdown_parser::ParseEvent;
+//! use streamdown_render::live::style_partial;
+//!
+//! let draw = |event: &ParseEvent| match event {
+//!     ParseEvent::Bold(text) => format!("<b>{text}</b>"),
+//!     ParseEvent::Text(text) => text.clone(),
+//!     _ => String::new(),
+//! };
+//! assert_eq!(style_partial("Hello **wor", draw), "Hello wor");
+//! assert_eq!(style_partial("Hello **world**", draw), "Hello <b>world</b>");
+//! ```
+
+use streamdown_ansi::codes::RESET;
+use streamdown_ansi::utils::{is_ansi_code, split_up};
+use streamdown_ansi::width::char_width;
+use streamdown_parser::{InlineElement, InlineParser, ParseEvent};
+
+/// Characters that open emphasis or code spans.
+const MARKERS: &[char] = &['*', '_', '`', '~'];
+
+/// Style a partial line: closed spans drawn with `draw`, and the text from
+/// the first span not yet closed plain, without its markers.
+pub fn style_partial(text: &str, draw: impl Fn(&ParseEvent) -> String) -> String {
+    let closed = closed_prefix_len(text);
+    let mut styled: String = InlineParser::new()
+        .parse(&text[..closed])
+        .into_iter()
+        .map(|element| draw(&ParseEvent::from(element)))
+        .collect();
+    styled.push_str(&hide_markers(&text[closed..]));
+    styled
+}
+
+/// Incremental [`style_partial`] for one line that keeps growing.
+#[derive(Debug, Default)]
+pub struct PartialLine {
+    /// Parser fed the line so far
+    parser: InlineParser,
+    /// Text fed to the parser
+    fed: String,
+    /// Byte length of the prefix of `fed` that is parsed with no span open
+    settled: usize,
+    /// That prefix, drawn
+    drawn: String,
+    /// Elements parsed since the parser last had no span open
+    staged: Vec<InlineElement>,
+}
+
+impl PartialLine {
+    /// Create an empty partial line.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Style `text` like [`style_partial`]. When `text` extends the text
+    /// styled last, only the new part is parsed and only the part after the
+    /// settled prefix is re-scanned; otherwise the line starts over.
+    pub fn style(&mut self, text: &str, draw: impl Fn(&ParseEvent) -> String) -> String {
+        if !text.starts_with(self.fed.as_str()) {
+            self.clear();
+        }
+        let chunk = &text[self.fed.len()..];
+        if !chunk.is_empty() {
+            self.fed.push_str(chunk);
+            self.staged.extend(self.parser.push(chunk));
+            if !self.parser.in_span() {
+                for element in self.staged.drain(..) {
+                    self.drawn.push_str(&draw(&ParseEvent::from(element)));
+                }
+                self.settled = self.fed.len() - self.parser.pending().len();
+            }
+        }
+        let mut styled = self.drawn.clone();
+        styled.push_str(&style_partial(&text[self.settled..], draw));
+        styled
+    }
+
+    /// Forget the line, e.g. once it is complete.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// A run of one marker character in a line.
+struct MarkerRun {
+    /// The marker character
+    ch: char,
+    /// Byte range of the run
+    start: usize,
+    end: usize,
+    /// Whether it can open a span (starts a word)
+    opens: bool,
+    /// Whether it can close a span (ends a word)
+    closes: bool,
+    /// Whether it ends the text, after a space (the start of a marker
+    /// whose word has not arrived yet)
+    trailing: bool,
+}
+
+/// Runs of marker characters in `text`.
+fn marker_runs(text: &str) -> Vec<MarkerRun> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut runs = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let (start, ch) = chars[i];
+        if !MARKERS.contains(&ch) {
+            i += 1;
+            continue;
+        }
+        let first = i;
+        while i < chars.len() && chars[i].1 == ch {
+            i += 1;
+        }
+        let before = first.checked_sub(1).map(|j| chars[j].1);
+        let after = chars.get(i).map(|&(_, c)| c);
+        let after_space = before.is_none_or(|c| c.is_whitespace());
+        runs.push(MarkerRun {
+            ch,
+            start,
+            end: chars.get(i).map_or(text.len(), |&(at, _)| at),
+            opens: after_space && after.is_some_and(|c| !c.is_whitespace()),
+            closes: !after_space
+                && after.is_none_or(|c| c.is_whitespace() || c.is_ascii_punctuation()),
+            trailing: after_space && after.is_none(),
+        });
+    }
+    runs
+}
+
+/// Length of the part of `text` before the first span that is opened but
+/// not closed yet.
+fn closed_prefix_len(text: &str) -> usize {
+    // Opening runs not closed yet, outermost first
+    let mut open: Vec<MarkerRun> = Vec::new();
+    for run in marker_runs(text) {
+        // Inside a code span only its closing backticks count
+        if let Some(code) = open.iter().position(|r| r.ch == '`') {
+            if run.ch == '`' && run.end - run.start == open[code].end - open[code].start {
+                open.truncate(code);
+            }
+            continue;
+        }
+        if run.ch == '~' && run.end - run.start < 2 {
+            continue;
+        }
+        match open.iter().rposition(|r| r.ch == run.ch) {
+            Some(at) if run.closes => open.truncate(at),
+            _ if run.opens || run.trailing || run.ch == '`' => open.push(run),
+            _ => {}
+        }
+    }
+    open.first().map_or(text.len(), |run| run.start)
+}
+
+/// Remove the marker runs at word boundaries, keeping those inside words
+/// (`snake_case`) and standing alone (`2 * 3`).
+fn hide_markers(text: &str) -> String {
+    let mut shown = String::with_capacity(text.len());
+    let mut from = 0;
+    for run in marker_runs(text) {
+        if run.opens || run.closes || run.trailing {
+            shown.push_str(&text[from..run.start]);
+            from = run.end;
+        }
+    }
+    shown.push_str(&text[from..]);
+    shown
+}
+
+/// One column position of a painted row: a character and the escape codes
+/// written just before it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Cell {
+    codes: String,
+    ch: char,
+}
+
+/// Paints a row that is redrawn as it changes, rewriting only from the
+/// first cell that differs from what is on screen.
+#[derive(Debug, Default)]
+pub struct LinePainter {
+    /// Cells on screen
+    cells: Vec<Cell>,
+    /// Escape codes after the last cell
+    tail: String,
+}
+
+impl LinePainter {
+    /// Create a painter for an empty row.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Output that turns the row into `line`, or an empty string if it is
+    /// already shown. The cursor is left at the end of the row.
+    pub fn paint(&mut self, line: &str) -> String {
+        let (cells, tail) = cells(line);
+        let same = self
+            .cells
+            .iter()
+            .zip(&cells)
+            .take_while(|(a, b)| a == b)
+            .count();
+        if same == cells.len() && same == self.cells.len() && tail == self.tail {
+            return String::new();
+        }
+        let mut output = if self.cells.is_empty() || same == 0 {
+            "\r\x1b[2K".to_string()
+        } else {
+            let column: usize = cells[..same]
+                .iter()
+                .map(|cell| char_width(cell.ch).unwrap_or(0))
+                .sum();
+            // Restore the styles in effect at the first changed cell
+            let codes: String = cells[..same]
+                .iter()
+                .map(|cell| cell.codes.as_str())
+                .collect();
+            // `CSI 0 C` still moves one column
+            let forward = if column > 0 {
+                format!("\x1b[{}C", column)
+            } else {
+                String::new()
+            };
+            format!("\r{}{}{}", forward, RESET, codes)
+        };
+        for cell in &cells[same..] {
+            output.push_str(&cell.codes);
+            output.push(cell.ch);
+        }
+        output.push_str(&tail);
+        if same > 0 {
+            output.push_str("\x1b[K");
+        }
+        self.cells = cells;
+        self.tail = tail;
+        output
+    }
+
+    /// Forget the row (after it was erased or replaced).
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.tail.clear();
+    }
+}
+
+/// Split a styled line into cells and the codes after the last one.
+fn cells(line: &str) -> (Vec<Cell>, String) {
+    let mut cells = Vec::new();
+    let mut codes = String::new();
+    for part in split_up(line) {
+        if is_ansi_code(&part) {
+            codes.push_str(&part);
+            continue;
+        }
+        for ch in part.chars() {
+            cells.push(Cell {
+                codes: std::mem::take(&mut codes),
+                ch,
+            });
+        }
+    }
+    (cells, codes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_closed_prefix() {
+        assert_eq!(closed_prefix_len("a **b** c"), 9);
+        assert_eq!(closed_prefix_len("a **b** c *d"), 10);
+        assert_eq!(closed_prefix_len("run `x *y`, then"), 16);
+        assert_eq!(closed_prefix_len("run `x"), 4);
+        assert_eq!(closed_prefix_len("almost *"), 7);
+        assert_eq!(closed_prefix_len("snake_case and 2 * 3"), 20);
+    }
+
+    #[test]
+    fn test_style_partial_hides_open_markers() {
+        let draw = |event: &ParseEvent| match event {
+            ParseEvent::Italic(text) => format!("<i>{text}</i>"),
+            ParseEvent::Text(text) => text.clone(),
+            other => format!("{other:?}"),
+        };
+        assert_eq!(style_partial("say **hel", draw), "say hel");
+        assert_eq!(style_partial("run `cargo", draw), "run cargo");
+        assert_eq!(
+            style_partial("*a* and **b `c` d", draw),
+            "<i>a</i> and b c d"
+        );
+        assert_eq!(style_partial("a snake_case *x", draw), "a snake_case x");
+    }
+
+    #[test]
+    fn test_partial_line_matches_style_partial() {
+        let draw = |event: &ParseEvent| match event {
+            ParseEvent::Text(text) => text.clone(),
+            ParseEvent::Italic(text) => format!("<i>{text}</i>"),
+            ParseEvent::Bold(text) => format!("<b>{text}</b>"),
+            ParseEvent::InlineCode(text) => format!("<code>{text}</code>"),
+            ParseEvent::Link { text, url } => format!("<a {url}>{text}</a>"),
+            other => format!("{other:?}"),
+        };
+        let lines = [
+            "plain words *then italic* and **bold that never closes",
+            "code `a b` and [a link](https://x.org) then `open",
+            "snake_case and 2 * 3 are *not* markers",
+        ];
+        // Words of one span may be drawn as adjacent spans of the same style
+        let merge = |styled: String| styled.replace("</i><i>", "").replace("</b><b>", "");
+        for line in lines {
+            let mut partial = PartialLine::new();
+            for end in (1..=line.len()).filter(|&end| line.is_char_boundary(end)) {
+                let text = &line[..end];
+                assert_eq!(
+                    merge(partial.style(text, draw)),
+                    merge(style_partial(text, draw)),
+                    "{text:?}"
+                );
+            }
+        }
+
+        // Text that doesn't extend the line starts it over
+        let mut partial = PartialLine::new();
+        partial.style("first line", draw);
+        assert_eq!(partial.style("other", draw), style_partial("other", draw));
+    }
+
+    #[test]
+    fn test_painter_rewrites_from_the_change() {
+        let mut painter = LinePainter::new();
+        assert_eq!(painter.paint("Hello wor"), "\r\x1b[2KHello wor");
+        assert_eq!(painter.paint("Hello wor"), "");
+        assert_eq!(painter.paint("Hello world"), "\r\x1b[9C\x1b[0mld\x1b[K");
+        // The span is re-styled in place when its closing marker arrives
+        assert_eq!(
+            painter.paint("Hello \x1b[1mworld\x1b[22m"),
+            "\r\x1b[6C\x1b[0m\x1b[1mworld\x1b[22m\x1b[K"
+        );
+        painter.clear();
+        assert_eq!(painter.paint("x"), "\r\x1b[2Kx");
+    }
+
+    #[test]
+    fn test_painter_skips_zero_width_move() {
+        let mut painter = LinePainter::new();
+        painter.paint("\u{200b}a");
+        assert_eq!(painter.paint("\u{200b}b"), "\r\x1b[0mb\x1b[K");
+    }
+}
@@ -0,0 +1,461 @@
+//! Mermaid diagrams in ```` ```mermaid ```` fences.
+//!
+//! [`MermaidHandler`] draws simple flowcharts (`graph`/`flowchart`) as
+//! chains of labelled nodes and sequence diagrams as lifelines with message
+//! arrows, under a "diagram" label. Other diagram types, and drawings that
+//! don't fit the width, are framed under the label with their source, which
+//! [`MermaidHandler::hide_source`] replaces with a line count. The handler
+//! is built in; register a configured one with
+//! [`Renderer::register_fence`](crate::Renderer::register_fence).
+//!
+//! ```
+//! use streamdown_render::RenderStyle;
+//! use streamdown_render::mermaid::{draw_graph, parse_graph};
+//!
+//! let graph = parse_graph("graph TD\n  A[Start] --> B{Ready?}\n  B -->|yes| C[Ship]").unwrap();
+//! assert_eq!(draw_graph(&graph, 60), ["[Start] ──▶ [Ready?] ──yes──▶ [Ship]"]);
+//! ```
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+use streamdown_ansi::codes::{DIM_ON, RESET};
+use streamdown_ansi::width::str_width;
+
+use crate::fence::FenceHandler;
+use crate::{RenderStyle, fg_color};
+
+/// Arrows between flowchart nodes: `--> --- ==> -.-> -.- --x --o`, the
+/// `-- text -->` forms, and an optional `|text|` after the arrow.
+static LINK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"\s*(?:--\s+([^|>-][^>]*?)\s+-->|==\s+([^|>=][^>]*?)\s+==>|-->|==>|-\.->|---|-\.-|--[xo])\s*(?:\|([^|]*)\|)?\s*",
+    )
+    .expect("link regex")
+});
+
+/// A sequence diagram message: `from ->> to: text`.
+static MESSAGE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^([^\s<>:+-][^<>:]*?)\s*(-->>|->>|-->|->|--x|-x|--\)|-\))\s*[+-]?([^:]+?)\s*:\s*(.*)$",
+    )
+    .expect("message regex")
+});
+
+/// Statements of a flowchart that don't add nodes or links.
+const GRAPH_DIRECTIVES: &[&str] = &[
+    "style",
+    "classDef",
+    "class",
+    "click",
+    "linkStyle",
+    "subgraph",
+    "end",
+    "direction",
+];
+
+/// A flowchart: node labels by id, and links in source order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Graph {
+    /// `(id, label)` in order of first appearance
+    pub nodes: Vec<(String, String)>,
+    /// `(from, to, label)`
+    pub links: Vec<(String, String, Option<String>)>,
+}
+
+impl Graph {
+    /// Label of node `id`.
+    fn label<'a>(&'a self, id: &'a str) -> &'a str {
+        self.nodes
+            .iter()
+            .find(|(node, _)| node == id)
+            .map_or(id, |(_, label)| label.as_str())
+    }
+
+    /// Add a node written as `id`, `id[label]`, `id(label)`, `id{label}`...,
+    /// returning its id.
+    fn add_node(&mut self, text: &str) -> Option<String> {
+        let text = text.trim();
+        let end = text
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-' || c == '.'))
+            .unwrap_or(text.len());
+        let id = &text[..end];
+        if id.is_empty() {
+            return None;
+        }
+        let shape = text[end..].trim();
+        let label = shape
+            .trim_start_matches(['[', '(', '{', '>', '/', '\\'])
+            .trim_end_matches([']', ')', '}', '/', '\\'])
+            .trim_matches('"');
+        match self.nodes.iter_mut().find(|(node, _)| node == id) {
+            Some((_, known)) if !label.is_empty() => *known = label.to_string(),
+            Some(_) => {}
+            None => {
+                let label = if label.is_empty() { id } else { label };
+                self.nodes.push((id.to_string(), label.to_string()));
+            }
+        }
+        Some(id.to_string())
+    }
+}
+
+/// Kind of the diagram in `text` (its first word: `graph`,
+/// `sequenceDiagram`, `pie`...).
+pub fn diagram_kind(text: &str) -> Option<&str> {
+    text.lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with("%%"))?
+        .split_whitespace()
+        .next()
+}
+
+/// Parse a `graph`/`flowchart` diagram, or None if it isn't one or has a
+/// statement that can't be read.
+pub fn parse_graph(text: &str) -> Option<Graph> {
+    if !matches!(diagram_kind(text), Some("graph" | "flowchart")) {
+        return None;
+    }
+    let mut graph = Graph::default();
+    let statements = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with("%%"))
+        .skip(1)
+        .flat_map(|line| line.split(';'))
+        .map(str::trim)
+        .filter(|statement| !statement.is_empty());
+    for statement in statements {
+        let first = statement.split_whitespace().next().unwrap_or("");
+        if GRAPH_DIRECTIVES.contains(&first) {
+            continue;
+        }
+        // Node groups between the links, and the link labels
+        let mut groups = Vec::new();
+        let mut labels = Vec::new();
+        let mut from = 0;
+        for link in LINK.captures_iter(statement) {
+            let whole = link.get(0).expect("match");
+            groups.push(&statement[from..whole.start()]);
+            let label = [1, 2, 3]
+                .iter()
+                .find_map(|&group| link.get(group))
+                .map(|label| label.as_str().trim().to_string())
+                .filter(|label| !label.is_empty());
+            labels.push(label);
+            from = whole.end();
+        }
+        groups.push(&statement[from..]);
+        let ids: Vec<Vec<String>> = groups
+            .iter()
+            .map(|group| group.split('&').map(|node| graph.add_node(node)).collect())
+            .collect::<Option<_>>()?;
+        for (i, label) in labels.into_iter().enumerate() {
+            for from in &ids[i] {
+                for to in &ids[i + 1] {
+                    graph.links.push((from.clone(), to.clone(), label.clone()));
+                }
+            }
+        }
+    }
+    Some(graph)
+}
+
+/// Draw `graph` as chains of `[label] ──▶ [label]`, continuing a chain
+/// while each link starts where the last one ended, within `width`
+/// columns where possible.
+pub fn draw_graph(graph: &Graph, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut last: Option<&str> = None;
+    for (from, to, label) in &graph.links {
+        let arrow = match label {
+            Some(label) => format!(" ──{}──▶ ", label),
+            None => " ──▶ ".to_string(),
+        };
+        let target = format!("[{}]", graph.label(to));
+        let continues = last == Some(from.as_str())
+            && lines.last().is_some_and(|line| {
+                str_width(line) + str_width(&arrow) + str_width(&target) <= width
+            });
+        if continues {
+            let line = lines.last_mut().expect("line");
+            line.push_str(&arrow);
+            line.push_str(&target);
+        } else {
+            lines.push(format!("[{}]{}{}", graph.label(from), arrow, target));
+        }
+        last = Some(to);
+    }
+    // Nodes without links
+    for (id, label) in &graph.nodes {
+        let linked = graph
+            .links
+            .iter()
+            .any(|(from, to, _)| from == id || to == id);
+        if !linked {
+            lines.push(format!("[{}]", label));
+        }
+    }
+    lines
+}
+
+/// A sequence diagram: participant names, and messages as
+/// `(from, to, dashed, text)` indexes into them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Sequence {
+    /// `(id, name)` in order of appearance
+    pub participants: Vec<(String, String)>,
+    /// `(from, to, dashed, text)`
+    pub messages: Vec<(usize, usize, bool, String)>,
+}
+
+impl Sequence {
+    /// Index of participant `id`, adding it if new.
+    fn participant(&mut self, id: &str, name: Option<&str>) -> usize {
+        let id = id.trim();
+        if let Some(i) = self.participants.iter().position(|(p, _)| p == id) {
+            if let Some(name) = name {
+                self.participants[i].1 = name.to_string();
+            }
+            return i;
+        }
+        let name = name.unwrap_or(id);
+        self.participants.push((id.to_string(), name.to_string()));
+        self.participants.len() - 1
+    }
+}
+
+/// Parse a `sequenceDiagram`, skipping notes and blocks (`loop`, `alt`...).
+pub fn parse_sequence(text: &str) -> Option<Sequence> {
+    if diagram_kind(text) != Some("sequenceDiagram") {
+        return None;
+    }
+    let mut sequence = Sequence::default();
+    for line in text.lines().map(str::trim).skip_while(|line| {
+        line.is_empty() || line.starts_with("%%") || line.starts_with("sequenceDiagram")
+    }) {
+        let declaration = line
+            .strip_prefix("participant ")
+            .or_else(|| line.strip_prefix("actor "));
+        if let Some(declaration) = declaration {
+            match declaration.split_once(" as ") {
+                Some((id, name)) => sequence.participant(id, Some(name.trim())),
+                None => sequence.participant(declaration, None),
+            };
+        } else if let Some(message) = MESSAGE.captures(line) {
+            let from = sequence.participant(&message[1], None);
+            let to = sequence.participant(&message[3], None);
+            let dashed = message[2].starts_with("--");
+            let text = message[4].trim().to_string();
+            sequence.messages.push((from, to, dashed, text));
+        }
+    }
+    (!sequence.participants.is_empty()).then_some(sequence)
+}
+
+/// Draw `sequence` as lifelines with arrows, or None if it is wider than
+/// `width` columns.
+pub fn draw_sequence(sequence: &Sequence, width: usize) -> Option<Vec<String>> {
+    let names = sequence
+        .participants
+        .iter()
+        .map(|(_, name)| str_width(name));
+    let texts = sequence
+        .messages
+        .iter()
+        .map(|(from, to, _, text)| str_width(text) / from.abs_diff(*to).max(1));
+    let step = names
+        .max()
+        .unwrap_or(0)
+        .max(texts.max().unwrap_or(0) + 4)
+        .max(6)
+        + 2;
+    let total = step * sequence.participants.len();
+    if total > width {
+        return None;
+    }
+    let center = |i: usize| i * step + step / 2;
+    let lifelines = || {
+        let mut row = vec![' '; total];
+        for i in 0..sequence.participants.len() {
+            row[center(i)] = '│';
+        }
+        row
+    };
+    let finish = |row: Vec<char>| row.into_iter().collect::<String>().trim_end().to_string();
+    let mut lines = Vec::new();
+    let mut header = vec![' '; total];
+    for (i, (_, name)) in sequence.participants.iter().enumerate() {
+        let start = center(i).saturating_sub(str_width(name) / 2);
+        for (j, c) in name.chars().enumerate() {
+            header[start + j] = c;
+        }
+    }
+    lines.push(finish(header));
+    for (from, to, dashed, text) in &sequence.messages {
+        let (lo, hi) = (center(*from.min(to)), center(*from.max(to)));
+        let mut label = lifelines();
+        for (j, c) in text.chars().enumerate().take(total - lo - 2) {
+            label[lo + 2 + j] = c;
+        }
+        lines.push(finish(label));
+        let mut arrow = lifelines();
+        if from == to {
+            arrow[lo + 1] = '↺';
+        } else {
+            let line = if *dashed { '╌' } else { '─' };
+            arrow[lo + 1..hi].fill(line);
+            if from < to {
+                arrow[hi - 1] = '▶';
+            } else {
+                arrow[lo + 1] = '◀';
+            }
+        }
+        lines.push(finish(arrow));
+    }
+    Some(lines)
+}
+
+/// Handler for ```` ```mermaid ```` fences.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MermaidHandler {
+    hide_source: bool,
+}
+
+impl MermaidHandler {
+    /// Handler showing the source of diagrams it can't draw.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Show only a line count for diagrams that can't be drawn.
+    pub fn hide_source(mut self, hide: bool) -> Self {
+        self.hide_source = hide;
+        self
+    }
+}
+
+impl FenceHandler for MermaidHandler {
+    fn render(&self, text: &str, width: usize, style: &RenderStyle) -> Option<Vec<String>> {
+        let kind = diagram_kind(text).unwrap_or("empty");
+        let label = format!(
+            "{}◆ diagram: {}{}",
+            fg_color(&style.code_label),
+            kind,
+            RESET
+        );
+        let drawing = if let Some(graph) = parse_graph(text) {
+            Some(draw_graph(&graph, width))
+                .filter(|lines| lines.iter().all(|l| str_width(l) <= width))
+        } else {
+            parse_sequence(text).and_then(|sequence| draw_sequence(&sequence, width))
+        };
+        let mut lines = vec![label];
+        match drawing {
+            Some(drawing) => lines.extend(drawing),
+            None if self.hide_source => {
+                let count = text.lines().count();
+                let noun = if count == 1 { "line" } else { "lines" };
+                lines.push(format!(
+                    "{}(source hidden, {} {}){}",
+                    DIM_ON, count, noun, RESET
+                ));
+            }
+            None => {
+                let border = fg_color(&style.blockquote_border);
+                for line in text.lines() {
+                    lines.push(format!("{}│{} {}{}{}", border, RESET, DIM_ON, line, RESET));
+                }
+            }
+        }
+        Some(lines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use streamdown_ansi::utils::visible;
+
+    #[test]
+    fn test_parse_graph() {
+        let graph = parse_graph(
+            "flowchart LR\n  %% comment\n  A[\"Load\"] -- ok --> B(Parse) & C\n  B -.-> D;style A fill:#f9f\n  E",
+        )
+        .unwrap();
+        assert_eq!(
+            graph.nodes,
+            [
+                ("A".into(), "Load".into()),
+                ("B".into(), "Parse".into()),
+                ("C".into(), "C".into()),
+                ("D".into(), "D".into()),
+                ("E".into(), "E".into()),
+            ]
+        );
+        assert_eq!(
+            graph.links,
+            [
+                ("A".into(), "B".into(), Some("ok".into())),
+                ("A".into(), "C".into(), Some("ok".into())),
+                ("B".into(), "D".into(), None),
+            ]
+        );
+        assert!(parse_graph("pie\n  \"a\" : 1").is_none());
+    }
+
+    #[test]
+    fn test_draw_graph() {
+        let graph = parse_graph("graph TD\nA --> B --> C\nA --> D\nE").unwrap();
+        assert_eq!(
+            draw_graph(&graph, 40),
+            ["[A] ──▶ [B] ──▶ [C]", "[A] ──▶ [D]", "[E]"]
+        );
+        // Chains break at the width
+        assert_eq!(draw_graph(&graph, 12)[..2], ["[A] ──▶ [B]", "[B] ──▶ [C]"]);
+    }
+
+    #[test]
+    fn test_sequence() {
+        let text = "sequenceDiagram\n  participant A as Alice\n  A->>B: Hi\n  B-->>A: Yo\n  loop Daily\n  A->>A: Think\n  end";
+        let sequence = parse_sequence(text).unwrap();
+        assert_eq!(
+            sequence.participants,
+            [("A".into(), "Alice".into()), ("B".into(), "B".into())]
+        );
+        let lines = draw_sequence(&sequence, 40).unwrap();
+        assert_eq!(
+            lines,
+            [
+                "   Alice        B",
+                "     │ Hi       │",
+                "     │─────────▶│",
+                "     │ Yo       │",
+                "     │◀╌╌╌╌╌╌╌╌╌│",
+                "     │ Think    │",
+                "     │↺         │",
+            ]
+        );
+        assert!(draw_sequence(&sequence, 10).is_none());
+    }
+
+    #[test]
+    fn test_handler_frames_what_it_cannot_draw() {
+        let style = RenderStyle::default();
+        let pie = "pie title Pets\n  \"Dogs\" : 3";
+        let lines: Vec<String> = MermaidHandler::new()
+            .render(pie, 40, &style)
+            .unwrap()
+            .iter()
+            .map(|line| visible(line))
+            .collect();
+        assert_eq!(
+            lines,
+            ["◆ diagram: pie", "│ pie title Pets", "│   \"Dogs\" : 3"]
+        );
+        let hidden = MermaidHandler::new()
+            .hide_source(true)
+            .render(pie, 40, &style);
+        assert_eq!(visible(&hidden.unwrap()[1]), "(source hidden, 2 lines)");
+    }
+}
@@ -0,0 +1,215 @@
+//! Styling text by host metadata.
+//!
+//! The host attaches [`SpanMeta`] (e.g. a model's confidence in the tokens
+//! of a chunk) to the text it renders, and a [`MetaStyler`] maps it to a
+//! subtle modulation of the inline spans drawn while it is set, such as
+//! dimming or a colored underline. [`ConfidenceStyler`] does this for a
+//! `confidence` score.
+//!
+//! Hosts driving the parser themselves set the metadata around the events
+//! with [`Renderer::set_span_meta`](crate::Renderer::set_span_meta);
+//! [`Multiplexer::push_with_meta`](crate::Multiplexer::push_with_meta)
+//! tracks it per chunk, looking up the spans of each source line that an
+//! event's text came from.
+//!
+//! ```
+//! use std::sync::Arc;
+//! use streamdown_parser::Parser;
+//! use streamdown_render::{ConfidenceStyler, Renderer, SpanMeta};
+//!
+//! let mut renderer = Renderer::new(Vec::new(), 40);
+//! renderer.set_meta_styler(Some(Arc::new(ConfidenceStyler::default())));
+//! renderer.set_span_meta(Some(SpanMeta::new().with("confidence", 0.2)));
+//! let mut parser = Parser::new();
+//! renderer.render(&parser.parse_line("a guess")).unwrap();
+//! let output = String::from_utf8(renderer.into_writer()).unwrap();
+//! assert!(output.contains("\x1b[2ma guess"));
+//! ```
+
+use std::fmt;
+use std::ops::Range;
+
+use streamdown_ansi::codes::{DIM_OFF, DIM_ON, UNDERLINE_OFF, UNDERLINE_ON};
+use streamdown_parser::ParseEvent;
+
+use crate::RenderStyle;
+use crate::colors::Color;
+
+/// Metadata attached to a stretch of text: named numeric values.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SpanMeta {
+    values: Vec<(String, f64)>,
+}
+
+impl SpanMeta {
+    /// Create empty metadata.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set `key` to `value`.
+    pub fn with(mut self, key: &str, value: f64) -> Self {
+        self.values.retain(|(k, _)| k != key);
+        self.values.push((key.to_string(), value));
+        self
+    }
+
+    /// Value of `key`, if set.
+    pub fn get(&self, key: &str) -> Option<f64> {
+        self.values
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| *value)
+    }
+}
+
+/// Maps metadata to escape codes around the spans carrying it.
+pub trait MetaStyler: fmt::Debug + Send + Sync {
+    /// Codes to write before and after a span with `meta`, or None to draw
+    /// it unchanged. The end codes must undo only what the start codes set.
+    fn modulate(&self, meta: &SpanMeta, style: &RenderStyle) -> Option<(String, String)>;
+}
+
+/// Styler for a confidence score from 0 to 1: dims text below one
+/// threshold and underlines it in a warning color below another.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfidenceStyler {
+    /// Metadata key of the score
+    pub key: String,
+    /// Scores below this are dimmed
+    pub dim_below: f64,
+    /// Scores below this (and not dimmed) are underlined
+    pub underline_below: f64,
+    /// Underline color (a color name, `#rrggbb` or `ansi256:N`)
+    pub underline_color: String,
+}
+
+impl Default for ConfidenceStyler {
+    fn default() -> Self {
+        Self {
+            key: "confidence".to_string(),
+            dim_below: 0.4,
+            underline_below: 0.7,
+            underline_color: "#d7875f".to_string(),
+        }
+    }
+}
+
+impl MetaStyler for ConfidenceStyler {
+    fn modulate(&self, meta: &SpanMeta, _: &RenderStyle) -> Option<(String, String)> {
+        let score = meta.get(&self.key)?;
+        if score < self.dim_below {
+            Some((DIM_ON.to_string(), DIM_OFF.to_string()))
+        } else if score < self.underline_below {
+            let color = Color::parse(&self.underline_color)
+                .map(Color::underline)
+                .unwrap_or_default();
+            Some((
+                format!("{}{}", UNDERLINE_ON, color),
+                format!("\x1b[59m{}", UNDERLINE_OFF),
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+/// Metadata of the byte ranges of one source line, for finding the
+/// metadata of the events parsed from it.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LineMeta {
+    /// The source line
+    line: String,
+    /// Metadata by byte range of the line
+    spans: Vec<(Range<usize>, SpanMeta)>,
+    /// Where the next event's text is searched from
+    cursor: usize,
+}
+
+impl LineMeta {
+    /// Metadata of `spans` (byte ranges of `line`).
+    pub(crate) fn new(line: &str, spans: Vec<(Range<usize>, SpanMeta)>) -> Self {
+        Self {
+            line: line.to_string(),
+            spans,
+            cursor: 0,
+        }
+    }
+
+    /// Metadata of the span overlapping most of the text of `event`, found
+    /// in the line after the previous event's.
+    pub(crate) fn lookup(&mut self, event: &ParseEvent) -> Option<SpanMeta> {
+        let text = match event {
+            ParseEvent::Text(text)
+            | ParseEvent::InlineCode(text)
+            | ParseEvent::Bold(text)
+            | ParseEvent::Italic(text)
+            | ParseEvent::Underline(text)
+            | ParseEvent::Strikeout(text)
+            | ParseEvent::BoldItalic(text)
+            | ParseEvent::Spoiler(text)
+            | ParseEvent::Kbd(text)
+            | ParseEvent::Link { text, .. } => text,
+            _ => return None,
+        };
+        if text.is_empty() || self.spans.is_empty() {
+            return None;
+        }
+        let start = self.cursor + self.line.get(self.cursor..)?.find(text.as_str())?;
+        let end = start + text.len();
+        self.cursor = end;
+        self.spans
+            .iter()
+            .map(|(range, meta)| {
+                (
+                    range.end.min(end).saturating_sub(range.start.max(start)),
+                    meta,
+                )
+            })
+            .filter(|(overlap, _)| *overlap > 0)
+            .max_by_key(|(overlap, _)| *overlap)
+            .map(|(_, meta)| meta.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_confidence_styler() {
+        let styler = ConfidenceStyler::default();
+        let style = RenderStyle::default();
+        let at = |score| styler.modulate(&SpanMeta::new().with("confidence", score), &style);
+        assert_eq!(at(0.1).unwrap().0, DIM_ON);
+        let (on, off) = at(0.5).unwrap();
+        assert!(on.contains("58;2;215;135;95") && off.ends_with(UNDERLINE_OFF));
+        assert_eq!(at(0.9), None);
+        assert_eq!(styler.modulate(&SpanMeta::new(), &style), None);
+    }
+
+    #[test]
+    fn test_line_meta_lookup() {
+        let sure = SpanMeta::new().with("confidence", 1.0);
+        let unsure = SpanMeta::new().with("confidence", 0.1);
+        // "the **cat** sat" streamed as "the **c" (sure) and "at** sat" (unsure)
+        let mut meta = LineMeta::new(
+            "the **cat** sat",
+            vec![(0..7, sure.clone()), (7..15, unsure.clone())],
+        );
+        let lookup = |meta: &mut LineMeta, event| meta.lookup(&event);
+        assert_eq!(
+            lookup(&mut meta, ParseEvent::Text("the ".into())),
+            Some(sure)
+        );
+        assert_eq!(
+            lookup(&mut meta, ParseEvent::Bold("cat".into())),
+            Some(unsure.clone())
+        );
+        assert_eq!(
+            lookup(&mut meta, ParseEvent::Text(" sat".into())),
+            Some(unsure)
+        );
+        assert_eq!(lookup(&mut meta, ParseEvent::Text("dog".into())), None);
+    }
+}
@@ -0,0 +1,348 @@
+//! Multiplexed rendering of concurrent streams.
+//!
+//! A [`Multiplexer`] owns one parse/render session per channel (e.g. one per
+//! agent in a multi-agent frontend) and accepts their chunks interleaved.
+//! Each channel gets its own seeded theme ([`RenderStyle::from_seed`]).
+//!
+//! With [`MuxLayout::Prefix`] complete output lines are written as soon as
+//! they are rendered, each tagged with its channel label. With
+//! [`MuxLayout::Panes`] lines are kept per channel for a host that draws
+//! its own split panes.
+//!
+//! ```
+//! use streamdown_render::Multiplexer;
+//!
+//! let mut mux = Multiplexer::new(Vec::new(), 60);
+//! let planner = mux.add_channel("planner");
+//! let coder = mux.add_channel("coder");
+//! mux.push(planner, "# Plan\n").unwrap();
+//! mux.push(coder, "Writing *code*\n").unwrap();
+//! mux.finish_all().unwrap();
+//!
+//! let output = String::from_utf8(mux.into_writer()).unwrap();
+//! assert!(output.contains("planner"));
+//! assert!(output.contains("coder"));
+//! ```
+
+use std::io::{self, Write};
+
+use streamdown_ansi::codes::{DIM_OFF, DIM_ON, RESET};
+use streamdown_ansi::utils::visible_length;
+
+use crate::chunk::ChunkRenderer;
+use crate::{BorderStyle, RenderStyle, Renderer, SpanMeta, fg_color};
+
+/// Base hue channel themes are rotated around.
+const CHANNEL_BASE_HUE: f64 = 0.8;
+
+/// How channel output is laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MuxLayout {
+    /// Interleave lines on one writer, each prefixed with its channel tag
+    #[default]
+    Prefix,
+    /// Keep lines per channel (see [`Multiplexer::pane_lines`])
+    Panes,
+}
+
+/// Handle to a channel of a [`Multiplexer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChannelId(usize);
+
+/// One session of a multiplexer.
+struct Channel {
+    /// Label shown in the tag
+    label: String,
+    /// Color code the label is drawn in
+    color: String,
+    /// Parse/render pipeline
+    inner: ChunkRenderer,
+    /// Rendered output after the last newline
+    partial: Vec<u8>,
+    /// Completed lines (Panes layout)
+    lines: Vec<String>,
+    /// Whether the stream has ended
+    finished: bool,
+}
+
+impl Channel {
+    /// Tag written before each line (Prefix layout): the label and a
+    /// divider from the renderer's border glyphs.
+    fn tag(&self) -> String {
+        let border = self.inner.renderer.features().border_style;
+        tag(&self.color, &self.label, border)
+    }
+}
+
+/// Tag of a channel labeled `label`, drawn in `color`.
+fn tag(color: &str, label: &str, border: BorderStyle) -> String {
+    format!(
+        "{}{}{} {}{}{} ",
+        color,
+        label,
+        RESET,
+        DIM_ON,
+        border.glyphs().vertical,
+        DIM_OFF
+    )
+}
+
+/// Renders several markdown streams into labeled, interleaved regions.
+pub struct Multiplexer<W: Write> {
+    /// Output for the Prefix layout
+    writer: W,
+    /// Total width in columns
+    width: usize,
+    /// Output layout
+    layout: MuxLayout,
+    /// Sessions, indexed by [`ChannelId`]
+    channels: Vec<Channel>,
+}
+
+impl<W: Write> Multiplexer<W> {
+    /// Create a multiplexer with prefix tags.
+    pub fn new(writer: W, width: usize) -> Self {
+        Self::with_layout(writer, width, MuxLayout::Prefix)
+    }
+
+    /// Create a multiplexer with the given layout.
+    ///
+    /// In the Panes layout `width` is the width of each pane.
+    pub fn with_layout(writer: W, width: usize, layout: MuxLayout) -> Self {
+        Self {
+            writer,
+            width,
+            layout,
+            channels: Vec::new(),
+        }
+    }
+
+    /// Add a channel with a theme seeded by its position.
+    pub fn add_channel(&mut self, label: &str) -> ChannelId {
+        let style = RenderStyle::from_seed(self.channels.len() as u64, CHANNEL_BASE_HUE);
+        self.add_channel_with_style(label, style)
+    }
+
+    /// Add a channel with a custom style.
+    ///
+    /// The tag is drawn in the style's `h2` color.
+    pub fn add_channel_with_style(&mut self, label: &str, style: RenderStyle) -> ChannelId {
+        let color = fg_color(&style.h2);
+        let width = match self.layout {
+            MuxLayout::Prefix => {
+                let tag = tag(&color, label, BorderStyle::default());
+                self.width.saturating_sub(visible_length(&tag)).max(1)
+            }
+            MuxLayout::Panes => self.width,
+        };
+        self.channels.push(Channel {
+            label: label.to_string(),
+            color,
+            inner: ChunkRenderer::new(Renderer::with_style(Vec::new(), width, style)),
+            partial: Vec::new(),
+            lines: Vec::new(),
+            finished: false,
+        });
+        ChannelId(self.channels.len() - 1)
+    }
+
+    /// Add a chunk of markdown to a channel.
+    ///
+    /// Lines completed by the chunk are rendered; in the Prefix layout they
+    /// are written out immediately.
+    pub fn push(&mut self, id: ChannelId, chunk: &str) -> io::Result<()> {
+        let channel = &mut self.channels[id.0];
+        if channel.finished {
+            return Ok(());
+        }
+        channel.inner.push(chunk)?;
+        self.flush_channel(id)
+    }
+
+    /// Add a chunk of markdown to a channel, with metadata (e.g. model
+    /// confidence) for the channel renderer's
+    /// [`MetaStyler`](crate::MetaStyler) to style its text by.
+    pub fn push_with_meta(&mut self, id: ChannelId, chunk: &str, meta: SpanMeta) -> io::Result<()> {
+        let channel = &mut self.channels[id.0];
+        if channel.finished {
+            return Ok(());
+        }
+        channel.inner.push_with_meta(chunk, Some(meta))?;
+        self.flush_channel(id)
+    }
+
+    /// End a channel's stream, rendering its trailing partial line.
+    pub fn finish(&mut self, id: ChannelId) -> io::Result<()> {
+        let channel = &mut self.channels[id.0];
+        if channel.finished {
+            return Ok(());
+        }
+        channel.finished = true;
+        channel.inner.finish()?;
+        self.flush_channel(id)?;
+
+        // Output not ending in a newline is still a line of its own
+        let channel = &mut self.channels[id.0];
+        if !channel.partial.is_empty() {
+            let line = String::from_utf8_lossy(&std::mem::take(&mut channel.partial)).into_owned();
+            self.emit(id, line)?;
+        }
+        Ok(())
+    }
+
+    /// End every channel's stream.
+    pub fn finish_all(&mut self) -> io::Result<()> {
+        for i in 0..self.channels.len() {
+            self.finish(ChannelId(i))?;
+        }
+        Ok(())
+    }
+
+    /// Get a channel's label.
+    pub fn label(&self, id: ChannelId) -> &str {
+        &self.channels[id.0].label
+    }
+
+    /// Get all channel handles, in the order they were added.
+    pub fn channels(&self) -> impl Iterator<Item = ChannelId> + use<W> {
+        (0..self.channels.len()).map(ChannelId)
+    }
+
+    /// Get the completed lines of a channel (Panes layout).
+    pub fn pane_lines(&self, id: ChannelId) -> &[String] {
+        &self.channels[id.0].lines
+    }
+
+    /// Get a mutable reference to a channel's renderer (e.g. to set features).
+    pub fn renderer_mut(&mut self, id: ChannelId) -> &mut Renderer<Vec<u8>> {
+        &mut self.channels[id.0].inner.renderer
+    }
+
+    /// Consume the multiplexer and return the writer.
+    pub fn into_writer(self) -> W {
+        self.writer
+    }
+
+    /// Move a channel's rendered output into complete lines.
+    fn flush_channel(&mut self, id: ChannelId) -> io::Result<()> {
+        let channel = &mut self.channels[id.0];
+        let output = channel.inner.take_output();
+        channel.partial.extend_from_slice(&output);
+        let Some(end) = memchr::memrchr(b'\n', &channel.partial) else {
+            return Ok(());
+        };
+        let complete: Vec<u8> = channel.partial.drain(..=end).collect();
+        let text = String::from_utf8_lossy(&complete[..end]).into_owned();
+        for line in text.split('\n') {
+            self.emit(id, line.to_string())?;
+        }
+        Ok(())
+    }
+
+    /// Write or store one rendered line.
+    fn emit(&mut self, id: ChannelId, line: String) -> io::Result<()> {
+        let channel = &mut self.channels[id.0];
+        match self.layout {
+            MuxLayout::Prefix => writeln!(self.writer, "{}{}{}", channel.tag(), line, RESET),
+            MuxLayout::Panes => {
+                channel.lines.push(line);
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use streamdown_ansi::utils::visible;
+
+    fn output(mux: Multiplexer<Vec<u8>>) -> Vec<String> {
+        String::from_utf8(mux.into_writer())
+            .unwrap()
+            .lines()
+            .map(visible)
+            .collect()
+    }
+
+    #[test]
+    fn test_interleaved_lines_are_tagged() {
+        let mut mux = Multiplexer::new(Vec::new(), 40);
+        let a = mux.add_channel("a");
+        let b = mux.add_channel("b");
+        mux.push(a, "first from ").unwrap();
+        mux.push(b, "first from b\n").unwrap();
+        mux.push(a, "a\n").unwrap();
+        mux.finish_all().unwrap();
+
+        let lines = output(mux);
+        let b_pos = lines.iter().position(|l| l.contains("first from b"));
+        let a_pos = lines.iter().position(|l| l.contains("first from a"));
+        assert!(b_pos.unwrap() < a_pos.unwrap());
+        assert!(lines[b_pos.unwrap()].starts_with("b │ "));
+        assert!(lines[a_pos.unwrap()].starts_with("a │ "));
+        assert!(
+            lines
+                .iter()
+                .all(|l| l.starts_with("a │") || l.starts_with("b │"))
+        );
+    }
+
+    #[test]
+    fn test_divider_follows_border_style() {
+        let mut mux = Multiplexer::new(Vec::new(), 40);
+        let id = mux.add_channel("a");
+        mux.renderer_mut(id).set_features(crate::RenderFeatures {
+            border_style: BorderStyle::Ascii,
+            ..Default::default()
+        });
+        mux.push(id, "text\n").unwrap();
+        mux.finish_all().unwrap();
+        assert!(output(mux).iter().all(|l| l.starts_with("a | ")));
+    }
+
+    #[test]
+    fn test_renderer_width_leaves_room_for_tag() {
+        let mut mux = Multiplexer::new(Vec::new(), 30);
+        let id = mux.add_channel("agent");
+        // "agent │ " takes 8 columns
+        assert_eq!(mux.renderer_mut(id).width(), 22);
+
+        let mut mux = Multiplexer::with_layout(Vec::new(), 30, MuxLayout::Panes);
+        let id = mux.add_channel("agent");
+        assert_eq!(mux.renderer_mut(id).width(), 30);
+    }
+
+    #[test]
+    fn test_panes_layout() {
+        let mut mux = Multiplexer::with_layout(Vec::new(), 40, MuxLayout::Panes);
+        let a = mux.add_channel("a");
+        let b = mux.add_channel("b");
+        mux.push(a, "# Title\n").unwrap();
+        mux.push(b, "text").unwrap();
+        assert!(mux.pane_lines(b).is_empty());
+        mux.finish_all().unwrap();
+
+        assert!(
+            mux.pane_lines(a)
+                .iter()
+                .any(|l| visible(l).contains("Title"))
+        );
+        assert!(
+            mux.pane_lines(b)
+                .iter()
+                .any(|l| visible(l).contains("text"))
+        );
+        assert_eq!(mux.label(b), "b");
+        assert!(mux.into_writer().is_empty());
+    }
+
+    #[test]
+    fn test_channels_get_distinct_themes() {
+        let mut mux = Multiplexer::new(Vec::new(), 40);
+        let a = mux.add_channel("agent");
+        let b = mux.add_channel("agent");
+        assert_ne!(mux.channels[a.0].tag(), mux.channels[b.0].tag());
+        assert_eq!(mux.channels().count(), 2);
+    }
+}
@@ -0,0 +1,307 @@
+//! Block navigation over rendered output.
+//!
+//! With line metadata enabled ([`Renderer::set_line_metadata`]), the renderer
+//! tags every output line with the block it belongs to. Hosts that keep the
+//! output around (pagers, TUIs) can then jump between blocks of a kind, vim
+//! style:
+//!
+//! ```
+//! use streamdown_parser::Parser;
+//! use streamdown_render::{NavCommand, Renderer};
+//!
+//! let mut renderer = Renderer::new(Vec::new(), 60);
+//! renderer.set_line_metadata(true);
+//! let mut parser = Parser::new();
+//! for line in ["# Intro", "text", "```", "code", "```"] {
+//!     for event in parser.parse_line(line) {
+//!         renderer.render_event(&event).unwrap();
+//!     }
+//! }
+//!
+//! // `]c` from the top jumps to the first line of the code block
+//! let command = NavCommand::parse("]c").unwrap();
+//! let target = command.apply(renderer.line_metadata(), 0).unwrap();
+//! assert!(target > 0);
+//! ```
+//!
+//! Every block also gets a [`BlockId`], a hash of its kind, position and
+//! content. Rendering the same document again gives the same IDs, so tools
+//! can refer to a block across re-renders and tell which blocks changed.
+//! With retained events, a block can be replaced by ID (the "edit a previous
+//! message" pattern): [`Renderer::rerender_block`] renders the new markdown
+//! at the stored width and returns a [`BlockPatch`] for the retained events.
+//!
+//! [`Renderer::set_line_metadata`]: crate::Renderer::set_line_metadata
+//! [`Renderer::rerender_block`]: crate::Renderer::rerender_block
+
+use std::fmt;
+use std::ops::Range;
+
+use streamdown_parser::ParseEvent;
+
+/// Kind of block an output line belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum BlockKind {
+    /// Paragraph text (and anything not otherwise classified)
+    #[default]
+    Paragraph,
+    /// Heading
+    Heading,
+    /// Fenced code block
+    Code,
+    /// Table
+    Table,
+    /// List
+    List,
+    /// Blockquote
+    Blockquote,
+    /// `<think>` block
+    Think,
+    /// `<details>` summary line
+    Details,
+    /// Horizontal rule
+    Rule,
+}
+
+impl BlockKind {
+    /// Block kind for a navigation key (`h`, `c`, `t`, `l`, `q`).
+    pub fn from_key(key: char) -> Option<Self> {
+        match key {
+            'h' => Some(Self::Heading),
+            'c' => Some(Self::Code),
+            't' => Some(Self::Table),
+            'l' => Some(Self::List),
+            'q' => Some(Self::Blockquote),
+            _ => None,
+        }
+    }
+
+    /// Lowercase name of the kind (`code`, `table`, ...).
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Paragraph => "paragraph",
+            Self::Heading => "heading",
+            Self::Code => "code",
+            Self::Table => "table",
+            Self::List => "list",
+            Self::Blockquote => "blockquote",
+            Self::Think => "think",
+            Self::Details => "details",
+            Self::Rule => "rule",
+        }
+    }
+}
+
+/// Stable identifier of a block: a hash of its kind, ordinal and content.
+///
+/// The ID of a block still being rendered changes as content arrives; it is
+/// final once the next block starts. Displayed as 16 hex digits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct BlockId(pub u64);
+
+impl BlockId {
+    const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+    /// Ends each field; never occurs in UTF-8 text
+    const SEPARATOR: u8 = 0xff;
+
+    /// ID of an empty block of `kind` at ordinal `block`.
+    pub(crate) fn new(kind: BlockKind, block: usize) -> Self {
+        let mut id = Self(Self::FNV_OFFSET);
+        id.feed(kind.name().as_bytes());
+        id.feed(&(block as u64).to_le_bytes());
+        id
+    }
+
+    /// Add one field of the block's content (FNV-1a, so the result is
+    /// stable across runs and platforms). Fields are separated, so `"ab", "c"`
+    /// and `"a", "bc"` hash differently.
+    pub(crate) fn feed(&mut self, bytes: &[u8]) {
+        for &byte in bytes.iter().chain([&Self::SEPARATOR]) {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(Self::FNV_PRIME);
+        }
+    }
+}
+
+impl fmt::Display for BlockId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+/// Replacement for one block of the retained events, from
+/// [`Renderer::rerender_block`](crate::Renderer::rerender_block).
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockPatch {
+    /// ID of the replaced block
+    pub id: BlockId,
+    /// Ordinal of the replaced block
+    pub block: usize,
+    /// Retained events the block spans
+    pub events: Range<usize>,
+    /// Events replacing them
+    pub replacement: Vec<ParseEvent>,
+    /// The replacement rendered at the stored width, one entry per line
+    pub lines: Vec<String>,
+}
+
+/// Metadata for one output line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineMeta {
+    /// Kind of the block the line belongs to
+    pub kind: BlockKind,
+    /// Ordinal of the block within the document
+    pub block: usize,
+}
+
+/// Direction to move in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NavDirection {
+    /// Towards the end of the document
+    Next,
+    /// Towards the start of the document
+    Previous,
+}
+
+/// A navigation request, e.g. "next code block".
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NavCommand {
+    /// Direction to move in
+    pub direction: NavDirection,
+    /// Kind of block to stop at (None = any block)
+    pub kind: Option<BlockKind>,
+}
+
+impl NavCommand {
+    /// Parse a vim-style binding: `]` or `[` followed by a kind key
+    /// (see [`BlockKind::from_key`]) or `]` / `[` again for any block.
+    pub fn parse(keys: &str) -> Option<Self> {
+        let mut chars = keys.chars();
+        let direction = match chars.next()? {
+            ']' => NavDirection::Next,
+            '[' => NavDirection::Previous,
+            _ => return None,
+        };
+        let key = chars.next()?;
+        if chars.next().is_some() {
+            return None;
+        }
+        let kind = match key {
+            ']' | '[' => None,
+            key => Some(BlockKind::from_key(key)?),
+        };
+        Some(Self { direction, kind })
+    }
+
+    /// Find the target line for a cursor at `line`.
+    pub fn apply(&self, lines: &[LineMeta], line: usize) -> Option<usize> {
+        match self.direction {
+            NavDirection::Next => next_block(lines, line, self.kind),
+            NavDirection::Previous => previous_block(lines, line, self.kind),
+        }
+    }
+}
+
+/// First line of the next block after the one containing `line`.
+///
+/// With `kind` set, blocks of other kinds are skipped.
+pub fn next_block(lines: &[LineMeta], line: usize, kind: Option<BlockKind>) -> Option<usize> {
+    let current = lines.get(line).map(|m| m.block);
+    block_starts(lines)
+        .find(|&(i, meta)| i > line && Some(meta.block) != current && matches(meta, kind))
+        .map(|(i, _)| i)
+}
+
+/// First line of the nearest block before the one containing `line`.
+///
+/// With `kind` set, blocks of other kinds are skipped.
+pub fn previous_block(lines: &[LineMeta], line: usize, kind: Option<BlockKind>) -> Option<usize> {
+    let current = lines.get(line).map(|m| m.block);
+    block_starts(lines)
+        .filter(|&(i, meta)| i < line && Some(meta.block) != current && matches(meta, kind))
+        .last()
+        .map(|(i, _)| i)
+}
+
+/// Lines that start a block.
+fn block_starts(lines: &[LineMeta]) -> impl Iterator<Item = (usize, &LineMeta)> {
+    lines
+        .iter()
+        .enumerate()
+        .filter(|&(i, meta)| i == 0 || lines[i - 1].block != meta.block)
+}
+
+fn matches(meta: &LineMeta, kind: Option<BlockKind>) -> bool {
+    kind.is_none_or(|k| meta.kind == k)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc() -> Vec<LineMeta> {
+        use BlockKind::*;
+        [
+            (Heading, 0),
+            (Paragraph, 1),
+            (Code, 2),
+            (Code, 2),
+            (Paragraph, 3),
+            (Code, 4),
+            (Table, 5),
+        ]
+        .into_iter()
+        .map(|(kind, block)| LineMeta { kind, block })
+        .collect()
+    }
+
+    #[test]
+    fn test_next_block() {
+        let lines = doc();
+        assert_eq!(next_block(&lines, 0, Some(BlockKind::Code)), Some(2));
+        // From inside a code block, skip to the next one
+        assert_eq!(next_block(&lines, 3, Some(BlockKind::Code)), Some(5));
+        assert_eq!(next_block(&lines, 5, Some(BlockKind::Code)), None);
+        assert_eq!(next_block(&lines, 2, None), Some(4));
+    }
+
+    #[test]
+    fn test_previous_block() {
+        let lines = doc();
+        assert_eq!(previous_block(&lines, 6, Some(BlockKind::Code)), Some(5));
+        assert_eq!(previous_block(&lines, 5, Some(BlockKind::Code)), Some(2));
+        assert_eq!(previous_block(&lines, 3, None), Some(1));
+        assert_eq!(previous_block(&lines, 0, None), None);
+    }
+
+    #[test]
+    fn test_block_id() {
+        let id = |kind, block, text: &str| {
+            let mut id = BlockId::new(kind, block);
+            for field in text.split('|') {
+                id.feed(field.as_bytes());
+            }
+            id
+        };
+        assert_eq!(id(BlockKind::Code, 2, "x"), id(BlockKind::Code, 2, "x"));
+        assert_ne!(id(BlockKind::Code, 2, "x"), id(BlockKind::Code, 3, "x"));
+        assert_ne!(id(BlockKind::Code, 2, "x"), id(BlockKind::List, 2, "x"));
+        assert_ne!(id(BlockKind::Code, 2, "x"), id(BlockKind::Code, 2, "y"));
+        assert_ne!(
+            id(BlockKind::Code, 2, "ab|c"),
+            id(BlockKind::Code, 2, "a|bc")
+        );
+        assert_eq!(BlockId(0xab).to_string(), "00000000000000ab");
+    }
+
+    #[test]
+    fn test_parse_command() {
+        let cmd = NavCommand::parse("]c").unwrap();
+        assert_eq!(cmd.direction, NavDirection::Next);
+        assert_eq!(cmd.kind, Some(BlockKind::Code));
+        assert_eq!(NavCommand::parse("[[").unwrap().kind, None);
+        assert!(NavCommand::parse("]x").is_none());
+        assert!(NavCommand::parse("c").is_none());
+        assert_eq!(cmd.apply(&doc(), 0), Some(2));
+    }
+}
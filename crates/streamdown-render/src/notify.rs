@@ -0,0 +1,228 @@
+//! Desktop notifications via terminal escapes.
+//!
+//! With [`NotifyOptions`] set ([`Renderer::set_notifications`]) the renderer
+//! writes a notification escape when rendered text contains one of the
+//! watched patterns, and [`Renderer::notify_finished`] sends one when a long
+//! document is done. The escape is picked from
+//! [`TerminalCapabilities::notifications`]; terminals without support get
+//! nothing.
+//!
+//! ```
+//! use streamdown_render::{notification_sequence, NotificationProtocol};
+//!
+//! let seq = notification_sequence(NotificationProtocol::Osc777, "sd", "done").unwrap();
+//! assert_eq!(seq, "\x1b]777;notify;sd;done\x07");
+//! ```
+//!
+//! [`Renderer::set_notifications`]: crate::Renderer::set_notifications
+//! [`Renderer::notify_finished`]: crate::Renderer::notify_finished
+//! [`TerminalCapabilities::notifications`]: crate::TerminalCapabilities::notifications
+
+use std::time::{Duration, Instant};
+
+use streamdown_parser::ParseEvent;
+
+use crate::capabilities::NotificationProtocol;
+use crate::footer::for_each_text;
+
+/// Notification title.
+pub const NOTIFY_TITLE: &str = "streamdown";
+
+/// Longest notification body, in characters.
+const MAX_BODY: usize = 120;
+
+/// When to send notifications.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotifyOptions {
+    /// Notify when the document finishes
+    pub on_finish: bool,
+    /// Only notify on finish if rendering took at least this long
+    pub min_elapsed: Duration,
+    /// Notify (once each) when rendered text contains one of these
+    pub patterns: Vec<String>,
+}
+
+impl Default for NotifyOptions {
+    fn default() -> Self {
+        Self {
+            on_finish: true,
+            min_elapsed: Duration::from_secs(10),
+            patterns: Vec::new(),
+        }
+    }
+}
+
+/// Build the escape sequence for a notification.
+///
+/// Control characters are dropped from the title and body (and `;` from
+/// `OSC 777` fields, where it separates them). Returns `None` for
+/// [`NotificationProtocol::None`].
+pub fn notification_sequence(
+    protocol: NotificationProtocol,
+    title: &str,
+    body: &str,
+) -> Option<String> {
+    let clean = |s: &str, extra: Option<char>| -> String {
+        s.chars()
+            .filter(|&c| !c.is_control() && Some(c) != extra)
+            .take(MAX_BODY)
+            .collect()
+    };
+    match protocol {
+        NotificationProtocol::None => None,
+        NotificationProtocol::Osc9 => Some(format!(
+            "\x1b]9;{}: {}\x07",
+            clean(title, None),
+            clean(body, None)
+        )),
+        NotificationProtocol::Osc777 => Some(format!(
+            "\x1b]777;notify;{};{}\x07",
+            clean(title, Some(';')),
+            clean(body, Some(';'))
+        )),
+    }
+}
+
+/// Notification state of a renderer.
+#[derive(Debug, Clone)]
+pub(crate) struct Notifier {
+    /// When to notify
+    pub(crate) options: NotifyOptions,
+    /// Time of the first event
+    started: Option<Instant>,
+    /// Which patterns have already fired
+    fired: Vec<bool>,
+    /// End of the inline text seen so far on the current line, so patterns
+    /// split across events (or stream chunks) still match and the body shows
+    /// the line they are on
+    tail: String,
+}
+
+impl Notifier {
+    /// Create a notifier for a new document.
+    pub(crate) fn new(options: NotifyOptions) -> Self {
+        let fired = vec![false; options.patterns.len()];
+        Self {
+            options,
+            started: None,
+            fired,
+            tail: String::new(),
+        }
+    }
+
+    /// Bodies of the notifications triggered by an event.
+    pub(crate) fn check_event(&mut self, event: &ParseEvent) -> Vec<String> {
+        self.started.get_or_insert_with(Instant::now);
+        if !event.is_inline() {
+            self.tail.clear();
+        }
+        let keep = self
+            .options
+            .patterns
+            .iter()
+            .map(|p| p.chars().count())
+            .fold(MAX_BODY, usize::max);
+        let mut bodies = Vec::new();
+        for_each_text(event, |text, whole_line| {
+            // Complete lines stand alone; inline runs continue the tail
+            let mut seen = if whole_line {
+                String::new()
+            } else {
+                std::mem::take(&mut self.tail)
+            };
+            seen.push_str(text);
+            for (pattern, fired) in self.options.patterns.iter().zip(&mut self.fired) {
+                if !*fired && !pattern.is_empty() && seen.contains(pattern.as_str()) {
+                    *fired = true;
+                    bodies.push(seen.trim().to_string());
+                }
+            }
+            if !whole_line {
+                let start = seen
+                    .char_indices()
+                    .rev()
+                    .nth(keep - 1)
+                    .map_or(0, |(i, _)| i);
+                self.tail = seen[start..].to_string();
+            }
+        });
+        bodies
+    }
+
+    /// Body of the completion notification, if one is due.
+    pub(crate) fn finished(&self, lines: usize) -> Option<String> {
+        let elapsed = self.started.map(|s| s.elapsed()).unwrap_or_default();
+        (self.options.on_finish && elapsed >= self.options.min_elapsed)
+            .then(|| format!("Finished rendering ({} lines)", lines))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sequences() {
+        assert_eq!(
+            notification_sequence(NotificationProtocol::Osc9, "sd", "done").as_deref(),
+            Some("\x1b]9;sd: done\x07")
+        );
+        // Field separators and escapes can't leak into the sequence
+        assert_eq!(
+            notification_sequence(NotificationProtocol::Osc777, "a;b", "x\x1by\x07").as_deref(),
+            Some("\x1b]777;notify;ab;xy\x07")
+        );
+        assert!(notification_sequence(NotificationProtocol::None, "a", "b").is_none());
+    }
+
+    #[test]
+    fn test_patterns_fire_once() {
+        let mut notifier = Notifier::new(NotifyOptions {
+            patterns: vec!["ERROR".to_string()],
+            ..Default::default()
+        });
+        let event = ParseEvent::Text("ERROR: disk full".to_string());
+        assert_eq!(notifier.check_event(&event), vec!["ERROR: disk full"]);
+        assert!(notifier.check_event(&event).is_empty());
+        assert!(
+            notifier
+                .check_event(&ParseEvent::Text("fine".to_string()))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_patterns_split_across_events() {
+        let mut notifier = Notifier::new(NotifyOptions {
+            patterns: vec!["ERROR".to_string()],
+            ..Default::default()
+        });
+        let text = |s: &str| ParseEvent::Text(s.to_string());
+        assert!(notifier.check_event(&text("build ER")).is_empty());
+        assert_eq!(
+            notifier.check_event(&text("ROR: x")),
+            vec!["build ERROR: x"]
+        );
+
+        // A line break in between is not a match
+        let mut notifier = Notifier::new(notifier.options.clone());
+        notifier.check_event(&text("ER"));
+        notifier.check_event(&ParseEvent::Newline);
+        assert!(notifier.check_event(&text("ROR")).is_empty());
+    }
+
+    #[test]
+    fn test_finish_needs_min_elapsed() {
+        let notifier = Notifier::new(NotifyOptions::default());
+        assert!(notifier.finished(10).is_none());
+
+        let notifier = Notifier::new(NotifyOptions {
+            min_elapsed: Duration::ZERO,
+            ..Default::default()
+        });
+        assert_eq!(
+            notifier.finished(3).as_deref(),
+            Some("Finished rendering (3 lines)")
+        );
+    }
+}
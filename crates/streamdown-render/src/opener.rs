@@ -0,0 +1,108 @@
+//! Opening links in an external program.
+//!
+//! The renderer numbers the distinct link URLs of a document in order of
+//! appearance (shown as `[n]` after the link text with
+//! [`RenderFeatures::numbered_links`](crate::RenderFeatures::numbered_links)),
+//! and [`Renderer::open_link`](crate::Renderer::open_link) launches link
+//! `n` with a [`LinkOpener`]: `open` on macOS, `wslview` under WSL,
+//! `xdg-open` elsewhere, or a configured command.
+//!
+//! ```
+//! use streamdown_parser::Parser;
+//! use streamdown_render::{LinkOpener, Renderer};
+//!
+//! let mut renderer = Renderer::new(Vec::new(), 60);
+//! renderer.set_link_opener(LinkOpener::new("true"));
+//! let mut parser = Parser::new();
+//! renderer
+//!     .render(&parser.parse_line("See [docs](https://example.com/docs)."))
+//!     .unwrap();
+//! assert_eq!(renderer.links(), ["https://example.com/docs"]);
+//! # #[cfg(unix)]
+//! assert_eq!(renderer.open_link(1).unwrap(), "https://example.com/docs");
+//! assert!(renderer.open_link(2).is_err());
+//! ```
+
+use std::io;
+use std::process::{Command, Stdio};
+
+/// Program that opens URLs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkOpener {
+    /// Command and leading arguments; the URL is appended
+    command: Vec<String>,
+}
+
+impl Default for LinkOpener {
+    fn default() -> Self {
+        Self::detect()
+    }
+}
+
+impl LinkOpener {
+    /// Opener running `command` (split on whitespace, e.g. `firefox
+    /// --new-tab`) with the URL as its last argument.
+    pub fn new(command: &str) -> Self {
+        Self {
+            command: command.split_whitespace().map(String::from).collect(),
+        }
+    }
+
+    /// The platform's opener: `open` on macOS, `wslview` under WSL,
+    /// `xdg-open` otherwise.
+    pub fn detect() -> Self {
+        let wsl = std::env::var_os("WSL_DISTRO_NAME").is_some();
+        Self::new(Self::platform_command(cfg!(target_os = "macos"), wsl))
+    }
+
+    /// Opener command for a platform.
+    fn platform_command(macos: bool, wsl: bool) -> &'static str {
+        if macos {
+            "open"
+        } else if wsl {
+            "wslview"
+        } else {
+            "xdg-open"
+        }
+    }
+
+    /// Command and leading arguments.
+    pub fn command(&self) -> &[String] {
+        &self.command
+    }
+
+    /// Launch the opener on `url` without waiting for it to exit.
+    pub fn open(&self, url: &str) -> io::Result<()> {
+        let (program, args) = self
+            .command
+            .split_first()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty opener command"))?;
+        let mut child = Command::new(program)
+            .args(args)
+            .arg(url)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        // Reap the child in the background so it does not linger
+        std::thread::spawn(move || child.wait());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opener_command() {
+        assert_eq!(
+            LinkOpener::new("firefox  --new-tab").command(),
+            ["firefox", "--new-tab"]
+        );
+        assert_eq!(LinkOpener::platform_command(true, false), "open");
+        assert_eq!(LinkOpener::platform_command(false, true), "wslview");
+        assert_eq!(LinkOpener::platform_command(false, false), "xdg-open");
+        assert!(LinkOpener::new("").open("https://example.com").is_err());
+    }
+}
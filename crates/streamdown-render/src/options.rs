@@ -0,0 +1,183 @@
+//! Renderer options.
+//!
+//! [`RendererOptions`] holds settings about how output is written, as
+//! opposed to [`RenderFeatures`](crate::RenderFeatures), which decide what
+//! is drawn. The library writes colors unless [`ColorChoice`] says
+//! otherwise, so its output doesn't depend on the process environment;
+//! [`ColorChoice::Auto`] follows the `NO_COLOR`, `CLICOLOR` and
+//! `CLICOLOR_FORCE` conventions, as `sd` does by default. Without colors the
+//! renderer drops SGR sequences (colors, bold, italic...) but keeps the
+//! layout: padding, borders, glyphs and hyperlinks.
+//!
+//! ```
+//! use streamdown_render::ColorChoice;
+//!
+//! let env = |name: &str| (name == "NO_COLOR").then(|| "1".to_string());
+//! assert!(!ColorChoice::Auto.enabled_with(env));
+//! assert!(ColorChoice::Always.enabled_with(env));
+//! ```
+//!
+//! With the `serde` feature, [`RendererOptions`] (like
+//! [`RenderFeatures`](crate::RenderFeatures) and
+//! [`RenderStyle`](crate::RenderStyle)) can be persisted as user
+//! preferences.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// When to write colors and text attributes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum ColorChoice {
+    /// Follow `NO_COLOR`, `CLICOLOR` and `CLICOLOR_FORCE`
+    Auto,
+    /// Always write them
+    #[default]
+    Always,
+    /// Never write them
+    Never,
+}
+
+impl ColorChoice {
+    /// All choices, in declaration order.
+    pub const ALL: [ColorChoice; 3] = [ColorChoice::Auto, ColorChoice::Always, ColorChoice::Never];
+
+    /// Name used on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorChoice::Auto => "auto",
+            ColorChoice::Always => "always",
+            ColorChoice::Never => "never",
+        }
+    }
+
+    /// Whether colors are enabled, given the process environment.
+    pub fn enabled(self) -> bool {
+        self.enabled_with(|name| std::env::var(name).ok())
+    }
+
+    /// Whether colors are enabled, given an environment lookup function.
+    ///
+    /// For [`Auto`](Self::Auto), a non-empty `NO_COLOR` disables colors,
+    /// then a `CLICOLOR_FORCE` other than `0` enables them, then
+    /// `CLICOLOR=0` disables them. Whether output is a terminal is left to
+    /// the host.
+    pub fn enabled_with<F>(self, var: F) -> bool
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                let set = |name| var(name).filter(|v| !v.is_empty());
+                if set("NO_COLOR").is_some() {
+                    false
+                } else if set("CLICOLOR_FORCE").is_some_and(|v| v != "0") {
+                    true
+                } else {
+                    set("CLICOLOR").is_none_or(|v| v != "0")
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for ColorChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ColorChoice::ALL
+            .into_iter()
+            .find(|choice| choice.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                format!(
+                    "unknown color choice '{}' (expected auto, always or never)",
+                    s
+                )
+            })
+    }
+}
+
+/// How the renderer writes its output.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct RendererOptions {
+    /// When to write colors and text attributes
+    pub color: ColorChoice,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(pairs: &'static [(&'static str, &'static str)]) -> impl Fn(&str) -> Option<String> {
+        move |name| {
+            pairs
+                .iter()
+                .find(|(k, _)| *k == name)
+                .map(|(_, v)| v.to_string())
+        }
+    }
+
+    #[test]
+    fn test_auto_precedence() {
+        let auto = |pairs| ColorChoice::Auto.enabled_with(env(pairs));
+        assert!(auto(&[]));
+        assert!(auto(&[("NO_COLOR", "")]));
+        assert!(!auto(&[("NO_COLOR", "1"), ("CLICOLOR_FORCE", "1")]));
+        assert!(!auto(&[("CLICOLOR", "0")]));
+        assert!(auto(&[("CLICOLOR", "0"), ("CLICOLOR_FORCE", "1")]));
+        assert!(!auto(&[("CLICOLOR", "0"), ("CLICOLOR_FORCE", "0")]));
+    }
+
+    #[test]
+    fn test_explicit_choice_ignores_env() {
+        assert!(!ColorChoice::Never.enabled_with(env(&[("CLICOLOR_FORCE", "1")])));
+        assert_eq!("ALWAYS".parse::<ColorChoice>(), Ok(ColorChoice::Always));
+        assert!("sometimes".parse::<ColorChoice>().is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde_roundtrip() {
+        use crate::{LinkStyle, RenderFeatures};
+
+        let options = RendererOptions {
+            color: ColorChoice::Never,
+        };
+        let json = serde_json::to_string(&options).unwrap();
+        assert_eq!(json, r#"{"color":"never"}"#);
+        assert_eq!(
+            serde_json::from_str::<RendererOptions>(&json).unwrap(),
+            options
+        );
+        assert_eq!(
+            serde_json::from_str::<RendererOptions>("{}").unwrap(),
+            RendererOptions::default()
+        );
+
+        let features: RenderFeatures =
+            serde_json::from_str(r#"{"margin": 3, "link_style": "endnotes"}"#).unwrap();
+        assert_eq!(features.margin, 3);
+        assert_eq!(features.link_style, LinkStyle::Endnotes);
+        assert!(features.highlight);
+        let saved = serde_json::to_string(&features).unwrap();
+        let loaded: RenderFeatures = serde_json::from_str(&saved).unwrap();
+        assert_eq!(loaded.margin, 3);
+    }
+}
@@ -0,0 +1,112 @@
+//! Outline-only rendering.
+//!
+//! With [`RenderFeatures::outline`] set, the renderer draws just the
+//! skeleton of a document: headings, the first line of prose in each
+//! section, and a one-line summary (`▶ rust code (42 lines)`) per code
+//! block. Lists, tables, quotes and the rest of each section are skipped,
+//! which makes very long answers quick to skim.
+//!
+//! ```
+//! use streamdown_render::{RenderFeatures, Renderer};
+//! use streamdown_parser::Parser;
+//!
+//! let features = RenderFeatures { outline: true, ..Default::default() };
+//! let mut renderer = Renderer::with_features(Vec::new(), 60, features);
+//! let mut parser = Parser::new();
+//! for line in ["# Setup", "Install it first.", "Then configure.", "```sh", "make", "```"] {
+//!     for event in parser.parse_line(line) {
+//!         renderer.render_event(&event).unwrap();
+//!     }
+//! }
+//! let output = String::from_utf8(renderer.into_writer()).unwrap();
+//! assert!(output.contains("Install it first."));
+//! assert!(!output.contains("Then configure."));
+//! assert!(output.contains("sh code (1 line)"));
+//! ```
+//!
+//! [`RenderFeatures::outline`]: crate::RenderFeatures::outline
+
+use streamdown_parser::ParseEvent;
+
+/// Progress through the first prose line of a section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum FirstLine {
+    /// Not seen yet
+    #[default]
+    Pending,
+    /// Being drawn
+    Drawing,
+    /// Drawn; the rest of the section is skipped
+    Done,
+}
+
+/// Decides which events an outline draws.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct OutlineFilter {
+    /// First line of the current section
+    first_line: FirstLine,
+}
+
+impl OutlineFilter {
+    /// Whether `event` is drawn in the outline. Code blocks are summarized
+    /// by the caller and not passed here.
+    pub(crate) fn keeps(&mut self, event: &ParseEvent) -> bool {
+        match event {
+            ParseEvent::Heading { .. } => {
+                self.first_line = FirstLine::Pending;
+                true
+            }
+            ParseEvent::InlineElements(_) => self.keeps_inline(),
+            event if event.is_inline() => self.keeps_inline(),
+            ParseEvent::Newline | ParseEvent::EmptyLine => {
+                let drawing = self.first_line == FirstLine::Drawing;
+                if drawing {
+                    self.first_line = FirstLine::Done;
+                }
+                drawing
+            }
+            _ => {
+                // Any other block ends the first line
+                if self.first_line == FirstLine::Drawing {
+                    self.first_line = FirstLine::Done;
+                }
+                false
+            }
+        }
+    }
+
+    /// Whether an inline event is drawn.
+    fn keeps_inline(&mut self) -> bool {
+        if self.first_line == FirstLine::Pending {
+            self.first_line = FirstLine::Drawing;
+        }
+        self.first_line == FirstLine::Drawing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_line_per_section() {
+        let mut filter = OutlineFilter::default();
+        let text = |s: &str| ParseEvent::Text(s.to_string());
+        let heading = ParseEvent::Heading {
+            level: 2,
+            content: "Usage".to_string(),
+        };
+
+        assert!(filter.keeps(&text("intro")));
+        assert!(filter.keeps(&ParseEvent::Newline));
+        assert!(!filter.keeps(&text("more")));
+        assert!(!filter.keeps(&ParseEvent::Newline));
+        assert!(filter.keeps(&heading));
+        assert!(!filter.keeps(&ParseEvent::HorizontalRule));
+        assert!(!filter.keeps(&ParseEvent::EmptyLine));
+        assert!(filter.keeps(&ParseEvent::Bold("first".to_string())));
+        assert!(filter.keeps(&text(" line")));
+        assert!(!filter.keeps(&ParseEvent::ListEnd));
+        assert!(!filter.keeps(&text("after the list")));
+    }
+}
@@ -0,0 +1,114 @@
+//! Pinned header region.
+//!
+//! A [`PinnedHeader`] reserves the top row of the terminal for the current
+//! H1/H2 headings: the rest of the screen becomes the scroll region
+//! (DECSTBM), and the header row is redrawn in place whenever a heading
+//! streams by. Earlier output stays on screen and scrolls up as new output
+//! arrives. Hosts running full screen set `alt_screen` so the previous
+//! terminal contents come back when the header is unpinned.
+//!
+//! ```
+//! use streamdown_render::{PinnedHeader, Renderer};
+//!
+//! let mut renderer = Renderer::new(Vec::new(), 40);
+//! renderer.set_pinned_header(Some(PinnedHeader::new(24))).unwrap();
+//! renderer.set_pinned_header(None).unwrap();
+//! ```
+
+use streamdown_ansi::codes::{BOLD_OFF, BOLD_ON, RESET};
+use streamdown_ansi::utils::visible_length;
+
+use crate::fg_color;
+use crate::text::truncate_to_visible;
+
+/// Headings at or above this level are shown in the pinned row.
+pub const PIN_MAX_LEVEL: u8 = 2;
+
+/// Terminal layout for a pinned header row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PinnedHeader {
+    /// Terminal height in rows
+    pub height: usize,
+    /// Switch to the alternate screen while pinned
+    pub alt_screen: bool,
+}
+
+impl PinnedHeader {
+    /// Pin a header on the main screen of a terminal `height` rows tall.
+    pub fn new(height: usize) -> Self {
+        Self {
+            height,
+            alt_screen: false,
+        }
+    }
+
+    /// Sequence that reserves the header row without clearing the screen.
+    ///
+    /// On the main screen the cursor goes to a fresh line at the bottom of
+    /// the scroll region, below what is already shown; on the (blank)
+    /// alternate screen it goes to the top of the region.
+    pub fn enter_sequence(&self) -> String {
+        let height = self.height.max(2);
+        if self.alt_screen {
+            format!("\x1b[?1049h\x1b[2;{}r\x1b[2;1H", height)
+        } else {
+            format!("\x1b[2;{}r\x1b[{};1H\n", height, height)
+        }
+    }
+
+    /// Sequence that releases the header row (and leaves the alternate
+    /// screen, if entered).
+    pub fn leave_sequence(&self) -> String {
+        let alt = if self.alt_screen { "\x1b[?1049l" } else { "" };
+        format!("\x1b7\x1b[r\x1b8{}", alt)
+    }
+
+    /// Sequence that redraws the header row with `text`, truncated to
+    /// `width` columns, leaving the cursor where it was.
+    pub fn draw_sequence(&self, text: &str, width: usize, color: &str) -> String {
+        let text = if visible_length(text) > width {
+            format!("{}…", truncate_to_visible(text, width.saturating_sub(1)))
+        } else {
+            text.to_string()
+        };
+        format!(
+            "\x1b7\x1b[1;1H\x1b[2K{}{}{}{}{}\x1b8",
+            BOLD_ON,
+            fg_color(color),
+            text,
+            BOLD_OFF,
+            RESET
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use streamdown_ansi::utils::visible;
+
+    #[test]
+    fn test_enter_reserves_top_row() {
+        let pin = PinnedHeader::new(30);
+        assert_eq!(pin.enter_sequence(), "\x1b[2;30r\x1b[30;1H\n");
+        assert!(!pin.enter_sequence().contains("\x1b[2J"));
+        assert!(!pin.leave_sequence().contains("1049"));
+
+        let pin = PinnedHeader {
+            alt_screen: true,
+            ..pin
+        };
+        assert!(pin.enter_sequence().starts_with("\x1b[?1049h"));
+        assert!(pin.leave_sequence().ends_with("\x1b[?1049l"));
+    }
+
+    #[test]
+    fn test_draw_truncates() {
+        let pin = PinnedHeader::new(30);
+        let seq = pin.draw_sequence("A very long heading", 10, "white");
+        assert!(seq.starts_with("\x1b7\x1b[1;1H"));
+        assert!(seq.ends_with("\x1b8"));
+        let body = &seq["\x1b7\x1b[1;1H\x1b[2K".len()..seq.len() - 2];
+        assert!(visible_length(&visible(body)) <= 10);
+    }
+}
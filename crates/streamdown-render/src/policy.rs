@@ -0,0 +1,209 @@
+//! Policy for unsafe content.
+//!
+//! A [`Policy`] decides, in one place, what a document may make the renderer
+//! do beyond drawing text: interpret raw HTML, link remote images, open local
+//! files, emit OSC sequences (hyperlinks, notifications), write to the
+//! clipboard and pass its own escape sequences to the terminal. The default
+//! trusts the document; [`Policy::untrusted`] is meant for content from the
+//! internet or an LLM.
+//!
+//! ```
+//! use streamdown_render::Policy;
+//!
+//! let policy = Policy::untrusted();
+//! assert!(!policy.allows_link("https://example.com"));
+//!
+//! let policy = Policy {
+//!     osc: true,
+//!     ..Policy::untrusted()
+//! };
+//! assert!(policy.allows_link("https://example.com"));
+//! assert!(!policy.allows_link("file:///etc/passwd"));
+//! assert!(!policy.allows_link("javascript:alert(1)"));
+//! ```
+
+/// What a document is allowed to do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Policy {
+    /// Interpret raw HTML (`<details>` folding); otherwise it is shown expanded
+    pub raw_html: bool,
+    /// Make image markers for remote URLs clickable
+    pub remote_images: bool,
+    /// Open local files: `file://` links, copy links and images read from disk
+    pub file_links: bool,
+    /// Emit OSC sequences (hyperlinks, desktop notifications)
+    pub osc: bool,
+    /// Write to the clipboard (OSC 52)
+    pub clipboard: bool,
+    /// Pass escape sequences in the source (title changes, cursor moves,
+    /// colors) to the terminal; otherwise they are removed, or shown with
+    /// [`ControlChars::Show`](crate::ControlChars::Show). The renderer's
+    /// own styling is unaffected.
+    pub source_escapes: bool,
+    /// URL schemes hyperlinks may use (empty = any)
+    pub allow_schemes: Vec<String>,
+    /// URL schemes [`Renderer::open_link`](crate::Renderer::open_link) may
+    /// launch; `file` and `mailto` have to be added explicitly
+    pub open_schemes: Vec<String>,
+    /// Hosts that never get hyperlinks
+    pub deny_hosts: Vec<String>,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self::trusted()
+    }
+}
+
+impl Policy {
+    /// Allow everything the renderer supports.
+    pub fn trusted() -> Self {
+        Self {
+            raw_html: true,
+            remote_images: true,
+            file_links: true,
+            osc: true,
+            clipboard: true,
+            source_escapes: true,
+            allow_schemes: Vec::new(),
+            open_schemes: web_schemes(),
+            deny_hosts: Vec::new(),
+        }
+    }
+
+    /// Deny everything beyond drawing text; hyperlinks, if `osc` is turned
+    /// back on, are limited to web and mail URLs.
+    pub fn untrusted() -> Self {
+        Self {
+            raw_html: false,
+            remote_images: false,
+            file_links: false,
+            osc: false,
+            clipboard: false,
+            source_escapes: false,
+            allow_schemes: ["http", "https", "mailto"].map(String::from).to_vec(),
+            open_schemes: web_schemes(),
+            deny_hosts: Vec::new(),
+        }
+    }
+
+    /// Whether `url` may be made a clickable (OSC 8) hyperlink.
+    pub fn allows_link(&self, url: &str) -> bool {
+        self.osc && self.allows_target(url)
+    }
+
+    /// Whether `url` may be launched in an external program (see
+    /// [`Renderer::open_link`](crate::Renderer::open_link)): a URL with one
+    /// of the [`open_schemes`](Self::open_schemes), without control
+    /// characters, that hyperlinks could use.
+    pub fn allows_open(&self, url: &str) -> bool {
+        let opens = url_scheme(url).is_some_and(|scheme| {
+            self.open_schemes
+                .iter()
+                .any(|s| s.eq_ignore_ascii_case(scheme))
+        });
+        opens && streamdown_ansi::sanitize::is_safe_url(url) && self.allows_target(url)
+    }
+
+    /// Whether the scheme and host of `url` are allowed.
+    fn allows_target(&self, url: &str) -> bool {
+        let scheme = url_scheme(url).unwrap_or("file");
+        if scheme.eq_ignore_ascii_case("file") && !self.file_links {
+            return false;
+        }
+        if !self.allow_schemes.is_empty()
+            && !self
+                .allow_schemes
+                .iter()
+                .any(|s| s.eq_ignore_ascii_case(scheme))
+        {
+            return false;
+        }
+        match url_host(url) {
+            Some(host) => !self
+                .deny_hosts
+                .iter()
+                .any(|denied| host_matches(host, denied)),
+            None => true,
+        }
+    }
+}
+
+/// The schemes of web URLs.
+fn web_schemes() -> Vec<String> {
+    ["http", "https"].map(String::from).to_vec()
+}
+
+/// Scheme of a URL (`https` in `https://…`), if it has one.
+fn url_scheme(url: &str) -> Option<&str> {
+    let (scheme, _) = url.split_once(':')?;
+    let valid = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    valid.then_some(scheme)
+}
+
+/// Host of a hierarchical URL (`example.com` in `https://user@example.com:8080/x`).
+fn url_host(url: &str) -> Option<&str> {
+    let (_, rest) = url.split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?;
+    let host = host.split(':').next()?;
+    (!host.is_empty()).then_some(host)
+}
+
+/// Whether `host` is `denied` or one of its subdomains.
+fn host_matches(host: &str, denied: &str) -> bool {
+    host.eq_ignore_ascii_case(denied)
+        || host.len() > denied.len()
+            && host.as_bytes()[host.len() - denied.len() - 1] == b'.'
+            && host[host.len() - denied.len()..].eq_ignore_ascii_case(denied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trusted_allows_any_link() {
+        let policy = Policy::default();
+        assert!(policy.allows_link("file:///tmp/x"));
+        assert!(policy.allows_link("/tmp/x"));
+        assert!(policy.allows_link("ssh://host"));
+    }
+
+    #[test]
+    fn test_opening_needs_an_open_scheme() {
+        let policy = Policy::default();
+        assert!(policy.allows_open("https://example.com"));
+        assert!(!policy.allows_open("file:///etc/passwd"));
+        assert!(!policy.allows_open("mailto:a@b"));
+        assert!(!policy.allows_open("/tmp/x"));
+        let policy = Policy {
+            open_schemes: vec!["file".to_string()],
+            ..Default::default()
+        };
+        assert!(policy.allows_open("file:///tmp/x"));
+        assert!(!policy.allows_open("https://example.com"));
+    }
+
+    #[test]
+    fn test_deny_hosts_covers_subdomains() {
+        let policy = Policy {
+            deny_hosts: vec!["evil.com".to_string()],
+            ..Default::default()
+        };
+        assert!(!policy.allows_link("https://evil.com/x"));
+        assert!(!policy.allows_link("https://user@cdn.EVIL.com:443/x"));
+        assert!(policy.allows_link("https://notevil.com"));
+    }
+
+    #[test]
+    fn test_url_parts() {
+        assert_eq!(url_scheme("HTTPS://x"), Some("HTTPS"));
+        assert_eq!(url_scheme("./a:b"), None);
+        assert_eq!(url_host("https://a.b:1/c?d"), Some("a.b"));
+        assert_eq!(url_host("mailto:a@b"), None);
+    }
+}
@@ -0,0 +1,385 @@
+//! Pretty-printing for ```` ```json ```` and ```` ```yaml ```` fences.
+//!
+//! [`JsonHandler`] re-indents a JSON fence and colors its keys, strings and
+//! numbers; [`YamlHandler`] colors a YAML fence line by line, keeping its
+//! layout. Both are opt-in fence handlers: register them with
+//! [`Renderer::register_fence`](crate::Renderer::register_fence). Malformed
+//! JSON, and output that doesn't fit the width, falls back to the verbatim
+//! code block. [`JsonHandler`] checks the JSON with `serde_json`, so it
+//! needs the `serde` feature.
+
+use streamdown_ansi::codes::{DIM_ON, FGRESET, RESET};
+use streamdown_ansi::width::str_width;
+
+use crate::fence::FenceHandler;
+use crate::{RenderStyle, fg_color};
+
+/// Indentation per nesting level.
+#[cfg(feature = "serde")]
+const INDENT: &str = "  ";
+
+/// Colors for the parts of a data fence.
+struct Palette {
+    key: String,
+    string: String,
+    number: String,
+}
+
+impl Palette {
+    fn new(style: &RenderStyle) -> Self {
+        Self {
+            key: fg_color(&style.h3),
+            string: fg_color("green"),
+            number: fg_color("yellow"),
+        }
+    }
+
+    /// `text` in `color`.
+    fn paint(color: &str, text: &str) -> String {
+        format!("{}{}{}", color, text, FGRESET)
+    }
+}
+
+/// A JSON token.
+#[cfg(feature = "serde")]
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+    /// `{ } [ ] , :`
+    Punct(char),
+    /// A string literal with its quotes
+    String(&'a str),
+    /// A number, `true`, `false` or `null`
+    Scalar(&'a str),
+}
+
+/// Split valid JSON into tokens.
+#[cfg(feature = "serde")]
+fn tokens(text: &str) -> Vec<Token<'_>> {
+    let bytes = text.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b if b.is_ascii_whitespace() => i += 1,
+            b @ (b'{' | b'}' | b'[' | b']' | b',' | b':') => {
+                tokens.push(Token::Punct(b as char));
+                i += 1;
+            }
+            b'"' => {
+                let start = i;
+                i += 1;
+                while bytes[i] != b'"' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                i += 1;
+                tokens.push(Token::String(&text[start..i]));
+            }
+            _ => {
+                let start = i;
+                while i < bytes.len() && !b"{}[],: \t\r\n".contains(&bytes[i]) {
+                    i += 1;
+                }
+                tokens.push(Token::Scalar(&text[start..i]));
+            }
+        }
+    }
+    tokens
+}
+
+/// Re-indent and color `text` as JSON, keeping its key order and number
+/// spelling. None if it isn't valid JSON or a line is wider than `width`.
+///
+/// ```
+/// use streamdown_render::RenderStyle;
+/// use streamdown_render::pretty::pretty_json;
+///
+/// let style = RenderStyle::default();
+/// let lines = pretty_json(r#"{"id": 7, "tags": []}"#, 40, &style).unwrap();
+/// assert_eq!(lines.len(), 4);
+/// assert!(pretty_json("{\"id\": ", 40, &style).is_none());
+/// ```
+#[cfg(feature = "serde")]
+pub fn pretty_json(text: &str, width: usize, style: &RenderStyle) -> Option<Vec<String>> {
+    serde_json::from_str::<serde::de::IgnoredAny>(text).ok()?;
+    let palette = Palette::new(style);
+    let tokens = tokens(text);
+    let mut lines = Vec::new();
+    // The line being built, plain and colored
+    let mut plain = String::new();
+    let mut line = String::new();
+    let mut depth = 0;
+    let mut push = |plain: &mut String, line: &mut String| {
+        lines.push((std::mem::take(plain), std::mem::take(line)));
+    };
+    for (i, token) in tokens.iter().enumerate() {
+        let next = tokens.get(i + 1);
+        // Closing brackets are indented after leaving their level
+        if plain.is_empty() && !matches!(token, Token::Punct('}' | ']')) {
+            plain.push_str(&INDENT.repeat(depth));
+            line.push_str(&INDENT.repeat(depth));
+        }
+        match token {
+            Token::Punct(open @ ('{' | '[')) => {
+                plain.push(*open);
+                line.push(*open);
+                // Empty containers stay on one line
+                if !matches!(next, Some(Token::Punct('}' | ']'))) {
+                    depth += 1;
+                    push(&mut plain, &mut line);
+                }
+            }
+            Token::Punct(close @ ('}' | ']')) => {
+                let empty = matches!(tokens[i - 1], Token::Punct('{' | '['));
+                if !empty {
+                    depth -= 1;
+                    if !plain.is_empty() {
+                        push(&mut plain, &mut line);
+                    }
+                    plain.push_str(&INDENT.repeat(depth));
+                    line.push_str(&INDENT.repeat(depth));
+                }
+                plain.push(*close);
+                line.push(*close);
+                if !matches!(next, Some(Token::Punct(','))) {
+                    push(&mut plain, &mut line);
+                }
+            }
+            Token::Punct(',') => {
+                plain.push(',');
+                line.push(',');
+                push(&mut plain, &mut line);
+            }
+            Token::Punct(_) => {
+                plain.push_str(": ");
+                line.push_str(": ");
+            }
+            Token::String(string) => {
+                let key = matches!(next, Some(Token::Punct(':')));
+                let color = if key { &palette.key } else { &palette.string };
+                plain.push_str(string);
+                line.push_str(&Palette::paint(color, string));
+            }
+            Token::Scalar(scalar) => {
+                plain.push_str(scalar);
+                line.push_str(&Palette::paint(&palette.number, scalar));
+            }
+        }
+    }
+    // A bare scalar
+    if !plain.is_empty() {
+        push(&mut plain, &mut line);
+    }
+    if lines.iter().any(|(plain, _)| str_width(plain) > width) {
+        return None;
+    }
+    Some(lines.into_iter().map(|(_, line)| line).collect())
+}
+
+/// Color one line of YAML: mapping keys, quoted strings, numbers and
+/// literals, and comments. Anything else is kept as written.
+pub fn yaml_line(line: &str, style: &RenderStyle) -> String {
+    let palette = Palette::new(style);
+    let body = line.trim_start();
+    let indent = &line[..line.len() - body.len()];
+    if body.starts_with('#') {
+        return format!("{}{}{}{}", indent, DIM_ON, body, RESET);
+    }
+    // Sequence markers
+    let item = body.len() - body.trim_start_matches(['-', ' ']).len();
+    let (item, body) = if body.starts_with("- ") || body == "-" {
+        body.split_at(item)
+    } else {
+        ("", body)
+    };
+    let (value, comment) = split_comment(body);
+    let colored = match mapping_key(value) {
+        Some(colon) => {
+            let (key, rest) = value.split_at(colon);
+            let rest = &rest[1..];
+            let scalar = rest.trim_start();
+            format!(
+                "{}:{}{}",
+                Palette::paint(&palette.key, key),
+                &rest[..rest.len() - scalar.len()],
+                yaml_scalar(scalar, &palette)
+            )
+        }
+        None => yaml_scalar(value, &palette),
+    };
+    let comment = if comment.is_empty() {
+        String::new()
+    } else {
+        format!("{}{}{}", DIM_ON, comment, RESET)
+    };
+    format!("{}{}{}{}", indent, item, colored, comment)
+}
+
+/// Byte offset of the colon ending a mapping key at the start of `text`.
+fn mapping_key(text: &str) -> Option<usize> {
+    if text.starts_with(['"', '\'', '[', '{']) {
+        return None;
+    }
+    text.find(": ")
+        .or_else(|| text.strip_suffix(':').map(str::len))
+}
+
+/// Split `text` at a ` #` comment outside quotes.
+fn split_comment(text: &str) -> (&str, &str) {
+    let mut quote = None;
+    let mut prev = ' ';
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            (None, '#') if prev.is_whitespace() && i > 0 => return text.split_at(i),
+            _ => {}
+        }
+        prev = c;
+    }
+    (text, "")
+}
+
+/// Color a YAML scalar value.
+fn yaml_scalar(text: &str, palette: &Palette) -> String {
+    let value = text.trim_end();
+    let padding = &text[value.len()..];
+    let color = if value.starts_with(['"', '\'']) {
+        &palette.string
+    } else if value.parse::<f64>().is_ok()
+        || matches!(value, "true" | "false" | "null" | "~" | "yes" | "no")
+    {
+        &palette.number
+    } else {
+        return text.to_string();
+    };
+    format!("{}{}", Palette::paint(color, value), padding)
+}
+
+/// Handler for ```` ```json ```` fences.
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonHandler;
+
+#[cfg(feature = "serde")]
+impl FenceHandler for JsonHandler {
+    fn render(&self, text: &str, width: usize, style: &RenderStyle) -> Option<Vec<String>> {
+        pretty_json(text, width, style)
+    }
+}
+
+/// Handler for ```` ```yaml ```` fences.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct YamlHandler;
+
+impl FenceHandler for YamlHandler {
+    fn render(&self, text: &str, width: usize, style: &RenderStyle) -> Option<Vec<String>> {
+        if text.lines().any(|line| str_width(line) > width) {
+            return None;
+        }
+        Some(text.lines().map(|line| yaml_line(line, style)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "serde")]
+    use streamdown_ansi::utils::visible;
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_pretty_json() {
+        let style = RenderStyle::default();
+        let text = r#"{"b": [1, 2.50, {"c": null}], "a": {}, "s": "x\"y"}"#;
+        let lines: Vec<String> = pretty_json(text, 40, &style)
+            .unwrap()
+            .iter()
+            .map(|line| visible(line))
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "{",
+                "  \"b\": [",
+                "    1,",
+                "    2.50,",
+                "    {",
+                "      \"c\": null",
+                "    }",
+                "  ],",
+                "  \"a\": {},",
+                "  \"s\": \"x\\\"y\"",
+                "}",
+            ]
+        );
+        assert_eq!(pretty_json("[]", 40, &style).unwrap(), ["[]"]);
+        assert_eq!(visible(&pretty_json("42", 40, &style).unwrap()[0]), "42");
+        // Malformed or too wide
+        assert!(pretty_json("{\"a\": 1,}", 40, &style).is_none());
+        assert!(pretty_json("{\"a\": \"a long value\"}", 10, &style).is_none());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_pretty_json_colors() {
+        let style = RenderStyle::default();
+        let lines = pretty_json(r#"{"k": "v", "n": 1}"#, 40, &style).unwrap();
+        let key = fg_color(&style.h3);
+        assert_eq!(
+            lines[1],
+            format!(
+                "  {key}\"k\"{FGRESET}: {}\"v\"{FGRESET},",
+                fg_color("green")
+            )
+        );
+        assert_eq!(
+            lines[2],
+            format!("  {key}\"n\"{FGRESET}: {}1{FGRESET}", fg_color("yellow"))
+        );
+    }
+
+    #[test]
+    fn test_yaml_line() {
+        let style = RenderStyle::default();
+        let key = fg_color(&style.h3);
+        let number = fg_color("yellow");
+        assert_eq!(
+            yaml_line("  port: 8080 # default", &style),
+            format!("  {key}port{FGRESET}: {number}8080{FGRESET} {DIM_ON}# default{RESET}")
+        );
+        assert_eq!(
+            yaml_line("- name: \"x\"", &style),
+            format!("- {key}name{FGRESET}: {}\"x\"{FGRESET}", fg_color("green"))
+        );
+        assert_eq!(yaml_line("  plain text", &style), "  plain text");
+        assert_eq!(
+            yaml_line("# note", &style),
+            format!("{DIM_ON}# note{RESET}")
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_malformed_json_fence_is_verbatim() {
+        use crate::Renderer;
+        use std::sync::Arc;
+        use streamdown_parser::Parser;
+
+        let mut renderer = Renderer::new(Vec::new(), 40);
+        renderer.register_fence("json", Arc::new(JsonHandler));
+        let mut parser = Parser::new();
+        for line in [
+            "```json",
+            "{\"a\": [1,",
+            "```",
+            "```json",
+            "{\"b\": 2}",
+            "```",
+        ] {
+            renderer.render(&parser.parse_line(line)).unwrap();
+        }
+        let output = visible(&String::from_utf8(renderer.into_writer()).unwrap());
+        assert!(output.contains("{\"a\": [1,"));
+        assert!(output.contains("  \"b\": 2\n"));
+    }
+}
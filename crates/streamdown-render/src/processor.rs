@@ -0,0 +1,79 @@
+//! Block post-processors.
+//!
+//! A [`BlockProcessor`] claims blocks by their first event. The renderer
+//! then holds the block's events until it ends and lets the processor draw
+//! it instead, which suits views that need the whole block (timelines,
+//! charts). A processor that declines a finished block leaves it to be
+//! rendered as usual.
+//!
+//! ```
+//! use std::sync::Arc;
+//! use streamdown_parser::{ParseEvent, Parser};
+//! use streamdown_render::{BlockProcessor, RenderStyle, Renderer};
+//!
+//! /// Draws lists as their item count.
+//! #[derive(Debug)]
+//! struct CountItems;
+//!
+//! impl BlockProcessor for CountItems {
+//!     fn claims(&self, first: &ParseEvent) -> bool {
+//!         matches!(first, ParseEvent::ListItem { .. })
+//!     }
+//!
+//!     fn process(&self, events: &[ParseEvent], _: usize, _: &RenderStyle) -> Option<Vec<String>> {
+//!         let items = events.iter().filter(|e| matches!(e, ParseEvent::ListItem { .. }));
+//!         Some(vec![format!("{} items", items.count())])
+//!     }
+//! }
+//!
+//! let mut renderer = Renderer::new(Vec::new(), 40);
+//! renderer.add_block_processor(Arc::new(CountItems));
+//! let mut parser = Parser::new();
+//! for line in ["- a", "- b", ""] {
+//!     renderer.render(&parser.parse_line(line)).unwrap();
+//! }
+//! let output = String::from_utf8(renderer.into_writer()).unwrap();
+//! assert!(output.contains("2 items"));
+//! ```
+
+use std::fmt;
+
+use streamdown_parser::ParseEvent;
+
+use crate::RenderStyle;
+
+/// Draws whole blocks in place of the renderer.
+pub trait BlockProcessor: fmt::Debug + Send + Sync {
+    /// Whether to hold the block that starts with `first` for this processor.
+    fn claims(&self, first: &ParseEvent) -> bool;
+
+    /// Whether `event` ends the block that started with `first`.
+    fn is_end(&self, first: &ParseEvent, event: &ParseEvent) -> bool {
+        ends_block(first, event)
+    }
+
+    /// Lines drawing the finished block within `width` columns (the margin
+    /// is added by the renderer), or None to render its events as usual.
+    fn process(
+        &self,
+        events: &[ParseEvent],
+        width: usize,
+        style: &RenderStyle,
+    ) -> Option<Vec<String>>;
+}
+
+/// Whether `event` ends the block that `first` starts.
+pub fn ends_block(first: &ParseEvent, event: &ParseEvent) -> bool {
+    matches!(
+        (first, event),
+        (ParseEvent::CodeBlockStart { .. }, ParseEvent::CodeBlockEnd)
+            | (ParseEvent::ThinkBlockStart, ParseEvent::ThinkBlockEnd)
+            | (ParseEvent::ListItem { .. }, ParseEvent::ListEnd)
+            | (ParseEvent::TableHeader(_), ParseEvent::TableEnd)
+            | (
+                ParseEvent::BlockquoteStart { .. },
+                ParseEvent::BlockquoteEnd
+            )
+            | (ParseEvent::DetailsStart { .. }, ParseEvent::DetailsEnd)
+    )
+}
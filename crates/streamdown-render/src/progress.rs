@@ -0,0 +1,201 @@
+//! Inline progress bars.
+//!
+//! [`ProgressHandler`] is an opt-in [`InlineHandler`] drawing
+//! `[####----] 50%` and `progress:0.5` (or `progress:50%`) in text as a
+//! colored bar sized to the content width, for status updates
+//! streamed as markdown.
+//!
+//! ```
+//! use std::sync::Arc;
+//! use streamdown_parser::Parser;
+//! use streamdown_render::{ProgressHandler, Renderer};
+//!
+//! let mut renderer = Renderer::new(Vec::new(), 40);
+//! renderer.register_inline("progress", Arc::new(ProgressHandler));
+//! let mut parser = Parser::new();
+//! renderer.render(&parser.parse_line("Indexing progress:0.5")).unwrap();
+//! let output = String::from_utf8(renderer.into_writer()).unwrap();
+//! assert!(output.contains("█████"));
+//! assert!(output.contains(" 50%"));
+//! ```
+
+use std::ops::Range;
+
+use crate::inline::InlineHandler;
+use crate::{RESET, RenderStyle, fg_color};
+
+/// Keyword of the `progress:<fraction>` form.
+const KEYWORD: &str = "progress:";
+
+/// Partial blocks for the bar end, in eighths.
+const EIGHTHS: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// Bar length in cells for `width` columns of content width.
+pub fn bar_cells(width: usize) -> usize {
+    (width / 4).clamp(8, 30)
+}
+
+/// Draw a `fraction` (clamped to 0-1) as a bar of `cells` columns; filled
+/// cells in `fill`, the rest of the track in `track`.
+///
+/// ```
+/// use streamdown_render::progress::progress_bar;
+///
+/// assert_eq!(progress_bar(0.5, 4, "", ""), "██░░\x1b[0m");
+/// ```
+pub fn progress_bar(fraction: f64, cells: usize, fill: &str, track: &str) -> String {
+    let eighths = (fraction.clamp(0.0, 1.0) * (cells * 8) as f64).round() as usize;
+    let mut bar = String::from(fill);
+    let (full, partial) = (eighths / 8, eighths % 8);
+    bar.push_str(&"█".repeat(full));
+    let mut used = full;
+    if partial != 0 {
+        bar.push(EIGHTHS[partial]);
+        used += 1;
+    }
+    bar.push_str(track);
+    bar.push_str(&"░".repeat(cells - used));
+    bar.push_str(RESET);
+    bar
+}
+
+/// Parse a span: `[####----]` with an optional ` NN%`, or
+/// `progress:<fraction>` / `progress:NN%`. Returns the fraction.
+pub fn parse_progress(span: &str) -> Option<f64> {
+    let fraction = if let Some(value) = span.strip_prefix(KEYWORD) {
+        parse_amount(value)?
+    } else {
+        let (bar, percent) = span.strip_prefix('[')?.split_once(']')?;
+        match percent.trim() {
+            "" => bar.chars().filter(|&c| c == '#').count() as f64 / bar.chars().count() as f64,
+            percent => parse_amount(percent).filter(|_| percent.ends_with('%'))?,
+        }
+    };
+    fraction.is_finite().then(|| fraction.clamp(0.0, 1.0))
+}
+
+/// Parse `NN%` as a percentage or a bare number as a fraction.
+fn parse_amount(value: &str) -> Option<f64> {
+    match value.strip_suffix('%') {
+        Some(percent) => percent.parse::<f64>().ok().map(|p| p / 100.0),
+        None => value.parse().ok(),
+    }
+}
+
+/// Handler drawing inline progress bars.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProgressHandler;
+
+impl ProgressHandler {
+    /// Range of the first `[#…-]` bar (with its percentage, if any).
+    fn find_bar(text: &str) -> Option<Range<usize>> {
+        let mut from = 0;
+        while let Some(offset) = text[from..].find('[') {
+            let start = from + offset;
+            let inner = &text[start + 1..];
+            let len = inner.find(|c| c != '#' && c != '-').unwrap_or(inner.len());
+            if len > 0 && inner[len..].starts_with(']') {
+                let mut end = start + len + 2;
+                if let Some(percent) = percent_len(&text[end..]) {
+                    end += percent;
+                }
+                return Some(start..end);
+            }
+            from = start + 1;
+        }
+        None
+    }
+
+    /// Range of the first `progress:<amount>` starting a word.
+    fn find_keyword(text: &str) -> Option<Range<usize>> {
+        let (start, _) = text.match_indices(KEYWORD).find(|&(start, _)| {
+            !text[..start]
+                .chars()
+                .next_back()
+                .is_some_and(|c| c.is_alphanumeric() || c == '_')
+        })?;
+        let value = &text[start + KEYWORD.len()..];
+        let mut len = value
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(value.len());
+        if value[len..].starts_with('%') {
+            len += 1;
+        }
+        (len > 0).then(|| start..start + KEYWORD.len() + len)
+    }
+}
+
+/// Length of a ` NN%` suffix at the start of `text`.
+fn percent_len(text: &str) -> Option<usize> {
+    let digits = text.strip_prefix(' ')?;
+    let len = digits
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(digits.len());
+    (len > 0 && digits[len..].starts_with('%')).then_some(len + 2)
+}
+
+impl InlineHandler for ProgressHandler {
+    fn find(&self, text: &str) -> Option<Range<usize>> {
+        match (Self::find_bar(text), Self::find_keyword(text)) {
+            (Some(bar), Some(keyword)) if keyword.start < bar.start => Some(keyword),
+            (Some(bar), _) => Some(bar),
+            (None, keyword) => keyword,
+        }
+    }
+
+    fn render(&self, span: &str, width: usize, style: &RenderStyle) -> Option<String> {
+        let fraction = parse_progress(span)?;
+        Some(format!(
+            "{} {:.0}%",
+            progress_bar(
+                fraction,
+                bar_cells(width),
+                &fg_color(&style.task_checked),
+                &fg_color(&style.task_unchecked),
+            ),
+            fraction * 100.0
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_spans() {
+        let find = |text| ProgressHandler.find(text).map(|r| &text[r]);
+        assert_eq!(find("done [####----] 50% now"), Some("[####----] 50%"));
+        assert_eq!(find("[##--]!"), Some("[##--]"));
+        assert_eq!(find("see [a] and [#-]"), Some("[#-]"));
+        assert_eq!(find("at progress:0.25, then"), Some("progress:0.25"));
+        assert_eq!(find("progress:75% [#-]"), Some("progress:75%"));
+        assert_eq!(find("[] progress: x"), None);
+        assert_eq!(find("myprogress:1"), None);
+        assert_eq!(find("my_progress:1 (progress:1)"), Some("progress:1"));
+    }
+
+    #[test]
+    fn test_parse_progress() {
+        assert_eq!(parse_progress("[####----] 50%"), Some(0.5));
+        assert_eq!(parse_progress("[###-]"), Some(0.75));
+        assert_eq!(parse_progress("progress:0.2"), Some(0.2));
+        assert_eq!(parse_progress("progress:40%"), Some(0.4));
+        assert_eq!(parse_progress("progress:7"), Some(1.0));
+    }
+
+    #[test]
+    fn test_render_bar() {
+        let style = RenderStyle::default();
+        let drawn = ProgressHandler.render("[#---] 25%", 40, &style).unwrap();
+        let plain = streamdown_ansi::utils::visible(&drawn);
+        assert_eq!(plain, "██▌░░░░░░░ 25%");
+        assert_eq!(
+            progress_bar(0.55, 10, "", "")
+                .chars()
+                .filter(|&c| c == '▌')
+                .count(),
+            1
+        );
+    }
+}
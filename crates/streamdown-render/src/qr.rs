@@ -0,0 +1,538 @@
+//! QR codes drawn with half blocks.
+//!
+//! [`QrCode::encode`] builds a byte-mode QR code at error correction level
+//! M, picking the smallest version (1-40) that fits. [`QrCode::lines`] draws
+//! it two module rows per text row, with light modules as the foreground,
+//! so it scans as-is on dark terminals; hosts that can set colors wrap the
+//! rows in [`QR_COLORS`] for light ones. [`QrHandler`] draws ```` ```qr ````
+//! fences, and is one of the built-in [`FenceHandlers`].
+//!
+//! ```
+//! use streamdown_render::QrCode;
+//!
+//! let qr = QrCode::encode(b"https://example.com").unwrap();
+//! assert_eq!(qr.size(), 25);
+//! let lines = qr.lines();
+//! assert_eq!(lines.len(), 17);
+//! assert!(lines.iter().all(|line| line.chars().count() == 33));
+//! ```
+//!
+//! [`FenceHandlers`]: crate::FenceHandlers
+
+use crate::fence::FenceHandler;
+use crate::{RESET, RenderStyle, fg_color};
+
+/// SGR for the QR code rows: white modules on black.
+pub const QR_COLORS: &str = "\x1b[97;40m";
+
+/// Light modules drawn around the code, the margin the QR spec requires.
+pub const QUIET_ZONE: usize = 4;
+
+/// Lines drawing `payload` as a QR code within `width` columns, or the
+/// payload as text if it is too long or the code would not fit.
+pub(crate) fn payload_lines(payload: &str, width: usize, style: &RenderStyle) -> Vec<String> {
+    if payload.is_empty() {
+        return Vec::new();
+    }
+    let lines = QrCode::encode(payload.as_bytes())
+        .map(|qr| qr.lines())
+        .filter(|lines| lines.first().is_some_and(|l| l.chars().count() <= width));
+    match lines {
+        Some(lines) => lines
+            .into_iter()
+            .map(|line| format!("{}{}{}", QR_COLORS, line, RESET))
+            .collect(),
+        None => vec![format!("{}{}{}", fg_color(&style.link_url), payload, RESET)],
+    }
+}
+
+/// Fence handler drawing the text of a ```` ```qr ```` block as a QR code.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QrHandler;
+
+impl FenceHandler for QrHandler {
+    fn render(&self, text: &str, width: usize, style: &RenderStyle) -> Option<Vec<String>> {
+        Some(payload_lines(text.trim(), width, style))
+    }
+}
+
+/// Error correction codewords per block at level M, by version.
+const ECC_CODEWORDS_PER_BLOCK: [usize; 41] = [
+    0, 10, 16, 26, 18, 24, 16, 18, 22, 22, 26, 30, 22, 22, 24, 24, 28, 28, 26, 26, 26, 26, 28, 28,
+    28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28,
+];
+
+/// Error correction blocks at level M, by version.
+const NUM_ERROR_CORRECTION_BLOCKS: [usize; 41] = [
+    0, 1, 1, 1, 2, 2, 4, 4, 4, 5, 5, 5, 8, 9, 9, 10, 10, 11, 13, 14, 16, 17, 17, 18, 20, 21, 23,
+    25, 26, 28, 29, 31, 33, 35, 37, 38, 40, 43, 45, 47, 49,
+];
+
+/// Format bits for level M.
+const ECC_FORMAT_BITS: u32 = 0;
+
+/// A QR code symbol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QrCode {
+    version: usize,
+    size: usize,
+    /// Dark modules, row-major
+    modules: Vec<bool>,
+    /// Modules of function patterns, which masks leave alone
+    is_function: Vec<bool>,
+}
+
+impl QrCode {
+    /// Encode `data` in byte mode, or None if it is too long for version 40.
+    pub fn encode(data: &[u8]) -> Option<Self> {
+        let version = (1..=40).find(|&v| {
+            let count_bits = if v <= 9 { 8 } else { 16 };
+            data.len() < 1 << count_bits
+                && 4 + count_bits + data.len() * 8 <= num_data_codewords(v) * 8
+        })?;
+
+        // Mode, length, data, terminator, then pad to the capacity
+        let capacity = num_data_codewords(version) * 8;
+        let mut bits = BitBuffer::default();
+        bits.push(0b0100, 4);
+        bits.push(data.len() as u32, if version <= 9 { 8 } else { 16 });
+        for &byte in data {
+            bits.push(u32::from(byte), 8);
+        }
+        bits.push(0, (capacity - bits.0.len()).min(4));
+        bits.push(0, (8 - bits.0.len() % 8) % 8);
+        for pad in [0xEC, 0x11].into_iter().cycle() {
+            if bits.0.len() >= capacity {
+                break;
+            }
+            bits.push(pad, 8);
+        }
+        let codewords: Vec<u8> = bits
+            .0
+            .chunks(8)
+            .map(|byte| byte.iter().fold(0, |acc, &bit| acc << 1 | u8::from(bit)))
+            .collect();
+
+        let size = version * 4 + 17;
+        let mut qr = Self {
+            version,
+            size,
+            modules: vec![false; size * size],
+            is_function: vec![false; size * size],
+        };
+        qr.draw_function_patterns();
+        qr.draw_codewords(&add_ecc_and_interleave(&codewords, version));
+
+        // Keep the mask that scores best
+        let mut best: Option<(u32, u8)> = None;
+        for mask in 0..8 {
+            qr.apply_mask(mask);
+            qr.draw_format_bits(mask);
+            let penalty = qr.penalty_score();
+            if best.is_none_or(|(score, _)| penalty < score) {
+                best = Some((penalty, mask));
+            }
+            qr.apply_mask(mask);
+        }
+        let (_, mask) = best?;
+        qr.apply_mask(mask);
+        qr.draw_format_bits(mask);
+        Some(qr)
+    }
+
+    /// Symbol version (1-40).
+    pub fn version(&self) -> usize {
+        self.version
+    }
+
+    /// Width and height in modules.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Whether the module at column `x`, row `y` is dark (false outside).
+    pub fn module(&self, x: usize, y: usize) -> bool {
+        x < self.size && y < self.size && self.modules[y * self.size + x]
+    }
+
+    /// Text rows of the code with its quiet zone, light modules drawn.
+    pub fn lines(&self) -> Vec<String> {
+        let span = self.size + 2 * QUIET_ZONE;
+        let light = |x: usize, y: usize| {
+            !(x >= QUIET_ZONE && y >= QUIET_ZONE && self.module(x - QUIET_ZONE, y - QUIET_ZONE))
+        };
+        (0..span)
+            .step_by(2)
+            .map(|y| {
+                (0..span)
+                    .map(|x| match (light(x, y), light(x, y + 1)) {
+                        (true, true) => '█',
+                        (true, false) => '▀',
+                        (false, true) => '▄',
+                        (false, false) => ' ',
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn set_function(&mut self, x: usize, y: usize, dark: bool) {
+        self.modules[y * self.size + x] = dark;
+        self.is_function[y * self.size + x] = true;
+    }
+
+    fn draw_function_patterns(&mut self) {
+        let size = self.size;
+        for i in 0..size {
+            self.set_function(6, i, i % 2 == 0);
+            self.set_function(i, 6, i % 2 == 0);
+        }
+
+        for (x, y) in [(3, 3), (size - 4, 3), (3, size - 4)] {
+            self.draw_finder_pattern(x, y);
+        }
+
+        let positions = alignment_pattern_positions(self.version);
+        let last = positions.len().saturating_sub(1);
+        for (i, &x) in positions.iter().enumerate() {
+            for (j, &y) in positions.iter().enumerate() {
+                // Corners taken by finder patterns
+                if (i == 0 && (j == 0 || j == last)) || (j == 0 && i == last) {
+                    continue;
+                }
+                for dy in -2i32..=2 {
+                    for dx in -2i32..=2 {
+                        let dark = dx.abs().max(dy.abs()) != 1;
+                        self.set_function((x as i32 + dx) as usize, (y as i32 + dy) as usize, dark);
+                    }
+                }
+            }
+        }
+
+        // Reserve the format areas; the mask loop fills them
+        self.draw_format_bits(0);
+        self.draw_version();
+    }
+
+    fn draw_finder_pattern(&mut self, x: usize, y: usize) {
+        for dy in -4i32..=4 {
+            for dx in -4i32..=4 {
+                let (xx, yy) = (x as i32 + dx, y as i32 + dy);
+                if (0..self.size as i32).contains(&xx) && (0..self.size as i32).contains(&yy) {
+                    let distance = dx.abs().max(dy.abs());
+                    self.set_function(xx as usize, yy as usize, distance != 2 && distance != 4);
+                }
+            }
+        }
+    }
+
+    fn draw_format_bits(&mut self, mask: u8) {
+        let data = ECC_FORMAT_BITS << 3 | u32::from(mask);
+        let mut rem = data;
+        for _ in 0..10 {
+            rem = (rem << 1) ^ ((rem >> 9) * 0x537);
+        }
+        let bits = (data << 10 | rem) ^ 0x5412;
+        let bit = |i: usize| (bits >> i) & 1 != 0;
+
+        // Around the top left finder
+        for i in 0..=5 {
+            self.set_function(8, i, bit(i));
+        }
+        self.set_function(8, 7, bit(6));
+        self.set_function(8, 8, bit(7));
+        self.set_function(7, 8, bit(8));
+        for i in 9..15 {
+            self.set_function(14 - i, 8, bit(i));
+        }
+
+        // Split between the other two
+        let size = self.size;
+        for i in 0..8 {
+            self.set_function(size - 1 - i, 8, bit(i));
+        }
+        for i in 8..15 {
+            self.set_function(8, size - 15 + i, bit(i));
+        }
+        self.set_function(8, size - 8, true);
+    }
+
+    fn draw_version(&mut self) {
+        if self.version < 7 {
+            return;
+        }
+        let version = self.version as u32;
+        let mut rem = version;
+        for _ in 0..12 {
+            rem = (rem << 1) ^ ((rem >> 11) * 0x1F25);
+        }
+        let bits = version << 12 | rem;
+        for i in 0..18 {
+            let dark = (bits >> i) & 1 != 0;
+            let (a, b) = (self.size - 11 + i % 3, i / 3);
+            self.set_function(a, b, dark);
+            self.set_function(b, a, dark);
+        }
+    }
+
+    /// Place codewords in the zigzag order, two columns at a time.
+    fn draw_codewords(&mut self, data: &[u8]) {
+        let size = self.size;
+        let mut i = 0;
+        let mut right = size - 1;
+        while right >= 1 {
+            if right == 6 {
+                right = 5;
+            }
+            let upward = (right + 1) & 2 == 0;
+            for vert in 0..size {
+                for x in [right, right - 1] {
+                    let y = if upward { size - 1 - vert } else { vert };
+                    if !self.is_function[y * size + x] && i < data.len() * 8 {
+                        self.modules[y * size + x] = (data[i >> 3] >> (7 - (i & 7))) & 1 != 0;
+                        i += 1;
+                    }
+                }
+            }
+            if right < 2 {
+                break;
+            }
+            right -= 2;
+        }
+    }
+
+    /// XOR the data modules with `mask`; applying it twice undoes it.
+    fn apply_mask(&mut self, mask: u8) {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let invert = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                let index = y * self.size + x;
+                self.modules[index] ^= invert && !self.is_function[index];
+            }
+        }
+    }
+
+    /// Penalty of the current modules by the four rules of the standard.
+    fn penalty_score(&self) -> u32 {
+        let size = self.size;
+        let mut score = 0;
+        let row = |y: usize| -> Vec<bool> { (0..size).map(|x| self.module(x, y)).collect() };
+        let column = |x: usize| -> Vec<bool> { (0..size).map(|y| self.module(x, y)).collect() };
+        let finder = [
+            true, false, true, true, true, false, true, false, false, false, false,
+        ];
+
+        for line in (0..size).map(row).chain((0..size).map(column)) {
+            // Runs of five or more
+            let mut run = 1;
+            for i in 1..=size {
+                if i < size && line[i] == line[i - 1] {
+                    run += 1;
+                } else {
+                    if run >= 5 {
+                        score += run - 2;
+                    }
+                    run = 1;
+                }
+            }
+            // Finder-like patterns
+            for window in line.windows(finder.len()) {
+                if window == finder || window.iter().eq(finder.iter().rev()) {
+                    score += 40;
+                }
+            }
+        }
+
+        // 2x2 blocks of one color
+        for y in 1..size {
+            for x in 1..size {
+                let dark = self.module(x, y);
+                if self.module(x - 1, y) == dark
+                    && self.module(x, y - 1) == dark
+                    && self.module(x - 1, y - 1) == dark
+                {
+                    score += 3;
+                }
+            }
+        }
+
+        // Balance of dark and light
+        let dark = self.modules.iter().filter(|&&dark| dark).count();
+        let percent = dark * 100 / self.modules.len();
+        score + (percent.abs_diff(50) / 5 * 10) as u32
+    }
+}
+
+/// Bits being collected for the data codewords.
+#[derive(Default)]
+struct BitBuffer(Vec<bool>);
+
+impl BitBuffer {
+    fn push(&mut self, value: u32, len: usize) {
+        self.0.extend((0..len).rev().map(|i| (value >> i) & 1 != 0));
+    }
+}
+
+/// Centers of the alignment patterns along each axis.
+fn alignment_pattern_positions(version: usize) -> Vec<usize> {
+    if version == 1 {
+        return Vec::new();
+    }
+    let count = version / 7 + 2;
+    let step = if version == 32 {
+        26
+    } else {
+        (version * 4 + count * 2 + 1) / (count * 2 - 2) * 2
+    };
+    let mut positions = vec![6];
+    let mut position = version * 4 + 17 - 7;
+    for _ in 0..count - 1 {
+        positions.insert(1, position);
+        position -= step;
+    }
+    positions
+}
+
+/// Modules available for data and error correction.
+fn num_raw_data_modules(version: usize) -> usize {
+    let mut result = (16 * version + 128) * version + 64;
+    if version >= 2 {
+        let count = version / 7 + 2;
+        result -= (25 * count - 10) * count - 55;
+        if version >= 7 {
+            result -= 36;
+        }
+    }
+    result
+}
+
+/// Data codewords at level M.
+fn num_data_codewords(version: usize) -> usize {
+    num_raw_data_modules(version) / 8
+        - ECC_CODEWORDS_PER_BLOCK[version] * NUM_ERROR_CORRECTION_BLOCKS[version]
+}
+
+/// Split `data` into blocks, append each block's error correction, and
+/// interleave them.
+fn add_ecc_and_interleave(data: &[u8], version: usize) -> Vec<u8> {
+    let num_blocks = NUM_ERROR_CORRECTION_BLOCKS[version];
+    let ecc_len = ECC_CODEWORDS_PER_BLOCK[version];
+    let raw_codewords = num_raw_data_modules(version) / 8;
+    let num_short_blocks = num_blocks - raw_codewords % num_blocks;
+    let short_block_len = raw_codewords / num_blocks;
+
+    let divisor = reed_solomon_divisor(ecc_len);
+    let mut blocks = Vec::with_capacity(num_blocks);
+    let mut start = 0;
+    for i in 0..num_blocks {
+        let len = short_block_len - ecc_len + usize::from(i >= num_short_blocks);
+        let block_data = &data[start..start + len];
+        start += len;
+        let mut block = block_data.to_vec();
+        // Short blocks get a placeholder so columns line up
+        if i < num_short_blocks {
+            block.push(0);
+        }
+        block.extend(reed_solomon_remainder(block_data, &divisor));
+        blocks.push(block);
+    }
+
+    let mut result = Vec::with_capacity(raw_codewords);
+    for i in 0..=short_block_len {
+        for (j, block) in blocks.iter().enumerate() {
+            if i != short_block_len - ecc_len || j >= num_short_blocks {
+                result.push(block[i]);
+            }
+        }
+    }
+    result
+}
+
+/// Generator polynomial of `degree`, highest coefficient dropped.
+fn reed_solomon_divisor(degree: usize) -> Vec<u8> {
+    let mut result = vec![0u8; degree];
+    result[degree - 1] = 1;
+    let mut root = 1u8;
+    for _ in 0..degree {
+        for j in 0..degree {
+            result[j] = gf_multiply(result[j], root);
+            if j + 1 < degree {
+                result[j] ^= result[j + 1];
+            }
+        }
+        root = gf_multiply(root, 0x02);
+    }
+    result
+}
+
+/// Error correction codewords for `data`.
+fn reed_solomon_remainder(data: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut result = vec![0u8; divisor.len()];
+    for &byte in data {
+        let factor = byte ^ result.remove(0);
+        result.push(0);
+        for (x, &y) in result.iter_mut().zip(divisor) {
+            *x ^= gf_multiply(y, factor);
+        }
+    }
+    result
+}
+
+/// Multiply in GF(2^8) modulo x^8 + x^4 + x^3 + x^2 + 1.
+fn gf_multiply(x: u8, y: u8) -> u8 {
+    let mut z: u32 = 0;
+    for i in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x11D);
+        z ^= ((u32::from(y) >> i) & 1) * u32::from(x);
+    }
+    z as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reed_solomon() {
+        // "HELLO WORLD" at 1-M
+        let data = [
+            32, 91, 11, 120, 209, 114, 220, 77, 67, 64, 236, 17, 236, 17, 236, 17,
+        ];
+        let ecc = reed_solomon_remainder(&data, &reed_solomon_divisor(10));
+        assert_eq!(ecc, [196, 35, 39, 119, 235, 215, 231, 226, 93, 23]);
+    }
+
+    #[test]
+    fn test_structure() {
+        let qr = QrCode::encode(&[b'x'; 100]).unwrap();
+        assert_eq!(qr.version(), 6);
+        assert_eq!(alignment_pattern_positions(qr.version()), [6, 34]);
+        // Finder corners, alignment center, timing, dark module
+        assert!(qr.module(0, 0) && qr.module(qr.size() - 1, 0) && qr.module(0, qr.size() - 1));
+        assert!(qr.module(34, 34) && !qr.module(33, 34));
+        assert!(qr.module(8, 6) && !qr.module(9, 6));
+        assert!(qr.module(8, qr.size() - 8));
+        assert_eq!(num_data_codewords(40), 2334);
+        assert!(QrCode::encode(&[0; 2331]).is_some());
+        assert!(QrCode::encode(&[0; 2332]).is_none());
+    }
+
+    #[test]
+    fn test_format_bits() {
+        let mut qr = QrCode::encode(b"a").unwrap();
+        qr.draw_format_bits(0);
+        // M with mask 0 is 101010000010010
+        let bits: Vec<bool> = (0..=5).map(|y| qr.module(8, y)).collect();
+        assert_eq!(bits, [false, true, false, false, true, false]);
+        assert!(qr.module(0, 8));
+    }
+}
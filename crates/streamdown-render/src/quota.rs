@@ -0,0 +1,132 @@
+//! Quota-based output truncation.
+//!
+//! A [`RenderQuota`] caps how much a renderer draws, for previews of long
+//! documents in small panes. Once the quota is used up the renderer holds
+//! back the rest of the events instead of drawing them; the host can then
+//! show a trailer ([`Renderer::render_truncation_trailer`]) or draw the rest
+//! later ([`Renderer::resume`]). A code block cut off by the quota gets its
+//! closing frame, and at most [`MAX_HELD_EVENTS`] events are held: later ones
+//! are still counted for the trailer but not drawn on resuming.
+//!
+//! ```
+//! use streamdown_parser::Parser;
+//! use streamdown_render::{RenderQuota, Renderer};
+//!
+//! let mut renderer = Renderer::new(Vec::new(), 60);
+//! renderer.set_quota(Some(RenderQuota::blocks(1)));
+//! let mut parser = Parser::new();
+//! for line in ["# One", "# Two", "# Three"] {
+//!     for event in parser.parse_line(line) {
+//!         renderer.render_event(&event).unwrap();
+//!     }
+//! }
+//! assert_eq!(renderer.remaining_blocks(), 2);
+//! renderer.render_truncation_trailer().unwrap();
+//! ```
+//!
+//! [`Renderer::render_truncation_trailer`]: crate::Renderer::render_truncation_trailer
+//! [`Renderer::resume`]: crate::Renderer::resume
+
+use crate::navigation::{BlockId, LineMeta};
+use streamdown_parser::ParseEvent;
+
+/// Most events held back after the quota runs out.
+pub const MAX_HELD_EVENTS: usize = 65_536;
+
+/// Limits on rendered output.
+///
+/// Rendering stops at the first event after either limit is reached; a
+/// block limit stops at the start of the next block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RenderQuota {
+    /// Maximum number of output lines
+    pub max_lines: Option<usize>,
+    /// Maximum number of blocks
+    pub max_blocks: Option<usize>,
+}
+
+impl RenderQuota {
+    /// Quota of `n` output lines.
+    pub fn lines(n: usize) -> Self {
+        Self {
+            max_lines: Some(n),
+            max_blocks: None,
+        }
+    }
+
+    /// Quota of `n` blocks.
+    pub fn blocks(n: usize) -> Self {
+        Self {
+            max_lines: None,
+            max_blocks: Some(n),
+        }
+    }
+
+    /// Whether an event may not be rendered after `lines` lines and `blocks`
+    /// blocks (including the one the event belongs to).
+    pub(crate) fn exceeded(&self, lines: usize, blocks: usize) -> bool {
+        self.max_lines.is_some_and(|max| lines >= max)
+            || self.max_blocks.is_some_and(|max| blocks > max)
+    }
+}
+
+/// Block tracking state, saved so held events can be replayed.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct BlockSnapshot {
+    pub(crate) current_block: LineMeta,
+    pub(crate) block_ended: bool,
+    pub(crate) blocks_started: usize,
+    /// Number of block IDs and the ID of the current block
+    pub(crate) block_ids: (usize, Option<BlockId>),
+}
+
+/// Events held back after the quota ran out.
+#[derive(Debug, Clone)]
+pub(crate) struct Truncation {
+    /// Held events, in order
+    pub(crate) events: Vec<ParseEvent>,
+    /// Block tracking state before the first held event
+    pub(crate) snapshot: BlockSnapshot,
+    /// Whether the first held event continued an already rendered block
+    pub(crate) mid_block: bool,
+    /// Whether a code block's frame was closed when output stopped
+    pub(crate) closed_code: bool,
+}
+
+impl Truncation {
+    /// Hold `event`, unless the buffer is full.
+    pub(crate) fn hold(&mut self, event: &ParseEvent) {
+        if self.events.len() < MAX_HELD_EVENTS {
+            self.events.push(event.clone());
+        }
+    }
+
+    /// Number of blocks not rendered, given the blocks started so far.
+    pub(crate) fn remaining_blocks(&self, blocks_started: usize) -> usize {
+        let started = blocks_started - self.snapshot.blocks_started;
+        let partial = self.mid_block
+            && self
+                .events
+                .iter()
+                .any(|e| !matches!(e, ParseEvent::EmptyLine | ParseEvent::Newline));
+        started + usize::from(partial)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quota_exceeded() {
+        let quota = RenderQuota::lines(10);
+        assert!(!quota.exceeded(9, 100));
+        assert!(quota.exceeded(10, 1));
+
+        let quota = RenderQuota::blocks(2);
+        assert!(!quota.exceeded(1000, 2));
+        assert!(quota.exceeded(0, 3));
+
+        assert!(!RenderQuota::default().exceeded(usize::MAX, usize::MAX));
+    }
+}
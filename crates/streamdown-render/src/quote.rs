@@ -0,0 +1,116 @@
+//! Muting quoted history.
+//!
+//! Chat replies often quote earlier messages. With a [`QuoteMatcher`] set
+//! ([`Renderer::set_quote_matcher`]), blockquote lines the matcher recognizes
+//! as earlier transcript text are drawn muted (in
+//! [`RenderStyle::quoted_attrs`], dim italic by default), so the new part of
+//! the reply stands out. [`TranscriptMatcher`] is the built-in matcher: the
+//! host feeds it each message as it is shown.
+//!
+//! ```
+//! use streamdown_render::{QuoteMatcher, TranscriptMatcher};
+//!
+//! let mut transcript = TranscriptMatcher::new();
+//! transcript.push_message("Could you   check the *build* logs?");
+//! assert!(transcript.is_quoted("could you check the *build* logs?"));
+//! assert!(!transcript.is_quoted("check the"));
+//! assert!(!transcript.is_quoted("I checked them."));
+//! ```
+//!
+//! [`Renderer::set_quote_matcher`]: crate::Renderer::set_quote_matcher
+//! [`RenderStyle::quoted_attrs`]: crate::RenderStyle::quoted_attrs
+
+use std::collections::HashSet;
+use std::fmt;
+
+/// Recognizes quoted lines that repeat earlier transcript text.
+pub trait QuoteMatcher: fmt::Debug + Send + Sync {
+    /// Whether a blockquote line (markdown, without the `>`) quotes history.
+    fn is_quoted(&self, line: &str) -> bool;
+}
+
+/// Matches quotes against the messages of a transcript.
+///
+/// A quoted line matches if it equals a whole line of an earlier message,
+/// ignoring case, runs of whitespace and leading `>` quote markers; a part
+/// of a line never matches, so short phrases don't mute unrelated quotes.
+#[derive(Debug, Clone, Default)]
+pub struct TranscriptMatcher {
+    /// Number of messages pushed
+    messages: usize,
+    /// Normalized lines of the earlier messages
+    lines: HashSet<String>,
+}
+
+impl TranscriptMatcher {
+    /// Create a matcher with no history.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a message to the history.
+    pub fn push_message(&mut self, text: &str) {
+        self.messages += 1;
+        self.lines
+            .extend(text.lines().map(normalize).filter(|line| !line.is_empty()));
+    }
+
+    /// Number of messages in the history.
+    pub fn len(&self) -> usize {
+        self.messages
+    }
+
+    /// Whether the history is empty.
+    pub fn is_empty(&self) -> bool {
+        self.messages == 0
+    }
+
+    /// Forget the history.
+    pub fn clear(&mut self) {
+        self.messages = 0;
+        self.lines.clear();
+    }
+}
+
+impl QuoteMatcher for TranscriptMatcher {
+    fn is_quoted(&self, line: &str) -> bool {
+        self.lines.contains(&normalize(line))
+    }
+}
+
+/// Strip leading `>` markers, lowercase and collapse whitespace to single
+/// spaces.
+fn normalize(text: &str) -> String {
+    text.trim_start_matches(|c: char| c == '>' || c.is_whitespace())
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_whole_lines() {
+        let mut transcript = TranscriptMatcher::new();
+        transcript.push_message("First line\nsecond line\n> an older quote");
+        assert!(transcript.is_quoted("  Second   Line "));
+        assert!(transcript.is_quoted("an older quote"));
+        // Parts of lines, or lines run together, are new text
+        assert!(!transcript.is_quoted("line"));
+        assert!(!transcript.is_quoted("first line second line"));
+        assert_eq!(transcript.len(), 1);
+    }
+
+    #[test]
+    fn test_blank_lines_never_match() {
+        let mut transcript = TranscriptMatcher::new();
+        transcript.push_message("anything");
+        assert!(!transcript.is_quoted("   "));
+        transcript.clear();
+        assert!(transcript.is_empty());
+        assert!(!transcript.is_quoted("anything"));
+    }
+}
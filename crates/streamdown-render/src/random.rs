@@ -0,0 +1,112 @@
+//! Seeded theme generation.
+//!
+//! [`RenderStyle::from_seed`] derives a full style from a seed by rotating
+//! the hue of the config palette around a base, so the same seed always gives
+//! the same theme. Consecutive seeds are spread around the color wheel by the
+//! golden ratio, which keeps simultaneously streamed agents easy to tell apart:
+//!
+//! ```
+//! use streamdown_render::RenderStyle;
+//!
+//! let agents: Vec<RenderStyle> = (0..3).map(|i| RenderStyle::from_seed(i, 0.6)).collect();
+//! assert_ne!(agents[0].h2, agents[1].h2);
+//! assert_eq!(agents[2].h2, RenderStyle::from_seed(2, 0.6).h2);
+//! ```
+
+use streamdown_config::{ComputedStyle, StyleConfig};
+
+use crate::RenderStyle;
+
+/// Fractional part of the golden ratio in 64-bit fixed point; successive
+/// multiples (wrapping) are evenly spread over the whole `u64` range.
+const GOLDEN_STEP: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Largest saturation/value shift applied on top of the base.
+const MAX_JITTER: f64 = 0.1;
+
+impl RenderStyle {
+    /// Generate a coherent style from a seed.
+    ///
+    /// `base_hue` is in `0.0..1.0` (like the config `HSV` hue). The palette's
+    /// hue is rotated away from it by the seed; saturation and value get a
+    /// small seeded jitter. Structural colors (Colodore defaults for unstyled
+    /// slots) are unaffected.
+    pub fn from_seed(seed: u64, base_hue: f64) -> Self {
+        let defaults = StyleConfig::default();
+        let mut rng = SplitMix64(seed);
+
+        let hue = (base_hue + unit(seed.wrapping_mul(GOLDEN_STEP))).rem_euclid(1.0);
+        let saturation = (defaults.hsv[1] + rng.jitter()).clamp(0.0, 1.0);
+        let value = (defaults.hsv[2] + rng.jitter()).clamp(0.0, 1.0);
+
+        let config = StyleConfig {
+            hsv: [hue, saturation, value],
+            ..defaults
+        };
+        Self::from_computed(&ComputedStyle::from_config(&config))
+    }
+}
+
+/// Map the top 53 bits of `bits` to a uniform value in `0.0..1.0`.
+fn unit(bits: u64) -> f64 {
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Small deterministic PRNG (SplitMix64); good enough for color jitter.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(GOLDEN_STEP);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in `-MAX_JITTER..MAX_JITTER`.
+    fn jitter(&mut self) -> f64 {
+        (unit(self.next_u64()) * 2.0 - 1.0) * MAX_JITTER
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seed_is_deterministic() {
+        let a = RenderStyle::from_seed(42, 0.3);
+        let b = RenderStyle::from_seed(42, 0.3);
+        assert_eq!(a.colors(), b.colors());
+        assert!(a.validate().is_ok());
+    }
+
+    #[test]
+    fn test_seeds_differ() {
+        let styles: Vec<_> = (0..4).map(|i| RenderStyle::from_seed(i, 0.0)).collect();
+        for (i, a) in styles.iter().enumerate() {
+            for b in &styles[i + 1..] {
+                assert_ne!(a.h2, b.h2);
+            }
+        }
+    }
+
+    #[test]
+    fn test_large_seeds_differ() {
+        // Past 2^53 neighbouring seeds are indistinguishable as `f64`
+        let base = 1u64 << 60;
+        let a = RenderStyle::from_seed(base, 0.0);
+        let b = RenderStyle::from_seed(base + 1, 0.0);
+        assert_ne!(a.h2, b.h2);
+    }
+
+    #[test]
+    fn test_jitter_in_range() {
+        let mut rng = SplitMix64(7);
+        for _ in 0..1000 {
+            let j = rng.jitter();
+            assert!((-MAX_JITTER..MAX_JITTER).contains(&j));
+        }
+    }
+}
@@ -0,0 +1,349 @@
+//! Man page (roff) backend.
+//!
+//! [`RoffBackend`] writes the event stream as roff using the `man` macros,
+//! so markdown docs can be installed as man pages. Level 1 headings become
+//! sections (`.SH`), level 2 subsections (`.SS`); code blocks are set
+//! unfilled and tables go through `tbl`.
+//!
+//! ```
+//! use streamdown_render::{RoffBackend, render_str};
+//!
+//! let mut man = RoffBackend::new(Vec::new(), "sd", "1");
+//! render_str(&mut man, "# Name\n\nsd - render **markdown**\n").unwrap();
+//! let output = String::from_utf8(man.into_writer()).unwrap();
+//! assert_eq!(output, ".TH \"SD\" \"1\"\n.SH \"NAME\"\n.PP\nsd - render \\fBmarkdown\\fR\n");
+//! ```
+
+use std::io::{self, Write};
+
+use streamdown_parser::{InlineElement, InlineParser, ListBullet};
+
+use crate::backend::RenderBackend;
+
+/// Escape text for roff: backslashes, and control characters at the start
+/// of a line.
+pub fn roff_escape(text: &str) -> String {
+    guard_line(text.replace('\\', "\\e"))
+}
+
+/// Keep an escaped line from being read as a request.
+fn guard_line(line: String) -> String {
+    if line.starts_with(['.', '\'']) {
+        format!("\\&{}", line)
+    } else {
+        line
+    }
+}
+
+/// Roff for an inline element.
+pub fn inline_roff(element: &InlineElement) -> String {
+    let font = |font: &str, text: &str| format!("\\f{}{}\\fR", font, text.replace('\\', "\\e"));
+    match element {
+        InlineElement::Text(text) | InlineElement::Spoiler(text) => text.replace('\\', "\\e"),
+        InlineElement::Bold(text) | InlineElement::Code(text) | InlineElement::Kbd(text) => {
+            font("B", text)
+        }
+        InlineElement::Italic(text) | InlineElement::Underline(text) => font("I", text),
+        InlineElement::BoldItalic(text) => font("(BI", text),
+        InlineElement::Strikeout(text) => text.replace('\\', "\\e"),
+        InlineElement::Footnote(text) => format!("[{}]", text.replace('\\', "\\e")),
+        InlineElement::Link { text, url } if text == url => font("I", url),
+        InlineElement::Link { text, url } => {
+            format!("{} <{}>", text.replace('\\', "\\e"), font("I", url))
+        }
+        InlineElement::Image { alt, .. } => format!("[{}]", alt.replace('\\', "\\e")),
+    }
+}
+
+/// Quote a macro argument.
+fn quoted(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\e").replace('"', "\\(dq"))
+}
+
+/// Renders parse events as a man page.
+#[derive(Debug)]
+pub struct RoffBackend<W: Write> {
+    writer: W,
+    /// `.TH` line, written before the first output
+    header: Option<String>,
+    /// Whether a filled paragraph is open
+    in_paragraph: bool,
+    /// Whether the current output line has text
+    mid_line: bool,
+    /// Indents of the open lists, outermost first
+    list_indents: Vec<usize>,
+    /// Parser for markdown content of block events
+    inline: InlineParser,
+}
+
+impl<W: Write> RoffBackend<W> {
+    /// Create a backend for the man page `title` in `section` (e.g. "1").
+    pub fn new(writer: W, title: &str, section: &str) -> Self {
+        Self {
+            writer,
+            header: Some(format!(
+                ".TH {} {}",
+                quoted(&title.to_uppercase()),
+                quoted(section)
+            )),
+            in_paragraph: false,
+            mid_line: false,
+            list_indents: Vec::new(),
+            inline: InlineParser::new(),
+        }
+    }
+
+    /// Get a mutable reference to the output writer.
+    pub fn writer_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Consume the backend, returning the output writer.
+    pub fn into_writer(self) -> W {
+        self.writer
+    }
+
+    /// Write a request line, ending any text line first.
+    fn request(&mut self, line: &str) -> io::Result<()> {
+        if let Some(header) = self.header.take() {
+            writeln!(self.writer, "{}", header)?;
+        }
+        if self.mid_line {
+            writeln!(self.writer)?;
+            self.mid_line = false;
+        }
+        writeln!(self.writer, "{}", line)
+    }
+
+    /// Write text, escaping a control character at the start of a line.
+    fn text(&mut self, text: &str) -> io::Result<()> {
+        if text.is_empty() {
+            return Ok(());
+        }
+        if let Some(header) = self.header.take() {
+            writeln!(self.writer, "{}", header)?;
+        }
+        if !self.mid_line && text.starts_with(['.', '\'']) {
+            self.writer.write_all(b"\\&")?;
+        }
+        self.mid_line = true;
+        self.writer.write_all(text.as_bytes())
+    }
+
+    /// Write markdown `content` as roff text.
+    fn write_inline(&mut self, content: &str) -> io::Result<()> {
+        let roff: String = self.inline.parse(content).iter().map(inline_roff).collect();
+        self.text(&roff)
+    }
+
+    /// End an open paragraph and lists before a new block.
+    fn end_flow(&mut self) -> io::Result<()> {
+        self.in_paragraph = false;
+        for _ in 1..self.list_indents.len() {
+            self.request(".RE")?;
+        }
+        self.list_indents.clear();
+        Ok(())
+    }
+}
+
+impl<W: Write> RenderBackend for RoffBackend<W> {
+    fn heading(&mut self, level: u8, content: &str) -> io::Result<()> {
+        self.end_flow()?;
+        let plain: String = self
+            .inline
+            .parse(content)
+            .iter()
+            .map(|element| match element {
+                InlineElement::Link { text, .. } => text.clone(),
+                InlineElement::Image { alt, .. } => alt.clone(),
+                InlineElement::Text(text)
+                | InlineElement::Bold(text)
+                | InlineElement::Italic(text)
+                | InlineElement::BoldItalic(text)
+                | InlineElement::Underline(text)
+                | InlineElement::Strikeout(text)
+                | InlineElement::Code(text)
+                | InlineElement::Footnote(text)
+                | InlineElement::Spoiler(text)
+                | InlineElement::Kbd(text) => text.clone(),
+            })
+            .collect();
+        match level {
+            1 => self.request(&format!(".SH {}", quoted(&plain.to_uppercase()))),
+            2 => self.request(&format!(".SS {}", quoted(&plain))),
+            _ => {
+                self.request(".PP")?;
+                self.text(&format!("\\fB{}\\fR", plain.replace('\\', "\\e")))?;
+                self.request(".br")
+            }
+        }
+    }
+
+    fn code_block_start(&mut self, _language: Option<&str>, _indent: usize) -> io::Result<()> {
+        self.in_paragraph = false;
+        self.request(".PP")?;
+        self.request(".RS 4")?;
+        self.request(".nf")
+    }
+
+    fn code_block_line(&mut self, line: &str) -> io::Result<()> {
+        let line = roff_escape(line);
+        self.request(&line)
+    }
+
+    fn code_block_end(&mut self) -> io::Result<()> {
+        self.request(".fi")?;
+        self.request(".RE")
+    }
+
+    fn list_item(&mut self, indent: usize, bullet: &ListBullet, content: &str) -> io::Result<()> {
+        self.in_paragraph = false;
+        while self.list_indents.last().is_some_and(|&open| open > indent) {
+            self.list_indents.pop();
+            self.request(".RE")?;
+        }
+        if self.list_indents.last() != Some(&indent) {
+            if !self.list_indents.is_empty() {
+                self.request(".RS")?;
+            }
+            self.list_indents.push(indent);
+        }
+        let tag = match bullet {
+            ListBullet::Ordered(n) => format!("{}.", n),
+            _ => "\\(bu".to_string(),
+        };
+        self.request(&format!(".IP {} 4", tag))?;
+        self.write_inline(content)
+    }
+
+    fn list_end(&mut self) -> io::Result<()> {
+        self.end_flow()
+    }
+
+    fn table_header(&mut self, cells: &[String]) -> io::Result<()> {
+        self.end_flow()?;
+        self.request(".PP")?;
+        self.request(".TS")?;
+        self.request("allbox tab(\t);")?;
+        let format = vec!["l"; cells.len().max(1)].join(" ");
+        self.request(&format!("{}.", format))?;
+        self.table_row(cells)
+    }
+
+    fn table_row(&mut self, cells: &[String]) -> io::Result<()> {
+        let row: Vec<String> = cells
+            .iter()
+            .map(|cell| {
+                let roff: String = self.inline.parse(cell).iter().map(inline_roff).collect();
+                // Keep cells on their row
+                roff.replace('\t', " ")
+            })
+            .collect();
+        let row = guard_line(row.join("\t"));
+        self.request(&row)
+    }
+
+    fn table_end(&mut self) -> io::Result<()> {
+        self.request(".TE")
+    }
+
+    fn blockquote_start(&mut self, _depth: usize) -> io::Result<()> {
+        self.end_flow()?;
+        self.request(".RS 4")
+    }
+
+    fn blockquote_line(&mut self, text: &str) -> io::Result<()> {
+        self.write_inline(text)?;
+        self.request(".br")
+    }
+
+    fn blockquote_end(&mut self) -> io::Result<()> {
+        self.request(".RE")
+    }
+
+    fn details_start(&mut self, summary: &str, _open: bool) -> io::Result<()> {
+        self.end_flow()?;
+        self.request(".PP")?;
+        self.write_inline(summary)?;
+        self.request(".br")
+    }
+
+    fn horizontal_rule(&mut self) -> io::Result<()> {
+        self.end_flow()?;
+        self.request(".PP")?;
+        self.request("\\l'\\n(.lu'")
+    }
+
+    fn empty_line(&mut self) -> io::Result<()> {
+        self.in_paragraph = false;
+        Ok(())
+    }
+
+    fn newline(&mut self) -> io::Result<()> {
+        if self.mid_line {
+            writeln!(self.writer)?;
+            self.mid_line = false;
+        }
+        Ok(())
+    }
+
+    fn inline(&mut self, element: &InlineElement) -> io::Result<()> {
+        if !self.in_paragraph && self.list_indents.is_empty() {
+            self.request(".PP")?;
+            self.in_paragraph = true;
+        }
+        self.text(&inline_roff(element))
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.end_flow()?;
+        if let Some(header) = self.header.take() {
+            writeln!(self.writer, "{}", header)?;
+        }
+        if self.mid_line {
+            writeln!(self.writer)?;
+            self.mid_line = false;
+        }
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::render_str;
+
+    fn man(markdown: &str) -> String {
+        let mut backend = RoffBackend::new(Vec::new(), "t", "7");
+        render_str(&mut backend, markdown).unwrap();
+        String::from_utf8(backend.into_writer()).unwrap()
+    }
+
+    #[test]
+    fn test_code_and_lists() {
+        let output = man("```\n.hidden \\n\n```\n\n- a\n  - b\n- c\n");
+        assert_eq!(
+            output,
+            ".TH \"T\" \"7\"\n.PP\n.RS 4\n.nf\n\\&.hidden \\en\n.fi\n.RE\n\
+             .IP \\(bu 4\na\n.RS\n.IP \\(bu 4\nb\n.RE\n.IP \\(bu 4\nc\n"
+        );
+    }
+
+    #[test]
+    fn test_table() {
+        let output = man("| a | b |\n|---|---|\n| 1 | *2* |\n");
+        assert!(output.contains(".TS\nallbox tab(\t);\nl l.\na\tb\n1\t\\fI2\\fR\n.TE\n"));
+    }
+
+    #[test]
+    fn test_escapes() {
+        assert_eq!(roff_escape("'quote"), "\\&'quote");
+        assert_eq!(quoted("say \"hi\""), "\"say \\(dqhi\\(dq\"");
+        let link = InlineElement::Link {
+            text: "docs".to_string(),
+            url: "https://e.com".to_string(),
+        };
+        assert_eq!(inline_roff(&link), "docs <\\fIhttps://e.com\\fR>");
+    }
+}
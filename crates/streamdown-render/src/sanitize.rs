@@ -0,0 +1,320 @@
+//! Control characters and escape sequences in source text.
+//!
+//! Raw control characters in markdown (a BEL, backspaces, a stray carriage
+//! return, C1 controls) would ring the bell, move the cursor or otherwise
+//! garble the display if written as they are. The renderer removes them
+//! from the text of every event before drawing it, or with
+//! [`ControlChars::Show`] draws them as Control Pictures (`␇`, `␈`, `␍`).
+//!
+//! Tabs and newlines are kept, and so is the carriage return of code lines,
+//! which draws progress bars (see
+//! [`RenderFeatures::progress_lines`](crate::RenderFeatures::progress_lines)).
+//! Escape sequences in the source pass through unless the
+//! [`Policy`](crate::Policy) denies them (`source_escapes`); then whole
+//! sequences are removed, or shown starting with `␛`. This happens before
+//! drawing, so the renderer's own styling is never touched.
+//!
+//! ```
+//! use streamdown_render::sanitize::{ControlChars, sanitize_controls};
+//!
+//! assert_eq!(sanitize_controls("ding\x07!", ControlChars::Strip), "ding!");
+//! assert_eq!(sanitize_controls("ding\x07!", ControlChars::Show), "ding␇!");
+//! ```
+
+use streamdown_ansi::sanitize::strip_escape_sequences;
+use streamdown_parser::{InlineElement, ParseEvent};
+
+/// What happens to control characters in source text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum ControlChars {
+    /// Remove them
+    #[default]
+    Strip,
+    /// Draw them as Control Pictures (`␇`), C1 controls as `�`
+    Show,
+}
+
+/// Whether `c` is a control character to sanitize.
+fn is_unsafe_control(c: char) -> bool {
+    c.is_control() && !matches!(c, '\t' | '\n' | '\x1b')
+}
+
+/// The Control Picture drawing `c`.
+fn control_picture(c: char) -> char {
+    match c as u32 {
+        code @ 0..=0x1f => char::from_u32(0x2400 + code).unwrap_or('\u{fffd}'),
+        0x7f => '␡',
+        _ => '\u{fffd}',
+    }
+}
+
+/// `text` with its control characters (but tabs, newlines and ESC)
+/// removed or shown as symbols.
+pub fn sanitize_controls(text: &str, mode: ControlChars) -> String {
+    match mode {
+        ControlChars::Strip => text.chars().filter(|&c| !is_unsafe_control(c)).collect(),
+        ControlChars::Show => text
+            .chars()
+            .map(|c| {
+                if is_unsafe_control(c) {
+                    control_picture(c)
+                } else {
+                    c
+                }
+            })
+            .collect(),
+    }
+}
+
+/// `text` with its escape sequences neutralized as well: removed whole,
+/// or with [`ControlChars::Show`] their ESC drawn as `␛`.
+///
+/// ```
+/// use streamdown_render::sanitize::{ControlChars, neutralize_escapes};
+///
+/// let text = "\x1b]0;pwned\x07hi\x1b[2J";
+/// assert_eq!(neutralize_escapes(text, ControlChars::Strip), "hi");
+/// assert_eq!(neutralize_escapes(text, ControlChars::Show), "␛]0;pwned␇hi␛[2J");
+/// ```
+pub fn neutralize_escapes(text: &str, mode: ControlChars) -> String {
+    match mode {
+        ControlChars::Strip => strip_escape_sequences(text),
+        ControlChars::Show => sanitize_controls(&text.replace('\x1b', "␛"), mode),
+    }
+}
+
+/// `event` with the control characters of its text sanitized, and its
+/// escape sequences neutralized unless `keep_escapes`, or None if it has
+/// nothing to change.
+pub fn sanitize_event(
+    event: &ParseEvent,
+    mode: ControlChars,
+    keep_escapes: bool,
+) -> Option<ParseEvent> {
+    // Code lines keep the carriage returns of progress bars
+    let code = matches!(event, ParseEvent::CodeBlockLine(_));
+    let unsafe_char =
+        |c: char| (is_unsafe_control(c) && !(code && c == '\r')) || (c == '\x1b' && !keep_escapes);
+    if !texts(event).iter().any(|text| text.contains(unsafe_char)) {
+        return None;
+    }
+    let sanitize = |text: &str| {
+        if keep_escapes {
+            sanitize_controls(text, mode)
+        } else {
+            neutralize_escapes(text, mode)
+        }
+    };
+    let mut event = event.clone();
+    for text in texts_mut(&mut event) {
+        if code {
+            let lines: Vec<String> = text.split('\r').map(sanitize).collect();
+            *text = lines.join("\r");
+        } else {
+            *text = sanitize(text);
+        }
+    }
+    Some(event)
+}
+
+/// Every string of `event` that is drawn or linked to.
+pub(crate) fn texts(event: &ParseEvent) -> Vec<&str> {
+    match event {
+        ParseEvent::Text(text)
+        | ParseEvent::InlineCode(text)
+        | ParseEvent::Bold(text)
+        | ParseEvent::Italic(text)
+        | ParseEvent::Underline(text)
+        | ParseEvent::Strikeout(text)
+        | ParseEvent::BoldItalic(text)
+        | ParseEvent::Footnote(text)
+        | ParseEvent::Spoiler(text)
+        | ParseEvent::Kbd(text)
+        | ParseEvent::Heading { content: text, .. }
+        | ParseEvent::CodeBlockLanguage(text)
+        | ParseEvent::CodeBlockLine(text)
+        | ParseEvent::ListItem { content: text, .. }
+        | ParseEvent::BlockquoteLine(text)
+        | ParseEvent::ThinkBlockLine(text)
+        | ParseEvent::DetailsStart { summary: text, .. }
+        | ParseEvent::Prompt(text) => vec![text],
+        ParseEvent::Link { text, url } | ParseEvent::Image { alt: text, url } => {
+            vec![text, url]
+        }
+        ParseEvent::CodeBlockStart { language, .. } => {
+            language.iter().map(String::as_str).collect()
+        }
+        ParseEvent::TableHeader(cells) | ParseEvent::TableRow(cells) => {
+            cells.iter().map(String::as_str).collect()
+        }
+        ParseEvent::InlineElements(elements) => elements.iter().flat_map(inline_texts).collect(),
+        ParseEvent::CodeBlockEnd
+        | ParseEvent::ListEnd
+        | ParseEvent::TableSeparator
+        | ParseEvent::TableEnd
+        | ParseEvent::BlockquoteStart { .. }
+        | ParseEvent::BlockquoteEnd
+        | ParseEvent::ThinkBlockStart
+        | ParseEvent::ThinkBlockEnd
+        | ParseEvent::DetailsEnd
+        | ParseEvent::HorizontalRule
+        | ParseEvent::EmptyLine
+        | ParseEvent::Newline => Vec::new(),
+    }
+}
+
+/// The strings of `event`, as [`texts`] lists them, for rewriting.
+pub(crate) fn texts_mut(event: &mut ParseEvent) -> Vec<&mut String> {
+    match event {
+        ParseEvent::Text(text)
+        | ParseEvent::InlineCode(text)
+        | ParseEvent::Bold(text)
+        | ParseEvent::Italic(text)
+        | ParseEvent::Underline(text)
+        | ParseEvent::Strikeout(text)
+        | ParseEvent::BoldItalic(text)
+        | ParseEvent::Footnote(text)
+        | ParseEvent::Spoiler(text)
+        | ParseEvent::Kbd(text)
+        | ParseEvent::Heading { content: text, .. }
+        | ParseEvent::CodeBlockLanguage(text)
+        | ParseEvent::CodeBlockLine(text)
+        | ParseEvent::ListItem { content: text, .. }
+        | ParseEvent::BlockquoteLine(text)
+        | ParseEvent::ThinkBlockLine(text)
+        | ParseEvent::DetailsStart { summary: text, .. }
+        | ParseEvent::Prompt(text) => vec![text],
+        ParseEvent::Link { text, url } | ParseEvent::Image { alt: text, url } => {
+            vec![text, url]
+        }
+        ParseEvent::CodeBlockStart { language, .. } => language.iter_mut().collect(),
+        ParseEvent::TableHeader(cells) | ParseEvent::TableRow(cells) => cells.iter_mut().collect(),
+        ParseEvent::InlineElements(elements) => {
+            elements.iter_mut().flat_map(inline_texts_mut).collect()
+        }
+        ParseEvent::CodeBlockEnd
+        | ParseEvent::ListEnd
+        | ParseEvent::TableSeparator
+        | ParseEvent::TableEnd
+        | ParseEvent::BlockquoteStart { .. }
+        | ParseEvent::BlockquoteEnd
+        | ParseEvent::ThinkBlockStart
+        | ParseEvent::ThinkBlockEnd
+        | ParseEvent::DetailsEnd
+        | ParseEvent::HorizontalRule
+        | ParseEvent::EmptyLine
+        | ParseEvent::Newline => Vec::new(),
+    }
+}
+
+/// The strings of an inline element.
+fn inline_texts(element: &InlineElement) -> Vec<&str> {
+    match element {
+        InlineElement::Text(text)
+        | InlineElement::Bold(text)
+        | InlineElement::Italic(text)
+        | InlineElement::BoldItalic(text)
+        | InlineElement::Underline(text)
+        | InlineElement::Strikeout(text)
+        | InlineElement::Code(text)
+        | InlineElement::Footnote(text)
+        | InlineElement::Spoiler(text)
+        | InlineElement::Kbd(text) => vec![text],
+        InlineElement::Link { text, url } | InlineElement::Image { alt: text, url } => {
+            vec![text, url]
+        }
+    }
+}
+
+/// The strings of an inline element, for rewriting.
+fn inline_texts_mut(element: &mut InlineElement) -> Vec<&mut String> {
+    match element {
+        InlineElement::Text(text)
+        | InlineElement::Bold(text)
+        | InlineElement::Italic(text)
+        | InlineElement::BoldItalic(text)
+        | InlineElement::Underline(text)
+        | InlineElement::Strikeout(text)
+        | InlineElement::Code(text)
+        | InlineElement::Footnote(text)
+        | InlineElement::Spoiler(text)
+        | InlineElement::Kbd(text) => vec![text],
+        InlineElement::Link { text, url } | InlineElement::Image { alt: text, url } => {
+            vec![text, url]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_controls() {
+        let text = "a\x00b\x08c\rd\x7fe\u{9b}f\tg\x1b[1m";
+        assert_eq!(
+            sanitize_controls(text, ControlChars::Strip),
+            "abcdef\tg\x1b[1m"
+        );
+        assert_eq!(
+            sanitize_controls(text, ControlChars::Show),
+            "a␀b␈c␍d␡e\u{fffd}f\tg\x1b[1m"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_event() {
+        assert_eq!(
+            sanitize_event(&ParseEvent::Text("clean".into()), ControlChars::Strip, true),
+            None
+        );
+        let row = ParseEvent::TableRow(vec!["a\x07".into(), "b".into()]);
+        assert_eq!(
+            sanitize_event(&row, ControlChars::Show, true),
+            Some(ParseEvent::TableRow(vec!["a␇".into(), "b".into()]))
+        );
+        let link = ParseEvent::InlineElements(vec![InlineElement::Link {
+            text: "x\x08".into(),
+            url: "https://e.x/\x07".into(),
+        }]);
+        assert_eq!(
+            sanitize_event(&link, ControlChars::Strip, true),
+            Some(ParseEvent::InlineElements(vec![InlineElement::Link {
+                text: "x".into(),
+                url: "https://e.x/".into(),
+            }]))
+        );
+        // Progress bars keep their carriage returns
+        let progress = ParseEvent::CodeBlockLine("10%\r50%\x07\r".into());
+        assert_eq!(
+            sanitize_event(&progress, ControlChars::Strip, true),
+            Some(ParseEvent::CodeBlockLine("10%\r50%\r".into()))
+        );
+        let progress = ParseEvent::CodeBlockLine("10%\r50%".into());
+        assert_eq!(sanitize_event(&progress, ControlChars::Strip, true), None);
+    }
+
+    #[test]
+    fn test_sanitize_event_neutralizes_escapes() {
+        let text = ParseEvent::Text("\x1b[31mred\x1b[0m".into());
+        assert_eq!(sanitize_event(&text, ControlChars::Strip, true), None);
+        assert_eq!(
+            sanitize_event(&text, ControlChars::Strip, false),
+            Some(ParseEvent::Text("red".into()))
+        );
+        assert_eq!(
+            sanitize_event(&text, ControlChars::Show, false),
+            Some(ParseEvent::Text("␛[31mred␛[0m".into()))
+        );
+        let progress = ParseEvent::CodeBlockLine("\x1b[1m10%\r50%".into());
+        assert_eq!(
+            sanitize_event(&progress, ControlChars::Strip, false),
+            Some(ParseEvent::CodeBlockLine("10%\r50%".into()))
+        );
+    }
+}
@@ -0,0 +1,319 @@
+//! Renderer settings from the config file.
+//!
+//! [`RenderSettings`] turns a [`Config`] (usually the discovered
+//! `~/.config/streamdown/config.toml`) into a ready [`RenderStyle`],
+//! [`RendererOptions`] and [`RenderFeatures`], so embedders honor the same
+//! user configuration as the `sd` command:
+//!
+//! ```
+//! use streamdown_config::Config;
+//! use streamdown_render::{BorderStyle, ColorChoice, RenderSettings};
+//!
+//! let config: Config = toml::from_str(r#"
+//!     [style]
+//!     Width = 72
+//!     [render]
+//!     Theme = "light"
+//!     BorderStyle = "rounded"
+//!     Color = "never"
+//!     Outline = true
+//!     LanguageAliases = { hs = "haskell" }
+//! "#).unwrap();
+//! let settings = RenderSettings::from_config(&config, ".".as_ref()).unwrap();
+//! assert_eq!(settings.width, Some(72));
+//! assert_eq!(settings.features.border_style, BorderStyle::Rounded);
+//! assert_eq!(settings.options.color, ColorChoice::Never);
+//! assert!(settings.features.outline);
+//!
+//! let renderer = settings.renderer(Vec::new(), 80);
+//! assert_eq!(renderer.width(), 72);
+//! assert_eq!(renderer.language_aliases().resolve("hs"), "haskell");
+//! ```
+//!
+//! # Precedence
+//!
+//! [`resolve`](RenderSettings::resolve) (and [`load`](RenderSettings::load))
+//! layer the sources, first wins:
+//!
+//! 1. explicit API: fields set on the returned settings, or `set_*` calls
+//!    on the renderer afterwards
+//! 2. the environment: `STREAMDOWN_THEME`, `STREAMDOWN_WIDTH` and
+//!    `STREAMDOWN_BORDERS` (see [`Config::apply_env`])
+//! 3. the config file
+//! 4. the defaults
+
+use std::io::Write;
+use std::path::Path;
+
+use streamdown_config::Config;
+use streamdown_core::{Result, StreamdownError};
+
+use crate::theme::is_tm_theme;
+use crate::{
+    BorderStyle, ColorChoice, LinkStyle, RenderFeatures, RenderStyle, Renderer, RendererOptions,
+};
+
+/// Style, options and features configured for a renderer.
+#[derive(Debug, Clone, Default)]
+pub struct RenderSettings {
+    /// Render style (theme)
+    pub style: RenderStyle,
+    /// Output options
+    pub options: RendererOptions,
+    /// Feature toggles
+    pub features: RenderFeatures,
+    /// Syntax highlighting theme: a built-in name or a `.tmTheme` path
+    /// (None = the renderer's default)
+    pub highlight_theme: Option<String>,
+    /// Configured width (None = use the terminal's)
+    pub width: Option<usize>,
+    /// Fence language aliases added to the built-in ones
+    pub language_aliases: Vec<(String, String)>,
+}
+
+impl RenderSettings {
+    /// Settings from the discovered config file (or the defaults if there
+    /// is none) and the environment.
+    pub fn load() -> Result<Self> {
+        let Some(path) = Config::discover() else {
+            return Self::resolve(&Config::default(), Path::new("."));
+        };
+        let base = path.parent().unwrap_or(Path::new("."));
+        Self::resolve(&Config::load_from(&path)?, base)
+    }
+
+    /// Settings from `config` with the environment overrides applied.
+    pub fn resolve(config: &Config, base: &Path) -> Result<Self> {
+        Self::resolve_with(config, base, |name| std::env::var(name).ok())
+    }
+
+    /// Settings from `config` with the overrides of an environment lookup
+    /// function applied.
+    ///
+    /// ```
+    /// use streamdown_config::Config;
+    /// use streamdown_render::{BorderStyle, RenderSettings};
+    ///
+    /// let config: Config = toml::from_str("[render]\nBorderStyle = \"heavy\"").unwrap();
+    /// let env = |name: &str| (name == "STREAMDOWN_BORDERS").then(|| "double".to_string());
+    /// let settings = RenderSettings::resolve_with(&config, ".".as_ref(), env).unwrap();
+    /// assert_eq!(settings.features.border_style, BorderStyle::Double);
+    /// ```
+    pub fn resolve_with<F>(config: &Config, base: &Path, var: F) -> Result<Self>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let mut config = config.clone();
+        config.apply_env_with(var)?;
+        Self::from_config(&config, base)
+    }
+
+    /// Settings from `config`; relative theme paths are resolved against
+    /// `base` (the config file's directory).
+    ///
+    /// Fails on an unreadable theme or theme file, or an unknown border
+    /// style or color choice.
+    pub fn from_config(config: &Config, base: &Path) -> Result<Self> {
+        let render = &config.render;
+        let invalid = |key: &str, error: String| {
+            StreamdownError::Config(format!("[render] {}: {}", key, error))
+        };
+
+        let style = match render.theme.as_deref() {
+            None => RenderStyle::from_computed(&config.computed_style()),
+            Some("dark") => RenderStyle::default(),
+            Some("light") => RenderStyle::light(),
+            Some(path) => RenderStyle::from_path(base.join(path))?,
+        };
+        // A .tmTheme style theme highlights code too, unless another is set
+        let highlight_theme = match render.highlight_theme.as_deref().or(render
+            .theme
+            .as_deref()
+            .filter(|theme| is_tm_theme(Path::new(theme))))
+        {
+            Some(theme) if theme.ends_with(".tmTheme") => {
+                let path = base.join(theme);
+                if !path.is_file() {
+                    return Err(invalid(
+                        "HighlightTheme",
+                        format!("no such file {}", path.display()),
+                    ));
+                }
+                Some(path.to_string_lossy().into_owned())
+            }
+            theme => theme.map(String::from),
+        };
+        let border_style = match render.border_style.as_deref() {
+            Some(name) => name
+                .parse::<BorderStyle>()
+                .map_err(|e| invalid("BorderStyle", e))?,
+            None => BorderStyle::default(),
+        };
+        let color = match render.color.as_deref() {
+            Some(name) => name
+                .parse::<ColorChoice>()
+                .map_err(|e| invalid("Color", e))?,
+            None => ColorChoice::default(),
+        };
+        // Same as the CLI: ASCII borders imply ASCII-only list glyphs
+        let style = if border_style == BorderStyle::Ascii {
+            style.ascii_glyphs()
+        } else {
+            style
+        };
+
+        let width = (config.style.width > 0).then_some(config.style.width);
+        let defaults = RenderFeatures::default();
+        let features = RenderFeatures {
+            border_style,
+            highlight: render.highlight.unwrap_or(defaults.highlight),
+            line_numbers: render.line_numbers.unwrap_or(defaults.line_numbers),
+            link_style: match render.link_endnotes {
+                Some(true) => LinkStyle::Endnotes,
+                Some(false) => LinkStyle::Inline,
+                None => defaults.link_style,
+            },
+            color_swatches: render.swatches.unwrap_or(defaults.color_swatches),
+            live_blocks: render.live_blocks.unwrap_or(defaults.live_blocks),
+            outline: render.outline.unwrap_or(defaults.outline),
+            fixed_width: width,
+            width_wrap: width.is_none(),
+            ..defaults
+        };
+
+        Ok(Self {
+            style,
+            options: RendererOptions { color },
+            features,
+            highlight_theme,
+            width,
+            language_aliases: render
+                .language_aliases
+                .iter()
+                .flatten()
+                .map(|(alias, language)| (alias.clone(), language.clone()))
+                .collect(),
+        })
+    }
+
+    /// Apply the style, options, features and highlight theme to
+    /// `renderer`; a theme file that cannot be loaded is logged and skipped.
+    pub fn apply<W: Write>(&self, renderer: &mut Renderer<W>) {
+        renderer.set_style(self.style.clone());
+        renderer.set_options(self.options.clone());
+        renderer.set_features(self.features.clone());
+        for (alias, language) in &self.language_aliases {
+            renderer.alias_language(alias, language);
+        }
+        if let Some(theme) = &self.highlight_theme
+            && let Err(e) = renderer.set_highlight_theme(theme)
+        {
+            log::warn!("{}", e);
+        }
+    }
+
+    /// Create a renderer with these settings, `width` columns wide unless
+    /// the config sets a width.
+    pub fn renderer<W: Write>(&self, writer: W, width: usize) -> Renderer<W> {
+        let mut renderer = Renderer::new(writer, self.width.unwrap_or(width));
+        self.apply(&mut renderer);
+        renderer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_without_render_section() {
+        let config = Config::default();
+        let settings = RenderSettings::from_config(&config, Path::new(".")).unwrap();
+        assert_eq!(settings.width, None);
+        assert!(settings.features.highlight && settings.features.width_wrap);
+        assert_eq!(settings.options, RendererOptions::default());
+        assert_eq!(
+            settings.style.h1,
+            RenderStyle::from_computed(&config.computed_style()).h1
+        );
+    }
+
+    #[test]
+    fn test_env_overrides_config() {
+        let config: Config =
+            toml::from_str("[style]\nWidth = 100\n[render]\nTheme = \"dark\"").unwrap();
+        let env = |name: &str| match name {
+            "STREAMDOWN_WIDTH" => Some("60".to_string()),
+            "STREAMDOWN_THEME" => Some("light".to_string()),
+            _ => None,
+        };
+        let settings = RenderSettings::resolve_with(&config, Path::new("."), env).unwrap();
+        assert_eq!(settings.width, Some(60));
+        assert_eq!(settings.style.h1, RenderStyle::light().h1);
+
+        let settings = RenderSettings::resolve_with(&config, Path::new("."), |_| None).unwrap();
+        assert_eq!(settings.width, Some(100));
+        assert_eq!(settings.style.h1, RenderStyle::default().h1);
+
+        let bad = |name: &str| (name == "STREAMDOWN_BORDERS").then(|| "fancy".to_string());
+        assert!(RenderSettings::resolve_with(&config, Path::new("."), bad).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_theme_file_and_errors() {
+        let dir = std::env::temp_dir().join(format!("sd-settings-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("mine.toml"), "h1 = \"ansi256:203\"").unwrap();
+
+        let config: Config = toml::from_str(
+            "[render]\nTheme = \"mine.toml\"\nBorderStyle = \"ascii\"\nLinkEndnotes = true\nHighlightTheme = \"InspiredGitHub\"",
+        )
+        .unwrap();
+        let settings = RenderSettings::from_config(&config, &dir).unwrap();
+        assert_eq!(settings.style.h1, "ansi256:203");
+        assert_eq!(settings.style.bullets, crate::ASCII_BULLETS);
+        assert_eq!(settings.features.link_style, LinkStyle::Endnotes);
+        assert_eq!(settings.highlight_theme.as_deref(), Some("InspiredGitHub"));
+
+        for bad in [
+            "[render]\nTheme = \"missing.toml\"",
+            "[render]\nBorderStyle = \"fancy\"",
+            "[render]\nColor = \"sometimes\"",
+            "[render]\nHighlightTheme = \"missing.tmTheme\"",
+        ] {
+            let config: Config = toml::from_str(bad).unwrap();
+            assert!(RenderSettings::from_config(&config, &dir).is_err(), "{bad}");
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "syntax-highlighting")]
+    #[test]
+    fn test_tm_theme_styles_and_highlights() {
+        let dir = std::env::temp_dir().join(format!("sd-settings-tm-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("mine.tmTheme"),
+            "<plist version=\"1.0\"><dict><key>settings</key><array><dict>\
+             <key>settings</key><dict><key>background</key><string>#102030</string>\
+             </dict></dict></array></dict></plist>",
+        )
+        .unwrap();
+
+        let config: Config = toml::from_str("[render]\nTheme = \"mine.tmTheme\"").unwrap();
+        let settings = RenderSettings::from_config(&config, &dir).unwrap();
+        assert_eq!(settings.style.code_bg, "#102030");
+        let path = dir.join("mine.tmTheme").to_string_lossy().into_owned();
+        assert_eq!(settings.highlight_theme, Some(path));
+
+        // An explicit highlight theme wins
+        let config: Config = toml::from_str(
+            "[render]\nTheme = \"mine.tmTheme\"\nHighlightTheme = \"InspiredGitHub\"",
+        )
+        .unwrap();
+        let settings = RenderSettings::from_config(&config, &dir).unwrap();
+        assert_eq!(settings.highlight_theme.as_deref(), Some("InspiredGitHub"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,179 @@
+//! Sparklines and bar charts for ```` ```sparkline ```` fences.
+//!
+//! A fence of bare numbers (separated by spaces, commas or newlines) is
+//! drawn as a one-line sparkline; a fence of `label: value` lines as a
+//! horizontal bar chart. Both are sized to the content width.
+//!
+//! ```
+//! use streamdown_render::sparkline::{bar_chart, sparkline};
+//!
+//! assert_eq!(sparkline(&[1.0, 2.0, 3.0, 8.0], 10), "▁▂▃█");
+//! let bars = bar_chart(&[("a".to_string(), 1.0), ("bb".to_string(), 2.0)], 12);
+//! assert_eq!(bars, ["a  ███▌    1", "bb ███████ 2"]);
+//! ```
+
+use crate::fence::FenceHandler;
+use crate::{RESET, RenderStyle, fg_color};
+
+/// Sparkline levels, lowest first.
+const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Partial blocks for bar ends, in eighths.
+const EIGHTHS: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// Draw `values` as a sparkline of at most `width` columns, averaging
+/// neighbouring values when there are more than columns.
+pub fn sparkline(values: &[f64], width: usize) -> String {
+    let values: Vec<f64> = if values.len() > width && width > 0 {
+        (0..width)
+            .map(|i| {
+                let bucket = &values[i * values.len() / width..(i + 1) * values.len() / width];
+                bucket.iter().sum::<f64>() / bucket.len() as f64
+            })
+            .collect()
+    } else {
+        values.to_vec()
+    };
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let range = max - min;
+    values
+        .iter()
+        .map(|&v| {
+            if range > 0.0 {
+                LEVELS[((v - min) / range * 7.0).round() as usize]
+            } else {
+                LEVELS[3]
+            }
+        })
+        .collect()
+}
+
+/// Draw labelled `rows` as horizontal bars in `width` columns: labels,
+/// bars scaled to the largest value, then values.
+pub fn bar_chart(rows: &[(String, f64)], width: usize) -> Vec<String> {
+    let label_width = rows
+        .iter()
+        .map(|(label, _)| label.chars().count())
+        .max()
+        .unwrap_or(0);
+    let numbers: Vec<String> = rows.iter().map(|(_, v)| format_number(*v)).collect();
+    let number_width = numbers.iter().map(String::len).max().unwrap_or(0);
+    let bar_width = width.saturating_sub(label_width + number_width + 2).max(1);
+    let max = rows.iter().map(|(_, v)| *v).fold(0.0, f64::max);
+
+    rows.iter()
+        .zip(&numbers)
+        .map(|((label, value), number)| {
+            let eighths = if max > 0.0 {
+                (value.max(0.0) / max * (bar_width * 8) as f64).round() as usize
+            } else {
+                0
+            };
+            let mut bar = "█".repeat(eighths / 8);
+            if eighths % 8 > 0 {
+                bar.push(EIGHTHS[eighths % 8]);
+            }
+            let pad = bar_width - bar.chars().count();
+            format!(
+                "{:<lw$} {}{} {:>nw$}",
+                label,
+                bar,
+                " ".repeat(pad),
+                number,
+                lw = label_width,
+                nw = number_width
+            )
+        })
+        .collect()
+}
+
+/// Format a value without a needless fraction.
+fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        format!("{}", value)
+    }
+}
+
+/// Parse `label: value` lines, or None if any line isn't one.
+fn parse_rows(text: &str) -> Option<Vec<(String, f64)>> {
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let (label, value) = line.rsplit_once([':', '\t', ','])?;
+            Some((label.trim().to_string(), value.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+/// Parse bare numbers separated by spaces, commas or newlines.
+fn parse_values(text: &str) -> Option<Vec<f64>> {
+    text.split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse().ok().filter(|v: &f64| v.is_finite()))
+        .collect()
+}
+
+/// Handler for ```` ```sparkline ```` fences.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SparklineHandler;
+
+impl FenceHandler for SparklineHandler {
+    fn render(&self, text: &str, width: usize, style: &RenderStyle) -> Option<Vec<String>> {
+        let fg = fg_color(&style.h2);
+        if let Some(values) = parse_values(text).filter(|v| !v.is_empty()) {
+            return Some(vec![format!(
+                "{}{}{}",
+                fg,
+                sparkline(&values, width),
+                RESET
+            )]);
+        }
+        let rows = parse_rows(text).filter(|rows| !rows.is_empty())?;
+        Some(
+            bar_chart(&rows, width)
+                .into_iter()
+                .map(|line| format!("{}{}{}", fg, line, RESET))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sparkline_resamples() {
+        let values: Vec<f64> = (0..100).map(f64::from).collect();
+        let line = sparkline(&values, 10);
+        assert_eq!(line.chars().count(), 10);
+        assert!(line.starts_with('▁') && line.ends_with('█'));
+        assert_eq!(sparkline(&[5.0, 5.0], 10), "▄▄");
+    }
+
+    #[test]
+    fn test_parse_input() {
+        assert_eq!(parse_values("1, 2\n3"), Some(vec![1.0, 2.0, 3.0]));
+        assert_eq!(parse_values("1 two"), None);
+        let rows = parse_rows("mon: 3\ntue: 4.5\n").unwrap();
+        assert_eq!(rows[1], ("tue".to_string(), 4.5));
+        assert!(parse_rows("just text").is_none());
+    }
+
+    #[test]
+    fn test_handler_falls_back() {
+        let style = RenderStyle::default();
+        assert!(SparklineHandler.render("1 2 3", 20, &style).is_some());
+        assert_eq!(
+            SparklineHandler
+                .render("a: 1\nb: 2", 20, &style)
+                .unwrap()
+                .len(),
+            2
+        );
+        assert!(SparklineHandler.render("hello", 20, &style).is_none());
+    }
+}
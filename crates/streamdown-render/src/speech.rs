@@ -0,0 +1,576 @@
+//! Speech backends.
+//!
+//! [`SpeechBackend`] writes the event stream as sentences for
+//! text-to-speech engines: one paragraph, heading, list item or table row
+//! per line, each ending in sentence punctuation so the engine pauses,
+//! with URLs, footnote markers and table borders dropped. How structure is
+//! marked is up to a [`SpeechMarkup`]: [`SsmlMarkup`] adds SSML tags for
+//! heading levels, emphasis and code boundaries, and [`BareMarkup`] writes
+//! the bare sentences. [`SpeechBackend::for_features`] picks the bare
+//! sentences in [plain mode](crate::RenderFeatures::plain). The text read
+//! out is the visible text of the terminal renderer's plain mode.
+//!
+//! ```
+//! use streamdown_render::{SpeechBackend, render_str};
+//!
+//! let markdown = "# Setup\n\nRun it **now**\n\n- one\n- two\n";
+//! let mut speech = SpeechBackend::ssml(Vec::new());
+//! render_str(&mut speech, markdown).unwrap();
+//! assert_eq!(
+//!     String::from_utf8(speech.into_writer()).unwrap(),
+//!     "<speak>\n<p><mark name=\"heading-1\"/>Setup.</p>\n\
+//!      <p>Run it <emphasis level=\"strong\">now</emphasis>.</p>\n\
+//!      <s>one.</s>\n<s>two.</s>\n</speak>\n"
+//! );
+//!
+//! let mut plain = SpeechBackend::bare(Vec::new());
+//! render_str(&mut plain, markdown).unwrap();
+//! assert_eq!(
+//!     String::from_utf8(plain.into_writer()).unwrap(),
+//!     "Setup.\nRun it now.\none.\ntwo.\n"
+//! );
+//! ```
+
+use std::io::{self, Write};
+
+use streamdown_parser::{InlineElement, InlineParser, ListBullet};
+
+use crate::backend::RenderBackend;
+use crate::features::RenderFeatures;
+use crate::footer::inline_text;
+
+/// Characters that already end a sentence.
+const SENTENCE_ENDS: &[char] = &['.', '!', '?', ':', ';', '…'];
+
+/// How a [`SpeechBackend`] marks document structure. Every method returns
+/// the output for one piece of text that is already a sentence where it
+/// matters; methods returning `None` write nothing.
+pub trait SpeechMarkup {
+    /// Plain text, escaped for the output format.
+    fn text(&self, text: &str) -> String;
+
+    /// Emphasized text (`strong` for bold).
+    fn emphasis(&self, strong: bool, text: &str) -> String;
+
+    /// A heading line.
+    fn heading(&self, level: u8, sentence: &str) -> String;
+
+    /// A paragraph or blockquote line.
+    fn paragraph(&self, sentence: &str) -> String;
+
+    /// A list item, table row or other standalone sentence line.
+    fn sentence(&self, sentence: &str) -> String;
+
+    /// Line before a code block's lines.
+    fn code_start(&self, language: Option<&str>) -> Option<String>;
+
+    /// A code block line.
+    fn code_line(&self, line: &str) -> String;
+
+    /// Line after a code block's lines.
+    fn code_end(&self) -> Option<String>;
+
+    /// A pause between sections (horizontal rules).
+    fn pause(&self) -> Option<String>;
+
+    /// Lines around the whole document.
+    fn document(&self) -> Option<(String, String)>;
+}
+
+/// SSML markup: `<p>` paragraphs and `<s>` sentences, `<emphasis>`,
+/// `<mark>`s naming heading levels and code boundaries (with the language,
+/// as in `code-start-rust`), and `<break>` for pauses, all inside `<speak>`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SsmlMarkup;
+
+impl SpeechMarkup for SsmlMarkup {
+    fn text(&self, text: &str) -> String {
+        ssml_escape(text)
+    }
+
+    fn emphasis(&self, strong: bool, text: &str) -> String {
+        let level = if strong { "strong" } else { "moderate" };
+        format!(
+            "<emphasis level=\"{}\">{}</emphasis>",
+            level,
+            ssml_escape(text)
+        )
+    }
+
+    fn heading(&self, level: u8, sentence: &str) -> String {
+        format!("<p><mark name=\"heading-{}\"/>{}</p>", level, sentence)
+    }
+
+    fn paragraph(&self, sentence: &str) -> String {
+        format!("<p>{}</p>", sentence)
+    }
+
+    fn sentence(&self, sentence: &str) -> String {
+        format!("<s>{}</s>", sentence)
+    }
+
+    fn code_start(&self, language: Option<&str>) -> Option<String> {
+        Some(match language {
+            Some(language) => format!("<mark name=\"code-start-{}\"/>", ssml_escape(language)),
+            None => "<mark name=\"code-start\"/>".to_string(),
+        })
+    }
+
+    fn code_line(&self, line: &str) -> String {
+        format!("<s>{}</s>", ssml_escape(line.trim()))
+    }
+
+    fn code_end(&self) -> Option<String> {
+        Some("<mark name=\"code-end\"/>".to_string())
+    }
+
+    fn pause(&self) -> Option<String> {
+        Some("<break strength=\"x-strong\"/>".to_string())
+    }
+
+    fn document(&self) -> Option<(String, String)> {
+        Some(("<speak>".to_string(), "</speak>".to_string()))
+    }
+}
+
+/// No markup: only the sentences, with code lines as they are.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BareMarkup;
+
+impl SpeechMarkup for BareMarkup {
+    fn text(&self, text: &str) -> String {
+        text.to_string()
+    }
+
+    fn emphasis(&self, _strong: bool, text: &str) -> String {
+        text.to_string()
+    }
+
+    fn heading(&self, _level: u8, sentence: &str) -> String {
+        sentence.to_string()
+    }
+
+    fn paragraph(&self, sentence: &str) -> String {
+        sentence.to_string()
+    }
+
+    fn sentence(&self, sentence: &str) -> String {
+        sentence.to_string()
+    }
+
+    fn code_start(&self, _language: Option<&str>) -> Option<String> {
+        None
+    }
+
+    fn code_line(&self, line: &str) -> String {
+        line.to_string()
+    }
+
+    fn code_end(&self) -> Option<String> {
+        None
+    }
+
+    fn pause(&self) -> Option<String> {
+        None
+    }
+
+    fn document(&self) -> Option<(String, String)> {
+        None
+    }
+}
+
+impl<M: SpeechMarkup + ?Sized> SpeechMarkup for Box<M> {
+    fn text(&self, text: &str) -> String {
+        (**self).text(text)
+    }
+
+    fn emphasis(&self, strong: bool, text: &str) -> String {
+        (**self).emphasis(strong, text)
+    }
+
+    fn heading(&self, level: u8, sentence: &str) -> String {
+        (**self).heading(level, sentence)
+    }
+
+    fn paragraph(&self, sentence: &str) -> String {
+        (**self).paragraph(sentence)
+    }
+
+    fn sentence(&self, sentence: &str) -> String {
+        (**self).sentence(sentence)
+    }
+
+    fn code_start(&self, language: Option<&str>) -> Option<String> {
+        (**self).code_start(language)
+    }
+
+    fn code_line(&self, line: &str) -> String {
+        (**self).code_line(line)
+    }
+
+    fn code_end(&self) -> Option<String> {
+        (**self).code_end()
+    }
+
+    fn pause(&self) -> Option<String> {
+        (**self).pause()
+    }
+
+    fn document(&self) -> Option<(String, String)> {
+        (**self).document()
+    }
+}
+
+/// Escape `&`, `<`, `>` and `"` for SSML.
+pub fn ssml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Text of an inline element as spoken: its visible text in plain mode,
+/// except for footnote markers, which are not read out.
+fn spoken_text(element: &InlineElement) -> &str {
+    match element {
+        InlineElement::Footnote(_) => "",
+        element => inline_text(element),
+    }
+}
+
+/// Spoken text with its markup, and whether its text already ends a
+/// sentence.
+#[derive(Debug, Default)]
+struct Utterance {
+    /// Text with markup
+    spoken: String,
+    /// Last non-blank character of the text without markup
+    last: Option<char>,
+}
+
+impl Utterance {
+    /// Add `spoken` markup for `text`.
+    fn push(&mut self, spoken: &str, text: &str) {
+        self.spoken.push_str(spoken);
+        if let Some(last) = text.trim_end().chars().next_back() {
+            self.last = Some(last);
+        }
+    }
+
+    /// The text as a sentence, with a full stop added if it does not end
+    /// in punctuation; None if there is nothing to say.
+    fn sentence(self) -> Option<String> {
+        let last = self.last?;
+        let mut spoken = self.spoken.trim_end().to_string();
+        if !SENTENCE_ENDS.contains(&last) {
+            spoken.push('.');
+        }
+        (!spoken.is_empty()).then_some(spoken)
+    }
+}
+
+/// Renders parse events as sentences for text-to-speech, marked up by `M`.
+#[derive(Debug)]
+pub struct SpeechBackend<W: Write, M: SpeechMarkup = SsmlMarkup> {
+    writer: W,
+    markup: M,
+    /// Whether the document's opening line has been written
+    started: bool,
+    /// The open paragraph or blockquote text
+    flow: Utterance,
+    /// Parser for markdown content of block events
+    inline: InlineParser,
+}
+
+impl<W: Write> SpeechBackend<W, SsmlMarkup> {
+    /// Create a backend writing SSML.
+    pub fn ssml(writer: W) -> Self {
+        Self::with_markup(writer, SsmlMarkup)
+    }
+}
+
+impl<W: Write> SpeechBackend<W, BareMarkup> {
+    /// Create a backend writing bare sentences.
+    pub fn bare(writer: W) -> Self {
+        Self::with_markup(writer, BareMarkup)
+    }
+}
+
+impl<W: Write> SpeechBackend<W, Box<dyn SpeechMarkup>> {
+    /// Create a backend for `features`: bare sentences in plain mode,
+    /// SSML otherwise.
+    pub fn for_features(writer: W, features: &RenderFeatures) -> Self {
+        let markup: Box<dyn SpeechMarkup> = if features.plain {
+            Box::new(BareMarkup)
+        } else {
+            Box::new(SsmlMarkup)
+        };
+        Self::with_markup(writer, markup)
+    }
+}
+
+impl<W: Write, M: SpeechMarkup> SpeechBackend<W, M> {
+    /// Create a backend marking structure with `markup`.
+    pub fn with_markup(writer: W, markup: M) -> Self {
+        Self {
+            writer,
+            markup,
+            started: false,
+            flow: Utterance::default(),
+            inline: InlineParser::new(),
+        }
+    }
+
+    /// Get a mutable reference to the output writer.
+    pub fn writer_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Consume the backend, returning the output writer.
+    pub fn into_writer(self) -> W {
+        self.writer
+    }
+
+    /// Write an output line, opening the document first.
+    fn line(&mut self, line: &str) -> io::Result<()> {
+        if !self.started {
+            self.started = true;
+            if let Some((open, _)) = self.markup.document() {
+                writeln!(self.writer, "{}", open)?;
+            }
+        }
+        writeln!(self.writer, "{}", line)
+    }
+
+    /// Spoken form of an inline element.
+    fn speak(&self, element: &InlineElement) -> String {
+        match element {
+            InlineElement::Bold(text) | InlineElement::BoldItalic(text) => {
+                self.markup.emphasis(true, text)
+            }
+            InlineElement::Italic(text) | InlineElement::Underline(text) => {
+                self.markup.emphasis(false, text)
+            }
+            element => self.markup.text(spoken_text(element)),
+        }
+    }
+
+    /// Spoken form of markdown `content`.
+    fn utterance(&mut self, content: &str) -> Utterance {
+        let mut utterance = Utterance::default();
+        for element in self.inline.parse(content) {
+            utterance.push(&self.speak(&element), spoken_text(&element));
+        }
+        utterance
+    }
+
+    /// Write `content` as a standalone sentence.
+    fn write_sentence(&mut self, content: &str) -> io::Result<()> {
+        self.end_flow()?;
+        if let Some(sentence) = self.utterance(content).sentence() {
+            let line = self.markup.sentence(&sentence);
+            self.line(&line)?;
+        }
+        Ok(())
+    }
+
+    /// Add `content` to the open paragraph or blockquote.
+    fn continue_flow(&mut self, content: &str) {
+        let utterance = self.utterance(content);
+        if !self.flow.spoken.is_empty() && !self.flow.spoken.ends_with(' ') {
+            self.flow.spoken.push(' ');
+        }
+        self.flow.spoken.push_str(utterance.spoken.trim_start());
+        self.flow.last = utterance.last.or(self.flow.last);
+    }
+
+    /// Write the open paragraph or blockquote, if any.
+    fn end_flow(&mut self) -> io::Result<()> {
+        if let Some(sentence) = std::mem::take(&mut self.flow).sentence() {
+            let line = self.markup.paragraph(sentence.trim_start());
+            self.line(&line)?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write, M: SpeechMarkup> RenderBackend for SpeechBackend<W, M> {
+    fn heading(&mut self, level: u8, content: &str) -> io::Result<()> {
+        self.end_flow()?;
+        // Headings are read as they are, without emphasis inside
+        let text: String = self.inline.parse(content).iter().map(spoken_text).collect();
+        let mut utterance = Utterance::default();
+        utterance.push(&self.markup.text(&text), &text);
+        if let Some(sentence) = utterance.sentence() {
+            let line = self.markup.heading(level, &sentence);
+            self.line(&line)?;
+        }
+        Ok(())
+    }
+
+    fn code_block_start(&mut self, language: Option<&str>, _indent: usize) -> io::Result<()> {
+        self.end_flow()?;
+        match self.markup.code_start(language) {
+            Some(line) => self.line(&line),
+            None => Ok(()),
+        }
+    }
+
+    fn code_block_line(&mut self, line: &str) -> io::Result<()> {
+        if line.trim().is_empty() {
+            return Ok(());
+        }
+        let line = self.markup.code_line(line);
+        self.line(&line)
+    }
+
+    fn code_block_end(&mut self) -> io::Result<()> {
+        match self.markup.code_end() {
+            Some(line) => self.line(&line),
+            None => Ok(()),
+        }
+    }
+
+    fn list_item(&mut self, _indent: usize, _bullet: &ListBullet, content: &str) -> io::Result<()> {
+        self.write_sentence(content)
+    }
+
+    fn list_end(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn table_header(&mut self, cells: &[String]) -> io::Result<()> {
+        self.table_row(cells)
+    }
+
+    fn table_row(&mut self, cells: &[String]) -> io::Result<()> {
+        let cells: Vec<&str> = cells
+            .iter()
+            .map(|cell| cell.trim())
+            .filter(|cell| !cell.is_empty())
+            .collect();
+        self.write_sentence(&cells.join(", "))
+    }
+
+    fn table_end(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn blockquote_start(&mut self, _depth: usize) -> io::Result<()> {
+        self.end_flow()
+    }
+
+    fn blockquote_line(&mut self, text: &str) -> io::Result<()> {
+        if text.trim().is_empty() {
+            return self.end_flow();
+        }
+        self.continue_flow(text);
+        Ok(())
+    }
+
+    fn blockquote_end(&mut self) -> io::Result<()> {
+        self.end_flow()
+    }
+
+    fn details_start(&mut self, summary: &str, _open: bool) -> io::Result<()> {
+        self.write_sentence(summary)
+    }
+
+    fn horizontal_rule(&mut self) -> io::Result<()> {
+        self.end_flow()?;
+        match self.markup.pause() {
+            Some(line) => self.line(&line),
+            None => Ok(()),
+        }
+    }
+
+    fn empty_line(&mut self) -> io::Result<()> {
+        self.end_flow()
+    }
+
+    fn newline(&mut self) -> io::Result<()> {
+        if !self.flow.spoken.is_empty() && !self.flow.spoken.ends_with(' ') {
+            self.flow.spoken.push(' ');
+        }
+        Ok(())
+    }
+
+    fn inline(&mut self, element: &InlineElement) -> io::Result<()> {
+        let spoken = self.speak(element);
+        self.flow.push(&spoken, spoken_text(element));
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.end_flow()?;
+        if self.started
+            && let Some((_, close)) = self.markup.document()
+        {
+            writeln!(self.writer, "{}", close)?;
+        }
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::render_str;
+
+    fn ssml(markdown: &str) -> String {
+        let mut backend = SpeechBackend::ssml(Vec::new());
+        render_str(&mut backend, markdown).unwrap();
+        String::from_utf8(backend.into_writer()).unwrap()
+    }
+
+    fn plain(markdown: &str) -> String {
+        let features = RenderFeatures {
+            plain: true,
+            ..Default::default()
+        };
+        let mut backend = SpeechBackend::for_features(Vec::new(), &features);
+        render_str(&mut backend, markdown).unwrap();
+        String::from_utf8(backend.into_writer()).unwrap()
+    }
+
+    #[test]
+    fn test_code_boundaries_and_escaping() {
+        let output =
+            ssml("Use <b> & see [docs](https://a.example)?\n\n```rust\nlet a = 1;\n\n```\n");
+        assert_eq!(
+            output,
+            "<speak>\n<p>Use &lt;b&gt; &amp; see docs?</p>\n\
+             <mark name=\"code-start-rust\"/>\n<s>let a = 1;</s>\n\
+             <mark name=\"code-end\"/>\n</speak>\n"
+        );
+    }
+
+    #[test]
+    fn test_plain_sentences() {
+        let output = plain(
+            "## Table\n\n| a | b |\n|---|---|\n| 1 | 2 |\n\n> quoted\n> text\n\n---\nwrapped\nparagraph! *yes*\n",
+        );
+        assert_eq!(
+            output,
+            "Table.\na, b.\n1, 2.\nquoted text.\nwrapped paragraph! yes.\n"
+        );
+        assert_eq!(plain(""), "");
+    }
+
+    #[test]
+    fn test_emphasis_and_pause() {
+        let output = ssml("*soft* and ***loud***\n\n---\n");
+        assert!(output.contains(
+            "<p><emphasis level=\"moderate\">soft</emphasis> and \
+             <emphasis level=\"strong\">loud</emphasis>.</p>"
+        ));
+        assert!(output.contains("<break strength=\"x-strong\"/>\n</speak>"));
+    }
+}
@@ -0,0 +1,329 @@
+//! Splitting rendered output by heading.
+//!
+//! A [`SectionSplitter`] renders one markdown document into a separate
+//! sink per top-level section: each heading at or above the split level
+//! starts a new section, and text before the first heading (if any) is a
+//! section of its own. Sinks are opened on demand by a caller-supplied
+//! function, so sections can go to files ([`SectionSplitter::to_dir`], as
+//! plain text) or any other writers. Each section is a complete document: open blocks are
+//! closed and link endnotes written before the next one starts.
+//!
+//! ```
+//! use streamdown_render::{Renderer, SectionSplitter};
+//!
+//! let renderer = Renderer::new(Vec::new(), 40);
+//! let mut splitter = SectionSplitter::new(renderer, |_| Ok(Vec::new()));
+//! splitter.push("# Setup\nInstall it.\n# Usage\nRun it.\n").unwrap();
+//! let sections = splitter.finish().unwrap();
+//!
+//! assert_eq!(sections.len(), 2);
+//! assert_eq!(sections[1].0.title.as_deref(), Some("Usage"));
+//! assert!(String::from_utf8_lossy(&sections[1].1).contains("Run it."));
+//! ```
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+use streamdown_ansi::utils::visible;
+use streamdown_parser::{ParseEvent, Parser, ParserOptions};
+
+use crate::{ColorChoice, Renderer, RendererOptions};
+
+/// Longest file name stem made from a title, in bytes.
+const MAX_SLUG_LEN: usize = 48;
+
+/// A section of a split document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    /// Position in the document, from 1
+    pub index: usize,
+    /// Heading text (None for text before the first heading)
+    pub title: Option<String>,
+}
+
+impl Section {
+    /// File name for the section: the zero-padded index and a slug of the
+    /// title, e.g. `0002-getting-started.txt`, so names sort in document
+    /// order.
+    pub fn file_name(&self) -> String {
+        let slug = match &self.title {
+            Some(title) => slug(title),
+            None => "preamble".to_string(),
+        };
+        format!("{:04}-{}.txt", self.index, slug)
+    }
+}
+
+/// Lowercase ASCII letters and digits of `title`, other runs joined by
+/// `-`; `section` if nothing is left.
+pub fn slug(title: &str) -> String {
+    let mut slug = String::new();
+    for word in title
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+    {
+        if slug.len() + word.len() >= MAX_SLUG_LEN {
+            break;
+        }
+        if !slug.is_empty() {
+            slug.push('-');
+        }
+        slug.push_str(&word.to_ascii_lowercase());
+    }
+    if slug.is_empty() {
+        slug.push_str("section");
+    }
+    slug
+}
+
+/// Renders a markdown document into one sink per top-level section.
+pub struct SectionSplitter<W: Write, F: FnMut(&Section) -> io::Result<W>> {
+    /// Streaming parser
+    parser: Parser,
+    /// Renderer writing into an in-memory buffer
+    renderer: Renderer<Vec<u8>>,
+    /// Opens the sink of a section
+    open: F,
+    /// Sections opened so far, with their sinks
+    sections: Vec<(Section, W)>,
+    /// Deepest heading level that starts a section
+    level: u8,
+    /// Input received after the last newline
+    pending: String,
+}
+
+impl<W: Write, F: FnMut(&Section) -> io::Result<W>> SectionSplitter<W, F> {
+    /// Create a splitter drawing with `renderer` (whose settings every
+    /// section shares) and opening each section's sink with `open`.
+    /// Splits at level 1 headings.
+    pub fn new(renderer: Renderer<Vec<u8>>, open: F) -> Self {
+        Self {
+            parser: Parser::new(),
+            renderer,
+            open,
+            sections: Vec::new(),
+            level: 1,
+            pending: String::new(),
+        }
+    }
+
+    /// Parse with `options` (e.g. the spoiler and key cap opt-ins).
+    pub fn set_parser_options(&mut self, options: ParserOptions) {
+        self.parser.set_options(options);
+    }
+
+    /// Split at headings of `level` and above (1-6).
+    pub fn set_level(&mut self, level: u8) {
+        self.level = level.clamp(1, 6);
+    }
+
+    /// Add a chunk of markdown, rendering every line it completes.
+    pub fn push(&mut self, chunk: &str) -> io::Result<()> {
+        self.pending.push_str(chunk);
+        if let Some(end) = self.pending.rfind('\n') {
+            let complete: String = self.pending.drain(..=end).collect();
+            for line in complete[..end].split('\n') {
+                let line = line.strip_suffix('\r').unwrap_or(line);
+                let events = self.parser.parse_line(line);
+                self.render_events(&events)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Render the rest of the document and end the last section.
+    ///
+    /// Returns the sections with their sinks, in document order.
+    pub fn finish(mut self) -> io::Result<Vec<(Section, W)>> {
+        if !self.pending.is_empty() {
+            let line = std::mem::take(&mut self.pending);
+            let events = self.parser.parse_line(&line);
+            self.render_events(&events)?;
+        }
+        let events = self.parser.finalize();
+        self.render_events(&events)?;
+        self.end_section()?;
+        for (_, sink) in &mut self.sections {
+            sink.flush()?;
+        }
+        Ok(self.sections)
+    }
+
+    /// Sections opened so far.
+    pub fn sections(&self) -> impl Iterator<Item = &Section> {
+        self.sections.iter().map(|(section, _)| section)
+    }
+
+    /// Get a mutable reference to the renderer (e.g. to set features).
+    pub fn renderer_mut(&mut self) -> &mut Renderer<Vec<u8>> {
+        &mut self.renderer
+    }
+
+    /// Render events, starting a section at each splitting heading.
+    fn render_events(&mut self, events: &[ParseEvent]) -> io::Result<()> {
+        for event in events {
+            if let ParseEvent::Heading { level, content } = event
+                && *level <= self.level
+            {
+                self.end_section()?;
+                self.open_section(Some(content.clone()))?;
+            }
+            self.renderer.render_event(event)?;
+        }
+        self.write_output()
+    }
+
+    /// Move the rendered output into the current section's sink.
+    ///
+    /// Output before the first heading opens a preamble section, unless
+    /// it is only blank lines.
+    fn write_output(&mut self) -> io::Result<()> {
+        let output = std::mem::take(self.renderer.writer_mut());
+        if output.is_empty() {
+            return Ok(());
+        }
+        if self.sections.is_empty() {
+            if visible(&String::from_utf8_lossy(&output)).trim().is_empty() {
+                return Ok(());
+            }
+            self.open_section(None)?;
+        }
+        match self.sections.last_mut() {
+            Some((_, sink)) => sink.write_all(&output),
+            None => Ok(()),
+        }
+    }
+
+    /// Close the current section's open blocks and write its endnotes.
+    fn end_section(&mut self) -> io::Result<()> {
+        if self.sections.is_empty() {
+            self.write_output()?;
+        }
+        if self.sections.is_empty() {
+            // Nothing but blank lines so far
+            self.renderer.reset();
+            return Ok(());
+        }
+        self.renderer.finish()?;
+        self.write_output()?;
+        self.renderer.reset();
+        Ok(())
+    }
+
+    /// Open the sink of the next section.
+    fn open_section(&mut self, title: Option<String>) -> io::Result<()> {
+        let section = Section {
+            index: self.sections.len() + 1,
+            title,
+        };
+        let sink = (self.open)(&section)?;
+        self.sections.push((section, sink));
+        Ok(())
+    }
+}
+
+impl SectionSplitter<BufWriter<File>, Box<dyn FnMut(&Section) -> io::Result<BufWriter<File>>>> {
+    /// Create a splitter writing each section to a file in `dir` (created
+    /// if missing), named by [`Section::file_name`].
+    ///
+    /// The files are text notes, so the renderer is switched to plain text
+    /// without colors whatever the environment says.
+    pub fn to_dir(mut renderer: Renderer<Vec<u8>>, dir: impl Into<PathBuf>) -> io::Result<Self> {
+        renderer.set_options(RendererOptions {
+            color: ColorChoice::Never,
+        });
+        let mut features = renderer.features().clone();
+        features.plain = true;
+        renderer.set_features(features);
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        let open = move |section: &Section| -> io::Result<BufWriter<File>> {
+            File::create(dir.join(section.file_name())).map(BufWriter::new)
+        };
+        Ok(Self::new(renderer, Box::new(open)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LinkStyle, RenderFeatures};
+
+    fn split(markdown: &str, level: u8) -> Vec<(Section, String)> {
+        let mut renderer = Renderer::new(Vec::new(), 40);
+        renderer.set_features(RenderFeatures {
+            plain: true,
+            link_style: LinkStyle::Endnotes,
+            ..Default::default()
+        });
+        let mut splitter = SectionSplitter::new(renderer, |_| Ok(Vec::new()));
+        splitter.set_level(level);
+        splitter.push(markdown).unwrap();
+        splitter
+            .finish()
+            .unwrap()
+            .into_iter()
+            .map(|(section, sink)| (section, String::from_utf8(sink).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_split_sections() {
+        let sections = split(
+            "Intro text\n# One\nSee [docs](https://a.example)\n## Sub\n```\ncode\n# One B\n```\n# Two\n- item",
+            1,
+        );
+        let titles: Vec<_> = sections.iter().map(|(s, _)| s.title.as_deref()).collect();
+        assert_eq!(titles, [None, Some("One"), Some("Two")]);
+        assert!(sections[0].1.contains("Intro text"));
+        // Subheadings and headings in code stay in their section, and
+        // endnotes are written before the next section starts
+        assert!(sections[1].1.contains("Sub") && sections[1].1.contains("# One B"));
+        assert!(sections[1].1.contains("https://a.example"));
+        assert!(sections[2].1.contains("item") && !sections[2].1.contains("code"));
+        assert_eq!(sections[2].0.file_name(), "0003-two.txt");
+        let hundredth = Section {
+            index: 100,
+            title: Some("Two".to_string()),
+        };
+        assert!(sections[2].0.file_name() < hundredth.file_name());
+    }
+
+    #[test]
+    fn test_split_level_and_blank_preamble() {
+        let sections = split("\n\n# A\n## B\ntext\n", 2);
+        let names: Vec<_> = sections.iter().map(|(s, _)| s.file_name()).collect();
+        assert_eq!(names, ["0001-a.txt", "0002-b.txt"]);
+    }
+
+    #[test]
+    fn test_split_parser_options_and_plain_files() {
+        let dir = std::env::temp_dir().join(format!("sd-split-{}", std::process::id()));
+        let mut renderer = Renderer::new(Vec::new(), 40);
+        renderer.set_options(RendererOptions {
+            color: ColorChoice::Always,
+        });
+        let mut splitter = SectionSplitter::to_dir(renderer, &dir).unwrap();
+        splitter.set_parser_options(ParserOptions {
+            kbd_shorthand: true,
+            ..Default::default()
+        });
+        splitter.push("# Keys\nPress [[Ctrl]] now\n").unwrap();
+        splitter.finish().unwrap();
+        let text = std::fs::read_to_string(dir.join("0001-keys.txt")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(!text.contains('\x1b'), "{text:?}");
+        assert!(
+            text.contains("Ctrl") && !text.contains("[[Ctrl]]"),
+            "{text:?}"
+        );
+    }
+
+    #[test]
+    fn test_slug() {
+        assert_eq!(slug("Getting Started: **Fast**!"), "getting-started-fast");
+        assert_eq!(slug("✨"), "section");
+        assert!(slug(&"word ".repeat(40)).len() < MAX_SLUG_LEN);
+    }
+}
@@ -0,0 +1,218 @@
+//! Rendering for SSH PTY sessions.
+//!
+//! [`SshRenderSession`] maps the pieces of an SSH session that matter for
+//! rendering onto a renderer: the PTY request (terminal type and size), window
+//! change requests, and the channel's raw byte output. It has no dependency on
+//! a particular SSH library; wire its methods into your server's handler:
+//!
+//! ```
+//! use streamdown_render::{PtyRequest, SshRenderSession};
+//!
+//! // In `pty_request(channel, term, col_width, row_height, ..)`:
+//! let mut session = SshRenderSession::from_pty_request(&PtyRequest {
+//!     term: "xterm-256color".to_string(),
+//!     col_width: 100,
+//!     row_height: 40,
+//! });
+//!
+//! // In `window_change_request(channel, col_width, row_height, ..)`:
+//! session.window_change(120, 40);
+//!
+//! // Whenever markdown is available, send the bytes with `session.data(..)`:
+//! let bytes = session.feed("# Hello over SSH\n").unwrap();
+//! assert!(!bytes.is_empty());
+//! let tail = session.finish().unwrap();
+//! # let _ = tail;
+//! ```
+
+use crate::chunk::ChunkRenderer;
+use crate::{RenderResult, Renderer, TerminalCapabilities};
+
+/// Width used when the client reports a zero-sized PTY.
+const FALLBACK_WIDTH: usize = 80;
+
+/// Terminal parameters from an SSH `pty-req`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PtyRequest {
+    /// Client `TERM` value
+    pub term: String,
+    /// Width in columns
+    pub col_width: u32,
+    /// Height in rows
+    pub row_height: u32,
+}
+
+/// Markdown renderer bound to one SSH PTY channel.
+pub struct SshRenderSession {
+    /// Parse/render pipeline
+    inner: ChunkRenderer,
+    /// Client `TERM` value
+    term: String,
+    /// Window height in rows
+    rows: u32,
+}
+
+impl SshRenderSession {
+    /// Create a session sized and configured from the client's PTY request.
+    ///
+    /// Terminal capabilities are guessed from the client's `TERM`, not the
+    /// server's environment.
+    pub fn from_pty_request(request: &PtyRequest) -> Self {
+        let mut renderer = Renderer::new(Vec::new(), effective_width(request.col_width));
+        renderer.set_capabilities(TerminalCapabilities::from_env(|name| {
+            (name == "TERM").then(|| request.term.clone())
+        }));
+
+        Self {
+            inner: ChunkRenderer::new(renderer),
+            term: request.term.clone(),
+            rows: request.row_height,
+        }
+    }
+
+    /// Handle a window change request; later output uses the new width.
+    pub fn window_change(&mut self, col_width: u32, row_height: u32) {
+        self.inner.renderer.set_width(effective_width(col_width));
+        self.rows = row_height;
+    }
+
+    /// Render a chunk of markdown, returning bytes to send on the channel.
+    ///
+    /// Only complete lines are rendered; the remainder is kept for the next
+    /// call or [`finish`](Self::finish). On a render error, output rendered
+    /// so far is kept and returned by the next call.
+    pub fn feed(&mut self, chunk: &str) -> RenderResult<Vec<u8>> {
+        self.inner.push(chunk)?;
+        Ok(self.output())
+    }
+
+    /// Render any buffered partial line and close open blocks.
+    pub fn finish(&mut self) -> RenderResult<Vec<u8>> {
+        self.inner.finish()?;
+        Ok(self.output())
+    }
+
+    /// Get a mutable reference to the renderer (e.g. to change style).
+    pub fn renderer_mut(&mut self) -> &mut Renderer<Vec<u8>> {
+        &mut self.inner.renderer
+    }
+
+    /// Client `TERM` value.
+    pub fn term(&self) -> &str {
+        &self.term
+    }
+
+    /// Current render width in columns.
+    pub fn width(&self) -> usize {
+        self.inner.renderer.width()
+    }
+
+    /// Current window height in rows.
+    pub fn rows(&self) -> u32 {
+        self.rows
+    }
+
+    /// Take rendered output, translated for a raw PTY channel.
+    ///
+    /// There is no line discipline between the server and the channel, so
+    /// bare `\n` must become `\r\n` or lines stair-step on the client.
+    fn output(&mut self) -> Vec<u8> {
+        let raw = self.inner.take_output();
+        let mut out = Vec::with_capacity(raw.len() + raw.len() / 32);
+        let mut prev = 0u8;
+        for &b in &raw {
+            if b == b'\n' && prev != b'\r' {
+                out.push(b'\r');
+            }
+            out.push(b);
+            prev = b;
+        }
+        out
+    }
+}
+
+/// Render width for a reported column count.
+fn effective_width(col_width: u32) -> usize {
+    match col_width {
+        0 => FALLBACK_WIDTH,
+        cols => cols as usize,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GraphicsProtocol;
+
+    fn request(term: &str, cols: u32) -> PtyRequest {
+        PtyRequest {
+            term: term.to_string(),
+            col_width: cols,
+            row_height: 24,
+        }
+    }
+
+    #[test]
+    fn test_width_from_pty_request() {
+        let session = SshRenderSession::from_pty_request(&request("xterm", 100));
+        assert_eq!(session.width(), 100);
+
+        let session = SshRenderSession::from_pty_request(&request("xterm", 0));
+        assert_eq!(session.width(), FALLBACK_WIDTH);
+    }
+
+    #[test]
+    fn test_window_change() {
+        let mut session = SshRenderSession::from_pty_request(&request("xterm", 80));
+        session.window_change(132, 50);
+        assert_eq!(session.width(), 132);
+        assert_eq!(session.rows(), 50);
+    }
+
+    #[test]
+    fn test_capabilities_from_client_term() {
+        let mut session = SshRenderSession::from_pty_request(&request("xterm-kitty", 80));
+        assert_eq!(
+            session.renderer_mut().capabilities().graphics,
+            GraphicsProtocol::Kitty
+        );
+
+        let mut session = SshRenderSession::from_pty_request(&request("vt100", 80));
+        assert_eq!(
+            session.renderer_mut().capabilities().graphics,
+            GraphicsProtocol::None
+        );
+    }
+
+    #[test]
+    fn test_output_uses_crlf() {
+        let mut session = SshRenderSession::from_pty_request(&request("xterm", 80));
+        let mut out = session.feed("line one\nline two\n").unwrap();
+        out.extend(session.finish().unwrap());
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains("line one"));
+        assert!(text.contains("\r\n"));
+        assert!(!text.replace("\r\n", "").contains('\n'));
+    }
+
+    #[test]
+    fn test_partial_line_buffered() {
+        let mut session = SshRenderSession::from_pty_request(&request("xterm", 80));
+        assert!(session.feed("no newline yet").unwrap().is_empty());
+        let out = String::from_utf8(session.finish().unwrap()).unwrap();
+        assert!(out.contains("no newline yet"));
+    }
+
+    #[test]
+    fn test_render_errors_returned() {
+        let mut session = SshRenderSession::from_pty_request(&request("xterm", 80));
+        session.renderer_mut().set_width(2);
+        assert!(matches!(
+            session.feed("text\n"),
+            Err(crate::RenderError::WidthTooSmall { width: 2, .. })
+        ));
+        session.window_change(80, 24);
+        let out = String::from_utf8(session.feed("more\n").unwrap()).unwrap();
+        assert!(out.contains("more"));
+    }
+}
@@ -0,0 +1,179 @@
+//! Async stream adapter.
+//!
+//! [`RenderStream`] turns a `Stream` of markdown chunks into a `Stream` of
+//! rendered ANSI bytes (or render errors), so server-side applications (chat backends, SSH
+//! services) can render inside async pipelines without a blocking writer.
+//!
+//! ```
+//! use futures::{StreamExt, executor::block_on, stream};
+//! use streamdown_render::RenderStream;
+//!
+//! let input = stream::iter(vec!["# Hel".to_string(), "lo\n".to_string()]);
+//! let output: Vec<_> = block_on(RenderStream::new(input, 80).collect());
+//! assert!(!output.is_empty() && output.iter().all(Result::is_ok));
+//! ```
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use futures_core::Stream;
+use streamdown_parser::Parser;
+
+use crate::chunk::ChunkRenderer;
+use crate::{RenderError, RenderResult, Renderer};
+
+/// Stream of rendered output fed by a stream of markdown chunks.
+///
+/// Chunks may split lines anywhere; a line is rendered once its newline
+/// arrives, and the trailing partial line is rendered when the input ends.
+/// Each item holds the output of one or more complete lines, or an error
+/// the renderer hit; output written before the error comes first, and the
+/// stream goes on with the next chunk, so the consumer decides whether to
+/// stop.
+///
+/// The input must be `Unpin`; wrap other streams with `Box::pin`.
+pub struct RenderStream<S> {
+    /// Markdown chunk source
+    input: S,
+    /// Parse/render pipeline
+    inner: ChunkRenderer,
+    /// Whether the input has ended
+    done: bool,
+    /// Error to yield once the output before it is out
+    error: Option<RenderError>,
+}
+
+impl<S> RenderStream<S>
+where
+    S: Stream<Item = String> + Unpin,
+{
+    /// Create an adapter rendering at `width` columns with the default style.
+    pub fn new(input: S, width: usize) -> Self {
+        Self::with_renderer(input, Renderer::new(Vec::new(), width))
+    }
+
+    /// Create an adapter around a preconfigured renderer.
+    ///
+    /// Anything already in the renderer's buffer is emitted first.
+    pub fn with_renderer(input: S, renderer: Renderer<Vec<u8>>) -> Self {
+        Self {
+            input,
+            inner: ChunkRenderer::new(renderer),
+            done: false,
+            error: None,
+        }
+    }
+
+    /// Get a mutable reference to the parser (e.g. to toggle link handling).
+    pub fn parser_mut(&mut self) -> &mut Parser {
+        &mut self.inner.parser
+    }
+
+    /// Get a mutable reference to the renderer (e.g. to change style).
+    pub fn renderer_mut(&mut self) -> &mut Renderer<Vec<u8>> {
+        &mut self.inner.renderer
+    }
+}
+
+impl<S> Stream for RenderStream<S>
+where
+    S: Stream<Item = String> + Unpin,
+{
+    type Item = RenderResult<Bytes>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            let output = this.inner.take_output();
+            if !output.is_empty() {
+                return Poll::Ready(Some(Ok(Bytes::from(output))));
+            }
+            if let Some(error) = this.error.take() {
+                return Poll::Ready(Some(Err(error)));
+            }
+            if this.done {
+                return Poll::Ready(None);
+            }
+
+            let result = match Pin::new(&mut this.input).poll_next(cx) {
+                Poll::Ready(Some(chunk)) => this.inner.push(&chunk),
+                Poll::Ready(None) => {
+                    this.done = true;
+                    this.inner.finish()
+                }
+                Poll::Pending => return Poll::Pending,
+            };
+            if let Err(e) = result {
+                this.error = Some(e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use futures::{StreamExt, stream};
+
+    fn render(chunks: &[&str]) -> Vec<Bytes> {
+        let input = stream::iter(chunks.iter().map(|c| c.to_string()).collect::<Vec<_>>());
+        block_on(RenderStream::new(input, 80).collect::<Vec<_>>())
+            .into_iter()
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    fn joined(items: &[Bytes]) -> String {
+        items
+            .iter()
+            .map(|b| String::from_utf8_lossy(b).into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_split_chunks_match_whole_input() {
+        let whole = render(&["# Title\n\nSome *text* here\n"]);
+        let split = render(&["# Ti", "tle\n", "\nSome *te", "xt* here", "\n"]);
+        assert_eq!(joined(&whole), joined(&split));
+        assert!(joined(&split).contains("Title"));
+    }
+
+    #[test]
+    fn test_partial_line_rendered_at_end() {
+        let output = joined(&render(&["hello ", "world"]));
+        assert!(output.contains("hello world"));
+    }
+
+    #[test]
+    fn test_open_code_block_closed_at_end() {
+        let output = joined(&render(&["```rust\nfn main() {}\n"]));
+        assert!(output.contains("fn"));
+        assert!(output.contains("main"));
+    }
+
+    #[test]
+    fn test_no_empty_items() {
+        let items = render(&["a", "b", "c\n", ""]);
+        assert!(items.iter().all(|b| !b.is_empty()));
+    }
+
+    #[test]
+    fn test_empty_input() {
+        assert!(render(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_render_errors_are_yielded() {
+        let input = stream::iter(vec!["text\n".to_string(), "more\n".to_string()]);
+        let items: Vec<_> = block_on(RenderStream::new(input, 2).collect());
+        assert!(!items.is_empty());
+        assert!(
+            items
+                .iter()
+                .any(|item| matches!(item, Err(RenderError::WidthTooSmall { width: 2, .. })))
+        );
+    }
+}
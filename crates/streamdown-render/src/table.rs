@@ -1,9 +1,16 @@
 //! Table rendering.
 //!
 //! Renders markdown tables with full-width columns and styled borders.
+//!
+//! Column widths are balanced from the header row: each column gets a share
+//! of the available width proportional to its content, and cells that don't
+//! fit wrap across several physical rows instead of overflowing.
+
+use std::sync::Arc;
 
-use crate::RenderStyle;
-use crate::text::text_wrap;
+use crate::cells::{CellAlign, CellFormatter};
+use crate::text::{break_long_words, text_wrap};
+use crate::{BorderStyle, InlineHandlers, RenderStyle, TextPasses};
 use crate::{bg_color, fg_color};
 use streamdown_ansi::codes::RESET;
 use streamdown_ansi::utils::visible_length;
@@ -23,6 +30,16 @@ pub struct TableState {
     pub num_columns: usize,
     /// Available width for the table
     pub available_width: usize,
+    /// Glyphs for column separators and rules
+    pub border: BorderStyle,
+    /// Formatter for body cells (None = markdown only)
+    pub formatter: Option<Arc<dyn CellFormatter>>,
+    /// Text passes run on the text of markdown cells
+    pub text_passes: Arc<TextPasses>,
+    /// Handlers for inline extensions in markdown cells
+    pub inline_handlers: Arc<InlineHandlers>,
+    /// Visible length of each column's content the widths were balanced for
+    natural: Vec<usize>,
 }
 
 impl TableState {
@@ -33,18 +50,58 @@ impl TableState {
             column_widths: Vec::new(),
             num_columns: 0,
             available_width: 80,
+            border: BorderStyle::default(),
+            formatter: None,
+            text_passes: Arc::default(),
+            inline_handlers: Arc::default(),
+            natural: Vec::new(),
         }
     }
 
     /// Calculate column widths to fill the available width evenly.
     pub fn calculate_widths(&mut self, num_cols: usize, available_width: usize) {
-        self.num_columns = num_cols;
-        self.available_width = available_width;
+        self.balance(&vec![0; num_cols], available_width);
+    }
+
+    /// Calculate column widths proportional to the cells' visible lengths.
+    ///
+    /// Cells are measured after inline formatting, ignoring ANSI codes and
+    /// counting wide (CJK) characters as two columns.
+    pub fn calculate_widths_for(&mut self, cells: &[String], available_width: usize) {
+        let natural: Vec<usize> = cells
+            .iter()
+            .map(|cell| visible_length(&format_line(cell, true, true)))
+            .collect();
+        self.balance(&natural, available_width);
+    }
 
-        if num_cols == 0 {
-            self.column_widths = Vec::new();
-            return;
+    /// Rebalance the columns so they also fit `cells`, returning whether any
+    /// column width changed.
+    ///
+    /// Widths only ever grow from the longest content seen so far; a row
+    /// with a different number of columns leaves them alone.
+    pub fn widen(&mut self, cells: &[String], available_width: usize) -> bool {
+        if cells.len() != self.num_columns || self.natural.len() != cells.len() {
+            return false;
+        }
+        let mut natural = self.natural.clone();
+        for (longest, cell) in natural.iter_mut().zip(cells) {
+            *longest = (*longest).max(visible_length(&format_line(cell, true, true)));
         }
+        if natural == self.natural {
+            return false;
+        }
+        let before = std::mem::take(&mut self.column_widths);
+        self.balance(&natural, available_width);
+        self.column_widths != before
+    }
+
+    /// Distribute the available width over columns weighted by `natural`.
+    fn balance(&mut self, natural: &[usize], available_width: usize) {
+        let num_cols = natural.len();
+        self.num_columns = num_cols;
+        self.natural = natural.to_vec();
+        self.available_width = available_width;
 
         // Account for separators and padding
         // Each column has: " content " (2 chars padding)
@@ -53,19 +110,7 @@ impl TableState {
         let padding_width = num_cols * 2;
         let content_width = available_width.saturating_sub(separator_width + padding_width);
 
-        // Distribute evenly with remainder going to leftmost columns
-        let base_width = (content_width / num_cols).max(MIN_COL_WIDTH);
-        let remainder = content_width % num_cols;
-
-        self.column_widths = (0..num_cols)
-            .map(|i| {
-                if i < remainder {
-                    base_width + 1
-                } else {
-                    base_width
-                }
-            })
-            .collect();
+        self.column_widths = balance_widths(natural, content_width);
     }
 
     /// Get total table width including separators and padding
@@ -86,6 +131,7 @@ impl TableState {
         self.is_header = true;
         self.column_widths.clear();
         self.num_columns = 0;
+        self.natural.clear();
     }
 }
 
@@ -95,6 +141,50 @@ impl Default for TableState {
     }
 }
 
+/// Split `content_width` into column widths proportional to `natural`.
+///
+/// Short columns are raised to a floor (up to [`MIN_COL_WIDTH`]) so they stay
+/// readable; rounding leftovers go to the heaviest columns, leftmost first.
+/// The result only exceeds `content_width` when there is less than one
+/// column per cell.
+fn balance_widths(natural: &[usize], content_width: usize) -> Vec<usize> {
+    let num_cols = natural.len();
+    if num_cols == 0 {
+        return Vec::new();
+    }
+
+    let floor = (content_width / num_cols).clamp(1, MIN_COL_WIDTH);
+    let weights: Vec<usize> = natural.iter().map(|&w| w.max(floor)).collect();
+    let total_weight: usize = weights.iter().sum();
+
+    let mut widths: Vec<usize> = weights
+        .iter()
+        .map(|&w| (content_width * w / total_weight).max(floor))
+        .collect();
+
+    // Heaviest columns first; the sort is stable so ties go left to right
+    let mut order: Vec<usize> = (0..num_cols).collect();
+    order.sort_by(|&a, &b| weights[b].cmp(&weights[a]));
+
+    let mut total: usize = widths.iter().sum();
+    for &i in order.iter().cycle() {
+        if total >= content_width {
+            break;
+        }
+        widths[i] += 1;
+        total += 1;
+    }
+    while total > content_width {
+        let Some(&i) = order.iter().find(|&&i| widths[i] > floor) else {
+            break;
+        };
+        widths[i] -= 1;
+        total -= 1;
+    }
+
+    widths
+}
+
 /// Render a table row with full-width columns.
 pub fn render_table_row(
     cells: &[String],
@@ -108,7 +198,7 @@ pub fn render_table_row(
 
     // Calculate column widths if not already done
     if state.column_widths.is_empty() || state.num_columns != num_cols {
-        state.calculate_widths(num_cols, width);
+        state.calculate_widths_for(cells, width);
     }
 
     // Choose background color based on header state
@@ -120,13 +210,33 @@ pub fn render_table_row(
 
     // Wrap each cell's content to fit column width
     let mut wrapped_cells: Vec<Vec<String>> = Vec::with_capacity(num_cols);
+    let mut aligns: Vec<CellAlign> = Vec::with_capacity(num_cols);
     let mut max_height = 1;
 
     for (i, cell) in cells.iter().enumerate() {
         let col_width = state.column_widths.get(i).copied().unwrap_or(MIN_COL_WIDTH);
+        let custom = match &state.formatter {
+            Some(formatter) if !state.is_header => formatter.format(cell),
+            _ => None,
+        };
         // Process inline markdown (bold, italic, code, etc.) before wrapping
-        let formatted_cell = format_line(cell, true, true);
-        let wrapped = text_wrap(&formatted_cell, col_width, 0, "", "", true, true);
+        let (formatted_cell, align) = match custom {
+            Some(custom) => (custom.text, custom.align),
+            None => {
+                let text = state
+                    .text_passes
+                    .apply_styled(&format_line(cell, true, true));
+                let text = if state.inline_handlers.is_empty() {
+                    text
+                } else {
+                    state.inline_handlers.apply(&text, col_width, style)
+                };
+                (text, CellAlign::Left)
+            }
+        };
+        aligns.push(align);
+        let breakable = break_long_words(&formatted_cell, col_width);
+        let wrapped = text_wrap(&breakable, col_width, 0, "", "", false, true);
 
         let cell_lines = if wrapped.is_empty() {
             vec![String::new()]
@@ -154,13 +264,25 @@ pub fn render_table_row(
             let content = cell_lines.get(row_idx).cloned().unwrap_or_default();
             let content_len = visible_length(&content);
             let padding = col_width.saturating_sub(content_len);
+            let before = match aligns[col_idx] {
+                CellAlign::Left => 0,
+                CellAlign::Right => padding,
+                CellAlign::Center => padding / 2,
+            };
 
             // Format: bg + " " + content + padding + " "
-            line_parts.push(format!("{} {}{}", bg, content, " ".repeat(padding + 1)));
+            line_parts.push(format!(
+                "{} {}{}{}",
+                bg,
+                " ".repeat(before),
+                content,
+                " ".repeat(padding - before + 1)
+            ));
         }
 
         // Join with separator
-        let joined = line_parts.join(&format!("{}│{}", RESET, separator_fg));
+        let vertical = state.border.glyphs().vertical;
+        let joined = line_parts.join(&format!("{}{}{}", RESET, vertical, separator_fg));
 
         result.push(format!("{}{}{}{}", left_margin, joined, RESET, RESET));
     }
@@ -188,7 +310,7 @@ pub fn render_table_separator(
         "{}{}{}{}",
         left_margin,
         fg,
-        "─".repeat(separator_width),
+        state.border.glyphs().horizontal.repeat(separator_width),
         RESET
     )
 }
@@ -225,6 +347,64 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_balance_widths_proportional() {
+        let widths = balance_widths(&[10, 30], 60);
+        assert_eq!(widths.iter().sum::<usize>(), 60);
+        assert_eq!(widths, vec![15, 45]);
+
+        // Short columns keep a readable floor
+        let widths = balance_widths(&[1, 100], 60);
+        assert_eq!(widths, vec![MIN_COL_WIDTH, 60 - MIN_COL_WIDTH]);
+
+        // Narrow terminals: never wider than available
+        let widths = balance_widths(&[40, 40, 40, 40], 12);
+        assert_eq!(widths.iter().sum::<usize>(), 12);
+    }
+
+    #[test]
+    fn test_widen_only_grows() {
+        let mut state = TableState::new();
+        let cells = |a: &str, b: &str| vec![a.to_string(), b.to_string()];
+        state.calculate_widths_for(&cells("a", "b"), 40);
+        let even = state.column_widths.clone();
+        assert!(!state.widen(&cells("x", "y"), 40));
+        assert!(state.widen(&cells("a much longer cell", "z"), 40));
+        assert!(state.column_widths[0] > even[0]);
+        assert!(!state.widen(&cells("short", "z"), 40));
+        assert!(!state.widen(&vec!["three columns".to_string(); 3], 40));
+    }
+
+    #[test]
+    fn test_wide_table_fits_width() {
+        let mut state = TableState::new();
+        let cells: Vec<String> = (0..8).map(|i| format!("Column {}", i)).collect();
+        let lines = render_table_row(&cells, &mut state, 40, "", &default_style(), false);
+
+        assert!(state.total_width() <= 40);
+        assert!(lines.len() > 1);
+        for line in &lines {
+            assert!(visible_length(line) <= 40);
+        }
+    }
+
+    #[test]
+    fn test_cell_wraps_cjk() {
+        let mut state = TableState::new();
+        state.calculate_widths(2, 27);
+        let cells = vec!["日本語のテキストです".to_string(), "x".to_string()];
+        let lines = render_table_row(&cells, &mut state, 27, "", &default_style(), false);
+
+        assert!(lines.len() > 1);
+        let widths: Vec<usize> = lines.iter().map(|l| visible_length(l)).collect();
+        assert!(widths.iter().all(|&w| w == widths[0]));
+        let joined: String = lines
+            .iter()
+            .map(|l| streamdown_ansi::utils::visible(l))
+            .collect();
+        assert!(joined.contains("日本語"));
+    }
+
     #[test]
     fn test_render_table_row() {
         let mut state = TableState::new();
@@ -236,4 +416,18 @@ mod tests {
         assert!(lines[0].contains("B"));
         assert!(lines[0].contains("C"));
     }
+
+    #[test]
+    fn test_formatter_right_aligns_body_cells() {
+        let mut state = TableState::new();
+        state.formatter = Some(Arc::new(crate::cells::LocaleFormatter::from_tag("en")));
+        let style = default_style();
+        let header = render_table_row(&["10000".to_string()], &mut state, 20, "", &style, false);
+        assert!(streamdown_ansi::utils::visible(&header[0]).starts_with(" 10000 "));
+
+        state.end_header();
+        let row = render_table_row(&["10000".to_string()], &mut state, 20, "", &style, false);
+        let plain = streamdown_ansi::utils::visible(&row[0]);
+        assert!(plain.ends_with(" 10,000 "), "{plain:?}");
+    }
 }
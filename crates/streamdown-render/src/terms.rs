@@ -0,0 +1,280 @@
+//! Text passes marking terms in prose.
+//!
+//! A [`TextPass`] looks at the text of paragraphs, block quotes, list
+//! items, headings and table cells and marks spans of it (glossary terms,
+//! `TODO`/`FIXME`, ticket IDs) with a host-chosen [`TermStyle`]. [`TextPasses`] holds the passes the
+//! renderer runs, registered with
+//! [`Renderer::register_text_pass`](crate::Renderer::register_text_pass);
+//! none are registered by default. Marks are applied before wrapping and
+//! each word of a mark is styled on its own, so a term broken across lines
+//! keeps its style on both.
+//!
+//! ```
+//! use std::sync::Arc;
+//! use streamdown_parser::Parser;
+//! use streamdown_render::{PatternPass, Renderer, TermStyle, TextAttributes};
+//!
+//! let todo = TermStyle {
+//!     attributes: TextAttributes::BOLD,
+//!     ..Default::default()
+//! };
+//! let pass = PatternPass::new().pattern(r"\b(TODO|FIXME)\b", todo).unwrap();
+//! let mut renderer = Renderer::new(Vec::new(), 40);
+//! renderer.register_text_pass("todo", Arc::new(pass));
+//! let mut parser = Parser::new();
+//! renderer.render(&parser.parse_line("TODO: ship it")).unwrap();
+//! let output = String::from_utf8(renderer.into_writer()).unwrap();
+//! assert!(output.contains("\x1b[1mTODO\x1b[22m: ship it"));
+//! ```
+
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+
+use regex::{Regex, RegexBuilder};
+use streamdown_ansi::codes::{BGRESET, FGRESET};
+use streamdown_ansi::utils::{Token, tokens};
+use streamdown_core::{Result, StreamdownError};
+
+use crate::{TextAttributes, bg_color, fg_color};
+
+/// How a marked span is drawn.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TermStyle {
+    /// Text color, if any
+    pub color: Option<String>,
+    /// Background color, if any
+    pub background: Option<String>,
+    /// Attributes
+    pub attributes: TextAttributes,
+}
+
+impl TermStyle {
+    /// `text` with each of its words styled; the spaces between are left
+    /// plain so the style never runs into a line break.
+    pub fn apply(&self, text: &str) -> String {
+        let mut on = self.attributes.on();
+        let mut off = self.attributes.off();
+        if let Some(color) = &self.color {
+            on.push_str(&fg_color(color));
+            off.push_str(FGRESET);
+        }
+        if let Some(background) = &self.background {
+            on.push_str(&bg_color(background));
+            off.push_str(BGRESET);
+        }
+        if on.is_empty() {
+            return text.to_string();
+        }
+        let mut styled = String::with_capacity(text.len());
+        for (i, word) in text.split(' ').enumerate() {
+            if i > 0 {
+                styled.push(' ');
+            }
+            if !word.is_empty() {
+                styled.push_str(&on);
+                styled.push_str(word);
+                styled.push_str(&off);
+            }
+        }
+        styled
+    }
+}
+
+/// Marks spans of prose text.
+pub trait TextPass: fmt::Debug + Send + Sync {
+    /// Byte ranges of the spans to mark in `text`, with their styles.
+    fn mark(&self, text: &str) -> Vec<(Range<usize>, TermStyle)>;
+}
+
+/// Text passes by name.
+#[derive(Debug, Clone, Default)]
+pub struct TextPasses {
+    passes: Vec<(String, Arc<dyn TextPass>)>,
+}
+
+impl TextPasses {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `pass` under `name`, replacing any pass registered under it.
+    /// Earlier passes win where marks overlap.
+    pub fn register(&mut self, name: &str, pass: Arc<dyn TextPass>) {
+        self.passes.retain(|(n, _)| n != name);
+        self.passes.push((name.to_string(), pass));
+    }
+
+    /// Remove the pass registered under `name`.
+    pub fn unregister(&mut self, name: &str) {
+        self.passes.retain(|(n, _)| n != name);
+    }
+
+    /// Whether no pass is registered.
+    pub fn is_empty(&self) -> bool {
+        self.passes.is_empty()
+    }
+
+    /// Names of the registered passes, in registration order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.passes.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// `text` with the spans marked by every pass styled.
+    pub fn apply(&self, text: &str) -> String {
+        if self.passes.is_empty() {
+            return text.to_string();
+        }
+        let mut marks: Vec<(Range<usize>, TermStyle)> = Vec::new();
+        for (_, pass) in &self.passes {
+            for (range, style) in pass.mark(text) {
+                let valid = range.start < range.end
+                    && range.end <= text.len()
+                    && text.is_char_boundary(range.start)
+                    && text.is_char_boundary(range.end);
+                let free = marks
+                    .iter()
+                    .all(|(taken, _)| range.end <= taken.start || taken.end <= range.start);
+                if valid && free {
+                    marks.push((range, style));
+                }
+            }
+        }
+        marks.sort_by_key(|(range, _)| range.start);
+        let mut out = String::with_capacity(text.len());
+        let mut from = 0;
+        for (range, style) in marks {
+            out.push_str(&text[from..range.start]);
+            out.push_str(&style.apply(&text[range.clone()]));
+            from = range.end;
+        }
+        out.push_str(&text[from..]);
+        out
+    }
+
+    /// `text`, already styled with escapes, with the passes applied to the
+    /// text between its escapes.
+    pub fn apply_styled(&self, text: &str) -> String {
+        if self.passes.is_empty() {
+            return text.to_string();
+        }
+        tokens(text)
+            .map(|(_, token)| match token {
+                Token::Escape(escape) => escape.to_string(),
+                Token::Text(text) => self.apply(text),
+            })
+            .collect()
+    }
+}
+
+/// A pass marking whole-word terms and regex matches.
+#[derive(Debug, Clone, Default)]
+pub struct PatternPass {
+    rules: Vec<(Regex, TermStyle)>,
+}
+
+impl PatternPass {
+    /// Create a pass marking nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also mark `term` as a whole word, ignoring case.
+    pub fn term(mut self, term: &str, style: TermStyle) -> Self {
+        let pattern = format!(r"\b{}\b", regex::escape(term));
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(true)
+            .build()
+            .expect("escaped term");
+        self.rules.push((regex, style));
+        self
+    }
+
+    /// Also mark the matches of the regular expression `pattern`.
+    pub fn pattern(mut self, pattern: &str, style: TermStyle) -> Result<Self> {
+        let regex = Regex::new(pattern)
+            .map_err(|e| StreamdownError::Config(format!("Invalid pattern {}: {}", pattern, e)))?;
+        self.rules.push((regex, style));
+        Ok(self)
+    }
+}
+
+impl TextPass for PatternPass {
+    fn mark(&self, text: &str) -> Vec<(Range<usize>, TermStyle)> {
+        self.rules
+            .iter()
+            .flat_map(|(regex, style)| {
+                regex
+                    .find_iter(text)
+                    .map(move |found| (found.range(), style.clone()))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn yellow() -> TermStyle {
+        TermStyle {
+            color: Some("yellow".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_term_style_styles_each_word() {
+        let style = TermStyle {
+            attributes: TextAttributes::UNDERLINE,
+            ..yellow()
+        };
+        let on = format!("\x1b[4m{}", fg_color("yellow"));
+        let off = format!("\x1b[24m{}", FGRESET);
+        assert_eq!(
+            style.apply("rate limit"),
+            format!("{on}rate{off} {on}limit{off}")
+        );
+        assert_eq!(TermStyle::default().apply("as is"), "as is");
+    }
+
+    #[test]
+    fn test_pattern_pass() {
+        let pass = PatternPass::new()
+            .term("rate limit", yellow())
+            .pattern(r"\b[A-Z]+-\d+\b", yellow())
+            .unwrap();
+        let marks: Vec<Range<usize>> = pass
+            .mark("Rate limits hit the Rate Limit (see OPS-42)")
+            .into_iter()
+            .map(|(range, _)| range)
+            .collect();
+        assert_eq!(marks, [20..30, 36..42]);
+        assert!(PatternPass::new().pattern("(", yellow()).is_err());
+    }
+
+    #[test]
+    fn test_apply_keeps_first_of_overlapping_marks() {
+        let mut passes = TextPasses::new();
+        assert_eq!(passes.apply("OPS-42 TODO"), "OPS-42 TODO");
+        let bold = TermStyle {
+            attributes: TextAttributes::BOLD,
+            ..Default::default()
+        };
+        passes.register("todo", Arc::new(PatternPass::new().term("todo", bold)));
+        let tickets = PatternPass::new()
+            .pattern(r"OPS-\d+", yellow())
+            .and_then(|pass| pass.pattern("42 TODO", yellow()));
+        passes.register("tickets", Arc::new(tickets.unwrap()));
+        let yellow = fg_color("yellow");
+        assert_eq!(
+            passes.apply("OPS-42 TODO"),
+            format!("{yellow}OPS-42{FGRESET} \x1b[1mTODO\x1b[22m")
+        );
+        assert_eq!(passes.names().collect::<Vec<_>>(), ["todo", "tickets"]);
+        passes.unregister("todo");
+        passes.unregister("tickets");
+        assert!(passes.is_empty());
+    }
+}
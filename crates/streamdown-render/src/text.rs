@@ -4,8 +4,15 @@
 //! across line breaks, handles CJK characters correctly, and supports various
 //! formatting options.
 
-use streamdown_ansi::utils::{ansi_collapse, extract_ansi_codes, visible, visible_length};
-use streamdown_parser::tokenizer::cjk_count;
+use std::fmt;
+use std::str::FromStr;
+
+use memchr::memchr;
+use streamdown_ansi::utils::{
+    Token, ansi_collapse, extract_ansi_codes, tokens, visible, visible_length,
+};
+use streamdown_ansi::width::{char_width, str_width};
+use streamdown_parser::tokenizer::{cjk_count, is_cjk};
 
 /// Result of wrapping text.
 #[derive(Debug, Clone)]
@@ -36,55 +43,168 @@ impl WrappedText {
     }
 }
 
+/// Result of flowing a span of text onto a line already in progress.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FlowedText {
+    /// Text for each row: the first continues the current line, each of
+    /// the rest starts a new one
+    pub rows: Vec<String>,
+    /// Column the line ends at after the last row
+    pub column: usize,
+}
+
 /// Split text into words while preserving ANSI codes.
 ///
 /// This is smarter than a simple split - it keeps ANSI codes attached
 /// to the words they modify and handles CJK characters specially.
+///
+/// The text between escapes is split in bulk rather than char by char;
+/// escape-free text takes a fast path.
 pub fn split_text(text: &str) -> Vec<String> {
+    if memchr(0x1b, text.as_bytes()).is_none() {
+        return text.split_whitespace().map(str::to_string).collect();
+    }
+
     let mut words = Vec::new();
     let mut current = String::new();
-    let mut in_escape = false;
-    let mut escape_buf = String::new();
-
-    for ch in text.chars() {
-        if in_escape {
-            escape_buf.push(ch);
-            if ch == 'm' {
-                // End of ANSI sequence
-                current.push_str(&escape_buf);
-                escape_buf.clear();
-                in_escape = false;
-            }
-            continue;
+    for (_, token) in tokens(text) {
+        match token {
+            Token::Escape(escape) => current.push_str(escape),
+            Token::Text(plain) => push_plain(plain, &mut current, &mut words),
         }
+    }
 
-        if ch == '\x1b' {
-            in_escape = true;
-            escape_buf.push(ch);
-            continue;
-        }
+    if !current.is_empty() {
+        words.push(current);
+    }
 
-        if ch.is_whitespace() {
-            if !current.is_empty() {
-                words.push(std::mem::take(&mut current));
-            }
-        } else {
-            current.push(ch);
+    words
+}
+
+/// Words for [`text_wrap`], each with whether a space comes before it.
+///
+/// These are the whitespace-separated words of [`split_text`], each
+/// spaced, cut further between CJK characters by [`cjk_breaks`].
+#[cfg(not(feature = "linebreak"))]
+fn wrap_words(text: &str) -> Vec<(String, bool)> {
+    let mut words = Vec::new();
+    for word in split_text(text) {
+        for (i, unit) in cjk_breaks(&word).into_iter().enumerate() {
+            words.push((unit.to_string(), i == 0));
         }
     }
+    words
+}
 
-    // Don't forget trailing escape sequence
-    if !escape_buf.is_empty() {
-        current.push_str(&escape_buf);
+/// Characters that may not start a line (kinsoku): closing brackets,
+/// punctuation, small kana and iteration marks.
+const NO_LINE_START: &[char] = &[
+    '、', '。', '，', '．', '・', '：', '；', '？', '！', 'ー', '々', '〻', 'ゝ', 'ゞ', 'ヽ', 'ヾ',
+    'ぁ', 'ぃ', 'ぅ', 'ぇ', 'ぉ', 'っ', 'ゃ', 'ゅ', 'ょ', 'ゎ', 'ァ', 'ィ', 'ゥ', 'ェ', 'ォ', 'ッ',
+    'ャ', 'ュ', 'ョ', 'ヮ', 'ヵ', 'ヶ', '）', '］', '｝', '〕', '〉', '》', '」', '』', '】', '〙',
+    '〗', '〟', '’', '”', '｠', '»', '゠', '〜', '～', '…', '‥', ',', '.', ':', ';', '?', '!', ')',
+    ']', '}',
+];
+
+/// Characters that may not end a line (kinsoku): opening brackets.
+const NO_LINE_END: &[char] = &[
+    '（', '［', '｛', '〔', '〈', '《', '「', '『', '【', '〘', '〖', '〝', '‘', '“', '｟', '«',
+    '(', '[', '{',
+];
+
+/// Split `word` where a line may break inside it: next to CJK characters,
+/// which run on without spaces.
+///
+/// Basic kinsoku rules apply: no break before closing brackets,
+/// punctuation or small kana, or after an opening bracket.
+/// Escapes between two pieces go with the second. The pieces concatenate
+/// back to `word`.
+pub fn cjk_breaks(word: &str) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut from = 0;
+    // Where a piece starting at the next visible character begins
+    let mut cut = 0;
+    let mut prev: Option<char> = None;
+    let chars = tokens(word).flat_map(|(at, token)| match token {
+        Token::Escape(_) => None,
+        Token::Text(text) => Some(text.char_indices().map(move |(i, ch)| (at + i, ch))),
+    });
+    for (i, ch) in chars.flatten() {
+        if let Some(prev) = prev
+            && (is_cjk(prev) || is_cjk(ch))
+            && !prev.is_whitespace()
+            && !ch.is_whitespace()
+            && !NO_LINE_START.contains(&ch)
+            && !NO_LINE_END.contains(&prev)
+        {
+            pieces.push(&word[from..cut]);
+            from = cut;
+        }
+        prev = Some(ch);
+        cut = i + ch.len_utf8();
     }
+    pieces.push(&word[from..]);
+    pieces
+}
 
-    if !current.is_empty() {
-        words.push(current);
+/// Words for [`text_wrap`], each with whether a space comes before it.
+///
+/// Words end at the Unicode line break opportunities found by
+/// [`split_breakable`](streamdown_ansi::linebreak::split_breakable), so
+/// `well-known` may wrap after its hyphen but `( x )` stays whole. Runs of
+/// whitespace between words collapse to one space, as in [`split_text`].
+#[cfg(feature = "linebreak")]
+fn wrap_words(text: &str) -> Vec<(String, bool)> {
+    let mut words = Vec::new();
+    let mut spaced = true;
+    let mut word = String::new();
+    for piece in streamdown_ansi::linebreak::split_breakable(text) {
+        // Drop the trailing whitespace, keeping any escapes among it
+        let mut whitespace = String::new();
+        for (_, token) in tokens(piece) {
+            let text = match token {
+                Token::Escape(escape) => {
+                    word.push_str(escape);
+                    continue;
+                }
+                Token::Text(text) => text,
+            };
+            for ch in text.chars() {
+                if ch.is_whitespace() {
+                    whitespace.push(ch);
+                } else {
+                    word.push_str(&whitespace);
+                    whitespace.clear();
+                    word.push(ch);
+                }
+            }
+        }
+        // A piece of only whitespace and escapes goes with the next word
+        if visible_length(&word) > 0 {
+            words.push((std::mem::take(&mut word), spaced));
+            spaced = false;
+        }
+        spaced |= !whitespace.is_empty();
+    }
+    if !word.is_empty() {
+        match words.last_mut() {
+            Some((last, _)) => last.push_str(&word),
+            None => words.push((word, true)),
+        }
     }
-
     words
 }
 
+/// Append an escape-free segment, flushing the current word at whitespace.
+fn push_plain(segment: &str, current: &mut String, words: &mut Vec<String>) {
+    for (i, piece) in segment.split(char::is_whitespace).enumerate() {
+        if i > 0 && !current.is_empty() {
+            words.push(std::mem::take(current));
+        }
+        current.push_str(piece);
+    }
+}
+
 /// Wrap text to fit within a given width.
 ///
 /// This is ANSI-aware and will preserve formatting across line breaks.
@@ -110,7 +230,7 @@ pub fn text_wrap(
         return WrappedText::empty();
     }
 
-    let words = split_text(text);
+    let words = wrap_words(text);
     if words.is_empty() {
         return WrappedText::empty();
     }
@@ -123,7 +243,8 @@ pub fn text_wrap(
 
     let mut prev_word = String::new();
 
-    for word in words.iter().chain(std::iter::once(&String::new())) {
+    let end = (String::new(), true);
+    for (word, spaced) in words.iter().chain(std::iter::once(&end)) {
         // Extract ANSI codes from the word
         let codes = extract_ansi_codes(word);
 
@@ -136,7 +257,7 @@ pub fn text_wrap(
         let line_visible_len = visible_length(&current_line);
 
         // Check if word fits on current line
-        let space_needed = if current_line.is_empty() || word_visible_len == 0 {
+        let space_needed = if current_line.is_empty() || word_visible_len == 0 || !spaced {
             0
         } else {
             1 // space between words
@@ -243,36 +364,354 @@ pub fn text_wrap(
     WrappedText { lines, truncated }
 }
 
+/// Flow a span of text onto a line `width` wide that already reaches
+/// `column`.
+///
+/// The span breaks at spaces, and between CJK characters, before a word
+/// that would run past the edge; spaces starting a new row are dropped.
+/// Unlike [`text_wrap`], rows are neither prefixed nor padded, so spans
+/// flowed one after another continue each other's lines.
+pub fn flow_text(text: &str, width: usize, column: usize) -> FlowedText {
+    let mut rows: Vec<String> = Vec::new();
+    let mut column = column;
+    for piece in text.split_inclusive(' ').flat_map(cjk_breaks) {
+        if rows.is_empty() {
+            rows.push(String::new());
+        }
+        let word = visible_length(piece.trim_end_matches(' '));
+        if column > 0 && column + word > width {
+            rows.push(String::new());
+            column = 0;
+        }
+        let piece = if column == 0 {
+            piece.trim_start_matches(' ')
+        } else {
+            piece
+        };
+        if let Some(row) = rows.last_mut() {
+            row.push_str(piece);
+        }
+        column += visible_length(piece);
+    }
+    FlowedText { rows, column }
+}
+
 /// Truncate a string (with ANSI codes) to a visible length.
-fn truncate_to_visible(text: &str, max_visible: usize) -> String {
+pub(crate) fn truncate_to_visible(text: &str, max_visible: usize) -> String {
     let mut result = String::new();
     let mut visible_count = 0;
-    let mut in_escape = false;
 
-    for ch in text.chars() {
-        if in_escape {
-            result.push(ch);
-            if ch == 'm' {
-                in_escape = false;
+    for (_, token) in tokens(text) {
+        let text = match token {
+            Token::Escape(escape) => {
+                result.push_str(escape);
+                continue;
             }
+            Token::Text(text) => text,
+        };
+        for ch in text.chars() {
+            if visible_count >= max_visible {
+                return result;
+            }
+            result.push(ch);
+            visible_count += char_width(ch).unwrap_or(1);
+        }
+    }
+
+    result
+}
+
+/// Break words wider than `width` into pieces that fit.
+///
+/// ANSI-aware and width-aware (CJK characters count as two columns). Words
+/// that already fit are left alone; the result is rejoined with spaces so it
+/// can be passed straight to [`text_wrap`], which keeps CJK pieces together.
+pub fn break_long_words(text: &str, width: usize) -> String {
+    if width == 0 {
+        return text.to_string();
+    }
+
+    let mut pieces = Vec::new();
+    for word in split_text(text) {
+        if visible_length(&word) <= width {
+            pieces.push(word);
             continue;
         }
 
-        if ch == '\x1b' {
-            in_escape = true;
-            result.push(ch);
+        let mut piece = String::new();
+        let mut piece_width = 0;
+        for (_, token) in tokens(&word) {
+            let text = match token {
+                Token::Escape(escape) => {
+                    piece.push_str(escape);
+                    continue;
+                }
+                Token::Text(text) => text,
+            };
+            for ch in text.chars() {
+                let ch_width = char_width(ch).unwrap_or(0);
+                if piece_width + ch_width > width && piece_width > 0 {
+                    pieces.push(std::mem::take(&mut piece));
+                    piece_width = 0;
+                }
+                piece.push(ch);
+                piece_width += ch_width;
+            }
+        }
+        if !piece.is_empty() {
+            pieces.push(piece);
+        }
+    }
+    pieces.join(" ")
+}
+
+/// Where a word may be hyphenated.
+///
+/// [`hyphenate_long_words`] breaks a word wider than the line at its widest
+/// fitting syllable boundary. Hosts can plug in a dictionary-based
+/// hyphenator (e.g. the `hyphenation` crate) through
+/// [`Renderer::set_hyphenator`](crate::Renderer::set_hyphenator); the
+/// default, [`WidthHyphenator`], knows no syllables and breaks at the width.
+pub trait Hyphenator: fmt::Debug + Send + Sync {
+    /// Byte offsets into `word` (plain text, no escapes) where it may be
+    /// broken with a hyphen.
+    fn breaks(&self, word: &str) -> Vec<usize>;
+}
+
+/// A [`Hyphenator`] without syllable knowledge: words are broken after an
+/// existing hyphen or at the line width.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WidthHyphenator;
+
+impl Hyphenator for WidthHyphenator {
+    fn breaks(&self, _word: &str) -> Vec<usize> {
+        Vec::new()
+    }
+}
+
+/// Hyphenate words wider than `width` into pieces that fit.
+///
+/// Like [`break_long_words`], but each piece but the last ends in a hyphen:
+/// the word is broken after one of its own hyphens or at a syllable from
+/// `hyphenator` when one fits, otherwise at the width. No hyphen is added
+/// between wide (CJK) characters or next to punctuation. Escapes stay with
+/// the text they style, so [`text_wrap`] carries the ANSI state across the
+/// break.
+pub fn hyphenate_long_words(text: &str, width: usize, hyphenator: &dyn Hyphenator) -> String {
+    if width == 0 {
+        return text.to_string();
+    }
+
+    let mut pieces = Vec::new();
+    for word in split_text(text) {
+        if visible_length(&word) <= width {
+            pieces.push(word);
             continue;
         }
 
-        if visible_count >= max_visible {
-            break;
+        let cuts = hyphen_cuts(&visible(&word), width, hyphenator);
+        let mut cuts = cuts.into_iter().peekable();
+        let mut piece = String::new();
+        let mut pending = String::new();
+        let mut offset = 0;
+        for (_, token) in tokens(&word) {
+            let text = match token {
+                Token::Escape(escape) => {
+                    pending.push_str(escape);
+                    continue;
+                }
+                Token::Text(text) => text,
+            };
+            for ch in text.chars() {
+                if let Some(&(at, hyphen)) = cuts.peek()
+                    && at == offset
+                {
+                    if hyphen {
+                        piece.push('-');
+                    }
+                    pieces.push(std::mem::take(&mut piece));
+                    cuts.next();
+                }
+                piece.push_str(&pending);
+                pending.clear();
+                piece.push(ch);
+                offset += ch.len_utf8();
+            }
+        }
+        piece.push_str(&pending);
+        pieces.push(piece);
+    }
+    pieces.join(" ")
+}
+
+/// Byte offsets into `plain` where it is cut to fit `width`, each with
+/// whether a hyphen is added there.
+fn hyphen_cuts(plain: &str, width: usize, hyphenator: &dyn Hyphenator) -> Vec<(usize, bool)> {
+    let syllables = hyphenator.breaks(plain);
+    let hyphenable = |at: usize| {
+        let before = plain[..at].chars().next_back();
+        let after = plain[at..].chars().next();
+        [before, after]
+            .into_iter()
+            .all(|ch| ch.is_some_and(|ch| ch.is_alphanumeric() && char_width(ch).unwrap_or(1) < 2))
+    };
+
+    let mut cuts = Vec::new();
+    let mut start = 0;
+    while str_width(&plain[start..]) > width {
+        let mut best = None;
+        let mut hard = None;
+        let mut column = 0;
+        for (i, ch) in plain[start..].char_indices() {
+            let at = start + i;
+            if at > start {
+                let hyphen = width > 1 && hyphenable(at);
+                if column + usize::from(hyphen) <= width {
+                    hard = Some((at, hyphen));
+                }
+                if plain[..at].ends_with('-') {
+                    best = Some((at, false));
+                } else if hyphen && column < width && syllables.contains(&at) {
+                    best = Some((at, true));
+                }
+            }
+            column += char_width(ch).unwrap_or(0);
+            if column > width {
+                break;
+            }
+        }
+        let next = start + plain[start..].chars().next().map_or(0, char::len_utf8);
+        let cut = best.or(hard).unwrap_or((next, false));
+        cuts.push(cut);
+        start = cut.0;
+    }
+    cuts
+}
+
+/// Horizontal placement of wrapped text in its column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum TextAlign {
+    /// Flush with the left edge, ragged right
+    #[default]
+    Left,
+    /// Flush with the right edge, ragged left
+    Right,
+    /// Centered, ragged on both sides
+    Center,
+    /// Flush with both edges by widening the spaces between words; the
+    /// last line of a paragraph stays flush left
+    Justify,
+}
+
+impl TextAlign {
+    /// All alignments, in declaration order.
+    pub const ALL: [TextAlign; 4] = [
+        TextAlign::Left,
+        TextAlign::Right,
+        TextAlign::Center,
+        TextAlign::Justify,
+    ];
+
+    /// Name used on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            TextAlign::Left => "left",
+            TextAlign::Right => "right",
+            TextAlign::Center => "center",
+            TextAlign::Justify => "justify",
         }
+    }
+}
 
-        result.push(ch);
-        visible_count += unicode_width::UnicodeWidthChar::width(ch).unwrap_or(1);
+impl fmt::Display for TextAlign {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
     }
+}
 
-    result
+impl FromStr for TextAlign {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TextAlign::ALL
+            .into_iter()
+            .find(|align| align.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                format!(
+                    "unknown alignment '{}' (expected left, right, center or justify)",
+                    s
+                )
+            })
+    }
+}
+
+/// Place one wrapped `line` in a column `width` wide.
+///
+/// ANSI-aware: escapes take no columns, and justification widens only the
+/// spaces of the text. Trailing spaces are dropped. `last` marks the last
+/// line of a paragraph, which [`TextAlign::Justify`] leaves flush left.
+pub fn align_line(line: &str, width: usize, align: TextAlign, last: bool) -> String {
+    // Split into escapes and single characters
+    let mut pieces: Vec<(&str, bool)> = Vec::new();
+    for (_, token) in tokens(line) {
+        match token {
+            Token::Escape(escape) => pieces.push((escape, true)),
+            Token::Text(text) => pieces.extend(
+                text.char_indices()
+                    .map(|(i, ch)| (&text[i..i + ch.len_utf8()], false)),
+            ),
+        }
+    }
+
+    // Drop trailing spaces, keeping the escapes after them
+    let end = pieces
+        .iter()
+        .rposition(|&(token, escape)| !escape && token != " ")
+        .map_or(0, |i| i + 1);
+    let mut kept = 0;
+    pieces.retain(|&(token, escape)| {
+        kept += 1;
+        kept <= end || escape || token != " "
+    });
+
+    let text_width: usize = pieces
+        .iter()
+        .filter(|(_, escape)| !escape)
+        .map(|(token, _)| str_width(token))
+        .sum();
+    let spare = width.saturating_sub(text_width);
+    let joined = |pieces: &[(&str, bool)]| pieces.iter().map(|(token, _)| *token).collect();
+
+    match align {
+        TextAlign::Left => joined(&pieces),
+        TextAlign::Right => " ".repeat(spare) + &joined(&pieces),
+        TextAlign::Center => " ".repeat(spare / 2) + &joined(&pieces),
+        TextAlign::Justify => {
+            let gaps = pieces
+                .iter()
+                .filter(|&&(token, escape)| !escape && token == " ")
+                .count();
+            if last || gaps == 0 {
+                return joined(&pieces);
+            }
+            let mut result = String::with_capacity(line.len() + spare);
+            let mut gap = 0;
+            for (token, escape) in pieces {
+                result.push_str(token);
+                if !escape && token == " " {
+                    let extra = spare / gaps + usize::from(gap < spare % gaps);
+                    result.push_str(&" ".repeat(extra));
+                    gap += 1;
+                }
+            }
+            result
+        }
+    }
 }
 
 /// Simple text wrap without ANSI awareness (for plain text).
@@ -285,8 +724,8 @@ pub fn simple_wrap(text: &str, width: usize) -> Vec<String> {
     let mut current = String::new();
 
     for word in text.split_whitespace() {
-        let word_len = unicode_width::UnicodeWidthStr::width(word);
-        let current_len = unicode_width::UnicodeWidthStr::width(current.as_str());
+        let word_len = str_width(word);
+        let current_len = str_width(&current);
 
         if current.is_empty() {
             current = word.to_string();
@@ -314,6 +753,165 @@ pub fn simple_wrap(text: &str, width: usize) -> Vec<String> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_break_long_words() {
+        assert_eq!(break_long_words("ab abcdefg", 3), "ab abc def g");
+        // Wide characters count double
+        assert_eq!(break_long_words("日本語です", 4), "日本 語で す");
+        // Escapes don't count toward width
+        let broken = break_long_words("\x1b[1mabcd\x1b[0m", 2);
+        assert_eq!(visible(&broken), "ab cd");
+        let link = "\x1b]8;;https://m.example\x1b\\abcd\x1b]8;;\x1b\\";
+        assert_eq!(
+            break_long_words(link, 2),
+            "\x1b]8;;https://m.example\x1b\\ab cd\x1b]8;;\x1b\\"
+        );
+    }
+
+    #[test]
+    fn test_hyphenate_long_words() {
+        let width = WidthHyphenator;
+        assert_eq!(
+            hyphenate_long_words("a abcdefgh", 4, &width),
+            "a abc- def- gh"
+        );
+        // Existing hyphens are preferred, and don't get a second one
+        assert_eq!(hyphenate_long_words("well-known", 6, &width), "well- known");
+        // No hyphen between wide characters
+        assert_eq!(
+            hyphenate_long_words("日本語です", 4, &width),
+            "日本 語で す"
+        );
+        // Escapes move with the text they style
+        let styled = hyphenate_long_words("ab\x1b[1mcdef\x1b[0m", 4, &width);
+        assert_eq!(styled, "ab\x1b[1mc- def\x1b[0m");
+        // Hyperlinks are escapes too, whatever their URL holds
+        let link = "\x1b]8;;https://example.com\x1b\\abcdefgh\x1b]8;;\x1b\\";
+        assert_eq!(
+            hyphenate_long_words(link, 4, &width),
+            "\x1b]8;;https://example.com\x1b\\abc- def- gh\x1b]8;;\x1b\\"
+        );
+    }
+
+    #[test]
+    fn test_hyphenator_syllables() {
+        // hy-phen-a-tion
+        #[derive(Debug)]
+        struct Syllables;
+        impl Hyphenator for Syllables {
+            fn breaks(&self, _word: &str) -> Vec<usize> {
+                vec![2, 6, 7]
+            }
+        }
+        assert_eq!(
+            hyphenate_long_words("hyphenation", 8, &Syllables),
+            "hyphena- tion"
+        );
+        assert_eq!(
+            hyphenate_long_words("hyphenation", 6, &Syllables),
+            "hy- phena- tion"
+        );
+    }
+
+    #[cfg(feature = "linebreak")]
+    #[test]
+    fn test_text_wrap_unicode_breaks() {
+        let wrap = |text: &str, width: usize| text_wrap(text, width, 0, "", "", false, true).lines;
+        let trimmed = |lines: Vec<String>| {
+            lines
+                .iter()
+                .map(|line| line.trim_end().to_string())
+                .collect::<Vec<_>>()
+        };
+        // After a hyphen, without adding a space when rejoined
+        assert_eq!(
+            trimmed(wrap("a well-known fact", 9)),
+            ["a well-", "known", "fact"]
+        );
+        assert_eq!(
+            trimmed(wrap("a well-known fact", 20)),
+            ["a well-known fact"]
+        );
+        // Never before closing punctuation
+        assert_eq!(trimmed(wrap("see (the docs )", 11)), ["see (the", "docs )"]);
+        // Escapes are kept
+        let styled = wrap("\x1b[1mwell-known\x1b[22m", 6);
+        assert_eq!(visible(&styled.concat()).replace(' ', ""), "well-known");
+    }
+
+    #[test]
+    fn test_cjk_breaks() {
+        assert_eq!(cjk_breaks("日本語"), ["日", "本", "語"]);
+        assert_eq!(cjk_breaks("plain"), ["plain"]);
+        // Not before closing punctuation or small kana, not after an opening bracket
+        assert_eq!(
+            cjk_breaks("「ちょっと」です。"),
+            ["「ちょっ", "と」", "で", "す。"]
+        );
+        // Next to Latin text, and escapes go with the following piece
+        assert_eq!(cjk_breaks("用Rust"), ["用", "Rust"]);
+        assert_eq!(cjk_breaks("日\x1b[1m本"), ["日", "\x1b[1m本"]);
+        // Hyperlinks are escapes too, whatever their URL holds
+        let link = "\x1b]8;;https://m.example/日本\x1b\\日本\x1b]8;;\x1b\\";
+        assert_eq!(
+            cjk_breaks(link),
+            ["\x1b]8;;https://m.example/日本\x1b\\日", "本\x1b]8;;\x1b\\"]
+        );
+    }
+
+    #[cfg(not(feature = "linebreak"))]
+    #[test]
+    fn test_text_wrap_cjk_without_spaces() {
+        let text = "日本語の文章には空白がありません。";
+        let wrapped = text_wrap(text, 10, 0, "", "", false, true);
+        assert!(wrapped.len() > 1);
+        for line in &wrapped.lines {
+            assert!(visible_length(line) <= 10, "{line}");
+            assert!(!line.starts_with('。'), "{line}");
+        }
+        assert_eq!(visible(&wrapped.lines.concat()).replace(' ', ""), text);
+    }
+
+    #[test]
+    fn test_align_line() {
+        assert_eq!(align_line("a b  ", 7, TextAlign::Left, false), "a b");
+        assert_eq!(align_line("a b", 7, TextAlign::Right, false), "    a b");
+        assert_eq!(align_line("a b", 7, TextAlign::Center, false), "  a b");
+        // Extra spaces go to the leftmost gaps first
+        assert_eq!(
+            align_line("a b c ", 8, TextAlign::Justify, false),
+            "a   b  c"
+        );
+        assert_eq!(align_line("a b c", 8, TextAlign::Justify, true), "a b c");
+        assert_eq!(align_line("abc", 8, TextAlign::Justify, false), "abc");
+        // Escapes take no columns and stay in place
+        let styled = align_line("\x1b[1ma b\x1b[0m \x1b[22m", 5, TextAlign::Justify, false);
+        assert_eq!(styled, "\x1b[1ma   b\x1b[0m\x1b[22m");
+        assert_eq!(
+            visible_length(&align_line("日本 語", 9, TextAlign::Right, false)),
+            9
+        );
+        // Spaces in a hyperlink's URL are not gaps
+        let link = "\x1b]8;;https://example.com/a b\x1b\\a b\x1b]8;;\x1b\\";
+        assert_eq!(
+            align_line(link, 5, TextAlign::Justify, false),
+            "\x1b]8;;https://example.com/a b\x1b\\a   b\x1b]8;;\x1b\\"
+        );
+        assert_eq!(
+            visible_length(&align_line(link, 9, TextAlign::Right, false)),
+            9
+        );
+    }
+
+    #[test]
+    fn test_align_from_str() {
+        for align in TextAlign::ALL {
+            assert_eq!(align.name().parse::<TextAlign>(), Ok(align));
+        }
+        assert_eq!("Justify".parse::<TextAlign>(), Ok(TextAlign::Justify));
+        assert!("middle".parse::<TextAlign>().is_err());
+    }
+
     #[test]
     fn test_split_text() {
         let words = split_text("hello world");
@@ -328,6 +926,16 @@ mod tests {
         assert!(words[0].contains("\x1b[1m"));
     }
 
+    #[test]
+    fn test_split_text_escape_boundaries() {
+        let text = "  a\x1b[1mb c\x1b[0m  \x1b[3m d\u{3000}e\x1b[";
+        let words = split_text(text);
+        assert_eq!(
+            words,
+            vec!["a\x1b[1mb", "c\x1b[0m", "\x1b[3m", "d", "e\x1b["]
+        );
+    }
+
     #[test]
     fn test_simple_wrap() {
         let lines = simple_wrap("hello world foo bar", 10);
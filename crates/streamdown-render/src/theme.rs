@@ -0,0 +1,568 @@
+//! Theme files.
+//!
+//! With the `serde` feature, loads a [`RenderStyle`] from a TOML or JSON
+//! theme file so themes can be maintained and shared outside of Rust code.
+//! Missing keys keep their defaults, unknown keys produce warnings, and
+//! every color must be a Colodore preset name or a `#rrggbb` hex value.
+//!
+//! ```toml
+//! h1 = "#ff8800"
+//! h2 = "yellow"
+//! code_bg = "#1a1a1a"
+//! ```
+//!
+//! Applications shipping a theme can check it at build time instead: call
+//! [`embed_theme`] from `build.rs`, which fails the build on any error or
+//! unknown key, then load the checked theme with
+//! [`include_theme!`](crate::include_theme):
+//!
+//! ```ignore
+//! // build.rs
+//! fn main() {
+//!     streamdown_render::theme::embed_theme("themes/ocean.toml").unwrap();
+//! }
+//!
+//! // src/main.rs
+//! let style = streamdown_render::include_theme!("themes/ocean.toml");
+//! ```
+//!
+//! `RenderStyle` owns its strings, so the macro evaluates to a value rather
+//! than a `const`; parsing the embedded text can't fail.
+//!
+//! With the `syntax-highlighting` feature, a Sublime Text or bat `.tmTheme`
+//! color scheme can stand in for a theme file: [`RenderStyle::from_tm_theme`]
+//! maps its scope colors onto the style's slots (headings from
+//! `markup.heading` and `keyword`, code backgrounds from the editor
+//! background, and so on), and the same file serves as the highlight theme.
+
+use std::path::Path;
+#[cfg(feature = "serde")]
+use std::str::FromStr;
+
+use streamdown_core::{Result, StreamdownError};
+#[cfg(feature = "syntax-highlighting")]
+use streamdown_syntax::ThemePalette;
+
+use crate::colors::Color;
+use crate::{RenderError, RenderResult, RenderStyle};
+
+/// Theme file format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemeFormat {
+    /// TOML table of `key = "color"` pairs
+    Toml,
+    /// JSON object of `"key": "color"` pairs
+    Json,
+}
+
+impl ThemeFormat {
+    /// Guess the format from a file extension, defaulting to TOML.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Self::Json,
+            _ => Self::Toml,
+        }
+    }
+
+    /// Guess the format from content: a leading `{` means JSON.
+    pub fn sniff(content: &str) -> Self {
+        if content.trim_start().starts_with('{') {
+            Self::Json
+        } else {
+            Self::Toml
+        }
+    }
+}
+
+/// Check whether a color is a preset or terminal color name, a `#rrggbb`
+/// hex value, `ansi:NAME` or `ansi256:N` (see [`Color`]).
+pub fn is_valid_color(color: &str) -> bool {
+    Color::parse(color).is_some()
+}
+
+impl RenderStyle {
+    /// Load a theme file, choosing TOML or JSON by extension.
+    ///
+    /// Unknown keys are reported with `log::warn!`. Without the `serde`
+    /// feature only `.tmTheme` files can be loaded.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        #[cfg(feature = "syntax-highlighting")]
+        if is_tm_theme(path) {
+            return Self::from_tm_theme(path);
+        }
+        Self::from_theme_file(path)
+    }
+
+    #[cfg(feature = "serde")]
+    fn from_theme_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let (style, warnings) =
+            Self::parse_theme(&content, ThemeFormat::from_path(path)).map_err(|e| {
+                StreamdownError::Config(format!("Theme error in {}: {}", path.display(), e))
+            })?;
+        for warning in warnings {
+            log::warn!("{}: {}", path.display(), warning);
+        }
+        Ok(style)
+    }
+
+    #[cfg(not(feature = "serde"))]
+    fn from_theme_file(path: &Path) -> Result<Self> {
+        Err(StreamdownError::Config(format!(
+            "Theme error in {}: theme files need the `serde` feature",
+            path.display()
+        )))
+    }
+
+    /// Parse a theme, returning the style and any unknown-key warnings.
+    #[cfg(feature = "serde")]
+    pub fn parse_theme(content: &str, format: ThemeFormat) -> Result<(Self, Vec<String>)> {
+        let (style, keys): (Self, Vec<String>) = match format {
+            ThemeFormat::Toml => {
+                let table: toml::Table = toml::from_str(content)
+                    .map_err(|e| StreamdownError::Config(format!("Parse error: {}", e)))?;
+                let keys = table.keys().cloned().collect();
+                let style = table
+                    .try_into()
+                    .map_err(|e| StreamdownError::Config(format!("Parse error: {}", e)))?;
+                (style, keys)
+            }
+            ThemeFormat::Json => {
+                let value: serde_json::Value = serde_json::from_str(content)
+                    .map_err(|e| StreamdownError::Config(format!("Parse error: {}", e)))?;
+                let keys = value
+                    .as_object()
+                    .map(|o| o.keys().cloned().collect())
+                    .unwrap_or_default();
+                let style = serde_json::from_value(value)
+                    .map_err(|e| StreamdownError::Config(format!("Parse error: {}", e)))?;
+                (style, keys)
+            }
+        };
+
+        // Every serialized field is a known key
+        let known = toml::Table::try_from(Self::default())
+            .map_err(|e| StreamdownError::Config(e.to_string()))?;
+        let warnings = keys
+            .into_iter()
+            .filter(|k| !known.contains_key(k))
+            .map(|k| format!("unknown theme key `{}`", k))
+            .collect();
+
+        style.validate()?;
+        Ok((style, warnings))
+    }
+
+    /// Check that every color parses as a [`Color`].
+    pub fn validate(&self) -> RenderResult<()> {
+        for (key, color) in self.colors() {
+            if !is_valid_color(color) {
+                return Err(RenderError::InvalidColor {
+                    key: key.to_string(),
+                    value: color.to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Theme keys holding colors, in declaration order.
+    pub fn color_keys() -> [&'static str; 24] {
+        [
+            "h1",
+            "h2",
+            "h3",
+            "h4",
+            "h5",
+            "h6",
+            "code_bg",
+            "code_label",
+            "bullet",
+            "task_checked",
+            "task_unchecked",
+            "table_header_bg",
+            "table_border",
+            "blockquote_border",
+            "think_border",
+            "hr",
+            "link_url",
+            "image_marker",
+            "footnote",
+            "spoiler",
+            "details_marker",
+            "kbd",
+            "diff_add",
+            "diff_remove",
+        ]
+    }
+
+    /// Color values paired with their theme keys.
+    pub fn colors(&self) -> [(&'static str, &str); 24] {
+        let values = [
+            &self.h1,
+            &self.h2,
+            &self.h3,
+            &self.h4,
+            &self.h5,
+            &self.h6,
+            &self.code_bg,
+            &self.code_label,
+            &self.bullet,
+            &self.task_checked,
+            &self.task_unchecked,
+            &self.table_header_bg,
+            &self.table_border,
+            &self.blockquote_border,
+            &self.think_border,
+            &self.hr,
+            &self.link_url,
+            &self.image_marker,
+            &self.footnote,
+            &self.spoiler,
+            &self.details_marker,
+            &self.kbd,
+            &self.diff_add,
+            &self.diff_remove,
+        ];
+        let keys = Self::color_keys();
+        std::array::from_fn(|i| (keys[i], values[i].as_str()))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl FromStr for RenderStyle {
+    type Err = StreamdownError;
+
+    /// Parse a TOML or JSON theme (sniffed from the content).
+    ///
+    /// Unknown keys are reported with `log::warn!`.
+    fn from_str(s: &str) -> Result<Self> {
+        let (style, warnings) = Self::parse_theme(s, ThemeFormat::sniff(s))?;
+        for warning in warnings {
+            log::warn!("{}", warning);
+        }
+        Ok(style)
+    }
+}
+
+/// Whether `path` names a `.tmTheme` color scheme.
+pub fn is_tm_theme(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("tmTheme"))
+}
+
+#[cfg(feature = "syntax-highlighting")]
+impl RenderStyle {
+    /// Convert a `.tmTheme` color scheme into a style.
+    ///
+    /// Slots the scheme has no color for keep their defaults; pass the same
+    /// file to [`Renderer::set_highlight_theme`](crate::Renderer::set_highlight_theme)
+    /// to highlight code with it too.
+    pub fn from_tm_theme(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::from_palette(&ThemePalette::from_file(path.as_ref())?))
+    }
+
+    /// Style colored from a syntax theme's palette.
+    pub fn from_palette(palette: &ThemePalette) -> Self {
+        let defaults = Self::default();
+        let pick = |colors: &[&Option<String>], default: &str| {
+            colors
+                .iter()
+                .find_map(|color| color.as_deref())
+                .unwrap_or(default)
+                .to_string()
+        };
+        let p = palette;
+        Self {
+            h1: pick(&[&p.heading, &p.keyword], &defaults.h1),
+            h2: pick(&[&p.function, &p.keyword], &defaults.h2),
+            h3: pick(&[&p.string], &defaults.h3),
+            h4: pick(&[&p.type_name], &defaults.h4),
+            h5: pick(&[&p.constant], &defaults.h5),
+            h6: pick(&[&p.comment], &defaults.h6),
+            code_bg: pick(&[&p.background], &defaults.code_bg),
+            code_label: pick(&[&p.comment], &defaults.code_label),
+            bullet: pick(&[&p.keyword], &defaults.bullet),
+            task_checked: pick(&[&p.string], &defaults.task_checked),
+            task_unchecked: pick(&[&p.comment], &defaults.task_unchecked),
+            table_header_bg: pick(
+                &[&p.selection, &p.line_highlight],
+                &defaults.table_header_bg,
+            ),
+            table_border: pick(&[&p.gutter, &p.comment], &defaults.table_border),
+            blockquote_border: pick(&[&p.quote, &p.comment], &defaults.blockquote_border),
+            think_border: pick(&[&p.comment], &defaults.think_border),
+            hr: pick(&[&p.gutter, &p.comment], &defaults.hr),
+            link_url: pick(&[&p.link, &p.function], &defaults.link_url),
+            image_marker: pick(&[&p.constant], &defaults.image_marker),
+            footnote: pick(&[&p.constant], &defaults.footnote),
+            spoiler: pick(&[&p.comment], &defaults.spoiler),
+            details_marker: pick(&[&p.keyword], &defaults.details_marker),
+            kbd: pick(&[&p.type_name], &defaults.kbd),
+            ..defaults
+        }
+    }
+}
+
+/// Directory under `OUT_DIR` holding themes checked by [`embed_theme`].
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+pub const EMBEDDED_THEME_DIR: &str = "streamdown-themes";
+
+/// Check a theme file from a build script and stage it for
+/// [`include_theme!`](crate::include_theme).
+///
+/// `path` is relative to the package root (the build script's working
+/// directory) and must be passed to the macro unchanged. Unknown keys are
+/// errors here, since they can only be typos in a shipped theme. Cargo is
+/// told to rerun the build script when the file changes.
+#[cfg(feature = "serde")]
+pub fn embed_theme(path: impl AsRef<Path>) -> Result<()> {
+    let out_dir = std::env::var_os("OUT_DIR").ok_or_else(|| {
+        StreamdownError::Config("embed_theme must be called from a build script".into())
+    })?;
+    let root = std::env::var_os("CARGO_MANIFEST_DIR").unwrap_or_else(|| ".".into());
+    let path = path.as_ref();
+    println!("cargo:rerun-if-changed={}", path.display());
+    stage_theme(Path::new(&root), path, Path::new(&out_dir))
+}
+
+/// Validate `root/path` and write its normalized TOML to `out_dir`.
+#[cfg(feature = "serde")]
+fn stage_theme(root: &Path, path: &Path, out_dir: &Path) -> Result<()> {
+    if path.is_absolute()
+        || path
+            .components()
+            .any(|c| matches!(c, std::path::Component::ParentDir))
+    {
+        return Err(StreamdownError::Config(format!(
+            "theme path {} must be relative to the package root",
+            path.display()
+        )));
+    }
+
+    let content = std::fs::read_to_string(root.join(path))?;
+    let (style, warnings) = RenderStyle::parse_theme(&content, ThemeFormat::from_path(path))
+        .map_err(|e| {
+            StreamdownError::Config(format!("Theme error in {}: {}", path.display(), e))
+        })?;
+    if let Some(warning) = warnings.first() {
+        return Err(StreamdownError::Config(format!(
+            "Theme error in {}: {}",
+            path.display(),
+            warning
+        )));
+    }
+
+    let staged = out_dir.join(EMBEDDED_THEME_DIR).join(path);
+    if let Some(parent) = staged.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let normalized = toml::to_string(&style)
+        .map_err(|e| StreamdownError::Config(format!("Serialization error: {}", e)))?;
+    std::fs::write(staged, normalized)?;
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+impl RenderStyle {
+    /// Parse a theme staged by [`embed_theme`]; used by
+    /// [`include_theme!`](crate::include_theme).
+    #[doc(hidden)]
+    pub fn from_embedded(content: &str) -> Self {
+        toml::from_str(content).expect("theme checked by embed_theme")
+    }
+}
+
+/// Embed a theme checked at build time by
+/// [`embed_theme`](crate::theme::embed_theme),
+/// evaluating to its [`RenderStyle`].
+///
+/// The path must match the one given to `embed_theme`; a theme that wasn't
+/// checked by the build script fails to compile.
+#[cfg(feature = "serde")]
+#[macro_export]
+macro_rules! include_theme {
+    ($path:literal) => {
+        $crate::RenderStyle::from_embedded(include_str!(concat!(
+            env!("OUT_DIR"),
+            "/streamdown-themes/",
+            $path
+        )))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_from_str_toml_partial() {
+        let style: RenderStyle = "h1 = \"#ff0000\"\nbullet = \"yellow\"".parse().unwrap();
+        assert_eq!(style.h1, "#ff0000");
+        assert_eq!(style.bullet, "yellow");
+        assert_eq!(style.h2, RenderStyle::default().h2);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_from_str_json() {
+        let style = RenderStyle::from_str(r##"{ "code_bg": "#101010" }"##).unwrap();
+        assert_eq!(style.code_bg, "#101010");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_unknown_key_warning() {
+        let (style, warnings) =
+            RenderStyle::parse_theme("h1 = \"white\"\nheadr = \"red\"", ThemeFormat::Toml).unwrap();
+        assert_eq!(style.h1, "white");
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("headr"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_invalid_hex_rejected() {
+        assert!(RenderStyle::from_str("h1 = \"#ff00\"").is_err());
+        assert!(RenderStyle::from_str("h1 = \"#gg0000\"").is_err());
+        assert!(RenderStyle::from_str("h1 = \"not_a_color\"").is_err());
+        assert!(RenderStyle::from_str("h1 = \"ansi256:300\"").is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_palette_colors_accepted() {
+        let style: RenderStyle = "h1 = \"bright_blue\"\ncode_bg = \"ansi256:236\""
+            .parse()
+            .unwrap();
+        assert_eq!(crate::fg_color(&style.h1), "\x1b[94m");
+        assert_eq!(crate::bg_color(&style.code_bg), "\x1b[48;5;236m");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_text_attributes_in_theme() {
+        let style: RenderStyle =
+            "link_attrs = \"bold italic\"\nheading_attrs = [\"\", \"dim\", \"\", \"\", \"\", \"\"]"
+                .parse()
+                .unwrap();
+        assert!(style.link_attrs.bold && style.link_attrs.italic);
+        assert!(style.heading_attrs[0].is_plain() && style.heading_attrs[1].dim);
+        assert!(RenderStyle::from_str("think_attrs = \"blink\"").is_err());
+    }
+
+    #[test]
+    fn test_default_is_valid() {
+        assert!(RenderStyle::default().validate().is_ok());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_color_keys_are_serialized_fields() {
+        let table = toml::Table::try_from(RenderStyle::default()).unwrap();
+        for key in RenderStyle::color_keys() {
+            assert!(table.contains_key(key), "missing {}", key);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_from_path() {
+        let dir = std::env::temp_dir().join(format!("sd-theme-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let toml_path = dir.join("theme.toml");
+        std::fs::write(&toml_path, "h2 = \"#00ff00\"").unwrap();
+        assert_eq!(RenderStyle::from_path(&toml_path).unwrap().h2, "#00ff00");
+
+        let json_path = dir.join("theme.json");
+        std::fs::write(&json_path, r##"{ "h2": "cyan" }"##).unwrap();
+        assert_eq!(RenderStyle::from_path(&json_path).unwrap().h2, "cyan");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_stage_theme() {
+        let dir = std::env::temp_dir().join(format!("sd-embed-{}", std::process::id()));
+        let out = dir.join("out");
+        std::fs::create_dir_all(dir.join("themes")).unwrap();
+
+        std::fs::write(dir.join("themes/ok.json"), r##"{ "h1": "#ff8800" }"##).unwrap();
+        stage_theme(&dir, Path::new("themes/ok.json"), &out).unwrap();
+        let staged = out.join(EMBEDDED_THEME_DIR).join("themes/ok.json");
+        let style = RenderStyle::from_embedded(&std::fs::read_to_string(staged).unwrap());
+        assert_eq!(style.h1, "#ff8800");
+
+        // Unknown keys and bad colors fail the build
+        std::fs::write(dir.join("themes/typo.toml"), "headr = \"red\"").unwrap();
+        assert!(stage_theme(&dir, Path::new("themes/typo.toml"), &out).is_err());
+        std::fs::write(dir.join("themes/bad.toml"), "h1 = \"#ff00\"").unwrap();
+        assert!(stage_theme(&dir, Path::new("themes/bad.toml"), &out).is_err());
+        assert!(stage_theme(&dir, Path::new("../ok.json"), &out).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "syntax-highlighting")]
+    #[test]
+    fn test_tm_theme_conversion() {
+        let dir = std::env::temp_dir().join(format!("sd-tmtheme-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("mini.tmTheme");
+        let rule = |scope: &str, color: &str| {
+            format!(
+                "<dict><key>scope</key><string>{}</string><key>settings</key>\
+                 <dict><key>foreground</key><string>{}</string></dict></dict>",
+                scope, color
+            )
+        };
+        std::fs::write(
+            &path,
+            format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+                 <plist version=\"1.0\"><dict><key>name</key><string>Mini</string>\
+                 <key>settings</key><array>\
+                 <dict><key>settings</key><dict>\
+                 <key>background</key><string>#101820</string>\
+                 <key>foreground</key><string>#e0e0e0</string>\
+                 <key>selection</key><string>#ffffff80</string></dict></dict>\
+                 {}{}</array></dict></plist>",
+                rule("keyword", "#ff7700"),
+                rule("comment", "#708090"),
+            ),
+        )
+        .unwrap();
+
+        let style = RenderStyle::from_path(&path).unwrap();
+        assert_eq!(style.h1, "#ff7700");
+        assert_eq!(style.code_bg, "#101820");
+        assert_eq!(style.h6, "#708090");
+        // Translucent selection is blended over the background
+        assert_eq!(style.table_header_bg, "#878b8f");
+        // No string scope: the default is kept
+        assert_eq!(style.h3, RenderStyle::default().h3);
+        assert!(style.validate().is_ok());
+        assert!(RenderStyle::from_tm_theme(dir.join("missing.tmTheme")).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_format_detection() {
+        assert_eq!(ThemeFormat::sniff("  {\"h1\": \"red\"}"), ThemeFormat::Json);
+        assert_eq!(ThemeFormat::sniff("h1 = \"red\""), ThemeFormat::Toml);
+        assert_eq!(
+            ThemeFormat::from_path(Path::new("a/theme.JSON")),
+            ThemeFormat::Json
+        );
+        assert_eq!(
+            ThemeFormat::from_path(Path::new("theme.toml")),
+            ThemeFormat::Toml
+        );
+    }
+}
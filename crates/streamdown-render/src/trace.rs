@@ -0,0 +1,243 @@
+//! Agent trace rendering profile.
+//!
+//! Agent and MCP transcripts interleave markdown with role prefixes
+//! (`User:`, `Assistant:`) and tagged sections (`<tool_call>`,
+//! `<tool_result>`, `<reasoning>`). A [`TraceProfile`] recognizes those
+//! conventions line by line and maps each tag or role to a [`PanelStyle`];
+//! the renderer draws tagged sections as bordered, indented panels that nest.
+//!
+//! Classification happens before markdown parsing, so section content is
+//! still rendered as markdown inside its panel:
+//!
+//! ```
+//! use streamdown_render::{Renderer, TraceLine, TraceProfile};
+//! use streamdown_parser::Parser;
+//!
+//! let mut output = Vec::new();
+//! let mut renderer = Renderer::new(&mut output, 60);
+//! let mut parser = Parser::new();
+//! let profile = TraceProfile::agent();
+//!
+//! for line in ["Assistant: checking", "<tool_call>", "`ls -la`", "</tool_call>"] {
+//!     for segment in profile.classify(line) {
+//!         match segment {
+//!             TraceLine::Content(text) => {
+//!                 for event in parser.parse_line(text) {
+//!                     renderer.render_event(&event).unwrap();
+//!                 }
+//!             }
+//!             other => renderer.render_trace_marker(&other).unwrap(),
+//!         }
+//!     }
+//! }
+//! ```
+
+use std::collections::HashMap;
+
+/// How a trace section or role is drawn.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PanelStyle {
+    /// Title shown in the panel header
+    pub title: String,
+    /// Border and title color (preset name or hex)
+    pub color: String,
+}
+
+impl PanelStyle {
+    /// Create a panel style.
+    pub fn new(title: impl Into<String>, color: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            color: color.into(),
+        }
+    }
+}
+
+/// One classified piece of a trace line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceLine<'a> {
+    /// Opening tag of a mapped section
+    Open(PanelStyle),
+    /// Closing tag of a mapped section
+    Close,
+    /// Role prefix (e.g. `User:`)
+    Role(PanelStyle),
+    /// Markdown content to parse and render normally
+    Content(&'a str),
+}
+
+/// Mapping from trace tags and role prefixes to panel styles.
+#[derive(Debug, Clone, Default)]
+pub struct TraceProfile {
+    /// Tag name (without brackets, lowercase) → panel style
+    tags: HashMap<String, PanelStyle>,
+    /// Role name (lowercase, without colon) → label style
+    roles: HashMap<String, PanelStyle>,
+}
+
+impl TraceProfile {
+    /// Create an empty profile that recognizes nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Profile for common agent/MCP transcript conventions.
+    pub fn agent() -> Self {
+        let mut profile = Self::new();
+
+        let call = PanelStyle::new("tool call", "yellow");
+        for tag in ["tool_call", "tool_use", "function_call"] {
+            profile.map_tag(tag, call.clone());
+        }
+        let result = PanelStyle::new("tool result", "green");
+        for tag in ["tool_result", "function_result", "function_results"] {
+            profile.map_tag(tag, result.clone());
+        }
+        let reasoning = PanelStyle::new("reasoning", "grey");
+        for tag in ["reasoning", "thinking"] {
+            profile.map_tag(tag, reasoning.clone());
+        }
+
+        profile.map_role("system", PanelStyle::new("System", "grey"));
+        profile.map_role("user", PanelStyle::new("User", "cyan"));
+        profile.map_role("assistant", PanelStyle::new("Assistant", "light_green"));
+        profile.map_role("tool", PanelStyle::new("Tool", "yellow"));
+        profile
+    }
+
+    /// Map a tag (e.g. `tool_call`) to a panel style.
+    pub fn map_tag(&mut self, tag: &str, style: PanelStyle) {
+        self.tags.insert(tag.to_ascii_lowercase(), style);
+    }
+
+    /// Map a role prefix (e.g. `user` for `User:`) to a label style.
+    pub fn map_role(&mut self, role: &str, style: PanelStyle) {
+        self.roles.insert(role.to_ascii_lowercase(), style);
+    }
+
+    /// Split a raw input line into trace markers and markdown content.
+    ///
+    /// Lines without trace syntax come back as a single `Content`.
+    pub fn classify<'a>(&self, line: &'a str) -> Vec<TraceLine<'a>> {
+        let trimmed = line.trim();
+
+        if let Some(rest) = trimmed.strip_prefix("</") {
+            if let Some((name, after)) = split_tag(rest)
+                && after.trim().is_empty()
+                && self.tags.contains_key(&name)
+            {
+                return vec![TraceLine::Close];
+            }
+        } else if let Some(rest) = trimmed.strip_prefix('<')
+            && let Some((name, after)) = split_tag(rest)
+            && let Some(style) = self.tags.get(&name)
+        {
+            let mut out = vec![TraceLine::Open(style.clone())];
+            let closing = format!("</{}>", name);
+            match after.len().checked_sub(closing.len()) {
+                Some(end)
+                    if after
+                        .get(end..)
+                        .is_some_and(|t| t.eq_ignore_ascii_case(&closing)) =>
+                {
+                    let inner = after[..end].trim();
+                    if !inner.is_empty() {
+                        out.push(TraceLine::Content(inner));
+                    }
+                    out.push(TraceLine::Close);
+                }
+                _ => {
+                    let after = after.trim();
+                    if !after.is_empty() {
+                        out.push(TraceLine::Content(after));
+                    }
+                }
+            }
+            return out;
+        }
+
+        if let Some((role, rest)) = trimmed.split_once(':')
+            && !role.is_empty()
+            && role.len() <= 16
+            && let Some(style) = self.roles.get(&role.to_ascii_lowercase())
+        {
+            let mut out = vec![TraceLine::Role(style.clone())];
+            let rest = rest.trim();
+            if !rest.is_empty() {
+                out.push(TraceLine::Content(rest));
+            }
+            return out;
+        }
+
+        vec![TraceLine::Content(line)]
+    }
+}
+
+/// Split `name attr=..>rest` into a lowercase tag name and the text after `>`.
+fn split_tag(s: &str) -> Option<(String, &str)> {
+    let close = s.find('>')?;
+    let name = s[..close].split_whitespace().next()?;
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return None;
+    }
+    Some((name.to_ascii_lowercase(), &s[close + 1..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_plain_content() {
+        let profile = TraceProfile::agent();
+        assert_eq!(
+            profile.classify("just *markdown*"),
+            vec![TraceLine::Content("just *markdown*")]
+        );
+        assert_eq!(profile.classify("<div>"), vec![TraceLine::Content("<div>")]);
+    }
+
+    #[test]
+    fn test_classify_tags() {
+        let profile = TraceProfile::agent();
+        assert!(matches!(
+            profile.classify("<tool_call name=\"search\">")[..],
+            [TraceLine::Open(ref s)] if s.title == "tool call"
+        ));
+        assert_eq!(profile.classify("  </tool_call>"), vec![TraceLine::Close]);
+    }
+
+    #[test]
+    fn test_classify_single_line_section() {
+        let profile = TraceProfile::agent();
+        let segments = profile.classify("<tool_result>42 files</tool_result>");
+        assert_eq!(segments.len(), 3);
+        assert_eq!(segments[1], TraceLine::Content("42 files"));
+        assert_eq!(segments[2], TraceLine::Close);
+    }
+
+    #[test]
+    fn test_classify_roles() {
+        let profile = TraceProfile::agent();
+        let segments = profile.classify("User: list my files");
+        assert!(matches!(&segments[0], TraceLine::Role(s) if s.title == "User"));
+        assert_eq!(segments[1], TraceLine::Content("list my files"));
+
+        // Not a role: unmapped prefix
+        assert_eq!(profile.classify("Note: x").len(), 1);
+    }
+
+    #[test]
+    fn test_custom_mapping() {
+        let mut profile = TraceProfile::new();
+        profile.map_tag("Observation", PanelStyle::new("observation", "#88aaff"));
+        assert!(matches!(
+            profile.classify("<observation>")[..],
+            [TraceLine::Open(ref s)] if s.color == "#88aaff"
+        ));
+        assert_eq!(profile.classify("User: hi").len(), 1);
+    }
+}
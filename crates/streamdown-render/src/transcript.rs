@@ -0,0 +1,389 @@
+//! Session transcripts.
+//!
+//! A [`Transcript`] records the renderer features and parser options of a
+//! session and, per message, the input chunks as they arrived (with
+//! timing), the parse events and the rendered output, as one JSON file.
+//! Attached to a bug report, it lets a rendering issue be replayed exactly:
+//! [`TranscriptMessage::replay`] feeds the recorded input through a fresh
+//! renderer and parser set up like the recorded ones, and returns the events
+//! and output to compare.
+//!
+//! ```
+//! use streamdown_render::{Transcript, TranscriptRecorder};
+//!
+//! let mut recorder = TranscriptRecorder::new(80);
+//! recorder.begin_message();
+//! recorder.record_chunk("# Hello\n");
+//! let transcript = recorder.into_transcript();
+//!
+//! let json = transcript.to_json().unwrap();
+//! let loaded = Transcript::from_json(&json).unwrap();
+//! let replayed = loaded.messages[0]
+//!     .replay(loaded.renderer(), loaded.parser())
+//!     .unwrap();
+//! assert!(replayed.output.contains("Hello"));
+//! ```
+
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+use streamdown_parser::{ParseEvent, Parser, ParserOptions};
+
+use crate::{RenderFeatures, Renderer};
+
+/// Current transcript format version.
+pub const TRANSCRIPT_VERSION: u32 = 2;
+
+/// A recorded session.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Transcript {
+    /// Format version ([`TRANSCRIPT_VERSION`])
+    pub version: u32,
+    /// Render width in columns
+    pub width: usize,
+    /// Renderer features
+    #[serde(default)]
+    pub features: RenderFeatures,
+    /// Parser options
+    #[serde(default)]
+    pub parser: ParserOptions,
+    /// Recorded messages, in order
+    pub messages: Vec<TranscriptMessage>,
+}
+
+/// One recorded message.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct TranscriptMessage {
+    /// Input chunks as they arrived
+    pub chunks: Vec<TranscriptChunk>,
+    /// Parse events
+    pub events: Vec<ParseEvent>,
+    /// Rendered output (lossy UTF-8)
+    pub output: String,
+    /// Milliseconds from the start of the message to its end
+    pub elapsed_ms: u64,
+}
+
+/// One input chunk.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptChunk {
+    /// Milliseconds from the start of the message
+    pub at_ms: u64,
+    /// Chunk text
+    pub text: String,
+}
+
+impl Transcript {
+    /// Create an empty transcript for output `width` columns wide.
+    pub fn new(width: usize) -> Self {
+        Self {
+            version: TRANSCRIPT_VERSION,
+            width,
+            features: RenderFeatures::default(),
+            parser: ParserOptions::default(),
+            messages: Vec::new(),
+        }
+    }
+
+    /// Serialize as pretty-printed JSON.
+    pub fn to_json(&self) -> io::Result<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Parse a transcript from JSON.
+    pub fn from_json(json: &str) -> io::Result<Self> {
+        let transcript: Self = serde_json::from_str(json)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if transcript.version > TRANSCRIPT_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported transcript version {}", transcript.version),
+            ));
+        }
+        Ok(transcript)
+    }
+
+    /// Load a transcript file.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    /// Write the transcript to a file.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.to_json()?)
+    }
+
+    /// A renderer for replaying: the recorded width and features, writing
+    /// to a buffer.
+    pub fn renderer(&self) -> Renderer<Vec<u8>> {
+        let mut renderer = Renderer::new(Vec::new(), self.width);
+        renderer.set_features(self.features.clone());
+        renderer
+    }
+
+    /// A parser with the recorded options.
+    pub fn parser(&self) -> Parser {
+        Parser::with_options(self.parser)
+    }
+}
+
+impl TranscriptMessage {
+    /// The whole input of the message.
+    pub fn input(&self) -> String {
+        self.chunks.iter().map(|c| c.text.as_str()).collect()
+    }
+
+    /// Render the recorded input line by line through `parser` and
+    /// `renderer` (see [`Transcript::renderer`] and [`Transcript::parser`]).
+    ///
+    /// Returns the replayed message (same chunks, fresh events and output,
+    /// no timing) for comparison with the recording.
+    pub fn replay(
+        &self,
+        mut renderer: Renderer<Vec<u8>>,
+        mut parser: Parser,
+    ) -> io::Result<TranscriptMessage> {
+        let mut events = Vec::new();
+        let input = self.input();
+        let input = input.strip_suffix('\n').unwrap_or(&input);
+        if !input.is_empty() {
+            for line in input.split('\n') {
+                let line = line.strip_suffix('\r').unwrap_or(line);
+                let parsed = parser.parse_line(line);
+                renderer.render_events(&parsed)?;
+                events.extend(parsed);
+            }
+        }
+        renderer.finish()?;
+
+        Ok(TranscriptMessage {
+            chunks: self.chunks.clone(),
+            events,
+            output: String::from_utf8_lossy(&renderer.into_writer()).into_owned(),
+            elapsed_ms: 0,
+        })
+    }
+}
+
+/// Builds a [`Transcript`] while a session runs.
+#[derive(Debug)]
+pub struct TranscriptRecorder {
+    /// Transcript recorded so far
+    transcript: Transcript,
+    /// Message being recorded and when it began
+    current: Option<(TranscriptMessage, Instant)>,
+    /// Bytes of a character split across input chunks
+    partial: Vec<u8>,
+}
+
+impl TranscriptRecorder {
+    /// Create a recorder for output `width` columns wide.
+    pub fn new(width: usize) -> Self {
+        Self {
+            transcript: Transcript::new(width),
+            current: None,
+            partial: Vec::new(),
+        }
+    }
+
+    /// Record the features the session renders with.
+    pub fn set_features(&mut self, features: RenderFeatures) {
+        self.transcript.features = features;
+    }
+
+    /// Record the options the session parses with.
+    pub fn set_parser_options(&mut self, options: ParserOptions) {
+        self.transcript.parser = options;
+    }
+
+    /// Start a new message, ending the current one.
+    pub fn begin_message(&mut self) {
+        self.end_message();
+        self.current = Some((TranscriptMessage::default(), Instant::now()));
+    }
+
+    /// End the current message, if any.
+    pub fn end_message(&mut self) {
+        if !self.partial.is_empty() {
+            let rest = std::mem::take(&mut self.partial);
+            self.record_chunk(&String::from_utf8_lossy(&rest));
+        }
+        if let Some((mut message, start)) = self.current.take() {
+            message.elapsed_ms = start.elapsed().as_millis() as u64;
+            self.transcript.messages.push(message);
+        }
+    }
+
+    /// Record an input chunk.
+    pub fn record_chunk(&mut self, text: &str) {
+        let (message, start) = self.current();
+        let at_ms = start.elapsed().as_millis() as u64;
+        message.chunks.push(TranscriptChunk {
+            at_ms,
+            text: text.to_string(),
+        });
+    }
+
+    /// Record an input chunk as read, which may end in the middle of a
+    /// character: its first bytes are kept for the next chunk.
+    pub fn record_bytes(&mut self, bytes: &[u8]) {
+        self.partial.extend_from_slice(bytes);
+        let complete = complete_utf8_len(&self.partial);
+        if complete == 0 {
+            return;
+        }
+        let text = String::from_utf8_lossy(&self.partial[..complete]).into_owned();
+        self.partial.drain(..complete);
+        self.record_chunk(&text);
+    }
+
+    /// Record parse events.
+    pub fn record_events(&mut self, events: &[ParseEvent]) {
+        let (message, _) = self.current();
+        message.events.extend_from_slice(events);
+    }
+
+    /// Record rendered output.
+    pub fn record_output(&mut self, output: &[u8]) {
+        let (message, _) = self.current();
+        message.output.push_str(&String::from_utf8_lossy(output));
+    }
+
+    /// End the current message and return the transcript.
+    pub fn into_transcript(mut self) -> Transcript {
+        self.end_message();
+        self.transcript
+    }
+
+    /// The message being recorded, starting one if needed.
+    fn current(&mut self) -> &mut (TranscriptMessage, Instant) {
+        self.current
+            .get_or_insert_with(|| (TranscriptMessage::default(), Instant::now()))
+    }
+}
+
+/// Length of `bytes` without a character cut off at the end. Invalid bytes
+/// elsewhere count as complete (they are replaced when decoded).
+fn complete_utf8_len(bytes: &[u8]) -> usize {
+    let mut start = 0;
+    loop {
+        match std::str::from_utf8(&bytes[start..]) {
+            Ok(_) => return bytes.len(),
+            Err(e) => match e.error_len() {
+                Some(len) => start += e.valid_up_to() + len,
+                None => return start + e.valid_up_to(),
+            },
+        }
+    }
+}
+
+/// Writer that keeps a copy of everything written, for recording output.
+#[derive(Debug)]
+pub struct RecordingWriter<W> {
+    /// The real writer
+    inner: W,
+    /// Output since the last [`take_recorded`](Self::take_recorded)
+    /// (None = not recording)
+    recorded: Option<Vec<u8>>,
+}
+
+impl<W: Write> RecordingWriter<W> {
+    /// Wrap a writer, keeping a copy of its output if `record` is set.
+    pub fn new(inner: W, record: bool) -> Self {
+        Self {
+            inner,
+            recorded: record.then(Vec::new),
+        }
+    }
+
+    /// Take the output recorded since the last call.
+    pub fn take_recorded(&mut self) -> Vec<u8> {
+        self.recorded
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Get a mutable reference to the real writer.
+    pub fn inner_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+}
+
+impl<W: Write> Write for RecordingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        if let Some(recorded) = &mut self.recorded {
+            recorded.extend_from_slice(&buf[..n]);
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RenderFeatures;
+
+    #[test]
+    fn test_record_roundtrip_and_replay() {
+        let mut renderer = Renderer::new(RecordingWriter::new(Vec::new(), true), 40);
+        renderer.set_features(RenderFeatures::plain());
+        let mut parser = Parser::new();
+        parser.set_process_spoilers(true);
+        let mut recorder = TranscriptRecorder::new(40);
+        recorder.set_features(RenderFeatures::plain());
+        recorder.set_parser_options(parser.options());
+        recorder.begin_message();
+
+        for chunk in ["# Ti", "tle\n", "Some *text* ||hidden||\n"] {
+            recorder.record_chunk(chunk);
+        }
+        for line in ["# Title", "Some *text* ||hidden||"] {
+            let events = parser.parse_line(line);
+            renderer.render_events(&events).unwrap();
+            recorder.record_events(&events);
+        }
+        renderer.finish().unwrap();
+        recorder.record_output(&renderer.writer_mut().take_recorded());
+
+        let transcript =
+            Transcript::from_json(&recorder.into_transcript().to_json().unwrap()).unwrap();
+        let message = &transcript.messages[0];
+        assert_eq!(message.chunks.len(), 3);
+        assert_eq!(message.input(), "# Title\nSome *text* ||hidden||\n");
+        assert!(transcript.parser.spoilers);
+
+        let replayed = message
+            .replay(transcript.renderer(), transcript.parser())
+            .unwrap();
+        assert_eq!(replayed.events, message.events);
+        assert_eq!(replayed.output, message.output);
+        assert!(message.output.contains("Title"));
+    }
+
+    #[test]
+    fn test_split_characters_are_joined() {
+        let mut recorder = TranscriptRecorder::new(40);
+        let text = "naïve ☃";
+        for chunk in text.as_bytes().chunks(3) {
+            recorder.record_bytes(chunk);
+        }
+        recorder.record_bytes(b"\xff!\xe2\x98");
+        let transcript = recorder.into_transcript();
+        assert_eq!(transcript.messages[0].input(), "naïve ☃\u{fffd}!\u{fffd}");
+    }
+
+    #[test]
+    fn test_rejects_newer_version() {
+        let json = r#"{"version": 99, "width": 80, "messages": []}"#;
+        assert!(Transcript::from_json(json).is_err());
+    }
+}
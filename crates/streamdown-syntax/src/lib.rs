@@ -8,6 +8,8 @@
 //! - **Streaming highlighting** - Maintain state across lines for multi-line tokens
 //! - **Language aliases** - Map common names (py, js, ts) to proper syntax definitions
 //! - **Background override** - Override theme background for consistent code block styling
+//! - **Theme files** - Load Sublime Text / bat `.tmTheme` color schemes
+//! - **Theme palettes** - Read a theme's scope colors to style other elements
 //! - **ANSI output** - Generate 24-bit true color terminal escape codes
 //!
 //! # Example
@@ -30,12 +32,19 @@
 //! ```
 
 mod languages;
+mod palette;
 
 pub use languages::{LANGUAGE_ALIASES, aliases_for, all_aliases, language_alias};
+pub use palette::ThemePalette;
 
+use std::path::Path;
+
+use streamdown_core::{Result, StreamdownError};
 use syntect::easy::HighlightLines;
-use syntect::highlighting::{Color, FontStyle, Style, Theme, ThemeSet};
-use syntect::parsing::{SyntaxReference, SyntaxSet};
+use syntect::highlighting::{
+    Color, FontStyle, HighlightIterator, Highlighter as ThemeHighlighter, Style, Theme, ThemeSet,
+};
+use syntect::parsing::{ParseState, ScopeStack, SyntaxReference, SyntaxSet};
 use syntect::util::as_24_bit_terminal_escaped;
 
 /// Reset ANSI escape code
@@ -217,6 +226,48 @@ impl Highlighter {
         }
     }
 
+    /// Create an owned highlight state for a language.
+    ///
+    /// Unlike [`new_highlight_state`](Self::new_highlight_state), the returned
+    /// state does not borrow the highlighter, so it can live alongside it in a
+    /// renderer. Returns `None` for unknown languages so callers can fall back
+    /// to plain rendering.
+    pub fn line_state(&self, language: &str) -> Option<LineState> {
+        let syntax = self.syntax_for_language(language)?;
+        let highlighter = ThemeHighlighter::new(self.theme());
+        Some(LineState {
+            parse: ParseState::new(syntax),
+            highlight: syntect::highlighting::HighlightState::new(&highlighter, ScopeStack::new()),
+        })
+    }
+
+    /// Highlight a single line with an owned streaming state.
+    ///
+    /// # Returns
+    /// The highlighted line as an ANSI-escaped string (without trailing newline).
+    pub fn highlight_line_owned(&self, line: &str, state: &mut LineState) -> String {
+        // The default syntaxes expect newline-terminated lines; without the
+        // newline, line comments would leak into the next line.
+        let with_newline = format!("{}\n", line);
+        let ops = match state.parse.parse_line(&with_newline, &self.syntax_set) {
+            Ok(ops) => ops,
+            Err(_) => return line.to_string(),
+        };
+
+        let highlighter = ThemeHighlighter::new(self.theme());
+        let ranges: Vec<(Style, &str)> =
+            HighlightIterator::new(&mut state.highlight, &ops, &with_newline, &highlighter)
+                .map(|(style, text)| (style, text.trim_end_matches('\n')))
+                .collect();
+
+        if self.background_override.is_some() {
+            self.styles_to_ansi(&ranges)
+        } else {
+            let escaped = as_24_bit_terminal_escaped(&ranges, false);
+            format!("{}{}", escaped, RESET)
+        }
+    }
+
     /// Convert syntect styles to ANSI escape codes.
     fn styles_to_ansi(&self, ranges: &[(Style, &str)]) -> String {
         let mut output = String::new();
@@ -309,6 +360,29 @@ impl Highlighter {
     pub fn has_language(&self, name: &str) -> bool {
         self.syntax_for_language(name).is_some()
     }
+
+    /// Add a theme under `name`, replacing any theme of that name.
+    pub fn add_theme(&mut self, name: &str, theme: Theme) {
+        self.theme_set.themes.insert(name.to_string(), theme);
+    }
+
+    /// Load a `.tmTheme` file (a Sublime Text or bat color scheme) and make
+    /// it the current theme.
+    ///
+    /// The theme is added under the file's stem (`gruvbox` for
+    /// `gruvbox.tmTheme`), which is returned.
+    pub fn load_theme_file(&mut self, path: &Path) -> Result<String> {
+        let theme = ThemeSet::get_theme(path).map_err(|e| {
+            StreamdownError::Config(format!("Cannot load theme {}: {}", path.display(), e))
+        })?;
+        let name = path.file_stem().map_or_else(
+            || "custom".to_string(),
+            |stem| stem.to_string_lossy().into_owned(),
+        );
+        self.add_theme(&name, theme);
+        self.set_theme(&name);
+        Ok(name)
+    }
 }
 
 /// State for streaming syntax highlighting.
@@ -329,6 +403,18 @@ impl<'a> HighlightState<'a> {
     }
 }
 
+/// Owned state for streaming syntax highlighting.
+///
+/// Created by [`Highlighter::line_state`]. Holds the parser and theme
+/// scope stacks without borrowing the highlighter.
+#[derive(Debug, Clone)]
+pub struct LineState {
+    /// Syntax parse state
+    parse: ParseState,
+    /// Theme highlight state
+    highlight: syntect::highlighting::HighlightState,
+}
+
 /// Create a theme with overridden background color.
 ///
 /// This is equivalent to Python's `override_background()` function.
@@ -427,6 +513,29 @@ mod tests {
         assert!(line3.contains("\x1b["));
     }
 
+    #[test]
+    fn test_line_state_streaming() {
+        let h = Highlighter::new();
+        let mut state = h.line_state("rust").unwrap();
+
+        let line1 = h.highlight_line_owned("// comment", &mut state);
+        let line2 = h.highlight_line_owned("let x = 1;", &mut state);
+
+        assert!(line1.contains("\x1b["));
+        assert!(!line1.contains('\n'));
+        // The comment must not continue onto the next line
+        assert_ne!(
+            line1.split("\x1b[").nth(1).map(|s| s.split('m').next()),
+            line2.split("\x1b[").nth(1).map(|s| s.split('m').next())
+        );
+    }
+
+    #[test]
+    fn test_line_state_unknown_language() {
+        let h = Highlighter::new();
+        assert!(h.line_state("unknown-lang-xyz").is_none());
+    }
+
     #[test]
     fn test_themes() {
         let h = Highlighter::new();
@@ -453,6 +562,43 @@ mod tests {
         assert!(!h.has_theme("nonexistent-theme"));
     }
 
+    #[test]
+    fn test_load_theme_file() {
+        let dir = std::env::temp_dir().join(format!("sd-syntax-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("tiny.tmTheme");
+        std::fs::write(
+            &path,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0">
+<dict>
+  <key>name</key><string>Tiny</string>
+  <key>settings</key>
+  <array>
+    <dict><key>settings</key><dict>
+      <key>background</key><string>#101010</string>
+      <key>foreground</key><string>#E0E0E0</string>
+    </dict></dict>
+    <dict><key>scope</key><string>keyword, storage</string><key>settings</key><dict>
+      <key>foreground</key><string>#FF0000</string>
+    </dict></dict>
+  </array>
+</dict>
+</plist>"#,
+        )
+        .unwrap();
+
+        let mut h = Highlighter::new();
+        assert_eq!(h.load_theme_file(&path).unwrap(), "tiny");
+        assert_eq!(h.theme_name(), "tiny");
+        assert!(
+            h.highlight_block("fn main() {}", "rust")
+                .contains("38;2;255;0;0")
+        );
+        assert!(h.load_theme_file(&dir.join("missing.tmTheme")).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
     #[test]
     fn test_has_language() {
         let h = Highlighter::new();
@@ -0,0 +1,144 @@
+//! Theme palettes.
+//!
+//! A [`ThemePalette`] reads the colors a syntax theme gives to a handful of
+//! common scopes (keywords, strings, comments, markup headings...) and its
+//! editor colors, so a `.tmTheme` can color more than code blocks.
+//!
+//! ```
+//! use streamdown_syntax::{Highlighter, ThemePalette};
+//!
+//! let highlighter = Highlighter::new();
+//! let palette = ThemePalette::from_theme(highlighter.theme());
+//! assert!(palette.background.is_some());
+//! assert!(palette.comment.is_some());
+//! ```
+
+use std::path::Path;
+
+use streamdown_core::{Result, StreamdownError};
+use syntect::highlighting::{Color, Highlighter as ThemeHighlighter, Theme, ThemeSet};
+use syntect::parsing::Scope;
+
+/// Colors of a theme as `#rrggbb`; None where the theme sets none.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ThemePalette {
+    /// Editor background
+    pub background: Option<String>,
+    /// Default text color
+    pub foreground: Option<String>,
+    /// Selection background
+    pub selection: Option<String>,
+    /// Current line background
+    pub line_highlight: Option<String>,
+    /// Line number color
+    pub gutter: Option<String>,
+    /// `keyword`
+    pub keyword: Option<String>,
+    /// `string`
+    pub string: Option<String>,
+    /// `entity.name.function`
+    pub function: Option<String>,
+    /// `entity.name.type` or `storage.type`
+    pub type_name: Option<String>,
+    /// `constant.numeric` or `constant`
+    pub constant: Option<String>,
+    /// `comment`
+    pub comment: Option<String>,
+    /// `markup.heading`
+    pub heading: Option<String>,
+    /// `markup.underline.link`
+    pub link: Option<String>,
+    /// `markup.quote`
+    pub quote: Option<String>,
+}
+
+impl ThemePalette {
+    /// Read the palette of `theme`.
+    pub fn from_theme(theme: &Theme) -> Self {
+        let settings = &theme.settings;
+        let background = settings.background;
+        let hex = |color: Option<Color>| color.map(|color| blend(color, background));
+        let highlighter = ThemeHighlighter::new(theme);
+        let default = highlighter.get_default().foreground;
+        // Color of the first scope the theme styles, if any
+        let scope = |names: &[&str]| {
+            names.iter().find_map(|name| {
+                let scope = Scope::new(name).ok()?;
+                let color = highlighter.style_for_stack(&[scope]).foreground;
+                (color != default).then(|| blend(color, background))
+            })
+        };
+        Self {
+            background: hex(background),
+            foreground: hex(settings.foreground),
+            selection: hex(settings.selection),
+            line_highlight: hex(settings.line_highlight),
+            gutter: hex(settings.gutter_foreground),
+            keyword: scope(&["keyword", "storage"]),
+            string: scope(&["string"]),
+            function: scope(&["entity.name.function", "support.function"]),
+            type_name: scope(&["entity.name.type", "storage.type", "support.type"]),
+            constant: scope(&["constant.numeric", "constant"]),
+            comment: scope(&["comment"]),
+            heading: scope(&["markup.heading", "entity.name.section"]),
+            link: scope(&["markup.underline.link", "string.other.link"]),
+            quote: scope(&["markup.quote"]),
+        }
+    }
+
+    /// Read the palette of a `.tmTheme` file.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let theme = ThemeSet::get_theme(path).map_err(|e| {
+            StreamdownError::Config(format!("Cannot load theme {}: {}", path.display(), e))
+        })?;
+        Ok(Self::from_theme(&theme))
+    }
+}
+
+/// `#rrggbb` of `color`, with any transparency blended over `background`.
+fn blend(color: Color, background: Option<Color>) -> String {
+    let (r, g, b) = match background {
+        Some(bg) if color.a < 255 => {
+            let alpha = color.a as u32;
+            let mix =
+                |fg: u8, bg: u8| ((fg as u32 * alpha + bg as u32 * (255 - alpha)) / 255) as u8;
+            (mix(color.r, bg.r), mix(color.g, bg.g), mix(color.b, bg.b))
+        }
+        _ => (color.r, color.g, color.b),
+    };
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blend() {
+        let red = Color {
+            r: 255,
+            g: 0,
+            b: 0,
+            a: 255,
+        };
+        let black = Color {
+            r: 0,
+            g: 0,
+            b: 0,
+            a: 255,
+        };
+        assert_eq!(blend(red, Some(black)), "#ff0000");
+        assert_eq!(blend(Color { a: 0x80, ..red }, Some(black)), "#800000");
+        assert_eq!(blend(Color { a: 0x80, ..red }, None), "#ff0000");
+    }
+
+    #[test]
+    fn test_palette_of_bundled_themes() {
+        let themes = ThemeSet::load_defaults();
+        let palette = ThemePalette::from_theme(&themes.themes["base16-ocean.dark"]);
+        assert_eq!(palette.background.as_deref(), Some("#2b303b"));
+        assert!(palette.keyword.is_some() && palette.string.is_some());
+        assert_ne!(palette.keyword, palette.string);
+        assert!(ThemePalette::from_file(Path::new("missing.tmTheme")).is_err());
+    }
+}
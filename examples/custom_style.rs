@@ -29,22 +29,21 @@ print(greet("World"))
 "#;
 
     // Create a custom style with different colors
-    // Colors are in "r;g;bm" format for ANSI sequences
+    // Colors are hex values or Colodore preset names
     let custom_style = RenderStyle {
-        // Bright cyan for emphasis
-        bright: "0;255;255".to_string(),
-        // Green for headings
-        head: "0;255;128".to_string(),
-        // Yellow for symbols/bullets
-        symbol: "255;255;0".to_string(),
-        // Gray for muted text
-        grey: "128;128;128".to_string(),
-        // Dark blue for backgrounds
-        dark: "20;20;60".to_string(),
-        // Medium purple
-        mid: "80;60;120".to_string(),
-        // Light lavender
-        light: "180;160;220".to_string(),
+        // Bright cyan for the title
+        h1: "#00ffff".to_string(),
+        // Green for section headings
+        h2: "#00ff80".to_string(),
+        // Yellow for bullets
+        bullet: "#ffff00".to_string(),
+        // Gray for quote bars
+        blockquote_border: "#808080".to_string(),
+        // Dark blue for code backgrounds
+        code_bg: "#14143c".to_string(),
+        // Light lavender for link URLs
+        link_url: "#b4a0dc".to_string(),
+        ..Default::default()
     };
 
     // Create output buffer
@@ -4,6 +4,14 @@
 
 use clap::Parser;
 use std::path::PathBuf;
+use std::time::Duration;
+use streamdown_render::{
+    BorderStyle, CodeOverflow, ColorChoice, LocaleFormatter, NotifyOptions, PatternPass,
+    RenderQuota, TermStyle, TextAlign, TextAttributes,
+};
+
+/// Syntax highlighting theme used unless `--theme` or the config picks one.
+pub const DEFAULT_HIGHLIGHT_THEME: &str = "base16-ocean.dark";
 
 /// Streamdown - A streaming markdown renderer for modern terminals.
 ///
@@ -35,6 +43,15 @@ pub struct Cli {
     #[arg(short = 'b', long = "base")]
     pub base: Option<String>,
 
+    /// Convert a Sublime Text or bat .tmTheme color scheme into a TOML theme
+    /// file, written to stdout
+    #[arg(long = "convert-theme", value_name = "FILE")]
+    pub convert_theme: Option<PathBuf>,
+
+    /// Generate a theme from a seed (hue rotated around the base color)
+    #[arg(long = "seed", value_name = "N")]
+    pub seed: Option<u64>,
+
     /// Use a custom config file or inline TOML
     #[arg(short = 'c', long = "config")]
     pub config: Option<String>,
@@ -43,6 +60,18 @@ pub struct Cli {
     #[arg(short = 'w', long = "width", default_value = "0")]
     pub width: u16,
 
+    /// Cap the content width; narrower columns are centered
+    #[arg(long = "max-width", value_name = "COLS")]
+    pub max_width: Option<usize>,
+
+    /// Blank columns before the content
+    #[arg(long = "left-margin", value_name = "COLS", default_value = "0")]
+    pub left_margin: usize,
+
+    /// Blank columns after the content
+    #[arg(long = "right-margin", value_name = "COLS", default_value = "0")]
+    pub right_margin: usize,
+
     /// Wrap a program for proper streaming I/O handling
     #[arg(short = 'e', long = "exec", value_name = "CMD")]
     pub exec_cmd: Option<String>,
@@ -63,28 +92,290 @@ pub struct Cli {
     #[arg(long = "no-pretty-pad")]
     pub no_pretty_pad: bool,
 
+    /// Border glyphs: ascii, light, rounded, heavy, double, none
+    /// [default: light, or the config's]
+    #[arg(long = "border")]
+    pub border: Option<BorderStyle>,
+
     /// Enable code line wrapping (breaks copy-paste)
     #[arg(long = "pretty-broken")]
     pub pretty_broken: bool,
 
+    /// Long code lines: auto, wrap (with a ↩ marker), truncate (with …)
+    /// or overflow [default: auto]
+    #[arg(long = "code-overflow")]
+    pub code_overflow: Option<CodeOverflow>,
+
+    /// Show line numbers in code blocks
+    #[arg(long = "line-numbers")]
+    pub line_numbers: bool,
+
+    /// Keep the colors of pre-colored code block lines (captured output)
+    #[arg(long = "ansi-passthrough")]
+    pub ansi_passthrough: bool,
+
     /// Enable clipboard integration (OSC 52)
     #[arg(long = "clipboard")]
     pub clipboard: bool,
 
+    /// Add a clickable "copy" link (OSC 8) under each code block
+    #[arg(long = "copy-link")]
+    pub copy_link: bool,
+
+    /// Draw output that copies faithfully: no trailing padding, undecorated
+    /// code at column 0, fenced code in the clipboard (toggle with SIGUSR1)
+    #[arg(long = "copy-mode")]
+    pub copy_mode: bool,
+
+    /// Send a desktop notification (OSC 9/777) when a long document finishes
+    #[arg(long = "notify")]
+    pub notify: bool,
+
+    /// Send a desktop notification when rendered text contains PATTERN
+    #[arg(long = "notify-on", value_name = "PATTERN")]
+    pub notify_on: Vec<String>,
+
+    /// Mark text matching PATTERN (a regex, e.g. `TODO|FIXME`) in prose
+    #[arg(long = "mark", value_name = "PATTERN")]
+    pub mark: Vec<String>,
+
+    /// Minimum rendering time before --notify fires, in seconds
+    #[arg(long = "notify-after", value_name = "SECS", default_value = "10")]
+    pub notify_after: u64,
+
+    /// Group numbers and localize ISO dates in tables (locale tag, or "auto")
+    #[arg(long = "table-locale", value_name = "LOCALE")]
+    pub table_locale: Option<String>,
+
+    /// List link URLs as numbered endnotes instead of inline
+    #[arg(long = "link-endnotes")]
+    pub link_endnotes: bool,
+
+    /// Draw a QR code of each link after the line it is on
+    #[arg(long = "qr-links")]
+    pub qr_links: bool,
+
+    /// Mark each link with its number, in brackets after the link text
+    #[arg(long = "number-links")]
+    pub number_links: bool,
+
+    /// Open link N (as numbered by --number-links) once the document has
+    /// been rendered
+    #[arg(long = "open-link", value_name = "N")]
+    pub open_link: Option<usize>,
+
+    /// Command that opens links (default: open, wslview or xdg-open)
+    #[arg(long = "opener", value_name = "CMD")]
+    pub opener: Option<String>,
+
+    /// Show a color swatch after inline code like `#ff8800`
+    #[arg(long = "swatches")]
+    pub swatches: bool,
+
+    /// Show the line being received as it streams in, before it is rendered
+    #[arg(long = "partial-lines")]
+    pub partial_lines: bool,
+
+    /// Redraw a streaming table in place when later rows need wider
+    /// columns, and a code block when its language is detected
+    #[arg(long = "live-blocks")]
+    pub live_blocks: bool,
+
+    /// Draw task lists with dated items as a timeline
+    #[arg(long = "agenda")]
+    pub agenda: bool,
+
+    /// Keep the startup width when the terminal is resized
+    #[arg(long = "no-reflow")]
+    pub no_reflow: bool,
+
+    /// Guess the language of code blocks without one, for highlighting
+    #[arg(long = "detect-language")]
+    pub detect_language: bool,
+
+    /// Redraw carriage-return progress lines in code blocks in place
+    /// instead of showing only their final state
+    #[arg(long = "progress-in-place")]
+    pub progress_in_place: bool,
+
+    /// When to use colors: auto (follow NO_COLOR, CLICOLOR and
+    /// CLICOLOR_FORCE), always, never [default: auto, or the config's]
+    #[arg(long = "color")]
+    pub color: Option<ColorChoice>,
+
+    /// Write plain text without escape codes (for logs and pipes)
+    #[arg(long = "plain")]
+    pub plain: bool,
+
+    /// Paragraph alignment: left, right, center, justify
+    #[arg(long = "align", default_value = "left")]
+    pub align: TextAlign,
+
+    /// Put right-to-left text (Arabic, Hebrew) in display order, for
+    /// terminals that don't (needs a build with the `bidi` feature)
+    #[arg(long = "bidi")]
+    pub bidi: bool,
+
+    /// Break words wider than the terminal with a hyphen
+    #[arg(long = "hyphenate")]
+    pub hyphenate: bool,
+
+    /// Show control characters in the input as symbols (␇) instead of
+    /// removing them
+    #[arg(long = "show-controls")]
+    pub show_controls: bool,
+
+    /// Write a man page (roff) named after the input file instead of
+    /// terminal output
+    #[arg(long = "man", value_name = "SECTION", num_args = 0..=1, default_missing_value = "1")]
+    pub man: Option<String>,
+
+    /// Write sentences with SSML markup for text-to-speech instead of
+    /// terminal output (bare sentences with --plain)
+    #[arg(long = "speech")]
+    pub speech: bool,
+
+    /// Write each top-level section of the input to its own file in DIR
+    /// instead of terminal output
+    #[arg(long = "split", value_name = "DIR")]
+    pub split: Option<PathBuf>,
+
+    /// Deepest heading level that starts a section with --split
+    #[arg(long = "split-level", value_name = "N", default_value = "1",
+          value_parser = clap::value_parser!(u8).range(1..=6))]
+    pub split_level: u8,
+
+    /// Treat input as untrusted: no hyperlinks, clipboard writes, local
+    /// files, HTML folding or escape sequences from the input
+    #[arg(long = "untrusted")]
+    pub untrusted: bool,
+
+    /// Show think blocks as a one-line summary
+    #[arg(long = "collapse-think")]
+    pub collapse_think: bool,
+
+    /// Show code blocks longer than N lines as a one-line summary
+    #[arg(long = "collapse-code", value_name = "N")]
+    pub collapse_code: Option<usize>,
+
+    /// Render only the outline: headings, the first line of each section
+    /// and a summary line per code block
+    #[arg(long = "outline")]
+    pub outline: bool,
+
+    /// Draw `[####----] 50%` and `progress:0.5` in text as progress bars
+    #[arg(long = "progress-bars")]
+    pub progress_bars: bool,
+
+    /// Re-indent and color JSON code blocks and color YAML ones
+    #[arg(long = "pretty-data")]
+    pub pretty_data: bool,
+
+    /// Draw csv and tsv code blocks as tables
+    #[arg(long = "csv-tables")]
+    pub csv_tables: bool,
+
+    /// Show only a line count for mermaid diagrams that can't be drawn
+    #[arg(long = "hide-diagram-source")]
+    pub hide_diagram_source: bool,
+
+    /// Keep the current H1/H2 pinned in the top row (terminal output only)
+    #[arg(long = "pin-header")]
+    pub pin_header: bool,
+
+    /// Print a dim footer with character, word and timing stats
+    #[arg(long = "stats")]
+    pub stats: bool,
+
     /// Enable savebrace (save code to /tmp/savebrace)
     #[arg(long = "savebrace")]
     pub savebrace: bool,
 
+    /// Render agent traces (role prefixes, tool call/result panels)
+    #[arg(long = "trace")]
+    pub trace: bool,
+
+    /// Render `||spoiler||` text (marked, or concealed when collapsing)
+    #[arg(long = "spoilers")]
+    pub spoilers: bool,
+
+    /// Draw `[[Ctrl]]` as a keyboard key, like `<kbd>Ctrl</kbd>`
+    #[arg(long = "kbd-shorthand")]
+    pub kbd_shorthand: bool,
+
+    /// Draw a held block (collapsed, processed or data fence) as is when its
+    /// end hasn't arrived after this many milliseconds
+    #[arg(long = "block-timeout", value_name = "MS")]
+    pub block_timeout: Option<u64>,
+
+    /// Stop after this many output lines (preview mode)
+    #[arg(long = "max-lines", value_name = "N")]
+    pub max_lines: Option<usize>,
+
+    /// Stop after this many blocks (preview mode)
+    #[arg(long = "max-blocks", value_name = "N")]
+    pub max_blocks: Option<usize>,
+
+    /// Record the session (input chunks with timing, parse events, output)
+    /// to a JSON transcript that can be attached to bug reports
+    #[arg(long = "record", value_name = "FILE")]
+    pub record: Option<PathBuf>,
+
+    /// Render the input recorded in a JSON transcript
+    #[arg(long = "replay", value_name = "FILE", conflicts_with = "record")]
+    pub replay: Option<PathBuf>,
+
     /// Show configuration paths and exit
     #[arg(long = "paths")]
     pub show_paths: bool,
 
-    /// Syntax highlighting theme
-    #[arg(long = "theme", default_value = "base16-ocean.dark")]
+    /// Terminal background: auto (ask the terminal, then COLORFGBG), light
+    /// or dark. Light backgrounds get the light palette and highlighting
+    /// theme
+    #[arg(long = "background", default_value = "auto", value_parser = ["auto", "light", "dark"])]
+    pub background: String,
+
+    /// Syntax highlighting theme: a built-in name or a .tmTheme file
+    #[arg(long = "theme", default_value = DEFAULT_HIGHLIGHT_THEME)]
     pub theme: String,
 }
 
 impl Cli {
+    /// Get the notification options, if any were requested.
+    pub fn notify_options(&self) -> Option<NotifyOptions> {
+        (self.notify || !self.notify_on.is_empty()).then(|| NotifyOptions {
+            on_finish: self.notify,
+            min_elapsed: Duration::from_secs(self.notify_after),
+            patterns: self.notify_on.clone(),
+        })
+    }
+
+    /// Get the text pass marking `--mark` patterns, if any are given.
+    pub fn mark_pass(&self) -> streamdown_core::Result<Option<PatternPass>> {
+        let style = TermStyle {
+            color: Some("yellow".to_string()),
+            attributes: TextAttributes::BOLD,
+            ..Default::default()
+        };
+        if self.mark.is_empty() {
+            return Ok(None);
+        }
+        self.mark
+            .iter()
+            .try_fold(PatternPass::new(), |pass, pattern| {
+                pass.pattern(pattern, style.clone())
+            })
+            .map(Some)
+    }
+
+    /// Get the table cell formatter for `--table-locale`, if set.
+    pub fn cell_formatter(&self) -> Option<LocaleFormatter> {
+        self.table_locale.as_deref().map(|tag| match tag {
+            "auto" => LocaleFormatter::from_env(),
+            tag => LocaleFormatter::from_tag(tag),
+        })
+    }
+
     /// Get the effective width (0 means auto-detect).
     pub fn effective_width(&self) -> usize {
         if self.width == 0 {
@@ -97,9 +388,22 @@ impl Cli {
         }
     }
 
+    /// Output quota from `--max-lines`/`--max-blocks`, if either is set.
+    pub fn quota(&self) -> Option<RenderQuota> {
+        (self.max_lines.is_some() || self.max_blocks.is_some()).then_some(RenderQuota {
+            max_lines: self.max_lines,
+            max_blocks: self.max_blocks,
+        })
+    }
+
+    /// Held block timeout from `--block-timeout`, if set.
+    pub fn block_timeout(&self) -> Option<Duration> {
+        self.block_timeout.map(Duration::from_millis)
+    }
+
     /// Check if we should read from stdin.
     pub fn should_read_stdin(&self) -> bool {
-        self.files.is_empty() && self.exec_cmd.is_none()
+        self.files.is_empty() && self.exec_cmd.is_none() && self.replay.is_none()
     }
 
     /// Parse HSV base color if provided.
@@ -122,7 +426,8 @@ impl Cli {
 pub fn show_paths() {
     use streamdown_config::Config;
 
-    let config_path = Config::config_path()
+    let config_path = Config::discover()
+        .or_else(Config::config_path)
         .map(|p| p.display().to_string())
         .unwrap_or_else(|| "(not found)".to_string());
     let log_dir = std::env::temp_dir()
@@ -190,6 +495,312 @@ mod tests {
         assert!((v - 0.5).abs() < 0.01);
     }
 
+    #[test]
+    fn test_cli_parse_border() {
+        let cli = Cli::parse_from(["sd"]);
+        assert_eq!(cli.border, None);
+
+        let cli = Cli::parse_from(["sd", "--border", "ascii"]);
+        assert_eq!(cli.border, Some(BorderStyle::Ascii));
+        assert!(Cli::try_parse_from(["sd", "--border", "fancy"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_code_overflow() {
+        assert_eq!(Cli::parse_from(["sd"]).code_overflow, None);
+        let cli = Cli::parse_from(["sd", "--code-overflow", "truncate"]);
+        assert_eq!(cli.code_overflow, Some(CodeOverflow::Truncate));
+        assert!(Cli::try_parse_from(["sd", "--code-overflow", "fold"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_quota() {
+        let cli = Cli::parse_from(["sd"]);
+        assert_eq!(cli.quota(), None);
+
+        let cli = Cli::parse_from(["sd", "--max-blocks", "5"]);
+        assert_eq!(cli.quota(), Some(RenderQuota::blocks(5)));
+    }
+
+    #[test]
+    fn test_cli_parse_seed() {
+        let cli = Cli::parse_from(["sd", "--seed", "7"]);
+        assert_eq!(cli.seed, Some(7));
+        assert!(Cli::parse_from(["sd"]).seed.is_none());
+    }
+
+    #[test]
+    fn test_cli_parse_stats() {
+        assert!(Cli::parse_from(["sd", "--stats"]).stats);
+        assert!(!Cli::parse_from(["sd"]).stats);
+    }
+
+    #[test]
+    fn test_cli_parse_layout() {
+        let cli = Cli::parse_from(["sd", "--max-width", "100", "--left-margin", "2"]);
+        assert_eq!(cli.max_width, Some(100));
+        assert_eq!(cli.left_margin, 2);
+        assert_eq!(cli.right_margin, 0);
+    }
+
+    #[test]
+    fn test_cli_notify_options() {
+        assert!(Cli::parse_from(["sd"]).notify_options().is_none());
+
+        let cli = Cli::parse_from(["sd", "--notify-on", "ERROR", "--notify-on", "FAIL"]);
+        let options = cli.notify_options().unwrap();
+        assert!(!options.on_finish);
+        assert_eq!(options.patterns, ["ERROR", "FAIL"]);
+
+        let cli = Cli::parse_from(["sd", "--notify", "--notify-after", "0"]);
+        let options = cli.notify_options().unwrap();
+        assert!(options.on_finish);
+        assert_eq!(options.min_elapsed, Duration::ZERO);
+    }
+
+    #[test]
+    fn test_cli_block_timeout() {
+        assert!(Cli::parse_from(["sd"]).block_timeout().is_none());
+        let cli = Cli::parse_from(["sd", "--block-timeout", "1500"]);
+        assert_eq!(cli.block_timeout(), Some(Duration::from_millis(1500)));
+    }
+
+    #[test]
+    fn test_cli_parse_record_replay() {
+        let cli = Cli::parse_from(["sd", "--record", "session.json"]);
+        assert_eq!(cli.record, Some(PathBuf::from("session.json")));
+        assert!(cli.should_read_stdin());
+
+        let cli = Cli::parse_from(["sd", "--replay", "session.json"]);
+        assert_eq!(cli.replay, Some(PathBuf::from("session.json")));
+        assert!(!cli.should_read_stdin());
+
+        assert!(Cli::try_parse_from(["sd", "--record", "a", "--replay", "b"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_collapse() {
+        let cli = Cli::parse_from(["sd", "--collapse-think", "--collapse-code", "20"]);
+        assert!(cli.collapse_think);
+        assert_eq!(cli.collapse_code, Some(20));
+    }
+
+    #[test]
+    fn test_cli_parse_outline() {
+        assert!(Cli::parse_from(["sd", "--outline"]).outline);
+        assert!(!Cli::parse_from(["sd"]).outline);
+    }
+
+    #[test]
+    fn test_cli_parse_progress_bars() {
+        assert!(Cli::parse_from(["sd", "--progress-bars"]).progress_bars);
+        assert!(!Cli::parse_from(["sd"]).progress_bars);
+    }
+
+    #[test]
+    fn test_cli_parse_pretty_data() {
+        assert!(Cli::parse_from(["sd", "--pretty-data"]).pretty_data);
+        assert!(!Cli::parse_from(["sd"]).pretty_data);
+    }
+
+    #[test]
+    fn test_cli_parse_csv_tables() {
+        assert!(Cli::parse_from(["sd", "--csv-tables"]).csv_tables);
+        assert!(!Cli::parse_from(["sd"]).csv_tables);
+    }
+
+    #[test]
+    fn test_cli_parse_hide_diagram_source() {
+        assert!(Cli::parse_from(["sd", "--hide-diagram-source"]).hide_diagram_source);
+        assert!(!Cli::parse_from(["sd"]).hide_diagram_source);
+    }
+
+    #[test]
+    fn test_cli_parse_mark() {
+        let cli = Cli::parse_from(["sd", "--mark", "TODO", "--mark", r"OPS-\d+"]);
+        assert_eq!(cli.mark, ["TODO", r"OPS-\d+"]);
+        assert!(cli.mark_pass().unwrap().is_some());
+        assert!(Cli::parse_from(["sd"]).mark_pass().unwrap().is_none());
+        assert!(Cli::parse_from(["sd", "--mark", "("]).mark_pass().is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_link_endnotes() {
+        assert!(Cli::parse_from(["sd", "--link-endnotes"]).link_endnotes);
+        assert!(!Cli::parse_from(["sd"]).link_endnotes);
+    }
+
+    #[test]
+    fn test_cli_parse_open_link() {
+        let cli = Cli::parse_from([
+            "sd",
+            "--number-links",
+            "--open-link",
+            "2",
+            "--opener",
+            "firefox",
+        ]);
+        assert!(cli.number_links);
+        assert_eq!(cli.open_link, Some(2));
+        assert_eq!(cli.opener.as_deref(), Some("firefox"));
+        let cli = Cli::parse_from(["sd"]);
+        assert!(!cli.number_links && cli.open_link.is_none() && cli.opener.is_none());
+    }
+
+    #[test]
+    fn test_cli_parse_qr_links() {
+        assert!(Cli::parse_from(["sd", "--qr-links"]).qr_links);
+        assert!(!Cli::parse_from(["sd"]).qr_links);
+    }
+
+    #[test]
+    fn test_cli_parse_partial_lines() {
+        assert!(Cli::parse_from(["sd", "--partial-lines"]).partial_lines);
+        assert!(!Cli::parse_from(["sd"]).partial_lines);
+    }
+
+    #[test]
+    fn test_cli_parse_live_blocks() {
+        assert!(Cli::parse_from(["sd", "--live-blocks"]).live_blocks);
+        assert!(!Cli::parse_from(["sd"]).live_blocks);
+    }
+
+    #[test]
+    fn test_cli_parse_swatches() {
+        assert!(Cli::parse_from(["sd", "--swatches"]).swatches);
+        assert!(!Cli::parse_from(["sd"]).swatches);
+    }
+
+    #[test]
+    fn test_cli_parse_agenda() {
+        assert!(Cli::parse_from(["sd", "--agenda"]).agenda);
+        assert!(!Cli::parse_from(["sd"]).agenda);
+    }
+
+    #[test]
+    fn test_cli_parse_no_reflow() {
+        assert!(Cli::parse_from(["sd", "--no-reflow"]).no_reflow);
+        assert!(!Cli::parse_from(["sd"]).no_reflow);
+    }
+
+    #[test]
+    fn test_cli_parse_detect_language() {
+        assert!(Cli::parse_from(["sd", "--detect-language"]).detect_language);
+        assert!(!Cli::parse_from(["sd"]).detect_language);
+    }
+
+    #[test]
+    fn test_cli_parse_progress_in_place() {
+        assert!(Cli::parse_from(["sd", "--progress-in-place"]).progress_in_place);
+        assert!(!Cli::parse_from(["sd"]).progress_in_place);
+    }
+
+    #[test]
+    fn test_cli_parse_split() {
+        let cli = Cli::parse_from(["sd", "--split", "notes", "--split-level", "2"]);
+        assert_eq!(cli.split, Some(PathBuf::from("notes")));
+        assert_eq!(cli.split_level, 2);
+        assert_eq!(Cli::parse_from(["sd"]).split_level, 1);
+        assert!(Cli::try_parse_from(["sd", "--split-level", "7"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_man() {
+        assert_eq!(Cli::parse_from(["sd", "--man"]).man.as_deref(), Some("1"));
+        assert_eq!(
+            Cli::parse_from(["sd", "--man", "7", "a.md"]).man.as_deref(),
+            Some("7")
+        );
+        assert!(Cli::parse_from(["sd"]).man.is_none());
+    }
+
+    #[test]
+    fn test_cli_parse_convert_theme() {
+        let cli = Cli::parse_from(["sd", "--convert-theme", "gruvbox.tmTheme"]);
+        assert_eq!(cli.convert_theme, Some(PathBuf::from("gruvbox.tmTheme")));
+    }
+
+    #[test]
+    fn test_cli_parse_copy_mode() {
+        assert!(Cli::parse_from(["sd", "--copy-mode"]).copy_mode);
+        assert!(!Cli::parse_from(["sd"]).copy_mode);
+    }
+
+    #[test]
+    fn test_cli_parse_speech() {
+        let cli = Cli::parse_from(["sd", "--speech", "--plain", "a.md"]);
+        assert!(cli.speech && cli.plain);
+        assert!(!Cli::parse_from(["sd"]).speech);
+    }
+
+    #[test]
+    fn test_cli_parse_color() {
+        assert_eq!(Cli::parse_from(["sd"]).color, None);
+        assert_eq!(
+            Cli::parse_from(["sd", "--color", "never"]).color,
+            Some(ColorChoice::Never)
+        );
+        assert!(Cli::try_parse_from(["sd", "--color", "red"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_background() {
+        assert_eq!(Cli::parse_from(["sd"]).background, "auto");
+        assert_eq!(
+            Cli::parse_from(["sd", "--background", "light"]).background,
+            "light"
+        );
+        assert!(Cli::try_parse_from(["sd", "--background", "grey"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_plain() {
+        assert!(Cli::parse_from(["sd", "--plain"]).plain);
+        assert!(!Cli::parse_from(["sd"]).plain);
+    }
+
+    #[test]
+    fn test_cli_parse_align() {
+        let cli = Cli::parse_from(["sd", "--align", "justify"]);
+        assert_eq!(cli.align, TextAlign::Justify);
+        assert_eq!(Cli::parse_from(["sd"]).align, TextAlign::Left);
+        assert!(Cli::try_parse_from(["sd", "--align", "middle"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_bidi() {
+        assert!(Cli::parse_from(["sd", "--bidi"]).bidi);
+        assert!(!Cli::parse_from(["sd"]).bidi);
+    }
+
+    #[test]
+    fn test_cli_parse_hyphenate() {
+        assert!(Cli::parse_from(["sd", "--hyphenate"]).hyphenate);
+        assert!(!Cli::parse_from(["sd"]).hyphenate);
+    }
+
+    #[test]
+    fn test_cli_parse_show_controls() {
+        assert!(Cli::parse_from(["sd", "--show-controls"]).show_controls);
+        assert!(!Cli::parse_from(["sd"]).show_controls);
+    }
+
+    #[test]
+    fn test_cli_parse_untrusted() {
+        assert!(Cli::parse_from(["sd", "--untrusted"]).untrusted);
+        assert!(!Cli::parse_from(["sd"]).untrusted);
+    }
+
+    #[test]
+    fn test_cli_table_locale() {
+        assert!(Cli::parse_from(["sd"]).cell_formatter().is_none());
+        let cli = Cli::parse_from(["sd", "--table-locale", "de-DE"]);
+        assert_eq!(
+            cli.cell_formatter(),
+            Some(LocaleFormatter::from_tag("de-DE"))
+        );
+    }
+
     #[test]
     fn test_should_read_stdin() {
         let cli = Cli::parse_from(["sd"]);
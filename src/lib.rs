@@ -0,0 +1,118 @@
+//! Streaming markdown rendering for modern terminals.
+//!
+//! This crate gathers the streamdown workspace behind one dependency: the
+//! parser, the renderer with its styles and options, and a one-shot
+//! [`render()`] function, with the member crates re-exported as modules for
+//! everything else. Depend on it instead of version-matching
+//! `streamdown-parser`, `streamdown-render` and friends.
+//!
+//! ```
+//! let output = streamdown::render("# Hello\n\nSome **bold** text.", 40).unwrap();
+//! assert!(output.contains("Hello"));
+//! ```
+//!
+//! Streaming input goes through the parser and renderer one line at a time:
+//!
+//! ```
+//! use streamdown::prelude::*;
+//!
+//! let mut parser = Parser::new();
+//! let mut renderer = Renderer::new(Vec::new(), 60);
+//! for line in ["Counting:", "- one", "- two"] {
+//!     renderer.render(&parser.parse_line(line)).unwrap();
+//! }
+//! renderer.finish().unwrap();
+//! ```
+//!
+//! # Features
+//!
+//! - `syntax-highlighting` (default): token-colored code blocks, and the
+//!   [`syntax`] module
+//! - `image` (default): half-block drawing of local PNG images
+//! - `cli` (default): the `sd` binary
+//! - `plugins`: the [`plugin`] module (LaTeX and other plugins)
+//! - `stream`, `tokio`: async adapters of the renderer
+//! - `serde`: Serialize/Deserialize for [`RendererOptions`] and
+//!   [`RenderFeatures`]
+//!
+//! Library users can turn off the default features to leave out the
+//! binary's dependencies:
+//!
+//! ```toml
+//! streamdown = { version = "0.1", default-features = false, features = ["syntax-highlighting"] }
+//! ```
+
+pub use streamdown_ansi as ansi;
+pub use streamdown_config as config;
+pub use streamdown_core as types;
+pub use streamdown_parser as parser;
+#[cfg(feature = "plugins")]
+pub use streamdown_plugin as plugin;
+pub use streamdown_render as render;
+#[cfg(feature = "syntax-highlighting")]
+pub use streamdown_syntax as syntax;
+
+pub use streamdown_core::{Result, StreamdownError};
+pub use streamdown_parser::{ParseEvent, Parser};
+pub use streamdown_render::{
+    RenderError, RenderFeatures, RenderResult, RenderSettings, RenderStyle, RenderStyleBuilder,
+    Renderer, RendererOptions,
+};
+
+/// The types most programs need, for a glob import.
+pub mod prelude {
+    pub use crate::{
+        ParseEvent, Parser, RenderFeatures, RenderStyle, Renderer, RendererOptions, render,
+        render_with,
+    };
+}
+
+/// Render a whole markdown document for a terminal `width` columns wide,
+/// with the default style and features.
+pub fn render(markdown: &str, width: usize) -> RenderResult<String> {
+    render_with(
+        markdown,
+        width,
+        RenderStyle::default(),
+        RenderFeatures::default(),
+    )
+}
+
+/// Render a whole markdown document with `style` and `features`.
+pub fn render_with(
+    markdown: &str,
+    width: usize,
+    style: RenderStyle,
+    features: RenderFeatures,
+) -> RenderResult<String> {
+    let mut renderer = Renderer::with_features(Vec::new(), width, features);
+    renderer.set_style(style);
+    let mut parser = Parser::new();
+    renderer.render(&parser.parse_document(markdown))?;
+    renderer.finish()?;
+    Ok(String::from_utf8_lossy(&renderer.into_writer()).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let output = render("# Title\n\n- a **b**\n\n```rust\nlet x = 1;\n```", 40).unwrap();
+        let plain = ansi::utils::visible(&output);
+        assert!(plain.contains("Title"));
+        assert!(plain.contains("a b"));
+        assert!(plain.contains("let x = 1;"));
+    }
+
+    #[test]
+    fn test_render_with_features() {
+        let features = RenderFeatures {
+            plain: true,
+            ..Default::default()
+        };
+        let output = render_with("Some *text*", 40, RenderStyle::default(), features).unwrap();
+        assert!(ansi::utils::visible(&output).contains("Some text"));
+    }
+}
@@ -5,18 +5,27 @@
 
 mod cli;
 mod pty;
+mod terminal;
 
 use clap::Parser as ClapParser;
-use cli::Cli;
+use cli::{Cli, DEFAULT_HIGHLIGHT_THEME};
 use log::{LevelFilter, debug, error, info, trace};
 use std::fs::File;
-use std::io::{self, BufRead, BufReader, Write};
-use std::path::Path;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock, mpsc};
+use std::time::Duration;
 
-use streamdown_config::{ComputedStyle, Config};
+use streamdown_config::{ComputedStyle, Config, StyleConfig};
 use streamdown_parser::{ParseEvent, Parser as MarkdownParser};
 use streamdown_plugin::PluginManager;
-use streamdown_render::{RenderFeatures, RenderStyle, Renderer};
+use streamdown_render::{
+    AgendaProcessor, Background, BorderStyle, CollapseOptions, ColorChoice, ControlChars,
+    CsvHandler, JsonHandler, LinkOpener, LinkStyle, MermaidHandler, PinnedHeader, Policy,
+    ProgressHandler, ProgressLines, RecordingWriter, RenderFeatures, RenderSettings, RenderStyle,
+    Renderer, RoffBackend, SectionSplitter, SpeechBackend, TerminalCapabilities, TextAlign,
+    TraceLine, TraceProfile, Transcript, TranscriptRecorder, YamlHandler, is_tty, terminal_size,
+};
 
 fn main() {
     let cli = <Cli as ClapParser>::parse();
@@ -65,31 +74,54 @@ fn setup_logging(level: &str) {
 
 /// Main application logic.
 fn run(cli: &Cli) -> io::Result<()> {
+    if let Some(ref path) = cli.convert_theme {
+        return convert_theme(path);
+    }
+
     // Load and merge configuration
     let config = load_config(cli)?;
     let computed_style = config.computed_style();
     debug!("Loaded config with style: {:?}", computed_style);
 
-    // Create render features
-    let features = create_features(cli);
-    debug!("Render features: {:?}", features);
+    // Render style, options and features from the config and CLI
+    let settings = load_settings(cli, &config)?;
+    debug!("Render features: {:?}", settings.features);
+
+    // Reflow to the new width when an auto-sized terminal is resized
+    if settings.width.is_none() && !cli.no_reflow && is_tty() && !terminal::watch_resize() {
+        debug!("Could not watch for terminal resizes");
+    }
+    if is_tty() && !terminal::watch_copy_toggle() {
+        debug!("Could not watch for copy mode toggles");
+    }
 
     // Determine input source and process
-    if let Some(ref exec_cmd) = cli.exec_cmd {
+    if let Some(ref section) = cli.man {
+        run_man(cli, section)
+    } else if cli.speech {
+        run_speech(cli, &settings)
+    } else if let Some(ref dir) = cli.split {
+        run_split(cli, dir, &settings)
+    } else if let Some(ref path) = cli.replay {
+        run_replay(cli, path, &settings)
+    } else if let Some(ref exec_cmd) = cli.exec_cmd {
         // Wrap an external program
-        run_exec(cli, exec_cmd, &computed_style, &features)
+        run_exec(cli, exec_cmd, &computed_style, &settings)
     } else if cli.should_read_stdin() {
         // Read from stdin
-        run_stdin(cli, &computed_style, &features)
+        run_stdin(cli, &computed_style, &settings)
     } else {
         // Process files
-        run_files(cli, &computed_style, &features)
+        run_files(cli, &computed_style, &settings)
     }
 }
 
 /// Load configuration with optional overrides.
 fn load_config(cli: &Cli) -> io::Result<Config> {
     let mut config = Config::load().unwrap_or_default();
+    if let Err(e) = config.apply_env() {
+        error!("Ignoring environment config: {}", e);
+    }
 
     // Apply config override if provided
     if let Some(ref config_arg) = cli.config {
@@ -127,72 +159,384 @@ fn load_config(cli: &Cli) -> io::Result<Config> {
     Ok(config)
 }
 
-/// Create render features from CLI options.
-fn create_features(cli: &Cli) -> RenderFeatures {
+/// Build the render settings from the config's `[render]` section, with
+/// CLI options taking precedence. An invalid `[render]` value is an error.
+fn load_settings(cli: &Cli, config: &Config) -> io::Result<RenderSettings> {
+    // Relative theme paths are relative to the config file
+    let config_file = cli
+        .config
+        .as_deref()
+        .map(PathBuf::from)
+        .filter(|path| path.is_file())
+        .or_else(Config::discover);
+    let base = config_file
+        .as_deref()
+        .and_then(Path::parent)
+        .unwrap_or(Path::new("."));
+
+    let mut settings = RenderSettings::from_config(config, base)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+    // The config palette is derived for dark backgrounds
+    if config.render.theme.is_none() && detect_background(cli) == Background::Light {
+        settings.style = RenderStyle::light();
+    }
+    settings.features = create_features(cli, &settings.features);
+    // Unless the config decides, follow the environment like other CLIs
+    if let Some(color) = cli.color {
+        settings.options.color = color;
+    } else if config.render.color.is_none() {
+        settings.options.color = ColorChoice::Auto;
+    }
+    if cli.width > 0 {
+        settings.width = Some(cli.width as usize);
+    }
+    Ok(settings)
+}
+
+/// Create render features from CLI options on top of the configured ones.
+///
+/// Flags only turn features on, and options not given keep the configured
+/// value.
+fn create_features(cli: &Cli, base: &RenderFeatures) -> RenderFeatures {
     let (fixed_width, width_wrap) = if cli.width > 0 {
         (Some(cli.width as usize), false)
     } else {
-        (None, true)
+        (base.fixed_width, base.width_wrap)
     };
 
     RenderFeatures {
-        pretty_pad: !cli.no_pretty_pad,
+        pretty_pad: !cli.no_pretty_pad && !cli.plain,
+        border_style: cli.border.unwrap_or(base.border_style),
         pretty_broken: cli.pretty_broken,
+        code_overflow: cli.code_overflow.unwrap_or(base.code_overflow),
         clipboard: cli.clipboard,
+        copy_link: cli.copy_link,
+        copy_mode: cli.copy_mode,
         savebrace: cli.savebrace,
+        highlight: !cli.no_highlight && base.highlight && !cli.plain,
+        line_numbers: cli.line_numbers || base.line_numbers,
+        ansi_passthrough: cli.ansi_passthrough || base.ansi_passthrough,
+        link_style: if cli.link_endnotes {
+            LinkStyle::Endnotes
+        } else {
+            base.link_style
+        },
+        qr_links: cli.qr_links,
+        numbered_links: cli.number_links || cli.open_link.is_some() || base.numbered_links,
+        color_swatches: cli.swatches || base.color_swatches,
+        partial_lines: cli.partial_lines && !cli.plain,
+        live_blocks: cli.live_blocks || base.live_blocks,
+        progress_lines: if cli.progress_in_place {
+            ProgressLines::InPlace
+        } else {
+            ProgressLines::Collapse
+        },
+        collapse: CollapseOptions {
+            think: cli.collapse_think,
+            code_over: cli.collapse_code,
+        },
+        outline: cli.outline || base.outline,
+        left_margin: cli.left_margin,
+        right_margin: cli.right_margin,
+        max_width: cli.max_width,
+        plain: cli.plain,
+        control_chars: if cli.show_controls {
+            ControlChars::Show
+        } else {
+            base.control_chars
+        },
         fixed_width,
         width_wrap,
-        ..Default::default()
+        paragraph_align: if cli.align == TextAlign::default() {
+            base.paragraph_align
+        } else {
+            cli.align
+        },
+        bidi: cli.bidi || base.bidi,
+        hyphenate: cli.hyphenate || base.hyphenate,
+        ..base.clone()
+    }
+}
+
+/// Create a renderer writing to stdout.
+///
+/// A single renderer is kept for the whole stream so block state (code
+/// language, highlighting, tables, lists) carries across lines.
+fn create_renderer(cli: &Cli, settings: &RenderSettings) -> Renderer<io::Stdout> {
+    create_renderer_with(io::stdout(), cli, settings)
+}
+
+/// Create a renderer writing to `writer`.
+fn create_renderer_with<W: Write>(writer: W, cli: &Cli, settings: &RenderSettings) -> Renderer<W> {
+    let background = detect_background(cli);
+    let render_style = match cli.seed {
+        Some(seed) => {
+            let base_hue = cli
+                .parse_base()
+                .map_or(StyleConfig::default().hsv[0], |(h, _, _)| h as f64);
+            RenderStyle::from_seed(seed, base_hue)
+        }
+        None => settings.style.clone(),
+    };
+    // ASCII borders imply an ASCII-safe terminal, so list glyphs follow suit
+    let render_style = if settings.features.border_style == BorderStyle::Ascii {
+        render_style.ascii_glyphs()
+    } else {
+        render_style
+    };
+    let width = match settings.width {
+        Some(width) => width,
+        None => cli.effective_width(),
+    };
+    let mut renderer = Renderer::with_style(writer, width, render_style);
+    renderer.set_features(settings.features.clone());
+    for (alias, language) in &settings.language_aliases {
+        renderer.alias_language(alias, language);
+    }
+    // An explicit --theme wins over the configured one; the default follows
+    // the background
+    let highlight_theme = match &settings.highlight_theme {
+        Some(theme) if cli.theme == DEFAULT_HIGHLIGHT_THEME => theme.as_str(),
+        _ if background == Background::Light && cli.theme == DEFAULT_HIGHLIGHT_THEME => {
+            "base16-ocean.light"
+        }
+        _ => cli.theme.as_str(),
+    };
+    if let Err(e) = renderer.set_highlight_theme(highlight_theme) {
+        error!("{}", e);
+    }
+    renderer.set_capabilities(TerminalCapabilities::detect());
+    let mut options = settings.options.clone();
+    if options.color == ColorChoice::Auto {
+        options.color = if ColorChoice::Auto.enabled() {
+            ColorChoice::Always
+        } else {
+            ColorChoice::Never
+        };
+    }
+    renderer.set_options(options);
+    if cli.untrusted {
+        renderer.set_policy(Policy::untrusted());
+    }
+    if cli.trace {
+        renderer.set_trace_profile(Some(TraceProfile::agent()));
+    }
+    renderer.set_quota(cli.quota());
+    renderer.set_block_timeout(cli.block_timeout());
+    renderer.set_stats(cli.stats);
+    renderer.set_notifications(cli.notify_options());
+    if cli.agenda {
+        renderer.add_block_processor(Arc::new(AgendaProcessor));
+    }
+    if let Some(command) = &cli.opener {
+        renderer.set_link_opener(LinkOpener::new(command));
+    }
+    if cli.progress_bars {
+        renderer.register_inline("progress", Arc::new(ProgressHandler));
+    }
+    match cli.mark_pass() {
+        Ok(Some(pass)) => renderer.register_text_pass("mark", Arc::new(pass)),
+        Ok(None) => {}
+        Err(e) => error!("{}", e),
+    }
+    if cli.hide_diagram_source {
+        let mermaid = MermaidHandler::new().hide_source(true);
+        renderer.register_fence("mermaid", Arc::new(mermaid));
+    }
+    if cli.csv_tables {
+        renderer.register_fence("csv", Arc::new(CsvHandler::csv()));
+        renderer.register_fence("tsv", Arc::new(CsvHandler::tsv()));
+    }
+    if cli.pretty_data {
+        renderer.register_fence("json", Arc::new(JsonHandler));
+        for language in ["yaml", "yml"] {
+            renderer.register_fence(language, Arc::new(YamlHandler));
+        }
+    }
+    if let Some(formatter) = cli.cell_formatter() {
+        renderer.set_cell_formatter(Some(Arc::new(formatter)));
+    }
+    if is_tty() {
+        renderer.set_height(Some(terminal_size().1 as usize));
+    }
+    if cli.pin_header && is_tty() {
+        let height = terminal_size().1 as usize;
+        if let Err(e) = renderer.set_pinned_header(Some(PinnedHeader::new(height))) {
+            error!("Failed to pin header: {}", e);
+        }
+    }
+    renderer
+}
+
+/// Background of the terminal, asked once per run in auto mode.
+fn detect_background(cli: &Cli) -> Background {
+    static DETECTED: OnceLock<Background> = OnceLock::new();
+    match cli.background.as_str() {
+        "light" => Background::Light,
+        "dark" => Background::Dark,
+        _ => *DETECTED.get_or_init(|| {
+            // The DA1 sentinel ends the wait early; the timeout only
+            // covers terminals that answer nothing
+            let queried = (is_tty() && !cli.plain)
+                .then(|| terminal::query_background(Duration::from_millis(500)))
+                .flatten();
+            let background = queried
+                .or_else(|| Background::from_env(|name| std::env::var(name).ok()))
+                .unwrap_or_default();
+            debug!("Terminal background: {:?}", background);
+            background
+        }),
     }
 }
 
+/// Create a markdown parser with CLI opt-ins applied.
+fn create_parser(cli: &Cli) -> MarkdownParser {
+    let mut parser = MarkdownParser::new();
+    parser.set_process_spoilers(cli.spoilers);
+    parser.set_process_kbd_shorthand(cli.kbd_shorthand);
+    parser.set_detect_languages(cli.detect_language);
+    parser
+}
+
+/// Read `input` on a thread, sending chunks as they arrive; the channel
+/// closes at the end of input or after an error.
+fn read_chunks(mut input: impl Read + Send + 'static) -> mpsc::Receiver<io::Result<Vec<u8>>> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut buf = [0u8; 8192];
+        loop {
+            let chunk = match input.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => Ok(buf[..n].to_vec()),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => Err(e),
+            };
+            let failed = chunk.is_err();
+            if sender.send(chunk).is_err() || failed {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
 /// Process input from stdin.
-fn run_stdin(cli: &Cli, style: &ComputedStyle, features: &RenderFeatures) -> io::Result<()> {
+fn run_stdin(cli: &Cli, style: &ComputedStyle, settings: &RenderSettings) -> io::Result<()> {
     info!("Reading from stdin");
 
     let stdin = io::stdin();
-    let width = cli.effective_width();
-    let render_style = RenderStyle::from_computed(style);
-    let theme = cli.theme.clone();
-    let no_highlight = cli.no_highlight;
-
-    // Use a buffer for output
-    let mut output = Vec::new();
-    let mut parser = MarkdownParser::new();
+    let writer = RecordingWriter::new(io::stdout(), cli.record.is_some());
+    let mut renderer = create_renderer_with(writer, cli, settings);
+    let mut parser = create_parser(cli);
+    let mut recorder = cli.record.as_ref().map(|_| {
+        let mut recorder = TranscriptRecorder::new(renderer.width());
+        recorder.set_features(renderer.features().clone());
+        recorder.set_parser_options(parser.options());
+        recorder.begin_message();
+        recorder
+    });
     let mut plugin_manager = PluginManager::with_builtins();
     let parse_state = streamdown_core::state::ParseState::new();
 
-    // Read stdin line by line for streaming
-    for line in stdin.lock().lines() {
-        let line = line?;
-        trace!("Input line: {}", line);
+    // Read stdin as it arrives, rendering each complete line; when input
+    // stalls, blocks held past the timeout and a table row still waiting
+    // for its newline are drawn
+    let chunks = read_chunks(stdin);
+    let mut pending = Vec::new();
+    let mut row_flushed = false;
+    loop {
+        let chunk = match cli.block_timeout() {
+            Some(timeout) => match chunks.recv_timeout(timeout) {
+                Ok(chunk) => chunk?,
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    renderer.release_stale_block()?;
+                    if let Some(row) = take_stalled_row(&mut pending) {
+                        renderer.clear_partial_line()?;
+                        let events = emit_line(&row, &mut parser, &mut renderer, cli)?;
+                        if let Some(recorder) = &mut recorder {
+                            recorder.record_events(&events);
+                        }
+                        row_flushed = true;
+                    }
+                    continue;
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            },
+            None => match chunks.recv() {
+                Ok(chunk) => chunk?,
+                Err(_) => break,
+            },
+        };
+        if let Some(recorder) = &mut recorder {
+            recorder.record_bytes(&chunk);
+        }
+        pending.extend_from_slice(&chunk);
+
+        while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+            let bytes: Vec<u8> = pending.drain(..=end).collect();
+            let line = String::from_utf8(bytes)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            let line = line.trim_end_matches('\n');
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            trace!("Input line: {}", line);
+            // The newline of a row already drawn on timeout
+            if std::mem::take(&mut row_flushed) && line.is_empty() {
+                continue;
+            }
 
-        // Check plugins first
-        if let Some(plugin_output) = plugin_manager.process_line(&line, &parse_state, style) {
-            for output_line in plugin_output {
-                writeln!(output, "{}", output_line)?;
+            // Check plugins first
+            if let Some(plugin_output) = plugin_manager.process_line(line, &parse_state, style) {
+                renderer.clear_partial_line()?;
+                let mut stdout = io::stdout();
+                for output_line in plugin_output {
+                    writeln!(stdout, "{}", output_line)?;
+                }
+                stdout.flush()?;
+                continue;
+            }
+
+            // Parse and render (the renderer flushes after each event)
+            let events = emit_line(line, &mut parser, &mut renderer, cli)?;
+            if let Some(recorder) = &mut recorder {
+                recorder.record_events(&events);
             }
-            // Flush output
-            io::stdout().write_all(&output)?;
-            io::stdout().flush()?;
-            output.clear();
-            continue;
         }
 
-        // Parse and render
-        {
-            let mut renderer = Renderer::with_style(&mut output, width, render_style.clone());
-            renderer.set_features(features.clone());
-            if !no_highlight {
-                renderer.set_theme(&theme);
+        // Paint the line still arriving (up to a split character)
+        if settings.features.partial_lines && !pending.is_empty() && !parser.state().is_in_code() {
+            let valid = match std::str::from_utf8(&pending) {
+                Ok(text) => text,
+                Err(e) => std::str::from_utf8(&pending[..e.valid_up_to()]).unwrap_or_default(),
+            };
+            if !valid.contains('\r') {
+                renderer.draw_partial_line(valid)?;
+            }
+        }
+    }
+
+    // A last line without a newline
+    if !pending.is_empty() {
+        let line = String::from_utf8(pending)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if let Some(plugin_output) = plugin_manager.process_line(&line, &parse_state, style) {
+            for output_line in plugin_output {
+                writeln!(io::stdout(), "{}", output_line)?;
+            }
+        } else {
+            let events = emit_line(&line, &mut parser, &mut renderer, cli)?;
+            if let Some(recorder) = &mut recorder {
+                recorder.record_events(&events);
             }
-            emit_line(&line, &mut parser, &mut renderer, cli)?;
         }
+    }
+
+    renderer.finish()?;
+    renderer.set_pinned_header(None)?;
+    open_requested_link(cli, &renderer);
 
-        // Flush output
-        io::stdout().write_all(&output)?;
-        io::stdout().flush()?;
-        output.clear();
+    if let (Some(path), Some(mut recorder)) = (&cli.record, recorder) {
+        recorder.record_output(&renderer.writer_mut().take_recorded());
+        recorder.into_transcript().save(path)?;
     }
 
     // Flush any remaining plugin content
@@ -206,20 +550,15 @@ fn run_stdin(cli: &Cli, style: &ComputedStyle, features: &RenderFeatures) -> io:
 }
 
 /// Process input files.
-fn run_files(cli: &Cli, style: &ComputedStyle, features: &RenderFeatures) -> io::Result<()> {
-    let width = cli.effective_width();
-    let render_style = RenderStyle::from_computed(style);
-    let theme = cli.theme.clone();
-    let no_highlight = cli.no_highlight;
-
+fn run_files(cli: &Cli, style: &ComputedStyle, settings: &RenderSettings) -> io::Result<()> {
     for path in &cli.files {
         info!("Processing file: {}", path.display());
 
         let file = File::open(path)?;
         let reader = BufReader::new(file);
 
-        let mut output = Vec::new();
-        let mut parser = MarkdownParser::new();
+        let mut renderer = create_renderer(cli, settings);
+        let mut parser = create_parser(cli);
         let mut plugin_manager = PluginManager::with_builtins();
         let parse_state = streamdown_core::state::ParseState::new();
 
@@ -229,42 +568,164 @@ fn run_files(cli: &Cli, style: &ComputedStyle, features: &RenderFeatures) -> io:
             // Check plugins first
             if let Some(plugin_output) = plugin_manager.process_line(&line, &parse_state, style) {
                 for output_line in plugin_output {
-                    writeln!(output, "{}", output_line)?;
+                    writeln!(io::stdout(), "{}", output_line)?;
                 }
                 continue;
             }
 
             // Parse and render
-            {
-                let mut renderer = Renderer::with_style(&mut output, width, render_style.clone());
-                renderer.set_features(features.clone());
-                if !no_highlight {
-                    renderer.set_theme(&theme);
-                }
-                emit_line(&line, &mut parser, &mut renderer, cli)?;
-            }
+            emit_line(&line, &mut parser, &mut renderer, cli)?;
         }
 
+        renderer.finish()?;
+        renderer.set_pinned_header(None)?;
+        open_requested_link(cli, &renderer);
+
         // Flush remaining plugin content
         let plugin_output = plugin_manager.flush();
         for line in plugin_output {
-            writeln!(output, "{}", line)?;
+            writeln!(io::stdout(), "{}", line)?;
         }
+    }
+
+    io::stdout().flush()?;
+    Ok(())
+}
 
-        // Write all output
-        io::stdout().write_all(&output)?;
+/// Open the link given with `--open-link`, if any.
+fn open_requested_link<W: Write>(cli: &Cli, renderer: &Renderer<W>) {
+    if let Some(n) = cli.open_link {
+        match renderer.open_link(n) {
+            Ok(url) => info!("Opened link {}: {}", n, url),
+            Err(e) => error!("Cannot open link {}: {}", n, e),
+        }
+    }
+}
+
+/// Render the input recorded in a transcript, one renderer per message.
+fn run_replay(cli: &Cli, path: &Path, settings: &RenderSettings) -> io::Result<()> {
+    let transcript = Transcript::load(path)?;
+    info!(
+        "Replaying {} message(s) from {}",
+        transcript.messages.len(),
+        path.display()
+    );
+
+    for message in &transcript.messages {
+        let mut renderer = create_renderer(cli, settings);
+        renderer.resize(transcript.width);
+        renderer.set_features(transcript.features.clone());
+        let mut parser = transcript.parser();
+
+        let input = message.input();
+        for line in input.lines() {
+            emit_line(line, &mut parser, &mut renderer, cli)?;
+        }
+        renderer.finish()?;
     }
 
     io::stdout().flush()?;
     Ok(())
 }
 
+/// Write a `.tmTheme` color scheme as a TOML theme file.
+fn convert_theme(path: &Path) -> io::Result<()> {
+    let style = RenderStyle::from_tm_theme(path)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+    let toml =
+        toml::to_string(&style).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let mut stdout = io::stdout().lock();
+    writeln!(
+        stdout,
+        "# Converted from {}; set HighlightTheme to it for matching code colors",
+        path.display()
+    )?;
+    stdout.write_all(toml.as_bytes())
+}
+
+/// Write the input files (or stdin) as man pages.
+fn run_man(cli: &Cli, section: &str) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    if cli.files.is_empty() {
+        let markdown = io::read_to_string(io::stdin())?;
+        let mut backend = RoffBackend::new(&mut stdout, "stdin", section);
+        return streamdown_render::render_str(&mut backend, &markdown);
+    }
+    for path in &cli.files {
+        let markdown = std::fs::read_to_string(path)?;
+        let title = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy())
+            .unwrap_or_default();
+        let mut backend = RoffBackend::new(&mut stdout, &title, section);
+        streamdown_render::render_str(&mut backend, &markdown)?;
+    }
+    Ok(())
+}
+
+/// Take a table row (`| … |`) still waiting for its newline off `pending`,
+/// so a stalled table shows its last row.
+fn take_stalled_row(pending: &mut Vec<u8>) -> Option<String> {
+    let text = std::str::from_utf8(pending).ok()?;
+    let row = text.strip_suffix('\r').unwrap_or(text);
+    let trimmed = row.trim();
+    if trimmed.len() < 2 || !trimmed.starts_with('|') || !trimmed.ends_with('|') {
+        return None;
+    }
+    let row = row.to_string();
+    pending.clear();
+    Some(row)
+}
+
+/// Write the input files (or stdin) as sentences for text-to-speech.
+fn run_speech(cli: &Cli, settings: &RenderSettings) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    let inputs = if cli.files.is_empty() {
+        vec![io::read_to_string(io::stdin())?]
+    } else {
+        cli.files
+            .iter()
+            .map(std::fs::read_to_string)
+            .collect::<io::Result<_>>()?
+    };
+    for markdown in inputs {
+        let mut backend = SpeechBackend::for_features(&mut stdout, &settings.features);
+        streamdown_render::render_str(&mut backend, &markdown)?;
+    }
+    Ok(())
+}
+
+/// Write each top-level section of the input files (or stdin) to its own
+/// file in `dir`, listing the files written.
+fn run_split(cli: &Cli, dir: &Path, settings: &RenderSettings) -> io::Result<()> {
+    let renderer = create_renderer_with(Vec::new(), cli, settings);
+    let mut splitter = SectionSplitter::to_dir(renderer, dir)?;
+    splitter.set_level(cli.split_level);
+    splitter.set_parser_options(create_parser(cli).options());
+    if cli.files.is_empty() {
+        splitter.push(&io::read_to_string(io::stdin())?)?;
+    }
+    for path in &cli.files {
+        let markdown = std::fs::read_to_string(path)?;
+        splitter.push(&markdown)?;
+        if !markdown.ends_with('\n') {
+            splitter.push("\n")?;
+        }
+    }
+
+    let mut stdout = io::stdout().lock();
+    for (section, _) in splitter.finish()? {
+        writeln!(stdout, "{}", dir.join(section.file_name()).display())?;
+    }
+    Ok(())
+}
+
 /// Run with an exec'd subprocess using PTY.
 fn run_exec(
     cli: &Cli,
     exec_cmd: &str,
     style: &ComputedStyle,
-    features: &RenderFeatures,
+    settings: &RenderSettings,
 ) -> io::Result<()> {
     use pty::{PollResult, PtySession};
     use regex::Regex;
@@ -277,11 +738,6 @@ fn run_exec(
 
     info!("Executing with PTY: {}", exec_cmd);
 
-    let width = cli.effective_width();
-    let render_style = RenderStyle::from_computed(style);
-    let theme = cli.theme.clone();
-    let no_highlight = cli.no_highlight;
-
     // Compile prompt regex
     let prompt_regex =
         Regex::new(&cli.prompt).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
@@ -290,8 +746,8 @@ fn run_exec(
     let mut session = PtySession::spawn(exec_cmd)?;
     info!("PTY session started");
 
-    let mut output = Vec::new();
-    let mut parser = MarkdownParser::new();
+    let mut renderer = create_renderer(cli, settings);
+    let mut parser = create_parser(cli);
     let mut plugin_manager = PluginManager::with_builtins();
     let parse_state = streamdown_core::state::ParseState::new();
 
@@ -327,17 +783,12 @@ fn run_exec(
                     process_master_output(
                         &mut session,
                         &mut line_buffer,
-                        &mut output,
+                        &mut renderer,
                         &mut parser,
                         &mut plugin_manager,
                         &parse_state,
                         style,
                         &prompt_regex,
-                        width,
-                        &render_style,
-                        &theme,
-                        no_highlight,
-                        features,
                         cli,
                     )?;
                 }
@@ -347,17 +798,12 @@ fn run_exec(
                 process_master_output(
                     &mut session,
                     &mut line_buffer,
-                    &mut output,
+                    &mut renderer,
                     &mut parser,
                     &mut plugin_manager,
                     &parse_state,
                     style,
                     &prompt_regex,
-                    width,
-                    &render_style,
-                    &theme,
-                    no_highlight,
-                    features,
                     cli,
                 )?;
             }
@@ -403,17 +849,12 @@ fn run_exec(
 fn process_master_output(
     session: &mut pty::PtySession,
     line_buffer: &mut String,
-    output: &mut Vec<u8>,
+    renderer: &mut Renderer<io::Stdout>,
     parser: &mut MarkdownParser,
     plugin_manager: &mut PluginManager,
     parse_state: &streamdown_core::state::ParseState,
     style: &ComputedStyle,
     prompt_regex: &regex::Regex,
-    width: usize,
-    render_style: &RenderStyle,
-    theme: &str,
-    no_highlight: bool,
-    features: &RenderFeatures,
     cli: &Cli,
 ) -> io::Result<()> {
     let mut buf = [0u8; 1024];
@@ -450,30 +891,16 @@ fn process_master_output(
                 // Check plugins
                 if let Some(plugin_output) = plugin_manager.process_line(&line, parse_state, style)
                 {
+                    let mut stdout = io::stdout();
                     for output_line in plugin_output {
-                        writeln!(output, "{}", output_line)?;
+                        writeln!(stdout, "{}", output_line)?;
                     }
-                    io::stdout().write_all(output)?;
-                    io::stdout().flush()?;
-                    output.clear();
+                    stdout.flush()?;
                     continue;
                 }
 
                 // Parse and render
-                {
-                    let mut renderer =
-                        Renderer::with_style(&mut *output, width, render_style.clone());
-                    renderer.set_features(features.clone());
-                    if !no_highlight {
-                        renderer.set_theme(theme);
-                    }
-                    emit_line(&line, parser, &mut renderer, cli)?;
-                }
-
-                // Flush output
-                io::stdout().write_all(output)?;
-                io::stdout().flush()?;
-                output.clear();
+                emit_line(&line, parser, renderer, cli)?;
             } else if byte == b'\r' {
                 // Ignore carriage returns
             } else {
@@ -485,30 +912,62 @@ fn process_master_output(
     Ok(())
 }
 
-/// Emit a single line through the parser and renderer.
+/// Emit a single line through the parser and renderer, returning the
+/// events rendered.
 fn emit_line<W: Write>(
     line: &str,
     parser: &mut MarkdownParser,
     renderer: &mut Renderer<W>,
     cli: &Cli,
-) -> io::Result<()> {
+) -> io::Result<Vec<ParseEvent>> {
+    if terminal::take_resize() {
+        renderer.resize(cli.effective_width());
+        renderer.set_height(Some(terminal_size().1 as usize));
+    }
+    if terminal::take_copy_toggle() {
+        let enabled = !renderer.features().copy_mode;
+        renderer.set_copy_mode(enabled);
+    }
+
+    // Trace markers are only recognized outside code blocks
+    let segments = match renderer.trace_profile() {
+        Some(profile) if !parser.state().is_in_code() => profile.classify(line),
+        _ => vec![TraceLine::Content(line)],
+    };
+
+    let mut events = Vec::new();
+    for segment in segments {
+        match segment {
+            TraceLine::Content(text) => events.extend(emit_markdown(text, parser, renderer, cli)?),
+            marker => renderer.render_trace_marker(&marker)?,
+        }
+    }
+
+    Ok(events)
+}
+
+/// Parse and render one line of markdown, returning its events.
+fn emit_markdown<W: Write>(
+    line: &str,
+    parser: &mut MarkdownParser,
+    renderer: &mut Renderer<W>,
+    cli: &Cli,
+) -> io::Result<Vec<ParseEvent>> {
     // Parse the line and get events
     let events = parser.parse_line(line);
 
-    // Process all events
-    for event in events {
+    for event in &events {
         trace!("Parse event: {:?}", event);
 
         // Handle code scraping if enabled
         if let Some(ref scrape_dir) = cli.scrape {
-            scrape_code(&event, scrape_dir)?;
+            scrape_code(event, scrape_dir)?;
         }
-
-        // Render the event
-        renderer.render_event(&event)?;
     }
 
-    Ok(())
+    // The line is complete: render its events in one write
+    renderer.render_events(&events)?;
+    Ok(events)
 }
 
 /// Scrape code blocks to a directory.
@@ -568,15 +1027,17 @@ fn scrape_code(event: &ParseEvent, scrape_dir: &Path) -> io::Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use streamdown_render::CodeOverflow;
 
     #[test]
     fn test_create_features() {
         let cli = Cli::parse_from(["sd"]);
-        let features = create_features(&cli);
+        let features = create_features(&cli, &RenderFeatures::default());
 
         assert!(features.pretty_pad);
         assert!(!features.pretty_broken);
         assert!(!features.clipboard);
+        assert!(features.highlight);
     }
 
     #[test]
@@ -586,22 +1047,121 @@ mod tests {
             "--no-pretty-pad",
             "--pretty-broken",
             "--clipboard",
+            "--copy-link",
             "--savebrace",
+            "--no-highlight",
+            "--line-numbers",
+            "--ansi-passthrough",
         ]);
-        let features = create_features(&cli);
+        let features = create_features(&cli, &RenderFeatures::default());
 
         assert!(!features.pretty_pad);
         assert!(features.pretty_broken);
         assert!(features.clipboard);
+        assert!(features.copy_link);
         assert!(features.savebrace);
+        assert!(!features.highlight);
+        assert!(features.line_numbers);
+        assert!(features.ansi_passthrough);
+    }
+
+    #[test]
+    fn test_explicit_default_overrides_config() {
+        let base = RenderFeatures {
+            border_style: BorderStyle::Heavy,
+            code_overflow: CodeOverflow::Truncate,
+            ..RenderFeatures::default()
+        };
+        let features = create_features(&Cli::parse_from(["sd"]), &base);
+        assert_eq!(features.border_style, BorderStyle::Heavy);
+        assert_eq!(features.code_overflow, CodeOverflow::Truncate);
+
+        let cli = Cli::parse_from(["sd", "--border", "light", "--code-overflow", "auto"]);
+        let features = create_features(&cli, &base);
+        assert_eq!(features.border_style, BorderStyle::Light);
+        assert_eq!(features.code_overflow, CodeOverflow::Auto);
+    }
+
+    #[test]
+    fn test_invalid_render_config_fails() {
+        let config: Config = toml::from_str("[render]\nBorderStyle = \"fancy\"").unwrap();
+        let cli = Cli::parse_from(["sd", "--config", "/nonexistent/config.toml"]);
+        assert!(load_settings(&cli, &config).is_err());
     }
 
     #[test]
     fn test_create_features_with_width() {
         let cli = Cli::parse_from(["sd", "-w", "100"]);
-        let features = create_features(&cli);
+        let features = create_features(&cli, &RenderFeatures::default());
 
         assert_eq!(features.fixed_width, Some(100));
         assert!(!features.width_wrap);
     }
+
+    #[test]
+    fn test_create_parser_spoilers() {
+        let cli = Cli::parse_from(["sd", "--spoilers"]);
+        let mut renderer = Renderer::new(Vec::new(), 40);
+        let mut parser = create_parser(&cli);
+        emit_line("the ||butler|| did it", &mut parser, &mut renderer, &cli).unwrap();
+
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        assert!(output.contains('‖'));
+        assert!(!output.contains("||"));
+    }
+
+    #[test]
+    fn test_read_chunks_until_end() {
+        let chunks = read_chunks(io::Cursor::new(b"# a\nb".to_vec()));
+        let input: Vec<u8> = chunks.iter().flat_map(|chunk| chunk.unwrap()).collect();
+        assert_eq!(input, b"# a\nb");
+    }
+
+    #[test]
+    fn test_take_stalled_row() {
+        let mut pending = b"| a | b |\r".to_vec();
+        assert_eq!(take_stalled_row(&mut pending).as_deref(), Some("| a | b |"));
+        assert!(pending.is_empty());
+
+        let mut pending = b"| a | b".to_vec();
+        assert_eq!(take_stalled_row(&mut pending), None);
+        assert_eq!(pending, b"| a | b");
+        assert_eq!(take_stalled_row(&mut b"|".to_vec()), None);
+    }
+
+    #[test]
+    fn test_create_parser_kbd_shorthand() {
+        let cli = Cli::parse_from(["sd", "--kbd-shorthand"]);
+        let mut renderer = Renderer::new(Vec::new(), 40);
+        let mut parser = create_parser(&cli);
+        emit_line("press [[Esc]] twice", &mut parser, &mut renderer, &cli).unwrap();
+
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        assert!(output.contains("▐\x1b[7mEsc\x1b[27m▌"));
+        assert!(!output.contains("[["));
+    }
+
+    #[test]
+    fn test_emit_line_trace_panels() {
+        let cli = Cli::parse_from(["sd", "--trace"]);
+        let mut renderer = Renderer::new(Vec::new(), 40);
+        renderer.set_trace_profile(cli.trace.then(TraceProfile::agent));
+        let mut parser = MarkdownParser::new();
+
+        for line in [
+            "<tool_result>",
+            "```",
+            "</tool_result>",
+            "```",
+            "</tool_result>",
+        ] {
+            emit_line(line, &mut parser, &mut renderer, &cli).unwrap();
+        }
+
+        let output = String::from_utf8(renderer.into_writer()).unwrap();
+        assert!(output.contains("tool result"));
+        // The closing tag inside the fence is code, not a marker
+        assert!(output.contains("</tool_result>"));
+        assert_eq!(output.matches('╰').count(), 1);
+    }
 }
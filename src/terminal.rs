@@ -0,0 +1,127 @@
+//! Queries answered by the controlling terminal.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use streamdown_render::Background;
+
+/// Ask the terminal for its background color (OSC 11).
+///
+/// The query is followed by DA1, and the reply read up to DA1's answer, so a
+/// late OSC 11 reply can't leak into the input. Returns None if there is no
+/// controlling terminal, it doesn't know OSC 11, or it doesn't answer within
+/// `timeout`.
+#[cfg(unix)]
+pub fn query_background(timeout: Duration) -> Option<Background> {
+    use nix::poll::{PollFd, PollFlags, PollTimeout, poll};
+    use nix::sys::termios::{self, SetArg};
+    use std::fs::OpenOptions;
+    use std::io::{Read, Write};
+    use std::os::fd::AsFd;
+    use std::time::Instant;
+    use streamdown_render::{DA1_QUERY, OSC11_QUERY, has_da1_reply};
+
+    let mut tty = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .ok()?;
+    let original = termios::tcgetattr(tty.as_fd()).ok()?;
+    let mut raw = original.clone();
+    termios::cfmakeraw(&mut raw);
+    termios::tcsetattr(tty.as_fd(), SetArg::TCSANOW, &raw).ok()?;
+
+    let mut response = Vec::new();
+    let deadline = Instant::now() + timeout;
+    let query = format!("{}{}", OSC11_QUERY, DA1_QUERY);
+    if tty.write_all(query.as_bytes()).is_ok() && tty.flush().is_ok() {
+        // Read until the DA1 answer, which comes after any OSC 11 reply
+        while !has_da1_reply(&response) {
+            let left = deadline.saturating_duration_since(Instant::now());
+            let left = PollTimeout::try_from(left.as_millis() as i32).unwrap_or(PollTimeout::ZERO);
+            let mut fds = [PollFd::new(tty.as_fd(), PollFlags::POLLIN)];
+            if !matches!(poll(&mut fds, left), Ok(n) if n > 0) {
+                break;
+            }
+            let mut buf = [0u8; 64];
+            match tty.read(&mut buf) {
+                Ok(n) if n > 0 && response.len() < 256 => response.extend_from_slice(&buf[..n]),
+                _ => break,
+            }
+        }
+    }
+
+    let _ = termios::tcsetattr(tty.as_fd(), SetArg::TCSANOW, &original);
+    Background::from_osc11_response(&response)
+}
+
+/// Ask the terminal for its background color (unsupported on this platform).
+#[cfg(not(unix))]
+pub fn query_background(_timeout: Duration) -> Option<Background> {
+    None
+}
+
+/// Set by the SIGWINCH handler, cleared by [`take_resize`].
+static RESIZED: AtomicBool = AtomicBool::new(false);
+
+/// Start noting terminal resizes (SIGWINCH).
+#[cfg(unix)]
+pub fn watch_resize() -> bool {
+    use nix::sys::signal::{SaFlags, SigAction, SigHandler, SigSet, Signal, sigaction};
+
+    extern "C" fn on_resize(_: nix::libc::c_int) {
+        RESIZED.store(true, Ordering::Relaxed);
+    }
+
+    // Restart interrupted reads so stdin isn't cut short by a resize
+    let action = SigAction::new(
+        SigHandler::Handler(on_resize),
+        SaFlags::SA_RESTART,
+        SigSet::empty(),
+    );
+    // SAFETY: the handler only stores to an atomic
+    unsafe { sigaction(Signal::SIGWINCH, &action) }.is_ok()
+}
+
+/// Start noting terminal resizes (unsupported on this platform).
+#[cfg(not(unix))]
+pub fn watch_resize() -> bool {
+    false
+}
+
+/// Whether the terminal was resized since the last call.
+pub fn take_resize() -> bool {
+    RESIZED.swap(false, Ordering::Relaxed)
+}
+
+/// Set by the SIGUSR1 handler, cleared by [`take_copy_toggle`].
+static COPY_TOGGLED: AtomicBool = AtomicBool::new(false);
+
+/// Start noting copy mode toggles (SIGUSR1, e.g. `pkill -USR1 sd`).
+#[cfg(unix)]
+pub fn watch_copy_toggle() -> bool {
+    use nix::sys::signal::{SaFlags, SigAction, SigHandler, SigSet, Signal, sigaction};
+
+    extern "C" fn on_toggle(_: nix::libc::c_int) {
+        COPY_TOGGLED.store(true, Ordering::Relaxed);
+    }
+
+    let action = SigAction::new(
+        SigHandler::Handler(on_toggle),
+        SaFlags::SA_RESTART,
+        SigSet::empty(),
+    );
+    // SAFETY: the handler only stores to an atomic
+    unsafe { sigaction(Signal::SIGUSR1, &action) }.is_ok()
+}
+
+/// Start noting copy mode toggles (unsupported on this platform).
+#[cfg(not(unix))]
+pub fn watch_copy_toggle() -> bool {
+    false
+}
+
+/// Whether copy mode was toggled since the last call.
+pub fn take_copy_toggle() -> bool {
+    COPY_TOGGLED.swap(false, Ordering::Relaxed)
+}
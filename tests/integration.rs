@@ -517,13 +517,14 @@ fn test_render_with_custom_style() {
     let mut output = Vec::new();
 
     let style = RenderStyle {
-        bright: "#ff0000".to_string(),
-        head: "#00ff00".to_string(),
-        symbol: "#0000ff".to_string(),
-        grey: "#888888".to_string(),
-        dark: "#111111".to_string(),
-        mid: "#333333".to_string(),
-        light: "#555555".to_string(),
+        h1: "#ff0000".to_string(),
+        h2: "#00ff00".to_string(),
+        bullet: "#0000ff".to_string(),
+        blockquote_border: "#888888".to_string(),
+        code_bg: "#111111".to_string(),
+        table_header_bg: "#333333".to_string(),
+        link_url: "#555555".to_string(),
+        ..Default::default()
     };
 
     {